@@ -1,3 +1,8 @@
 fn main() {
-    slint_build::compile("ui/appwindow.slint").unwrap();
+    // translations/<lang>/LC_MESSAGES/eleventh_echo_rust.po, one msgid per
+    // @tr string regardless of which component it is in.
+    let config = slint_build::CompilerConfiguration::new()
+        .with_bundled_translations("translations")
+        .with_default_translation_context(slint_build::DefaultTranslationContext::None);
+    slint_build::compile_with_config("ui/appwindow.slint", config).unwrap();
 }
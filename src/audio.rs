@@ -1,15 +1,35 @@
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SizedSample};
 use tokio::sync::mpsc::Sender; // Use bounded sender for backpressure
 use tokio::sync::mpsc::error::TrySendError;
-use std::error::Error;
-use rubato::{Resampler, SincFixedIn, SincInterpolationType, SincInterpolationParameters, WindowFunction};
-use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc::UnboundedSender;
+use rubato::{
+    calculate_cutoff, FastFixedIn, PolynomialDegree, SincFixedIn, SincInterpolationParameters, SincInterpolationType,
+    VecResampler, WindowFunction,
+};
+use chrono::{DateTime, Local};
 use std::collections::VecDeque;
+use std::fs::File;
+use std::ops::Deref;
+use std::io::BufWriter;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::dsp::NoiseFilter;
+use crate::error::{EchoError, EchoResult};
+use crate::i18n::{self, tr};
+use crate::thread_priority;
+use serde::{Deserialize, Serialize};
 
 /// Audio configuration constants
-const TARGET_SAMPLE_RATE: u32 = 16000;
-const CHUNK_SIZE: usize = TARGET_SAMPLE_RATE as usize; // Send 1 second chunks at 16kHz mono
-const PRECONNECT_BUFFER_SAMPLES: usize = TARGET_SAMPLE_RATE as usize * 5; // Keep last 5s before consumer catches up
+const CHUNK_SIZE: usize = 16000; // Samples per chunk: 1 second at the default 16kHz
+pub const DEFAULT_PRECONNECT_SECS: u32 = 5; // Keep last 5s before consumer catches up
+pub const MAX_PRECONNECT_SECS: u32 = 30;
+const RAW_RING_SECONDS: usize = 2; // Raw input headroom between the callback and the worker
+const WORKER_POLL_INTERVAL: Duration = Duration::from_millis(10);
 
 pub fn list_input_devices() -> Vec<String> {
     let host = cpal::default_host();
@@ -33,9 +53,224 @@ pub fn default_input_device_name() -> Option<String> {
     host.default_input_device().and_then(|d| d.name().ok())
 }
 
+/// Where session audio comes from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureSource {
+    /// A microphone or other input device.
+    #[default]
+    Microphone,
+    /// Whatever is playing on the default output device (WASAPI loopback).
+    SystemAudio,
+}
+
+impl CaptureSource {
+    pub const ALL: [CaptureSource; 2] = [CaptureSource::Microphone, CaptureSource::SystemAudio];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CaptureSource::Microphone => "Microphone",
+            CaptureSource::SystemAudio => "System audio (loopback)",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|source| i18n::is_label(source.label(), label))
+    }
+}
+
+/// How samples are encoded on the wire to the speech service.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioEncoding {
+    /// Signed 16-bit little-endian PCM.
+    #[default]
+    Pcm16,
+    /// 8-bit G.711 μ-law, the telephony format.
+    MuLaw,
+}
+
+/// Sample rate and encoding of the stream a provider receives. Capture
+/// resamples to `sample_rate`; providers apply the encoding when sending.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AudioFormat {
+    pub sample_rate: u32,
+    pub encoding: AudioEncoding,
+}
+
+impl Default for AudioFormat {
+    fn default() -> Self {
+        AudioFormat::PCM_16K
+    }
+}
+
+impl AudioFormat {
+    pub const PCM_16K: AudioFormat = AudioFormat::pcm(16000);
+    pub const MU_LAW_8K: AudioFormat = AudioFormat {
+        sample_rate: 8000,
+        encoding: AudioEncoding::MuLaw,
+    };
+
+    pub const ALL: [AudioFormat; 7] = [
+        AudioFormat::pcm(8000),
+        AudioFormat::PCM_16K,
+        AudioFormat::pcm(22050),
+        AudioFormat::pcm(24000),
+        AudioFormat::pcm(44100),
+        AudioFormat::pcm(48000),
+        AudioFormat::MU_LAW_8K,
+    ];
+
+    pub const fn pcm(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            encoding: AudioEncoding::Pcm16,
+        }
+    }
+
+    /// In the UI language, so it can go straight into the format list.
+    pub fn label(self) -> String {
+        let khz = format!("{} kHz", self.sample_rate as f64 / 1000.0);
+        match (self.encoding, self.sample_rate) {
+            (AudioEncoding::MuLaw, _) => tr!("{} μ-law", khz),
+            (AudioEncoding::Pcm16, 16000) => tr!("{} PCM (recommended)", khz),
+            (AudioEncoding::Pcm16, _) => format!("{} PCM", khz),
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|format| format.label() == label)
+    }
+
+    /// Samples to wire bytes.
+    pub fn encode(self, samples: &[i16], out: &mut Vec<u8>) {
+        match self.encoding {
+            AudioEncoding::Pcm16 => {
+                out.reserve(samples.len() * 2);
+                for sample in samples {
+                    out.extend_from_slice(&sample.to_le_bytes());
+                }
+            }
+            AudioEncoding::MuLaw => out.extend(samples.iter().map(|&s| mu_law(s))),
+        }
+    }
+
+    pub fn seconds(self, samples: usize) -> f64 {
+        samples as f64 / self.sample_rate.max(1) as f64
+    }
+}
+
+/// G.711 μ-law companding of one sample.
+fn mu_law(sample: i16) -> u8 {
+    const BIAS: i32 = 0x84;
+    const CLIP: i32 = 32635;
+    let sign = if sample < 0 { 0x80 } else { 0 };
+    let magnitude = (sample as i32).abs().min(CLIP) + BIAS;
+    let exponent = 24 - magnitude.leading_zeros() as i32;
+    let mantissa = (magnitude >> (exponent + 3)) & 0x0F;
+    !(sign | (exponent << 4) | mantissa) as u8
+}
+
+/// Trade-off between resampling CPU cost and fidelity.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResamplerQuality {
+    /// Cubic interpolation without an anti-aliasing filter.
+    Fast,
+    /// Short sinc filter; plenty for speech.
+    #[default]
+    Balanced,
+    /// Long sinc filter with heavy oversampling.
+    Quality,
+}
+
+impl ResamplerQuality {
+    pub const ALL: [ResamplerQuality; 3] = [
+        ResamplerQuality::Fast,
+        ResamplerQuality::Balanced,
+        ResamplerQuality::Quality,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ResamplerQuality::Fast => "Fast (lowest CPU)",
+            ResamplerQuality::Balanced => "Balanced",
+            ResamplerQuality::Quality => "High quality",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|quality| i18n::is_label(quality.label(), label))
+    }
+
+    /// A mono resampler taking `CHUNK_SIZE` input frames per call.
+    fn build(self, ratio: f64) -> Option<Box<dyn VecResampler<f32>>> {
+        let sinc = |sinc_len: usize, oversampling_factor: usize, window: WindowFunction| {
+            let params = SincInterpolationParameters {
+                sinc_len,
+                f_cutoff: calculate_cutoff(sinc_len, window),
+                interpolation: SincInterpolationType::Linear,
+                oversampling_factor,
+                window,
+            };
+            SincFixedIn::<f32>::new(ratio, 2.0, params, CHUNK_SIZE, 1)
+                .ok()
+                .map(|r| Box::new(r) as Box<dyn VecResampler<f32>>)
+        };
+        match self {
+            ResamplerQuality::Fast => FastFixedIn::<f32>::new(ratio, 2.0, PolynomialDegree::Cubic, CHUNK_SIZE, 1)
+                .ok()
+                .map(|r| Box::new(r) as Box<dyn VecResampler<f32>>),
+            ResamplerQuality::Balanced => sinc(64, 128, WindowFunction::Blackman2),
+            ResamplerQuality::Quality => sinc(256, 256, WindowFunction::BlackmanHarris2),
+        }
+    }
+}
+
+/// How a capture stream is processed before it reaches the sink's channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaptureOptions {
+    /// Enables the high-pass + noise gate stage before resampling.
+    pub noise_suppression: bool,
+    pub source: CaptureSource,
+    pub resampler: ResamplerQuality,
+    /// Rate the stream is resampled to; the provider's negotiated format.
+    pub sample_rate: u32,
+    /// How much audio is held while the consumer isn't keeping up, capped at
+    /// `MAX_PRECONNECT_SECS`.
+    pub preconnect_secs: u32,
+    /// Raises the worker thread's scheduling priority while it runs.
+    pub boost_priority: bool,
+}
+
+impl Default for CaptureOptions {
+    fn default() -> Self {
+        Self {
+            noise_suppression: false,
+            source: CaptureSource::default(),
+            resampler: ResamplerQuality::default(),
+            sample_rate: AudioFormat::PCM_16K.sample_rate,
+            preconnect_secs: DEFAULT_PRECONNECT_SECS,
+            boost_priority: true,
+        }
+    }
+}
+
+impl CaptureOptions {
+    /// Ring capacity in output samples. At least one chunk is always kept so
+    /// a setting of 0 still lets audio through.
+    fn preconnect_samples(&self) -> usize {
+        let secs = self.preconnect_secs.min(MAX_PRECONNECT_SECS) as usize;
+        (secs * self.sample_rate as usize).max(CHUNK_SIZE)
+    }
+}
+
 struct CircularSampleBuffer {
     samples: VecDeque<i16>,
     capacity: usize,
+    /// Every sample ever pushed, so the front's position in the capture is
+    /// known even after overwrites.
+    pushed: u64,
 }
 
 impl CircularSampleBuffer {
@@ -43,19 +278,24 @@ impl CircularSampleBuffer {
         Self {
             samples: VecDeque::with_capacity(capacity),
             capacity,
+            pushed: 0,
         }
     }
 
-    fn push_samples(&mut self, incoming: &[i16]) {
+    /// Appends `incoming`, overwriting the oldest samples once full.
+    /// Returns how many samples were lost.
+    fn push_samples(&mut self, incoming: &[i16]) -> usize {
         if incoming.is_empty() {
-            return;
+            return 0;
         }
+        self.pushed += incoming.len() as u64;
 
         if incoming.len() >= self.capacity {
+            let lost = self.samples.len() + incoming.len() - self.capacity;
             self.samples.clear();
             self.samples
                 .extend(incoming[incoming.len() - self.capacity..].iter().copied());
-            return;
+            return lost;
         }
 
         let overflow = self
@@ -69,6 +309,16 @@ impl CircularSampleBuffer {
         }
 
         self.samples.extend(incoming.iter().copied());
+        overflow
+    }
+
+    fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Capture offset of the oldest buffered sample.
+    fn front_offset(&self) -> u64 {
+        self.pushed - self.samples.len() as u64
     }
 
     fn pop_chunk(&mut self, max_len: usize) -> Option<Vec<i16>> {
@@ -91,166 +341,696 @@ impl CircularSampleBuffer {
     }
 }
 
+/// A block of captured PCM and where it starts in the capture. Offsets count
+/// output samples since the capture began, which is also the sample index in
+/// the session's WAV recording.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioChunk {
+    pub samples: Vec<i16>,
+    pub capture_offset: u64,
+}
+
+impl Deref for AudioChunk {
+    type Target = [i16];
+
+    fn deref(&self) -> &[i16] {
+        &self.samples
+    }
+}
+
+/// Point where the streamed audio resumes at a new capture offset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TimelineSpan {
+    stream_offset: u64,
+    capture_offset: u64,
+}
+
+/// Maps the provider's clock onto the capture's. Providers time transcripts
+/// from the first sample they receive, but that stream starts with whatever
+/// the preconnect ring held and skips audio that was paused or overwritten,
+/// so its times drift from the recording. Each chunk actually streamed is
+/// recorded here to translate them back.
+#[derive(Debug, Clone)]
+pub struct AudioTimeline {
+    started_at: DateTime<Local>,
+    started: Instant,
+    sample_rate: u32,
+    spans: Vec<TimelineSpan>,
+    streamed: u64,
+}
+
+impl AudioTimeline {
+    /// Starts the capture epoch now.
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            started_at: Local::now(),
+            started: Instant::now(),
+            sample_rate: sample_rate.max(1),
+            spans: Vec::new(),
+            streamed: 0,
+        }
+    }
+
+    /// Wall-clock time of capture offset zero.
+    pub fn started_at(&self) -> DateTime<Local> {
+        self.started_at
+    }
+
+    /// Notes that `samples` samples from `capture_offset` on went to the provider.
+    pub fn record_streamed(&mut self, capture_offset: u64, samples: usize) {
+        let contiguous = self
+            .spans
+            .last()
+            .is_some_and(|span| span.capture_offset + (self.streamed - span.stream_offset) == capture_offset);
+        if !contiguous {
+            self.spans.push(TimelineSpan {
+                stream_offset: self.streamed,
+                capture_offset,
+            });
+        }
+        self.streamed += samples as u64;
+    }
+
+    /// Seconds of audio the provider has received.
+    pub fn streamed_secs(&self) -> f64 {
+        self.streamed as f64 / self.sample_rate as f64
+    }
+
+    /// A provider timestamp as seconds into the capture.
+    pub fn capture_secs(&self, stream_secs: f64) -> f64 {
+        let position = (stream_secs.max(0.0) * self.sample_rate as f64).round() as u64;
+        let index = self.spans.partition_point(|span| span.stream_offset <= position);
+        let Some(span) = index.checked_sub(1).map(|i| self.spans[i]) else {
+            return stream_secs.max(0.0);
+        };
+        (span.capture_offset + (position - span.stream_offset)) as f64 / self.sample_rate as f64
+    }
+
+    /// When the audio at provider time `stream_secs` was captured.
+    pub fn captured_at(&self, stream_secs: f64) -> Instant {
+        self.started + Duration::from_secs_f64(self.capture_secs(stream_secs))
+    }
+}
+
+/// Counters for audio held back because the session's channel was full.
+/// Samples wait in the preconnect ring until the consumer catches up; once
+/// the ring is full the oldest are overwritten.
+#[derive(Debug, Default)]
+pub struct AudioBackpressure {
+    buffered: AtomicU64,
+    capacity: AtomicU64,
+    peak_buffered: AtomicU64,
+    overwritten: AtomicU64,
+}
+
+impl AudioBackpressure {
+    /// Samples waiting in the ring right now.
+    pub fn buffered_samples(&self) -> u64 {
+        self.buffered.load(Ordering::Relaxed)
+    }
+
+    /// How full the ring is, 0-1; the audio queued for the provider.
+    pub fn fill(&self) -> f32 {
+        match self.capacity.load(Ordering::Relaxed) {
+            0 => 0.0,
+            capacity => (self.buffered_samples() as f64 / capacity as f64).min(1.0) as f32,
+        }
+    }
+
+    pub fn peak_buffered_samples(&self) -> u64 {
+        self.peak_buffered.load(Ordering::Relaxed)
+    }
+
+    /// Samples lost to the ring overwriting itself.
+    pub fn overwritten_samples(&self) -> u64 {
+        self.overwritten.load(Ordering::Relaxed)
+    }
+
+    /// The channel is full, so audio is reaching the provider late.
+    pub fn is_falling_behind(&self) -> bool {
+        self.buffered_samples() > 0
+    }
+
+    /// Updates the counters after a flush, warning when the backlog starts,
+    /// clears or first loses audio.
+    fn observe(&self, buffered: usize, capacity: usize, lost: usize) {
+        let buffered = buffered as u64;
+        self.capacity.store(capacity as u64, Ordering::Relaxed);
+        let before = self.buffered.swap(buffered, Ordering::Relaxed);
+        self.peak_buffered.fetch_max(buffered, Ordering::Relaxed);
+        if before == 0 && buffered > 0 {
+            warn!("⚠️ Audio is falling behind, buffering until the speech service catches up");
+        } else if before > 0 && buffered == 0 {
+            info!("✅ Audio caught up");
+        }
+        if lost > 0 && self.overwritten.fetch_add(lost as u64, Ordering::Relaxed) == 0 {
+            warn!("⚠️ Audio buffer full, the oldest audio is being overwritten");
+        }
+    }
+}
+
+/// Writes the resampled mono PCM of a session into a WAV file at the
+/// stream's sample rate. The file header is finalized when the recorder is
+/// dropped.
+pub struct SessionRecorder {
+    writer: hound::WavWriter<BufWriter<File>>,
+}
+
+impl SessionRecorder {
+    pub fn create(path: &Path, sample_rate: u32) -> EchoResult<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| EchoError::audio(e.to_string()))?;
+        }
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let writer = hound::WavWriter::create(path, spec)?;
+        Ok(Self { writer })
+    }
+
+    fn write_samples(&mut self, samples: &[i16]) {
+        for &sample in samples {
+            if let Err(e) = self.writer.write_sample(sample) {
+                error!("❌ Failed to write session audio: {}", e);
+                return;
+            }
+        }
+    }
+}
+
+/// Reads a session recording back as mono PCM at `sample_rate`, resampling
+/// with `quality` when it was recorded at another rate.
+pub fn read_recording(path: &Path, sample_rate: u32, quality: ResamplerQuality) -> EchoResult<Vec<i16>> {
+    let mut reader = hound::WavReader::open(path)?;
+    let spec = reader.spec();
+    if spec.sample_format != hound::SampleFormat::Int || spec.bits_per_sample != 16 {
+        return Err(EchoError::audio(format!("{} is not a 16-bit PCM recording", path.display())));
+    }
+    let samples = reader.samples::<i16>().collect::<Result<Vec<_>, _>>()?;
+    let channels = spec.channels.max(1) as usize;
+    let mono: Vec<i16> = if channels == 1 {
+        samples
+    } else {
+        samples
+            .chunks(channels)
+            .map(|frame| (frame.iter().map(|&s| s as i32).sum::<i32>() / frame.len() as i32) as i16)
+            .collect()
+    };
+    if spec.sample_rate == sample_rate {
+        return Ok(mono);
+    }
+
+    let ratio = sample_rate as f64 / spec.sample_rate as f64;
+    let mut resampler = quality.build(ratio).ok_or_else(|| {
+        EchoError::audio(format!("Cannot resample {} Hz to {} Hz", spec.sample_rate, sample_rate))
+    })?;
+    let input: Vec<f32> = mono.iter().map(|&s| s as f32 / i16::MAX as f32).collect();
+    let expected = (input.len() as f64 * ratio).round() as usize;
+    let mut output = resampler.output_buffer_allocate(true);
+    let mut resampled = Vec::with_capacity(expected + CHUNK_SIZE);
+    let mut chunks = input.chunks(CHUNK_SIZE);
+    // Partial and empty calls pad with silence, which pushes the end of the
+    // recording through the filter once the input runs out.
+    while resampled.len() < expected {
+        let result = match chunks.next() {
+            Some(chunk) if chunk.len() == CHUNK_SIZE => {
+                resampler.process_into_buffer(&[chunk.to_vec()], &mut output, None)
+            }
+            Some(chunk) => resampler.process_partial_into_buffer(Some(&[chunk.to_vec()]), &mut output, None),
+            None => resampler.process_partial_into_buffer(None::<&[Vec<f32>]>, &mut output, None),
+        };
+        let (_, produced) = result.map_err(|e| EchoError::audio(format!("Resampler error: {}", e)))?;
+        if produced == 0 {
+            break;
+        }
+        resampled.extend(output[0][..produced].iter().map(|&s| to_i16(s)));
+    }
+    resampled.truncate(expected);
+    Ok(resampled)
+}
+
+/// Device-independent half of a capture: the output channels, the preconnect
+/// ring and the optional WAV recorder. It outlives individual cpal streams so
+/// a session can move to another device without dropping buffered audio.
+pub struct CaptureSink {
+    sender: Sender<AudioChunk>,
+    level_sender: Sender<f32>,
+    ring_buffer: CircularSampleBuffer,
+    backpressure: Arc<AudioBackpressure>,
+    recorder: Option<SessionRecorder>,
+    options: CaptureOptions,
+}
+
+impl CaptureSink {
+    pub fn new(
+        sender: Sender<AudioChunk>,
+        level_sender: Sender<f32>,
+        recorder: Option<SessionRecorder>,
+        options: CaptureOptions,
+    ) -> Self {
+        Self {
+            sender,
+            level_sender,
+            ring_buffer: CircularSampleBuffer::new(options.preconnect_samples()),
+            backpressure: Arc::default(),
+            recorder,
+            options,
+        }
+    }
+
+    /// Shared counters that outlive stream reopens.
+    pub fn backpressure(&self) -> Arc<AudioBackpressure> {
+        self.backpressure.clone()
+    }
+}
+
+/// A running input stream plus the worker thread processing it.
+pub struct AudioCapture {
+    stream: cpal::Stream,
+    worker: thread::JoinHandle<Option<CaptureSink>>,
+    lost: Arc<AtomicBool>,
+    backpressure: Arc<AudioBackpressure>,
+}
+
+impl AudioCapture {
+    pub fn backpressure(&self) -> Arc<AudioBackpressure> {
+        self.backpressure.clone()
+    }
+
+    /// True once the stream reported that its device went away.
+    pub fn is_lost(&self) -> bool {
+        self.lost.load(Ordering::SeqCst)
+    }
+
+    /// Stops the stream. The worker keeps draining the remaining input;
+    /// stopping here drops the stream on this thread, which cpal requires.
+    pub fn stop(self) -> DrainingCapture {
+        drop(self.stream);
+        DrainingCapture(self.worker)
+    }
+}
+
+/// The worker of a stopped capture, still draining its input.
+pub struct DrainingCapture(thread::JoinHandle<Option<CaptureSink>>);
+
+impl DrainingCapture {
+    /// Waits for the worker to finish and takes back its sink. This blocks,
+    /// so async code should call it through `spawn_blocking`.
+    pub fn into_sink(self) -> EchoResult<CaptureSink> {
+        match self.0.join() {
+            Ok(Some(sink)) => Ok(sink),
+            Ok(None) | Err(_) => Err(EchoError::audio("The audio worker stopped without handing back its buffers")),
+        }
+    }
+}
+
+fn find_input_device(
+    host: &cpal::Host,
+    preferred_device_name: Option<String>,
+) -> EchoResult<cpal::Device> {
+    let device = match preferred_device_name {
+        Some(name) if !name.trim().is_empty() => host
+            .input_devices()
+            .ok()
+            .and_then(|mut devices| devices.find(|d| d.name().map(|n| n == name).unwrap_or(false)))
+            .or_else(|| host.default_input_device()),
+        _ => host.default_input_device(),
+    };
+    device.ok_or_else(|| EchoError::audio("No input device available"))
+}
+
+/// Picks the device and stream config for `source`. Loopback capture opens the
+/// default *output* device as an input stream, which cpal maps to WASAPI's
+/// loopback mode.
+fn open_device(
+    host: &cpal::Host,
+    source: CaptureSource,
+    preferred_device_name: Option<String>,
+) -> EchoResult<(cpal::Device, cpal::SupportedStreamConfig)> {
+    match source {
+        CaptureSource::Microphone => {
+            let device = find_input_device(host, preferred_device_name)?;
+            let config = device.default_input_config()?;
+            Ok((device, config))
+        }
+        #[cfg(target_os = "windows")]
+        CaptureSource::SystemAudio => {
+            let device = host
+                .default_output_device()
+                .ok_or_else(|| EchoError::audio("No output device available for loopback capture"))?;
+            let config = device.default_output_config()?;
+            Ok((device, config))
+        }
+        #[cfg(not(target_os = "windows"))]
+        CaptureSource::SystemAudio => {
+            Err(EchoError::audio("System audio capture is only supported on Windows (WASAPI loopback)"))
+        }
+    }
+}
+
 /// Starts the audio recording stream.
-/// Audio chunks (raw i16 PCM @ 16kHz) are sent to the provided `sender`.
+/// Audio chunks (raw i16 PCM at `options.sample_rate`, tagged with their
+/// capture offset) are sent to the provided `sender`.
+/// When a `recorder` is given, the same PCM is also written to its WAV file.
+/// `options` selects the source, noise suppression and resampler quality.
+/// `device_lost` is notified when the device disappears mid-stream.
 pub fn start_audio_capture(
-    sender: Sender<Vec<i16>>,
+    sender: Sender<AudioChunk>,
     level_sender: Sender<f32>,
     preferred_device_name: Option<String>,
-) -> Result<cpal::Stream, Box<dyn Error + Send + Sync>> {
+    recorder: Option<SessionRecorder>,
+    options: CaptureOptions,
+    device_lost: UnboundedSender<()>,
+) -> EchoResult<AudioCapture> {
+    let sink = CaptureSink::new(sender, level_sender, recorder, options);
+    open_capture(sink, preferred_device_name, device_lost).map_err(|(_, e)| e)
+}
+
+/// Opens a new input stream feeding an existing `sink`. On failure the sink
+/// is returned alongside the error so the caller can retry later; it is only
+/// lost if the worker thread died with it.
+#[allow(clippy::result_large_err)] // only called on session start and device loss
+pub fn open_capture(
+    sink: CaptureSink,
+    preferred_device_name: Option<String>,
+    device_lost: UnboundedSender<()>,
+) -> Result<AudioCapture, (Option<CaptureSink>, EchoError)> {
     let host = cpal::default_host();
-    let device = if let Some(name) = preferred_device_name {
-        if name.trim().is_empty() {
-            host.default_input_device().ok_or("No input device available")?
-        } else if let Ok(mut devices) = host.input_devices() {
-            devices
-                .find(|d| d.name().map(|n| n == name).unwrap_or(false))
-                .or_else(|| host.default_input_device())
-                .ok_or("No input device available")?
-        } else {
-            host.default_input_device().ok_or("No input device available")?
-        }
-    } else {
-        host.default_input_device().ok_or("No input device available")?
+    let (device, config) = match open_device(&host, sink.options.source, preferred_device_name) {
+        Ok(opened) => opened,
+        Err(e) => return Err((Some(sink), e)),
     };
-    let config = device.default_input_config()?;
     let input_sample_rate = config.sample_rate().0;
+    // Interleaved frames are averaged to mono before the ring, so the
+    // resampler always sees one channel at the device rate.
+    let channels = config.channels().max(1) as usize;
     
-    println!("🎤 Input device: {} @ {}Hz", device.name().unwrap_or_default(), input_sample_rate);
+    match sink.options.source {
+        CaptureSource::Microphone => info!(
+            "🎤 Input device: {} @ {}Hz, {} channel(s)",
+            device.name().unwrap_or_default(),
+            input_sample_rate,
+            channels
+        ),
+        CaptureSource::SystemAudio => info!(
+            "🔊 Loopback device: {} @ {}Hz, {} channel(s)",
+            device.name().unwrap_or_default(),
+            input_sample_rate,
+            channels
+        ),
+    }
 
     // Setup Resampler if needed
-    let resampler = if input_sample_rate != TARGET_SAMPLE_RATE {
-        println!("🔄 Resampling from {}Hz to {}Hz", input_sample_rate, TARGET_SAMPLE_RATE);
-        
-        let params = SincInterpolationParameters {
-            sinc_len: 256,
-            f_cutoff: 0.95,
-            interpolation: SincInterpolationType::Linear,
-            oversampling_factor: 256,
-            window: WindowFunction::BlackmanHarris2,
-        };
-        
-        SincFixedIn::<f32>::new(
-            TARGET_SAMPLE_RATE as f64 / input_sample_rate as f64,
-            2.0, // Max ratio
-            params,
-            CHUNK_SIZE, 
-            1 // channels
-        ).ok()
+    let target_sample_rate = sink.options.sample_rate;
+    let resampler = if input_sample_rate != target_sample_rate {
+        info!(
+            "🔄 Resampling from {}Hz to {}Hz ({})",
+            input_sample_rate,
+            target_sample_rate,
+            sink.options.resampler.label()
+        );
+        sink.options
+            .resampler
+            .build(target_sample_rate as f64 / input_sample_rate as f64)
     } else {
         None
     };
 
-    // Shared state for the callback (Resampler needs to be mutable).
-    let resampler_state = Arc::new(Mutex::new(resampler));
-    // Buffer to hold incoming samples until we have enough for a resampler chunk
-    let buffer_state = Arc::new(Mutex::new(Vec::<f32>::with_capacity(CHUNK_SIZE * 2)));
-    let ring_buffer_state = Arc::new(Mutex::new(CircularSampleBuffer::new(PRECONNECT_BUFFER_SAMPLES)));
-    
-    let err_fn = move |err| eprintln!("❌ Audio stream error: {}", err);
-
-    let sender_level = level_sender.clone();
-    let stream = match config.sample_format() {
-        cpal::SampleFormat::F32 => device.build_input_stream(
-            &config.into(),
-            move |data: &[f32], _: &_| {
-                process_audio_f32(
-                    data,
-                    &sender,
-                    &sender_level,
-                    &resampler_state,
-                    &buffer_state,
-                    &ring_buffer_state,
-                    input_sample_rate
-                );
-            },
-            err_fn,
-            None 
-        )?,
-        cpal::SampleFormat::I16 => device.build_input_stream(
-            &config.into(),
-            move |data: &[i16], _: &_| {
-                // Convert i16 -> f32 for resampling
-                let samples_f32: Vec<f32> = data.iter().map(|&s| s as f32 / i16::MAX as f32).collect();
-                process_audio_f32(
-                    &samples_f32,
-                    &sender,
-                    &sender_level,
-                    &resampler_state,
-                    &buffer_state,
-                    &ring_buffer_state,
-                    input_sample_rate
-                );
-            },
-            err_fn,
-            None
-        )?,
-        _ => return Err("Unsupported sample format".into()),
+    if sink.options.noise_suppression {
+        info!("🔇 Noise suppression enabled (high-pass + noise gate)");
+    }
+
+    // The realtime callback only pushes raw samples into a lock-free SPSC ring;
+    // the worker thread owns everything else (filtering, resampling, metering,
+    // recording and channel sends).
+    let (producer, consumer) =
+        rtrb::RingBuffer::<f32>::new(input_sample_rate as usize * RAW_RING_SECONDS);
+
+    let lost = Arc::new(AtomicBool::new(false));
+    let lost_for_callback = lost.clone();
+    let err_fn = move |err| {
+        error!("❌ Audio stream error: {}", err);
+        if matches!(err, cpal::StreamError::DeviceNotAvailable)
+            && !lost_for_callback.swap(true, Ordering::SeqCst)
+        {
+            let _ = device_lost.send(());
+        }
+    };
+
+    let sample_format = config.sample_format();
+    let stream_config: cpal::StreamConfig = config.into();
+    let stream = match sample_format {
+        cpal::SampleFormat::I8 => build_mono_stream::<i8, _>(&device, &stream_config, producer, channels, err_fn),
+        cpal::SampleFormat::I16 => build_mono_stream::<i16, _>(&device, &stream_config, producer, channels, err_fn),
+        cpal::SampleFormat::I32 => build_mono_stream::<i32, _>(&device, &stream_config, producer, channels, err_fn),
+        cpal::SampleFormat::I64 => build_mono_stream::<i64, _>(&device, &stream_config, producer, channels, err_fn),
+        cpal::SampleFormat::U8 => build_mono_stream::<u8, _>(&device, &stream_config, producer, channels, err_fn),
+        cpal::SampleFormat::U16 => build_mono_stream::<u16, _>(&device, &stream_config, producer, channels, err_fn),
+        cpal::SampleFormat::U32 => build_mono_stream::<u32, _>(&device, &stream_config, producer, channels, err_fn),
+        cpal::SampleFormat::U64 => build_mono_stream::<u64, _>(&device, &stream_config, producer, channels, err_fn),
+        cpal::SampleFormat::F32 => build_mono_stream::<f32, _>(&device, &stream_config, producer, channels, err_fn),
+        cpal::SampleFormat::F64 => build_mono_stream::<f64, _>(&device, &stream_config, producer, channels, err_fn),
+        other => return Err((Some(sink), EchoError::audio(format!("Unsupported sample format {}", other)))),
+    };
+    let stream = match stream {
+        Ok(stream) => stream,
+        Err(e) => return Err((Some(sink), e.into())),
     };
 
-    stream.play()?;
-    Ok(stream)
+    let backpressure = sink.backpressure();
+    let worker = CaptureWorker {
+        consumer,
+        resampler,
+        filter: sink.options.noise_suppression.then(|| NoiseFilter::new(input_sample_rate)),
+        buffer: Vec::with_capacity(CHUNK_SIZE * 2),
+        scratch: Vec::with_capacity(CHUNK_SIZE),
+        resample_output: Vec::new(),
+        sink,
+    };
+    // The worker state is handed over once the thread exists, so a failed
+    // spawn (resource exhaustion) can still give the sink back.
+    let (worker_tx, worker_rx) = std::sync::mpsc::sync_channel::<CaptureWorker>(1);
+    let spawned = thread::Builder::new()
+        .name("audio-worker".to_string())
+        .spawn(move || worker_rx.recv().ok().map(CaptureWorker::run));
+    let handle = match spawned {
+        Ok(handle) => handle,
+        Err(e) => {
+            let e = EchoError::audio(format!("Could not start the audio worker thread: {}", e));
+            return Err((Some(worker.sink), e));
+        }
+    };
+    if let Err(std::sync::mpsc::SendError(worker)) = worker_tx.send(worker) {
+        return Err((Some(worker.sink), EchoError::audio("The audio worker thread exited before it started")));
+    }
+
+    let capture = AudioCapture {
+        stream,
+        worker: handle,
+        lost,
+        backpressure,
+    };
+    if let Err(e) = capture.stream.play() {
+        let sink = match capture.stop().into_sink() {
+            Ok(sink) => Some(sink),
+            Err(join_error) => {
+                error!("❌ {}", join_error);
+                None
+            }
+        };
+        return Err((sink, e.into()));
+    }
+    Ok(capture)
 }
 
-fn process_audio_f32(
-    input: &[f32], 
-    sender: &Sender<Vec<i16>>, 
-    level_sender: &Sender<f32>,
-    resampler_state: &Arc<Mutex<Option<SincFixedIn<f32>>>>,
-    buffer_state: &Arc<Mutex<Vec<f32>>>,
-    ring_buffer_state: &Arc<Mutex<CircularSampleBuffer>>,
-    _input_rate: u32
+/// Averages each interleaved frame of `channels` samples into one mono
+/// sample and pushes it into the ring; stops early if the ring is full.
+fn push_mono<T: Copy>(
+    producer: &mut rtrb::Producer<f32>,
+    data: &[T],
+    channels: usize,
+    to_f32: impl Fn(T) -> f32,
 ) {
-    // Calculate peak level for feedback
-    let mut peak = 0.0f32;
-    for &sample in input {
-        let abs = sample.abs();
-        if abs > peak {
-            peak = abs;
+    for frame in data.chunks(channels) {
+        let sum: f32 = frame.iter().map(|&s| to_f32(s)).sum();
+        if producer.push(sum / frame.len() as f32).is_err() {
+            break;
         }
     }
-    let _ = level_sender.try_send(peak);
+}
 
-    let mut buffer = buffer_state.lock().unwrap();
-    buffer.extend_from_slice(input);
+/// Opens an input stream of sample type `T`, converting each frame to an
+/// f32 mono sample in the realtime callback.
+fn build_mono_stream<T, E>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut producer: rtrb::Producer<f32>,
+    channels: usize,
+    err_fn: E,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+    E: FnMut(cpal::StreamError) + Send + 'static,
+{
+    device.build_input_stream(
+        config,
+        move |data: &[T], _: &_| push_mono(&mut producer, data, channels, |s| s.to_sample::<f32>()),
+        err_fn,
+        None,
+    )
+}
 
-    let mut resampler_guard = resampler_state.lock().unwrap();
-    
-    if let Some(resampler) = resampler_guard.as_mut() {
-        while buffer.len() >= CHUNK_SIZE {
-            // Rubato requires strict chunk sizes for SincFixedIn
-            let input_frames = vec![buffer.drain(0..CHUNK_SIZE).collect::<Vec<f32>>()];
-
-            if let Ok(output_frames) = resampler.process(&input_frames, None) {
-                if let Some(channel_data) = output_frames.first() {
-                    let output_i16: Vec<i16> = channel_data
-                        .iter()
-                        .map(|&s| (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
-                        .collect();
-                    enqueue_and_flush(sender, ring_buffer_state, output_i16);
+/// Owns all non-realtime audio processing for one capture stream.
+/// Exits once the stream (and with it the ring producer) is dropped,
+/// returning the sink so it can be attached to a new stream.
+struct CaptureWorker {
+    consumer: rtrb::Consumer<f32>,
+    resampler: Option<Box<dyn VecResampler<f32>>>,
+    filter: Option<NoiseFilter>,
+    buffer: Vec<f32>,
+    scratch: Vec<f32>,
+    resample_output: Vec<Vec<f32>>,
+    sink: CaptureSink,
+}
+
+impl CaptureWorker {
+    fn run(mut self) -> CaptureSink {
+        // Reverted when the worker exits, so a reused thread isn't left boosted.
+        let _boost = self.sink.options.boost_priority.then(thread_priority::boost_current_thread);
+
+        if let Some(resampler) = self.resampler.as_ref() {
+            self.resample_output = resampler.output_buffer_allocate(true);
+        }
+
+        loop {
+            let available = self.consumer.slots();
+            if available == 0 {
+                if self.consumer.is_abandoned() {
+                    break;
                 }
+                thread::sleep(WORKER_POLL_INTERVAL);
+                continue;
+            }
+
+            self.scratch.clear();
+            if let Ok(chunk) = self.consumer.read_chunk(available) {
+                let (first, second) = chunk.as_slices();
+                self.scratch.extend_from_slice(first);
+                self.scratch.extend_from_slice(second);
+                chunk.commit_all();
             }
+            self.process_block();
         }
-    } else {
-        // No resampling needed
-        let output_i16: Vec<i16> = buffer
-            .drain(..)
-            .map(|s| (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
-            .collect();
-        enqueue_and_flush(sender, ring_buffer_state, output_i16);
+        self.flush_resampler();
+        self.sink
+    }
+
+    /// Pushes the partial chunk still waiting in `buffer`, plus the
+    /// resampler's internal delay, through with silence padding so the last
+    /// word isn't lost when the stream stops.
+    fn flush_resampler(&mut self) {
+        let Some(resampler) = self.resampler.as_mut() else {
+            return;
+        };
+        if self.buffer.is_empty() {
+            return;
+        }
+
+        let pending = self.buffer.len();
+        let mut remaining = resampler.output_delay()
+            + (pending * resampler.output_frames_next()).div_ceil(resampler.input_frames_next());
+        let mut input = Some([std::mem::take(&mut self.buffer)]);
+        let mut tail = Vec::with_capacity(remaining);
+        while remaining > 0 {
+            let result = match input.take() {
+                Some(partial) => resampler.process_partial_into_buffer(Some(&partial), &mut self.resample_output, None),
+                None => resampler.process_partial_into_buffer(None::<&[Vec<f32>]>, &mut self.resample_output, None),
+            };
+            match result {
+                Ok((_, produced)) if produced > 0 => {
+                    let take = produced.min(remaining);
+                    tail.extend(self.resample_output[0][..take].iter().map(|&s| to_i16(s)));
+                    remaining -= take;
+                }
+                Ok(_) => break,
+                Err(e) => {
+                    error!("❌ Resampler error while flushing: {}", e);
+                    break;
+                }
+            }
+        }
+        self.emit(tail);
+    }
+
+    fn process_block(&mut self) {
+        // Calculate peak level for feedback
+        let peak = self.scratch.iter().fold(0.0f32, |acc, s| acc.max(s.abs()));
+        let _ = self.sink.level_sender.try_send(peak);
+
+        let start = self.buffer.len();
+        self.buffer.extend_from_slice(&self.scratch);
+        if let Some(filter) = self.filter.as_mut() {
+            filter.process(&mut self.buffer[start..]);
+        }
+
+        if self.resampler.is_some() {
+            // The FixedIn resamplers require exactly CHUNK_SIZE input frames
+            while self.buffer.len() >= CHUNK_SIZE {
+                let Some(resampler) = self.resampler.as_mut() else {
+                    break;
+                };
+                let input = [self.buffer[..CHUNK_SIZE].to_vec()];
+                let result = resampler.process_into_buffer(&input, &mut self.resample_output, None);
+                self.buffer.drain(0..CHUNK_SIZE);
+                match result {
+                    Ok((_, produced)) => {
+                        let output_i16: Vec<i16> = self.resample_output[0][..produced]
+                            .iter()
+                            .map(|&s| to_i16(s))
+                            .collect();
+                        self.emit(output_i16);
+                    }
+                    Err(e) => error!("❌ Resampler error: {}", e),
+                }
+            }
+        } else {
+            // No resampling needed
+            let output_i16: Vec<i16> = self.buffer.drain(..).map(to_i16).collect();
+            self.emit(output_i16);
+        }
+    }
+
+    fn emit(&mut self, samples: Vec<i16>) {
+        let sink = &mut self.sink;
+        if let Some(recorder) = sink.recorder.as_mut() {
+            recorder.write_samples(&samples);
+        }
+        enqueue_and_flush(&sink.sender, &mut sink.ring_buffer, &sink.backpressure, samples);
     }
 }
 
+fn to_i16(sample: f32) -> i16 {
+    (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+}
+
 fn enqueue_and_flush(
-    sender: &Sender<Vec<i16>>,
-    ring_buffer_state: &Arc<Mutex<CircularSampleBuffer>>,
+    sender: &Sender<AudioChunk>,
+    ring_buffer: &mut CircularSampleBuffer,
+    backpressure: &AudioBackpressure,
     samples: Vec<i16>,
 ) {
-    let mut ring_buffer = ring_buffer_state.lock().unwrap();
-    ring_buffer.push_samples(&samples);
+    let lost = ring_buffer.push_samples(&samples);
 
-    while let Some(chunk) = ring_buffer.pop_chunk(CHUNK_SIZE) {
-        match sender.try_send(chunk) {
+    loop {
+        let capture_offset = ring_buffer.front_offset();
+        let Some(samples) = ring_buffer.pop_chunk(CHUNK_SIZE) else {
+            break;
+        };
+        match sender.try_send(AudioChunk { samples, capture_offset }) {
             Ok(()) => {}
             Err(TrySendError::Full(chunk)) => {
                 ring_buffer.push_front_samples(&chunk);
@@ -262,20 +1042,105 @@ fn enqueue_and_flush(
             }
         }
     }
+    backpressure.observe(ring_buffer.len(), ring_buffer.capacity, lost);
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{CircularSampleBuffer, CHUNK_SIZE, enqueue_and_flush};
-    use std::sync::{Arc, Mutex};
+    use super::{
+        mu_law, push_mono, AudioChunk, AudioEncoding, AudioFormat, AudioTimeline, CaptureOptions, CaptureSink,
+        CaptureSource, CaptureWorker, CircularSampleBuffer, CHUNK_SIZE, enqueue_and_flush, AudioBackpressure,
+        read_recording, ResamplerQuality, SessionRecorder,
+    };
+    use cpal::Sample;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
     use tokio::sync::mpsc;
 
+    #[test]
+    fn multi_channel_frames_are_averaged_to_mono() {
+        let (mut producer, mut consumer) = rtrb::RingBuffer::<f32>::new(8);
+        push_mono(&mut producer, &[1.0f32, 0.0, 0.5, 0.5, -1.0, -0.5], 2, |s| s);
+        push_mono(&mut producer, &[0.25f32, 0.5, 0.75], 3, |s| s);
+        let mono: Vec<f32> = std::iter::from_fn(|| consumer.pop().ok()).collect();
+        assert_eq!(mono, vec![0.5, 0.5, -0.75, 0.5]);
+    }
+
+    #[test]
+    fn integer_and_f64_formats_convert_to_unit_range() {
+        let (mut producer, mut consumer) = rtrb::RingBuffer::<f32>::new(8);
+        push_mono(&mut producer, &[u16::MIN, 32768, u16::MAX], 1, |s| s.to_sample::<f32>());
+        push_mono(&mut producer, &[i32::MIN, 0], 1, |s| s.to_sample::<f32>());
+        push_mono(&mut producer, &[0.25f64], 1, |s| s.to_sample::<f32>());
+        let mono: Vec<f32> = std::iter::from_fn(|| consumer.pop().ok()).collect();
+        assert_eq!(mono.len(), 6);
+        assert_eq!(mono[0], -1.0);
+        assert_eq!(mono[1], 0.0);
+        assert!((mono[2] - 1.0).abs() < 1e-3);
+        assert_eq!(mono[3], -1.0);
+        assert_eq!(mono[4], 0.0);
+        assert_eq!(mono[5], 0.25);
+    }
+
+    #[test]
+    fn every_resampler_preset_downsamples_a_chunk() {
+        for quality in ResamplerQuality::ALL {
+            assert_eq!(ResamplerQuality::from_label(quality.label()), Some(quality));
+            let mut resampler = quality.build(16000.0 / 48000.0).expect("resampler builds");
+            let mut output = resampler.output_buffer_allocate(true);
+            let (consumed, produced) = resampler
+                .process_into_buffer(&[vec![0.1f32; CHUNK_SIZE]], &mut output, None)
+                .unwrap();
+            assert_eq!(consumed, CHUNK_SIZE);
+            assert!(produced > 0 && produced <= CHUNK_SIZE / 3 + 1, "{:?}: {}", quality, produced);
+        }
+    }
+
     #[test]
     fn circular_buffer_trims_to_capacity() {
         let mut b = CircularSampleBuffer::new(4);
-        b.push_samples(&[1, 2, 3, 4, 5, 6]);
+        assert_eq!(b.push_samples(&[1, 2, 3, 4, 5, 6]), 2);
+        assert_eq!(b.push_samples(&[7]), 1);
         let out = b.pop_chunk(10).unwrap();
-        assert_eq!(out, vec![3, 4, 5, 6]);
+        assert_eq!(out, vec![4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn preconnect_capacity_is_clamped_and_keeps_one_chunk() {
+        let with_secs = |preconnect_secs| CaptureOptions {
+            preconnect_secs,
+            ..CaptureOptions::default()
+        };
+        assert_eq!(CaptureOptions::default().preconnect_samples(), 5 * 16000);
+        assert_eq!(with_secs(0).preconnect_samples(), CHUNK_SIZE);
+        assert_eq!(with_secs(120).preconnect_samples(), 30 * 16000);
+        let at_48k = CaptureOptions {
+            sample_rate: 48000,
+            ..CaptureOptions::default()
+        };
+        assert_eq!(at_48k.preconnect_samples(), 5 * 48000);
+    }
+
+    #[test]
+    fn audio_formats_encode_pcm_and_mu_law() {
+        for format in AudioFormat::ALL {
+            assert_eq!(AudioFormat::from_label(&format.label()), Some(format));
+        }
+        assert_eq!(AudioFormat::pcm(22050).label(), "22.05 kHz PCM");
+        assert_eq!(AudioFormat::MU_LAW_8K.label(), "8 kHz μ-law");
+        assert_eq!(AudioFormat::default().seconds(32000), 2.0);
+
+        let mut pcm = Vec::new();
+        AudioFormat::PCM_16K.encode(&[1, -1], &mut pcm);
+        assert_eq!(pcm, vec![1, 0, 0xFF, 0xFF]);
+
+        // Reference values from the G.711 tables.
+        assert_eq!([mu_law(0), mu_law(i16::MAX), mu_law(i16::MIN), mu_law(-1)], [0xFF, 0x80, 0x00, 0x7F]);
+        let mut encoded = Vec::new();
+        AudioFormat::MU_LAW_8K.encode(&[0, 1000, -1000], &mut encoded);
+        assert_eq!(encoded.len(), 3);
+        assert_eq!(AudioFormat::MU_LAW_8K.encoding, AudioEncoding::MuLaw);
+        assert_eq!(encoded[1] & 0x80, 0x80);
+        assert_eq!(encoded[2] & 0x80, 0x00);
     }
 
     #[test]
@@ -299,38 +1164,241 @@ mod tests {
 
     #[tokio::test]
     async fn enqueue_and_flush_sends_when_channel_has_space() {
-        let (tx, mut rx) = mpsc::channel::<Vec<i16>>(4);
-        let ring = Arc::new(Mutex::new(CircularSampleBuffer::new(CHUNK_SIZE * 2)));
-        enqueue_and_flush(&tx, &ring, vec![1; CHUNK_SIZE]);
+        let (tx, mut rx) = mpsc::channel::<AudioChunk>(4);
+        let mut ring = CircularSampleBuffer::new(CHUNK_SIZE * 2);
+        let backpressure = AudioBackpressure::default();
+        enqueue_and_flush(&tx, &mut ring, &backpressure, vec![1; CHUNK_SIZE]);
         let got = rx.recv().await.unwrap();
         assert_eq!(got.len(), CHUNK_SIZE);
+        assert!(!backpressure.is_falling_behind());
     }
 
     #[tokio::test]
     async fn enqueue_and_flush_handles_full_channel() {
-        let (tx, mut rx) = mpsc::channel::<Vec<i16>>(1);
-        let ring = Arc::new(Mutex::new(CircularSampleBuffer::new(CHUNK_SIZE * 3)));
+        let (tx, mut rx) = mpsc::channel::<AudioChunk>(1);
+        let mut ring = CircularSampleBuffer::new(CHUNK_SIZE * 3);
 
         // Fill channel so next send hits TrySendError::Full.
-        tx.try_send(vec![9; CHUNK_SIZE]).unwrap();
-        enqueue_and_flush(&tx, &ring, vec![1; CHUNK_SIZE]);
+        tx.try_send(AudioChunk { samples: vec![9; CHUNK_SIZE], capture_offset: 0 }).unwrap();
+        let backpressure = AudioBackpressure::default();
+        enqueue_and_flush(&tx, &mut ring, &backpressure, vec![1; CHUNK_SIZE]);
+        assert!(backpressure.is_falling_behind());
+        assert_eq!(backpressure.buffered_samples(), CHUNK_SIZE as u64);
+        assert!((backpressure.fill() - 1.0 / 3.0).abs() < 1e-6);
 
         // First message is the pre-filled one.
         let _ = rx.recv().await.unwrap();
 
         // The chunk should have been preserved in ring buffer.
-        let mut rb = ring.lock().unwrap();
-        let preserved = rb.pop_chunk(CHUNK_SIZE).unwrap();
+        let preserved = ring.pop_chunk(CHUNK_SIZE).unwrap();
         assert_eq!(preserved.len(), CHUNK_SIZE);
     }
 
+    #[tokio::test]
+    async fn backlog_past_the_ring_overwrites_the_oldest_audio() {
+        let (tx, mut rx) = mpsc::channel::<AudioChunk>(1);
+        let mut ring = CircularSampleBuffer::new(CHUNK_SIZE * 2);
+        let backpressure = AudioBackpressure::default();
+        tx.try_send(AudioChunk { samples: vec![9; CHUNK_SIZE], capture_offset: 0 }).unwrap();
+        for value in 1..=3 {
+            enqueue_and_flush(&tx, &mut ring, &backpressure, vec![value; CHUNK_SIZE]);
+        }
+        assert_eq!(backpressure.overwritten_samples(), CHUNK_SIZE as u64);
+        assert_eq!(backpressure.peak_buffered_samples(), 2 * CHUNK_SIZE as u64);
+
+        // Once the channel drains, the backlog clears.
+        let _ = rx.recv().await.unwrap();
+        enqueue_and_flush(&tx, &mut ring, &backpressure, Vec::new());
+        let resumed = rx.recv().await.unwrap();
+        assert_eq!(resumed[0], 2);
+        // The overwritten first chunk still counts towards capture time.
+        assert_eq!(resumed.capture_offset, CHUNK_SIZE as u64);
+        assert!(backpressure.is_falling_behind());
+        enqueue_and_flush(&tx, &mut ring, &backpressure, Vec::new());
+        assert!(!backpressure.is_falling_behind());
+        assert_eq!(backpressure.fill(), 0.0);
+    }
+
+    #[test]
+    fn timeline_maps_stream_time_past_skipped_audio() {
+        let mut timeline = AudioTimeline::new(16000);
+        assert_eq!(timeline.capture_secs(1.5), 1.5);
+
+        timeline.record_streamed(0, 16000);
+        timeline.record_streamed(16000, 16000);
+        // Two seconds paused or overwritten, then streaming resumes.
+        timeline.record_streamed(64000, 16000);
+        assert_eq!(timeline.streamed_secs(), 3.0);
+        assert_eq!(timeline.capture_secs(0.5), 0.5);
+        assert_eq!(timeline.capture_secs(1.75), 1.75);
+        assert_eq!(timeline.capture_secs(2.0), 4.0);
+        assert_eq!(timeline.capture_secs(2.5), 4.5);
+        assert_eq!(
+            timeline.captured_at(2.5).duration_since(timeline.captured_at(0.5)),
+            Duration::from_secs(4)
+        );
+        assert!(timeline.started_at() <= chrono::Local::now());
+    }
+
     #[tokio::test]
     async fn enqueue_and_flush_handles_closed_channel() {
-        let (tx, rx) = mpsc::channel::<Vec<i16>>(1);
+        let (tx, rx) = mpsc::channel::<AudioChunk>(1);
         drop(rx); // force TrySendError::Closed
-        let ring = Arc::new(Mutex::new(CircularSampleBuffer::new(CHUNK_SIZE * 2)));
-        enqueue_and_flush(&tx, &ring, vec![1; CHUNK_SIZE]);
-        let mut rb = ring.lock().unwrap();
-        assert!(rb.pop_chunk(CHUNK_SIZE).is_none());
+        let mut ring = CircularSampleBuffer::new(CHUNK_SIZE * 2);
+        enqueue_and_flush(&tx, &mut ring, &AudioBackpressure::default(), vec![1; CHUNK_SIZE]);
+        assert!(ring.pop_chunk(CHUNK_SIZE).is_none());
+    }
+
+    #[test]
+    fn session_recorder_writes_readable_wav() {
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!("eleventh_echo_recording_{}.wav", stamp));
+        let mut recorder = SessionRecorder::create(&path, 16000).unwrap();
+        recorder.write_samples(&[1, -2, 3]);
+        recorder.write_samples(&[4]);
+        drop(recorder);
+
+        let mut reader = hound::WavReader::open(&path).unwrap();
+        assert_eq!(reader.spec().sample_rate, 16000);
+        assert_eq!(reader.spec().channels, 1);
+        let samples: Vec<i16> = reader.samples::<i16>().map(|s| s.unwrap()).collect();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(samples, vec![1, -2, 3, 4]);
+    }
+
+    #[test]
+    fn recordings_are_read_back_at_the_requested_rate() {
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!("eleventh_echo_reread_{}.wav", stamp));
+        // 1.5s of a 440 Hz tone, so the last chunk is partial.
+        let tone = |rate: f32, len: usize| -> Vec<i16> {
+            (0..len)
+                .map(|i| ((i as f32 * 440.0 * std::f32::consts::TAU / rate).sin() * 8000.0) as i16)
+                .collect()
+        };
+        let mut recorder = SessionRecorder::create(&path, 48_000).unwrap();
+        recorder.write_samples(&tone(48_000.0, 72_000));
+        drop(recorder);
+
+        assert_eq!(read_recording(&path, 48_000, ResamplerQuality::Fast).unwrap(), tone(48_000.0, 72_000));
+        let resampled = read_recording(&path, 16_000, ResamplerQuality::Balanced).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(resampled.len(), 24_000);
+        // Same tone within a sample of phase: nothing is shifted or cut off.
+        let expected = tone(16_000.0, 24_000);
+        let error = (1000..23_000)
+            .map(|i| (resampled[i] as i32 - expected[i] as i32).abs())
+            .max()
+            .unwrap();
+        assert!(error < 1200, "{}", error);
+    }
+
+    #[tokio::test]
+    async fn worker_drains_ring_and_exits_when_producer_dropped() {
+        let (tx, mut rx) = mpsc::channel::<AudioChunk>(8);
+        let (level_tx, mut level_rx) = mpsc::channel::<f32>(8);
+        let (mut producer, consumer) = rtrb::RingBuffer::<f32>::new(CHUNK_SIZE);
+        let worker = CaptureWorker {
+            consumer,
+            resampler: None,
+            filter: None,
+            buffer: Vec::new(),
+            scratch: Vec::new(),
+            resample_output: Vec::new(),
+            sink: CaptureSink::new(tx, level_tx, None, CaptureOptions::default()),
+        };
+        for _ in 0..100 {
+            producer.push(0.5).unwrap();
+        }
+        drop(producer);
+        std::thread::spawn(move || worker.run()).join().unwrap();
+
+        let chunk = rx.recv().await.unwrap();
+        assert_eq!(chunk.len(), 100);
+        assert_eq!(chunk[0], (0.5 * i16::MAX as f32) as i16);
+        assert!((level_rx.recv().await.unwrap() - 0.5).abs() < f32::EPSILON);
+    }
+
+    #[tokio::test]
+    async fn worker_flushes_partial_resampler_chunk_on_exit() {
+        let (tx, mut rx) = mpsc::channel::<AudioChunk>(8);
+        let (level_tx, _level_rx) = mpsc::channel::<f32>(8);
+        let (mut producer, consumer) = rtrb::RingBuffer::<f32>::new(CHUNK_SIZE);
+        let resampler = ResamplerQuality::Fast.build(1.0 / 3.0).unwrap();
+        let expected = resampler.output_delay() + 1000;
+        let worker = CaptureWorker {
+            consumer,
+            resampler: Some(resampler),
+            filter: None,
+            buffer: Vec::new(),
+            scratch: Vec::new(),
+            resample_output: Vec::new(),
+            sink: CaptureSink::new(tx, level_tx, None, CaptureOptions::default()),
+        };
+        // Well short of one resampler chunk at 48kHz.
+        for _ in 0..3000 {
+            producer.push(0.5).unwrap();
+        }
+        drop(producer);
+        std::thread::spawn(move || worker.run()).join().unwrap();
+
+        let chunk = rx.recv().await.unwrap();
+        assert!(chunk.len().abs_diff(expected) <= 2, "{} vs {}", chunk.len(), expected);
+        assert!(chunk[chunk.len() / 2] > 0);
+    }
+
+    #[tokio::test]
+    async fn sink_keeps_buffered_audio_across_workers() {
+        let (tx, mut rx) = mpsc::channel::<AudioChunk>(1);
+        let (level_tx, _level_rx) = mpsc::channel::<f32>(8);
+        tx.try_send(AudioChunk { samples: vec![0; 1], capture_offset: 0 }).unwrap();
+        let mut sink = CaptureSink::new(tx, level_tx, None, CaptureOptions::default());
+
+        // First "device": output is held in the preconnect ring because the
+        // channel is full, then the stream goes away.
+        for value in [0.25f32, 0.5] {
+            let (mut producer, consumer) = rtrb::RingBuffer::<f32>::new(16);
+            let worker = CaptureWorker {
+                consumer,
+                resampler: None,
+                filter: None,
+                buffer: Vec::new(),
+                scratch: Vec::new(),
+                resample_output: Vec::new(),
+                sink,
+            };
+            for _ in 0..10 {
+                producer.push(value).unwrap();
+            }
+            drop(producer);
+            sink = std::thread::spawn(move || worker.run()).join().unwrap();
+            if value == 0.25 {
+                let _ = rx.recv().await.unwrap();
+            }
+        }
+
+        // The chunk buffered before the reopen is delivered ahead of new audio.
+        let chunk = rx.recv().await.unwrap();
+        assert_eq!(chunk.len(), 20);
+        assert_eq!(chunk[0], (0.25 * i16::MAX as f32) as i16);
+        assert_eq!(chunk[19], (0.5 * i16::MAX as f32) as i16);
+    }
+
+    #[test]
+    fn capture_source_labels_roundtrip() {
+        for source in CaptureSource::ALL {
+            assert_eq!(CaptureSource::from_label(source.label()), Some(source));
+        }
+        assert_eq!(CaptureSource::from_label("Speakers"), None);
+        assert_eq!(
+            serde_json::to_string(&CaptureSource::SystemAudio).unwrap(),
+            "\"system_audio\""
+        );
     }
 }
@@ -0,0 +1,177 @@
+// Append-only copy of the current session's finalized text. Each commit is
+// written as one JSON string per line and synced straight away, so after a
+// crash the file holds everything up to the last commit. A session that
+// finishes normally removes it; one left behind is set aside on the next
+// launch and offered for restore, so new sessions can't overwrite it. With
+// transcript encryption on, each line is sealed by the vault before it is
+// written.
+
+use crate::vault::{self, Vault};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use tracing::error;
+
+pub struct TranscriptAutosave {
+    path: PathBuf,
+    file: File,
+    vault: Option<&'static Vault>,
+}
+
+impl TranscriptAutosave {
+    /// Starts a fresh autosave at `path`, replacing any previous one. Lines
+    /// are sealed when `vault` is given.
+    pub fn create(path: &Path, vault: Option<&'static Vault>) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).write(true).truncate(true).open(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            vault,
+        })
+    }
+
+    pub fn append(&mut self, text: &str) -> io::Result<()> {
+        let mut line = serde_json::to_string(text).map_err(io::Error::other)?;
+        if let Some(vault) = self.vault {
+            line = vault.seal(&line).map_err(io::Error::other)?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.file.sync_data()
+    }
+
+    /// Closes and deletes the autosave once the session ended normally.
+    pub fn finish(self) {
+        let Self { path, file, .. } = self;
+        drop(file);
+        discard(&path);
+    }
+}
+
+/// Text left behind by a session that never finished, if any. A torn last
+/// line from a crash mid-write is skipped. `unlock` is asked for the vault
+/// only if the file has sealed lines.
+pub fn load_unfinished<'a>(path: &Path, unlock: impl Fn() -> Option<&'a Vault>) -> Option<String> {
+    let contents = fs::read_to_string(path).ok()?;
+    let text = contents
+        .lines()
+        .filter_map(|line| vault::read_line(line, &unlock).ok())
+        .filter_map(|line| serde_json::from_str::<String>(&line).ok())
+        .map(|segment| segment.trim().to_string())
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+/// Moves an autosave left at `path` to `recovered`, where the next session
+/// won't truncate it. If an earlier one is still waiting there, unanswered,
+/// the new lines are added to it.
+pub fn set_aside(path: &Path, recovered: &Path) -> io::Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    if !recovered.exists() {
+        return fs::rename(path, recovered);
+    }
+    let mut contents = fs::read_to_string(path)?;
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        // Drops a torn last line rather than gluing it to the next one.
+        contents.truncate(contents.rfind('\n').map_or(0, |end| end + 1));
+    }
+    let mut file = OpenOptions::new().append(true).open(recovered)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_data()?;
+    fs::remove_file(path)
+}
+
+pub fn discard(path: &Path) {
+    if let Err(e) = fs::remove_file(path) {
+        if e.kind() != io::ErrorKind::NotFound {
+            error!("❌ Failed to remove transcript autosave {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{discard, load_unfinished, set_aside, TranscriptAutosave};
+    use crate::vault::Vault;
+    use std::fs;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        std::env::temp_dir().join(format!("eleventh_echo_{}_{}.jsonl", name, stamp))
+    }
+
+    #[test]
+    fn unfinished_session_text_survives_and_skips_torn_lines() {
+        let path = temp_path("autosave");
+        let mut autosave = TranscriptAutosave::create(&path, None).unwrap();
+        autosave.append("First line.").unwrap();
+        autosave.append("Second \"quoted\"\nline.").unwrap();
+        drop(autosave);
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "\"half writ").unwrap();
+
+        assert_eq!(
+            load_unfinished(&path, || None).as_deref(),
+            Some("First line. Second \"quoted\"\nline.")
+        );
+        discard(&path);
+        assert_eq!(load_unfinished(&path, || None), None);
+    }
+
+    #[test]
+    fn finished_session_leaves_nothing_to_restore() {
+        let path = temp_path("autosave_done");
+        let mut autosave = TranscriptAutosave::create(&path, None).unwrap();
+        autosave.append("done").unwrap();
+        autosave.finish();
+        assert!(!path.exists());
+        assert_eq!(load_unfinished(&path, || None), None);
+    }
+
+    #[test]
+    fn set_aside_text_survives_the_next_session() {
+        let path = temp_path("autosave_live");
+        let recovered = temp_path("autosave_recovered");
+        let mut autosave = TranscriptAutosave::create(&path, None).unwrap();
+        autosave.append("Crashed once.").unwrap();
+        drop(autosave);
+        set_aside(&path, &recovered).unwrap();
+        assert!(!path.exists());
+
+        // A new session starts before the prompt is answered, and crashes too.
+        let mut autosave = TranscriptAutosave::create(&path, None).unwrap();
+        autosave.append("Crashed twice.").unwrap();
+        drop(autosave);
+        set_aside(&path, &recovered).unwrap();
+        set_aside(&path, &recovered).unwrap();
+
+        assert_eq!(
+            load_unfinished(&recovered, || None).as_deref(),
+            Some("Crashed once. Crashed twice.")
+        );
+        discard(&recovered);
+    }
+
+    #[test]
+    fn encrypted_autosave_needs_the_vault_to_restore() {
+        let path = temp_path("autosave_sealed");
+        let vault: &'static Vault = Box::leak(Box::new(Vault::from_secret(b"secret")));
+        let mut autosave = TranscriptAutosave::create(&path, Some(vault)).unwrap();
+        autosave.append("Account 1234.").unwrap();
+        drop(autosave);
+
+        assert!(!fs::read_to_string(&path).unwrap().contains("1234"));
+        assert_eq!(load_unfinished(&path, || Some(vault)).as_deref(), Some("Account 1234."));
+        assert_eq!(load_unfinished(&path, || None), None);
+        discard(&path);
+    }
+}
@@ -0,0 +1,74 @@
+#[cfg(windows)]
+use windows::core::{w, PCWSTR};
+#[cfg(windows)]
+use windows::Win32::Foundation::ERROR_FILE_NOT_FOUND;
+#[cfg(windows)]
+use windows::Win32::System::Registry::{RegDeleteKeyValueW, RegSetKeyValueW, HKEY_CURRENT_USER, REG_SZ};
+use std::path::Path;
+
+/// Passed by the login entry so the app starts hidden in the tray.
+pub const MINIMIZED_ARG: &str = "--minimized";
+
+#[cfg(windows)]
+const RUN_KEY: PCWSTR = w!("Software\\Microsoft\\Windows\\CurrentVersion\\Run");
+#[cfg(windows)]
+const RUN_VALUE: PCWSTR = w!("11th Echo");
+
+/// Command line stored in the Run key.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn run_command(exe: &Path) -> String {
+    format!("\"{}\" {}", exe.display(), MINIMIZED_ARG)
+}
+
+/// True when this process was started by the login entry.
+pub fn launched_minimized() -> bool {
+    std::env::args().skip(1).any(|arg| arg == MINIMIZED_ARG)
+}
+
+/// Adds or removes the per-user Run key entry pointing at this executable.
+#[cfg(windows)]
+pub fn set_enabled(enabled: bool) -> Result<(), String> {
+    if !enabled {
+        let result = unsafe { RegDeleteKeyValueW(HKEY_CURRENT_USER, RUN_KEY, RUN_VALUE) };
+        if result == ERROR_FILE_NOT_FOUND {
+            return Ok(());
+        }
+        return result.ok().map_err(|e| format!("Failed to remove login entry: {}", e));
+    }
+
+    let exe = std::env::current_exe().map_err(|e| format!("Failed to locate executable: {}", e))?;
+    let command: Vec<u16> = run_command(&exe).encode_utf16().chain(std::iter::once(0)).collect();
+    unsafe {
+        RegSetKeyValueW(
+            HKEY_CURRENT_USER,
+            RUN_KEY,
+            RUN_VALUE,
+            REG_SZ.0,
+            Some(command.as_ptr() as *const std::ffi::c_void),
+            (command.len() * 2) as u32,
+        )
+    }
+    .ok()
+    .map_err(|e| format!("Failed to add login entry: {}", e))
+}
+
+#[cfg(not(windows))]
+pub fn set_enabled(enabled: bool) -> Result<(), String> {
+    if enabled {
+        Err("Launch at login is only supported on Windows".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run_command, MINIMIZED_ARG};
+    use std::path::Path;
+
+    #[test]
+    fn run_command_quotes_path_and_starts_minimized() {
+        let command = run_command(Path::new("C:/Program Files/11th Echo/echo.exe"));
+        assert_eq!(command, format!("\"C:/Program Files/11th Echo/echo.exe\" {}", MINIMIZED_ARG));
+    }
+}
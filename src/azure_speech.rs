@@ -0,0 +1,418 @@
+use crate::audio::AudioFormat;
+use crate::error::EchoResult;
+use crate::network::{ControlMessage, ProviderError, TranscriptEvent, TranscriptSegment};
+use crate::provider::{connect_websocket, emit_log, ConnectError, ProviderCapabilities, ProviderKind, SpeechProvider};
+use crate::proxy::ProxyConfig;
+use async_trait::async_trait;
+use chrono::{SecondsFormat, Utc};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::json;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc::{self, Receiver, Sender, UnboundedReceiver, UnboundedSender};
+use tokio::time::{sleep_until, Instant};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::Message;
+use url::Url;
+
+const DEFAULT_LANGUAGE: &str = "en-US";
+/// Azure reports offsets and durations in 100ns ticks.
+const TICKS_PER_SECOND: f64 = 10_000_000.0;
+/// How long to wait for `turn.end` after the end of audio before committing
+/// the phrases received so far.
+const FINISH_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Azure Speech realtime recognition over the Speech service WebSocket
+/// protocol (the one the Speech SDK speaks), authenticated with a
+/// subscription key for a regional resource.
+pub struct AzureSpeechClient {
+    key: String,
+    region: String,
+    language: String,
+    format: AudioFormat,
+    proxy: Option<ProxyConfig>,
+}
+
+impl AzureSpeechClient {
+    pub fn new(key: String, region: String, language: String) -> Self {
+        Self {
+            key,
+            region,
+            language,
+            format: AudioFormat::default(),
+            proxy: None,
+        }
+    }
+
+    /// PCM only; the rate goes into the stream's WAV header.
+    pub fn with_audio_format(mut self, format: AudioFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn with_proxy(mut self, proxy: Option<ProxyConfig>) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    fn endpoint(&self) -> Result<Url, url::ParseError> {
+        let language = if self.language.is_empty() {
+            DEFAULT_LANGUAGE
+        } else {
+            self.language.as_str()
+        };
+        Url::parse_with_params(
+            &format!(
+                "wss://{}.stt.speech.microsoft.com/speech/recognition/conversation/cognitiveservices/v1",
+                self.region
+            ),
+            &[("language", language), ("format", "detailed")],
+        )
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum AzureEvent {
+    Hypothesis(String),
+    Phrase(TranscriptSegment),
+    TurnEnd,
+    Other,
+}
+
+/// Builds a segment from a detailed-format `speech.phrase` body: the top
+/// `NBest` entry supplies text and confidence, `Offset`/`Duration` the span.
+fn phrase_segment(body: &serde_json::Value) -> Option<TranscriptSegment> {
+    let best = body.get("NBest").and_then(|n| n.get(0));
+    let text = body
+        .get("DisplayText")
+        .or_else(|| best?.get("Display"))
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+    if text.is_empty() {
+        return None;
+    }
+    let mut segment = TranscriptSegment::text(text);
+    if let Some(offset) = body.get("Offset").and_then(|v| v.as_f64()) {
+        let duration = body.get("Duration").and_then(|v| v.as_f64()).unwrap_or_default();
+        segment.start_secs = Some(offset / TICKS_PER_SECOND);
+        segment.end_secs = Some((offset + duration) / TICKS_PER_SECOND);
+    }
+    segment.confidence = best
+        .and_then(|b| b.get("Confidence"))
+        .and_then(|v| v.as_f64())
+        .map(|c| c as f32);
+    Some(segment)
+}
+
+/// Splits a text frame into its `Path` header and JSON body and maps it to
+/// an event we care about.
+fn parse_text_frame(frame: &str) -> AzureEvent {
+    let (headers, body) = frame.split_once("\r\n\r\n").unwrap_or((frame, ""));
+    let path = headers
+        .lines()
+        .find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.trim().eq_ignore_ascii_case("path").then(|| value.trim().to_string())
+        })
+        .unwrap_or_default();
+    let body: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+
+    match path.as_str() {
+        "speech.hypothesis" => AzureEvent::Hypothesis(
+            body.get("Text").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+        ),
+        "speech.phrase" => {
+            let succeeded = body.get("RecognitionStatus").and_then(|v| v.as_str()) == Some("Success");
+            match phrase_segment(&body) {
+                Some(segment) if succeeded => AzureEvent::Phrase(segment),
+                _ => AzureEvent::Other,
+            }
+        }
+        "turn.end" => AzureEvent::TurnEnd,
+        _ => AzureEvent::Other,
+    }
+}
+
+fn request_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default();
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!("{:016x}{:016x}", nanos, count ^ u64::from(std::process::id()))
+}
+
+fn timestamp() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn speech_config_frame(request_id: &str) -> String {
+    let config = json!({
+        "context": {
+            "system": { "name": "11th Echo", "version": env!("CARGO_PKG_VERSION"), "build": "Rust" },
+            "os": { "platform": std::env::consts::OS, "name": std::env::consts::OS, "version": "" },
+            "audio": { "source": { "connectivity": "Unknown", "manufacturer": "", "model": "", "type": "Microphones" } }
+        }
+    });
+    format!(
+        "Path: speech.config\r\nX-RequestId: {}\r\nX-Timestamp: {}\r\nContent-Type: application/json\r\n\r\n{}",
+        request_id,
+        timestamp(),
+        config
+    )
+}
+
+/// 44-byte header for an open-ended 16-bit mono PCM stream. Azure expects
+/// the first audio frame of a turn to start with it.
+fn streaming_wav_header(sample_rate: u32) -> Vec<u8> {
+    let mut header = Vec::with_capacity(44);
+    header.extend_from_slice(b"RIFF");
+    header.extend_from_slice(&0u32.to_le_bytes());
+    header.extend_from_slice(b"WAVEfmt ");
+    header.extend_from_slice(&16u32.to_le_bytes());
+    header.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    header.extend_from_slice(&1u16.to_le_bytes()); // mono
+    header.extend_from_slice(&sample_rate.to_le_bytes());
+    header.extend_from_slice(&(sample_rate * 2).to_le_bytes()); // byte rate
+    header.extend_from_slice(&2u16.to_le_bytes()); // block align
+    header.extend_from_slice(&16u16.to_le_bytes());
+    header.extend_from_slice(b"data");
+    header.extend_from_slice(&0u32.to_le_bytes());
+    header
+}
+
+/// Binary audio frame: big-endian header length, ASCII headers, payload.
+/// An empty payload tells the service the audio stream has ended.
+fn audio_frame(request_id: &str, payload: &[u8]) -> Vec<u8> {
+    let headers = format!(
+        "Path: audio\r\nX-RequestId: {}\r\nX-Timestamp: {}\r\nContent-Type: audio/x-wav\r\n",
+        request_id,
+        timestamp()
+    );
+    let mut frame = Vec::with_capacity(2 + headers.len() + payload.len());
+    frame.extend_from_slice(&(headers.len() as u16).to_be_bytes());
+    frame.extend_from_slice(headers.as_bytes());
+    frame.extend_from_slice(payload);
+    frame
+}
+
+fn pcm_bytes(chunk: &[i16]) -> Vec<u8> {
+    chunk.iter().flat_map(|s| s.to_le_bytes()).collect()
+}
+
+#[async_trait]
+impl SpeechProvider for AzureSpeechClient {
+    fn name(&self) -> &'static str {
+        "Azure Speech"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderKind::Azure.capabilities()
+    }
+
+    fn audio_format(&self) -> AudioFormat {
+        self.format
+    }
+
+    async fn run(
+        &self,
+        mut audio_rx: Receiver<Vec<i16>>,
+        mut control_rx: UnboundedReceiver<ControlMessage>,
+        text_tx: Sender<TranscriptEvent>,
+        log_tx: UnboundedSender<String>,
+    ) -> EchoResult<()> {
+        let url = self.endpoint()?;
+        let connection_id = request_id();
+        emit_log(&log_tx, format!("🔌 Connecting to Azure Speech: {}", url));
+
+        let (ws_stream, response) = connect_websocket(&log_tx, self.proxy.as_ref(), || {
+            let mut request = url.as_str().into_client_request()?;
+            let key = self
+                .key
+                .parse()
+                .map_err(|_| ConnectError::InvalidRequest("Azure key contains invalid characters".into()))?;
+            let headers = request.headers_mut();
+            headers.insert("Ocp-Apim-Subscription-Key", key);
+            headers.insert(
+                "X-ConnectionId",
+                connection_id.parse().expect("hex connection id is a valid header"),
+            );
+            Ok(request)
+        })
+        .await?;
+        emit_log(
+            &log_tx,
+            format!("⬅️ [API IN] WebSocket CONNECT status={}", response.status()),
+        );
+        let (mut write, mut read) = ws_stream.split();
+
+        let turn_id = request_id();
+        write.send(Message::Text(speech_config_frame(&turn_id))).await?;
+        write
+            .send(Message::Binary(audio_frame(&turn_id, &streaming_wav_header(self.format.sample_rate))))
+            .await?;
+        emit_log(&log_tx, "✅ Azure Speech session configured".to_string());
+
+        let (turn_end_tx, mut turn_end_rx) = mpsc::unbounded_channel::<()>();
+        let (phrase_tx, mut phrase_rx) = mpsc::unbounded_channel::<TranscriptSegment>();
+        let log_tx_read = log_tx.clone();
+        let text_tx_read = text_tx.clone();
+        let read_task = tokio::spawn(async move {
+            while let Some(msg) = read.next().await {
+                match msg {
+                    Ok(Message::Text(text)) => match parse_text_frame(&text) {
+                        AzureEvent::Hypothesis(content) if !content.is_empty() => {
+                            emit_log(&log_tx_read, format!("📝 [PARTIAL] {}", content));
+                            let _ = text_tx_read
+                                .send(TranscriptEvent::Partial(TranscriptSegment::text(content)))
+                                .await;
+                        }
+                        AzureEvent::Phrase(segment) => {
+                            emit_log(&log_tx_read, format!("📝 [PHRASE] {}", segment.text));
+                            let _ = phrase_tx.send(segment);
+                        }
+                        AzureEvent::TurnEnd => {
+                            let _ = turn_end_tx.send(());
+                        }
+                        _ => {}
+                    },
+                    Ok(Message::Close(frame)) => {
+                        emit_log(&log_tx_read, format!("🔌 Azure WebSocket closed: {:?}", frame));
+                        let _ = turn_end_tx.send(());
+                        break;
+                    }
+                    Err(e) => {
+                        emit_log(&log_tx_read, format!("❌ Azure WebSocket error: {}", e));
+                        let _ = text_tx_read.send(TranscriptEvent::Error(ProviderError::new("websocket", e.to_string()))).await;
+                        let _ = turn_end_tx.send(());
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+        });
+
+        let mut accepting_audio = false;
+        let mut stopping = false;
+        let mut finish_by: Option<Instant> = None;
+        let mut final_phrases: Vec<TranscriptSegment> = Vec::new();
+        loop {
+            if stopping && finish_by.is_none() {
+                finish_by = Some(Instant::now() + FINISH_TIMEOUT);
+            }
+            tokio::select! {
+                Some(phrase) = phrase_rx.recv() => {
+                    if stopping {
+                        // Held back so the stop produces a single final commit.
+                        final_phrases.push(phrase);
+                    } else {
+                        let _ = text_tx.send(TranscriptEvent::Committed(phrase)).await;
+                    }
+                }
+                Some(()) = turn_end_rx.recv() => {
+                    while let Ok(phrase) = phrase_rx.try_recv() {
+                        final_phrases.push(phrase);
+                    }
+                    let _ = text_tx.send(TranscriptEvent::Committed(TranscriptSegment::join(final_phrases))).await;
+                    let _ = write.send(Message::Close(None)).await;
+                    break;
+                }
+                _ = sleep_until(finish_by.unwrap_or_else(Instant::now)), if finish_by.is_some() => {
+                    emit_log(
+                        &log_tx,
+                        format!("⚠️ Azure did not end the turn within {} s, committing what arrived", FINISH_TIMEOUT.as_secs()),
+                    );
+                    while let Ok(phrase) = phrase_rx.try_recv() {
+                        final_phrases.push(phrase);
+                    }
+                    let _ = text_tx.send(TranscriptEvent::Committed(TranscriptSegment::join(final_phrases))).await;
+                    let _ = write.send(Message::Close(None)).await;
+                    read_task.abort();
+                    return Ok(());
+                }
+                Some(cmd) = control_rx.recv(), if !stopping => {
+                    match cmd {
+                        ControlMessage::Start => accepting_audio = true,
+                        ControlMessage::Stop => {
+                            stopping = true;
+                            emit_log(&log_tx, "➡️ [API OUT] End of audio".to_string());
+                            write.send(Message::Binary(audio_frame(&turn_id, &[]))).await?;
+                        }
+                        ControlMessage::Cancel => {
+                            emit_log(&log_tx, "➡️ Session cancelled, closing Azure WebSocket".to_string());
+                            let _ = write.send(Message::Close(None)).await;
+                            read_task.abort();
+                            return Ok(());
+                        }
+                    }
+                }
+                maybe_chunk = audio_rx.recv(), if !stopping => {
+                    match maybe_chunk {
+                        Some(chunk) => {
+                            if accepting_audio {
+                                write.send(Message::Binary(audio_frame(&turn_id, &pcm_bytes(&chunk)))).await?;
+                            }
+                        }
+                        None => {
+                            stopping = true;
+                            write.send(Message::Binary(audio_frame(&turn_id, &[]))).await?;
+                        }
+                    }
+                }
+            }
+        }
+
+        let _ = read_task.await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{audio_frame, parse_text_frame, streaming_wav_header, AzureEvent, AzureSpeechClient};
+    use crate::network::TranscriptSegment;
+
+    #[test]
+    fn parses_hypothesis_phrase_and_turn_end() {
+        let hypothesis = "X-RequestId:abc\r\nPath:speech.hypothesis\r\nContent-Type:application/json\r\n\r\n{\"Text\":\"hello wor\",\"Offset\":0}";
+        assert_eq!(parse_text_frame(hypothesis), AzureEvent::Hypothesis("hello wor".to_string()));
+
+        let phrase = "Path: speech.phrase\r\n\r\n{\"RecognitionStatus\":\"Success\",\"DisplayText\":\"Hello world.\"}";
+        assert_eq!(parse_text_frame(phrase), AzureEvent::Phrase(TranscriptSegment::text("Hello world.")));
+
+        let detailed = "Path: speech.phrase\r\n\r\n{\"RecognitionStatus\":\"Success\",\"Offset\":5000000,\"Duration\":15000000,\"NBest\":[{\"Confidence\":0.62,\"Display\":\"Hi.\"}]}";
+        let AzureEvent::Phrase(segment) = parse_text_frame(detailed) else {
+            panic!("expected phrase");
+        };
+        assert_eq!(segment.text, "Hi.");
+        assert_eq!(segment.start_secs, Some(0.5));
+        assert_eq!(segment.end_secs, Some(2.0));
+        assert_eq!(segment.confidence, Some(0.62));
+
+        let no_match = "Path: speech.phrase\r\n\r\n{\"RecognitionStatus\":\"NoMatch\"}";
+        assert_eq!(parse_text_frame(no_match), AzureEvent::Other);
+
+        assert_eq!(parse_text_frame("Path: turn.end\r\n\r\n{}"), AzureEvent::TurnEnd);
+    }
+
+    #[test]
+    fn audio_frame_prefixes_header_length() {
+        let frame = audio_frame("rid", &[1, 2, 3]);
+        let header_len = u16::from_be_bytes([frame[0], frame[1]]) as usize;
+        let headers = std::str::from_utf8(&frame[2..2 + header_len]).unwrap();
+        assert!(headers.starts_with("Path: audio\r\nX-RequestId: rid\r\n"));
+        assert_eq!(&frame[2 + header_len..], &[1, 2, 3]);
+        let header = streaming_wav_header(8000);
+        assert_eq!(header.len(), 44);
+        assert_eq!(&header[24..32], &[0x40, 0x1F, 0, 0, 0x80, 0x3E, 0, 0]);
+    }
+
+    #[test]
+    fn endpoint_uses_region_and_default_language() {
+        let client = AzureSpeechClient::new("k".into(), "westeurope".into(), String::new());
+        let url = client.endpoint().unwrap();
+        assert_eq!(url.host_str(), Some("westeurope.stt.speech.microsoft.com"));
+        assert!(url.query().unwrap().contains("language=en-US"));
+    }
+}
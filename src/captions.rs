@@ -0,0 +1,215 @@
+// Live captions for streaming software. A localhost page shows the current
+// sentence as it is spoken; add http://127.0.0.1:<port>/?token=<token> as an
+// OBS browser source (transparent background) to overlay it on a stream.
+
+use crate::error::{EchoError, EchoResult};
+use crate::local_http;
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tiny_http::{Header, Method, Response, Server};
+use tracing::{error, info, warn};
+
+/// The caption page. It polls `/caption`, passing on the token from its own
+/// URL, and fades the text out a few seconds after the last final result.
+const CAPTION_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>11th Echo captions</title>
+<style>
+  html, body { margin: 0; background: transparent; overflow: hidden; }
+  #caption {
+    position: absolute; left: 4%; right: 4%; bottom: 6%;
+    font: 600 42px/1.25 "Segoe UI", sans-serif; color: #ffffff; text-align: center;
+    text-shadow: 0 0 6px #000, 0 0 3px #000, 2px 2px 2px #000;
+    transition: opacity 0.4s;
+  }
+  #caption.partial { color: #e8e8e8; }
+  #caption.hidden { opacity: 0; }
+</style>
+</head>
+<body>
+<div id="caption" class="hidden"></div>
+<script>
+  const caption = document.getElementById("caption");
+  let seq = -1, changedAt = Date.now();
+  async function poll() {
+    try {
+      const state = await (await fetch("/caption" + location.search, { cache: "no-store" })).json();
+      if (state.seq !== seq) {
+        seq = state.seq;
+        changedAt = Date.now();
+        caption.textContent = state.text;
+      }
+      const stale = state.final && Date.now() - changedAt > 5000;
+      caption.className = (!state.text || stale) ? "hidden" : (state.final ? "" : "partial");
+    } catch (e) {
+      caption.className = "hidden";
+    }
+    setTimeout(poll, 150);
+  }
+  poll();
+</script>
+</body>
+</html>
+"#;
+
+/// Roughly two lines at the page's font size; older words scroll off.
+const MAX_CAPTION_CHARS: usize = 110;
+
+/// The end of `text`, cut at a word boundary to fit a caption.
+fn caption_tail(text: &str) -> &str {
+    let text = text.trim();
+    let chars = text.chars().count();
+    if chars <= MAX_CAPTION_CHARS {
+        return text;
+    }
+    let (cut, _) = text.char_indices().nth(chars - MAX_CAPTION_CHARS).unwrap_or((0, ' '));
+    let tail = &text[cut..];
+    match tail.find(char::is_whitespace) {
+        Some(space) => tail[space..].trim_start(),
+        None => tail,
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Caption {
+    text: String,
+    is_final: bool,
+    /// Bumped on every change so the page can tell a repeat from an update.
+    seq: u64,
+}
+
+/// Shared handle the transcript task writes captions through.
+#[derive(Debug, Clone, Default)]
+pub struct CaptionFeed {
+    caption: Arc<Mutex<Caption>>,
+}
+
+impl CaptionFeed {
+    /// Shows the end of `text`; `is_final` once the provider has committed it.
+    pub fn set(&self, text: &str, is_final: bool) {
+        let text = caption_tail(text);
+        let mut caption = self.caption.lock().unwrap();
+        if caption.text == text && caption.is_final == is_final {
+            return;
+        }
+        caption.text = text.to_string();
+        caption.is_final = is_final;
+        caption.seq += 1;
+    }
+
+    pub fn clear(&self) {
+        self.set("", true);
+    }
+}
+
+struct Routed {
+    code: u16,
+    content_type: &'static str,
+    body: String,
+}
+
+fn route(method: &Method, path: &str, caption: &Caption) -> Routed {
+    let path = path.split('?').next().unwrap_or_default();
+    let (code, content_type, body) = match (method, path) {
+        (Method::Get, "/") => (200, "text/html; charset=utf-8", CAPTION_PAGE.to_string()),
+        (Method::Get, "/caption") => (
+            200,
+            "application/json",
+            json!({ "text": caption.text, "final": caption.is_final, "seq": caption.seq }).to_string(),
+        ),
+        (Method::Get, "/caption.txt") => (200, "text/plain; charset=utf-8", caption.text.clone()),
+        (_, "/" | "/caption" | "/caption.txt") => (405, "text/plain", "method not allowed".to_string()),
+        _ => (404, "text/plain", "not found".to_string()),
+    };
+    Routed { code, content_type, body }
+}
+
+/// Starts the caption server on a background thread. Every request needs
+/// `token`, see local_http.rs.
+///
+/// Endpoints:
+/// - `GET /` – the caption page for an OBS browser source
+/// - `GET /caption` – `{"text", "final", "seq"}` for custom overlays
+/// - `GET /caption.txt` – the current caption as plain text
+pub fn spawn(port: u16, token: String, feed: CaptionFeed) -> EchoResult<()> {
+    let server = Server::http(("127.0.0.1", port))
+        .map_err(|e| EchoError::config(format!("Could not listen on port {}: {}", port, e)))?;
+    info!("🎬 Captions available at http://127.0.0.1:{}/?token=…", port);
+
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let routed = match local_http::authorize(&request, port, &token) {
+                Ok(()) => {
+                    let caption = feed.caption.lock().unwrap();
+                    route(request.method(), request.url(), &caption)
+                }
+                Err(rejection) => {
+                    // Without the query string, which may carry `?token=`.
+                    let path = request.url().split('?').next().unwrap_or_default();
+                    warn!("🎬 Refused caption request {}: {}", path, rejection.reason);
+                    Routed {
+                        code: rejection.code,
+                        content_type: "text/plain",
+                        body: rejection.reason.to_string(),
+                    }
+                }
+            };
+            let content_type = Header::from_bytes(&b"Content-Type"[..], routed.content_type.as_bytes())
+                .expect("static header is valid");
+            let response = Response::from_string(routed.body)
+                .with_status_code(routed.code)
+                .with_header(content_type);
+            if let Err(e) = request.respond(response) {
+                error!("❌ Caption server failed to respond: {}", e);
+            }
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{caption_tail, route, CaptionFeed, MAX_CAPTION_CHARS};
+    use tiny_http::Method;
+
+    #[test]
+    fn captions_follow_partials_and_finals() {
+        let feed = CaptionFeed::default();
+        feed.set("hello wor", false);
+        feed.set("hello world", true);
+        feed.set("hello world", true);
+        let caption = feed.caption.lock().unwrap().clone();
+        assert_eq!(caption.seq, 2);
+
+        let routed = route(&Method::Get, "/caption?t=1", &caption);
+        assert_eq!(routed.code, 200);
+        let body: serde_json::Value = serde_json::from_str(&routed.body).unwrap();
+        assert_eq!(body["text"], "hello world");
+        assert_eq!(body["final"], true);
+        assert_eq!(route(&Method::Get, "/caption.txt", &caption).body, "hello world");
+    }
+
+    #[test]
+    fn long_transcripts_keep_only_the_latest_words() {
+        assert_eq!(caption_tail("  short  "), "short");
+        let long = format!("{} the end of the sentence", "blah ".repeat(40));
+        let tail = caption_tail(&long);
+        assert!(tail.chars().count() <= MAX_CAPTION_CHARS);
+        assert!(tail.ends_with("the end of the sentence"));
+        assert!(tail.starts_with("blah "));
+    }
+
+    #[test]
+    fn caption_page_and_unknown_paths() {
+        let caption = Default::default();
+        let page = route(&Method::Get, "/", &caption);
+        assert!(page.content_type.starts_with("text/html"));
+        assert!(page.body.contains("fetch(\"/caption\""));
+        assert_eq!(route(&Method::Post, "/caption", &caption).code, 405);
+        assert_eq!(route(&Method::Get, "/nope", &caption).code, 404);
+    }
+}
@@ -0,0 +1,246 @@
+// Voice commands: a committed phrase that starts with the command prefix
+// ("echo, press enter") runs a configured action instead of being typed.
+// Commands run as soon as they are recognized, so they suit voice control
+// between dictations rather than mid-sentence.
+
+use crate::error::{EchoError, EchoResult};
+use crate::injector;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_PREFIX: &str = "echo";
+
+/// What a recognized command does.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum CommandAction {
+    /// Key combos sent to the focused window in order, e.g. "Ctrl+A, Delete".
+    Keys(String),
+    /// A program, document or URL opened the way the shell would open it.
+    Run(String),
+}
+
+impl CommandAction {
+    /// Checks the action can run, without running it.
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            CommandAction::Keys(keys) => key_sequence(keys).map(|_| ()),
+            CommandAction::Run(target) if target.trim().is_empty() => Err("Nothing to run".to_string()),
+            CommandAction::Run(_) => Ok(()),
+        }
+    }
+
+    /// Blocks while keys are sent; launched programs are not waited for.
+    pub fn run(&self) -> EchoResult<()> {
+        match self {
+            CommandAction::Keys(keys) => {
+                for combo in key_sequence(keys).map_err(EchoError::config)? {
+                    injector::send_key_combo(&combo)?;
+                }
+                Ok(())
+            }
+            CommandAction::Run(target) => launch(target.trim()),
+        }
+    }
+}
+
+/// A spoken phrase and the action it triggers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoiceCommand {
+    pub phrase: String,
+    pub action: CommandAction,
+}
+
+impl VoiceCommand {
+    fn new(phrase: &str, action: CommandAction) -> Self {
+        Self {
+            phrase: phrase.to_string(),
+            action,
+        }
+    }
+}
+
+pub fn default_commands() -> Vec<VoiceCommand> {
+    vec![
+        VoiceCommand::new("press enter", CommandAction::Keys("Enter".to_string())),
+        VoiceCommand::new("press tab", CommandAction::Keys("Tab".to_string())),
+        VoiceCommand::new("select all", CommandAction::Keys("Ctrl+A".to_string())),
+        VoiceCommand::new("undo that", CommandAction::Keys("Ctrl+Z".to_string())),
+        VoiceCommand::new("open browser", CommandAction::Run("https://".to_string())),
+    ]
+}
+
+/// The outcome of checking a commit for a command.
+#[derive(Debug, PartialEq, Eq)]
+pub enum CommandMatch {
+    Known(VoiceCommand),
+    /// Prefixed, but no command has this phrase.
+    Unknown(String),
+}
+
+/// Looks for `prefix` at the start of `text` and the command spoken after
+/// it. Case and punctuation are ignored, so "Echo: Press enter." matches
+/// "press enter".
+pub fn match_command(text: &str, prefix: &str, commands: &[VoiceCommand]) -> Option<CommandMatch> {
+    let prefix = normalize(prefix);
+    if prefix.is_empty() {
+        return None;
+    }
+    let spoken = normalize(text);
+    let phrase = spoken.strip_prefix(&prefix)?;
+    if !phrase.is_empty() && !phrase.starts_with(' ') {
+        return None;
+    }
+    let phrase = phrase.trim();
+    Some(
+        commands
+            .iter()
+            .find(|command| normalize(&command.phrase) == phrase)
+            .map_or_else(|| CommandMatch::Unknown(phrase.to_string()), |command| CommandMatch::Known(command.clone())),
+    )
+}
+
+/// Lower case, with punctuation turned into spaces and runs of spaces
+/// collapsed.
+pub fn normalize(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn key_sequence(keys: &str) -> Result<Vec<injector::KeyCombo>, String> {
+    let combos = keys
+        .split(',')
+        .map(str::trim)
+        .filter(|combo| !combo.is_empty())
+        .map(injector::parse_key_combo)
+        .collect::<Result<Vec<_>, _>>()?;
+    if combos.is_empty() {
+        return Err("No keys given".to_string());
+    }
+    Ok(combos)
+}
+
+#[cfg(windows)]
+fn launch(target: &str) -> EchoResult<()> {
+    use windows::core::{w, PCWSTR};
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::Shell::ShellExecuteW;
+    use windows::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
+
+    // The shell resolves programs on the PATH, documents and URLs alike, and
+    // takes the target verbatim, so `&` in a query string is not a cmd separator.
+    let file: Vec<u16> = target.encode_utf16().chain(std::iter::once(0)).collect();
+    let instance = unsafe {
+        ShellExecuteW(HWND(0), w!("open"), PCWSTR(file.as_ptr()), PCWSTR::null(), PCWSTR::null(), SW_SHOWNORMAL)
+    };
+    // Anything above 32 is success.
+    if instance.0 <= 32 {
+        return Err(EchoError::config(format!("Could not run \"{}\" (shell error {})", target, instance.0)));
+    }
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn launch(target: &str) -> EchoResult<()> {
+    std::process::Command::new("xdg-open")
+        .arg(target)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| EchoError::config(format!("Could not run \"{}\": {}", target, e)))
+}
+
+/// Parses the settings text format, one command per line:
+///
+/// ```text
+/// press enter => keys: Enter
+/// clear line => keys: Home, Shift+End, Delete
+/// open notes => run: notepad.exe
+/// # comments and blank lines are ignored
+/// ```
+pub fn parse_commands(text: &str) -> Result<Vec<VoiceCommand>, String> {
+    let mut commands: Vec<VoiceCommand> = Vec::new();
+    for (index, raw_line) in text.lines().enumerate() {
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (phrase, action) = line
+            .split_once("=>")
+            .ok_or_else(|| format!("Line {}: expected \"phrase => keys: ...\" or \"phrase => run: ...\"", index + 1))?;
+        let phrase = normalize(phrase);
+        if phrase.is_empty() {
+            return Err(format!("Line {}: empty phrase", index + 1));
+        }
+        if commands.iter().any(|command| command.phrase == phrase) {
+            return Err(format!("Line {}: \"{}\" is defined more than once", index + 1, phrase));
+        }
+        let action = match action.trim().split_once(':') {
+            Some((kind, value)) if kind.trim().eq_ignore_ascii_case("keys") => CommandAction::Keys(value.trim().to_string()),
+            Some((kind, value)) if kind.trim().eq_ignore_ascii_case("run") => CommandAction::Run(value.trim().to_string()),
+            _ => return Err(format!("Line {}: the action must start with \"keys:\" or \"run:\"", index + 1)),
+        };
+        action.validate().map_err(|e| format!("Line {}: {}", index + 1, e))?;
+        commands.push(VoiceCommand { phrase, action });
+    }
+    Ok(commands)
+}
+
+/// Inverse of [`parse_commands`], used to populate the settings text box.
+pub fn format_commands(commands: &[VoiceCommand]) -> String {
+    commands
+        .iter()
+        .map(|command| match &command.action {
+            CommandAction::Keys(keys) => format!("{} => keys: {}", command.phrase, keys),
+            CommandAction::Run(target) => format!("{} => run: {}", command.phrase, target),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{default_commands, format_commands, match_command, parse_commands, CommandAction, CommandMatch};
+
+    #[test]
+    fn prefixed_phrases_match_regardless_of_case_and_punctuation() {
+        let commands = default_commands();
+        assert_eq!(
+            match_command("Echo: Press enter.", "echo", &commands),
+            Some(CommandMatch::Known(commands[0].clone()))
+        );
+        assert_eq!(
+            match_command("echo, fly away!", "Echo", &commands),
+            Some(CommandMatch::Unknown("fly away".to_string()))
+        );
+        assert_eq!(match_command("press enter", "echo", &commands), None);
+        assert_eq!(match_command("Echoes press enter", "echo", &commands), None);
+        assert_eq!(match_command("echo press enter", "", &commands), None);
+    }
+
+    #[test]
+    fn commands_roundtrip_through_text() {
+        let text = "# keys\nPress Enter => keys: Enter\nclear line => keys: Home, Shift+End, Delete\n\nopen notes => run: notepad.exe";
+        let commands = parse_commands(text).unwrap();
+        assert_eq!(commands[0].phrase, "press enter");
+        assert_eq!(commands[1].action, CommandAction::Keys("Home, Shift+End, Delete".to_string()));
+        assert_eq!(commands[2].action, CommandAction::Run("notepad.exe".to_string()));
+        assert_eq!(parse_commands(&format_commands(&commands)).unwrap(), commands);
+        assert_eq!(parse_commands(&format_commands(&default_commands())).unwrap(), default_commands());
+    }
+
+    #[test]
+    fn command_errors_name_the_line() {
+        let e = parse_commands("save => keys: Ctrl+S\nsave => keys: Ctrl+Shift+S").unwrap_err();
+        assert!(e.starts_with("Line 2") && e.contains("more than once"));
+        let e = parse_commands("save => type: hello").unwrap_err();
+        assert!(e.contains("keys:"));
+        let e = parse_commands("save => keys: Ctrl+Hyper").unwrap_err();
+        assert!(e.contains("Unsupported key token"));
+        let e = parse_commands("open => run:  ").unwrap_err();
+        assert!(e.contains("Nothing to run"));
+    }
+}
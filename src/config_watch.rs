@@ -0,0 +1,157 @@
+// Watches the settings file so edits made outside the app (by hand, a sync
+// tool or a script) apply without a restart.
+
+use crate::error::{EchoError, EchoResult};
+use crate::settings::AppSettings;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Editors often save in several steps (truncate, write, rename); events this
+/// close together are handled as one change.
+const SETTLE_TIME: Duration = Duration::from_millis(300);
+
+/// Keeps the watch alive; dropping it stops watching.
+pub struct ConfigWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+/// Calls `on_change` on a background thread whenever `path` is written,
+/// created or replaced. The parent folder is watched rather than the file,
+/// so a save that replaces the file is still seen.
+pub fn watch(path: PathBuf, on_change: impl Fn() + Send + 'static) -> EchoResult<ConfigWatcher> {
+    let folder = path
+        .parent()
+        .map(Path::to_path_buf)
+        .ok_or_else(|| EchoError::config(format!("{} has no parent folder", path.display())))?;
+    std::fs::create_dir_all(&folder)
+        .map_err(|e| EchoError::config(format!("Could not create {}: {}", folder.display(), e)))?;
+
+    let (tx, rx) = mpsc::channel::<()>();
+    let file_name = path.file_name().map(|name| name.to_os_string());
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<Event>| match result {
+        Ok(event) => {
+            let touches_file = event.paths.iter().any(|changed| changed.file_name() == file_name.as_deref());
+            if touches_file && (event.kind.is_create() || event.kind.is_modify()) {
+                let _ = tx.send(());
+            }
+        }
+        Err(e) => warn!("⚠️ Settings watcher error: {}", e),
+    })
+    .map_err(|e| EchoError::config(format!("Could not watch the settings file: {}", e)))?;
+    watcher
+        .watch(&folder, RecursiveMode::NonRecursive)
+        .map_err(|e| EchoError::config(format!("Could not watch {}: {}", folder.display(), e)))?;
+
+    std::thread::spawn(move || {
+        while rx.recv().is_ok() {
+            while rx.recv_timeout(SETTLE_TIME).is_ok() {}
+            debug!("Settings file changed on disk");
+            on_change();
+        }
+    });
+    Ok(ConfigWatcher { _watcher: watcher })
+}
+
+/// What a reload changed, for the status line. Empty when the file matches
+/// `current`, which is also what the app's own saves look like. Window state
+/// is left out: the app saves it on its own and only reads it at startup.
+pub fn describe_changes(current: &AppSettings, loaded: &AppSettings) -> Vec<&'static str> {
+    let as_json = |settings: &AppSettings| serde_json::to_value(settings).unwrap_or_default();
+    let mut without_window_state = loaded.clone();
+    without_window_state.mini_bar = current.mini_bar;
+    without_window_state.window_geometry = current.window_geometry;
+    without_window_state.active_tab = current.active_tab;
+    if as_json(current) == as_json(&without_window_state) {
+        return Vec::new();
+    }
+    let mut changes = Vec::new();
+    if current.replacement_rules != loaded.replacement_rules {
+        changes.push("replacements");
+    }
+    if current.format_numbers != loaded.format_numbers
+        || current.number_locale != loaded.number_locale
+        || current.formatters != loaded.formatters
+        || current.processors != loaded.processors
+    {
+        changes.push("formatting");
+    }
+    if current.keybindings != loaded.keybindings {
+        changes.push("hotkeys");
+    }
+    if changes.is_empty() {
+        changes.push("settings");
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{describe_changes, watch};
+    use crate::formatters::FormatterKind;
+    use crate::hotkey::HotkeyAction;
+    use crate::replacements::ReplacementRule;
+    use crate::settings::{AppSettings, WindowGeometry};
+    use std::sync::mpsc;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn changes_are_grouped_for_the_status_line() {
+        let current = AppSettings::default();
+        assert!(describe_changes(&current, &current.clone()).is_empty());
+
+        let mut loaded = current.clone();
+        loaded.replacement_rules = vec![ReplacementRule {
+            pattern: "k eight s".to_string(),
+            replacement: "k8s".to_string(),
+            is_regex: false,
+        }];
+        loaded.formatters = vec![FormatterKind::Legal];
+        loaded.set_binding(HotkeyAction::ToggleDictation, "Ctrl+F9");
+        assert_eq!(describe_changes(&current, &loaded), vec!["replacements", "formatting", "hotkeys"]);
+
+        let mut loaded = current.clone();
+        loaded.api_key = "sk_new".to_string();
+        assert_eq!(describe_changes(&current, &loaded), vec!["settings"]);
+    }
+
+    #[test]
+    fn window_state_alone_is_not_a_change() {
+        let current = AppSettings::default();
+        let mut loaded = current.clone();
+        loaded.mini_bar = true;
+        loaded.active_tab = 3;
+        loaded.window_geometry = Some(WindowGeometry {
+            x: 10,
+            y: 20,
+            width: 800,
+            height: 600,
+            maximized: false,
+        });
+        assert!(describe_changes(&current, &loaded).is_empty());
+
+        loaded.api_key = "sk_new".to_string();
+        assert_eq!(describe_changes(&current, &loaded), vec!["settings"]);
+    }
+
+    #[test]
+    fn writes_to_the_watched_file_are_reported_once_settled() {
+        let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        let folder = std::env::temp_dir().join(format!("eleventh_echo_watch_{}", stamp));
+        let path = folder.join("settings.json");
+        let (tx, rx) = mpsc::channel();
+        let _watcher = watch(path.clone(), move || {
+            let _ = tx.send(());
+        })
+        .unwrap();
+
+        std::fs::write(folder.join("other.json"), "{}").unwrap();
+        std::fs::write(&path, "{}").unwrap();
+        std::fs::write(&path, "{\"api_key\": \"x\"}").unwrap();
+        assert!(rx.recv_timeout(Duration::from_secs(5)).is_ok());
+        assert!(rx.recv_timeout(Duration::from_millis(800)).is_err());
+        let _ = std::fs::remove_dir_all(&folder);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::error::{EchoError, EchoResult};
+use crate::local_http;
+use serde::Serialize;
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tiny_http::{Header, Method, Response, Server};
+use tracing::{error, info, warn};
+
+/// Commands that external tools can issue through the HTTP API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlCommand {
+    Start,
+    Stop,
+    Toggle,
+}
+
+/// Snapshot of app state served to API clients. Refreshed by the UI timer;
+/// the transcript is built per request instead, see `spawn`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ControlStatus {
+    pub status: String,
+    /// `RecordingState::label()` of the current session.
+    pub state: String,
+    pub is_recording: bool,
+    pub has_error: bool,
+}
+
+struct Routed {
+    code: u16,
+    body: serde_json::Value,
+    command: Option<ControlCommand>,
+}
+
+fn route(method: &Method, path: &str, status: &ControlStatus, transcript: impl FnOnce() -> String) -> Routed {
+    let path = path.split('?').next().unwrap_or_default().trim_end_matches('/');
+    let command = match (method, path) {
+        (Method::Get, "/state") => {
+            return Routed {
+                code: 200,
+                body: json!({
+                    "status": status.status,
+                    "state": status.state,
+                    "is_recording": status.is_recording,
+                    "has_error": status.has_error,
+                }),
+                command: None,
+            };
+        }
+        (Method::Get, "/transcript") => {
+            return Routed {
+                code: 200,
+                body: json!({ "transcript": transcript() }),
+                command: None,
+            };
+        }
+        (Method::Post, "/start") => ControlCommand::Start,
+        (Method::Post, "/stop") => ControlCommand::Stop,
+        (Method::Post, "/toggle") => ControlCommand::Toggle,
+        (_, "/state" | "/transcript" | "/start" | "/stop" | "/toggle") => {
+            return Routed {
+                code: 405,
+                body: json!({ "error": "method not allowed" }),
+                command: None,
+            };
+        }
+        _ => {
+            return Routed {
+                code: 404,
+                body: json!({ "error": "not found" }),
+                command: None,
+            };
+        }
+    };
+
+    Routed {
+        code: 202,
+        body: json!({ "accepted": format!("{:?}", command).to_lowercase() }),
+        command: Some(command),
+    }
+}
+
+/// Starts the localhost control API on a background thread. Every request
+/// needs `token`, see local_http.rs. `transcript` is only called for
+/// `GET /transcript`.
+///
+/// Endpoints:
+/// - `GET /state` – current status text and recording flags
+/// - `GET /transcript` – the latest session's committed transcript
+/// - `POST /start`, `POST /stop`, `POST /toggle` – drive dictation
+pub fn spawn<F, T>(
+    port: u16,
+    token: String,
+    status: Arc<Mutex<ControlStatus>>,
+    transcript: T,
+    on_command: F,
+) -> EchoResult<()>
+where
+    F: Fn(ControlCommand) + Send + 'static,
+    T: Fn() -> String + Send + 'static,
+{
+    let server = Server::http(("127.0.0.1", port))
+        .map_err(|e| EchoError::config(format!("Could not listen on port {}: {}", port, e)))?;
+    info!("🌐 Control API listening on http://127.0.0.1:{}", port);
+
+    thread::spawn(move || {
+        let content_type = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+            .expect("static header is valid");
+        for request in server.incoming_requests() {
+            // Logged without the query string, which may carry `?token=`.
+            let path = request.url().split('?').next().unwrap_or_default().to_string();
+            let routed = match local_http::authorize(&request, port, &token) {
+                Ok(()) => {
+                    let snapshot = status.lock().unwrap().clone();
+                    route(request.method(), request.url(), &snapshot, &transcript)
+                }
+                Err(rejection) => {
+                    warn!("🌐 [API] Refused {} {}: {}", request.method(), path, rejection.reason);
+                    Routed {
+                        code: rejection.code,
+                        body: json!({ "error": rejection.reason }),
+                        command: None,
+                    }
+                }
+            };
+            if let Some(command) = routed.command {
+                info!("🌐 [API] {} {}", request.method(), path);
+                on_command(command);
+            }
+            let response = Response::from_string(routed.body.to_string())
+                .with_status_code(routed.code)
+                .with_header(content_type.clone());
+            if let Err(e) = request.respond(response) {
+                error!("❌ Control API failed to respond: {}", e);
+            }
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{route, ControlCommand, ControlStatus};
+    use tiny_http::Method;
+
+    fn status() -> ControlStatus {
+        ControlStatus {
+            status: "Listening...".to_string(),
+            state: "recording".to_string(),
+            is_recording: true,
+            has_error: false,
+        }
+    }
+
+    #[test]
+    fn state_endpoint_reports_flags() {
+        let routed = route(&Method::Get, "/state", &status(), String::new);
+        assert_eq!(routed.code, 200);
+        assert_eq!(routed.body["is_recording"], true);
+        assert_eq!(routed.body["status"], "Listening...");
+        assert_eq!(routed.body["state"], "recording");
+        assert!(routed.command.is_none());
+    }
+
+    #[test]
+    fn transcript_endpoint_returns_text() {
+        let routed = route(&Method::Get, "/transcript/", &status(), || "hello world".to_string());
+        assert_eq!(routed.body["transcript"], "hello world");
+    }
+
+    #[test]
+    fn post_endpoints_issue_commands() {
+        assert_eq!(route(&Method::Post, "/start", &status(), String::new).command, Some(ControlCommand::Start));
+        assert_eq!(route(&Method::Post, "/stop", &status(), String::new).command, Some(ControlCommand::Stop));
+        let toggle = route(&Method::Post, "/toggle?source=deck", &status(), String::new);
+        assert_eq!(toggle.code, 202);
+        assert_eq!(toggle.command, Some(ControlCommand::Toggle));
+    }
+
+    #[test]
+    fn wrong_method_and_unknown_path_are_rejected() {
+        let routed = route(&Method::Get, "/start", &status(), String::new);
+        assert_eq!(routed.code, 405);
+        assert!(routed.command.is_none());
+        assert_eq!(route(&Method::Get, "/nope", &status(), String::new).code, 404);
+    }
+}
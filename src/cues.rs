@@ -0,0 +1,191 @@
+// Short confirmation sounds for recording start, stop and errors. The tones
+// are synthesized on the fly and played on the default output device from a
+// throwaway thread, since a cpal stream can't cross threads on every host.
+
+use crate::error::{EchoError, EchoResult};
+use crate::tray::TrayStatus;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SizedSample};
+use std::f32::consts::TAU;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tracing::warn;
+
+/// Length of each tone in a cue.
+const TONE_MS: u32 = 70;
+/// Fade in/out per tone so the cue doesn't click.
+const FADE_MS: u32 = 8;
+/// Extra time the stream is kept open after the last sample.
+const TAIL_PADDING: Duration = Duration::from_millis(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cue {
+    Start,
+    Stop,
+    Error,
+}
+
+impl Cue {
+    /// The cue for moving into `status`, if it has one.
+    pub fn for_status(previous: TrayStatus, status: TrayStatus) -> Option<Self> {
+        match (previous, status) {
+            (TrayStatus::Recording, TrayStatus::Recording) => None,
+            (_, TrayStatus::Recording) => Some(Cue::Start),
+            (TrayStatus::Recording, TrayStatus::Finalizing) => Some(Cue::Stop),
+            (_, TrayStatus::Error) => Some(Cue::Error),
+            _ => None,
+        }
+    }
+
+    /// Tone frequencies in Hz, played back to back.
+    fn tones(self) -> &'static [f32] {
+        match self {
+            Cue::Start => &[660.0, 880.0],
+            Cue::Stop => &[880.0, 660.0],
+            Cue::Error => &[330.0, 0.0, 330.0],
+        }
+    }
+
+    /// Mono samples at `sample_rate`, peaking at `volume` (0.0-1.0). A zero
+    /// frequency is a gap of silence.
+    pub fn samples(self, sample_rate: u32, volume: f32) -> Vec<f32> {
+        let volume = volume.clamp(0.0, 1.0);
+        let tone_len = (sample_rate * TONE_MS / 1000) as usize;
+        let fade_len = ((sample_rate * FADE_MS / 1000) as usize).max(1);
+        let mut samples = Vec::with_capacity(tone_len * self.tones().len());
+        for &freq in self.tones() {
+            for i in 0..tone_len {
+                if freq == 0.0 {
+                    samples.push(0.0);
+                    continue;
+                }
+                let envelope = (i.min(tone_len - 1 - i) as f32 / fade_len as f32).min(1.0);
+                let phase = TAU * freq * i as f32 / sample_rate as f32;
+                samples.push(phase.sin() * envelope * volume);
+            }
+        }
+        samples
+    }
+}
+
+/// Which events make a sound, and how loud.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CueSettings {
+    pub on_start: bool,
+    pub on_stop: bool,
+    pub on_error: bool,
+    pub volume: f32,
+}
+
+impl CueSettings {
+    pub fn enabled(&self, cue: Cue) -> bool {
+        let on = match cue {
+            Cue::Start => self.on_start,
+            Cue::Stop => self.on_stop,
+            Cue::Error => self.on_error,
+        };
+        on && self.volume > 0.0
+    }
+}
+
+/// Plays `cue` in the background; failures are logged and otherwise ignored.
+pub fn play(cue: Cue, volume: f32) {
+    let spawned = thread::Builder::new().name("audio-cue".to_string()).spawn(move || {
+        if let Err(e) = play_blocking(cue, volume) {
+            warn!("⚠️ Could not play {:?} cue: {}", cue, e);
+        }
+    });
+    if let Err(e) = spawned {
+        warn!("⚠️ Could not start the cue thread: {}", e);
+    }
+}
+
+fn play_blocking(cue: Cue, volume: f32) -> EchoResult<()> {
+    let host = cpal::default_host();
+    let device = host
+        .default_output_device()
+        .ok_or_else(|| EchoError::audio("No output device available"))?;
+    let config = device.default_output_config()?;
+    let sample_rate = config.sample_rate().0;
+    let channels = config.channels() as usize;
+    let samples = Arc::new(cue.samples(sample_rate, volume));
+    let duration = Duration::from_secs_f32(samples.len() as f32 / sample_rate as f32) + TAIL_PADDING;
+
+    let sample_format = config.sample_format();
+    let stream_config: cpal::StreamConfig = config.into();
+    let stream = match sample_format {
+        cpal::SampleFormat::I16 => build_cue_stream::<i16>(&device, &stream_config, samples, channels),
+        cpal::SampleFormat::I32 => build_cue_stream::<i32>(&device, &stream_config, samples, channels),
+        cpal::SampleFormat::U16 => build_cue_stream::<u16>(&device, &stream_config, samples, channels),
+        cpal::SampleFormat::F32 => build_cue_stream::<f32>(&device, &stream_config, samples, channels),
+        cpal::SampleFormat::F64 => build_cue_stream::<f64>(&device, &stream_config, samples, channels),
+        other => return Err(EchoError::audio(format!("Unsupported output sample format {}", other))),
+    }?;
+    stream.play()?;
+    thread::sleep(duration);
+    Ok(())
+}
+
+/// Output stream that writes `samples` to every channel, then silence.
+fn build_cue_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    samples: Arc<Vec<f32>>,
+    channels: usize,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let mut position = 0;
+    device.build_output_stream(
+        config,
+        move |data: &mut [T], _: &_| {
+            for frame in data.chunks_mut(channels) {
+                let value = samples.get(position).copied().unwrap_or(0.0);
+                position += 1;
+                frame.fill(T::from_sample(value));
+            }
+        },
+        |err| warn!("⚠️ Cue output error: {}", err),
+        None,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cue, CueSettings};
+    use crate::tray::TrayStatus;
+
+    #[test]
+    fn status_changes_map_to_cues() {
+        assert_eq!(Cue::for_status(TrayStatus::Idle, TrayStatus::Recording), Some(Cue::Start));
+        assert_eq!(Cue::for_status(TrayStatus::Recording, TrayStatus::Finalizing), Some(Cue::Stop));
+        assert_eq!(Cue::for_status(TrayStatus::Recording, TrayStatus::Error), Some(Cue::Error));
+        assert_eq!(Cue::for_status(TrayStatus::Finalizing, TrayStatus::Idle), None);
+        assert_eq!(Cue::for_status(TrayStatus::Idle, TrayStatus::Finalizing), None);
+    }
+
+    #[test]
+    fn samples_respect_volume_and_fade_in() {
+        let samples = Cue::Start.samples(16000, 0.3);
+        assert_eq!(samples.len(), 2 * 16000 * 70 / 1000);
+        assert_eq!(samples[0], 0.0);
+        let peak = samples.iter().fold(0.0f32, |acc, s| acc.max(s.abs()));
+        assert!(peak > 0.25 && peak <= 0.3, "peak {}", peak);
+        assert!(Cue::Stop.samples(16000, 0.0).iter().all(|&s| s == 0.0));
+    }
+
+    #[test]
+    fn cues_can_be_disabled_per_event() {
+        let settings = CueSettings {
+            on_start: true,
+            on_stop: false,
+            on_error: true,
+            volume: 0.5,
+        };
+        assert!(settings.enabled(Cue::Start));
+        assert!(!settings.enabled(Cue::Stop));
+        assert!(!CueSettings { volume: 0.0, ..settings }.enabled(Cue::Error));
+    }
+}
@@ -0,0 +1,257 @@
+use std::collections::VecDeque;
+
+/// Cutoff for the high-pass stage. Speech carries very little below ~100Hz,
+/// while desk rumble, fans and mains hum live there.
+const HIGH_PASS_CUTOFF_HZ: f32 = 100.0;
+/// Envelope level (linear, ~-40 dBFS) under which the gate closes.
+const GATE_THRESHOLD: f32 = 0.01;
+/// Attenuation applied while the gate is closed (~-30 dB), so quiet word
+/// endings are softened rather than chopped off entirely.
+const GATE_FLOOR_GAIN: f32 = 0.03;
+/// How long the gate stays open after the signal drops below the threshold.
+const GATE_HOLD_SECS: f32 = 0.25;
+const GATE_ATTACK_SECS: f32 = 0.005;
+const GATE_RELEASE_SECS: f32 = 0.08;
+const ENVELOPE_RELEASE_SECS: f32 = 0.05;
+
+/// Lightweight noise reduction applied to raw input before resampling:
+/// a first-order high-pass filter followed by a noise gate.
+pub struct NoiseFilter {
+    hp_alpha: f32,
+    hp_prev_in: f32,
+    hp_prev_out: f32,
+    envelope: f32,
+    envelope_release: f32,
+    gain: f32,
+    attack_step: f32,
+    release_step: f32,
+    hold_samples: usize,
+    hold_remaining: usize,
+}
+
+impl NoiseFilter {
+    pub fn new(sample_rate: u32) -> Self {
+        let rate = sample_rate.max(1) as f32;
+        let dt = 1.0 / rate;
+        let rc = 1.0 / (2.0 * std::f32::consts::PI * HIGH_PASS_CUTOFF_HZ);
+        Self {
+            hp_alpha: rc / (rc + dt),
+            hp_prev_in: 0.0,
+            hp_prev_out: 0.0,
+            envelope: 0.0,
+            envelope_release: (-1.0 / (ENVELOPE_RELEASE_SECS * rate)).exp(),
+            gain: GATE_FLOOR_GAIN,
+            attack_step: (1.0 - GATE_FLOOR_GAIN) / (GATE_ATTACK_SECS * rate),
+            release_step: (1.0 - GATE_FLOOR_GAIN) / (GATE_RELEASE_SECS * rate),
+            hold_samples: (GATE_HOLD_SECS * rate) as usize,
+            hold_remaining: 0,
+        }
+    }
+
+    /// Filters `samples` in place.
+    pub fn process(&mut self, samples: &mut [f32]) {
+        for sample in samples.iter_mut() {
+            let input = *sample;
+            let high_passed = self.hp_alpha * (self.hp_prev_out + input - self.hp_prev_in);
+            self.hp_prev_in = input;
+            self.hp_prev_out = high_passed;
+
+            let level = high_passed.abs();
+            self.envelope = if level > self.envelope {
+                level
+            } else {
+                level + self.envelope_release * (self.envelope - level)
+            };
+
+            if self.envelope >= GATE_THRESHOLD {
+                self.hold_remaining = self.hold_samples;
+            } else if self.hold_remaining > 0 {
+                self.hold_remaining -= 1;
+            }
+
+            let gate_open = self.hold_remaining > 0;
+            self.gain = if gate_open {
+                (self.gain + self.attack_step).min(1.0)
+            } else {
+                (self.gain - self.release_step).max(GATE_FLOOR_GAIN)
+            };
+
+            *sample = high_passed * self.gain;
+        }
+    }
+}
+
+/// RMS level (16-bit PCM) above which a frame counts as speech (~-36 dBFS).
+const SPEECH_RMS_THRESHOLD: f64 = 500.0;
+/// Frame length used for speech detection (100ms at 16kHz).
+const VAD_FRAME_SAMPLES: usize = 1600;
+
+/// Counts consecutive silent audio at 16kHz and reports when it exceeds a timeout.
+pub struct SilenceDetector {
+    timeout_samples: usize,
+    silent_samples: usize,
+}
+
+impl SilenceDetector {
+    pub fn new(timeout_secs: u32, sample_rate: u32) -> Self {
+        Self {
+            timeout_samples: timeout_secs as usize * sample_rate as usize,
+            silent_samples: 0,
+        }
+    }
+
+    /// Feeds a chunk of PCM. Returns `true` once the accumulated silence
+    /// reaches the timeout.
+    pub fn observe(&mut self, chunk: &[i16]) -> bool {
+        for frame in chunk.chunks(VAD_FRAME_SAMPLES) {
+            if frame_is_speech(frame) {
+                self.silent_samples = 0;
+            } else {
+                self.silent_samples += frame.len();
+            }
+        }
+        self.timeout_samples > 0 && self.silent_samples >= self.timeout_samples
+    }
+}
+
+/// True when any 100ms frame of `samples` is loud enough to be speech.
+pub fn contains_speech(samples: &[i16]) -> bool {
+    samples.chunks(VAD_FRAME_SAMPLES).any(frame_is_speech)
+}
+
+fn frame_is_speech(frame: &[i16]) -> bool {
+    if frame.is_empty() {
+        return false;
+    }
+    let sum_sq: f64 = frame.iter().map(|&s| (s as f64) * (s as f64)).sum();
+    (sum_sq / frame.len() as f64).sqrt() >= SPEECH_RMS_THRESHOLD
+}
+
+/// Number of level readings kept for the waveform display.
+pub const LEVEL_HISTORY_LEN: usize = 64;
+/// Half-height of a bar for a silent reading, so the baseline stays visible.
+const MIN_BAR_HALF_HEIGHT: f32 = 0.02;
+
+/// Ring buffer of recent peak levels (0..1), oldest first.
+pub struct LevelHistory {
+    levels: VecDeque<f32>,
+}
+
+impl Default for LevelHistory {
+    fn default() -> Self {
+        Self {
+            levels: VecDeque::from(vec![0.0; LEVEL_HISTORY_LEN]),
+        }
+    }
+}
+
+impl LevelHistory {
+    pub fn push(&mut self, level: f32) {
+        if self.levels.len() == LEVEL_HISTORY_LEN {
+            self.levels.pop_front();
+        }
+        self.levels.push_back(level.clamp(0.0, 1.0));
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// SVG path commands drawing one vertical bar per reading, mirrored
+    /// around the middle of a `LEVEL_HISTORY_LEN` x 2 viewbox.
+    pub fn path_commands(&self) -> String {
+        self.levels
+            .iter()
+            .enumerate()
+            .map(|(i, level)| {
+                let half = level.max(MIN_BAR_HALF_HEIGHT);
+                let x = i as f32 + 0.5;
+                format!("M {x} {:.3} L {x} {:.3}", 1.0 - half, 1.0 + half)
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LevelHistory, NoiseFilter, SilenceDetector, LEVEL_HISTORY_LEN};
+
+    fn sine(rate: u32, freq: f32, amplitude: f32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| amplitude * (2.0 * std::f32::consts::PI * freq * i as f32 / rate as f32).sin())
+            .collect()
+    }
+
+    fn peak(samples: &[f32]) -> f32 {
+        samples.iter().fold(0.0f32, |acc, s| acc.max(s.abs()))
+    }
+
+    #[test]
+    fn high_pass_removes_dc_offset() {
+        let mut filter = NoiseFilter::new(16000);
+        let mut samples = vec![0.5f32; 16000];
+        filter.process(&mut samples);
+        assert!(peak(&samples[8000..]) < 0.001);
+    }
+
+    #[test]
+    fn gate_attenuates_quiet_noise() {
+        let mut filter = NoiseFilter::new(16000);
+        let mut samples = sine(16000, 1000.0, 0.004, 16000);
+        filter.process(&mut samples);
+        assert!(peak(&samples[8000..]) < 0.004 * 0.1);
+    }
+
+    #[test]
+    fn gate_passes_speech_level_signal() {
+        let mut filter = NoiseFilter::new(16000);
+        let mut samples = sine(16000, 440.0, 0.5, 16000);
+        filter.process(&mut samples);
+        assert!(peak(&samples[8000..]) > 0.4);
+    }
+
+    #[test]
+    fn silence_detector_fires_after_timeout() {
+        let mut detector = SilenceDetector::new(2, 16000);
+        let silence = vec![0i16; 16000];
+        assert!(!detector.observe(&silence));
+        assert!(detector.observe(&silence));
+    }
+
+    #[test]
+    fn speech_resets_silence_detector() {
+        let mut detector = SilenceDetector::new(2, 16000);
+        let silence = vec![0i16; 16000];
+        let mut speech = vec![0i16; 16000];
+        for (i, s) in speech.iter_mut().enumerate() {
+            *s = if i % 2 == 0 { 4000 } else { -4000 };
+        }
+        assert!(!detector.observe(&silence));
+        assert!(!detector.observe(&speech));
+        assert!(!detector.observe(&silence));
+        assert!(detector.observe(&silence));
+    }
+
+    #[test]
+    fn zero_timeout_disables_detector() {
+        let mut detector = SilenceDetector::new(0, 16000);
+        assert!(!detector.observe(&vec![0i16; 160000]));
+    }
+
+    #[test]
+    fn level_history_keeps_latest_readings() {
+        let mut history = LevelHistory::default();
+        for i in 0..LEVEL_HISTORY_LEN + 3 {
+            history.push(i as f32 / 100.0);
+        }
+        let commands = history.path_commands();
+        assert_eq!(commands.matches('M').count(), LEVEL_HISTORY_LEN);
+        // The oldest three readings were dropped, so the first bar is 0.03.
+        assert!(commands.starts_with("M 0.5 0.970 L 0.5 1.030"));
+        history.push(2.0);
+        assert!(history.path_commands().ends_with("0.000 L 63.5 2.000"));
+        history.clear();
+        assert!(history.path_commands().starts_with("M 0.5 0.980 L 0.5 1.020"));
+    }
+}
@@ -0,0 +1,96 @@
+// Running as administrator. Windows won't let a non-elevated app type into
+// an elevated one (UIPI), so dictating into an admin terminal needs this app
+// elevated as well; "Restart as administrator" relaunches it that way.
+
+use crate::error::{EchoError, EchoResult};
+#[cfg(windows)]
+use crate::single_instance::RELAUNCH_ARG;
+#[cfg(windows)]
+use std::sync::OnceLock;
+#[cfg(windows)]
+use windows::core::{w, PCWSTR};
+#[cfg(windows)]
+use windows::Win32::Foundation::{CloseHandle, HANDLE, HWND};
+#[cfg(windows)]
+use windows::Win32::Security::{GetTokenInformation, TokenElevation, TOKEN_ELEVATION, TOKEN_QUERY};
+#[cfg(windows)]
+use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+#[cfg(windows)]
+use windows::Win32::UI::Shell::ShellExecuteW;
+#[cfg(windows)]
+use windows::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
+
+/// Whether `process` runs with an elevated token.
+#[cfg(windows)]
+pub fn process_is_elevated(process: HANDLE) -> windows::core::Result<bool> {
+    let mut token = HANDLE::default();
+    unsafe { OpenProcessToken(process, TOKEN_QUERY, &mut token) }?;
+    let mut elevation = TOKEN_ELEVATION::default();
+    let mut size = 0u32;
+    let result = unsafe {
+        GetTokenInformation(
+            token,
+            TokenElevation,
+            Some(&mut elevation as *mut TOKEN_ELEVATION as *mut std::ffi::c_void),
+            std::mem::size_of::<TOKEN_ELEVATION>() as u32,
+            &mut size,
+        )
+    };
+    let _ = unsafe { CloseHandle(token) };
+    result.map(|()| elevation.TokenIsElevated != 0)
+}
+
+/// Whether this app is running as administrator.
+#[cfg(windows)]
+pub fn is_elevated() -> bool {
+    static ELEVATED: OnceLock<bool> = OnceLock::new();
+    *ELEVATED.get_or_init(|| process_is_elevated(unsafe { GetCurrentProcess() }).unwrap_or(false))
+}
+
+#[cfg(not(windows))]
+pub fn is_elevated() -> bool {
+    false
+}
+
+/// Whether "Restart as administrator" can do anything here.
+pub fn can_relaunch_elevated() -> bool {
+    cfg!(windows) && !is_elevated()
+}
+
+/// Starts an elevated copy of this executable through the UAC prompt. The
+/// copy waits for this instance to exit, so the caller should quit once this
+/// returns Ok.
+#[cfg(windows)]
+pub fn relaunch_elevated() -> EchoResult<()> {
+    let exe = std::env::current_exe()
+        .map_err(|e| EchoError::config(format!("Could not locate the executable: {}", e)))?;
+    let exe: Vec<u16> = exe.to_string_lossy().encode_utf16().chain(std::iter::once(0)).collect();
+    let args: Vec<u16> = RELAUNCH_ARG.encode_utf16().chain(std::iter::once(0)).collect();
+    // Without a directory the elevated copy starts in System32.
+    let dir: Option<Vec<u16>> = std::env::current_dir()
+        .ok()
+        .map(|dir| dir.to_string_lossy().encode_utf16().chain(std::iter::once(0)).collect());
+    let directory = dir.as_ref().map_or(PCWSTR::null(), |dir| PCWSTR(dir.as_ptr()));
+    let instance = unsafe {
+        ShellExecuteW(
+            HWND(0),
+            w!("runas"),
+            PCWSTR(exe.as_ptr()),
+            PCWSTR(args.as_ptr()),
+            directory,
+            SW_SHOWNORMAL,
+        )
+    };
+    // Anything above 32 is success; declining the UAC prompt lands below.
+    if instance.0 <= 32 {
+        return Err(EchoError::config(
+            "Windows did not start 11th Echo as administrator (the prompt may have been declined)",
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(not(windows))]
+pub fn relaunch_elevated() -> EchoResult<()> {
+    Err(EchoError::config("Restarting as administrator is only available on Windows"))
+}
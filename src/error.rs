@@ -0,0 +1,135 @@
+// Crate-wide error type. Each variant is a category the UI can give its own
+// advice for; the message carries the detail from the failing library.
+
+use crate::i18n::tr;
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum EchoError {
+    /// Opening or running an input/output device, or writing audio files.
+    #[error("{0}")]
+    Audio(String),
+    /// Talking to a speech, translation or post-processing service.
+    #[error("{0}")]
+    Network(String),
+    /// Typing into (or focusing) another application's window.
+    #[error("{0}")]
+    Injection(String),
+    /// Missing or invalid settings, keys and fixture files.
+    #[error("{0}")]
+    Config(String),
+    /// The window to type into runs as administrator and this app doesn't,
+    /// so Windows blocks the keystrokes.
+    #[error("the window you're dictating into is running as administrator")]
+    ElevatedTarget,
+}
+
+pub type EchoResult<T> = Result<T, EchoError>;
+
+impl EchoError {
+    pub fn audio(message: impl Into<String>) -> Self {
+        EchoError::Audio(message.into())
+    }
+
+    pub fn network(message: impl Into<String>) -> Self {
+        EchoError::Network(message.into())
+    }
+
+    pub fn injection(message: impl Into<String>) -> Self {
+        EchoError::Injection(message.into())
+    }
+
+    pub fn config(message: impl Into<String>) -> Self {
+        EchoError::Config(message.into())
+    }
+
+    pub fn category(&self) -> &'static str {
+        match self {
+            EchoError::Audio(_) => "Audio",
+            EchoError::Network(_) => "Network",
+            EchoError::Injection(_) | EchoError::ElevatedTarget => "Injection",
+            EchoError::Config(_) => "Configuration",
+        }
+    }
+
+    /// What the user can do about this kind of failure.
+    pub fn guidance(&self) -> &'static str {
+        match self {
+            EchoError::Audio(_) => "Check that a microphone is connected and not in use by another app, or pick another one in Settings.",
+            EchoError::Network(_) => "Check your internet connection, proxy and API key, then try again.",
+            EchoError::Injection(_) => "Click into the window you want to type into, or copy the transcript from the Transcripts tab.",
+            EchoError::Config(_) => "Open Settings and fill in the missing or invalid values.",
+            EchoError::ElevatedTarget => "Run 11th Echo as administrator, or add \"Copy to the clipboard\" to the outputs and paste the transcript.",
+        }
+    }
+
+    /// "Category: message" plus guidance, for banners and status lines, in
+    /// the UI language. Messages from libraries have no catalog entry and
+    /// stay as they are.
+    pub fn user_message(&self) -> String {
+        tr!("{} error: {} {}", tr!(self.category()), tr!(&self.to_string()), tr!(self.guidance()))
+    }
+}
+
+macro_rules! echo_error_from {
+    ($variant:ident: $($source:ty),+ $(,)?) => {
+        $(
+            impl From<$source> for EchoError {
+                fn from(e: $source) -> Self {
+                    EchoError::$variant(e.to_string())
+                }
+            }
+        )+
+    };
+}
+
+echo_error_from!(Audio:
+    cpal::BuildStreamError,
+    cpal::PlayStreamError,
+    cpal::DefaultStreamConfigError,
+    cpal::SupportedStreamConfigsError,
+    cpal::DevicesError,
+    hound::Error,
+);
+
+echo_error_from!(Network:
+    tokio_tungstenite::tungstenite::Error,
+    reqwest::Error,
+    url::ParseError,
+    serde_json::Error,
+    http::Error,
+);
+
+echo_error_from!(Config:
+    rusqlite::Error,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::EchoError;
+
+    #[test]
+    fn library_errors_map_to_their_category() {
+        let parse: EchoError = url::Url::parse("not a url").unwrap_err().into();
+        assert!(matches!(parse, EchoError::Network(_)));
+        let device: EchoError = cpal::DefaultStreamConfigError::DeviceNotAvailable.into();
+        assert_eq!(device.category(), "Audio");
+    }
+
+    #[test]
+    fn user_message_leads_with_category_and_ends_with_guidance() {
+        let error = EchoError::config("Missing API key");
+        assert_eq!(error.to_string(), "Missing API key");
+        let message = error.user_message();
+        assert!(message.starts_with("Configuration error: Missing API key"));
+        assert!(message.ends_with(error.guidance()));
+    }
+
+    #[test]
+    fn elevated_targets_suggest_admin_or_clipboard() {
+        let message = EchoError::ElevatedTarget.user_message();
+        assert!(message.starts_with("Injection error: the window you're dictating into is running as administrator"));
+        assert!(message.contains("Run 11th Echo as administrator"));
+        assert!(message.contains("Copy to the clipboard"));
+    }
+}
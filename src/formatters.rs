@@ -0,0 +1,306 @@
+// Dictation styles. A profile can chain formatters that reshape each commit
+// for a particular kind of writing: code identifiers, medical notes with
+// their shorthand spelled out, or legal text with section references.
+// They run after number formatting and before the user's replacements.
+
+use regex::{Captures, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FormatterKind {
+    SnakeCase,
+    CamelCase,
+    Medical,
+    Legal,
+}
+
+impl FormatterKind {
+    pub const ALL: [FormatterKind; 4] = [
+        FormatterKind::SnakeCase,
+        FormatterKind::CamelCase,
+        FormatterKind::Medical,
+        FormatterKind::Legal,
+    ];
+
+    /// The name used in the settings text box.
+    pub fn label(self) -> &'static str {
+        match self {
+            FormatterKind::SnakeCase => "snake_case",
+            FormatterKind::CamelCase => "camelCase",
+            FormatterKind::Medical => "medical",
+            FormatterKind::Legal => "legal",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.label().eq_ignore_ascii_case(label))
+    }
+
+    fn build(self) -> Box<dyn Formatter> {
+        match self {
+            FormatterKind::SnakeCase => Box::new(Identifier { camel: false }),
+            FormatterKind::CamelCase => Box::new(Identifier { camel: true }),
+            FormatterKind::Medical => Box::new(MedicalAbbreviations::new()),
+            FormatterKind::Legal => Box::new(LegalNumbering::new()),
+        }
+    }
+}
+
+/// One step of a dictation style.
+pub trait Formatter: Send + Sync {
+    fn format(&self, text: &str) -> String;
+
+    /// Whether the output must reach the transcript as written, without
+    /// sentence capitalization or spacing fixes.
+    fn verbatim(&self) -> bool {
+        false
+    }
+}
+
+/// A profile's formatters, built once per session and applied in order.
+pub struct FormatterChain {
+    formatters: Vec<Box<dyn Formatter>>,
+}
+
+impl FormatterChain {
+    pub fn new(kinds: &[FormatterKind]) -> Self {
+        Self {
+            formatters: kinds.iter().map(|kind| kind.build()).collect(),
+        }
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        self.formatters
+            .iter()
+            .fold(text.to_string(), |text, formatter| formatter.format(&text))
+    }
+    /// True when a formatter in the chain produces text that must not be
+    /// reformatted, such as an identifier.
+    pub fn is_verbatim(&self) -> bool {
+        self.formatters.iter().any(|formatter| formatter.verbatim())
+    }
+}
+
+/// Turns the whole commit into one identifier: "get user name" becomes
+/// `get_user_name` or `getUserName`.
+struct Identifier {
+    camel: bool,
+}
+
+impl Formatter for Identifier {
+    fn format(&self, text: &str) -> String {
+        let words = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase);
+        if !self.camel {
+            return words.collect::<Vec<_>>().join("_");
+        }
+        words
+            .enumerate()
+            .map(|(index, word)| {
+                let mut chars = word.chars();
+                match chars.next() {
+                    Some(first) if index > 0 => first.to_uppercase().chain(chars).collect(),
+                    _ => word,
+                }
+            })
+            .collect()
+    }
+
+    fn verbatim(&self) -> bool {
+        true
+    }
+}
+
+/// Prescription and charting shorthand, written out. Dotted forms ("b.i.d.")
+/// are matched as well as the plain ones.
+const MEDICAL_ABBREVIATIONS: [(&str, &str); 14] = [
+    ("qd", "once daily"),
+    ("bid", "twice daily"),
+    ("tid", "three times daily"),
+    ("qid", "four times daily"),
+    ("qhs", "at bedtime"),
+    ("prn", "as needed"),
+    ("po", "by mouth"),
+    ("npo", "nothing by mouth"),
+    ("iv", "intravenously"),
+    ("im", "intramuscularly"),
+    ("sob", "shortness of breath"),
+    ("htn", "hypertension"),
+    ("dm", "diabetes mellitus"),
+    ("nkda", "no known drug allergies"),
+];
+
+struct MedicalAbbreviations {
+    pattern: Regex,
+}
+
+impl MedicalAbbreviations {
+    fn new() -> Self {
+        let alternatives = MEDICAL_ABBREVIATIONS
+            .iter()
+            .map(|(abbreviation, _)| {
+                let letters: Vec<String> = abbreviation.chars().map(|c| c.to_string()).collect();
+                format!(r"{}\b|{}\.?", abbreviation, letters.join(r"\."))
+            })
+            .collect::<Vec<_>>()
+            .join("|");
+        let pattern = RegexBuilder::new(&format!(r"\b(?:{})", alternatives))
+            .case_insensitive(true)
+            .build()
+            .expect("medical abbreviation pattern");
+        Self { pattern }
+    }
+}
+
+impl Formatter for MedicalAbbreviations {
+    fn format(&self, text: &str) -> String {
+        self.pattern
+            .replace_all(text, |caps: &Captures| {
+                let key = caps[0].replace('.', "").to_lowercase();
+                MEDICAL_ABBREVIATIONS
+                    .iter()
+                    .find(|(abbreviation, _)| *abbreviation == key)
+                    .map_or_else(|| caps[0].to_string(), |(_, expansion)| expansion.to_string())
+            })
+            .into_owned()
+    }
+}
+
+const SMALL_NUMBERS: [&str; 20] = [
+    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten", "eleven", "twelve",
+    "thirteen", "fourteen", "fifteen", "sixteen", "seventeen", "eighteen", "nineteen", "twenty",
+];
+
+/// "section twelve subsection b" becomes "§ 12(b)" and "paragraphs four"
+/// becomes "¶¶ 4".
+struct LegalNumbering {
+    reference: Regex,
+    subdivision: Regex,
+}
+
+impl LegalNumbering {
+    fn new() -> Self {
+        let number = format!(r"\d+(?:\.\d+)*|{}", SMALL_NUMBERS.join("|"));
+        let reference = RegexBuilder::new(&format!(r"\b(sections?|paragraphs?)\s+({})\b", number))
+            .case_insensitive(true)
+            .build()
+            .expect("legal reference pattern");
+        let subdivision = RegexBuilder::new(&format!(
+            r"([§¶] [\w.()]+),?\s+(?:sub-?\s?section|clause)\s+([a-z]\b|[ivx]+\b|{})\b",
+            number
+        ))
+        .case_insensitive(true)
+        .build()
+        .expect("legal subdivision pattern");
+        Self { reference, subdivision }
+    }
+}
+
+impl Formatter for LegalNumbering {
+    fn format(&self, text: &str) -> String {
+        let mut output = self
+            .reference
+            .replace_all(text, |caps: &Captures| {
+                let word = caps[1].to_lowercase();
+                let sign = match (word.starts_with("section"), word.ends_with('s')) {
+                    (true, false) => "§",
+                    (true, true) => "§§",
+                    (false, false) => "¶",
+                    (false, true) => "¶¶",
+                };
+                format!("{} {}", sign, legal_number(&caps[2]))
+            })
+            .into_owned();
+        // Each pass attaches one level: "§ 3(a)" then "§ 3(a)(ii)".
+        loop {
+            let next = self
+                .subdivision
+                .replace(&output, |caps: &Captures| {
+                    format!("{}({})", &caps[1], legal_number(&caps[2]).to_lowercase())
+                })
+                .into_owned();
+            if next == output {
+                return output;
+            }
+            output = next;
+        }
+    }
+}
+
+fn legal_number(token: &str) -> String {
+    let lower = token.to_lowercase();
+    SMALL_NUMBERS
+        .iter()
+        .position(|word| *word == lower)
+        .map_or_else(|| token.to_string(), |index| (index + 1).to_string())
+}
+
+/// Parses the settings text box: formatter names separated by commas, in the
+/// order they run.
+pub fn parse_formatters(text: &str) -> Result<Vec<FormatterKind>, String> {
+    let mut kinds = Vec::new();
+    for name in text.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+        let kind = FormatterKind::from_label(name).ok_or_else(|| {
+            let known: Vec<&str> = FormatterKind::ALL.iter().map(|kind| kind.label()).collect();
+            format!("Unknown formatter \"{}\" (expected one of {})", name, known.join(", "))
+        })?;
+        if !kinds.contains(&kind) {
+            kinds.push(kind);
+        }
+    }
+    Ok(kinds)
+}
+
+pub fn format_formatters(kinds: &[FormatterKind]) -> String {
+    kinds.iter().map(|kind| kind.label()).collect::<Vec<_>>().join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_formatters, parse_formatters, FormatterChain, FormatterKind};
+
+    fn apply(kinds: &[FormatterKind], text: &str) -> String {
+        FormatterChain::new(kinds).apply(text)
+    }
+
+    #[test]
+    fn identifiers_join_the_spoken_words() {
+        assert_eq!(apply(&[FormatterKind::SnakeCase], "Get user name."), "get_user_name");
+        assert_eq!(apply(&[FormatterKind::CamelCase], "get user-name 2"), "getUserName2");
+        assert_eq!(apply(&[], "As said."), "As said.");
+        assert!(FormatterChain::new(&[FormatterKind::Medical, FormatterKind::CamelCase]).is_verbatim());
+        assert!(!FormatterChain::new(&[FormatterKind::Legal]).is_verbatim());
+    }
+
+    #[test]
+    fn medical_shorthand_is_written_out() {
+        assert_eq!(
+            apply(&[FormatterKind::Medical], "Amoxicillin 500 mg PO b.i.d., paracetamol prn. Pt has HTN"),
+            "Amoxicillin 500 mg by mouth twice daily, paracetamol as needed. Pt has hypertension"
+        );
+        // Only whole words.
+        assert_eq!(apply(&[FormatterKind::Medical], "the poem"), "the poem");
+    }
+
+    #[test]
+    fn legal_references_use_section_and_paragraph_signs() {
+        assert_eq!(
+            apply(&[FormatterKind::Legal], "Under section twelve subsection b, clause ii and paragraphs 4"),
+            "Under § 12(b)(ii) and ¶¶ 4"
+        );
+        assert_eq!(apply(&[FormatterKind::Legal], "see Section 2.1"), "see § 2.1");
+    }
+
+    #[test]
+    fn formatter_names_roundtrip_in_order() {
+        let kinds = parse_formatters("Legal, snake_case,, legal").unwrap();
+        assert_eq!(kinds, vec![FormatterKind::Legal, FormatterKind::SnakeCase]);
+        assert_eq!(parse_formatters(&format_formatters(&kinds)).unwrap(), kinds);
+        assert!(parse_formatters("pirate").unwrap_err().contains("expected one of snake_case"));
+    }
+}
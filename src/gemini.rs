@@ -1,9 +1,17 @@
 use crate::settings::AppSettings;
-use reqwest::Client;
 use serde_json::json;
+use tracing::{error, info, warn};
 
 const GEMINI_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta/models";
 
+/// The prompt presets `build_prompt` knows, as saved in the settings.
+pub const PRESETS: [&str; 4] = [
+    "Minimal corrections",
+    "Sound like a pirate",
+    "Sound like a medieval knight",
+    "Custom",
+];
+
 fn build_prompt(settings: &AppSettings, original: &str) -> String {
     let base_instruction = match settings.gemini_prompt_preset.as_str() {
         "Minimal corrections" => "You are a text rewriter. Take the user's text and ONLY fix minimal grammar, spelling, and punctuation. Do not change the tone or meaning. Return ONLY the corrected text with no explanations, no prefixes, and no extra commentary.",
@@ -20,9 +28,16 @@ fn build_prompt(settings: &AppSettings, original: &str) -> String {
     )
 }
 
-pub async fn rewrite_text(api_key: &str, model: &str, prompt_preset: &str, custom_prompt: &str, original: &str) -> String {
+pub async fn rewrite_text(
+    api_key: &str,
+    model: &str,
+    prompt_preset: &str,
+    custom_prompt: &str,
+    proxy_url: &str,
+    original: &str,
+) -> String {
     if api_key.trim().is_empty() {
-        eprintln!("⚠️ Gemini rewriting is enabled but no API key is configured; skipping rewrite.");
+        warn!("⚠️ Gemini rewriting is enabled but no API key is configured; skipping rewrite.");
         return original.to_string();
     }
 
@@ -39,7 +54,7 @@ pub async fn rewrite_text(api_key: &str, model: &str, prompt_preset: &str, custo
         ..Default::default()
     };
     let prompt = build_prompt(&settings_stub, original);
-    println!("🤖 [Gemini] Sending rewrite request to {} ({} chars)", model_id, original.len());
+    info!("🤖 [Gemini] Sending rewrite request to {} ({} chars)", model_id, original.len());
 
     let body = json!({
         "contents": [{
@@ -51,7 +66,13 @@ pub async fn rewrite_text(api_key: &str, model: &str, prompt_preset: &str, custo
         }
     });
 
-    let client = Client::new();
+    let client = match crate::proxy::resolve(proxy_url).and_then(|proxy| crate::proxy::http_client(proxy.as_ref(), None)) {
+        Ok(client) => client,
+        Err(e) => {
+            error!("❌ Gemini request failed: {}", e);
+            return original.to_string();
+        }
+    };
     let response = match client
         .post(&endpoint)
         .header("x-goog-api-key", api_key)
@@ -61,7 +82,7 @@ pub async fn rewrite_text(api_key: &str, model: &str, prompt_preset: &str, custo
     {
         Ok(r) => r,
         Err(e) => {
-            eprintln!("❌ Gemini request failed: {}", e);
+            error!("❌ Gemini request failed: {}", e);
             return original.to_string();
         }
     };
@@ -70,7 +91,7 @@ pub async fn rewrite_text(api_key: &str, model: &str, prompt_preset: &str, custo
     let value: serde_json::Value = match response.json().await {
         Ok(v) => v,
         Err(e) => {
-            eprintln!("❌ Failed to decode Gemini response (status {}): {}", status, e);
+            error!("❌ Failed to decode Gemini response (status {}): {}", status, e);
             return original.to_string();
         }
     };
@@ -86,14 +107,14 @@ pub async fn rewrite_text(api_key: &str, model: &str, prompt_preset: &str, custo
     {
         let cleaned = text.trim();
         if cleaned.is_empty() {
-            println!("🤖 [Gemini] Got empty response, using original text");
+            info!("🤖 [Gemini] Got empty response, using original text");
             original.to_string()
         } else {
-            println!("🤖 [Gemini] Rewrite complete: \"{}\" -> \"{}\"", original, cleaned);
+            info!("🤖 [Gemini] Rewrite complete: \"{}\" -> \"{}\"", original, cleaned);
             cleaned.to_string()
         }
     } else {
-        eprintln!(
+        error!(
             "❌ Unexpected Gemini response structure (status {}): {}",
             status,
             value
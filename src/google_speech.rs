@@ -0,0 +1,307 @@
+use crate::audio::{AudioEncoding, AudioFormat};
+use crate::dsp::contains_speech;
+use crate::error::EchoResult;
+use crate::network::{ControlMessage, ProviderError, TranscriptEvent, TranscriptSegment, WordTiming};
+use crate::provider::{emit_log, ProviderCapabilities, ProviderKind, SpeechProvider};
+use crate::proxy::ProxyConfig;
+use async_trait::async_trait;
+use base64::{engine::general_purpose, Engine as _};
+use reqwest::Client;
+use serde_json::json;
+use tokio::sync::mpsc::{Receiver, Sender, UnboundedReceiver, UnboundedSender};
+
+const GOOGLE_RECOGNIZE_URL: &str = "https://speech.googleapis.com/v1/speech:recognize";
+const DEFAULT_LANGUAGE: &str = "en-US";
+/// Segments are cut at the first silent chunk after this many seconds...
+const MIN_SEGMENT_SECS: usize = 5;
+/// ...and unconditionally here, well below the 60s synchronous limit.
+const MAX_SEGMENT_SECS: usize = 30;
+
+/// Google Cloud Speech-to-Text authenticated with an API key.
+///
+/// Google's streaming recognizer is gRPC-only, so this backend uses the REST
+/// `speech:recognize` method on pause-delimited segments: each segment is
+/// committed as soon as it comes back, at the cost of no partial results.
+pub struct GoogleSpeechClient {
+    api_key: String,
+    language: String,
+    format: AudioFormat,
+    http: Client,
+}
+
+impl GoogleSpeechClient {
+    pub fn new(api_key: String, language: String) -> Self {
+        Self {
+            api_key,
+            language,
+            format: AudioFormat::default(),
+            http: Client::new(),
+        }
+    }
+
+    pub fn with_audio_format(mut self, format: AudioFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Routes requests through `proxy` instead of reqwest's environment lookup.
+    pub fn with_proxy(mut self, proxy: Option<&ProxyConfig>) -> Result<Self, String> {
+        if proxy.is_some() {
+            self.http = crate::proxy::http_client(proxy, None)?;
+        }
+        Ok(self)
+    }
+
+    fn request_body(&self, samples: &[i16]) -> serde_json::Value {
+        let mut bytes = Vec::new();
+        self.format.encode(samples, &mut bytes);
+        let encoding = match self.format.encoding {
+            AudioEncoding::Pcm16 => "LINEAR16",
+            AudioEncoding::MuLaw => "MULAW",
+        };
+        let language = if self.language.is_empty() {
+            DEFAULT_LANGUAGE
+        } else {
+            self.language.as_str()
+        };
+        json!({
+            "config": {
+                "encoding": encoding,
+                "sampleRateHertz": self.format.sample_rate,
+                "languageCode": language,
+                "enableAutomaticPunctuation": true,
+                "enableWordTimeOffsets": true
+            },
+            "audio": { "content": general_purpose::STANDARD.encode(bytes) }
+        })
+    }
+
+    /// Recognizes one segment; `offset_secs` is where it starts in the session.
+    async fn recognize(
+        &self,
+        samples: &[i16],
+        offset_secs: f64,
+        log_tx: &UnboundedSender<String>,
+    ) -> Result<TranscriptSegment, String> {
+        if !contains_speech(samples) {
+            return Ok(TranscriptSegment::default());
+        }
+        emit_log(
+            log_tx,
+            format!("➡️ [API OUT] Google recognize: {} samples", samples.len()),
+        );
+        let response = self
+            .http
+            .post(GOOGLE_RECOGNIZE_URL)
+            .header("x-goog-api-key", &self.api_key)
+            .json(&self.request_body(samples))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let status = response.status();
+        let value: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+        if !status.is_success() {
+            return Err(value.to_string());
+        }
+        Ok(transcript_from_response(&value, offset_secs))
+    }
+}
+
+/// Parses Google's `"1.300s"` duration strings.
+fn parse_duration_secs(value: &serde_json::Value) -> Option<f64> {
+    value.as_str()?.trim_end_matches('s').parse().ok()
+}
+
+/// Joins the top alternative of every result, shifting word times by
+/// `offset_secs` so they are relative to the session.
+fn transcript_from_response(value: &serde_json::Value, offset_secs: f64) -> TranscriptSegment {
+    let segments = value
+        .get("results")
+        .and_then(|r| r.as_array())
+        .map(|results| {
+            results
+                .iter()
+                .filter_map(|result| {
+                    let best = result.get("alternatives")?.get(0)?;
+                    let text = best.get("transcript")?.as_str()?.trim();
+                    if text.is_empty() {
+                        return None;
+                    }
+                    let words = best
+                        .get("words")
+                        .and_then(|w| w.as_array())
+                        .map(|words| {
+                            words
+                                .iter()
+                                .filter_map(|word| {
+                                    Some(WordTiming {
+                                        text: word.get("word")?.as_str()?.to_string(),
+                                        start_secs: offset_secs + parse_duration_secs(word.get("startTime")?)?,
+                                        end_secs: offset_secs + parse_duration_secs(word.get("endTime")?)?,
+                                        confidence: None,
+                                        speaker: None,
+                                    })
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default();
+                    let mut segment = TranscriptSegment::from_words(text, words);
+                    segment.confidence = best.get("confidence").and_then(|c| c.as_f64()).map(|c| c as f32);
+                    Some(segment)
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    TranscriptSegment::join(segments)
+}
+
+/// Whether the buffered segment should be sent after appending `latest`.
+fn segment_ready(segment_len: usize, latest: &[i16], sample_rate: u32) -> bool {
+    let rate = sample_rate as usize;
+    segment_len >= MAX_SEGMENT_SECS * rate
+        || (segment_len >= MIN_SEGMENT_SECS * rate && !contains_speech(latest))
+}
+
+#[async_trait]
+impl SpeechProvider for GoogleSpeechClient {
+    fn name(&self) -> &'static str {
+        "Google Cloud Speech"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderKind::Google.capabilities()
+    }
+
+    fn audio_format(&self) -> AudioFormat {
+        self.format
+    }
+
+    async fn run(
+        &self,
+        mut audio_rx: Receiver<Vec<i16>>,
+        mut control_rx: UnboundedReceiver<ControlMessage>,
+        text_tx: Sender<TranscriptEvent>,
+        log_tx: UnboundedSender<String>,
+    ) -> EchoResult<()> {
+        emit_log(&log_tx, "✅ Google Cloud Speech ready (segmented REST recognition)".to_string());
+        let mut accepting_audio = false;
+        let mut segment: Vec<i16> = Vec::new();
+        let mut segment_offset_secs = 0.0;
+
+        loop {
+            tokio::select! {
+                Some(cmd) = control_rx.recv() => {
+                    match cmd {
+                        ControlMessage::Start => accepting_audio = true,
+                        ControlMessage::Stop => break,
+                        ControlMessage::Cancel => {
+                            emit_log(&log_tx, "➡️ Session cancelled, discarding the current segment".to_string());
+                            return Ok(());
+                        }
+                    }
+                }
+                maybe_chunk = audio_rx.recv() => {
+                    let Some(chunk) = maybe_chunk else {
+                        break;
+                    };
+                    if !accepting_audio {
+                        continue;
+                    }
+                    segment.extend_from_slice(&chunk);
+                    if segment_ready(segment.len(), &chunk, self.format.sample_rate) {
+                        match self.recognize(&segment, segment_offset_secs, &log_tx).await {
+                            Ok(result) if !result.text.is_empty() => {
+                                emit_log(&log_tx, format!("📝 [COMMITTED] {}", result.text));
+                                let _ = text_tx.send(TranscriptEvent::Committed(result)).await;
+                            }
+                            Ok(_) => {}
+                            Err(e) => {
+                                emit_log(&log_tx, format!("❌ [API ERROR] {}", e));
+                                let _ = text_tx.send(TranscriptEvent::Error(ProviderError::new("recognize_failed", e))).await;
+                            }
+                        }
+                        segment_offset_secs += self.format.seconds(segment.len());
+                        segment.clear();
+                    }
+                }
+            }
+        }
+
+        // Drain audio captured before the stop, then send the final commit.
+        while let Ok(chunk) = audio_rx.try_recv() {
+            segment.extend_from_slice(&chunk);
+        }
+        let final_segment = match self.recognize(&segment, segment_offset_secs, &log_tx).await {
+            Ok(result) => result,
+            Err(e) => {
+                emit_log(&log_tx, format!("❌ [API ERROR] {}", e));
+                let _ = text_tx.send(TranscriptEvent::Error(ProviderError::new("recognize_failed", e))).await;
+                return Ok(());
+            }
+        };
+        emit_log(&log_tx, format!("📝 [COMMITTED] {}", final_segment.text));
+        let _ = text_tx.send(TranscriptEvent::Committed(final_segment)).await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{segment_ready, transcript_from_response, GoogleSpeechClient, MAX_SEGMENT_SECS, MIN_SEGMENT_SECS};
+    use crate::audio::AudioFormat;
+    use serde_json::json;
+
+    #[test]
+    fn joins_top_alternatives() {
+        let value = json!({
+            "results": [
+                { "alternatives": [{ "transcript": "hello there", "confidence": 0.9 }, { "transcript": "hollow" }] },
+                { "alternatives": [{ "transcript": " general kenobi " }] }
+            ]
+        });
+        assert_eq!(transcript_from_response(&value, 0.0).text, "hello there general kenobi");
+        assert_eq!(transcript_from_response(&value, 0.0).confidence, Some(0.9));
+        assert_eq!(transcript_from_response(&json!({}), 0.0).text, "");
+    }
+
+    #[test]
+    fn word_offsets_are_shifted_to_session_time() {
+        let value = json!({
+            "results": [{ "alternatives": [{
+                "transcript": "hi",
+                "confidence": 0.5,
+                "words": [{ "word": "hi", "startTime": "0.200s", "endTime": "0.600s" }]
+            }]}]
+        });
+        let segment = transcript_from_response(&value, 10.0);
+        assert_eq!(segment.start_secs, Some(10.2));
+        assert_eq!(segment.end_secs, Some(10.6));
+        assert_eq!(segment.words[0].text, "hi");
+    }
+
+    #[test]
+    fn segments_split_on_pause_or_length() {
+        let silence = vec![0i16; 16000];
+        let speech: Vec<i16> = (0..16000).map(|i| if i % 2 == 0 { 4000 } else { -4000 }).collect();
+        assert!(!segment_ready(MIN_SEGMENT_SECS * 16000 - 1, &silence, 16000));
+        assert!(segment_ready(MIN_SEGMENT_SECS * 16000, &silence, 16000));
+        assert!(!segment_ready(MIN_SEGMENT_SECS * 16000, &speech, 16000));
+        assert!(segment_ready(MAX_SEGMENT_SECS * 16000, &speech, 16000));
+        assert!(!segment_ready(MAX_SEGMENT_SECS * 16000, &speech, 48000));
+    }
+
+    #[test]
+    fn request_body_encodes_linear16() {
+        let client = GoogleSpeechClient::new("key".into(), "de-DE".into());
+        let body = client.request_body(&[1, -1]);
+        assert_eq!(body["config"]["languageCode"], "de-DE");
+        assert_eq!(body["config"]["sampleRateHertz"], 16000);
+        assert_eq!(body["audio"]["content"], "AQD//w==");
+
+        let telephony = GoogleSpeechClient::new("key".into(), String::new()).with_audio_format(AudioFormat::MU_LAW_8K);
+        let body = telephony.request_body(&[0, 0]);
+        assert_eq!(body["config"]["encoding"], "MULAW");
+        assert_eq!(body["config"]["sampleRateHertz"], 8000);
+        assert_eq!(body["audio"]["content"], "//8=");
+    }
+}
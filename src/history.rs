@@ -0,0 +1,396 @@
+// Finished dictation sessions, kept across restarts for the History tab. One
+// JSON object per line, appended when a session ends and rewritten when its
+// tags or notes are edited. With transcript encryption on, each line is
+// sealed by the vault; reading handles both kinds so turning encryption on or
+// off never hides older sessions.
+
+use crate::error::{EchoError, EchoResult};
+use crate::i18n::tr;
+use crate::vault::{self, Vault};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::warn;
+
+/// Held for every write to a history file, so a session saved while the
+/// file is being rewritten can't land between the read and the swap and be
+/// lost.
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// When the session started, RFC 3339 in local time.
+    pub started_at: String,
+    pub provider: String,
+    pub text: String,
+    /// Lower-case labels such as "meeting" or "blog draft", see
+    /// [`parse_tags`].
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub notes: String,
+    /// The transcript after whole-session LLM post-processing, when that
+    /// changed it. `text` is what the provider delivered.
+    #[serde(default)]
+    pub post_processed: Option<String>,
+    /// The session WAV, when session audio was recorded.
+    #[serde(default)]
+    pub recording: Option<PathBuf>,
+    /// Later transcripts of `recording`, oldest first.
+    #[serde(default)]
+    pub retranscriptions: Vec<Retranscription>,
+}
+
+/// The recording of a session run through a provider again, kept beside the
+/// live transcript for comparison.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Retranscription {
+    /// When it was made, RFC 3339 in local time.
+    pub transcribed_at: String,
+    pub provider: String,
+    pub text: String,
+}
+
+impl Retranscription {
+    /// "OpenAI Whisper · 2024-03-09 10:00" for the version picker.
+    pub fn label(&self) -> String {
+        let when = DateTime::parse_from_rfc3339(&self.transcribed_at)
+            .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|_| self.transcribed_at.clone());
+        format!("{} · {}", self.provider, when)
+    }
+}
+
+impl HistoryEntry {
+    /// "2024-03-08 14:05 · ElevenLabs" for the history list.
+    pub fn title(&self) -> String {
+        let when = DateTime::parse_from_rfc3339(&self.started_at)
+            .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|_| self.started_at.clone());
+        if self.provider.is_empty() {
+            when
+        } else {
+            format!("{} · {}", when, self.provider)
+        }
+    }
+
+    /// Every transcript of the session as (label, text): the live one first,
+    /// then the LLM-cleaned one and each re-transcription.
+    pub fn versions(&self) -> Vec<(String, &str)> {
+        let live = if self.provider.is_empty() {
+            tr!("Live")
+        } else {
+            tr!("Live · {}", self.provider)
+        };
+        std::iter::once((live, self.text.as_str()))
+            .chain(
+                self.post_processed
+                    .as_deref()
+                    .map(|text| (tr!("After LLM cleanup"), text)),
+            )
+            .chain(self.retranscriptions.iter().map(|version| (version.label(), version.text.as_str())))
+            .collect()
+    }
+}
+
+/// Adds `entry` to the end of the history file, sealed if `vault` is given.
+pub fn append(path: &Path, entry: &HistoryEntry, vault: Option<&Vault>) -> EchoResult<()> {
+    let line = encode(entry, vault)?;
+    let _write = WRITE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| EchoError::config(format!("Could not create {}: {}", parent.display(), e)))?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| EchoError::config(format!("Could not open {}: {}", path.display(), e)))?;
+    writeln!(file, "{}", line).map_err(|e| EchoError::config(format!("Could not write {}: {}", path.display(), e)))
+}
+
+/// Every session that can be read, oldest first. `unlock` is asked for the
+/// vault only if the file has sealed lines; lines that cannot be decrypted
+/// or parsed are skipped with a warning.
+pub fn load<'a>(path: &Path, unlock: impl Fn() -> Option<&'a Vault>) -> Vec<HistoryEntry> {
+    let Ok(contents) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    let mut skipped = 0;
+    let entries = contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match decode(line, &unlock) {
+            Ok(entry) => Some(entry),
+            Err(_) => {
+                skipped += 1;
+                None
+            }
+        })
+        .collect();
+    if skipped > 0 {
+        warn!("⚠️ Skipped {} history entries that could not be read", skipped);
+    }
+    entries
+}
+
+/// Rewrites the file with every entry sealed by `seal_with`, or in plain
+/// text when it is None, after encryption was turned on or off.
+pub fn reencode<'a>(path: &Path, unlock: impl Fn() -> Option<&'a Vault>, seal_with: Option<&Vault>) -> EchoResult<()> {
+    rewrite(path, unlock, seal_with, |entry| entry)
+}
+
+/// Keeps a history file's encryption in step with the setting. It remembers
+/// what the file was last written with, because by the time Apply runs the
+/// live settings already hold the new value.
+#[derive(Debug)]
+pub struct HistoryEncryption {
+    path: PathBuf,
+    encrypted: bool,
+}
+
+impl HistoryEncryption {
+    pub fn new(path: PathBuf, encrypted: bool) -> Self {
+        Self { path, encrypted }
+    }
+
+    /// Whether turning encryption to `encrypt` means rewriting the file.
+    pub fn needs_rewrite(&self, encrypt: bool) -> bool {
+        encrypt != self.encrypted
+    }
+
+    /// [`reencode`]s the file for `seal_with` if that changes whether it is
+    /// encrypted; returns whether it did.
+    pub fn sync<'a>(&mut self, unlock: impl Fn() -> Option<&'a Vault>, seal_with: Option<&Vault>) -> EchoResult<bool> {
+        let encrypt = seal_with.is_some();
+        if !self.needs_rewrite(encrypt) {
+            return Ok(false);
+        }
+        reencode(&self.path, unlock, seal_with)?;
+        self.encrypted = encrypt;
+        Ok(true)
+    }
+}
+
+/// Swaps the saved copy of `original` for `updated`, e.g. after its tags
+/// were edited. The rest of the file is rewritten as [`reencode`] does.
+pub fn replace<'a>(
+    path: &Path,
+    unlock: impl Fn() -> Option<&'a Vault>,
+    seal_with: Option<&Vault>,
+    original: &HistoryEntry,
+    updated: &HistoryEntry,
+) -> EchoResult<()> {
+    rewrite(path, unlock, seal_with, |entry| {
+        if entry == *original {
+            updated.clone()
+        } else {
+            entry
+        }
+    })
+}
+
+/// Passes every readable entry through `edit` and writes the file back.
+/// Lines that cannot be read are kept exactly as they were.
+fn rewrite<'a>(
+    path: &Path,
+    unlock: impl Fn() -> Option<&'a Vault>,
+    seal_with: Option<&Vault>,
+    mut edit: impl FnMut(HistoryEntry) -> HistoryEntry,
+) -> EchoResult<()> {
+    let _write = WRITE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let Ok(contents) = fs::read_to_string(path) else {
+        return Ok(());
+    };
+    let mut output = String::with_capacity(contents.len());
+    for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+        match decode(line, &unlock) {
+            Ok(entry) => output.push_str(&encode(&edit(entry), seal_with)?),
+            Err(_) => output.push_str(line),
+        }
+        output.push('\n');
+    }
+    // Write beside the file and swap it in, so a crash leaves one or the other.
+    let temp = path.with_extension("jsonl.tmp");
+    fs::write(&temp, output).map_err(|e| EchoError::config(format!("Could not write {}: {}", temp.display(), e)))?;
+    fs::rename(&temp, path).map_err(|e| EchoError::config(format!("Could not replace {}: {}", path.display(), e)))
+}
+
+/// Parses a comma-separated tag list. Tags are trimmed and lower-cased, and
+/// repeats are dropped.
+pub fn parse_tags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in text.split(',') {
+        let tag = tag.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+pub fn format_tags(tags: &[String]) -> String {
+    tags.join(", ")
+}
+
+/// Writes `entries` to a Markdown file in `folder`, one section per session.
+pub fn export_markdown(entries: &[&HistoryEntry], folder: &Path) -> io::Result<PathBuf> {
+    fs::create_dir_all(folder)?;
+    let path = folder.join(format!("history-{}.md", Local::now().format("%Y%m%d-%H%M%S")));
+    let mut output = String::new();
+    for entry in entries {
+        output.push_str(&format!("## {}\n\n", entry.title()));
+        if !entry.tags.is_empty() {
+            output.push_str(&format!("Tags: {}\n\n", format_tags(&entry.tags)));
+        }
+        if !entry.notes.trim().is_empty() {
+            output.push_str(&format!("> {}\n\n", entry.notes.trim().replace('\n', "\n> ")));
+        }
+        output.push_str(entry.text.trim());
+        output.push_str("\n\n");
+        if let Some(text) = &entry.post_processed {
+            output.push_str(&format!("### After LLM cleanup\n\n{}\n\n", text.trim()));
+        }
+        for version in &entry.retranscriptions {
+            output.push_str(&format!("### Re-transcribed: {}\n\n{}\n\n", version.label(), version.text.trim()));
+        }
+    }
+    fs::write(&path, output)?;
+    Ok(path)
+}
+
+fn encode(entry: &HistoryEntry, vault: Option<&Vault>) -> EchoResult<String> {
+    let json = serde_json::to_string(entry).map_err(|e| EchoError::config(e.to_string()))?;
+    match vault {
+        Some(vault) => vault.seal(&json),
+        None => Ok(json),
+    }
+}
+
+fn decode<'a>(line: &str, unlock: &impl Fn() -> Option<&'a Vault>) -> EchoResult<HistoryEntry> {
+    let json = vault::read_line(line, unlock)?;
+    serde_json::from_str(&json).map_err(|e| EchoError::config(format!("Damaged history entry: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        append, export_markdown, load, parse_tags, reencode, replace, HistoryEncryption, HistoryEntry, Retranscription,
+    };
+    use crate::vault::{is_sealed, Vault};
+    use std::fs;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn entry(text: &str) -> HistoryEntry {
+        HistoryEntry {
+            started_at: "2024-03-08T14:05:00+01:00".to_string(),
+            provider: "ElevenLabs".to_string(),
+            text: text.to_string(),
+            tags: Vec::new(),
+            notes: String::new(),
+            post_processed: None,
+            recording: None,
+            retranscriptions: Vec::new(),
+        }
+    }
+
+    fn temp_path() -> std::path::PathBuf {
+        let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        std::env::temp_dir().join(format!("eleventh_echo_history_{}.jsonl", stamp))
+    }
+
+    #[test]
+    fn encrypted_and_plain_entries_load_together() {
+        let path = temp_path();
+        let vault = Vault::from_secret(b"secret");
+        append(&path, &entry("plain one"), None).unwrap();
+        append(&path, &entry("secret two"), Some(&vault)).unwrap();
+        let raw = fs::read_to_string(&path).unwrap();
+        assert!(raw.contains("plain one") && !raw.contains("secret two"));
+
+        assert_eq!(load(&path, || Some(&vault)), vec![entry("plain one"), entry("secret two")]);
+        // Without the keychain secret only the plain entry can be shown.
+        assert_eq!(load(&path, || None), vec![entry("plain one")]);
+        assert_eq!(entry("x").title(), "2024-03-08 14:05 · ElevenLabs");
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn reencoding_seals_everything_it_can_read() {
+        let path = temp_path();
+        let vault = Vault::from_secret(b"secret");
+        let other = Vault::from_secret(b"lost secret");
+        append(&path, &entry("first"), None).unwrap();
+        append(&path, &entry("unreadable"), Some(&other)).unwrap();
+
+        reencode(&path, || Some(&vault), Some(&vault)).unwrap();
+        let raw = fs::read_to_string(&path).unwrap();
+        assert_eq!(raw.lines().count(), 2);
+        assert!(raw.lines().all(is_sealed));
+        assert_eq!(load(&path, || Some(&vault)), vec![entry("first")]);
+        assert_eq!(load(&path, || Some(&other)), vec![entry("unreadable")]);
+
+        reencode(&path, || Some(&vault), None).unwrap();
+        assert!(fs::read_to_string(&path).unwrap().contains("\"first\""));
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn toggling_encryption_rewrites_the_file_once() {
+        let path = temp_path();
+        let vault = Vault::from_secret(b"secret");
+        append(&path, &entry("first"), None).unwrap();
+        let mut encryption = HistoryEncryption::new(path.clone(), false);
+        assert!(!encryption.needs_rewrite(false));
+        assert!(!encryption.sync(|| Some(&vault), None).unwrap());
+
+        assert!(encryption.needs_rewrite(true));
+        assert!(encryption.sync(|| Some(&vault), Some(&vault)).unwrap());
+        assert!(fs::read_to_string(&path).unwrap().lines().all(is_sealed));
+        assert!(!encryption.needs_rewrite(true));
+        assert!(!encryption.sync(|| Some(&vault), Some(&vault)).unwrap());
+
+        assert!(encryption.sync(|| Some(&vault), None).unwrap());
+        assert!(fs::read_to_string(&path).unwrap().contains("\"first\""));
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn tags_and_notes_are_saved_with_the_entry() {
+        let path = temp_path();
+        let vault = Vault::from_secret(b"secret");
+        fs::write(&path, "{\"started_at\":\"2024-03-08T14:05:00+01:00\",\"provider\":\"ElevenLabs\",\"text\":\"old\"}\n").unwrap();
+        append(&path, &entry("second"), Some(&vault)).unwrap();
+
+        let mut tagged = entry("second");
+        tagged.tags = parse_tags(" Meeting, blog   draft,meeting,, ");
+        tagged.notes = "Send to Sam".to_string();
+        tagged.post_processed = Some("Second!".to_string());
+        tagged.retranscriptions.push(Retranscription {
+            transcribed_at: "2024-03-09T10:00:00+01:00".to_string(),
+            provider: "OpenAI Whisper".to_string(),
+            text: "Second.".to_string(),
+        });
+        assert_eq!(tagged.tags, vec!["meeting", "blog draft"]);
+        assert_eq!(
+            tagged.versions(),
+            vec![
+                ("Live · ElevenLabs".to_string(), "second"),
+                ("After LLM cleanup".to_string(), "Second!"),
+                ("OpenAI Whisper · 2024-03-09 10:00".to_string(), "Second."),
+            ]
+        );
+        replace(&path, || Some(&vault), Some(&vault), &entry("second"), &tagged).unwrap();
+        assert_eq!(load(&path, || Some(&vault)), vec![entry("old"), tagged.clone()]);
+
+        let folder = path.with_extension("exports");
+        let export = export_markdown(&[&tagged], &folder).unwrap();
+        let markdown = fs::read_to_string(&export).unwrap();
+        assert!(markdown.starts_with("## 2024-03-08 14:05 · ElevenLabs\n\nTags: meeting, blog draft\n\n> Send to Sam"));
+        assert!(markdown.contains("second\n\n### After LLM cleanup\n\nSecond!\n\n### Re-transcribed: OpenAI Whisper · 2024-03-09 10:00\n\nSecond."));
+        let _ = fs::remove_dir_all(&folder);
+        let _ = fs::remove_file(&path);
+    }
+}
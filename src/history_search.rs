@@ -0,0 +1,331 @@
+// Full-text search over the saved history. The index is an in-memory SQLite
+// FTS5 table, built from the history file at startup and kept up to date as
+// sessions finish or are tagged. It is never written to disk, so encrypted
+// history stays encrypted at rest.
+
+use crate::error::EchoResult;
+use crate::history::HistoryEntry;
+use crate::i18n::tr;
+use chrono::{DateTime, NaiveDate};
+use rusqlite::{params, Connection};
+
+/// What the History tab's search box, date fields and tag filter ask for.
+/// Empty text lists every session in the date range.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HistoryQuery {
+    pub text: String,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    /// Only sessions with this tag.
+    pub tag: Option<String>,
+}
+
+impl HistoryQuery {
+    pub fn is_empty(&self) -> bool {
+        self.text.trim().is_empty() && self.from.is_none() && self.to.is_none() && self.tag.is_none()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    /// The session's id, for [`HistoryIndex::get`].
+    pub id: usize,
+    /// The matching part of the text, a later version of it, the notes or
+    /// the tags, with the matched words in «».
+    pub excerpt: String,
+}
+
+/// The saved sessions, oldest first, and their search index.
+pub struct HistoryIndex {
+    conn: Connection,
+    entries: Vec<HistoryEntry>,
+}
+
+impl HistoryIndex {
+    pub fn build(entries: Vec<HistoryEntry>) -> EchoResult<Self> {
+        let conn = Connection::open_in_memory()?;
+        conn.execute_batch(
+            "CREATE VIRTUAL TABLE sessions USING fts5(
+                 text,
+                 versions,
+                 notes,
+                 tags,
+                 day UNINDEXED,
+                 tokenize = 'unicode61 remove_diacritics 2'
+             );
+             CREATE TABLE session_tags (session INTEGER NOT NULL, tag TEXT NOT NULL);
+             CREATE INDEX session_tags_by_tag ON session_tags (tag);",
+        )?;
+        let mut index = Self {
+            conn,
+            entries: Vec::with_capacity(entries.len()),
+        };
+        for entry in entries {
+            index.push(entry)?;
+        }
+        Ok(index)
+    }
+
+    pub fn push(&mut self, entry: HistoryEntry) -> EchoResult<()> {
+        self.index(self.entries.len(), &entry)?;
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Swaps in an edited copy of session `id`, e.g. with new tags or notes.
+    pub fn update(&mut self, id: usize, entry: HistoryEntry) -> EchoResult<()> {
+        if id >= self.entries.len() {
+            return Ok(());
+        }
+        self.conn.execute("DELETE FROM sessions WHERE rowid = ?1", params![id as i64])?;
+        self.conn.execute("DELETE FROM session_tags WHERE session = ?1", params![id as i64])?;
+        self.index(id, &entry)?;
+        self.entries[id] = entry;
+        Ok(())
+    }
+
+    fn index(&self, id: usize, entry: &HistoryEntry) -> EchoResult<()> {
+        self.conn.execute(
+            "INSERT INTO sessions (rowid, text, versions, notes, tags, day) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![id as i64, entry.text, later_versions(entry), entry.notes, entry.tags.join(" "), day(entry)],
+        )?;
+        for tag in &entry.tags {
+            self.conn
+                .execute("INSERT INTO session_tags (session, tag) VALUES (?1, ?2)", params![id as i64, tag])?;
+        }
+        Ok(())
+    }
+
+    /// Every tag in use, alphabetically, for the tag filter.
+    pub fn tags(&self) -> EchoResult<Vec<String>> {
+        let mut statement = self.conn.prepare("SELECT DISTINCT tag FROM session_tags ORDER BY tag")?;
+        let tags = statement
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(tags)
+    }
+
+    pub fn get(&self, id: usize) -> Option<&HistoryEntry> {
+        self.entries.get(id)
+    }
+
+    /// Matching sessions, newest first. Every word must appear in the text
+    /// (any version of it), notes or tags, each as a word or the start of
+    /// one; quotes and FTS operators are taken literally.
+    pub fn search(&self, query: &HistoryQuery) -> EchoResult<Vec<SearchHit>> {
+        let from = query.from.map(|date| date.to_string()).unwrap_or_default();
+        let to = query.to.map(|date| date.to_string()).unwrap_or_else(|| "9999-12-31".to_string());
+        let tag = query.tag.as_deref();
+        let terms = match_terms(&query.text);
+        let mut hits = Vec::new();
+        if terms.is_empty() {
+            let mut statement = self.conn.prepare(
+                "SELECT rowid FROM sessions
+                 WHERE day BETWEEN ?1 AND ?2 AND (?3 IS NULL OR rowid IN (SELECT session FROM session_tags WHERE tag = ?3))
+                 ORDER BY rowid DESC",
+            )?;
+            let rows = statement.query_map(params![from, to, tag], |row| row.get::<_, i64>(0))?;
+            for id in rows {
+                let id = id? as usize;
+                hits.push(SearchHit {
+                    id,
+                    excerpt: self.entries[id].text.clone(),
+                });
+            }
+        } else {
+            let mut statement = self.conn.prepare(
+                "SELECT rowid, snippet(sessions, -1, '«', '»', '…', 24) FROM sessions
+                 WHERE sessions MATCH ?1 AND day BETWEEN ?2 AND ?3
+                   AND (?4 IS NULL OR rowid IN (SELECT session FROM session_tags WHERE tag = ?4))
+                 ORDER BY rowid DESC",
+            )?;
+            let rows = statement.query_map(params![terms, from, to, tag], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })?;
+            for row in rows {
+                let (id, excerpt) = row?;
+                hits.push(SearchHit {
+                    id: id as usize,
+                    excerpt,
+                });
+            }
+        }
+        Ok(hits)
+    }
+}
+
+/// The LLM-cleaned transcript and every re-transcription, which the user
+/// may be looking at instead of the live text.
+fn later_versions(entry: &HistoryEntry) -> String {
+    entry
+        .post_processed
+        .iter()
+        .chain(entry.retranscriptions.iter().map(|version| &version.text))
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// The local calendar day a session started, as stored in the index.
+fn day(entry: &HistoryEntry) -> String {
+    DateTime::parse_from_rfc3339(&entry.started_at)
+        .map(|at| at.date_naive().to_string())
+        .unwrap_or_default()
+}
+
+/// Turns what was typed into an FTS5 query: each word quoted (so `"`, `-`
+/// or `OR` can't break the syntax) and matched as a prefix.
+fn match_terms(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Parses a date field; empty means no limit.
+pub fn parse_date(text: &str) -> Result<Option<NaiveDate>, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(None);
+    }
+    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .map(Some)
+        .map_err(|_| tr!("\"{}\" is not a date (use YYYY-MM-DD)", text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_date, HistoryIndex, HistoryQuery};
+    use crate::history::{HistoryEntry, Retranscription};
+    use chrono::NaiveDate;
+
+    fn entry(started_at: &str, text: &str) -> HistoryEntry {
+        HistoryEntry {
+            started_at: started_at.to_string(),
+            provider: "ElevenLabs".to_string(),
+            text: text.to_string(),
+            tags: Vec::new(),
+            notes: String::new(),
+            post_processed: None,
+            recording: None,
+            retranscriptions: Vec::new(),
+        }
+    }
+
+    fn index() -> HistoryIndex {
+        HistoryIndex::build(vec![
+            entry("2024-03-01T09:00:00+01:00", "Kickoff meeting about the Kubernetes migration."),
+            entry("2024-03-08T14:05:00+01:00", "Draft of the blog post on café culture."),
+            entry("2024-03-09T10:00:00+01:00", "Follow up on the migration budget."),
+        ])
+        .unwrap()
+    }
+
+    fn ids(index: &HistoryIndex, query: &HistoryQuery) -> Vec<usize> {
+        index.search(query).unwrap().into_iter().map(|hit| hit.id).collect()
+    }
+
+    #[test]
+    fn words_match_as_prefixes_newest_first() {
+        let index = index();
+        let query = HistoryQuery {
+            text: "migrat".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(ids(&index, &query), vec![2, 0]);
+        let hits = index.search(&query).unwrap();
+        assert!(hits[0].excerpt.contains("«migration»"));
+
+        let query = HistoryQuery {
+            text: "cafe blog".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(ids(&index, &query), vec![1]);
+        // Operators and stray quotes are plain words, not syntax errors.
+        let query = HistoryQuery {
+            text: "\"budget OR -".to_string(),
+            ..Default::default()
+        };
+        assert!(index.search(&query).unwrap().is_empty());
+    }
+
+    #[test]
+    fn dates_limit_the_results() {
+        let mut index = index();
+        let march = |day| NaiveDate::from_ymd_opt(2024, 3, day);
+        let query = HistoryQuery {
+            from: march(2),
+            to: march(8),
+            ..Default::default()
+        };
+        assert_eq!(ids(&index, &query), vec![1]);
+        assert_eq!(index.search(&query).unwrap()[0].excerpt, index.get(1).unwrap().text);
+
+        index.push(entry("2024-03-08T18:00:00+01:00", "Evening notes")).unwrap();
+        assert_eq!(ids(&index, &query), vec![3, 1]);
+        assert_eq!(ids(&index, &HistoryQuery::default()), vec![3, 2, 1, 0]);
+
+        assert_eq!(parse_date(" 2024-03-08 ").unwrap(), march(8));
+        assert_eq!(parse_date("").unwrap(), None);
+        assert!(parse_date("8.3.2024").unwrap_err().contains("YYYY-MM-DD"));
+    }
+
+    #[test]
+    fn tags_filter_and_edits_are_reindexed() {
+        let mut index = index();
+        let mut tagged = index.get(2).unwrap().clone();
+        tagged.tags = vec!["meeting".to_string(), "blog draft".to_string()];
+        tagged.notes = "Ask finance about Q3".to_string();
+        index.update(2, tagged.clone()).unwrap();
+        let mut other = index.get(0).unwrap().clone();
+        other.tags = vec!["meeting".to_string()];
+        index.update(0, other).unwrap();
+        assert_eq!(index.tags().unwrap(), vec!["blog draft", "meeting"]);
+
+        let by_tag = |tag: &str, text: &str| HistoryQuery {
+            text: text.to_string(),
+            tag: Some(tag.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(&index, &by_tag("meeting", "")), vec![2, 0]);
+        assert_eq!(ids(&index, &by_tag("blog draft", "")), vec![2]);
+        assert_eq!(ids(&index, &by_tag("meeting", "kickoff")), vec![0]);
+        // Notes are searchable too.
+        let query = HistoryQuery {
+            text: "finance".to_string(),
+            ..Default::default()
+        };
+        let hits = index.search(&query).unwrap();
+        assert_eq!(hits[0].id, 2);
+        assert!(hits[0].excerpt.contains("«finance»"));
+
+        tagged.tags.clear();
+        index.update(2, tagged).unwrap();
+        assert_eq!(ids(&index, &by_tag("blog draft", "")), Vec::<usize>::new());
+        assert_eq!(index.get(2).unwrap().notes, "Ask finance about Q3");
+    }
+
+    #[test]
+    fn later_versions_are_searchable() {
+        let mut index = index();
+        let mut cleaned = index.get(1).unwrap().clone();
+        cleaned.post_processed = Some("Draft of the blog post on coffee culture.".to_string());
+        cleaned.retranscriptions.push(Retranscription {
+            transcribed_at: "2024-03-10T08:00:00+01:00".to_string(),
+            provider: "OpenAI Whisper".to_string(),
+            text: "Draft of the blog post on Viennese café culture.".to_string(),
+        });
+        index.update(1, cleaned).unwrap();
+
+        for word in ["coffee", "viennese"] {
+            let query = HistoryQuery {
+                text: word.to_string(),
+                ..Default::default()
+            };
+            let hits = index.search(&query).unwrap();
+            assert_eq!(hits.len(), 1);
+            assert_eq!(hits[0].id, 1);
+            assert!(hits[0].excerpt.to_lowercase().contains(&format!("«{}»", word)));
+        }
+    }
+}
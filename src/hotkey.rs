@@ -1,6 +1,9 @@
+use serde::{Deserialize, Serialize};
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum HotkeyKey {
     Space,
+    Escape,
     Letter(char),
     Digit(u8),
     Function(u8),
@@ -30,6 +33,7 @@ pub fn parse_hotkey_spec(input: &str) -> Result<HotkeySpec, String> {
             "alt" => alt = true,
             "meta" | "win" | "super" => meta = true,
             "space" => key = Some(HotkeyKey::Space),
+            "esc" | "escape" => key = Some(HotkeyKey::Escape),
             "a" => key = Some(HotkeyKey::Letter('A')),
             "b" => key = Some(HotkeyKey::Letter('B')),
             "c" => key = Some(HotkeyKey::Letter('C')),
@@ -92,9 +96,139 @@ pub fn parse_hotkey_spec(input: &str) -> Result<HotkeySpec, String> {
     })
 }
 
+/// Whether two combo strings name the same keys, e.g. "ctrl+space" and
+/// "Ctrl+Space". Unparseable combos never match.
+pub fn same_combo(a: &str, b: &str) -> bool {
+    match (parse_hotkey_spec(a), parse_hotkey_spec(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// What a registered global hotkey does when pressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyAction {
+    /// Starts a session, or stops the running one.
+    ToggleDictation,
+    /// Records only while the combo is held down.
+    PushToTalk,
+    PauseResume,
+    UndoLastInjection,
+    /// Switches to the next saved profile.
+    NextProfile,
+    /// Ends the session without injecting anything.
+    CancelRecording,
+}
+
+impl HotkeyAction {
+    pub const ALL: [HotkeyAction; 6] = [
+        HotkeyAction::ToggleDictation,
+        HotkeyAction::PushToTalk,
+        HotkeyAction::PauseResume,
+        HotkeyAction::UndoLastInjection,
+        HotkeyAction::NextProfile,
+        HotkeyAction::CancelRecording,
+    ];
+
+    /// Name used in the settings text box; matches the serialized form.
+    pub fn name(self) -> &'static str {
+        match self {
+            HotkeyAction::ToggleDictation => "toggle_dictation",
+            HotkeyAction::PushToTalk => "push_to_talk",
+            HotkeyAction::PauseResume => "pause_resume",
+            HotkeyAction::UndoLastInjection => "undo_last_injection",
+            HotkeyAction::NextProfile => "next_profile",
+            HotkeyAction::CancelRecording => "cancel_recording",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+}
+
+/// One row of the keybinding table. An action may appear more than once.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyBinding {
+    pub action: HotkeyAction,
+    pub keys: String,
+}
+
+impl KeyBinding {
+    pub fn new(action: HotkeyAction, keys: &str) -> Self {
+        Self {
+            action,
+            keys: keys.to_string(),
+        }
+    }
+}
+
+pub fn default_keybindings() -> Vec<KeyBinding> {
+    vec![
+        KeyBinding::new(HotkeyAction::ToggleDictation, "Ctrl+Space"),
+        KeyBinding::new(HotkeyAction::PauseResume, "Ctrl+Shift+Space"),
+        KeyBinding::new(HotkeyAction::UndoLastInjection, "Ctrl+Alt+Z"),
+        KeyBinding::new(HotkeyAction::CancelRecording, "Esc"),
+    ]
+}
+
+/// The bindings to register right now. Cancel is only grabbed while
+/// recording, so other apps keep its key (usually a bare Esc) otherwise.
+pub fn active_keybindings(bindings: &[KeyBinding], recording: bool) -> Vec<KeyBinding> {
+    bindings
+        .iter()
+        .filter(|binding| recording || binding.action != HotkeyAction::CancelRecording)
+        .cloned()
+        .collect()
+}
+
+/// Parses the settings text format, one binding per line:
+///
+/// ```text
+/// toggle_dictation = Ctrl+Space
+/// push_to_talk = Ctrl+F9
+/// # comments and blank lines are ignored
+/// ```
+pub fn parse_keybindings(text: &str) -> Result<Vec<KeyBinding>, String> {
+    let mut bindings: Vec<KeyBinding> = Vec::new();
+    let mut specs: Vec<HotkeySpec> = Vec::new();
+    for (index, raw_line) in text.lines().enumerate() {
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (name, keys) = line
+            .split_once('=')
+            .ok_or_else(|| format!("Line {}: expected \"action = keys\"", index + 1))?;
+        let action = HotkeyAction::from_name(name.trim())
+            .ok_or_else(|| format!("Line {}: unknown action \"{}\"", index + 1, name.trim()))?;
+        let keys = keys.trim();
+        let spec = parse_hotkey_spec(keys).map_err(|e| format!("Line {}: {}", index + 1, e))?;
+        if specs.contains(&spec) {
+            return Err(format!("Line {}: {} is bound more than once", index + 1, keys));
+        }
+        specs.push(spec);
+        bindings.push(KeyBinding::new(action, keys));
+    }
+    Ok(bindings)
+}
+
+/// Inverse of [`parse_keybindings`], used to populate the settings text box.
+pub fn format_keybindings(bindings: &[KeyBinding]) -> String {
+    bindings
+        .iter()
+        .map(|binding| format!("{} = {}", binding.action.name(), binding.keys))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{parse_hotkey_spec, HotkeyKey};
+    use super::{
+        active_keybindings, default_keybindings, format_keybindings, parse_hotkey_spec, parse_keybindings, same_combo, HotkeyAction,
+        HotkeyKey, KeyBinding,
+    };
 
     #[test]
     fn parse_letters_digits_and_function_keys() {
@@ -143,4 +277,52 @@ mod tests {
         let e = parse_hotkey_spec("Ctrl+Tab").unwrap_err();
         assert!(e.contains("Unsupported key token"));
     }
+
+    #[test]
+    fn keybindings_roundtrip_through_text() {
+        let text = "# dictation\ntoggle_dictation = Ctrl+Space\n\npush_to_talk = Ctrl+F9\nnext_profile=Ctrl+Alt+P";
+        let bindings = parse_keybindings(text).unwrap();
+        assert_eq!(
+            bindings,
+            vec![
+                KeyBinding::new(HotkeyAction::ToggleDictation, "Ctrl+Space"),
+                KeyBinding::new(HotkeyAction::PushToTalk, "Ctrl+F9"),
+                KeyBinding::new(HotkeyAction::NextProfile, "Ctrl+Alt+P"),
+            ]
+        );
+        assert_eq!(parse_keybindings(&format_keybindings(&bindings)).unwrap(), bindings);
+        assert_eq!(
+            parse_keybindings(&format_keybindings(&default_keybindings())).unwrap(),
+            default_keybindings()
+        );
+    }
+
+    #[test]
+    fn keybinding_errors_name_the_line() {
+        let e = parse_keybindings("toggle_dictation = Ctrl+Space\nfly = Ctrl+F").unwrap_err();
+        assert!(e.starts_with("Line 2: unknown action"));
+        let e = parse_keybindings("pause_resume Ctrl+P").unwrap_err();
+        assert!(e.contains("expected"));
+        let e = parse_keybindings("undo_last_injection = Ctrl+Tab").unwrap_err();
+        assert!(e.contains("Unsupported key token"));
+    }
+
+    #[test]
+    fn same_combo_cannot_be_bound_twice() {
+        let e = parse_keybindings("toggle_dictation = Ctrl+Space\npush_to_talk = control+space").unwrap_err();
+        assert!(e.contains("bound more than once"));
+        assert!(same_combo("Shift+Ctrl+F8", "ctrl+shift+f8"));
+        assert!(!same_combo("Ctrl+F8", "Ctrl+F9"));
+        assert!(!same_combo("Ctrl+Tab", "Ctrl+Tab"));
+    }
+
+    #[test]
+    fn cancel_binding_is_only_active_while_recording() {
+        assert_eq!(parse_hotkey_spec("Esc").unwrap().key, HotkeyKey::Escape);
+        let bindings = default_keybindings();
+        let idle = active_keybindings(&bindings, false);
+        assert!(idle.iter().all(|binding| binding.action != HotkeyAction::CancelRecording));
+        assert_eq!(idle.len(), bindings.len() - 1);
+        assert_eq!(active_keybindings(&bindings, true), bindings);
+    }
 }
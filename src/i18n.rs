@@ -0,0 +1,314 @@
+// UI language. The strings in ui/appwindow.slint are wrapped in @tr and the
+// gettext catalogs under translations/ are compiled into the binary (see
+// build.rs). Selecting a language re-evaluates every @tr binding in place,
+// so switching doesn't need a restart. Text set from Rust (status lines,
+// option labels, error messages) goes through `tr!`, which reads the same
+// catalogs; main.rs refills it when the language changes.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::{Mutex, OnceLock};
+use tracing::warn;
+
+/// Languages with a catalog in translations/, by folder name, and the
+/// catalog itself for the text set from Rust.
+const BUNDLED: [(&str, &str); 2] = [
+    ("de", include_str!("../translations/de/LC_MESSAGES/eleventh_echo_rust.po")),
+    ("es", include_str!("../translations/es/LC_MESSAGES/eleventh_echo_rust.po")),
+];
+
+/// The catalog `apply` selected last; empty is the English source text.
+static ACTIVE: Mutex<&str> = Mutex::new("");
+
+/// `text` in the active language. With arguments, each "{}" (or "{0}",
+/// "{1}" where a translation reorders them) is filled in like @tr does, so
+/// a msgid reads the same in .slint and Rust.
+macro_rules! tr {
+    ($text:expr) => {
+        $crate::i18n::translate($text)
+    };
+    ($text:expr, $($arg:expr),+ $(,)?) => {
+        $crate::i18n::fill(&$crate::i18n::translate($text), &[$(&$arg as &dyn std::fmt::Display),+])
+    };
+}
+pub(crate) use tr;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UiLanguage {
+    /// The operating system's language if there is a catalog for it,
+    /// otherwise English.
+    #[default]
+    System,
+    English,
+    German,
+    Spanish,
+}
+
+impl UiLanguage {
+    pub const ALL: [UiLanguage; 4] = [
+        UiLanguage::System,
+        UiLanguage::English,
+        UiLanguage::German,
+        UiLanguage::Spanish,
+    ];
+
+    /// Each language names itself, so the list stays readable whichever
+    /// one is active.
+    pub fn label(self) -> &'static str {
+        match self {
+            UiLanguage::System => "System default",
+            UiLanguage::English => "English",
+            UiLanguage::German => "Deutsch",
+            UiLanguage::Spanish => "Español",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|language| is_label(language.label(), label))
+    }
+
+    /// The catalog to select; empty is the English source text.
+    fn catalog(self) -> &'static str {
+        match self {
+            UiLanguage::System => sys_locale::get_locale().map_or("", |locale| catalog_for_locale(&locale)),
+            UiLanguage::English => "",
+            UiLanguage::German => "de",
+            UiLanguage::Spanish => "es",
+        }
+    }
+}
+
+/// The bundled catalog for a locale such as "de-AT" or "es_MX.UTF-8".
+fn catalog_for_locale(locale: &str) -> &'static str {
+    let language = locale.split(['-', '_', '.', '@']).next().unwrap_or_default().to_lowercase();
+    BUNDLED.into_iter().map(|(code, _)| code).find(|code| *code == language).unwrap_or("")
+}
+
+/// Switches every open window to `language`. Must be called after the
+/// first window is created.
+pub fn apply(language: UiLanguage) {
+    *ACTIVE.lock().unwrap() = language.catalog();
+    if let Err(e) = slint::select_bundled_translation(language.catalog()) {
+        warn!("⚠️ Cannot switch the UI to {}: {}", language.label(), e);
+    }
+}
+
+/// The catalog `apply` selected last, so the tray menu can notice a switch.
+#[cfg(target_os = "windows")]
+pub fn active() -> &'static str {
+    *ACTIVE.lock().unwrap()
+}
+
+/// `text` from the active catalog, or `text` itself when it has no entry.
+pub fn translate(text: &str) -> String {
+    let active = *ACTIVE.lock().unwrap();
+    lookup(active, text).unwrap_or(text).to_string()
+}
+
+/// Whether `shown` is the English `label` or its translation in any
+/// catalog, so a combo box value still matches while the language changes.
+pub fn is_label(label: &str, shown: &str) -> bool {
+    shown == label || BUNDLED.iter().any(|(code, _)| lookup(code, label) == Some(shown))
+}
+
+/// The English source of `shown` in the active catalog, for re-translating
+/// text that was set before a language switch. Text without an entry (or
+/// with placeholders filled in) comes back unchanged.
+pub fn source_text(shown: &str) -> String {
+    let active = *ACTIVE.lock().unwrap();
+    catalog(active)
+        .and_then(|messages| messages.iter().find(|(_, text)| *text == shown))
+        .map_or(shown, |(id, _)| id.as_str())
+        .to_string()
+}
+
+/// Fills "{}" and "{n}" in a translated `template` with `args`.
+pub fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut filled = String::with_capacity(template.len());
+    let mut next = 0;
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('}').filter(|end| after[..*end].bytes().all(|b| b.is_ascii_digit())) {
+            Some(end) => {
+                let index = if end == 0 {
+                    next += 1;
+                    next - 1
+                } else {
+                    after[..end].parse().unwrap_or(usize::MAX)
+                };
+                if let Some(arg) = args.get(index) {
+                    filled.push_str(&arg.to_string());
+                }
+                rest = &after[end + 1..];
+            }
+            None => {
+                filled.push('{');
+                rest = after;
+            }
+        }
+    }
+    filled.push_str(rest);
+    filled
+}
+
+fn lookup(code: &str, text: &str) -> Option<&'static str> {
+    catalog(code)?.get(text).map(String::as_str)
+}
+
+/// The parsed catalog for `code`, None for English.
+fn catalog(code: &str) -> Option<&'static HashMap<String, String>> {
+    static CATALOGS: OnceLock<Vec<(&str, HashMap<String, String>)>> = OnceLock::new();
+    CATALOGS
+        .get_or_init(|| BUNDLED.iter().map(|(code, po)| (*code, parse_po(po))).collect())
+        .iter()
+        .find(|(bundled, _)| *bundled == code)
+        .map(|(_, messages)| messages)
+}
+
+/// msgid -> msgstr for the translated entries of a .po file. Entries with a
+/// msgctxt belong to one .slint element and are left out.
+fn parse_po(po: &str) -> HashMap<String, String> {
+    #[derive(PartialEq)]
+    enum Field {
+        Context,
+        Id,
+        Text,
+    }
+    let mut messages = HashMap::new();
+    let (mut context, mut id, mut text) = (false, String::new(), String::new());
+    let mut field = Field::Text;
+    let mut finish = |context: &mut bool, id: &mut String, text: &mut String| {
+        if !*context && !id.is_empty() && !text.is_empty() {
+            messages.insert(std::mem::take(id), std::mem::take(text));
+        }
+        *context = false;
+        id.clear();
+        text.clear();
+    };
+    for line in po.lines().map(str::trim) {
+        if let Some(rest) = line.strip_prefix("msgctxt ") {
+            finish(&mut context, &mut id, &mut text);
+            context = !unquote(rest).is_empty();
+            field = Field::Context;
+        } else if let Some(rest) = line.strip_prefix("msgid ") {
+            if field != Field::Context {
+                finish(&mut context, &mut id, &mut text);
+            }
+            id = unquote(rest);
+            field = Field::Id;
+        } else if let Some(rest) = line.strip_prefix("msgstr ") {
+            text = unquote(rest);
+            field = Field::Text;
+        } else if line.starts_with('"') {
+            match field {
+                Field::Context => {}
+                Field::Id => id.push_str(&unquote(line)),
+                Field::Text => text.push_str(&unquote(line)),
+            }
+        }
+    }
+    finish(&mut context, &mut id, &mut text);
+    messages
+}
+
+/// The contents of a quoted .po (or Rust) string literal.
+fn unquote(literal: &str) -> String {
+    let inner = literal.trim().trim_start_matches('"').strip_suffix('"').unwrap_or_default();
+    let mut text = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => text.push('\n'),
+            Some('t') => text.push('\t'),
+            Some(other) => text.push(other),
+            None => {}
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{catalog, catalog_for_locale, fill, is_label, lookup, parse_po, unquote, UiLanguage, BUNDLED};
+
+    #[test]
+    fn languages_map_to_bundled_catalogs() {
+        for language in UiLanguage::ALL {
+            assert_eq!(UiLanguage::from_label(language.label()), Some(language));
+        }
+        assert_eq!(UiLanguage::German.catalog(), "de");
+        assert_eq!(UiLanguage::English.catalog(), "");
+        assert_eq!(catalog_for_locale("de-AT"), "de");
+        assert_eq!(catalog_for_locale("es_MX.UTF-8"), "es");
+        assert_eq!(catalog_for_locale("ES"), "es");
+        assert_eq!(catalog_for_locale("fr-FR"), "");
+        assert_eq!(catalog_for_locale(""), "");
+    }
+
+    #[test]
+    fn catalogs_translate_text_set_from_rust() {
+        assert_eq!(lookup("de", "Idle"), Some("Bereit"));
+        assert_eq!(lookup("es", "Idle"), Some("Inactivo"));
+        assert_eq!(lookup("", "Idle"), None);
+        let po = "msgid \"\"\nmsgstr \"Language: de\\n\"\n\n\
+                  msgctxt \"mini bar\"\nmsgid \"Stop\"\nmsgstr \"Stopp\"\n\n\
+                  msgid \"Say \\\"hi\\\"\"\nmsgstr \"\"\n\"Sag \\\"hallo\\\"\"\n";
+        let messages = parse_po(po);
+        assert_eq!(messages.len(), 1, "{:?}", messages);
+        assert_eq!(messages["Say \"hi\""], "Sag \"hallo\"");
+        assert!(is_label("Dark", "Dunkel"));
+        assert!(is_label("Dark", "Dark"));
+        assert!(!is_label("Dark", "Hell"));
+        assert_eq!(fill("{} of {}", &[&1, &"two"]), "1 of two");
+        assert_eq!(fill("{1} before {0}", &[&"a", &"b"]), "b before a");
+        assert_eq!(fill("{not a placeholder}", &[&1]), "{not a placeholder}");
+    }
+
+    /// Every literal passed to `tr!` needs an entry in each catalog.
+    #[test]
+    fn every_tr_literal_is_translated() {
+        let sources = std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/src")).unwrap();
+        let mut missing = Vec::new();
+        for path in sources.map(|entry| entry.unwrap().path()) {
+            let source = std::fs::read_to_string(&path).unwrap();
+            for (at, _) in source.match_indices("tr!(") {
+                let is_macro = !source[..at].ends_with(|c: char| c.is_alphanumeric() || c == '_' || c == '"');
+                let rest = source[at + 4..].trim_start();
+                if !is_macro || !rest.starts_with('"') {
+                    continue;
+                }
+                let mut escaped = false;
+                let end = rest
+                    .char_indices()
+                    .skip(1)
+                    .find(|(_, c)| match c {
+                        '\\' if !escaped => {
+                            escaped = true;
+                            false
+                        }
+                        '"' if !escaped => true,
+                        _ => {
+                            escaped = false;
+                            false
+                        }
+                    })
+                    .map_or(rest.len() - 1, |(end, _)| end);
+                let text = unquote(&rest[..=end]);
+                for (code, _) in BUNDLED {
+                    if !catalog(code).unwrap().contains_key(&text) {
+                        missing.push(format!("{} in {}: {:?}", code, path.display(), text));
+                    }
+                }
+            }
+        }
+        assert!(missing.is_empty(), "untranslated:\n{}", missing.join("\n"));
+    }
+}
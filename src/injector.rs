@@ -2,22 +2,531 @@
 use windows::Win32::UI::Input::KeyboardAndMouse::{
     SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS,
     KEYEVENTF_KEYUP, KEYEVENTF_UNICODE, VIRTUAL_KEY,
-    GetAsyncKeyState, VK_CONTROL, VK_MENU, VK_SHIFT, VK_LWIN, VK_RWIN,
+    GetAsyncKeyState, VK_BACK, VK_CONTROL, VK_RETURN, VK_MENU, VK_SHIFT, VK_LWIN, VK_RWIN,
 };
-use std::error::Error;
+#[cfg(windows)]
+use windows::Win32::Foundation::{CloseHandle, E_ACCESSDENIED, HWND, LPARAM, WPARAM};
+#[cfg(windows)]
+use windows::Win32::System::Threading::{OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION};
+#[cfg(windows)]
+use windows::Win32::UI::WindowsAndMessaging::{
+    GetForegroundWindow, GetGUIThreadInfo, GetWindowThreadProcessId, IsWindow, PostMessageW, SetForegroundWindow,
+    GUITHREADINFO, WM_CHAR,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+#[cfg(windows)]
+use crate::elevation;
+#[cfg(windows)]
+use crate::error::EchoError;
+use crate::error::EchoResult;
+use crate::i18n;
+#[cfg(windows)]
+use std::time::Duration;
 
-/// Inject UTF-16 text into the system input stream using Win32 SendInput.
-/// This will go to whichever window has focus.
-/// 
-/// Returns Ok(()) if successful, or an Error if SendInput fails.
+/// Throttling for targets that drop characters when text arrives instantly
+/// (remote desktop clients, some Electron editors).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InjectionOptions {
+    /// Pause between bursts, in milliseconds. 0 disables throttling.
+    pub burst_delay_ms: u32,
+    /// Characters (UTF-16 units) per burst. 0 uses the largest burst size.
+    pub chunk_size: usize,
+}
+
+impl InjectionOptions {
+    #[cfg_attr(not(windows), allow(dead_code))]
+    fn burst_units(&self) -> usize {
+        match self.chunk_size {
+            0 => MAX_UNITS_PER_SEND,
+            n => n.min(MAX_UNITS_PER_SEND),
+        }
+    }
+}
+
+/// What to do when the window that had focus at recording start is no
+/// longer in front by the time the transcript is ready.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetLock {
+    /// Type into whatever window has focus.
+    Off,
+    /// Bring the original window back to the front, then type.
+    Refocus,
+    /// Leave focus alone and skip injection.
+    SkipIfMoved,
+}
+
+impl TargetLock {
+    pub const ALL: [TargetLock; 3] = [TargetLock::Off, TargetLock::Refocus, TargetLock::SkipIfMoved];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TargetLock::Off => "Off (type into focused window)",
+            TargetLock::Refocus => "Refocus the original window",
+            TargetLock::SkipIfMoved => "Skip if focus moved",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|lock| i18n::is_label(lock.label(), label))
+    }
+}
+
+/// A top-level window handle captured when recording started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TargetWindow(isize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TargetAction {
+    Inject,
+    Refocus,
+    Skip,
+}
+
+fn target_action(lock: TargetLock, target: TargetWindow, foreground: Option<TargetWindow>) -> TargetAction {
+    match lock {
+        TargetLock::Off => TargetAction::Inject,
+        _ if foreground == Some(target) => TargetAction::Inject,
+        TargetLock::Refocus => TargetAction::Refocus,
+        TargetLock::SkipIfMoved => TargetAction::Skip,
+    }
+}
+
+/// The window that currently has keyboard focus.
 #[cfg(windows)]
-pub fn inject_text(text: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
-    let utf16: Vec<u16> = text.encode_utf16().collect();
-    if utf16.is_empty() {
-        return Ok(());
+pub fn foreground_window() -> Option<TargetWindow> {
+    let hwnd = unsafe { GetForegroundWindow() };
+    (hwnd.0 != 0).then_some(TargetWindow(hwnd.0))
+}
+
+#[cfg(not(windows))]
+pub fn foreground_window() -> Option<TargetWindow> {
+    None
+}
+
+/// The focused window to lock injection to, unless it is one of ours (the
+/// user clicked Start in the main window).
+#[cfg(windows)]
+pub fn capture_target_window() -> Option<TargetWindow> {
+    let target = foreground_window()?;
+    let mut pid = 0u32;
+    unsafe { GetWindowThreadProcessId(HWND(target.0), Some(&mut pid as *mut u32)) };
+    (pid != std::process::id()).then_some(target)
+}
+
+#[cfg(not(windows))]
+pub fn capture_target_window() -> Option<TargetWindow> {
+    foreground_window()
+}
+
+/// Makes sure `target` has focus before injecting, according to `lock`.
+/// Returns Ok(false) when injection should be skipped.
+#[cfg(windows)]
+pub fn prepare_target(target: TargetWindow, lock: TargetLock) -> EchoResult<bool> {
+    match target_action(lock, target, foreground_window()) {
+        TargetAction::Inject => Ok(true),
+        TargetAction::Skip => Ok(false),
+        TargetAction::Refocus => {
+            let hwnd = HWND(target.0);
+            if !unsafe { IsWindow(hwnd) }.as_bool() {
+                return Err(EchoError::injection("The target window was closed"));
+            }
+            // Windows may refuse to hand over focus (foreground lock), so
+            // verify instead of trusting the return value.
+            let _ = unsafe { SetForegroundWindow(hwnd) };
+            std::thread::sleep(Duration::from_millis(50));
+            if foreground_window() == Some(target) {
+                Ok(true)
+            } else {
+                Err(EchoError::injection("Windows refused to refocus the target window"))
+            }
+        }
+    }
+}
+
+#[cfg(not(windows))]
+pub fn prepare_target(target: TargetWindow, lock: TargetLock) -> EchoResult<bool> {
+    Ok(target_action(lock, target, foreground_window()) == TargetAction::Inject)
+}
+
+/// Remembers what was typed into other windows so it can be taken back.
+#[derive(Debug, Default)]
+pub struct InjectionHistory {
+    entries: VecDeque<String>,
+}
+
+/// How many injections can be undone in a row.
+const MAX_UNDO_ENTRIES: usize = 20;
+
+impl InjectionHistory {
+    pub fn record(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        if self.entries.len() == MAX_UNDO_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(text.to_string());
+    }
+
+    /// Removes the most recent injection and returns how many Backspace
+    /// presses erase it.
+    pub fn pop_last(&mut self) -> Option<usize> {
+        self.entries.pop_back().map(|text| backspace_count(&text))
+    }
+}
+
+/// Editors delete one character per Backspace, including astral characters
+/// that take two UTF-16 units, so count chars rather than units.
+fn backspace_count(text: &str) -> usize {
+    text.chars().filter(|&c| c != '\0').count()
+}
+
+/// The keystrokes that turn text already typed at the cursor into a new
+/// version of it. Only the end of the text can be reached with Backspace, so
+/// the edit keeps the longest common prefix and retypes the rest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub backspaces: usize,
+    pub insert: String,
+}
+
+impl TextEdit {
+    pub fn between(typed: &str, target: &str) -> Self {
+        let prefix_len: usize = typed
+            .chars()
+            .zip(target.chars())
+            .take_while(|(a, b)| a == b)
+            .map(|(c, _)| c.len_utf8())
+            .sum();
+        Self {
+            backspaces: backspace_count(&typed[prefix_len..]),
+            insert: target[prefix_len..].to_string(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.backspaces == 0 && self.insert.is_empty()
+    }
+}
+
+/// Erases and retypes the changed tail of previously injected text.
+pub fn apply_edit(edit: &TextEdit, options: &InjectionOptions) -> EchoResult<()> {
+    send_backspaces(edit.backspaces)?;
+    inject_text_with(&edit.insert, options)
+}
+
+/// How text reaches the target window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InjectionStrategy {
+    /// Synthesized key events through SendInput; works with any app that
+    /// reads the keyboard, as long as UIPI lets the events through.
+    SendInput,
+    /// WM_CHAR messages posted straight to the focused control, for
+    /// elevated or sandboxed windows that reject SendInput.
+    PostMessage,
+}
+
+impl InjectionStrategy {
+    pub fn label(self) -> &'static str {
+        match self {
+            InjectionStrategy::SendInput => "SendInput",
+            InjectionStrategy::PostMessage => "WM_CHAR",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StrategyStats {
+    pub successes: u64,
+    pub failures: u64,
+}
+
+/// Per-strategy success counts since the app started.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InjectionTelemetry {
+    pub send_input: StrategyStats,
+    pub post_message: StrategyStats,
+}
+
+impl InjectionTelemetry {
+    const EMPTY: InjectionTelemetry = InjectionTelemetry {
+        send_input: StrategyStats { successes: 0, failures: 0 },
+        post_message: StrategyStats { successes: 0, failures: 0 },
+    };
+
+    fn stats_mut(&mut self, strategy: InjectionStrategy) -> &mut StrategyStats {
+        match strategy {
+            InjectionStrategy::SendInput => &mut self.send_input,
+            InjectionStrategy::PostMessage => &mut self.post_message,
+        }
+    }
+
+    #[cfg_attr(not(windows), allow(dead_code))]
+    fn record(&mut self, strategy: InjectionStrategy, ok: bool) {
+        let stats = self.stats_mut(strategy);
+        if ok {
+            stats.successes += 1;
+        } else {
+            stats.failures += 1;
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        [
+            (InjectionStrategy::SendInput, self.send_input),
+            (InjectionStrategy::PostMessage, self.post_message),
+        ]
+        .iter()
+        .map(|(strategy, stats)| format!("{} {} ok / {} failed", strategy.label(), stats.successes, stats.failures))
+        .collect::<Vec<_>>()
+        .join(" · ")
+    }
+}
+
+static TELEMETRY: Mutex<InjectionTelemetry> = Mutex::new(InjectionTelemetry::EMPTY);
+
+/// A snapshot of how each injection strategy has fared so far.
+pub fn telemetry() -> InjectionTelemetry {
+    *TELEMETRY.lock().unwrap()
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+fn record_attempt(strategy: InjectionStrategy, ok: bool) {
+    TELEMETRY.lock().unwrap().record(strategy, ok);
+}
+
+/// Maximum UTF-16 code units submitted per SendInput call. Each unit becomes a
+/// key-down and key-up INPUT, so a call carries at most twice this many events.
+#[cfg_attr(not(windows), allow(dead_code))]
+const MAX_UNITS_PER_SEND: usize = 512;
+
+/// Splits UTF-16 text into slices of at most `max_units`, never separating the
+/// two halves of a surrogate pair.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn split_for_send_input(units: &[u16], max_units: usize) -> Vec<&[u16]> {
+    let max_units = max_units.max(2);
+    let mut chunks = Vec::new();
+    let mut rest = units;
+    while !rest.is_empty() {
+        let mut end = rest.len().min(max_units);
+        if end < rest.len() && (0xD800..=0xDBFF).contains(&rest[end - 1]) {
+            end -= 1;
+        }
+        let (head, tail) = rest.split_at(end);
+        chunks.push(head);
+        rest = tail;
+    }
+    chunks
+}
+
+#[cfg(windows)]
+fn keyboard_input(vk: VIRTUAL_KEY, scan: u16, flags: u32) -> INPUT {
+    INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: vk,
+                wScan: scan,
+                dwFlags: KEYBD_EVENT_FLAGS(flags),
+                time: 0,
+                dwExtraInfo: 0,
+            },
+        },
+    }
+}
+
+/// Submits `inputs`, returning false when SendInput rejected all of them
+/// (no window focused, or the target is blocked by UIPI).
+#[cfg(windows)]
+fn send_inputs(inputs: &[INPUT]) -> bool {
+    if inputs.is_empty() {
+        return true;
+    }
+    let sent = unsafe { SendInput(inputs, std::mem::size_of::<INPUT>() as i32) };
+    if sent != inputs.len() as u32 {
+        // SendInput returned less than expected.
+        tracing::warn!("⚠ SendInput only sent {} out of {} inputs", sent, inputs.len());
+    }
+    sent != 0
+}
+
+/// The control with keyboard focus inside the foreground window, which is
+/// where WM_CHAR has to go; falls back to the window itself.
+#[cfg(windows)]
+fn focused_control() -> Option<HWND> {
+    let foreground = unsafe { GetForegroundWindow() };
+    if foreground.0 == 0 {
+        return None;
+    }
+    let thread = unsafe { GetWindowThreadProcessId(foreground, None) };
+    let mut info = GUITHREADINFO {
+        cbSize: std::mem::size_of::<GUITHREADINFO>() as u32,
+        ..Default::default()
+    };
+    if unsafe { GetGUIThreadInfo(thread, &mut info) }.is_ok() && info.hwndFocus.0 != 0 {
+        Some(info.hwndFocus)
+    } else {
+        Some(foreground)
+    }
+}
+
+/// The foreground window belongs to an elevated process and this app isn't
+/// elevated, so Windows (UIPI) drops our input without SendInput failing.
+#[cfg(windows)]
+fn foreground_is_elevated() -> bool {
+    if elevation::is_elevated() {
+        return false;
+    }
+    let foreground = unsafe { GetForegroundWindow() };
+    if foreground.0 == 0 {
+        return false;
+    }
+    let mut pid = 0u32;
+    unsafe { GetWindowThreadProcessId(foreground, Some(&mut pid as *mut u32)) };
+    let Ok(process) = (unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) }) else {
+        return false;
+    };
+    let elevated = match elevation::process_is_elevated(process) {
+        Ok(elevated) => elevated,
+        // Reading an elevated process's token is refused to a non-elevated one.
+        Err(e) => e.code() == E_ACCESSDENIED,
+    };
+    let _ = unsafe { CloseHandle(process) };
+    elevated
+}
+
+/// Fails up front when the focused window would silently ignore our input.
+#[cfg(windows)]
+fn ensure_input_reaches_target() -> EchoResult<()> {
+    if foreground_is_elevated() {
+        tracing::warn!("⚠ The foreground window is running as administrator, input would be dropped");
+        return Err(EchoError::ElevatedTarget);
+    }
+    Ok(())
+}
+
+/// Fallback for when SendInput is rejected: posts each UTF-16 unit as a
+/// WM_CHAR message. Surrogate pairs go as two messages, which edit controls
+/// reassemble.
+#[cfg(windows)]
+fn post_chars(units: &[u16]) -> EchoResult<()> {
+    let hwnd = focused_control().ok_or_else(|| EchoError::injection("No focused window to type into"))?;
+    for &unit in units {
+        unsafe { PostMessageW(hwnd, WM_CHAR, WPARAM(unit as usize), LPARAM(1)) }.map_err(|e| {
+            EchoError::injection(format!(
+                "SendInput and WM_CHAR were both rejected - the target may be running as administrator ({})",
+                e
+            ))
+        })?;
+    }
+    Ok(())
+}
+
+/// Tries the WM_CHAR strategy after SendInput returned 0 and records how
+/// both fared.
+#[cfg(windows)]
+fn fall_back_to_post_message(units: &[u16]) -> EchoResult<()> {
+    record_attempt(InjectionStrategy::SendInput, false);
+    tracing::warn!("⚠ SendInput returned 0, falling back to WM_CHAR for {} characters", units.len());
+    let result = post_chars(units);
+    record_attempt(InjectionStrategy::PostMessage, result.is_ok());
+    result
+}
+
+/// Virtual-key codes for Alt and the Windows keys. Releasing one of these on
+/// its own activates the menu bar or opens Start.
+#[cfg_attr(not(windows), allow(dead_code))]
+const MENU_ACTIVATING_KEYS: [u16; 3] = [0x12, 0x5B, 0x5C];
+/// An unassigned virtual-key code, tapped before lifting Alt/Win so Windows
+/// sees a key combination rather than a lone Alt or Win press.
+#[cfg_attr(not(windows), allow(dead_code))]
+const MASK_KEY: u16 = 0xE8;
+/// How long to wait for the user to let go of the hotkey's modifiers before
+/// releasing them on their behalf.
+#[cfg(windows)]
+const MODIFIER_RELEASE_WAIT: Duration = Duration::from_millis(300);
+
+/// Key events (virtual-key code, key-up) that lift the `held` modifiers
+/// before typing, so Ctrl+H doesn't reach the target instead of "h".
+#[cfg_attr(not(windows), allow(dead_code))]
+fn modifier_release_events(held: &[u16]) -> Vec<(u16, bool)> {
+    let mut events = Vec::with_capacity(held.len() + 2);
+    if held.iter().any(|key| MENU_ACTIVATING_KEYS.contains(key)) {
+        events.push((MASK_KEY, false));
+        events.push((MASK_KEY, true));
+    }
+    events.extend(held.iter().map(|&key| (key, true)));
+    events
+}
+
+/// Key events that press the `held` modifiers again after typing, in
+/// reverse order so the original chord is restored.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn modifier_restore_events(held: &[u16]) -> Vec<(u16, bool)> {
+    held.iter().rev().map(|&key| (key, false)).collect()
+}
+
+#[cfg(windows)]
+fn modifier_inputs(events: &[(u16, bool)]) -> impl Iterator<Item = INPUT> + '_ {
+    events.iter().map(|&(key, up)| {
+        let flags = if up { KEYEVENTF_KEYUP.0 } else { 0 };
+        keyboard_input(VIRTUAL_KEY(key), 0, flags)
+    })
+}
+
+/// Modifiers lifted for an injection. While `released` is set, dropping this
+/// presses them again, so a failed send or the WM_CHAR fallback does not
+/// leave the user's chord half released.
+#[cfg(windows)]
+struct LiftedModifiers {
+    keys: Vec<u16>,
+    released: bool,
+}
+
+#[cfg(windows)]
+impl LiftedModifiers {
+    fn settle() -> Self {
+        Self { keys: settled_modifiers(), released: false }
+    }
+
+    fn release_inputs(&self) -> Vec<INPUT> {
+        modifier_inputs(&modifier_release_events(&self.keys)).collect()
     }
 
-    // Identify which modifiers are currently held down.
+    fn restore_inputs(&self) -> Vec<INPUT> {
+        modifier_inputs(&modifier_restore_events(&self.keys)).collect()
+    }
+}
+
+#[cfg(windows)]
+impl Drop for LiftedModifiers {
+    fn drop(&mut self) {
+        if self.released {
+            send_inputs(&self.restore_inputs());
+        }
+    }
+}
+
+/// Modifiers still held once the user has had a moment to release the
+/// hotkey; only those need to be neutralized during injection.
+#[cfg(windows)]
+fn settled_modifiers() -> Vec<u16> {
+    let started = std::time::Instant::now();
+    loop {
+        let held = held_modifiers();
+        if held.is_empty() || started.elapsed() >= MODIFIER_RELEASE_WAIT {
+            return held.into_iter().map(|key| key.0).collect();
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Modifiers currently held down, e.g. by the hotkey that triggered us.
+#[cfg(windows)]
+fn held_modifiers() -> Vec<VIRTUAL_KEY> {
     let modifiers = [
         VK_CONTROL,
         VK_MENU, // Alt
@@ -26,115 +535,256 @@ pub fn inject_text(text: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
         VK_RWIN,
     ];
 
-    let mut held_modifiers = Vec::new();
+    let mut held = Vec::new();
     for &mod_key in &modifiers {
         unsafe {
             // GetAsyncKeyState returns a short where the high bit (0x8000) is set if the key is down.
             if (GetAsyncKeyState(mod_key.0 as i32) as u16) & 0x8000 != 0 {
-                held_modifiers.push(mod_key);
+                held.push(mod_key);
             }
         }
     }
+    held
+}
 
-    // Total inputs: (release mods) + (press chars) + (release chars) + (restore mods)
-    let mut inputs: Vec<INPUT> = Vec::with_capacity(held_modifiers.len() * 2 + utf16.len() * 2);
+/// Inject UTF-16 text into the system input stream using Win32 SendInput.
+/// This will go to whichever window has focus.
+///
+/// The whole string is converted into one INPUT array and submitted in as few
+/// SendInput calls as possible (chunked at `MAX_UNITS_PER_SEND`), so the burst
+/// cannot interleave with the user's own typing. If SendInput is rejected, the
+/// rest of the text is posted as WM_CHAR messages instead.
+///
+/// Returns Ok(()) if successful, or an Error if SendInput fails.
+#[cfg(windows)]
+pub fn inject_text(text: &str) -> EchoResult<()> {
+    inject_text_with(text, &InjectionOptions::default())
+}
 
-    // 1. Temporarily release held modifiers
-    for &mod_key in &held_modifiers {
-        inputs.push(INPUT {
-            r#type: INPUT_KEYBOARD,
-            Anonymous: INPUT_0 {
-                ki: KEYBDINPUT {
-                    wVk: mod_key,
-                    wScan: 0,
-                    dwFlags: KEYBD_EVENT_FLAGS(KEYEVENTF_KEYUP.0),
-                    time: 0,
-                    dwExtraInfo: 0,
-                },
-            },
-        });
+/// Like [`inject_text`], but splits the text into `options.chunk_size` bursts
+/// separated by `options.burst_delay_ms`. Blocks the calling thread while throttling.
+#[cfg(windows)]
+pub fn inject_text_with(
+    text: &str,
+    options: &InjectionOptions,
+) -> EchoResult<()> {
+    // "\r\n" is typed as a single Enter, like "\n".
+    let utf16: Vec<u16> = text.encode_utf16().filter(|&unit| unit != 0 && unit != 0x0D).collect();
+    if utf16.is_empty() {
+        return Ok(());
     }
+    ensure_input_reaches_target()?;
 
-    // 2. Inject UTF-16 characters
-    for &code_unit in &utf16 {
-        if code_unit == 0 {
-            continue;
+    let mut modifiers = LiftedModifiers::settle();
+    let chunks = split_for_send_input(&utf16, options.burst_units());
+    let last_index = chunks.len() - 1;
+    let mut inputs: Vec<INPUT> = Vec::with_capacity(modifiers.keys.len() * 2 + 2 + MAX_UNITS_PER_SEND * 2);
+
+    for (index, chunk) in chunks.iter().enumerate() {
+        inputs.clear();
+
+        // 1. Temporarily release held modifiers before the first burst
+        if index == 0 {
+            inputs.extend(modifiers.release_inputs());
         }
 
-        // Key down
-        inputs.push(INPUT {
-            r#type: INPUT_KEYBOARD,
-            Anonymous: INPUT_0 {
-                ki: KEYBDINPUT {
-                    wVk: VIRTUAL_KEY(0),
-                    wScan: code_unit,
-                    dwFlags: KEYBD_EVENT_FLAGS(KEYEVENTF_UNICODE.0),
-                    time: 0,
-                    dwExtraInfo: 0,
-                },
-            },
-        });
+        // 2. Inject UTF-16 characters (key down + key up)
+        for &code_unit in *chunk {
+            if code_unit == 0x0A {
+                // Most edit controls ignore a typed line feed; Enter starts
+                // the new line instead.
+                inputs.push(keyboard_input(VK_RETURN, 0, 0));
+                inputs.push(keyboard_input(VK_RETURN, 0, KEYEVENTF_KEYUP.0));
+                continue;
+            }
+            inputs.push(keyboard_input(VIRTUAL_KEY(0), code_unit, KEYEVENTF_UNICODE.0));
+            inputs.push(keyboard_input(
+                VIRTUAL_KEY(0),
+                code_unit,
+                KEYEVENTF_UNICODE.0 | KEYEVENTF_KEYUP.0,
+            ));
+        }
 
-        // Key up
-        inputs.push(INPUT {
-            r#type: INPUT_KEYBOARD,
-            Anonymous: INPUT_0 {
-                ki: KEYBDINPUT {
-                    wVk: VIRTUAL_KEY(0),
-                    wScan: code_unit,
-                    dwFlags: KEYBD_EVENT_FLAGS(
-                        KEYEVENTF_UNICODE.0 | KEYEVENTF_KEYUP.0,
-                    ),
-                    time: 0,
-                    dwExtraInfo: 0,
-                },
-            },
-        });
-    }
+        // 3. Restore modifiers after the last burst
+        if index == last_index {
+            inputs.extend(modifiers.restore_inputs());
+        }
 
-    // 3. Restore modifiers
-    for &mod_key in held_modifiers.iter().rev() {
-        inputs.push(INPUT {
-            r#type: INPUT_KEYBOARD,
-            Anonymous: INPUT_0 {
-                ki: KEYBDINPUT {
-                    wVk: mod_key,
-                    wScan: 0,
-                    dwFlags: KEYBD_EVENT_FLAGS(0), // Key down
-                    time: 0,
-                    dwExtraInfo: 0,
-                },
-            },
-        });
+        if !send_inputs(&inputs) {
+            return fall_back_to_post_message(&chunks[index..].concat());
+        }
+        modifiers.released = index != last_index;
+        if options.burst_delay_ms > 0 && index != last_index {
+            std::thread::sleep(Duration::from_millis(options.burst_delay_ms as u64));
+        }
     }
 
-    if inputs.is_empty() {
+    record_attempt(InjectionStrategy::SendInput, true);
+    Ok(())
+}
+
+/// Presses Backspace `count` times in the focused window. Held modifiers are
+/// released first so the undo hotkey's Ctrl does not turn this into
+/// word-wise deletion.
+#[cfg(windows)]
+pub fn send_backspaces(count: usize) -> EchoResult<()> {
+    if count == 0 {
         return Ok(());
     }
+    ensure_input_reaches_target()?;
+    let mut modifiers = LiftedModifiers::settle();
+    let presses = MAX_UNITS_PER_SEND;
+    let mut remaining = count;
+    let mut first = true;
+    let mut inputs: Vec<INPUT> = Vec::with_capacity(modifiers.keys.len() * 2 + 2 + presses * 2);
+    while remaining > 0 {
+        inputs.clear();
+        if first {
+            inputs.extend(modifiers.release_inputs());
+            first = false;
+        }
+        let batch = remaining.min(presses);
+        for _ in 0..batch {
+            inputs.push(keyboard_input(VK_BACK, 0, 0));
+            inputs.push(keyboard_input(VK_BACK, 0, KEYEVENTF_KEYUP.0));
+        }
+        if remaining == batch {
+            inputs.extend(modifiers.restore_inputs());
+        }
+        if !send_inputs(&inputs) {
+            // Edit controls treat a backspace character as Backspace.
+            return fall_back_to_post_message(&vec![0x08; remaining]);
+        }
+        modifiers.released = remaining != batch;
+        remaining -= batch;
+    }
+    record_attempt(InjectionStrategy::SendInput, true);
+    Ok(())
+}
 
-    unsafe {
-        let sent = SendInput(&inputs, std::mem::size_of::<INPUT>() as i32);
-        if sent != inputs.len() as u32 {
-            // SendInput returned less than expected.
-            eprintln!("⚠ SendInput only sent {} out of {} inputs", sent, inputs.len());
-            if sent == 0 {
-                return Err("SendInput returned 0 - possible causes: no window focused, input blocked by system (UIPI), or insufficient privileges".into());
+#[cfg(not(windows))]
+pub fn send_backspaces(count: usize) -> EchoResult<()> {
+    tracing::debug!("BACKSPACE x{} (No-op on Linux)", count);
+    Ok(())
+}
+
+/// A key chord as virtual-key codes: modifiers held while `key` is tapped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyCombo {
+    pub modifiers: Vec<u16>,
+    pub key: u16,
+}
+
+/// Parses a chord such as "Ctrl+Shift+T" or "Enter". Covers more keys than
+/// global hotkeys do, since it only has to be sent, not registered.
+pub fn parse_key_combo(input: &str) -> Result<KeyCombo, String> {
+    let mut modifiers = Vec::new();
+    let mut key = None;
+    for part in input.split('+').map(str::trim).filter(|p| !p.is_empty()) {
+        let token = part.to_ascii_lowercase();
+        let modifier = match token.as_str() {
+            "ctrl" | "control" => Some(0x11),
+            "shift" => Some(0x10),
+            "alt" => Some(0x12),
+            "win" | "meta" | "super" => Some(0x5B),
+            _ => None,
+        };
+        if let Some(modifier) = modifier {
+            if !modifiers.contains(&modifier) {
+                modifiers.push(modifier);
             }
+            continue;
         }
+        if key.is_some() {
+            return Err(format!("More than one key in \"{}\"", input.trim()));
+        }
+        key = Some(match token.as_str() {
+            "enter" | "return" => 0x0D,
+            "tab" => 0x09,
+            "backspace" => 0x08,
+            "delete" | "del" => 0x2E,
+            "insert" | "ins" => 0x2D,
+            "esc" | "escape" => 0x1B,
+            "space" => 0x20,
+            "left" => 0x25,
+            "up" => 0x26,
+            "right" => 0x27,
+            "down" => 0x28,
+            "home" => 0x24,
+            "end" => 0x23,
+            "pageup" | "pgup" => 0x21,
+            "pagedown" | "pgdn" => 0x22,
+            t if t.len() == 1 && t.as_bytes()[0].is_ascii_alphanumeric() => t.to_ascii_uppercase().as_bytes()[0] as u16,
+            t => match t.strip_prefix('f').and_then(|n| n.parse::<u16>().ok()) {
+                Some(n @ 1..=24) => 0x70 + n - 1,
+                _ => return Err(format!("Unsupported key token: {}", part)),
+            },
+        });
+    }
+    let key = key.ok_or_else(|| format!("No key found in \"{}\"", input.trim()))?;
+    Ok(KeyCombo { modifiers, key })
+}
+
+/// Key events (virtual-key code, key-up) that press `combo` and let go of it
+/// in reverse order.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn combo_events(combo: &KeyCombo) -> Vec<(u16, bool)> {
+    let mut events: Vec<(u16, bool)> = combo.modifiers.iter().map(|&key| (key, false)).collect();
+    events.push((combo.key, false));
+    events.push((combo.key, true));
+    events.extend(combo.modifiers.iter().rev().map(|&key| (key, true)));
+    events
+}
+
+/// Presses `combo` in the focused window, with the user's own held
+/// modifiers lifted for the duration.
+#[cfg(windows)]
+pub fn send_key_combo(combo: &KeyCombo) -> EchoResult<()> {
+    ensure_input_reaches_target()?;
+    let held_modifiers = settled_modifiers();
+    let mut events = modifier_release_events(&held_modifiers);
+    events.extend(combo_events(combo));
+    events.extend(modifier_restore_events(&held_modifiers));
+    let inputs: Vec<INPUT> = modifier_inputs(&events).collect();
+    let sent = send_inputs(&inputs);
+    record_attempt(InjectionStrategy::SendInput, sent);
+    if sent {
+        Ok(())
+    } else {
+        Err(EchoError::injection(
+            "The key press was rejected - the target may be running as administrator",
+        ))
     }
+}
 
+#[cfg(not(windows))]
+pub fn send_key_combo(combo: &KeyCombo) -> EchoResult<()> {
+    tracing::debug!("KEYS {:?} (No-op on Linux)", combo);
     Ok(())
 }
 
 #[cfg(not(windows))]
-pub fn inject_text(text: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
-    println!("INJECT (No-op on Linux): {}", text);
+pub fn inject_text(text: &str) -> EchoResult<()> {
+    tracing::debug!("INJECT (No-op on Linux): {}", text);
     Ok(())
 }
 
+#[cfg(not(windows))]
+pub fn inject_text_with(
+    text: &str,
+    _options: &InjectionOptions,
+) -> EchoResult<()> {
+    inject_text(text)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::inject_text;
+    use super::{
+        combo_events, inject_text, inject_text_with, modifier_release_events, modifier_restore_events, parse_key_combo,
+        split_for_send_input,
+        target_action, InjectionHistory, InjectionOptions, InjectionStrategy, InjectionTelemetry, TargetAction,
+        TargetLock, TargetWindow, TextEdit, MASK_KEY, MAX_UNDO_ENTRIES, MAX_UNITS_PER_SEND,
+    };
 
     #[test]
     fn inject_empty_text_is_ok() {
@@ -145,4 +795,140 @@ mod tests {
     fn inject_null_only_text_is_ok() {
         assert!(inject_text("\0").is_ok());
     }
+
+    #[test]
+    fn split_keeps_short_text_in_one_chunk() {
+        let units: Vec<u16> = "hello".encode_utf16().collect();
+        assert_eq!(split_for_send_input(&units, 512), vec![&units[..]]);
+    }
+
+    #[test]
+    fn split_chunks_long_text() {
+        let units = vec![b'a' as u16; 1100];
+        let sizes: Vec<usize> = split_for_send_input(&units, 512).iter().map(|c| c.len()).collect();
+        assert_eq!(sizes, vec![512, 512, 76]);
+    }
+
+    #[test]
+    fn split_never_breaks_surrogate_pairs() {
+        let units: Vec<u16> = "ab😀cd".encode_utf16().collect();
+        let chunks = split_for_send_input(&units, 3);
+        assert_eq!(chunks[0], &units[..2]);
+        for chunk in chunks {
+            assert!(String::from_utf16(chunk).is_ok());
+        }
+    }
+
+    #[test]
+    fn burst_units_defaults_to_max_and_caps_large_values() {
+        assert_eq!(InjectionOptions::default().burst_units(), MAX_UNITS_PER_SEND);
+        let small = InjectionOptions { burst_delay_ms: 10, chunk_size: 4 };
+        assert_eq!(small.burst_units(), 4);
+        let huge = InjectionOptions { burst_delay_ms: 0, chunk_size: 100_000 };
+        assert_eq!(huge.burst_units(), MAX_UNITS_PER_SEND);
+    }
+
+    #[test]
+    fn inject_with_throttling_accepts_empty_text() {
+        let options = InjectionOptions { burst_delay_ms: 5, chunk_size: 1 };
+        assert!(inject_text_with("", &options).is_ok());
+    }
+
+    #[test]
+    fn history_undoes_most_recent_injection_first() {
+        let mut history = InjectionHistory::default();
+        history.record("hello ");
+        history.record("");
+        history.record("caf\u{e9} \u{1F600} ");
+        assert_eq!(history.pop_last(), Some(7));
+        assert_eq!(history.pop_last(), Some(6));
+        assert_eq!(history.pop_last(), None);
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut history = InjectionHistory::default();
+        for i in 0..MAX_UNDO_ENTRIES + 5 {
+            history.record(&"x".repeat(i + 1));
+        }
+        let mut undone = 0;
+        while history.pop_last().is_some() {
+            undone += 1;
+        }
+        assert_eq!(undone, MAX_UNDO_ENTRIES);
+    }
+
+    #[test]
+    fn text_edit_only_retypes_the_changed_tail() {
+        let edit = |typed: &str, target: &str| {
+            let edit = TextEdit::between(typed, target);
+            (edit.backspaces, edit.insert)
+        };
+        assert_eq!(edit("hello wor", "hello world "), (0, "ld ".to_string()));
+        assert_eq!(edit("hello word", "hello world"), (1, "ld".to_string()));
+        assert_eq!(edit("caf\u{e9} \u{1F600}", "cafe"), (3, "e".to_string()));
+        assert_eq!(edit("", "hi"), (0, "hi".to_string()));
+        assert_eq!(edit("too much", ""), (8, String::new()));
+        assert!(TextEdit::between("same", "same").is_empty());
+    }
+
+    #[test]
+    fn held_modifiers_are_lifted_and_restored_in_reverse() {
+        const CTRL: u16 = 0x11;
+        const SHIFT: u16 = 0x10;
+        assert_eq!(modifier_release_events(&[CTRL, SHIFT]), vec![(CTRL, true), (SHIFT, true)]);
+        assert_eq!(modifier_restore_events(&[CTRL, SHIFT]), vec![(SHIFT, false), (CTRL, false)]);
+        assert!(modifier_release_events(&[]).is_empty());
+    }
+
+    #[test]
+    fn releasing_alt_is_masked_so_menus_stay_closed() {
+        const ALT: u16 = 0x12;
+        const CTRL: u16 = 0x11;
+        assert_eq!(
+            modifier_release_events(&[CTRL, ALT]),
+            vec![(MASK_KEY, false), (MASK_KEY, true), (CTRL, true), (ALT, true)]
+        );
+    }
+
+    #[test]
+    fn key_combos_press_modifiers_around_the_key() {
+        let combo = parse_key_combo("Ctrl+Shift+t").unwrap();
+        assert_eq!(combo.modifiers, vec![0x11, 0x10]);
+        assert_eq!(combo.key, b'T' as u16);
+        assert_eq!(
+            combo_events(&combo),
+            vec![(0x11, false), (0x10, false), (0x54, false), (0x54, true), (0x10, true), (0x11, true)]
+        );
+        assert_eq!(parse_key_combo("Enter").unwrap().key, 0x0D);
+        assert_eq!(parse_key_combo("alt+F4").unwrap().key, 0x73);
+        assert!(parse_key_combo("Ctrl+A+B").unwrap_err().contains("More than one key"));
+        assert!(parse_key_combo("Ctrl").unwrap_err().contains("No key found"));
+        assert!(parse_key_combo("Hyper+X").unwrap_err().contains("Unsupported key token"));
+    }
+
+    #[test]
+    fn telemetry_counts_each_strategy_separately() {
+        let mut telemetry = InjectionTelemetry::default();
+        telemetry.record(InjectionStrategy::SendInput, true);
+        telemetry.record(InjectionStrategy::SendInput, false);
+        telemetry.record(InjectionStrategy::PostMessage, true);
+        assert_eq!(telemetry.send_input.successes, 1);
+        assert_eq!(telemetry.send_input.failures, 1);
+        assert_eq!(telemetry.post_message.successes, 1);
+        assert_eq!(telemetry.summary(), "SendInput 1 ok / 1 failed · WM_CHAR 1 ok / 0 failed");
+    }
+
+    #[test]
+    fn target_lock_decides_between_inject_refocus_and_skip() {
+        let target = TargetWindow(42);
+        let other = Some(TargetWindow(7));
+        assert_eq!(target_action(TargetLock::Off, target, other), TargetAction::Inject);
+        assert_eq!(target_action(TargetLock::Refocus, target, Some(target)), TargetAction::Inject);
+        assert_eq!(target_action(TargetLock::Refocus, target, other), TargetAction::Refocus);
+        assert_eq!(target_action(TargetLock::SkipIfMoved, target, None), TargetAction::Skip);
+        for lock in TargetLock::ALL {
+            assert_eq!(TargetLock::from_label(lock.label()), Some(lock));
+        }
+    }
 }
@@ -0,0 +1,171 @@
+use crate::i18n;
+use crate::settings::AppSettings;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Duration;
+
+/// What the post-processing prompt is applied to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LlmScope {
+    /// Every committed segment, before it reaches the history.
+    EachCommit,
+    /// The whole session transcript, once, right before injection.
+    WholeSession,
+}
+
+impl LlmScope {
+    pub const ALL: [LlmScope; 2] = [LlmScope::EachCommit, LlmScope::WholeSession];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LlmScope::EachCommit => "Each committed segment",
+            LlmScope::WholeSession => "Whole session before injection",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|scope| i18n::is_label(scope.label(), label))
+    }
+}
+
+/// Snapshot of the post-processing settings, taken outside the settings lock.
+#[derive(Debug, Clone)]
+pub struct LlmConfig {
+    pub endpoint: String,
+    pub api_key: String,
+    pub model: String,
+    pub prompt: String,
+    pub timeout: Duration,
+    pub proxy_url: String,
+}
+
+impl LlmConfig {
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        Self {
+            endpoint: settings.llm_endpoint.trim().to_string(),
+            api_key: settings.llm_api_key.trim().to_string(),
+            model: settings.llm_model.trim().to_string(),
+            prompt: settings.llm_prompt.clone(),
+            timeout: Duration::from_secs(settings.llm_timeout_secs.max(1) as u64),
+            proxy_url: settings.proxy_url.clone(),
+        }
+    }
+}
+
+/// Accepts either an API base (`.../v1`) or the full chat completions URL.
+fn chat_completions_url(endpoint: &str) -> String {
+    let endpoint = endpoint.trim_end_matches('/');
+    if endpoint.ends_with("/chat/completions") {
+        endpoint.to_string()
+    } else {
+        format!("{}/chat/completions", endpoint)
+    }
+}
+
+fn request_body(config: &LlmConfig, text: &str) -> serde_json::Value {
+    json!({
+        "model": config.model,
+        "temperature": 0.2,
+        "messages": [
+            {
+                "role": "system",
+                "content": format!(
+                    "{}\n\nThe user message is dictated text. Return ONLY the processed text, with no explanations or extra commentary.",
+                    config.prompt.trim()
+                )
+            },
+            { "role": "user", "content": text }
+        ]
+    })
+}
+
+fn completion_text(value: &serde_json::Value) -> Option<String> {
+    let text = value
+        .get("choices")?
+        .get(0)?
+        .get("message")?
+        .get("content")?
+        .as_str()?
+        .trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Sends `text` through the configured OpenAI-compatible chat endpoint.
+/// Errors (including the timeout) leave it to the caller to fall back to
+/// the original text.
+pub async fn post_process(config: &LlmConfig, text: &str) -> Result<String, String> {
+    if config.endpoint.is_empty() || config.model.is_empty() {
+        return Err("LLM endpoint and model are required".to_string());
+    }
+    let proxy = crate::proxy::resolve(&config.proxy_url)?;
+    let http = crate::proxy::http_client(proxy.as_ref(), Some(config.timeout))?;
+
+    let mut request = http.post(chat_completions_url(&config.endpoint)).json(&request_body(config, text));
+    // Local servers (Ollama, LM Studio) usually run without a key.
+    if !config.api_key.is_empty() {
+        request = request.bearer_auth(&config.api_key);
+    }
+    let response = request.send().await.map_err(|e| {
+        if e.is_timeout() {
+            format!("timed out after {}s", config.timeout.as_secs())
+        } else {
+            e.to_string()
+        }
+    })?;
+    let status = response.status();
+    let value: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+    if !status.is_success() {
+        return Err(format!("{}: {}", status, value));
+    }
+    completion_text(&value).ok_or_else(|| format!("Unexpected response: {}", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{chat_completions_url, completion_text, request_body, LlmConfig, LlmScope};
+    use serde_json::json;
+    use std::time::Duration;
+
+    #[test]
+    fn endpoint_accepts_base_or_full_url() {
+        assert_eq!(
+            chat_completions_url("https://api.openai.com/v1/"),
+            "https://api.openai.com/v1/chat/completions"
+        );
+        assert_eq!(
+            chat_completions_url("http://localhost:11434/v1/chat/completions"),
+            "http://localhost:11434/v1/chat/completions"
+        );
+    }
+
+    #[test]
+    fn request_carries_prompt_and_text() {
+        let config = LlmConfig {
+            endpoint: "http://localhost".into(),
+            api_key: String::new(),
+            model: "gpt-4o-mini".into(),
+            prompt: "Format as bullet points".into(),
+            timeout: Duration::from_secs(5),
+            proxy_url: String::new(),
+        };
+        let body = request_body(&config, "buy milk and eggs");
+        assert_eq!(body["model"], "gpt-4o-mini");
+        assert!(body["messages"][0]["content"].as_str().unwrap().starts_with("Format as bullet points"));
+        assert_eq!(body["messages"][1]["content"], "buy milk and eggs");
+    }
+
+    #[test]
+    fn reads_first_choice() {
+        let value = json!({ "choices": [{ "message": { "content": " - milk\n- eggs " } }] });
+        assert_eq!(completion_text(&value).as_deref(), Some("- milk\n- eggs"));
+        assert_eq!(completion_text(&json!({ "choices": [{ "message": { "content": "" } }] })), None);
+        assert_eq!(completion_text(&json!({ "error": "nope" })), None);
+    }
+
+    #[test]
+    fn scope_labels_roundtrip() {
+        for scope in LlmScope::ALL {
+            assert_eq!(LlmScope::from_label(scope.label()), Some(scope));
+        }
+    }
+}
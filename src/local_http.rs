@@ -0,0 +1,121 @@
+// Access checks shared by the localhost HTTP servers (control API and
+// captions). Listening on 127.0.0.1 keeps other machines out but not web
+// pages in the user's browser, which can reach localhost through CSRF or DNS
+// rebinding. So every request must name the server by its loopback address
+// in `Host` (and `Origin`, when sent) and carry the per-install token, either
+// as `Authorization: Bearer <token>` or as `?token=<token>` for OBS browser
+// sources, which can't set headers.
+
+use tiny_http::Request;
+
+/// Why a request was turned away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rejection {
+    pub code: u16,
+    pub reason: &'static str,
+}
+
+/// A fresh random token: 32 hex-encoded bytes from the OS generator.
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).expect("the OS random number generator is unavailable");
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Checks `request` against `port` and `token`.
+pub fn authorize(request: &Request, port: u16, token: &str) -> Result<(), Rejection> {
+    let header = |name: &'static str| {
+        request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv(name))
+            .map(|header| header.value.as_str())
+    };
+    check(header("Host"), header("Origin"), header("Authorization"), request.url(), port, token)
+}
+
+fn check(
+    host: Option<&str>,
+    origin: Option<&str>,
+    authorization: Option<&str>,
+    url: &str,
+    port: u16,
+    token: &str,
+) -> Result<(), Rejection> {
+    let loopback = [format!("127.0.0.1:{}", port), format!("localhost:{}", port)];
+    let forbidden = Rejection {
+        code: 403,
+        reason: "forbidden host or origin",
+    };
+    let host = host.map(str::trim).unwrap_or_default();
+    if !loopback.iter().any(|allowed| allowed.eq_ignore_ascii_case(host)) {
+        return Err(forbidden);
+    }
+    if let Some(origin) = origin {
+        let origin = origin.trim();
+        if !loopback.iter().any(|allowed| format!("http://{}", allowed).eq_ignore_ascii_case(origin)) {
+            return Err(forbidden);
+        }
+    }
+
+    let bearer = authorization.and_then(|value| value.trim().strip_prefix("Bearer ")).map(str::trim);
+    let query = url
+        .split_once('?')
+        .into_iter()
+        .flat_map(|(_, query)| query.split('&'))
+        .find_map(|pair| pair.strip_prefix("token="));
+    let presented = bearer.or(query).unwrap_or_default();
+    if token.is_empty() || !constant_time_eq(presented.as_bytes(), token.as_bytes()) {
+        return Err(Rejection {
+            code: 401,
+            reason: "missing or wrong token",
+        });
+    }
+    Ok(())
+}
+
+/// Compares without returning early, so response times don't leak how much
+/// of a guessed token was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, generate_token};
+
+    #[test]
+    fn requests_need_a_loopback_host_and_the_token() {
+        let token = generate_token();
+        assert_eq!(token.len(), 64);
+        assert_ne!(token, generate_token());
+        let bearer = format!("Bearer {}", token);
+
+        assert!(check(Some("127.0.0.1:8787"), None, Some(&bearer), "/state", 8787, &token).is_ok());
+        assert!(check(Some("localhost:8787"), None, None, &format!("/?token={}", token), 8787, &token).is_ok());
+        assert!(check(
+            Some("localhost:8787"),
+            Some("http://localhost:8787"),
+            None,
+            &format!("/caption?t=1&token={}", token),
+            8787,
+            &token
+        )
+        .is_ok());
+
+        // DNS rebinding: the browser sends the attacker's host name.
+        assert_eq!(check(Some("evil.example:8787"), None, Some(&bearer), "/state", 8787, &token).unwrap_err().code, 403);
+        assert_eq!(check(None, None, Some(&bearer), "/state", 8787, &token).unwrap_err().code, 403);
+        assert_eq!(check(Some("127.0.0.1:9999"), None, Some(&bearer), "/state", 8787, &token).unwrap_err().code, 403);
+        // CSRF from a page on another origin.
+        assert_eq!(
+            check(Some("127.0.0.1:8787"), Some("https://evil.example"), Some(&bearer), "/start", 8787, &token)
+                .unwrap_err()
+                .code,
+            403
+        );
+        assert_eq!(check(Some("127.0.0.1:8787"), None, None, "/start", 8787, &token).unwrap_err().code, 401);
+        assert_eq!(check(Some("127.0.0.1:8787"), None, Some("Bearer nope"), "/start", 8787, &token).unwrap_err().code, 401);
+        assert_eq!(check(Some("127.0.0.1:8787"), None, Some("Bearer "), "/start", 8787, "").unwrap_err().code, 401);
+    }
+}
@@ -0,0 +1,84 @@
+use crate::settings::logs_dir;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, EnvFilter, Registry};
+
+/// Levels offered in the settings dropdown, most to least severe.
+pub const LOG_LEVELS: [&str; 5] = ["error", "warn", "info", "debug", "trace"];
+/// Daily log files kept before the oldest is deleted.
+const MAX_LOG_FILES: usize = 7;
+
+/// Keeps the file writer flushing and lets the level change at runtime.
+pub struct Logging {
+    filter: reload::Handle<EnvFilter, Registry>,
+    _file_guard: Option<WorkerGuard>,
+}
+
+impl Logging {
+    pub fn set_level(&self, level: &str) {
+        if let Err(e) = self.filter.reload(filter_for(level)) {
+            eprintln!("❌ Failed to change log level: {}", e);
+        }
+    }
+}
+
+/// `RUST_LOG` wins over the setting so a one-off debug run needs no config
+/// change. Unknown levels fall back to `info`.
+fn filter_for(level: &str) -> EnvFilter {
+    if let Ok(filter) = EnvFilter::try_from_default_env() {
+        return filter;
+    }
+    let level = level.trim().to_ascii_lowercase();
+    let level = if LOG_LEVELS.contains(&level.as_str()) { level.as_str() } else { "info" };
+    EnvFilter::new(level)
+}
+
+/// Logs to stdout and to a daily rolling file in `logs_dir()`. If the file
+/// can't be opened, console logging still works.
+pub fn init(level: &str) -> Logging {
+    let (filter, handle) = reload::Layer::new(filter_for(level));
+    let file = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix("11th_echo")
+        .filename_suffix("log")
+        .max_log_files(MAX_LOG_FILES)
+        .build(logs_dir());
+    let (file_layer, file_guard) = match file {
+        Ok(appender) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            (Some(fmt::layer().with_writer(writer).with_ansi(false)), Some(guard))
+        }
+        Err(e) => {
+            eprintln!("⚠️ File logging disabled: {}", e);
+            (None, None)
+        }
+    };
+    let result = tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer())
+        .with(file_layer)
+        .try_init();
+    if let Err(e) = result {
+        eprintln!("⚠️ Logging was already initialised: {}", e);
+    }
+    Logging {
+        filter: handle,
+        _file_guard: file_guard,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::filter_for;
+
+    #[test]
+    fn unknown_level_falls_back_to_info() {
+        if std::env::var_os("RUST_LOG").is_some() {
+            return;
+        }
+        assert_eq!(filter_for("DEBUG").to_string(), "debug");
+        assert_eq!(filter_for("loud").to_string(), "info");
+    }
+}
@@ -1,22 +1,78 @@
 mod injector;
+mod error;
 mod audio;
+mod autostart;
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
 mod hotkey;
+mod llm;
+mod logging;
 mod network;
+mod output;
 mod pipeline;
 mod settings;
 mod state;
 mod gemini;
+mod dsp;
+mod replacements;
+mod numbers;
+mod formatters;
+mod processors;
+mod wasm_plugins;
+mod config_watch;
+mod redaction;
+mod commands;
+mod snippets;
+mod control_api;
+mod local_http;
+mod captions;
+mod metrics;
+mod provider;
+mod provider_race;
+mod azure_speech;
+mod google_speech;
+mod openai_whisper;
+mod vosk_speech;
+mod vosk_models;
+mod mock_provider;
+mod session_trace;
+mod session_log;
+mod meeting;
+mod subtitles;
+mod wake_word;
+mod cues;
+mod autosave;
+mod history;
+mod history_search;
+mod retranscribe;
+mod transcript_diff;
+mod transcript_edit;
+mod transcript_panel;
+mod vault;
+mod mic_test;
+mod single_instance;
+mod elevation;
+mod proxy;
+mod translate;
+mod tray;
+mod thread_priority;
+mod i18n;
+mod theme;
 
-use slint::{CloseRequestResponse, Color, ComponentHandle, ModelRc, SharedString, VecModel};
+use slint::{CloseRequestResponse, Color, ComponentHandle, Model, ModelRc, SharedString, VecModel};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
+use error::EchoError;
+use i18n::tr;
 use pipeline::TranscriptPipeline;
 use settings::{load_settings, save_settings};
 use state::RecordingState;
 use tokio::runtime::Runtime;
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
 use arboard::Clipboard;
 use chrono::Local;
+use tracing::{debug, error, info, warn};
 
 #[cfg(target_os = "windows")]
 use global_hotkey::{
@@ -29,40 +85,287 @@ use windows::Win32::UI::Input::KeyboardAndMouse::{
     VK_SPACE, VK_ESCAPE, VK_F1, VK_F2, VK_F3, VK_F4, VK_F5, VK_F6, VK_F7, VK_F8, VK_F9, VK_F10, VK_F11, VK_F12,
 };
 #[cfg(target_os = "windows")]
-use windows::Win32::UI::WindowsAndMessaging::{GetSystemMetrics, SM_CYSCREEN};
-#[cfg(target_os = "windows")]
-use std::{cell::RefCell, rc::Rc};
+use windows::Win32::UI::WindowsAndMessaging::{
+    GetSystemMetrics, SM_CXSCREEN, SM_CXVIRTUALSCREEN, SM_CYSCREEN, SM_CYVIRTUALSCREEN, SM_XVIRTUALSCREEN,
+    SM_YVIRTUALSCREEN,
+};
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
 #[cfg(target_os = "windows")]
 use tray_icon::{
-    menu::{Menu, MenuEvent, MenuItem},
+    menu::{CheckMenuItem, Menu, MenuEvent, MenuId, MenuItem, Submenu},
     MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent,
 };
 
 slint::include_modules!();
 
-const ELEVEN_MODEL_ID: &str = "scribe_v2_realtime";
+/// How often, and how many times, to retry opening a microphone after the
+/// active one disappears mid-session.
+const DEVICE_RECONNECT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+const MAX_DEVICE_RECONNECT_ATTEMPTS: u32 = 30;
+/// How long a status toast stays on screen.
+const TOAST_DURATION: std::time::Duration = std::time::Duration::from_millis(2500);
+/// How long Quit waits for the active session to deliver its final transcript.
+const SHUTDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+/// How long a session started before its key's quota was known can still be
+/// refused once the lookup answers.
+const QUOTA_CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+/// How long a failed quota lookup is remembered before a session start asks
+/// again.
+const QUOTA_RETRY_AFTER: std::time::Duration = std::time::Duration::from_secs(60);
+/// Length of audio captured for one wake word sample (16kHz samples).
+const WAKE_SAMPLE_LEN: usize = 2 * 16000;
+
+/// Tray menu entries, matched against incoming `MenuEvent`s.
+#[cfg(target_os = "windows")]
+struct TrayMenuIds {
+    toggle: tray_icon::menu::MenuId,
+    show: tray_icon::menu::MenuId,
+    folder: tray_icon::menu::MenuId,
+    logs: tray_icon::menu::MenuId,
+    settings: tray_icon::menu::MenuId,
+    mini_bar: tray_icon::menu::MenuId,
+    restart_as_admin: tray_icon::menu::MenuId,
+    quit: tray_icon::menu::MenuId,
+}
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 enum AppCommand {
-    StartRecording,
+    /// Starts a session with the given ElevenLabs realtime model.
+    StartRecording { model_id: String },
     StopRecording,
+    /// Ends the session and discards its transcript instead of injecting it.
+    CancelRecording,
+    PauseRecording,
+    ResumeRecording,
+    /// Sent by the undo hotkey, which is only registered on Windows.
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    UndoLastInjection,
+    /// Re-fetches the ElevenLabs model list for the settings dropdown.
+    RefreshElevenModels,
+    /// Checks the ElevenLabs key and reports plan and remaining quota.
+    ValidateElevenKey,
+    /// Looks up the quota of the configured ElevenLabs key if nothing is known
+    /// about it yet, so a new key is checked before its first session.
+    LookUpQuota,
+    /// Sent when session `session`, started before its key's quota was known,
+    /// turns out to be below the threshold with Refuse set.
+    QuotaExhausted { session: u64, warning: String },
+    /// Finalizes the active session, if any, then quits. Sent by the tray's
+    /// Quit entry, which only exists on Windows.
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    Shutdown,
+    /// Starts or stops listening for the wake word between sessions. Arming
+    /// again restarts the listener with the current samples.
+    ArmWakeWord(bool),
+    /// Turns the next couple of seconds heard by the wake word listener into
+    /// a new sample.
+    RecordWakeWordSample,
+    /// Starts or stops the provider-free microphone test; the flag turns on
+    /// playback through the default output device.
+    TestMicrophone { enabled: bool, loopback: bool },
+    /// Downloads the Vosk runtime, if missing, and a model from the catalog.
+    DownloadVoskModel(vosk_models::VoskModel),
+    /// Runs the recording of saved session `id` through `provider` and keeps
+    /// the result beside its live transcript.
+    RetranscribeHistory { id: usize, provider: provider::ProviderKind },
+    /// Types hand-corrected transcript text into `target`, the last window
+    /// outside the app to have focus, bringing it to the front first.
+    TypeText { text: String, target: Option<injector::TargetWindow> },
+}
+
+impl AppCommand {
+    fn start(settings: &settings::AppSettings) -> Self {
+        AppCommand::StartRecording {
+            model_id: settings.eleven_model_id.clone(),
+        }
+    }
 }
 
 struct Session {
-    state: Arc<Mutex<RecordingState>>,
-    _audio_stream: Option<cpal::Stream>,
+    state: state::SessionState,
+    audio: Option<audio::AudioCapture>,
+    /// Holds the buffered audio while no input device is open.
+    detached_audio: Option<audio::CaptureSink>,
+    reconnect_attempts: u32,
     network_stop_tx: Option<mpsc::UnboundedSender<network::ControlMessage>>,
     transcript_pipeline: Arc<Mutex<TranscriptPipeline>>,
+    /// While set, captured chunks are discarded instead of streamed.
+    paused: Arc<AtomicBool>,
+    metrics: Arc<Mutex<metrics::SessionMetrics>>,
+    /// Audio held back or lost while the provider wasn't keeping up.
+    backpressure: Arc<audio::AudioBackpressure>,
+    audio_format: audio::AudioFormat,
+    /// Handles transcript events; aborted to drop pending text on cancel.
+    transcript_task: tokio::task::JoinHandle<()>,
+    /// Updates the elapsed time and enforces the max session length.
+    timer_task: tokio::task::JoinHandle<()>,
+    /// Crash-safe copy of the finalized text; removed when the session ends.
+    autosave: Arc<Mutex<Option<autosave::TranscriptAutosave>>>,
+    /// System audio transcription running alongside in meeting mode.
+    loopback: Option<meeting::LoopbackSession>,
+    /// The session WAV, if one is being recorded.
+    recording_path: Option<std::path::PathBuf>,
+    /// Committed text on the recording's clock, saved beside the WAV.
+    subtitles: Arc<Mutex<subtitles::SubtitleTrack>>,
+    /// The whole-session LLM result, kept in the history beside the raw text.
+    post_processed: Arc<Mutex<Option<String>>>,
+    cancelled: bool,
 }
 
 impl Session {
-    fn stop_network(&mut self) {
+    /// Sends `message` to the provider session, and to the loopback one in
+    /// meeting mode.
+    fn send_control(&self, message: network::ControlMessage) {
+        if let Some(loopback) = self.loopback.as_ref() {
+            loopback.send(message.clone());
+        }
         if let Some(tx) = self.network_stop_tx.as_ref() {
-            let _ = tx.send(network::ControlMessage::Stop);
+            let _ = tx.send(message);
+        }
+    }
+
+    fn stop_network(&mut self) {
+        self.send_control(network::ControlMessage::Stop);
+    }
+
+    /// Moves the session to finalizing and asks the provider for its final
+    /// commit; the session closes once that transcript arrives.
+    fn request_stop(&mut self) {
+        self.state.update(|s| {
+            if s.can_stop() {
+                s.transition_to_finalizing();
+            }
+        });
+        if let Ok(mut pipeline) = self.transcript_pipeline.lock() {
+            pipeline.request_stop();
+        }
+        self.stop_network();
+    }
+
+    /// Ends the session without a final transcript; the caller still has to
+    /// trigger finalize.
+    fn cancel(&mut self) {
+        self.cancelled = true;
+        self.transcript_task.abort();
+        // Keeps the provider task from sending a second finalize.
+        if let Ok(mut pipeline) = self.transcript_pipeline.lock() {
+            pipeline.request_stop();
         }
+        self.send_control(network::ControlMessage::Cancel);
+    }
+}
+
+/// Microphone listener that runs while the wake word is armed and no
+/// session is active.
+struct WakeListener {
+    _capture: audio::AudioCapture,
+    task: tokio::task::JoinHandle<()>,
+    record_sample: Arc<AtomicBool>,
+}
+
+impl Drop for WakeListener {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn wake_word_samples_text(count: usize) -> String {
+    match count {
+        0 => tr!("No samples yet - record yourself saying \"hey echo\" about three times"),
+        1 => tr!("1 sample recorded"),
+        n => tr!("{} samples recorded", n),
     }
 }
 
+/// Opens the microphone and starts a session whenever one of the recorded
+/// wake word samples is heard.
+fn start_wake_listener(
+    settings: &Arc<Mutex<settings::AppSettings>>,
+    cmd_tx: mpsc::UnboundedSender<AppCommand>,
+    ui_handle: slint::Weak<AppWindow>,
+) -> Result<WakeListener, String> {
+    let (preferred_device, resampler) = {
+        let s = settings.lock().unwrap();
+        (
+            (!s.use_default_microphone).then(|| s.selected_microphone.clone()),
+            s.resampler_quality,
+        )
+    };
+    let dir = settings::wake_word_dir();
+    let mut detector = wake_word::WakeWordDetector::new(&wake_word::load_templates(&dir));
+    let (audio_tx, mut audio_rx) = mpsc::channel::<audio::AudioChunk>(8);
+    // The listener shows no level meter and just stops if the device goes.
+    let (level_tx, _) = mpsc::channel::<f32>(1);
+    let (device_lost_tx, _) = mpsc::unbounded_channel::<()>();
+    let capture = audio::start_audio_capture(
+        audio_tx,
+        level_tx,
+        preferred_device,
+        None,
+        audio::CaptureOptions {
+            resampler,
+            ..audio::CaptureOptions::default()
+        },
+        device_lost_tx,
+    )
+    .map_err(|e| e.to_string())?;
+
+    let record_sample = Arc::new(AtomicBool::new(false));
+    let record_flag = record_sample.clone();
+    let settings = settings.clone();
+    let task = tokio::spawn(async move {
+        let mut sample: Option<Vec<i16>> = None;
+        while let Some(chunk) = audio_rx.recv().await {
+            if record_flag.swap(false, Ordering::SeqCst) {
+                sample = Some(Vec::with_capacity(WAKE_SAMPLE_LEN));
+            }
+            let Some(recorded) = sample.as_mut() else {
+                if detector.push(&chunk) {
+                    info!("👂 Wake word detected, starting dictation");
+                    let _ = cmd_tx.send(AppCommand::start(&settings.lock().unwrap()));
+                }
+                continue;
+            };
+            recorded.extend_from_slice(&chunk);
+            if recorded.len() < WAKE_SAMPLE_LEN {
+                continue;
+            }
+            let recorded = sample.take().unwrap_or_default();
+            let speech = wake_word::trim_silence(&recorded);
+            let status = if speech.len() < wake_word::MIN_TEMPLATE_SAMPLES {
+                tr!("No speech heard - record the wake word sample again")
+            } else {
+                match wake_word::save_template(&dir, speech) {
+                    Ok(path) => {
+                        info!("💾 Saved wake word sample {}", path.display());
+                        detector.add_template(speech);
+                        tr!("Wake word sample saved")
+                    }
+                    Err(e) => {
+                        error!("❌ Failed to save wake word sample: {}", e);
+                        tr!("Could not save the wake word sample: {}", e)
+                    }
+                }
+            };
+            let samples_text = wake_word_samples_text(detector.template_count());
+            let _ = ui_handle.upgrade_in_event_loop(move |ui| {
+                ui.set_status_text(status.into());
+                ui.set_wake_word_samples_text(samples_text.into());
+            });
+        }
+    });
+
+    Ok(WakeListener {
+        _capture: capture,
+        task,
+        record_sample,
+    })
+}
+
 #[cfg(target_os = "windows")]
 fn parse_hotkey(input: &str) -> Result<HotKey, String> {
     use hotkey::HotkeyKey;
@@ -84,6 +387,7 @@ fn parse_hotkey(input: &str) -> Result<HotKey, String> {
 
     let key = match spec.key {
         HotkeyKey::Space => Code::Space,
+        HotkeyKey::Escape => Code::Escape,
         HotkeyKey::Letter('A') => Code::KeyA,
         HotkeyKey::Letter('B') => Code::KeyB,
         HotkeyKey::Letter('C') => Code::KeyC,
@@ -110,7 +414,7 @@ fn parse_hotkey(input: &str) -> Result<HotKey, String> {
         HotkeyKey::Letter('X') => Code::KeyX,
         HotkeyKey::Letter('Y') => Code::KeyY,
         HotkeyKey::Letter('Z') => Code::KeyZ,
-        HotkeyKey::Letter(other) => return Err(format!("Unsupported letter key: {}", other)),
+        HotkeyKey::Letter(other) => return Err(tr!("Unsupported letter key: {}", other)),
         HotkeyKey::Digit(0) => Code::Digit0,
         HotkeyKey::Digit(1) => Code::Digit1,
         HotkeyKey::Digit(2) => Code::Digit2,
@@ -121,7 +425,7 @@ fn parse_hotkey(input: &str) -> Result<HotKey, String> {
         HotkeyKey::Digit(7) => Code::Digit7,
         HotkeyKey::Digit(8) => Code::Digit8,
         HotkeyKey::Digit(9) => Code::Digit9,
-        HotkeyKey::Digit(other) => return Err(format!("Unsupported digit key: {}", other)),
+        HotkeyKey::Digit(other) => return Err(tr!("Unsupported digit key: {}", other)),
         HotkeyKey::Function(1) => Code::F1,
         HotkeyKey::Function(2) => Code::F2,
         HotkeyKey::Function(3) => Code::F3,
@@ -134,38 +438,20 @@ fn parse_hotkey(input: &str) -> Result<HotKey, String> {
         HotkeyKey::Function(10) => Code::F10,
         HotkeyKey::Function(11) => Code::F11,
         HotkeyKey::Function(12) => Code::F12,
-        HotkeyKey::Function(other) => return Err(format!("Unsupported function key: F{}", other)),
+        HotkeyKey::Function(other) => return Err(tr!("Unsupported function key: F{}", other)),
     };
     Ok(HotKey::new(Some(modifiers), key))
 }
 
 #[cfg(target_os = "windows")]
-fn apply_hotkey(
-    manager: &GlobalHotKeyManager,
-    current_hotkey: &mut Option<HotKey>,
-    hotkey_text: &str,
-) -> Result<u32, String> {
-    let new_hotkey = parse_hotkey(hotkey_text)?;
-    let previous = current_hotkey.clone();
-
-    if let Some(existing) = previous.clone() {
-        let _ = manager.unregister(existing);
-    }
-
-    match manager.register(new_hotkey.clone()) {
-        Ok(_) => {
-            let id = new_hotkey.id();
-            *current_hotkey = Some(new_hotkey);
-            Ok(id)
-        }
+fn register_hotkey(manager: &GlobalHotKeyManager, hotkey_text: &str) -> Result<HotKey, String> {
+    let hotkey = parse_hotkey(hotkey_text)?;
+    match manager.register(hotkey.clone()) {
+        Ok(_) => Ok(hotkey),
         Err(err) => {
-            if let Some(existing) = previous {
-                let _ = manager.register(existing.clone());
-                *current_hotkey = Some(existing);
-            }
             let message = err.to_string();
             if message.contains("AlreadyRegistered") {
-                Err("Hotkey is already registered by another application".to_string())
+                Err(tr!("Hotkey is already registered by another application"))
             } else {
                 Err(message)
             }
@@ -173,6 +459,51 @@ fn apply_hotkey(
     }
 }
 
+/// The global hotkeys currently registered from the keybinding table.
+#[cfg(target_os = "windows")]
+struct HotkeyRegistry {
+    manager: GlobalHotKeyManager,
+    registered: Vec<(HotKey, hotkey::KeyBinding)>,
+    /// The table `registered` was built from.
+    bindings: Vec<hotkey::KeyBinding>,
+}
+
+#[cfg(target_os = "windows")]
+impl HotkeyRegistry {
+    /// Registers `bindings` in place of the current set. Unchanged entries
+    /// stay registered, so a held push-to-talk key still sees its release.
+    /// Entries that can't be registered are skipped and returned with the reason.
+    fn apply(&mut self, bindings: &[hotkey::KeyBinding]) -> Vec<(hotkey::KeyBinding, String)> {
+        let (kept, removed): (Vec<_>, Vec<_>) = self
+            .registered
+            .drain(..)
+            .partition(|(_, binding)| bindings.contains(binding));
+        for (existing, _) in removed {
+            let _ = self.manager.unregister(existing);
+        }
+        self.registered = kept;
+        self.bindings = bindings.to_vec();
+        let mut failures = Vec::new();
+        for binding in bindings {
+            if self.registered.iter().any(|(_, registered)| registered == binding) {
+                continue;
+            }
+            match register_hotkey(&self.manager, &binding.keys) {
+                Ok(hotkey) => self.registered.push((hotkey, binding.clone())),
+                Err(err) => failures.push((binding.clone(), err)),
+            }
+        }
+        failures
+    }
+
+    fn action_for(&self, id: u32) -> Option<hotkey::HotkeyAction> {
+        self.registered
+            .iter()
+            .find(|(hotkey, _)| hotkey.id() == id)
+            .map(|(_, binding)| binding.action)
+    }
+}
+
 #[cfg(target_os = "windows")]
 fn vk_down(vk: i32) -> bool {
     unsafe { (GetAsyncKeyState(vk) & 0x8000u16 as i16) != 0 }
@@ -235,225 +566,1743 @@ fn parse_theme_color(s: &str, default: Color) -> Color {
     default
 }
 
-fn overlay_size_for_text(text: &str) -> (i32, i32) {
-    let chars = text.chars().count().max(1);
-    let width = 520;
-    let chars_per_line = 58usize;
-    let lines = chars.div_ceil(chars_per_line).clamp(1, 8) as i32;
-    let height = (70 + (lines * 30)).clamp(110, 260);
-    (width, height)
+/// Rebuilds the tray's Profile submenu with a checked entry for `active`.
+#[cfg(target_os = "windows")]
+fn fill_profile_menu(menu: &Submenu, names: &[String], active: &str) -> Vec<(MenuId, String)> {
+    for item in menu.items() {
+        let _ = menu.remove(item.as_ref());
+    }
+    names
+        .iter()
+        .filter_map(|name| {
+            let item = CheckMenuItem::new(name, true, name == active, None);
+            menu.append(&item).ok()?;
+            Some((item.id().clone(), name.clone()))
+        })
+        .collect()
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    env_logger::init();
-    println!("🦋 11th Echo Rust (Iron Butterfly) Starting...");
+/// Greys out the settings `kind` would ignore.
+fn apply_provider_capabilities(ui: &AppWindow, kind: provider::ProviderKind) {
+    let capabilities = kind.capabilities();
+    ui.set_provider_capabilities_text(capabilities.summary().into());
+    ui.set_provider_supports_diarization(capabilities.diarization);
+    ui.set_provider_supports_language(capabilities.supports_language_choice());
+    ui.set_provider_languages_text(capabilities.languages.join(", ").into());
+}
 
-    let microphones = audio::list_input_devices();
-    let default_microphone =
-        audio::default_input_device_name().unwrap_or_else(|| "Unavailable".to_string());
-    let mut initial_settings = load_settings();
-    if initial_settings.selected_microphone.trim().is_empty() {
-        initial_settings.selected_microphone = if !default_microphone.is_empty() {
-            default_microphone.clone()
-        } else {
-            microphones.first().cloned().unwrap_or_default()
-        };
+/// Pushes the per-profile settings (and the profile list) into the UI after
+/// a profile switch, so the 50ms settings sync doesn't write old values back.
+fn apply_profile_to_ui(ui: &AppWindow, s: &settings::AppSettings) {
+    let names: Vec<SharedString> = s.profile_names().into_iter().map(Into::into).collect();
+    ui.set_profile_options(ModelRc::new(VecModel::from(names)));
+    ui.set_active_profile(s.active_profile.clone().into());
+    ui.set_api_key_text(s.api_key.clone().into());
+    let eleven_models: Vec<SharedString> = eleven_model_options(Vec::new(), &s.eleven_model_id)
+        .into_iter()
+        .map(Into::into)
+        .collect();
+    ui.set_eleven_model_options(ModelRc::new(VecModel::from(eleven_models)));
+    ui.set_selected_eleven_model(s.eleven_model_id.clone().into());
+    ui.set_selected_provider(tr!(s.provider.label()).into());
+    apply_provider_capabilities(ui, s.provider);
+    ui.set_azure_key_text(s.azure_speech_key.clone().into());
+    ui.set_azure_region_text(s.azure_speech_region.clone().into());
+    ui.set_google_key_text(s.google_speech_api_key.clone().into());
+    ui.set_speech_language_text(s.speech_language.clone().into());
+    ui.set_openai_key_text(s.openai_api_key.clone().into());
+    ui.set_openai_model_text(s.openai_transcription_model.clone().into());
+    ui.set_replacements_text(replacements::format_rules(&s.replacement_rules).into());
+    ui.set_format_numbers(s.format_numbers);
+    ui.set_number_locale_text(s.number_locale.clone().into());
+    ui.set_formatters_text(formatters::format_formatters(&s.formatters).into());
+    ui.set_vocabulary_text(settings::format_vocabulary(&s.custom_vocabulary).into());
+    ui.set_injection_delay_text(s.injection_burst_delay_ms.to_string().into());
+    ui.set_injection_chunk_text(s.injection_chunk_size.to_string().into());
+    ui.set_selected_target_lock(tr!(s.target_lock.label()).into());
+    ui.set_transcript_only(s.transcript_only);
+    ui.set_preview_before_inject(s.preview_before_inject);
+}
+
+/// Settings hint under the Vosk model picker.
+fn vosk_model_status(model: &vosk_models::VoskModel) -> String {
+    if vosk_models::is_ready(model, &settings::vosk_models_dir()) {
+        tr!("Downloaded and ready; recognition runs on this computer.")
+    } else {
+        tr!("Not downloaded yet.")
     }
-    if initial_settings.hotkey_text.trim().is_empty() {
-        initial_settings.hotkey_text = "Ctrl+Space".to_string();
+}
+
+/// What is known about one API key's ElevenLabs quota.
+#[derive(Clone)]
+enum QuotaStatus {
+    Known(network::SubscriptionInfo),
+    /// The last lookup failed at this time.
+    Failed(std::time::Instant),
+}
+
+/// Last known ElevenLabs quota per API key, so switching keys never reuses
+/// another account's numbers.
+type QuotaCache = Arc<Mutex<HashMap<String, QuotaStatus>>>;
+
+/// The running session's transcript, if any, read by the control API.
+type LiveTranscript = Arc<Mutex<Option<Arc<Mutex<TranscriptPipeline>>>>>;
+
+/// Asks ElevenLabs for the quota of the configured key and stores it in `cache`.
+async fn fetch_quota(api_key: String, proxy_url: String, cache: QuotaCache) -> Option<network::SubscriptionInfo> {
+    let result = match proxy::resolve(&proxy_url) {
+        Ok(proxy) => network::validate_api_key(&api_key, proxy.as_ref()).await,
+        Err(e) => Err(EchoError::Config(e)),
+    };
+    match result {
+        Ok(info) => {
+            cache.lock().unwrap().insert(api_key, QuotaStatus::Known(info.clone()));
+            Some(info)
+        }
+        Err(e) => {
+            debug!("Could not refresh the ElevenLabs quota: {}", e);
+            cache.lock().unwrap().insert(api_key, QuotaStatus::Failed(std::time::Instant::now()));
+            None
+        }
     }
-    save_settings(&initial_settings);
-    let selected_microphone = initial_settings.selected_microphone.clone();
-    let settings = Arc::new(Mutex::new(initial_settings.clone()));
+}
 
-    #[cfg(target_os = "windows")]
-    let hotkey_text = Arc::new(Mutex::new(initial_settings.hotkey_text.clone()));
+/// Fetches the ElevenLabs quota in the background so the next session start
+/// can be checked against `low_quota_threshold` without waiting on the API.
+fn refresh_quota(settings: &settings::AppSettings, cache: &QuotaCache) {
+    let api_key = settings.api_key.trim().to_string();
+    if settings.low_quota_threshold == 0 || api_key.is_empty() {
+        return;
+    }
+    tokio::spawn(fetch_quota(api_key, settings.proxy_url.clone(), cache.clone()));
+}
 
-    #[cfg(target_os = "windows")]
-    let hotkey_manager = GlobalHotKeyManager::new().unwrap();
-    #[cfg(target_os = "windows")]
-    let hotkey_state = Rc::new(RefCell::new(None::<HotKey>));
-    #[cfg(target_os = "windows")]
-    let hotkey_id_state = Rc::new(RefCell::new(None::<u32>));
+/// Writes the dictation pad to a timestamped file in the transcripts folder.
+fn save_pad_text(text: &str) -> std::io::Result<std::path::PathBuf> {
+    let folder = settings::transcripts_dir();
+    std::fs::create_dir_all(&folder)?;
+    let path = folder.join(format!("dictation-pad-{}.txt", Local::now().format("%Y%m%d-%H%M%S")));
+    std::fs::write(&path, text)?;
+    Ok(path)
+}
 
-    #[cfg(target_os = "windows")]
-    {
-        let startup_hotkey = hotkey_text.lock().unwrap().clone();
-        match apply_hotkey(&hotkey_manager, &mut hotkey_state.borrow_mut(), &startup_hotkey) {
-            Ok(id) => {
-                *hotkey_id_state.borrow_mut() = Some(id);
-            }
-            Err(err) => {
-                eprintln!(
-                    "⚠️ Failed to register global hotkey {}: {}. Continuing without hotkey support.",
-                    startup_hotkey,
-                    err
-                );
-            }
+/// Runs a profile operation on the shared settings: the UI's current values
+/// are captured first, then the result is saved and shown.
+fn update_profiles(
+    ui: &AppWindow,
+    settings: &Mutex<settings::AppSettings>,
+    op: impl FnOnce(&mut settings::AppSettings) -> bool,
+) -> bool {
+    let snapshot = {
+        let mut current = settings.lock().unwrap();
+        read_settings_from_ui(ui, &mut current);
+        if let Ok(rules) = replacements::parse_rules(&ui.get_replacements_text()) {
+            current.replacement_rules = rules;
+        }
+        if let Ok(kinds) = formatters::parse_formatters(&ui.get_formatters_text()) {
+            current.formatters = kinds;
+        }
+        if !op(&mut current) {
+            return false;
+        }
+        current.clone()
+    };
+    save_settings(&snapshot);
+    apply_profile_to_ui(ui, &snapshot);
+    ui.set_status_text(tr!("Profile: {}", snapshot.active_profile).into());
+    true
+}
+
+/// Runs LLM post-processing, keeping `text` unchanged if the request fails.
+async fn post_process_or_keep(config: &llm::LlmConfig, text: String, log_tx: &mpsc::UnboundedSender<String>) -> String {
+    info!("🤖 [LLM] Post-processing {} chars with {}", text.len(), config.model);
+    match llm::post_process(config, &text).await {
+        Ok(processed) => {
+            let _ = log_tx.send(format!("🤖 [LLM] \"{}\" -> \"{}\"", text, processed));
+            processed
+        }
+        Err(e) => {
+            error!("❌ LLM post-processing failed: {}", e);
+            let _ = log_tx.send(format!("❌ [LLM] {} - keeping the original text", e));
+            text
         }
     }
+}
 
-    #[cfg(target_os = "windows")]
-    let (quit_item_id, settings_item_id, _tray_icon) = {
-        let tray_menu = Menu::new();
-        let settings_item = MenuItem::new("Settings Tab", true, None);
-        let quit_item = MenuItem::new("Quit", true, None);
-        tray_menu.append_items(&[&settings_item, &quit_item])?;
+/// Live partial injection: brings the text typed so far up to date with
+/// `target`, touching only the part that changed. Returns Ok(false) when the
+/// target lock says focus has moved away.
+async fn sync_live_text(
+    typed: &str,
+    target: &str,
+    injection_target: Option<(injector::TargetWindow, injector::TargetLock)>,
+    options: injector::InjectionOptions,
+) -> error::EchoResult<bool> {
+    if typed == target {
+        return Ok(true);
+    }
+    let mut keyboard = output::KeyboardSink::new(injection_target, options, typed.to_string());
+    let target = target.to_string();
+    tokio::task::spawn_blocking(move || keyboard.sync(&target))
+        .await
+        .unwrap_or_else(|e| Err(EchoError::injection(e.to_string())))
+}
 
-        let icon = tray_icon::Icon::from_path("eleventhecho.png", None).or_else(|png_err| {
-            eprintln!(
-                "⚠️ Tray icon PNG load failed ({}), trying ICO fallback.",
-                png_err
-            );
-            tray_icon::Icon::from_path("eleventhecho.ico", None)
-        })?;
-        let tray = TrayIconBuilder::new()
-            .with_menu(Box::new(tray_menu))
-            .with_tooltip("11th Echo")
-            .with_icon(icon)
-            .build()?;
-        (quit_item.id().clone(), settings_item.id().clone(), Some(tray))
+/// Where the preview window's Inject/Discard answer goes.
+type PreviewReply = Arc<Mutex<Option<tokio::sync::oneshot::Sender<Option<String>>>>>;
+
+/// Shows `text` in the injection preview and waits for the user. Returns the
+/// (possibly edited) text to inject, or None if it was discarded.
+async fn confirm_injection(
+    preview: &slint::Weak<InjectionPreviewWindow>,
+    reply: &PreviewReply,
+    text: String,
+) -> Option<String> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    // A preview still open from an earlier session counts as discarded.
+    *reply.lock().unwrap() = Some(tx);
+    let _ = preview.upgrade_in_event_loop(move |preview| {
+        preview.set_pending_text(text.into());
+        preview.set_editing(false);
+        let _ = preview.show();
+    });
+    rx.await.ok().flatten()
+}
+
+/// Runs a recognized voice command off the async runtime, since key presses
+/// block while they are sent.
+async fn run_voice_command(command: commands::VoiceCommand, log_tx: &mpsc::UnboundedSender<String>) {
+    info!("🗣️ Voice command: {}", command.phrase);
+    let action = command.action.clone();
+    match tokio::task::spawn_blocking(move || action.run()).await {
+        Ok(Ok(())) => {
+            let _ = log_tx.send(format!("🗣️ [COMMAND] {}", command.phrase));
+        }
+        Ok(Err(e)) => {
+            error!("❌ Voice command \"{}\" failed: {}", command.phrase, e);
+            let _ = log_tx.send(format!("❌ [COMMAND] {}: {}", command.phrase, e.user_message()));
+        }
+        Err(e) => error!("❌ Voice command task failed: {}", e),
+    }
+}
+
+/// Dropdown entries: `fetched` (or the built-in list when empty), plus the
+/// saved model so a selection never disappears.
+fn eleven_model_options(fetched: Vec<String>, selected: &str) -> Vec<String> {
+    let mut options = if fetched.is_empty() {
+        network::KNOWN_REALTIME_MODELS.iter().map(|id| id.to_string()).collect()
+    } else {
+        fetched
     };
+    if !selected.is_empty() && !options.iter().any(|id| id == selected) {
+        options.push(selected.to_string());
+    }
+    options
+}
 
-    let (cmd_tx, mut cmd_rx) = mpsc::unbounded_channel::<AppCommand>();
-    let (level_tx, mut level_rx) = mpsc::channel::<f32>(10);
+/// Shows `message` in the toast window and hides it after `TOAST_DURATION`,
+/// unless a newer toast replaced it in the meantime. UI thread only.
+fn show_toast(toast: &ToastWindow, message: &str, is_error: bool, generation: &Rc<Cell<u64>>) {
+    toast.set_message(message.into());
+    toast.set_is_error(is_error);
+    let _ = toast.show();
+    let id = generation.get() + 1;
+    generation.set(id);
+    let generation = generation.clone();
+    let toast = toast.as_weak();
+    slint::Timer::single_shot(TOAST_DURATION, move || {
+        if generation.get() == id {
+            if let Some(toast) = toast.upgrade() {
+                let _ = toast.hide();
+            }
+        }
+    });
+}
 
-    let ui = AppWindow::new()?;
-    ui.set_active_tab(0);
-    ui.set_status_text("Idle".into());
-    ui.set_is_recording(false);
+/// Puts the main window back where it was when the app last closed. The
+/// size always comes back; the position only if it is still on a screen.
+fn restore_window_geometry(ui: &AppWindow, geometry: settings::WindowGeometry) {
+    let window = ui.window();
+    window.set_size(slint::PhysicalSize::new(geometry.width, geometry.height));
     #[cfg(target_os = "windows")]
-    ui.set_hotkey_text(hotkey_text.lock().unwrap().clone().into());
+    let on_screen = unsafe {
+        geometry.is_on_screen(
+            GetSystemMetrics(SM_XVIRTUALSCREEN),
+            GetSystemMetrics(SM_YVIRTUALSCREEN),
+            GetSystemMetrics(SM_CXVIRTUALSCREEN),
+            GetSystemMetrics(SM_CYVIRTUALSCREEN),
+        )
+    };
     #[cfg(not(target_os = "windows"))]
-    ui.set_hotkey_text("Unavailable".into());
-    ui.set_api_key_text(initial_settings.api_key.clone().into());
-    ui.set_gemini_api_key_text(initial_settings.gemini_api_key.clone().into());
-    ui.set_selected_microphone(selected_microphone.clone().into());
-    ui.set_use_default_microphone(initial_settings.use_default_microphone);
-    ui.set_default_microphone_text(default_microphone.clone().into());
-    ui.set_microphone_options(ModelRc::new(VecModel::from(
-        microphones
-            .iter()
-            .cloned()
-            .map(SharedString::from)
-            .collect::<Vec<SharedString>>(),
-    )));
+    let on_screen = true;
+    if on_screen {
+        window.set_position(slint::PhysicalPosition::new(geometry.x, geometry.y));
+    }
+    window.set_maximized(geometry.maximized);
+}
 
-    let gemini_preset_options: Vec<SharedString> = vec![
-        "Minimal corrections".into(),
-        "Sound like a pirate".into(),
-        "Sound like a medieval knight".into(),
-        "Custom".into(),
-    ];
-    ui.set_gemini_preset_options(ModelRc::new(VecModel::from(gemini_preset_options)));
-    ui.set_selected_gemini_preset(initial_settings.gemini_prompt_preset.clone().into());
-    ui.set_gemini_custom_prompt(initial_settings.gemini_custom_prompt.clone().into());
-    ui.set_gemini_model_text(initial_settings.gemini_model.clone().into());
-    ui.set_use_gemini_modifier(initial_settings.gemini_enabled);
+/// The main window's current placement, keeping the saved size and position
+/// while it is maximized. `None` while it is hidden or minimized.
+fn capture_window_geometry(ui: &AppWindow, last: Option<settings::WindowGeometry>) -> Option<settings::WindowGeometry> {
+    let window = ui.window();
+    if !window.is_visible() || window.is_minimized() {
+        return None;
+    }
+    if window.is_maximized() {
+        return last.map(|geometry| settings::WindowGeometry { maximized: true, ..geometry });
+    }
+    let position = window.position();
+    let size = window.size();
+    Some(settings::WindowGeometry {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+        maximized: false,
+    })
+}
+
+/// Writes the window state tracked in `current` (geometry, open tab, mini
+/// bar) to the settings file without saving unapplied edits along with it.
+fn save_window_state(current: &settings::AppSettings) {
+    let mut saved = load_settings();
+    saved.window_geometry = current.window_geometry;
+    saved.active_tab = current.active_tab;
+    saved.mini_bar = current.mini_bar;
+    save_settings(&saved);
+}
+
+/// The global (not per-profile) text processing settings.
+fn apply_text_processing_to_ui(ui: &AppWindow, s: &settings::AppSettings) {
+    ui.set_filter_profanity(s.filter_profanity);
+    ui.set_redact_sensitive_data(s.redact_sensitive_data);
+    ui.set_redaction_patterns_text(redaction::format_patterns(&s.redaction_patterns).into());
+    ui.set_voice_commands_enabled(s.voice_commands_enabled);
+    ui.set_voice_command_prefix_text(s.voice_command_prefix.clone().into());
+    ui.set_voice_commands_text(commands::format_commands(&s.voice_commands).into());
+    ui.set_snippets_text(snippets::format_snippets(&s.snippets).into());
+    ui.set_processors_text(processors::format_steps(&s.processors).into());
+}
+
+/// Applies a settings file that changed outside the app. Hotkeys follow on
+/// the next timer tick, which re-registers whatever the settings hold.
+fn reload_settings(ui: &AppWindow, settings: &Mutex<settings::AppSettings>) {
+    let loaded = match settings::read_settings_from_path(&settings::settings_path()) {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            warn!("⚠️ Ignoring the changed settings file: {}", err);
+            ui.set_status_text(tr!("Settings file not reloaded: {}", err).into());
+            return;
+        }
+    };
+    let changes = {
+        let mut current = settings.lock().unwrap();
+        let changes = config_watch::describe_changes(&current, &loaded);
+        if !changes.is_empty() {
+            *current = loaded.clone();
+        }
+        changes
+    };
+    if changes.is_empty() {
+        return;
+    }
+    apply_settings_to_ui(ui, &loaded);
+    info!("🔄 Settings reloaded from disk: {}", changes.join(", "));
+    let changed: Vec<String> = changes.iter().map(|change| tr!(change)).collect();
+    ui.set_status_text(tr!("Settings file changed - reloaded {}", changed.join(", ")).into());
+}
 
-    ui.set_overlay_opacity(initial_settings.overlay_opacity);
+/// Shows every UI-backed setting in the window: at startup, and when the
+/// settings file changes on disk, so the 50ms settings sync reads back the
+/// new values instead of writing the old ones over them.
+fn apply_settings_to_ui(ui: &AppWindow, s: &settings::AppSettings) {
+    i18n::apply(s.ui_language);
+    apply_option_labels(ui);
+    apply_keybindings_to_ui(ui, s);
+    ui.set_gemini_api_key_text(s.gemini_api_key.clone().into());
+    ui.set_selected_microphone(s.selected_microphone.clone().into());
+    ui.set_use_default_microphone(s.use_default_microphone);
+    ui.set_proxy_url_text(s.proxy_url.clone().into());
+    ui.set_vosk_model_status(vosk_model_status(&selected_vosk_model(s)).into());
+    ui.set_meeting_mode(s.meeting_mode);
+    ui.set_gemini_custom_prompt(s.gemini_custom_prompt.clone().into());
+    ui.set_gemini_model_text(s.gemini_model.clone().into());
+    ui.set_use_gemini_modifier(s.gemini_enabled);
+    ui.set_record_session_audio(s.record_session_audio);
+    ui.set_record_session_trace(s.record_session_trace);
+    ui.set_race_eleven_model_text(s.race_eleven_model_id.clone().into());
+    ui.set_wake_word_armed(s.wake_word_armed);
+    ui.set_noise_suppression(s.noise_suppression);
+    ui.set_boost_audio_thread(s.boost_audio_thread);
+    ui.set_auto_stop_text(s.auto_stop_silence_secs.to_string().into());
+    ui.set_paragraph_pause_text(s.paragraph_pause_secs.to_string().into());
+    ui.set_max_session_text(s.max_session_minutes.to_string().into());
+    ui.set_low_quota_text(s.low_quota_threshold.to_string().into());
+    ui.set_preconnect_text(s.preconnect_buffer_secs.to_string().into());
+    ui.set_low_confidence_text(format!("{:.0}", s.low_confidence_threshold * 100.0).into());
+    ui.set_skip_low_confidence_injection(s.skip_low_confidence_injection);
+    ui.set_dictation_pad(s.dictation_pad);
+    ui.set_live_partial_injection(s.live_partial_injection);
+    let sink_enabled = |kind| s.output_sinks.contains(&kind);
+    ui.set_output_keyboard(sink_enabled(output::OutputSinkKind::Keyboard));
+    ui.set_output_clipboard(sink_enabled(output::OutputSinkKind::Clipboard));
+    ui.set_output_file(sink_enabled(output::OutputSinkKind::File));
+    ui.set_output_stdout(sink_enabled(output::OutputSinkKind::Stdout));
+    ui.set_output_webhook(sink_enabled(output::OutputSinkKind::Webhook));
+    ui.set_webhook_url_text(s.webhook_url.clone().into());
+    ui.set_output_markdown(sink_enabled(output::OutputSinkKind::Markdown));
+    ui.set_markdown_note_text(s.markdown_note_path.clone().into());
+    ui.set_markdown_note_placeholder(s.markdown_note_template().into());
+    ui.set_output_file_text(s.output_file_path.clone().into());
+    ui.set_output_file_placeholder(s.output_file().display().to_string().into());
+    ui.set_autosave_transcripts(s.autosave_transcripts);
+    ui.set_keep_history(s.keep_history);
+    ui.set_encrypt_transcripts(s.encrypt_transcripts);
+    ui.set_show_toasts(s.show_toasts);
+    ui.set_diarization_enabled(s.diarization_enabled);
+    ui.set_strip_speaker_labels(s.strip_speaker_labels);
+    apply_text_processing_to_ui(ui, s);
+    ui.set_cue_on_start(s.cue_on_start);
+    ui.set_cue_on_stop(s.cue_on_stop);
+    ui.set_cue_on_error(s.cue_on_error);
+    ui.set_cue_volume(s.cue_volume);
+    ui.set_start_hidden(s.start_hidden);
+    ui.set_selected_log_level(s.log_level.clone().into());
+    ui.set_llm_enabled(s.llm_enabled);
+    ui.set_llm_endpoint_text(s.llm_endpoint.clone().into());
+    ui.set_llm_api_key_text(s.llm_api_key.clone().into());
+    ui.set_llm_model_text(s.llm_model.clone().into());
+    ui.set_llm_prompt_text(s.llm_prompt.clone().into());
+    ui.set_llm_timeout_text(s.llm_timeout_secs.to_string().into());
+    ui.set_translation_enabled(s.translation_enabled);
+    ui.set_deepl_api_key_text(s.deepl_api_key.clone().into());
+    ui.set_translation_source_text(s.translation_source_lang.clone().into());
+    ui.set_translation_target_text(s.translation_target_lang.clone().into());
+    ui.set_launch_at_login(s.launch_at_login);
+    ui.set_control_api_enabled(s.control_api_enabled);
+    ui.set_control_api_port_text(s.control_api_port.to_string().into());
+    ui.set_captions_enabled(s.captions_enabled);
+    ui.set_captions_port_text(s.captions_port.to_string().into());
+    ui.set_local_api_token(s.local_api_token.clone().into());
+    apply_profile_to_ui(ui, s);
+    apply_option_selections(ui, s);
+    ui.set_overlay_opacity(s.overlay_opacity);
     ui.set_theme_background_top_color(parse_theme_color(
-        &initial_settings.theme_background_top_color,
+        &s.theme_background_top_color,
         Color::from_rgb_u8(2, 20, 11),
     ));
     ui.set_theme_background_bottom_color(parse_theme_color(
-        &initial_settings.theme_background_bottom_color,
+        &s.theme_background_bottom_color,
         Color::from_rgb_u8(0, 8, 6),
     ));
     ui.set_theme_window_color(parse_theme_color(
-        &initial_settings.theme_window_color,
+        &s.theme_window_color,
         Color::from_rgb_u8(4, 27, 17),
     ));
     ui.set_theme_button_accent_color(parse_theme_color(
-        &initial_settings.theme_button_accent_color,
+        &s.theme_button_accent_color,
         Color::from_rgb_u8(74, 222, 128),
     ));
     ui.set_theme_title_color(parse_theme_color(
-        &initial_settings.theme_title_color,
+        &s.theme_title_color,
         Color::from_rgb_u8(228, 255, 233),
     ));
     ui.set_theme_text_color(parse_theme_color(
-        &initial_settings.theme_text_color,
+        &s.theme_text_color,
         Color::from_rgb_u8(204, 239, 214),
     ));
-    ui.set_transcript_history(ModelRc::new(VecModel::from(Vec::<SharedString>::new())));
-    ui.set_log_items(ModelRc::new(VecModel::from(Vec::<SharedString>::new())));
+    ui.set_overlay_background_color(parse_theme_color(&s.overlay_background_color, Color::from_rgb_u8(3, 21, 12)));
+    ui.set_overlay_text_color(parse_theme_color(&s.overlay_text_color, Color::from_rgb_u8(230, 255, 240)));
+}
 
-    // When the user closes the main window, hide it but keep the Slint
-    // event loop alive so the app can continue running from the tray.
-    let ui_weak_for_close = ui.as_weak();
-    ui.window().on_close_requested(move || {
-        if let Some(ui) = ui_weak_for_close.upgrade() {
-            let _ = ui.window().hide();
+/// The combo box entries that come from Rust, in the UI language.
+fn apply_option_labels(ui: &AppWindow) {
+    ui.set_capture_source_options(option_model(audio::CaptureSource::ALL.iter().map(|source| tr!(source.label()))));
+    ui.set_resampler_quality_options(option_model(
+        audio::ResamplerQuality::ALL.iter().map(|quality| tr!(quality.label())),
+    ));
+    ui.set_audio_format_options(option_model(audio::AudioFormat::ALL.iter().map(|format| format.label())));
+    ui.set_provider_options(option_model(provider::ProviderKind::ALL.iter().map(|kind| tr!(kind.label()))));
+    ui.set_vosk_model_options(option_model(vosk_models::CATALOG.iter().map(|model| model.label())));
+    ui.set_gemini_preset_options(option_model(gemini::PRESETS.iter().map(|preset| tr!(preset))));
+    ui.set_race_provider_options(option_model(
+        std::iter::once("Off")
+            .chain(provider::ProviderKind::ALL.iter().map(|kind| kind.label()))
+            .map(|label| tr!(label)),
+    ));
+    ui.set_max_session_action_options(option_model(
+        metrics::MaxDurationAction::ALL.iter().map(|action| tr!(action.label())),
+    ));
+    ui.set_low_quota_action_options(option_model(network::LowQuotaAction::ALL.iter().map(|action| tr!(action.label()))));
+    ui.set_redaction_mode_options(option_model(redaction::RedactionMode::ALL.iter().map(|mode| tr!(mode.label()))));
+    ui.set_llm_scope_options(option_model(llm::LlmScope::ALL.iter().map(|scope| tr!(scope.label()))));
+    ui.set_translation_backend_options(option_model(
+        translate::TranslationBackend::ALL.iter().map(|backend| tr!(backend.label())),
+    ));
+    ui.set_ui_language_options(option_model(i18n::UiLanguage::ALL.iter().map(|language| tr!(language.label()))));
+    ui.set_theme_mode_options(option_model(theme::ThemeMode::ALL.iter().map(|mode| tr!(mode.label()))));
+    ui.set_transcript_retention_options(option_model(
+        transcript_panel::TranscriptRetention::ALL.iter().map(|retention| tr!(retention.label())),
+    ));
+    ui.set_target_lock_options(option_model(injector::TargetLock::ALL.iter().map(|lock| tr!(lock.label()))));
+}
+
+fn option_model(labels: impl Iterator<Item = String>) -> ModelRc<SharedString> {
+    ModelRc::new(VecModel::from(labels.map(SharedString::from).collect::<Vec<SharedString>>()))
+}
+
+/// Selects `s`'s values in the combo boxes `apply_option_labels` fills.
+fn apply_option_selections(ui: &AppWindow, s: &settings::AppSettings) {
+    ui.set_selected_capture_source(tr!(s.capture_source.label()).into());
+    ui.set_selected_resampler_quality(tr!(s.resampler_quality.label()).into());
+    ui.set_selected_audio_format(s.audio_format.label().into());
+    ui.set_selected_provider(tr!(s.provider.label()).into());
+    ui.set_selected_vosk_model(selected_vosk_model(s).label().into());
+    ui.set_selected_gemini_preset(tr!(&s.gemini_prompt_preset).into());
+    ui.set_race_provider(tr!(s.race_provider.map_or("Off", |kind| kind.label())).into());
+    ui.set_selected_max_session_action(tr!(s.max_session_action.label()).into());
+    ui.set_selected_low_quota_action(tr!(s.low_quota_action.label()).into());
+    ui.set_selected_redaction_mode(tr!(s.redaction_mode.label()).into());
+    ui.set_selected_llm_scope(tr!(s.llm_scope.label()).into());
+    ui.set_selected_translation_backend(tr!(s.translation_backend.label()).into());
+    ui.set_selected_ui_language(tr!(s.ui_language.label()).into());
+    ui.set_theme_mode(tr!(s.theme_mode.label()).into());
+    ui.set_selected_transcript_retention(tr!(s.transcript_retention.label()).into());
+    ui.set_selected_target_lock(tr!(s.target_lock.label()).into());
+}
+
+fn selected_vosk_model(s: &settings::AppSettings) -> vosk_models::VoskModel {
+    vosk_models::VoskModel::from_name(&s.vosk_model).unwrap_or(vosk_models::CATALOG[0])
+}
+
+/// Switches the UI to `language`. @tr bindings follow by themselves; the
+/// text set from Rust is set again. `s` holds what the window shows.
+fn switch_language(
+    ui: &AppWindow,
+    language: i18n::UiLanguage,
+    s: &settings::AppSettings,
+    history: &history_search::HistoryIndex,
+) {
+    let status = i18n::source_text(&ui.get_status_text());
+    let api_key_status = i18n::source_text(&ui.get_api_key_status_text());
+    let default_microphone = i18n::source_text(&ui.get_default_microphone_text());
+    let session_stats = i18n::source_text(&ui.get_session_stats_text());
+    let latency = i18n::source_text(&ui.get_latency_text());
+    let all_tags = i18n::is_label(ALL_TAGS, &ui.get_history_tag_filter());
+
+    i18n::apply(language);
+    apply_option_labels(ui);
+    apply_option_selections(ui, s);
+    ui.set_status_text(tr!(&status).into());
+    ui.set_api_key_status_text(tr!(&api_key_status).into());
+    ui.set_default_microphone_text(tr!(&default_microphone).into());
+    ui.set_session_stats_text(tr!(&session_stats).into());
+    ui.set_latency_text(tr!(&latency).into());
+    apply_hotkey_labels(ui, s);
+    apply_provider_capabilities(ui, s.provider);
+    if !ui.get_vosk_downloading() {
+        ui.set_vosk_model_status(vosk_model_status(&selected_vosk_model(s)).into());
+    }
+    ui.set_wake_word_samples_text(
+        wake_word_samples_text(wake_word::load_templates(&settings::wake_word_dir()).len()).into(),
+    );
+    let monthly_totals = metrics::load_monthly_totals().for_month(&metrics::current_month());
+    ui.set_monthly_stats_text(monthly_totals.summary().into());
+    if all_tags {
+        ui.set_history_tag_filter(tr!(ALL_TAGS).into());
+    }
+    refresh_history(ui, history);
+    if let Some(entry) = usize::try_from(ui.get_history_selected()).ok().and_then(|id| history.get(id)) {
+        ui.set_history_detail_versions(version_labels(&entry.versions()));
+        if ui.get_history_comparing() {
+            show_history_diff(ui, entry);
         }
-        CloseRequestResponse::KeepWindowShown
-    });
+    }
+}
 
-    #[cfg(target_os = "windows")]
-    let hotkey_capture_window = HotkeyCaptureWindow::new()?;
-    #[cfg(target_os = "windows")]
-    hotkey_capture_window.set_state_text("Waiting for key combo...".into());
-    #[cfg(target_os = "windows")]
-    hotkey_capture_window.set_combo_text("".into());
-    #[cfg(target_os = "windows")]
-    let hotkey_capture_active = Rc::new(RefCell::new(false));
-    #[cfg(target_os = "windows")]
-    let hotkey_capture_latched = Rc::new(RefCell::new(false));
+/// Main-tab labels for the toggle and pause bindings, plus the bindings table.
+fn apply_keybindings_to_ui(ui: &AppWindow, s: &settings::AppSettings) {
+    apply_hotkey_labels(ui, s);
+    ui.set_keybindings_text(hotkey::format_keybindings(&s.keybindings).into());
+}
 
-    let transcript_overlay = TranscriptOverlayWindow::new()?;
-    transcript_overlay.set_sentence_text("".into());
-    transcript_overlay.set_window_width(520);
-    transcript_overlay.set_window_height(120);
-    transcript_overlay.set_overlay_opacity(initial_settings.overlay_opacity);
-    transcript_overlay.set_overlay_background_color(parse_theme_color(
-        &initial_settings.overlay_background_color,
-        Color::from_rgb_u8(3, 21, 12),
-    ));
-    transcript_overlay.set_overlay_text_color(parse_theme_color(
-        &initial_settings.overlay_text_color,
-        Color::from_rgb_u8(230, 255, 240),
-    ));
-    transcript_overlay.set_is_error(false);
+fn apply_hotkey_labels(ui: &AppWindow, s: &settings::AppSettings) {
     #[cfg(target_os = "windows")]
-    {
-        let screen_h = unsafe { GetSystemMetrics(SM_CYSCREEN) };
-        let overlay_h = 120;
-        let margin = 24;
-        transcript_overlay.window().set_position(slint::PhysicalPosition::new(margin, (screen_h - overlay_h - margin).max(0)));
-    }
+    ui.set_hotkey_text(s.binding(hotkey::HotkeyAction::ToggleDictation).map_or_else(|| tr!("Unbound"), str::to_string).into());
     #[cfg(not(target_os = "windows"))]
-    {
-        transcript_overlay.window().set_position(slint::LogicalPosition::new(24.0, 820.0));
+    ui.set_hotkey_text(tr!("Unavailable").into());
+    ui.set_pause_hotkey_text(s.binding(hotkey::HotkeyAction::PauseResume).map_or_else(|| tr!("Unbound"), str::to_string).into());
+}
+
+/// The vault to seal transcript files with: None when encryption is off, an
+/// error when it is on but the keychain can't be used.
+fn transcript_vault(encrypt: bool) -> Result<Option<&'static vault::Vault>, EchoError> {
+    if !encrypt {
+        return Ok(None);
     }
+    vault::Vault::global()
+        .map(Some)
+        .ok_or_else(|| EchoError::config("the system keychain is unavailable, so transcripts can't be encrypted"))
+}
 
-    let overlay_weak_for_drag = transcript_overlay.as_weak();
-    transcript_overlay.on_move_window(move |dx, dy| {
-        if let Some(overlay) = overlay_weak_for_drag.upgrade() {
-            let current = overlay.window().position();
-            let scale = overlay.window().scale_factor();
-            overlay.window().set_position(slint::PhysicalPosition::new(
-                current.x + (dx as f32 * scale) as i32,
-                current.y + (dy as f32 * scale) as i32,
-            ));
+/// The History tab's tag filter entry that doesn't filter.
+const ALL_TAGS: &str = "All tags";
+
+/// The History tab's search box, date fields and tag filter.
+fn history_query_from_ui(ui: &AppWindow) -> Result<history_search::HistoryQuery, String> {
+    let tag = ui.get_history_tag_filter();
+    Ok(history_search::HistoryQuery {
+        text: ui.get_history_query().to_string(),
+        from: history_search::parse_date(&ui.get_history_from_text())?,
+        to: history_search::parse_date(&ui.get_history_to_text())?,
+        tag: (!tag.is_empty() && !i18n::is_label(ALL_TAGS, &tag)).then(|| tag.to_string()),
+    })
+}
+
+/// Runs the History tab's search and filters and shows the matching
+/// sessions, newest first.
+fn refresh_history(ui: &AppWindow, index: &history_search::HistoryIndex) {
+    let mut tag_options = vec![SharedString::from(tr!(ALL_TAGS))];
+    tag_options.extend(index.tags().unwrap_or_default().into_iter().map(SharedString::from));
+    ui.set_history_tag_options(ModelRc::new(VecModel::from(tag_options)));
+    let query = match history_query_from_ui(ui) {
+        Ok(query) => query,
+        Err(err) => {
+            ui.set_history_search_error(err.into());
+            return;
         }
+    };
+    let hits = match index.search(&query) {
+        Ok(hits) => hits,
+        Err(e) => {
+            ui.set_history_search_error(tr!("Search failed: {}", e).into());
+            return;
+        }
+    };
+    let items: Vec<HistoryItem> = hits
+        .into_iter()
+        .filter_map(|hit| {
+            let entry = index.get(hit.id)?;
+            Some(HistoryItem {
+                id: hit.id as i32,
+                title: entry.title().into(),
+                tags: history::format_tags(&entry.tags).into(),
+                text: hit.excerpt.into(),
+            })
+        })
+        .collect();
+    ui.set_history_search_error(if items.is_empty() && !query.is_empty() {
+        tr!("No sessions match").into()
+    } else {
+        "".into()
     });
+    ui.set_history_items(ModelRc::new(VecModel::from(items)));
+}
 
+/// The Transcription tab's rows. main() installs one VecModel, so segments
+/// are added and updated in place rather than rebuilding the whole list.
+fn with_transcript_rows(ui: &AppWindow, update: impl FnOnce(&VecModel<TranscriptSegment>)) {
+    let rows = ui.get_transcript_segments();
+    if let Some(rows) = rows.as_any().downcast_ref::<VecModel<TranscriptSegment>>() {
+        update(rows);
+    }
+}
+
+/// Rows for committed list lines, e.g. after a hand edit.
+fn transcript_rows(display: &[String]) -> Vec<TranscriptSegment> {
+    display
+        .iter()
+        .map(|line| {
+            let (time, text) = transcript_edit::split_timestamp(line);
+            TranscriptSegment {
+                time: time.unwrap_or_default().into(),
+                text: text.into(),
+                is_final: true,
+            }
+        })
+        .collect()
+}
+
+/// Removes the row of words still being recognized, if there is one.
+fn drop_partial_row(rows: &VecModel<TranscriptSegment>) {
+    if let Some(last) = rows.row_count().checked_sub(1) {
+        if rows.row_data(last).is_some_and(|row| !row.is_final) {
+            rows.remove(last);
+        }
+    }
+}
+
+/// Shows the words recognized so far as the last row, updating it in place.
+fn show_partial_row(rows: &VecModel<TranscriptSegment>, time: &str, text: &str) {
+    let text = text.trim();
+    if text.is_empty() {
+        drop_partial_row(rows);
+        return;
+    }
+    let row = TranscriptSegment {
+        time: time.into(),
+        text: text.into(),
+        is_final: false,
+    };
+    match rows.row_count().checked_sub(1) {
+        Some(last) if rows.row_data(last).is_some_and(|row| !row.is_final) => rows.set_row_data(last, row),
+        _ => rows.push(row),
+    }
+}
+
+/// Opens session `id` in the History detail pane with transcript `version`
+/// (0 is the live one) on screen.
+fn show_history_entry(ui: &AppWindow, id: i32, entry: &history::HistoryEntry, version: usize) {
+    let versions = entry.versions();
+    let version = version.min(versions.len() - 1);
+    ui.set_history_selected(id);
+    ui.set_history_detail_title(entry.title().into());
+    ui.set_history_detail_text(versions[version].1.into());
+    ui.set_history_detail_versions(version_labels(&versions));
+    ui.set_history_detail_version(version as i32);
+    // Compare against the live transcript, or the newest one from it.
+    ui.set_history_compare_version(if version == 0 { versions.len() as i32 - 1 } else { 0 });
+    if versions.len() < 2 {
+        ui.set_history_comparing(false);
+    }
+    ui.set_history_detail_has_recording(entry.recording.as_ref().is_some_and(|path| path.exists()));
+    ui.set_history_detail_tags(history::format_tags(&entry.tags).into());
+    ui.set_history_detail_notes(entry.notes.clone().into());
+    if ui.get_history_comparing() {
+        show_history_diff(ui, entry);
+    }
+}
+
+/// The version picker entries for `entry.versions()`.
+fn version_labels(versions: &[(String, &str)]) -> ModelRc<SharedString> {
+    option_model(versions.iter().map(|(label, _)| label.clone()))
+}
+
+/// Fills the compare view with the version on screen on the left and the
+/// one picked to compare against on the right.
+fn show_history_diff(ui: &AppWindow, entry: &history::HistoryEntry) {
+    let versions = entry.versions();
+    let text_of = |index: i32| versions.get(index.max(0) as usize).map_or("", |(_, text)| *text);
+    let diff = transcript_diff::diff_words(
+        text_of(ui.get_history_detail_version()),
+        text_of(ui.get_history_compare_version()),
+    );
+    ui.set_history_diff_summary(diff.summary().into());
+    ui.set_history_diff_rows(ModelRc::new(VecModel::from(
+        diff.rows
+            .into_iter()
+            .map(|row| DiffRow {
+                left: row.left.into(),
+                right: row.right.into(),
+                changed: row.changed,
+            })
+            .collect::<Vec<DiffRow>>(),
+    )));
+}
+
+/// Appends a finished session to the history file and the search index.
+fn save_to_history(entry: history::HistoryEntry, encrypt: bool, index: &Mutex<history_search::HistoryIndex>) {
+    let result = transcript_vault(encrypt)
+        .and_then(|vault| history::append(&settings::history_path(), &entry, vault))
+        .and_then(|()| index.lock().unwrap().push(entry));
+    if let Err(e) = result {
+        error!("❌ Session not saved to history: {}", e);
+    }
+}
+
+/// Rewrites the saved history to match the encryption setting, off the UI
+/// thread.
+fn reencode_history(encryption: Arc<Mutex<history::HistoryEncryption>>, encrypt: bool) {
+    thread::spawn(move || {
+        let result = transcript_vault(encrypt)
+            .and_then(|seal_with| encryption.lock().unwrap().sync(vault::Vault::global, seal_with));
+        match result {
+            Ok(false) => {}
+            Ok(true) if encrypt => info!("🔐 Encrypted the saved history"),
+            Ok(true) => info!("🔓 Saved history is no longer encrypted"),
+            Err(e) => error!("❌ Failed to rewrite the saved history: {}", e),
+        }
+    });
+}
+
+/// Copies every UI-backed setting from the window into `s`.
+fn read_settings_from_ui(ui: &AppWindow, s: &mut settings::AppSettings) {
+    s.api_key = ui.get_api_key_text().to_string();
+    if let Some(kind) = provider::ProviderKind::from_label(&ui.get_selected_provider()) {
+        s.provider = kind;
+    }
+    s.azure_speech_key = ui.get_azure_key_text().trim().to_string();
+    s.azure_speech_region = ui.get_azure_region_text().trim().to_string();
+    s.google_speech_api_key = ui.get_google_key_text().trim().to_string();
+    s.speech_language = ui.get_speech_language_text().trim().to_string();
+    s.openai_api_key = ui.get_openai_key_text().trim().to_string();
+    s.openai_transcription_model = ui.get_openai_model_text().trim().to_string();
+    s.proxy_url = ui.get_proxy_url_text().trim().to_string();
+    if let Some(model) = vosk_models::VoskModel::from_label(&ui.get_selected_vosk_model()) {
+        s.vosk_model = model.name.to_string();
+    }
+    s.gemini_api_key = ui.get_gemini_api_key_text().to_string();
+    s.gemini_enabled = ui.get_use_gemini_modifier();
+    s.gemini_model = ui.get_gemini_model_text().to_string();
+    let preset = ui.get_selected_gemini_preset();
+    s.gemini_prompt_preset = gemini::PRESETS
+        .into_iter()
+        .find(|known| i18n::is_label(known, &preset))
+        .map_or_else(|| preset.to_string(), str::to_string);
+    s.gemini_custom_prompt = ui.get_gemini_custom_prompt().to_string();
+    s.selected_microphone = ui.get_selected_microphone().to_string();
+    s.use_default_microphone = ui.get_use_default_microphone();
+    if let Some(quality) = audio::ResamplerQuality::from_label(&ui.get_selected_resampler_quality()) {
+        s.resampler_quality = quality;
+    }
+    if let Some(format) = audio::AudioFormat::from_label(&ui.get_selected_audio_format()) {
+        s.audio_format = format;
+    }
+    if let Some(source) = audio::CaptureSource::from_label(&ui.get_selected_capture_source()) {
+        s.capture_source = source;
+    }
+    s.meeting_mode = ui.get_meeting_mode();
+    s.record_session_audio = ui.get_record_session_audio();
+    s.record_session_trace = ui.get_record_session_trace();
+    s.race_provider = provider::ProviderKind::from_label(&ui.get_race_provider());
+    s.race_eleven_model_id = ui.get_race_eleven_model_text().trim().to_string();
+    s.autosave_transcripts = ui.get_autosave_transcripts();
+    s.keep_history = ui.get_keep_history();
+    s.encrypt_transcripts = ui.get_encrypt_transcripts();
+    s.wake_word_armed = ui.get_wake_word_armed();
+    s.noise_suppression = ui.get_noise_suppression();
+    s.boost_audio_thread = ui.get_boost_audio_thread();
+    if let Ok(secs) = ui.get_auto_stop_text().trim().parse::<u32>() {
+        s.auto_stop_silence_secs = secs;
+    }
+    if let Ok(secs) = ui.get_paragraph_pause_text().trim().parse::<f32>() {
+        s.paragraph_pause_secs = secs.max(0.0);
+    }
+    if let Ok(minutes) = ui.get_max_session_text().trim().parse::<u32>() {
+        s.max_session_minutes = minutes;
+    }
+    if let Some(action) = metrics::MaxDurationAction::from_label(&ui.get_selected_max_session_action()) {
+        s.max_session_action = action;
+    }
+    if let Ok(threshold) = ui.get_low_quota_text().trim().parse::<u64>() {
+        s.low_quota_threshold = threshold;
+    }
+    if let Some(action) = network::LowQuotaAction::from_label(&ui.get_selected_low_quota_action()) {
+        s.low_quota_action = action;
+    }
+    if let Ok(secs) = ui.get_preconnect_text().trim().parse::<u32>() {
+        s.preconnect_buffer_secs = secs.min(audio::MAX_PRECONNECT_SECS);
+    }
+    if let Ok(delay) = ui.get_injection_delay_text().trim().parse::<u32>() {
+        s.injection_burst_delay_ms = delay;
+    }
+    if let Ok(chunk) = ui.get_injection_chunk_text().trim().parse::<u32>() {
+        s.injection_chunk_size = chunk;
+    }
+    if let Ok(percent) = ui.get_low_confidence_text().trim().parse::<f32>() {
+        s.low_confidence_threshold = (percent / 100.0).clamp(0.0, 1.0);
+    }
+    s.skip_low_confidence_injection = ui.get_skip_low_confidence_injection();
+    s.transcript_only = ui.get_transcript_only();
+    s.preview_before_inject = ui.get_preview_before_inject();
+    let sinks_enabled = [
+        ui.get_output_keyboard(),
+        ui.get_output_clipboard(),
+        ui.get_output_file(),
+        ui.get_output_stdout(),
+        ui.get_output_webhook(),
+        ui.get_output_markdown(),
+    ];
+    s.output_sinks = output::OutputSinkKind::ALL
+        .into_iter()
+        .zip(sinks_enabled)
+        .filter_map(|(kind, enabled)| enabled.then_some(kind))
+        .collect();
+    s.output_file_path = ui.get_output_file_text().trim().to_string();
+    s.webhook_url = ui.get_webhook_url_text().trim().to_string();
+    s.markdown_note_path = ui.get_markdown_note_text().trim().to_string();
+    s.custom_vocabulary = settings::parse_vocabulary(&ui.get_vocabulary_text());
+    s.format_numbers = ui.get_format_numbers();
+    s.number_locale = ui.get_number_locale_text().trim().to_string();
+    s.diarization_enabled = ui.get_diarization_enabled();
+    s.strip_speaker_labels = ui.get_strip_speaker_labels();
+    s.filter_profanity = ui.get_filter_profanity();
+    s.redact_sensitive_data = ui.get_redact_sensitive_data();
+    if let Some(mode) = redaction::RedactionMode::from_label(&ui.get_selected_redaction_mode()) {
+        s.redaction_mode = mode;
+    }
+    s.voice_commands_enabled = ui.get_voice_commands_enabled();
+    s.voice_command_prefix = ui.get_voice_command_prefix_text().trim().to_string();
+    s.dictation_pad = ui.get_dictation_pad();
+    s.live_partial_injection = ui.get_live_partial_injection();
+    s.show_toasts = ui.get_show_toasts();
+    s.cue_on_start = ui.get_cue_on_start();
+    s.cue_on_stop = ui.get_cue_on_stop();
+    s.cue_on_error = ui.get_cue_on_error();
+    s.cue_volume = ui.get_cue_volume();
+    s.start_hidden = ui.get_start_hidden();
+    s.log_level = ui.get_selected_log_level().to_string();
+    s.llm_enabled = ui.get_llm_enabled();
+    s.llm_endpoint = ui.get_llm_endpoint_text().trim().to_string();
+    s.llm_api_key = ui.get_llm_api_key_text().trim().to_string();
+    s.llm_model = ui.get_llm_model_text().trim().to_string();
+    s.llm_prompt = ui.get_llm_prompt_text().to_string();
+    if let Some(scope) = llm::LlmScope::from_label(&ui.get_selected_llm_scope()) {
+        s.llm_scope = scope;
+    }
+    if let Ok(secs) = ui.get_llm_timeout_text().trim().parse::<u32>() {
+        s.llm_timeout_secs = secs.max(1);
+    }
+    s.translation_enabled = ui.get_translation_enabled();
+    if let Some(backend) = translate::TranslationBackend::from_label(&ui.get_selected_translation_backend()) {
+        s.translation_backend = backend;
+    }
+    s.deepl_api_key = ui.get_deepl_api_key_text().trim().to_string();
+    s.translation_source_lang = ui.get_translation_source_text().trim().to_string();
+    s.translation_target_lang = ui.get_translation_target_text().trim().to_string();
+    s.launch_at_login = ui.get_launch_at_login();
+    if let Some(language) = i18n::UiLanguage::from_label(&ui.get_selected_ui_language()) {
+        s.ui_language = language;
+    }
+    if let Some(mode) = theme::ThemeMode::from_label(&ui.get_theme_mode()) {
+        s.theme_mode = mode;
+    }
+    if let Some(retention) = transcript_panel::TranscriptRetention::from_label(&ui.get_selected_transcript_retention()) {
+        s.transcript_retention = retention;
+    }
+    let model = ui.get_selected_eleven_model().trim().to_string();
+    if !model.is_empty() {
+        s.eleven_model_id = model;
+    }
+    if let Some(lock) = injector::TargetLock::from_label(ui.get_selected_target_lock().as_str()) {
+        s.target_lock = lock;
+    }
+    s.control_api_enabled = ui.get_control_api_enabled();
+    if let Ok(port) = ui.get_control_api_port_text().trim().parse::<u16>() {
+        s.control_api_port = port;
+    }
+    s.captions_enabled = ui.get_captions_enabled();
+    if let Ok(port) = ui.get_captions_port_text().trim().parse::<u16>() {
+        s.captions_port = port;
+    }
+    s.overlay_opacity = ui.get_overlay_opacity();
+    s.theme_background_top_color = ui.get_theme_background_top_color().to_string();
+    s.theme_background_bottom_color = ui.get_theme_background_bottom_color().to_string();
+    s.theme_window_color = ui.get_theme_window_color().to_string();
+    s.theme_button_accent_color = ui.get_theme_button_accent_color().to_string();
+    s.theme_title_color = ui.get_theme_title_color().to_string();
+    s.theme_text_color = ui.get_theme_text_color().to_string();
+    s.overlay_background_color = ui.get_overlay_background_color().to_string();
+    s.overlay_text_color = ui.get_overlay_text_color().to_string();
+}
+
+fn overlay_size_for_text(text: &str) -> (i32, i32) {
+    let chars = text.chars().count().max(1);
+    let width = 520;
+    let chars_per_line = 58usize;
+    let lines = chars.div_ceil(chars_per_line).clamp(1, 8) as i32;
+    let height = (70 + (lines * 30)).clamp(110, 260);
+    (width, height)
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut initial_settings = load_settings();
+    let logging = Rc::new(logging::init(&initial_settings.log_level));
+    info!("🦋 11th Echo Rust (Iron Butterfly) Starting...");
+    let relaunched = std::env::args().skip(1).any(|arg| arg == single_instance::RELAUNCH_ARG);
+    let instance_guard = match single_instance::acquire(
+        single_instance::InstanceCommand::from_args(std::env::args().skip(1)),
+        relaunched,
+    ) {
+        Ok(single_instance::Acquired::Primary(guard)) if relaunched => {
+            // The instance that started us saved its window state on the way out.
+            initial_settings = load_settings();
+            info!("🛡 Restarted as administrator");
+            Some(guard)
+        }
+        Ok(single_instance::Acquired::Primary(guard)) => Some(guard),
+        Ok(single_instance::Acquired::Forwarded) => {
+            info!("👋 11th Echo is already running, handed over to it");
+            return Ok(());
+        }
+        Err(e) => {
+            warn!("⚠️ Single-instance check failed, continuing anyway: {}", e);
+            None
+        }
+    };
+
+    let microphones = audio::list_input_devices();
+    let default_microphone = audio::default_input_device_name().filter(|name| !name.is_empty());
+    if initial_settings.selected_microphone.trim().is_empty() {
+        initial_settings.selected_microphone = default_microphone
+            .clone()
+            .unwrap_or_else(|| microphones.first().cloned().unwrap_or_default());
+    }
+    if initial_settings.local_api_token.is_empty() {
+        initial_settings.local_api_token = local_http::generate_token();
+    }
+    save_settings(&initial_settings);
+    if initial_settings.launch_at_login {
+        // Re-register so the entry follows the executable if it moved.
+        if let Err(err) = autostart::set_enabled(true) {
+            warn!("⚠️ {}", err);
+        }
+    }
+    let settings = Arc::new(Mutex::new(initial_settings.clone()));
+
+    #[cfg(target_os = "windows")]
+    let hotkey_registry = {
+        let mut registry = HotkeyRegistry {
+            manager: GlobalHotKeyManager::new().unwrap(),
+            registered: Vec::new(),
+            bindings: Vec::new(),
+        };
+        for (binding, err) in registry.apply(&hotkey::active_keybindings(&initial_settings.keybindings, false)) {
+            warn!(
+                "⚠️ Failed to register {} hotkey {}: {}",
+                binding.action.name(),
+                binding.keys,
+                err
+            );
+        }
+        RefCell::new(registry)
+    };
+
+    #[cfg(target_os = "windows")]
+    let (tray_items, tray_labels, toggle_item, profile_menu, tray_handle, app_icon) = {
+        let tray_menu = Menu::new();
+        let toggle_item = MenuItem::new(tray::TrayStatus::Idle.toggle_label(), true, None);
+        let profile_menu = Submenu::new("Profile", true);
+        let show_item = MenuItem::new("Show 11th Echo", true, None);
+        let folder_item = MenuItem::new("Open transcript folder", true, None);
+        let logs_item = MenuItem::new("Open logs", true, None);
+        let settings_item = MenuItem::new("Settings Tab", true, None);
+        let mini_bar_item = MenuItem::new("Compact mini bar", true, None);
+        let restart_as_admin_item = MenuItem::new("Restart as administrator", elevation::can_relaunch_elevated(), None);
+        let quit_item = MenuItem::new("Quit", true, None);
+        tray_menu.append_items(&[
+            &toggle_item,
+            &profile_menu,
+            &show_item,
+            &folder_item,
+            &logs_item,
+            &settings_item,
+            &mini_bar_item,
+            &restart_as_admin_item,
+            &quit_item,
+        ])?;
+
+        let icon = tray_icon::Icon::from_path(tray::asset_path("eleventhecho.png"), None).or_else(|png_err| {
+            warn!(
+                "⚠️ Tray icon PNG load failed ({}), trying ICO fallback.",
+                png_err
+            );
+            tray_icon::Icon::from_path(tray::asset_path("eleventhecho.ico"), None)
+        })?;
+        let tray = TrayIconBuilder::new()
+            .with_menu(Box::new(tray_menu))
+            .with_tooltip(tray::TrayStatus::Idle.tooltip())
+            .with_icon(icon.clone())
+            .build()?;
+        let items = TrayMenuIds {
+            toggle: toggle_item.id().clone(),
+            show: show_item.id().clone(),
+            folder: folder_item.id().clone(),
+            logs: logs_item.id().clone(),
+            settings: settings_item.id().clone(),
+            mini_bar: mini_bar_item.id().clone(),
+            restart_as_admin: restart_as_admin_item.id().clone(),
+            quit: quit_item.id().clone(),
+        };
+        // The menu is built before the UI language is known; the timer
+        // translates these once it is, and again whenever it changes.
+        let labels = vec![
+            (show_item, "Show 11th Echo"),
+            (folder_item, "Open transcript folder"),
+            (logs_item, "Open logs"),
+            (settings_item, "Settings Tab"),
+            (mini_bar_item, "Compact mini bar"),
+            (restart_as_admin_item, "Restart as administrator"),
+            (quit_item, "Quit"),
+        ];
+        (items, labels, toggle_item, profile_menu, tray, icon)
+    };
+
+    let (cmd_tx, mut cmd_rx) = mpsc::unbounded_channel::<AppCommand>();
+    let (level_tx, mut level_rx) = mpsc::channel::<f32>(10);
+
+    let control_status = Arc::new(Mutex::new(control_api::ControlStatus::default()));
+    // The latest session's pipeline, read when the control API asks for the transcript.
+    let live_transcript: LiveTranscript = Arc::new(Mutex::new(None));
+    if initial_settings.control_api_enabled {
+        let api_cmd_tx = cmd_tx.clone();
+        let api_settings = settings.clone();
+        let api_status = control_status.clone();
+        let result = control_api::spawn(
+            initial_settings.control_api_port,
+            initial_settings.local_api_token.clone(),
+            control_status.clone(),
+            {
+                let live_transcript = live_transcript.clone();
+                move || {
+                    live_transcript
+                        .lock()
+                        .unwrap()
+                        .as_ref()
+                        .map(|pipeline| pipeline.lock().unwrap().committed_text().trim().to_string())
+                        .unwrap_or_default()
+                }
+            },
+            move |command| {
+                let cmd = match command {
+                    control_api::ControlCommand::Start => AppCommand::start(&api_settings.lock().unwrap()),
+                    control_api::ControlCommand::Stop => AppCommand::StopRecording,
+                    control_api::ControlCommand::Toggle => {
+                        if api_status.lock().unwrap().is_recording {
+                            AppCommand::StopRecording
+                        } else {
+                            AppCommand::start(&api_settings.lock().unwrap())
+                        }
+                    }
+                };
+                let _ = api_cmd_tx.send(cmd);
+            },
+        );
+        if let Err(err) = result {
+            warn!(
+                "⚠️ Failed to start control API on port {}: {}",
+                initial_settings.control_api_port, err
+            );
+        }
+    }
+
+    let caption_feed = initial_settings.captions_enabled.then(captions::CaptionFeed::default);
+    if let Some(feed) = caption_feed.clone() {
+        if let Err(err) = captions::spawn(initial_settings.captions_port, initial_settings.local_api_token.clone(), feed) {
+            warn!(
+                "⚠️ Failed to start the caption server on port {}: {}",
+                initial_settings.captions_port, err
+            );
+        }
+    }
+
+    let ui = AppWindow::new()?;
+    // Before any text is set from Rust, which is translated as it is set.
+    i18n::apply(initial_settings.ui_language);
+    // Tabs run from 0 (Main) to 4 (History).
+    ui.set_active_tab(initial_settings.active_tab.clamp(0, 4));
+    if let Some(geometry) = initial_settings.window_geometry {
+        restore_window_geometry(&ui, geometry);
+    }
+    ui.set_status_text(tr!("Idle").into());
+    ui.set_is_recording(false);
+    ui.set_default_microphone_text(default_microphone.unwrap_or_else(|| tr!("Unavailable")).into());
+    ui.set_microphone_options(ModelRc::new(VecModel::from(
+        microphones
+            .iter()
+            .cloned()
+            .map(SharedString::from)
+            .collect::<Vec<SharedString>>(),
+    )));
+
+    ui.set_wake_word_samples_text(
+        wake_word_samples_text(wake_word::load_templates(&settings::wake_word_dir()).len()).into(),
+    );
+    let stored_totals = metrics::load_monthly_totals().for_month(&metrics::current_month());
+    ui.set_monthly_stats_text(stored_totals.summary().into());
+    if let Err(e) = autosave::set_aside(&settings::autosave_path(), &settings::recovered_autosave_path()) {
+        error!("❌ Failed to set aside the unfinished session's autosave: {}", e);
+    }
+    if let Some(recovered) = autosave::load_unfinished(&settings::recovered_autosave_path(), vault::Vault::global) {
+        info!("🩹 Found text from an unfinished session");
+        ui.set_recovered_text(recovered.into());
+    }
+    ui.set_log_level_options(ModelRc::new(VecModel::from(
+        logging::LOG_LEVELS
+            .iter()
+            .map(|level| SharedString::from(*level))
+            .collect::<Vec<SharedString>>(),
+    )));
+    ui.set_can_restart_as_admin(elevation::can_relaunch_elevated());
+    ui.set_waveform_commands(dsp::LevelHistory::default().path_commands().into());
+    apply_settings_to_ui(&ui, &initial_settings);
+
+    ui.set_transcript_segments(ModelRc::new(VecModel::<TranscriptSegment>::default()));
+    ui.set_log_items(ModelRc::new(VecModel::from(Vec::<SharedString>::new())));
+
+    // When the user closes the main window, hide it but keep the Slint
+    // event loop alive so the app can continue running from the tray.
+    let ui_weak_for_close = ui.as_weak();
+    let settings_for_close = settings.clone();
+    ui.window().on_close_requested(move || {
+        if let Some(ui) = ui_weak_for_close.upgrade() {
+            let _ = ui.window().hide();
+        }
+        save_window_state(&settings_for_close.lock().unwrap());
+        CloseRequestResponse::KeepWindowShown
+    });
+
+    #[cfg(target_os = "windows")]
+    let hotkey_capture_window = HotkeyCaptureWindow::new()?;
+    #[cfg(target_os = "windows")]
+    hotkey_capture_window.set_state_text(tr!("Waiting for key combo...").into());
+    #[cfg(target_os = "windows")]
+    hotkey_capture_window.set_combo_text("".into());
+    #[cfg(target_os = "windows")]
+    let hotkey_capture_active = Rc::new(RefCell::new(false));
+    #[cfg(target_os = "windows")]
+    let hotkey_capture_latched = Rc::new(RefCell::new(false));
+
+    let transcript_overlay = TranscriptOverlayWindow::new()?;
+    transcript_overlay.set_sentence_text("".into());
+    transcript_overlay.set_window_width(520);
+    transcript_overlay.set_window_height(120);
+    transcript_overlay.set_overlay_opacity(initial_settings.overlay_opacity);
+    transcript_overlay.set_overlay_background_color(parse_theme_color(
+        &initial_settings.overlay_background_color,
+        Color::from_rgb_u8(3, 21, 12),
+    ));
+    transcript_overlay.set_overlay_text_color(parse_theme_color(
+        &initial_settings.overlay_text_color,
+        Color::from_rgb_u8(230, 255, 240),
+    ));
+    transcript_overlay.set_is_error(false);
+    #[cfg(target_os = "windows")]
+    {
+        let screen_h = unsafe { GetSystemMetrics(SM_CYSCREEN) };
+        let overlay_h = 120;
+        let margin = 24;
+        transcript_overlay.window().set_position(slint::PhysicalPosition::new(margin, (screen_h - overlay_h - margin).max(0)));
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        transcript_overlay.window().set_position(slint::LogicalPosition::new(24.0, 820.0));
+    }
+
+    let toast = ToastWindow::new()?;
+    #[cfg(target_os = "windows")]
+    {
+        let screen_w = unsafe { GetSystemMetrics(SM_CXSCREEN) };
+        toast.window().set_position(slint::PhysicalPosition::new(((screen_w - 280) / 2).max(0), 24));
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        toast.window().set_position(slint::LogicalPosition::new(24.0, 24.0));
+    }
+    let toast_generation = Rc::new(Cell::new(0u64));
+
+    let mini_bar = MiniBarWindow::new()?;
+    #[cfg(target_os = "windows")]
+    {
+        let screen_w = unsafe { GetSystemMetrics(SM_CXSCREEN) };
+        mini_bar.window().set_position(slint::PhysicalPosition::new(((screen_w - 320) / 2).max(0), 24));
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        mini_bar.window().set_position(slint::LogicalPosition::new(24.0, 24.0));
+    }
+    let mini_bar_weak_for_drag = mini_bar.as_weak();
+    mini_bar.on_move_window(move |dx, dy| {
+        if let Some(bar) = mini_bar_weak_for_drag.upgrade() {
+            let current = bar.window().position();
+            let scale = bar.window().scale_factor();
+            bar.window().set_position(slint::PhysicalPosition::new(
+                current.x + (dx as f32 * scale) as i32,
+                current.y + (dy as f32 * scale) as i32,
+            ));
+        }
+    });
+    let mini_bar_tx = cmd_tx.clone();
+    let mini_bar_settings = settings.clone();
+    let ui_weak_for_mini_bar = ui.as_weak();
+    mini_bar.on_toggle_recording(move || {
+        if let Some(ui) = ui_weak_for_mini_bar.upgrade() {
+            let cmd = if ui.get_is_recording() {
+                AppCommand::StopRecording
+            } else {
+                AppCommand::start(&mini_bar_settings.lock().unwrap())
+            };
+            let _ = mini_bar_tx.send(cmd);
+        }
+    });
+    let ui_weak_for_expand = ui.as_weak();
+    mini_bar.on_expand(move || {
+        if let Some(ui) = ui_weak_for_expand.upgrade() {
+            ui.invoke_toggle_mini_bar();
+        }
+    });
+    // Swaps the main window and the mini bar; the choice is kept in settings
+    // so the next launch opens the same one.
+    let ui_weak_for_mini_toggle = ui.as_weak();
+    let mini_bar_weak = mini_bar.as_weak();
+    let settings_for_mini_toggle = settings.clone();
+    ui.on_toggle_mini_bar(move || {
+        let (Some(ui), Some(bar)) = (ui_weak_for_mini_toggle.upgrade(), mini_bar_weak.upgrade()) else {
+            return;
+        };
+        let compact = !bar.window().is_visible();
+        if compact {
+            let _ = bar.show();
+            let _ = ui.hide();
+        } else {
+            let _ = bar.hide();
+            let _ = ui.show();
+        }
+        settings_for_mini_toggle.lock().unwrap().mini_bar = compact;
+    });
+
+    let injection_preview = InjectionPreviewWindow::new()?;
+    let preview_reply: PreviewReply = Arc::new(Mutex::new(None));
+    let answer_preview = {
+        let reply = preview_reply.clone();
+        let preview_weak = injection_preview.as_weak();
+        move |answer: Option<String>| {
+            if let Some(tx) = reply.lock().unwrap().take() {
+                let _ = tx.send(answer);
+            }
+            if let Some(preview) = preview_weak.upgrade() {
+                let _ = preview.hide();
+            }
+        }
+    };
+    injection_preview.on_inject({
+        let answer = answer_preview.clone();
+        move |text| answer(Some(text.to_string()))
+    });
+    injection_preview.on_discard({
+        let answer = answer_preview.clone();
+        move || answer(None)
+    });
+    injection_preview.window().on_close_requested(move || {
+        answer_preview(None);
+        CloseRequestResponse::HideWindow
+    });
+
+    let overlay_weak_for_drag = transcript_overlay.as_weak();
+    transcript_overlay.on_move_window(move |dx, dy| {
+        if let Some(overlay) = overlay_weak_for_drag.upgrade() {
+            let current = overlay.window().position();
+            let scale = overlay.window().scale_factor();
+            overlay.window().set_position(slint::PhysicalPosition::new(
+                current.x + (dx as f32 * scale) as i32,
+                current.y + (dy as f32 * scale) as i32,
+            ));
+        }
+    });
+
+    let cmd_tx_for_runtime = cmd_tx.clone();
     let ui_handle_for_tokio = ui.as_weak();
     let overlay_handle_for_tokio = transcript_overlay.as_weak();
+    let preview_for_tokio = injection_preview.as_weak();
+    let preview_reply_for_tokio = preview_reply.clone();
+    let caption_feed_for_tokio = caption_feed.clone();
     let settings_for_runtime = settings.clone();
+    let control_status_for_runtime = control_status.clone();
+    let live_transcript_for_runtime = live_transcript.clone();
+    // The active session's state; each transition is broadcast to the UI,
+    // tray, control API and session log.
+    let state_bus = state::StateBus::default();
+    let state_bus_for_runtime = state_bus.clone();
+    let injection_history = Arc::new(Mutex::new(injector::InjectionHistory::default()));
+    // Rolling transcript latency across sessions.
+    let latency_for_runtime = Arc::new(Mutex::new(metrics::LatencyTracker::default()));
+    // The latest session's event log, kept for "Export session".
+    let session_log = Arc::new(Mutex::new(session_log::SessionLog::default()));
+    let session_log_for_runtime = session_log.clone();
+    // Finished sessions from the history file, with their search index.
+    let history_index = history_search::HistoryIndex::build(history::load(
+        &settings::history_path(),
+        vault::Vault::global,
+    ))
+    .or_else(|e| {
+        error!("❌ History search is unavailable: {}", e);
+        history_search::HistoryIndex::build(Vec::new())
+    })?;
+    let history_index = Arc::new(Mutex::new(history_index));
+    refresh_history(&ui, &history_index.lock().unwrap());
+    let history_for_runtime = history_index.clone();
+    // Tags typed on the Main tab, given to the session when it finishes.
+    let session_tags = Arc::new(Mutex::new(String::new()));
+    let session_tags_for_runtime = session_tags.clone();
+
+    ui.on_session_tags_edited(move |text| {
+        *session_tags.lock().unwrap() = text.to_string();
+    });
+
+    ui.on_search_history({
+        let ui_handle = ui.as_weak();
+        let index = history_index.clone();
+        move || {
+            if let Some(ui) = ui_handle.upgrade() {
+                refresh_history(&ui, &index.lock().unwrap());
+            }
+        }
+    });
+
+    ui.on_open_history_entry({
+        let ui_handle = ui.as_weak();
+        let index = history_index.clone();
+        move |id| {
+            let Some(ui) = ui_handle.upgrade() else {
+                return;
+            };
+            if let Some(entry) = index.lock().unwrap().get(id as usize) {
+                show_history_entry(&ui, id, entry, 0);
+            }
+        }
+    });
+
+    ui.on_show_history_version({
+        let ui_handle = ui.as_weak();
+        let index = history_index.clone();
+        move |version| {
+            let Some(ui) = ui_handle.upgrade() else {
+                return;
+            };
+            let index = index.lock().unwrap();
+            let Some(entry) = index.get(ui.get_history_selected() as usize) else {
+                return;
+            };
+            if let Some((_, text)) = entry.versions().get(version as usize) {
+                ui.set_history_detail_text((*text).into());
+            }
+            if ui.get_history_comparing() {
+                show_history_diff(&ui, entry);
+            }
+        }
+    });
+
+    ui.on_compare_history_versions({
+        let ui_handle = ui.as_weak();
+        let index = history_index.clone();
+        move || {
+            let Some(ui) = ui_handle.upgrade() else {
+                return;
+            };
+            if let Some(entry) = index.lock().unwrap().get(ui.get_history_selected() as usize) {
+                show_history_diff(&ui, entry);
+            }
+        }
+    });
+
+    ui.on_save_history_details({
+        let ui_handle = ui.as_weak();
+        let index = history_index.clone();
+        let settings = settings.clone();
+        move || {
+            let Some(ui) = ui_handle.upgrade() else {
+                return;
+            };
+            let id = ui.get_history_selected() as usize;
+            let mut index = index.lock().unwrap();
+            let Some(original) = index.get(id).cloned() else {
+                return;
+            };
+            let mut updated = original.clone();
+            updated.tags = history::parse_tags(&ui.get_history_detail_tags());
+            updated.notes = ui.get_history_detail_notes().trim().to_string();
+            let encrypt = settings.lock().unwrap().encrypt_transcripts;
+            let result = transcript_vault(encrypt)
+                .and_then(|seal_with| {
+                    history::replace(
+                        &settings::history_path(),
+                        vault::Vault::global,
+                        seal_with,
+                        &original,
+                        &updated,
+                    )
+                })
+                .and_then(|()| index.update(id, updated.clone()));
+            match result {
+                Ok(()) => {
+                    ui.set_history_detail_tags(history::format_tags(&updated.tags).into());
+                    ui.set_status_text(tr!("Session tags and notes saved").into());
+                    refresh_history(&ui, &index);
+                }
+                Err(e) => {
+                    error!("❌ Failed to save session tags: {}", e);
+                    ui.set_status_text(tr!("Tags not saved: {}", e).into());
+                }
+            }
+        }
+    });
+
+    ui.on_export_history({
+        let ui_handle = ui.as_weak();
+        let index = history_index.clone();
+        move || {
+            let Some(ui) = ui_handle.upgrade() else {
+                return;
+            };
+            let index = index.lock().unwrap();
+            let hits = history_query_from_ui(&ui)
+                .map_err(EchoError::config)
+                .and_then(|query| index.search(&query));
+            let entries: Vec<&history::HistoryEntry> = match hits {
+                Ok(hits) => hits.iter().rev().filter_map(|hit| index.get(hit.id)).collect(),
+                Err(e) => {
+                    ui.set_status_text(tr!("Could not export the history: {}", e).into());
+                    return;
+                }
+            };
+            if entries.is_empty() {
+                ui.set_status_text(tr!("No sessions to export").into());
+                return;
+            }
+            match history::export_markdown(&entries, &settings::transcripts_dir()) {
+                Ok(path) => {
+                    info!("💾 Exported {} sessions to {}", entries.len(), path.display());
+                    ui.set_status_text(tr!("{} sessions exported to {}", entries.len(), path.display()).into());
+                }
+                Err(err) => {
+                    error!("❌ Failed to export the history: {}", err);
+                    ui.set_status_text(tr!("Could not export the history: {}", err).into());
+                }
+            }
+        }
+    });
+
+    ui.on_copy_history_entry({
+        let ui_handle = ui.as_weak();
+        let index = history_index.clone();
+        move |id| {
+            let Some(ui) = ui_handle.upgrade() else {
+                return;
+            };
+            // The version on screen, which may be a re-transcription.
+            let version = ui.get_history_detail_version().max(0) as usize;
+            if let Some(entry) = index.lock().unwrap().get(id as usize) {
+                let text = entry.versions().get(version).map_or(entry.text.as_str(), |(_, text)| text).to_string();
+                if let Ok(mut cb) = Clipboard::new() {
+                    let _ = cb.set_text(text);
+                }
+            }
+        }
+    });
+
+    let transcript_panel = Arc::new(Mutex::new(transcript_panel::TranscriptPanel::default()));
+    let log_raw_for_clipboard: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+
+    ui.on_copy_all_transcripts({
+        let panel = transcript_panel.clone();
+        move || {
+            let text = panel.lock().unwrap().copy_text();
+            if text.is_empty() {
+                return;
+            }
+            if let Ok(mut cb) = Clipboard::new() {
+                let _ = cb.set_text(text);
+            }
+        }
+    });
 
-    let transcript_raw_for_clipboard: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
-    let log_raw_for_clipboard: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+    ui.on_clear_transcript({
+        let ui_handle = ui.as_weak();
+        let panel = transcript_panel.clone();
+        move || {
+            panel.lock().unwrap().clear();
+            if let Some(ui) = ui_handle.upgrade() {
+                with_transcript_rows(&ui, |rows| rows.set_vec(Vec::new()));
+                ui.set_transcript_editing(false);
+            }
+        }
+    });
+
+    ui.on_export_session({
+        let ui_handle = ui.as_weak();
+        let session_log = session_log.clone();
+        move || {
+            let Some(ui) = ui_handle.upgrade() else {
+                return;
+            };
+            let log = session_log.lock().unwrap().clone();
+            if log.is_empty() {
+                ui.set_status_text(tr!("Nothing to export yet - record a session first").into());
+                return;
+            }
+            match log.export(&settings::transcripts_dir()) {
+                Ok(path) => {
+                    info!("💾 Exported session log to {}", path.display());
+                    ui.set_status_text(tr!("Session exported to {}", path.display()).into());
+                }
+                Err(err) => {
+                    error!("❌ Failed to export session log: {}", err);
+                    ui.set_status_text(tr!("Could not export the session: {}", err).into());
+                }
+            }
+        }
+    });
+
+    ui.on_edit_transcript({
+        let ui_handle = ui.as_weak();
+        let panel = transcript_panel.clone();
+        move || {
+            if let Some(ui) = ui_handle.upgrade() {
+                ui.set_transcript_edit_text(panel.lock().unwrap().begin_edit().into());
+                ui.set_transcript_editing(true);
+            }
+        }
+    });
+
+    ui.on_finish_transcript_edit({
+        let ui_handle = ui.as_weak();
+        let panel = transcript_panel.clone();
+        move || {
+            let Some(ui) = ui_handle.upgrade() else {
+                return;
+            };
+            let mut panel = panel.lock().unwrap();
+            panel.finish_edit(&ui.get_transcript_edit_text());
+            with_transcript_rows(&ui, |rows| rows.set_vec(transcript_rows(panel.display())));
+            ui.set_transcript_editing(false);
+        }
+    });
+
+    // The window that had focus before the user switched to the app, for
+    // typing corrected text back into it.
+    let last_external_window: Rc<Cell<Option<injector::TargetWindow>>> = Rc::new(Cell::new(None));
+    ui.on_type_transcript_selection({
+        let ui_handle = ui.as_weak();
+        let cmd_tx = cmd_tx.clone();
+        let last_external_window = last_external_window.clone();
+        move |anchor, cursor| {
+            let Some(ui) = ui_handle.upgrade() else {
+                return;
+            };
+            let text = ui.get_transcript_edit_text();
+            match transcript_edit::selection(&text, anchor, cursor) {
+                Some(selected) => {
+                    let _ = cmd_tx.send(AppCommand::TypeText {
+                        text: selected.to_string(),
+                        target: last_external_window.get(),
+                    });
+                }
+                None => ui.set_status_text(tr!("Select the text to type first").into()),
+            }
+        }
+    });
 
     ui.on_copy_transcript({
-        let raw = transcript_raw_for_clipboard.clone();
+        let panel = transcript_panel.clone();
         move |index| {
-            if let Ok(hist) = raw.lock() {
-                if let Some(text) = hist.get(index as usize) {
-                    if let Ok(mut cb) = Clipboard::new() {
-                        let _ = cb.set_text(text.clone());
+            if let Some(text) = panel.lock().unwrap().raw().get(index as usize) {
+                if let Ok(mut cb) = Clipboard::new() {
+                    let _ = cb.set_text(text.clone());
+                }
+            }
+        }
+    });
+
+    ui.on_copy_pad({
+        let ui_handle = ui.as_weak();
+        move || {
+            if let Some(ui) = ui_handle.upgrade() {
+                let text = ui.get_pad_text();
+                if text.trim().is_empty() {
+                    return;
+                }
+                if let Ok(mut cb) = Clipboard::new() {
+                    let _ = cb.set_text(text.to_string());
+                }
+            }
+        }
+    });
+
+    ui.on_clear_pad({
+        let ui_handle = ui.as_weak();
+        move || {
+            if let Some(ui) = ui_handle.upgrade() {
+                ui.set_pad_text("".into());
+            }
+        }
+    });
+
+    ui.on_save_pad({
+        let ui_handle = ui.as_weak();
+        move || {
+            if let Some(ui) = ui_handle.upgrade() {
+                let text = ui.get_pad_text();
+                if text.trim().is_empty() {
+                    return;
+                }
+                match save_pad_text(&text) {
+                    Ok(path) => {
+                        info!("💾 Saved dictation pad to {}", path.display());
+                        ui.set_status_text(tr!("Saved to {}", path.display()).into());
+                    }
+                    Err(err) => {
+                        error!("❌ Failed to save dictation pad: {}", err);
+                        ui.set_status_text(tr!("Could not save the dictation pad: {}", err).into());
+                    }
+                }
+            }
+        }
+    });
+
+    ui.on_restore_recovered({
+        let ui_handle = ui.as_weak();
+        move || {
+            if let Some(ui) = ui_handle.upgrade() {
+                let pad = ui.get_pad_text();
+                let segment = pipeline::format_segment(&pad, &ui.get_recovered_text());
+                ui.set_pad_text(format!("{}{}", pad, segment).into());
+                ui.set_recovered_text("".into());
+                ui.set_active_tab(1);
+                autosave::discard(&settings::recovered_autosave_path());
+            }
+        }
+    });
+
+    ui.on_discard_recovered({
+        let ui_handle = ui.as_weak();
+        move || {
+            if let Some(ui) = ui_handle.upgrade() {
+                ui.set_recovered_text("".into());
+                autosave::discard(&settings::recovered_autosave_path());
+            }
+        }
+    });
+
+    ui.on_dismiss_error_banner({
+        let ui_handle = ui.as_weak();
+        move || {
+            if let Some(ui) = ui_handle.upgrade() {
+                ui.set_error_banner_text("".into());
+                ui.set_error_banner_suggests_admin(false);
+            }
+        }
+    });
+
+    ui.on_restart_as_admin({
+        let ui_handle = ui.as_weak();
+        let cmd_tx = cmd_tx.clone();
+        let settings = settings.clone();
+        move || {
+            let Some(ui) = ui_handle.upgrade() else {
+                return;
+            };
+            if ui.get_is_recording() {
+                ui.set_status_text(tr!("Stop dictating before restarting as administrator").into());
+                return;
+            }
+            // Saved first so the new copy starts with any edits not yet
+            // applied; the window state follows on the way out.
+            {
+                let mut current = settings.lock().unwrap();
+                read_settings_from_ui(&ui, &mut current);
+                save_settings(&current);
+            }
+            match elevation::relaunch_elevated() {
+                Ok(()) => {
+                    info!("🛡 Restarting as administrator");
+                    if cmd_tx.send(AppCommand::Shutdown).is_err() {
+                        let _ = slint::quit_event_loop();
                     }
                 }
+                Err(e) => {
+                    warn!("⚠️ {}", e);
+                    ui.set_status_text(e.user_message().into());
+                }
             }
         }
     });
@@ -474,32 +2323,200 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     thread::spawn(move || {
         let rt = Runtime::new().unwrap();
         rt.block_on(async move {
-            println!("⚡ Tokio Runtime Active");
+            info!("⚡ Tokio Runtime Active");
+            let quota_cache: QuotaCache = Arc::new(Mutex::new(HashMap::new()));
+            refresh_quota(&settings_for_runtime.lock().unwrap(), &quota_cache);
+
+            if let Some(guard) = instance_guard {
+                let cmd_tx = cmd_tx_for_runtime.clone();
+                let settings = settings_for_runtime.clone();
+                let status = control_status_for_runtime.clone();
+                let ui_handle = ui_handle_for_tokio.clone();
+                tokio::spawn(guard.serve(move |command| match command {
+                    single_instance::InstanceCommand::Show => {
+                        let _ = ui_handle.upgrade_in_event_loop(|ui| {
+                            ui.set_active_tab(0);
+                            let _ = ui.show();
+                        });
+                    }
+                    single_instance::InstanceCommand::Start => {
+                        let _ = cmd_tx.send(AppCommand::start(&settings.lock().unwrap()));
+                    }
+                    single_instance::InstanceCommand::Stop => {
+                        let _ = cmd_tx.send(AppCommand::StopRecording);
+                    }
+                    single_instance::InstanceCommand::Cancel => {
+                        let _ = cmd_tx.send(AppCommand::CancelRecording);
+                    }
+                    single_instance::InstanceCommand::Toggle => {
+                        let cmd = if status.lock().unwrap().is_recording {
+                            AppCommand::StopRecording
+                        } else {
+                            AppCommand::start(&settings.lock().unwrap())
+                        };
+                        let _ = cmd_tx.send(cmd);
+                    }
+                }));
+            }
 
             let mut active_session: Option<Session> = None;
+            let mut monthly_totals = metrics::load_monthly_totals();
             let (finalize_tx, mut finalize_rx) = mpsc::unbounded_channel::<()>();
+            let (device_lost_tx, mut device_lost_rx) = mpsc::unbounded_channel::<()>();
             let overlay_visible = Arc::new(std::sync::atomic::AtomicBool::new(false));
+            let mut level_history = dsp::LevelHistory::default();
+            // Set once Quit is requested; the next finalization ends the app.
+            let mut shutting_down = false;
+            let start_listener = || {
+                start_wake_listener(&settings_for_runtime, cmd_tx_for_runtime.clone(), ui_handle_for_tokio.clone())
+            };
+            let wake_word_armed = || settings_for_runtime.lock().unwrap().wake_word_armed;
+            let mut wake_listener: Option<WakeListener> = None;
+            let mut mic_test: Option<mic_test::MicTest> = None;
+            if wake_word_armed() {
+                match start_listener() {
+                    Ok(listener) => wake_listener = Some(listener),
+                    Err(e) => warn!("⚠️ Wake word listener unavailable: {}", e),
+                }
+            }
+            {
+                // Publishes each transition to the control API and the status bar.
+                let mut state_rx = state_bus_for_runtime.subscribe();
+                let control_status = control_status_for_runtime.clone();
+                let ui_handle = ui_handle_for_tokio.clone();
+                tokio::spawn(async move {
+                    loop {
+                        let change = match state_rx.recv().await {
+                            Ok(change) => change,
+                            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                            Err(broadcast::error::RecvError::Closed) => break,
+                        };
+                        debug!("🔁 Recording state: {} (session {})", change.state.label(), change.session);
+                        control_status.lock().unwrap().state = change.state.label().to_string();
+                        let _ = ui_handle.upgrade_in_event_loop(move |ui| {
+                            ui.set_session_state(change.state.label().into());
+                        });
+                    }
+                });
+            }
+            let metrics_bus = metrics::MetricsBus::default();
+            {
+                // Publishes the session's numbers to the status bar as they change.
+                let mut metrics_rx = metrics_bus.subscribe();
+                let ui_handle = ui_handle_for_tokio.clone();
+                tokio::spawn(async move {
+                    loop {
+                        let update = match metrics_rx.recv().await {
+                            Ok(update) => update,
+                            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                            Err(broadcast::error::RecvError::Closed) => break,
+                        };
+                        let _ = ui_handle.upgrade_in_event_loop(move |ui| match update {
+                            metrics::MetricsUpdate::Audio { summary, queue_fill, falling_behind } => {
+                                ui.set_session_stats_text(summary.into());
+                                ui.set_audio_queue_fill(queue_fill);
+                                ui.set_audio_falling_behind(falling_behind);
+                            }
+                            metrics::MetricsUpdate::Elapsed(elapsed) => {
+                                ui.set_elapsed_text(metrics::format_elapsed(elapsed).into());
+                            }
+                        });
+                    }
+                });
+            }
 
             loop {
                 tokio::select! {
                     Some(level) = level_rx.recv() => {
+                        level_history.push(level);
+                        let waveform = level_history.path_commands();
                         let _ = ui_handle_for_tokio.upgrade_in_event_loop(move |ui| {
                             ui.set_audio_level(level);
+                            ui.set_waveform_commands(waveform.into());
                         });
                     }
                     Some(()) = finalize_rx.recv() => {
+                        let mut status_text = tr!("Idle");
                         if let Some(session) = active_session.take() {
-                            if let Ok(mut state) = session.state.lock() {
-                                state.transition_to_idle();
+                            session.timer_task.abort();
+                            if let Some(autosave) = session.autosave.lock().unwrap().take() {
+                                autosave.finish();
+                            }
+                            if session.cancelled {
+                                status_text = tr!("Recording cancelled");
+                            }
+                            session.state.update(|s| s.transition_to_idle());
+                            let overwritten = session.backpressure.overwritten_samples();
+                            if overwritten > 0 {
+                                warn!(
+                                    "⚠️ {:.1}s of audio was overwritten while the speech service fell behind (peak backlog {:.1}s)",
+                                    session.audio_format.seconds(overwritten as usize),
+                                    session.audio_format.seconds(session.backpressure.peak_buffered_samples() as usize)
+                                );
+                            }
+                            if let Some(path) = session.recording_path.as_ref() {
+                                let subtitles = session.subtitles.lock().unwrap();
+                                if !subtitles.is_empty() {
+                                    match subtitles.save_beside(path) {
+                                        Ok(()) => info!("💾 Saved subtitles beside {}", path.display()),
+                                        Err(e) => error!("❌ Failed to save subtitles for {}: {}", path.display(), e),
+                                    }
+                                }
+                            }
+                            let session_metrics = session.metrics.lock().unwrap().clone();
+                            session_log_for_runtime.lock().unwrap().finish(session_metrics.audio_seconds());
+                            let (keep_history, encrypt) = {
+                                let current = settings_for_runtime.lock().unwrap();
+                                (current.keep_history, current.encrypt_transcripts)
+                            };
+                            let text = session.transcript_pipeline.lock().unwrap().committed_text().trim().to_string();
+                            if keep_history && !session.cancelled && !text.is_empty() {
+                                let tags = std::mem::take(&mut *session_tags_for_runtime.lock().unwrap());
+                                let entry = {
+                                    let log = session_log_for_runtime.lock().unwrap();
+                                    history::HistoryEntry {
+                                        started_at: log.started_at.clone(),
+                                        provider: log.provider.clone(),
+                                        text,
+                                        tags: history::parse_tags(&tags),
+                                        notes: String::new(),
+                                        post_processed: session.post_processed.lock().unwrap().take(),
+                                        recording: session.recording_path.clone(),
+                                        retranscriptions: Vec::new(),
+                                    }
+                                };
+                                save_to_history(entry, encrypt, &history_for_runtime);
+                                let index = history_for_runtime.clone();
+                                let _ = ui_handle_for_tokio.upgrade_in_event_loop(move |ui| {
+                                    ui.set_session_tags_text("".into());
+                                    refresh_history(&ui, &index.lock().unwrap());
+                                });
                             }
-                            println!("✅ Finalization complete, session closed");
+                            monthly_totals.add_session(&metrics::current_month(), &session_metrics);
+                            metrics::save_monthly_totals(&monthly_totals);
+                            let session_summary = session_metrics.summary();
+                            let monthly_summary = monthly_totals.summary();
+                            info!("📊 Session usage: {}", session_summary);
+                            let _ = ui_handle_for_tokio.upgrade_in_event_loop(move |ui| {
+                                ui.set_session_stats_text(session_summary.into());
+                                ui.set_monthly_stats_text(monthly_summary.into());
+                            });
+                            info!("✅ Finalization complete, session closed");
                         }
                         overlay_visible.store(false, std::sync::atomic::Ordering::SeqCst);
-                        let _ = ui_handle_for_tokio.upgrade_in_event_loop(|ui| {
+                        level_history.clear();
+                        let waveform = level_history.path_commands();
+                        let _ = ui_handle_for_tokio.upgrade_in_event_loop(move |ui| {
+                            with_transcript_rows(&ui, drop_partial_row);
+                            ui.set_session_provider_text("".into());
+                            ui.set_audio_queue_fill(0.0);
                             ui.set_audio_level(0.0);
+                            ui.set_waveform_commands(waveform.into());
+                            ui.set_audio_falling_behind(false);
                             ui.set_is_recording(false);
+                            ui.set_is_paused(false);
                             ui.set_has_error(false);
-                            ui.set_status_text("Idle".into());
+                            ui.set_status_text(status_text.into());
                         });
                         let _ = overlay_handle_for_tokio.upgrade_in_event_loop(|overlay| {
                             overlay.set_sentence_text("".into());
@@ -509,23 +2526,110 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             overlay.set_is_visible(false);
                             let _ = overlay.hide();
                         });
+                        if shutting_down {
+                            info!("👋 Session finalized, quitting");
+                            let _ = slint::quit_event_loop();
+                        } else if wake_listener.is_none() && wake_word_armed() {
+                            match start_listener() {
+                                Ok(listener) => wake_listener = Some(listener),
+                                Err(e) => warn!("⚠️ Wake word listener unavailable: {}", e),
+                            }
+                        }
+                    }
+                    Some(()) = device_lost_rx.recv() => {
+                        let Some(session) = active_session.as_mut() else {
+                            continue;
+                        };
+                        let sink = match session.audio.take() {
+                            Some(capture) if capture.is_lost() => {
+                                warn!("⚠️ Microphone disconnected, reopening the default input device");
+                                let _ = ui_handle_for_tokio.upgrade_in_event_loop(|ui| {
+                                    ui.set_status_text(tr!("Microphone disconnected - reconnecting...").into());
+                                    ui.set_has_error(true);
+                                    ui.set_audio_level(0.0);
+                                });
+                                let _ = overlay_handle_for_tokio.upgrade_in_event_loop(|overlay| {
+                                    overlay.set_sentence_text(tr!("⚠️ Microphone disconnected").into());
+                                });
+                                // Joining the worker blocks; keep it off the runtime.
+                                let draining = capture.stop();
+                                match tokio::task::spawn_blocking(move || draining.into_sink()).await {
+                                    Ok(Ok(sink)) => Some(sink),
+                                    Ok(Err(e)) => {
+                                        error!("❌ {}", e);
+                                        None
+                                    }
+                                    Err(e) => {
+                                        error!("❌ Audio worker join failed: {}", e);
+                                        None
+                                    }
+                                }
+                            }
+                            Some(capture) => {
+                                // Stale notification from a stream we already replaced.
+                                session.audio = Some(capture);
+                                continue;
+                            }
+                            None => session.detached_audio.take(),
+                        };
+                        let Some(sink) = sink else {
+                            continue;
+                        };
+
+                        match audio::open_capture(sink, None, device_lost_tx.clone()) {
+                            Ok(capture) => {
+                                info!("🎤 Microphone reconnected");
+                                session.audio = Some(capture);
+                                session.reconnect_attempts = 0;
+                                let status = if session.paused.load(Ordering::SeqCst) {
+                                    tr!("Paused")
+                                } else {
+                                    tr!("Listening...")
+                                };
+                                let overlay_status = status.clone();
+                                let _ = ui_handle_for_tokio.upgrade_in_event_loop(move |ui| {
+                                    ui.set_status_text(status.into());
+                                    ui.set_has_error(false);
+                                });
+                                let _ = overlay_handle_for_tokio.upgrade_in_event_loop(move |overlay| {
+                                    overlay.set_sentence_text(overlay_status.into());
+                                });
+                            }
+                            Err((sink, e)) => {
+                                // Without its sink there is nothing left to reconnect.
+                                let retry = sink.is_some();
+                                session.detached_audio = sink;
+                                session.reconnect_attempts += 1;
+                                if !retry || session.reconnect_attempts >= MAX_DEVICE_RECONNECT_ATTEMPTS {
+                                    error!("❌ No microphone available after {} attempts: {}", session.reconnect_attempts, e);
+                                    let _ = ui_handle_for_tokio.upgrade_in_event_loop(|ui| {
+                                        ui.set_status_text(tr!("Microphone unavailable").into());
+                                        ui.set_has_error(true);
+                                    });
+                                    let _ = cmd_tx_for_runtime.send(AppCommand::StopRecording);
+                                } else {
+                                    let retry_tx = device_lost_tx.clone();
+                                    tokio::spawn(async move {
+                                        tokio::time::sleep(DEVICE_RECONNECT_INTERVAL).await;
+                                        let _ = retry_tx.send(());
+                                    });
+                                }
+                            }
+                        }
                     }
                     Some(cmd) = cmd_rx.recv() => {
                     match cmd {
-                        AppCommand::StartRecording => {
+                        AppCommand::StartRecording { model_id } => {
                             if let Some(session) = active_session.as_mut() {
-                                if session.state.lock().unwrap().can_start() {
+                                if session.state.get().can_start() {
                                     if let Ok(mut pipeline) = session.transcript_pipeline.lock() {
-                                        *pipeline = TranscriptPipeline::new();
-                                    }
-                                    if let Ok(mut s) = session.state.lock() {
-                                        s.transition_to_recording();
+                                        pipeline.clear();
                                     }
+                                    session.state.update(|s| s.transition_to_recording());
                                     let _ = ui_handle_for_tokio.upgrade_in_event_loop(|ui| {
-                                        ui.set_status_text("Listening...".into());
+                                        ui.set_status_text(tr!("Listening...").into());
                                         ui.set_is_recording(true);
                                         ui.set_has_error(false);
-                                        ui.set_transcript("".into());
                                     });
                                     overlay_visible.store(true, std::sync::atomic::Ordering::SeqCst);
                                     let _ = overlay_handle_for_tokio.upgrade_in_event_loop(|overlay| {
@@ -535,83 +2639,268 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                         overlay.set_is_visible(true);
                                         let _ = overlay.show();
                                     });
-                                    if let Some(tx) = session.network_stop_tx.as_ref() {
-                                        let _ = tx.send(network::ControlMessage::Start);
-                                    }
-                                    println!("⚡ Resumed existing transcription session");
+                                    session.send_control(network::ControlMessage::Start);
+                                    info!("⚡ Resumed existing transcription session");
                                     continue;
                                 } else {
-                                    println!("❌ Cannot start recording: session already active");
+                                    warn!("❌ Cannot start recording: session already active");
                                     continue;
                                 }
                             }
 
                             let current_settings = settings_for_runtime.lock().unwrap().clone();
-                            if current_settings.api_key.trim().is_empty() {
-                                let _ = ui_handle_for_tokio.upgrade_in_event_loop(|ui| {
-                                    ui.set_status_text("Missing API key".into());
-                                    ui.set_is_recording(false);
-                                });
-                                continue;
+                            let client = match provider::create_provider(&current_settings, &model_id) {
+                                Ok(client) => client,
+                                Err(e) => {
+                                    warn!("❌ Cannot start recording: {}", e);
+                                    let status = e.user_message();
+                                    let _ = ui_handle_for_tokio.upgrade_in_event_loop(move |ui| {
+                                        ui.set_status_text(status.into());
+                                        ui.set_is_recording(false);
+                                    });
+                                    continue;
+                                }
+                            };
+
+                            let mut quota_warning = None;
+                            let mut pending_quota = None;
+                            let api_key = current_settings.api_key.trim().to_string();
+                            if current_settings.provider == provider::ProviderKind::ElevenLabs
+                                && current_settings.low_quota_threshold > 0
+                                && !api_key.is_empty()
+                            {
+                                let cached = quota_cache.lock().unwrap().get(&api_key).cloned();
+                                match cached {
+                                    Some(QuotaStatus::Known(info)) => {
+                                        refresh_quota(&current_settings, &quota_cache);
+                                        quota_warning = info.low_quota_warning(current_settings.low_quota_threshold);
+                                    }
+                                    Some(QuotaStatus::Failed(at)) if at.elapsed() < QUOTA_RETRY_AFTER => {}
+                                    // Nothing known for this key yet: looked up while the session starts.
+                                    _ => {
+                                        let fetch = fetch_quota(api_key, current_settings.proxy_url.clone(), quota_cache.clone());
+                                        pending_quota = Some(tokio::spawn(fetch));
+                                    }
+                                }
+                            }
+                            if let Some(warning) = quota_warning.as_ref() {
+                                warn!("⚠️ {}", warning);
+                                if current_settings.low_quota_action == network::LowQuotaAction::Refuse {
+                                    let status = tr!("{} - not starting", warning);
+                                    let _ = ui_handle_for_tokio.upgrade_in_event_loop(move |ui| {
+                                        ui.set_status_text(status.clone().into());
+                                        ui.set_has_error(true);
+                                        ui.set_error_banner_text(status.into());
+                                        ui.set_is_recording(false);
+                                    });
+                                    continue;
+                                }
                             }
 
+                            // Captured now, while the app the user is dictating into still has focus.
+                            let injection_target = match current_settings.target_lock {
+                                // The preview takes focus, so confirmed text has to be sent back.
+                                injector::TargetLock::Off if current_settings.preview_before_inject => {
+                                    injector::capture_target_window().map(|window| (window, injector::TargetLock::Refocus))
+                                }
+                                injector::TargetLock::Off => None,
+                                lock => injector::capture_target_window().map(|window| (window, lock)),
+                            };
+
                             let preferred_device = if current_settings.use_default_microphone {
                                 None
                             } else {
                                 Some(current_settings.selected_microphone.clone())
                             };
 
-                            println!("⚡ Starting Recording Session...");
-                            let _ = ui_handle_for_tokio.upgrade_in_event_loop(|ui| {
-                                ui.set_status_text("Connecting...".into());
+                            let audio_format = client.audio_format();
+                            let provider_text = match provider::selected_model(&current_settings, &model_id) {
+                                Some(model) => format!("{} · {}", client.name(), model),
+                                None => client.name().to_string(),
+                            };
+                            info!(
+                                "⚡ Starting Recording Session ({}: {}, streaming {})...",
+                                client.name(),
+                                client.capabilities().summary(),
+                                audio_format.label()
+                            );
+                            let _ = ui_handle_for_tokio.upgrade_in_event_loop(move |ui| {
+                                ui.set_status_text(tr!("Connecting...").into());
                                 ui.set_has_error(false);
-                                ui.set_transcript("".into());
+                                ui.set_error_banner_text(quota_warning.unwrap_or_default().into());
+                                ui.set_error_banner_suggests_admin(false);
+                                ui.set_session_provider_text(provider_text.into());
                             });
                             overlay_visible.store(true, std::sync::atomic::Ordering::SeqCst);
                             let _ = overlay_handle_for_tokio.upgrade_in_event_loop(|overlay| {
-                                overlay.set_sentence_text("Listening...".into());
+                                overlay.set_sentence_text(tr!("Listening...").into());
                                 overlay.set_window_width(520);
                                 overlay.set_window_height(120);
                                 overlay.set_is_visible(true);
                                 let _ = overlay.show();
                             });
 
-                            let state = Arc::new(Mutex::new(RecordingState::BufferingPreConnect));
-                            let transcript_pipeline = Arc::new(Mutex::new(TranscriptPipeline::new()));
-                            let transcript_history: Arc<Mutex<Vec<SharedString>>> = Arc::new(Mutex::new(Vec::new()));
-                            let transcript_raw: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+                            *session_log_for_runtime.lock().unwrap() = session_log::SessionLog::new(
+                                client.name().to_string(),
+                                client.capabilities().summary(),
+                                audio_format,
+                                preferred_device.clone(),
+                            );
+                            let mut state_rx = state_bus_for_runtime.subscribe();
+                            let state = state_bus_for_runtime.begin_session();
+                            if let Some(fetch) = pending_quota {
+                                let session = state.id();
+                                let threshold = current_settings.low_quota_threshold;
+                                let refuse = current_settings.low_quota_action == network::LowQuotaAction::Refuse;
+                                let cmd_tx = cmd_tx_for_runtime.clone();
+                                let ui_handle = ui_handle_for_tokio.clone();
+                                tokio::spawn(async move {
+                                    let Ok(Ok(Some(info))) = tokio::time::timeout(QUOTA_CHECK_TIMEOUT, fetch).await else {
+                                        return;
+                                    };
+                                    let Some(warning) = info.low_quota_warning(threshold) else {
+                                        return;
+                                    };
+                                    warn!("⚠️ {}", warning);
+                                    if refuse {
+                                        let _ = cmd_tx.send(AppCommand::QuotaExhausted { session, warning });
+                                    } else {
+                                        let _ = ui_handle.upgrade_in_event_loop(move |ui| {
+                                            ui.set_error_banner_text(warning.into());
+                                        });
+                                    }
+                                });
+                            }
+                            {
+                                // Records every state change until the session is back to idle.
+                                let log = session_log_for_runtime.clone();
+                                let session_id = state.id();
+                                tokio::spawn(async move {
+                                    loop {
+                                        match state_rx.recv().await {
+                                            Ok(change) if change.session == session_id => {
+                                                log.lock().unwrap().record_state(change.state.label());
+                                                if change.state == RecordingState::Idle {
+                                                    break;
+                                                }
+                                            }
+                                            // A newer session has started.
+                                            Ok(change) if change.session > session_id => break,
+                                            Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                                            Err(broadcast::error::RecvError::Closed) => break,
+                                        }
+                                    }
+                                });
+                            }
+                            if let Some(feed) = caption_feed_for_tokio.as_ref() {
+                                feed.clear();
+                            }
+                            let paused = Arc::new(AtomicBool::new(false));
+                            let session_metrics = Arc::new(Mutex::new(metrics::SessionMetrics::default()));
+                            let transcript_pipeline = Arc::new(Mutex::new(TranscriptPipeline::with_paragraph_pause(
+                                current_settings.paragraph_pause_secs as f64,
+                            )));
+                            *live_transcript_for_runtime.lock().unwrap() = Some(transcript_pipeline.clone());
+                            let cleared = transcript_panel.lock().unwrap().start_session(current_settings.transcript_retention);
+                            let _ = ui_handle_for_tokio.upgrade_in_event_loop(move |ui| {
+                                with_transcript_rows(&ui, |rows| {
+                                    if cleared {
+                                        rows.set_vec(Vec::new());
+                                    } else {
+                                        drop_partial_row(rows);
+                                    }
+                                });
+                            });
                             let log_display: Arc<Mutex<Vec<SharedString>>> = Arc::new(Mutex::new(Vec::new()));
                             let log_raw: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
-                            let (audio_tx, mut audio_rx) = mpsc::channel::<Vec<i16>>(50);
+                            let (audio_tx, mut audio_rx) = mpsc::channel::<audio::AudioChunk>(50);
                             let (network_stop_tx, network_stop_rx) =
                                 mpsc::unbounded_channel::<network::ControlMessage>();
                             let (text_tx, mut text_rx) =
-                                mpsc::channel::<network::TranscriptMessage>(100);
+                                mpsc::channel::<network::TranscriptEvent>(100);
                             let (log_line_tx, mut log_line_rx) =
                                 mpsc::unbounded_channel::<String>();
                             let audio_level_tx = level_tx.clone();
 
-                            let stream_result =
-                                audio::start_audio_capture(audio_tx, audio_level_tx, preferred_device);
+                            let mut recording_path = None;
+                            let recorder = if current_settings.record_session_audio {
+                                let path = settings::recordings_dir().join(format!(
+                                    "session_{}.wav",
+                                    Local::now().format("%Y%m%d_%H%M%S")
+                                ));
+                                match audio::SessionRecorder::create(&path, audio_format.sample_rate) {
+                                    Ok(recorder) => {
+                                        info!("💾 Recording session audio to {}", path.display());
+                                        recording_path = Some(path);
+                                        Some(recorder)
+                                    }
+                                    Err(e) => {
+                                        error!("❌ Failed to create session recording {}: {}", path.display(), e);
+                                        None
+                                    }
+                                }
+                            } else {
+                                None
+                            };
+
+                            // The session needs the microphone to itself.
+                            wake_listener = None;
+                            if mic_test.take().is_some() {
+                                let _ = ui_handle_for_tokio.upgrade_in_event_loop(|ui| {
+                                    ui.set_mic_testing(false);
+                                    ui.set_mic_test_level(0.0);
+                                });
+                            }
+                            let timeline = Arc::new(Mutex::new(audio::AudioTimeline::new(audio_format.sample_rate)));
+                            let subtitles = Arc::new(Mutex::new(subtitles::SubtitleTrack::new(
+                                timeline.lock().unwrap().started_at(),
+                            )));
+                            let stream_result = audio::start_audio_capture(
+                                audio_tx,
+                                audio_level_tx,
+                                preferred_device,
+                                recorder,
+                                audio::CaptureOptions {
+                                    noise_suppression: current_settings.noise_suppression,
+                                    source: if current_settings.meeting_mode {
+                                        audio::CaptureSource::Microphone
+                                    } else {
+                                        current_settings.capture_source
+                                    },
+                                    resampler: current_settings.resampler_quality,
+                                    sample_rate: audio_format.sample_rate,
+                                    preconnect_secs: current_settings.preconnect_buffer_secs,
+                                    boost_priority: current_settings.boost_audio_thread,
+                                },
+                                device_lost_tx.clone(),
+                            );
 
                             match stream_result {
-                                Ok(stream) => {
-                                    let client = network::ElevenLabsClient::new(
-                                        current_settings.api_key,
-                                        ELEVEN_MODEL_ID.to_string(),
-                                    );
+                                Ok(capture) => {
                                     let client_state = state.clone();
                                     let injection_state = state.clone();
                                     let transcript_pipeline_for_network = transcript_pipeline.clone();
                                     let transcript_pipeline_for_text = transcript_pipeline.clone();
-                                    let transcript_history_for_text = transcript_history.clone();
-                                    let transcript_raw_for_text = transcript_raw.clone();
-                                    let transcript_raw_for_cb = transcript_raw_for_clipboard.clone();
+                                    let transcript_panel_for_text = transcript_panel.clone();
+                                    let autosave = Arc::new(Mutex::new(if current_settings.autosave_transcripts {
+                                        transcript_vault(current_settings.encrypt_transcripts)
+                                            .and_then(|vault| {
+                                                autosave::TranscriptAutosave::create(&settings::autosave_path(), vault)
+                                                    .map_err(|e| EchoError::config(e.to_string()))
+                                            })
+                                            .map_err(|e| warn!("⚠️ Transcript autosave unavailable: {}", e))
+                                            .ok()
+                                    } else {
+                                        None
+                                    }));
+                                    let autosave_for_text = autosave.clone();
                                     let log_display_for_text = log_display.clone();
                                     let log_raw_for_text = log_raw.clone();
                                     let log_raw_for_cb = log_raw_for_clipboard.clone();
                                     let log_line_tx_for_text = log_line_tx.clone();
                                     let settings_for_text = settings_for_runtime.clone();
+                                    let metrics_for_text = session_metrics.clone();
+                                    let injection_history_for_text = injection_history.clone();
                                     let finalize_tx_for_network = finalize_tx.clone();
                                     let finalize_tx_for_transcript = finalize_tx.clone();
                                     let ui_handle_for_network = ui_handle_for_tokio.clone();
@@ -624,32 +2913,83 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
                                     let _ = ui_handle_for_tokio.upgrade_in_event_loop(|ui| {
                                         ui.set_is_recording(true);
-                                        ui.set_status_text("Listening...".into());
+                                        ui.set_status_text(tr!("Listening...").into());
+                                        ui.set_elapsed_text("0:00".into());
                                     });
 
                                     let (audio_to_net_tx, audio_to_net_rx) = mpsc::channel::<Vec<i16>>(50);
+                                    let backpressure = capture.backpressure();
+                                    let backpressure_for_audio = backpressure.clone();
+                                    let metrics_bus_for_audio = metrics_bus.clone();
+                                    let paused_for_audio = paused.clone();
+                                    let cmd_tx_for_silence = cmd_tx_for_runtime.clone();
+                                    let metrics_for_audio = session_metrics.clone();
+                                    let timeline_for_audio = timeline.clone();
+                                    let mut silence_detector = dsp::SilenceDetector::new(
+                                        current_settings.auto_stop_silence_secs,
+                                        audio_format.sample_rate,
+                                    );
                                     tokio::spawn(async move {
+                                        let mut auto_stopped = false;
                                         while let Some(chunk) = audio_rx.recv().await {
+                                            if paused_for_audio.load(Ordering::SeqCst) {
+                                                continue;
+                                            }
+                                            if !auto_stopped && silence_detector.observe(&chunk) {
+                                                auto_stopped = true;
+                                                info!("🔕 No speech detected, stopping automatically");
+                                                let _ = cmd_tx_for_silence.send(AppCommand::StopRecording);
+                                            }
                                             if overlay_visible_for_audio.load(std::sync::atomic::Ordering::SeqCst) {
-                                                let _ = audio_to_net_tx.send(chunk).await;
+                                                let samples = chunk.len();
+                                                timeline_for_audio.lock().unwrap().record_streamed(chunk.capture_offset, samples);
+                                                if audio_to_net_tx.send(chunk.samples).await.is_ok() {
+                                                    let summary = {
+                                                        let mut m = metrics_for_audio.lock().unwrap();
+                                                        m.record_audio(samples, audio_format.sample_rate);
+                                                        m.summary()
+                                                    };
+                                                    metrics_bus_for_audio.publish(metrics::MetricsUpdate::Audio {
+                                                        summary,
+                                                        queue_fill: backpressure_for_audio.fill(),
+                                                        falling_behind: backpressure_for_audio.is_falling_behind(),
+                                                    });
+                                                }
                                             }
                                         }
                                     });
 
+                                    let (client_text_tx, loopback) = if current_settings.meeting_mode {
+                                        let (me_tx, me_rx) = mpsc::channel::<network::TranscriptEvent>(100);
+                                        tokio::spawn(meeting::relay(me_rx, meeting::Speaker::Me, text_tx.clone()));
+                                        let loopback = meeting::LoopbackSession::start(
+                                            &current_settings,
+                                            &model_id,
+                                            paused.clone(),
+                                            text_tx,
+                                            log_line_tx.clone(),
+                                        )
+                                        .map_err(|e| {
+                                            warn!("⚠️ Meeting mode: system audio unavailable, transcribing the microphone only: {}", e)
+                                        })
+                                        .ok();
+                                        (me_tx, loopback)
+                                    } else {
+                                        (text_tx, None)
+                                    };
+
                                     tokio::spawn(async move {
-                                        {
-                                            let mut s = client_state.lock().unwrap();
-                                            s.transition_to_connecting();
-                                        }
+                                        client_state.update(|s| s.transition_to_connecting());
 
-                                        let result = client.run(audio_to_net_rx, network_stop_rx, text_tx, log_line_tx).await;
+                                        let result = client.run(audio_to_net_rx, network_stop_rx, client_text_tx, log_line_tx).await;
                                         if let Err(err) = result {
-                                            eprintln!("❌ Network client failed: {}", err);
-                                            if let Ok(mut s) = client_state.lock() {
-                                                *s = RecordingState::Error;
-                                            }
-                                            let _ = ui_handle_for_network.upgrade_in_event_loop(|ui| {
-                                                ui.set_status_text("Network error".into());
+                                            error!("❌ Network client failed: {}", err);
+                                            client_state.set(RecordingState::Error);
+                                            let banner = tr!("Speech service connection failed. {}", err.user_message());
+                                            let status = tr!("{} error", tr!(err.category()));
+                                            let _ = ui_handle_for_network.upgrade_in_event_loop(move |ui| {
+                                                ui.set_error_banner_text(banner.into());
+                                                ui.set_status_text(status.into());
                                                 ui.set_is_recording(false);
                                                 ui.set_has_error(true);
                                             });
@@ -664,7 +3004,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                         if should_finalize {
                                             let _ = finalize_tx_for_network.send(());
                                         }
-                                        println!("⚡ Network client task ended");
+                                        info!("⚡ Network client task ended");
                                     });
 
                                     tokio::spawn(async move {
@@ -691,38 +3031,106 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                         }
                                     });
 
-                                    tokio::spawn(async move {
-                                        let mut latest_partial = String::new();
-                                        while let Some(msg) = text_rx.recv().await {
-                                            {
-                                                let mut s = injection_state.lock().unwrap();
-                                                s.transition_to_recording();
-                                            }
+                                    // Nothing may be typed before a previewed transcript is confirmed.
+                                    let preview_before_inject = current_settings.preview_before_inject;
+                                    let preview_for_transcript = preview_for_tokio.clone();
+                                    let caption_feed_for_transcript = caption_feed_for_tokio.clone();
+                                    let preview_reply_for_transcript = preview_reply_for_tokio.clone();
+                                    let output_sinks = current_settings.output_sinks.clone();
+                                    let session_log_for_text = session_log_for_runtime.clone();
+                                    let latency_for_text = latency_for_runtime.clone();
+                                    let timeline_for_text = timeline.clone();
+                                    let subtitles_for_text = subtitles.clone();
+                                    let post_processed = Arc::new(Mutex::new(None));
+                                    let post_processed_for_text = post_processed.clone();
+                                    let mut sink_config = output::SinkConfig {
+                                        file: current_settings.output_file(),
+                                        webhook_url: current_settings.webhook_url.clone(),
+                                        markdown_template: current_settings.markdown_note_template(),
+                                        proxy_url: current_settings.proxy_url.clone(),
+                                        session_id: None,
+                                        started_at: Local::now(),
+                                    };
+                                    let mut live_injection = current_settings.live_partial_injection
+                                        && output_sinks.contains(&output::OutputSinkKind::Keyboard)
+                                        && !preview_before_inject
+                                        && !current_settings.transcript_only
+                                        && !current_settings.dictation_pad;
+                                    let live_injection_options = injector::InjectionOptions {
+                                        burst_delay_ms: current_settings.injection_burst_delay_ms,
+                                        chunk_size: current_settings.injection_chunk_size as usize,
+                                    };
+                                    let redactor = redaction::Redactor::from_settings(&current_settings);
+                                    let paragraph_pause_secs = current_settings.paragraph_pause_secs as f64;
+                                    let transcript_task = tokio::spawn(async move {
+                                        // The latest partial of each stream; meeting mode runs two.
+                                        let mut latest_partials: HashMap<Option<meeting::Speaker>, String> = HashMap::new();
+                                        let mut low_confidence_in_session = false;
+                                        // What live partial injection has typed into the target so far.
+                                        let mut live_typed = String::new();
+                                        // A commit with long pauses between its words is handled one
+                                        // part at a time, so each pause can start a paragraph.
+                                        let mut commit_parts: VecDeque<network::TranscriptEvent> = VecDeque::new();
+                                        loop {
+                                            let msg = match commit_parts.pop_front() {
+                                                Some(part) => part,
+                                                None => match text_rx.recv().await {
+                                                    Some(network::TranscriptEvent::Committed(segment)) if paragraph_pause_secs > 0.0 => {
+                                                        commit_parts.extend(
+                                                            segment
+                                                                .split_at_pauses(paragraph_pause_secs)
+                                                                .into_iter()
+                                                                .map(network::TranscriptEvent::Committed),
+                                                        );
+                                                        continue;
+                                                    }
+                                                    Some(msg) => msg,
+                                                    None => break,
+                                                },
+                                            };
+                                            injection_state.update(|s| s.transition_to_recording());
+                                            // Redacted before anything is shown, typed or logged.
+                                            let msg = redactor.apply_event(msg);
+                                            session_log_for_text.lock().unwrap().record_transcript(&msg);
 
                                             let mut was_committed = false;
                                             let mut is_error = false;
                                             let mut stop_requested_for_msg = false;
                                             let display_text = match msg {
-                                                network::TranscriptMessage::Partial(text) => {
-                                                    latest_partial = text;
+                                                network::TranscriptEvent::Partial(segment) => {
+                                                    let latest_partial = segment.text.clone();
+                                                    latest_partials.insert(segment.channel, segment.text);
+                                                    let partial = latest_partial.clone();
+                                                    let ts = Local::now().format("%H:%M:%S").to_string();
+                                                    let _ = ui_handle_for_transcript.upgrade_in_event_loop(move |ui| {
+                                                        with_transcript_rows(&ui, |rows| show_partial_row(rows, &ts, &partial));
+                                                    });
                                                     let committed = {
                                                         let pipeline = transcript_pipeline_for_text.lock().unwrap();
                                                         pipeline.committed_text().trim().to_string()
                                                     };
                                                     if committed.is_empty() {
-                                                        latest_partial.clone()
+                                                        latest_partial
                                                     } else if latest_partial.trim().is_empty() {
                                                         committed
                                                     } else {
                                                         format!("{} {}", committed, latest_partial.trim())
                                                     }
                                                 }
-                                                network::TranscriptMessage::Committed(text) => {
+                                                network::TranscriptEvent::Committed(segment) => {
+                                                    let received_at = std::time::Instant::now();
+                                                    // Measured from when the segment's last word was spoken, not the latest chunk sent.
+                                                    let captured_at = segment.end_secs.map(|end| timeline_for_text.lock().unwrap().captured_at(end));
+                                                    let transcription_latency = metrics_for_text.lock().unwrap().transcription_latency(captured_at, received_at);
+                                                    let mut delivery_latency = None;
+                                                    let text = segment.text.clone();
                                                     // Decide what text to actually commit:
                                                     // - If ElevenLabs sends an empty committed transcript, only
                                                     //   commit the current partial if we have one. Falling back to
                                                     //   the existing committed transcript would duplicate content.
                                                     let empty_commit = text.trim().is_empty();
+                                                    // Only this stream's partial; the other one is still going.
+                                                    let latest_partial = latest_partials.remove(&segment.channel).unwrap_or_default();
                                                     let base_text = if empty_commit {
                                                         if !latest_partial.trim().is_empty() {
                                                             latest_partial.trim().to_string()
@@ -732,83 +3140,292 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                                     } else {
                                                         text.clone()
                                                     };
-                                                    // Clear partial now that we've used it for empty-commit fallback.
-                                                    latest_partial.clear();
+                                                    // Prefixed phrases run as commands and are never typed.
+                                                    let voice_command = {
+                                                        let s = settings_for_text.lock().unwrap();
+                                                        (s.voice_commands_enabled && segment.channel != Some(meeting::Speaker::Them))
+                                                            .then(|| commands::match_command(&base_text, &s.voice_command_prefix, &s.voice_commands))
+                                                            .flatten()
+                                                    };
+                                                    let base_text = match voice_command {
+                                                        Some(commands::CommandMatch::Known(command)) => {
+                                                            run_voice_command(command, &log_line_tx_for_text).await;
+                                                            String::new()
+                                                        }
+                                                        Some(commands::CommandMatch::Unknown(phrase)) => {
+                                                            warn!("🗣️ No voice command matches \"{}\"", phrase);
+                                                            let _ = log_line_tx_for_text.send(format!("🗣️ [COMMAND] Unknown command \"{}\"", phrase));
+                                                            String::new()
+                                                        }
+                                                        None => base_text,
+                                                    };
+                                                    // "insert <name>" types the saved snippet instead; it
+                                                    // skips the rewrites below so it goes out as written.
+                                                    let expansion = (segment.channel != Some(meeting::Speaker::Them))
+                                                        .then(|| {
+                                                            let s = settings_for_text.lock().unwrap();
+                                                            snippets::match_snippet(&base_text, &s.snippets)
+                                                                .map(|snippet| snippets::expand(&snippet.body, Local::now().naive_local()))
+                                                        })
+                                                        .flatten();
+                                                    let base_text = if expansion.is_some() { String::new() } else { base_text };
+                                                    subtitles_for_text.lock().unwrap().push(
+                                                        &base_text,
+                                                        segment.start_secs.zip(segment.end_secs),
+                                                        &timeline_for_text.lock().unwrap(),
+                                                    );
 
-                                                    // Snapshot Gemini settings while holding the lock briefly.
-                                                    let (gemini_on, gkey, gmodel, gpreset, gcustom) = {
+                                                    // Snapshot the processing settings while holding the lock briefly.
+                                                    let (processor_pipeline, injection_options, low_confidence, skip_low_confidence, transcript_only, dictation_pad, session_llm_config, diarization, strip_labels) = {
                                                         let s = settings_for_text.lock().unwrap();
                                                         (
-                                                            s.gemini_enabled,
-                                                            s.gemini_api_key.clone(),
-                                                            s.gemini_model.clone(),
-                                                            s.gemini_prompt_preset.clone(),
-                                                            s.gemini_custom_prompt.clone(),
+                                                            processors::ProcessorPipeline::from_settings(&s),
+                                                            injector::InjectionOptions {
+                                                                burst_delay_ms: s.injection_burst_delay_ms,
+                                                                chunk_size: s.injection_chunk_size as usize,
+                                                            },
+                                                            segment.is_low_confidence(s.low_confidence_threshold),
+                                                            s.skip_low_confidence_injection,
+                                                            s.transcript_only,
+                                                            s.dictation_pad,
+                                                            (s.llm_enabled && s.llm_scope == llm::LlmScope::WholeSession).then(|| llm::LlmConfig::from_settings(&s)),
+                                                            s.diarization_enabled,
+                                                            s.strip_speaker_labels,
                                                         )
                                                     };
-                                                    // Lock is dropped here before any await.
-
-                                                    let final_text = if gemini_on {
-                                                        println!("🤖 [Gemini] Rewriting committed text...");
-                                                        gemini::rewrite_text(&gkey, &gmodel, &gpreset, &gcustom, &base_text).await
-                                                    } else {
-                                                        base_text
+                                                    low_confidence_in_session |= low_confidence;
+                                                    let base_text = match segment.speaker_labelled_text() {
+                                                        Some(labelled) if diarization && !base_text.is_empty() && !empty_commit && segment.channel.is_none() => labelled,
+                                                        _ => base_text,
                                                     };
+                                                    // Lock is dropped here before any await.
 
+                                                    let final_text = processor_pipeline.run(base_text, &log_line_tx_for_text).await;
+                                                    // Processors can bring filtered words back.
+                                                    let final_text = redactor.apply(&final_text);
                                                     let final_text = final_text.trim().trim_start_matches('-').trim().to_string();
-                                                    stop_requested_for_msg = {
+                                                    // Snippets and identifier styles skip `format_segment` too,
+                                                    // keeping their case, spacing and line breaks.
+                                                    let verbatim = expansion.is_some() || processor_pipeline.output_is_verbatim();
+                                                    let final_text = match expansion {
+                                                        Some(snippet) => redactor.apply(snippet.trim()),
+                                                        None => final_text,
+                                                    };
+                                                    // Only the microphone's final commit closes a meeting, once
+                                                    // its last part is in.
+                                                    stop_requested_for_msg = segment.channel != Some(meeting::Speaker::Them) && commit_parts.is_empty() && {
                                                         let pipeline = transcript_pipeline_for_text.lock().unwrap();
                                                         pipeline.stop_requested()
                                                     };
 
+                                                    let span = segment.start_secs.zip(segment.end_secs);
                                                     let aggregated = {
                                                         let mut pipeline = transcript_pipeline_for_text.lock().unwrap();
+                                                        // Labels stay in the transcript; only the typed text drops them.
+                                                        pipeline.set_strip_speaker_labels(strip_labels);
                                                         if final_text.is_empty() {
                                                             pipeline.committed_text().to_string()
+                                                        } else if segment.channel == Some(meeting::Speaker::Them) {
+                                                            // The other side of a meeting is never typed.
+                                                            if verbatim {
+                                                                pipeline.push_heard_verbatim_segment(&final_text, span)
+                                                            } else {
+                                                                pipeline.push_heard_segment(&final_text, span)
+                                                            }
+                                                        } else if verbatim {
+                                                            pipeline.push_verbatim_segment(&final_text, span)
                                                         } else {
-                                                            pipeline.push_fragment(&final_text)
+                                                            pipeline.push_segment(&final_text, span)
                                                         }
                                                     };
                                                     if !final_text.is_empty() {
-                                                        let ts = Local::now().format("%H:%M:%S");
-                                                        let display: SharedString = format!("[{}] {}", ts, final_text).into();
-                                                        {
-                                                            let mut history = transcript_history_for_text.lock().unwrap();
-                                                            let mut raw = transcript_raw_for_text.lock().unwrap();
-                                                            history.push(display);
-                                                            raw.push(final_text.clone());
-                                                            let items = history.clone();
-                                                            let _ = ui_handle_for_transcript.upgrade_in_event_loop(move |ui| {
-                                                                ui.set_transcript_history(ModelRc::new(VecModel::from(items)));
-                                                            });
-                                                            *transcript_raw_for_cb.lock().unwrap() = raw.clone();
+                                                        metrics_for_text.lock().unwrap().record_transcript(&final_text);
+                                                        let ts = Local::now().format("%H:%M:%S").to_string();
+                                                        let shown = match segment.confidence {
+                                                            Some(confidence) if low_confidence => {
+                                                                tr!("⚠ {} (confidence {}%)", final_text, format!("{:.0}", confidence * 100.0))
+                                                            }
+                                                            _ => final_text.clone(),
+                                                        };
+                                                        transcript_panel_for_text
+                                                            .lock()
+                                                            .unwrap()
+                                                            .push(format!("[{}] {}", ts, shown), final_text.clone());
+                                                        if let Some(autosave) = autosave_for_text.lock().unwrap().as_mut() {
+                                                            if let Err(e) = autosave.append(&final_text) {
+                                                                warn!("⚠️ Failed to autosave transcript: {}", e);
+                                                            }
                                                         }
+                                                        let row = TranscriptSegment {
+                                                            time: ts.into(),
+                                                            text: shown.into(),
+                                                            is_final: true,
+                                                        };
+                                                        let _ = ui_handle_for_transcript.upgrade_in_event_loop(move |ui| {
+                                                            with_transcript_rows(&ui, |rows| {
+                                                                drop_partial_row(rows);
+                                                                rows.push(row);
+                                                            });
+                                                        });
                                                         let _ = log_line_tx_for_text.send(format!("⌨ [TRANSCRIPT] {}", final_text));
                                                     }
                                                     if stop_requested_for_msg {
-                                                        let final_payload = aggregated.trim().to_string();
-                                                        if !final_payload.is_empty() {
-                                                            println!("⌨ Injecting full transcript into active window");
-                                                            let to_inject = format!("{} ", final_payload);
-                                                            if let Err(e) = injector::inject_text(&to_inject) {
-                                                                eprintln!("❌ Injection Error: {}", e);
+                                                        let final_payload = transcript_pipeline_for_text.lock().unwrap().typed_text().trim().to_string();
+                                                        let final_payload = match &session_llm_config {
+                                                            Some(config) if !final_payload.is_empty() => {
+                                                                let _ = ui_handle_for_transcript.upgrade_in_event_loop(|ui| {
+                                                                    ui.set_status_text(tr!("Post-processing transcript...").into());
+                                                                });
+                                                                let processed = post_process_or_keep(config, final_payload.clone(), &log_line_tx_for_text).await;
+                                                                let processed = redactor.apply(&processed);
+                                                                if processed.trim() != final_payload {
+                                                                    *post_processed_for_text.lock().unwrap() = Some(processed.trim().to_string());
+                                                                }
+                                                                processed
+                                                            }
+                                                            _ => final_payload,
+                                                        };
+                                                        if dictation_pad && !final_payload.is_empty() {
+                                                            info!("📝 Appending transcript to the dictation pad");
+                                                            let _ = ui_handle_for_transcript.upgrade_in_event_loop(move |ui| {
+                                                                let pad = ui.get_pad_text();
+                                                                let segment = pipeline::format_segment(&pad, &final_payload);
+                                                                ui.set_pad_text(format!("{}{}", pad, segment).into());
+                                                                ui.set_status_text(tr!("Transcript added to the dictation pad").into());
+                                                            });
+                                                        } else if transcript_only && !final_payload.is_empty() {
+                                                            info!("📝 Transcript-only mode, not injecting");
+                                                            let _ = ui_handle_for_transcript.upgrade_in_event_loop(|ui| {
+                                                                ui.set_status_text(tr!("Transcript ready - review and copy it from the Transcripts tab").into());
+                                                            });
+                                                        } else if low_confidence_in_session && skip_low_confidence && !final_payload.is_empty() {
+                                                            warn!("⚠ Low-confidence transcript, skipping auto-injection");
+                                                            if !live_typed.is_empty() {
+                                                                if let Err(e) = sync_live_text(&live_typed, "", injection_target, injection_options).await {
+                                                                    warn!("⚠️ Could not remove the live partial text: {}", e);
+                                                                }
+                                                            }
+                                                            let _ = ui_handle_for_transcript.upgrade_in_event_loop(|ui| {
+                                                                ui.set_status_text(tr!("Low confidence - transcript not injected, copy it from history").into());
+                                                            });
+                                                        } else if !final_payload.is_empty() {
+                                                            // Time in the preview is the user's, not the pipeline's.
+                                                            let mut preview_wait = std::time::Duration::ZERO;
+                                                            let confirmed = if preview_before_inject {
+                                                                info!("👀 Waiting for the transcript to be confirmed");
+                                                                let _ = ui_handle_for_transcript.upgrade_in_event_loop(|ui| {
+                                                                    ui.set_status_text(tr!("Review the transcript before it is typed").into());
+                                                                });
+                                                                let waiting_since = std::time::Instant::now();
+                                                                let confirmed = confirm_injection(&preview_for_transcript, &preview_reply_for_transcript, final_payload.clone())
+                                                                    .await
+                                                                    .map(|text| text.trim().to_string())
+                                                                    .filter(|text| !text.is_empty());
+                                                                preview_wait = waiting_since.elapsed();
+                                                                confirmed
+                                                            } else {
+                                                                Some(final_payload.clone())
+                                                            };
+                                                            if let Some(final_payload) = confirmed {
+                                                                // With live partials on, the keyboard sink only retypes the corrected tail.
+                                                                let keyboard = output::KeyboardSink::new(injection_target, injection_options, live_typed.clone());
+                                                                let sinks = output::build_sinks(&output_sinks, &sink_config, keyboard);
+                                                                info!(
+                                                                    "📤 Sending transcript to: {}",
+                                                                    sinks.iter().map(|sink| sink.kind().label()).collect::<Vec<_>>().join(", ")
+                                                                );
+                                                                let text = final_payload.clone();
+                                                                let results = tokio::task::spawn_blocking(move || output::deliver_all(sinks, &text))
+                                                                    .await
+                                                                    .unwrap_or_else(|e| vec![(output::OutputSinkKind::Keyboard, Err(EchoError::injection(e.to_string())))]);
+                                                                let mut injected = None;
+                                                                for (kind, result) in results {
+                                                                    session_log_for_text.lock().unwrap().record_output(kind.label(), &result);
+                                                                    match (kind, result) {
+                                                                        (output::OutputSinkKind::Keyboard, result) => injected = Some(result),
+                                                                        (kind, Err(e)) => {
+                                                                            error!("❌ Output to {} failed: {}", kind.label(), e);
+                                                                            let status = e.user_message();
+                                                                            let _ = ui_handle_for_transcript.upgrade_in_event_loop(move |ui| {
+                                                                                ui.set_status_text(status.into());
+                                                                                ui.set_has_error(true);
+                                                                            });
+                                                                        }
+                                                                        (kind, Ok(_)) => debug!("📤 Transcript sent to {}", kind.label()),
+                                                                    }
+                                                                }
+                                                                let injected_text = format!("{} ", final_payload);
+                                                                if let Some(injected) = injected {
+                                                                    if injected == Ok(false) {
+                                                                        warn!("⚠ Focus moved away from the target window, skipping injection");
+                                                                        let _ = ui_handle_for_transcript.upgrade_in_event_loop(|ui| {
+                                                                            ui.set_status_text(tr!("Focus moved - transcript not injected, copy it from history").into());
+                                                                        });
+                                                                    } else if injected.is_ok() {
+                                                                        metrics_for_text.lock().unwrap().record_injection(&final_payload);
+                                                                        delivery_latency = Some(received_at.elapsed().saturating_sub(preview_wait));
+                                                                        injection_history_for_text.lock().unwrap().record(&injected_text);
+                                                                        debug!("⌨ Injection strategies: {}", injector::telemetry().summary());
+                                                                    }
+                                                                    if let Err(e) = injected {
+                                                                        error!("❌ Injection Error: {}", e);
+                                                                        let status = e.user_message();
+                                                                        // Typing will keep failing until something changes, so say how.
+                                                                        let banner = if e == EchoError::ElevatedTarget { status.clone() } else { String::new() };
+                                                                        let _ = ui_handle_for_transcript.upgrade_in_event_loop(move |ui| {
+                                                                            if !banner.is_empty() {
+                                                                                ui.set_error_banner_text(banner.into());
+                                                                                ui.set_error_banner_suggests_admin(true);
+                                                                            }
+                                                                            ui.set_status_text(status.into());
+                                                                            ui.set_has_error(true);
+                                                                            ui.set_is_recording(false);
+                                                                        });
+                                                                    }
+                                                                }
+                                                            } else {
+                                                                info!("🗑 Transcript discarded from the preview");
                                                                 let _ = ui_handle_for_transcript.upgrade_in_event_loop(|ui| {
-                                                                    ui.set_status_text("Injection error - check focused window and permissions".into());
-                                                                    ui.set_has_error(true);
-                                                                    ui.set_is_recording(false);
+                                                                    ui.set_status_text(tr!("Transcript discarded - it is still in history").into());
                                                                 });
                                                             }
                                                         }
                                                     }
+                                                    if let Some(transcription) = transcription_latency.filter(|_| !final_text.is_empty()) {
+                                                        let sample = metrics::LatencySample { transcription, delivery: delivery_latency };
+                                                        let summary = {
+                                                            let mut latency = latency_for_text.lock().unwrap();
+                                                            latency.record(sample);
+                                                            latency.summary()
+                                                        };
+                                                        debug!("⏱ Latency {} ({})", metrics::format_latency(sample.total()), summary);
+                                                        let _ = ui_handle_for_transcript.upgrade_in_event_loop(move |ui| {
+                                                            ui.set_latency_text(summary.into());
+                                                        });
+                                                    }
                                                     was_committed = true;
                                                     aggregated
                                                 }
-                                                network::TranscriptMessage::Error(err_json) => {
-                                                    latest_partial.clear();
+                                                network::TranscriptEvent::SessionStarted { session_id } => {
+                                                    let _ = log_line_tx_for_text.send(format!(
+                                                        "✅ Speech session started{}",
+                                                        session_id.as_ref().map(|id| format!(" ({})", id)).unwrap_or_default()
+                                                    ));
+                                                    sink_config.session_id = session_id;
+                                                    continue;
+                                                }
+                                                network::TranscriptEvent::EndOfStream => {
+                                                    let _ = log_line_tx_for_text.send("🔌 Speech service closed the stream".to_string());
+                                                    continue;
+                                                }
+                                                network::TranscriptEvent::Error(err) => {
+                                                    latest_partials.clear();
                                                     is_error = true;
-                                                    let friendly = format!("Error from speech service:\n{}", err_json);
-                                                    let _ = ui_handle_for_transcript.upgrade_in_event_loop(|ui| {
-                                                        ui.set_status_text("Speech service error".into());
+                                                    let friendly = tr!("Error from speech service ({}):\n{}", err.kind, err.message);
+                                                    let banner = tr!("Speech service error: {}", err);
+                                                    let _ = ui_handle_for_transcript.upgrade_in_event_loop(move |ui| {
+                                                        ui.set_error_banner_text(banner.into());
+                                                        ui.set_status_text(tr!("Speech service error").into());
                                                         ui.set_is_recording(false);
                                                         ui.set_has_error(true);
                                                     });
@@ -816,17 +3433,42 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                                 }
                                             };
 
+                                            if let Some(feed) = caption_feed_for_transcript.as_ref().filter(|_| !is_error) {
+                                                feed.set(&display_text, was_committed);
+                                            }
+
+                                            if live_injection && !is_error && !(was_committed && stop_requested_for_msg) {
+                                                // Typed text plus this side's partial, never the other side's.
+                                                let typed_text = {
+                                                    let pipeline = transcript_pipeline_for_text.lock().unwrap();
+                                                    let own_partial = latest_partials
+                                                        .iter()
+                                                        .filter(|(speaker, _)| **speaker != Some(meeting::Speaker::Them))
+                                                        .map(|(_, partial)| partial.trim())
+                                                        .find(|partial| !partial.is_empty());
+                                                    match own_partial {
+                                                        Some(partial) if !pipeline.typed_text().trim().is_empty() => {
+                                                            format!("{} {}", pipeline.typed_text().trim(), partial)
+                                                        }
+                                                        Some(partial) => partial.to_string(),
+                                                        None => pipeline.typed_text().to_string(),
+                                                    }
+                                                };
+                                                match sync_live_text(&live_typed, &typed_text, injection_target, live_injection_options).await {
+                                                    Ok(true) => live_typed = typed_text,
+                                                    Ok(false) => {
+                                                        warn!("⚠ Focus moved away from the target window, pausing live partials");
+                                                        live_injection = false;
+                                                    }
+                                                    Err(e) => {
+                                                        warn!("⚠️ Live partial injection failed, waiting for the final text: {}", e);
+                                                        live_injection = false;
+                                                    }
+                                                }
+                                            }
+
                                             let aggregated_for_overlay = display_text.clone();
                                             let hide_overlay = (was_committed && stop_requested_for_msg) || is_error;
-                                            let text_for_ui = if was_committed || is_error {
-                                                display_text.clone()
-                                            } else {
-                                                let pipeline = transcript_pipeline_for_text.lock().unwrap();
-                                                pipeline.committed_text().to_string()
-                                            };
-                                            let _ = ui_handle_for_transcript.upgrade_in_event_loop(move |ui| {
-                                                ui.set_transcript(text_for_ui.into());
-                                            });
                                             let overlay_visible_setter = overlay_visible_for_transcript.clone();
                                             let _ = overlay_handle_for_transcript
                                                 .upgrade_in_event_loop(move |overlay| {
@@ -861,23 +3503,72 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                         }
                                     });
 
+                                    let timer_task = {
+                                        let ui_handle = ui_handle_for_tokio.clone();
+                                        let metrics_bus = metrics_bus.clone();
+                                        let cmd_tx = cmd_tx_for_runtime.clone();
+                                        let max_minutes = current_settings.max_session_minutes;
+                                        let action = current_settings.max_session_action;
+                                        let mut guard = metrics::MaxDurationGuard::new(max_minutes);
+                                        let started = std::time::Instant::now();
+                                        tokio::spawn(async move {
+                                            let mut ticks = tokio::time::interval(std::time::Duration::from_secs(1));
+                                            loop {
+                                                ticks.tick().await;
+                                                let elapsed = started.elapsed();
+                                                metrics_bus.publish(metrics::MetricsUpdate::Elapsed(elapsed));
+                                                if !guard.check(elapsed) {
+                                                    continue;
+                                                }
+                                                match action {
+                                                    metrics::MaxDurationAction::Stop => {
+                                                        warn!("⏱ Session reached the {}-minute limit, stopping", max_minutes);
+                                                        let _ = cmd_tx.send(AppCommand::StopRecording);
+                                                    }
+                                                    metrics::MaxDurationAction::Warn => {
+                                                        warn!("⏱ Session has been recording for over {} minutes", max_minutes);
+                                                        let status = tr!("Recording for over {} minutes", max_minutes);
+                                                        let _ = ui_handle.upgrade_in_event_loop(move |ui| {
+                                                            ui.set_status_text(status.into());
+                                                        });
+                                                    }
+                                                }
+                                            }
+                                        })
+                                    };
+
                                     active_session = Some(Session {
                                         state,
-                                        _audio_stream: Some(stream),
+                                        audio: Some(capture),
+                                        detached_audio: None,
+                                        reconnect_attempts: 0,
                                         network_stop_tx: Some(network_stop_tx),
                                         transcript_pipeline,
+                                        paused,
+                                        metrics: session_metrics,
+                                        backpressure,
+                                        audio_format,
+                                        transcript_task,
+                                        timer_task,
+                                        autosave,
+                                        loopback,
+                                        recording_path,
+                                        subtitles,
+                                        post_processed,
+                                        cancelled: false,
                                     });
                                     if let Some(session) = active_session.as_ref() {
-                                        if let Some(tx) = session.network_stop_tx.as_ref() {
-                                            let _ = tx.send(network::ControlMessage::Start);
-                                        }
+                                        session.send_control(network::ControlMessage::Start);
                                     }
                                     }
                                     Err(e) => {
-                                    eprintln!("❌ Failed to start audio: {}", e);
+                                    error!("❌ Failed to start audio: {}", e);
+                                    if wake_word_armed() {
+                                        wake_listener = start_listener().ok();
+                                    }
                                     let _ = ui_handle_for_tokio.upgrade_in_event_loop(move |ui| {
                                         ui.set_is_recording(false);
-                                        ui.set_status_text(format!("Audio error: {}", e).into());
+                                        ui.set_status_text(e.user_message().into());
                                         ui.set_active_tab(2);
                                     });
                                     let _ = overlay_handle_for_tokio.upgrade_in_event_loop(|overlay| {
@@ -891,24 +3582,454 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                     }
                                     }
                                     AppCommand::StopRecording => {
-                                    println!("⚡ Stop requested");
+                                    info!("⚡ Stop requested");
                                     let _ = ui_handle_for_tokio.upgrade_in_event_loop(|ui| {
-                                    ui.set_status_text("Finalizing...".into());
+                                    ui.set_status_text(tr!("Finalizing...").into());
                                     ui.set_is_recording(false);
+                                    ui.set_is_paused(false);
                                     });
 
                                     if let Some(session) = active_session.as_mut() {
-                                    {
-                                    let mut s = session.state.lock().unwrap();
-                                    if s.can_stop() {
-                                        s.transition_to_finalizing();
+                                    session.request_stop();
                                     }
                                     }
-                                    if let Ok(mut pipeline) = session.transcript_pipeline.lock() {
-                                    pipeline.request_stop();
+                                    AppCommand::CancelRecording => {
+                                        let Some(session) = active_session.as_mut() else {
+                                            continue;
+                                        };
+                                        info!("✖ Recording cancelled, discarding the transcript");
+                                        session.cancel();
+                                        let _ = finalize_tx.send(());
+                                    }
+                                    AppCommand::QuotaExhausted { session: id, warning } => {
+                                        // The session may already be over, or a newer one running.
+                                        let Some(session) = active_session.as_mut().filter(|s| s.state.id() == id) else {
+                                            continue;
+                                        };
+                                        info!("✖ {}, discarding the session", warning);
+                                        session.cancel();
+                                        let _ = finalize_tx.send(());
+                                        let status = tr!("{} - not starting", warning);
+                                        let _ = ui_handle_for_tokio.upgrade_in_event_loop(move |ui| {
+                                            ui.set_has_error(true);
+                                            ui.set_error_banner_text(status.into());
+                                        });
+                                    }
+                                    AppCommand::Shutdown => {
+                                        let Some(session) = active_session.as_mut() else {
+                                            info!("👋 Quitting");
+                                            let _ = slint::quit_event_loop();
+                                            continue;
+                                        };
+                                        info!("👋 Quit requested, finalizing the active session first");
+                                        shutting_down = true;
+                                        // Nothing said after Quit should reach the transcript.
+                                        session.paused.store(true, Ordering::SeqCst);
+                                        session.request_stop();
+                                        let _ = ui_handle_for_tokio.upgrade_in_event_loop(|ui| {
+                                            ui.set_status_text(tr!("Finishing the last sentence before quitting...").into());
+                                            ui.set_is_recording(false);
+                                            ui.set_is_paused(false);
+                                        });
+                                        tokio::spawn(async move {
+                                            tokio::time::sleep(SHUTDOWN_TIMEOUT).await;
+                                            warn!(
+                                                "⚠️ Session did not finalize within {}s, quitting anyway",
+                                                SHUTDOWN_TIMEOUT.as_secs()
+                                            );
+                                            let _ = slint::quit_event_loop();
+                                        });
+                                    }
+                                    AppCommand::ArmWakeWord(armed) => {
+                                        wake_listener = None;
+                                        if !armed || active_session.is_some() {
+                                            continue;
+                                        }
+                                        match start_listener() {
+                                            Ok(listener) => {
+                                                info!("👂 Listening for the wake word");
+                                                wake_listener = Some(listener);
+                                            }
+                                            Err(e) => {
+                                                warn!("⚠️ Wake word listener unavailable: {}", e);
+                                                let _ = ui_handle_for_tokio.upgrade_in_event_loop(move |ui| {
+                                                    ui.set_status_text(tr!("Wake word unavailable: {}", e).into());
+                                                });
+                                            }
+                                        }
+                                    }
+                                    AppCommand::TestMicrophone { enabled, loopback } => {
+                                        mic_test = None;
+                                        if !enabled {
+                                            info!("🎙 Microphone test stopped");
+                                            let _ = ui_handle_for_tokio.upgrade_in_event_loop(|ui| {
+                                                ui.set_mic_test_level(0.0);
+                                            });
+                                            if wake_listener.is_none() && wake_word_armed() && active_session.is_none() {
+                                                wake_listener = start_listener().ok();
+                                            }
+                                            continue;
+                                        }
+                                        if active_session.is_some() {
+                                            let _ = ui_handle_for_tokio.upgrade_in_event_loop(|ui| {
+                                                ui.set_mic_testing(false);
+                                                ui.set_status_text(tr!("Stop dictating before testing the microphone").into());
+                                            });
+                                            continue;
+                                        }
+                                        wake_listener = None;
+                                        let test_settings = settings_for_runtime.lock().unwrap().clone();
+                                        let ui_for_level = ui_handle_for_tokio.clone();
+                                        let started = mic_test::MicTest::start(&test_settings, loopback, move |level| {
+                                            let _ = ui_for_level.upgrade_in_event_loop(move |ui| {
+                                                ui.set_mic_test_level(level);
+                                            });
+                                        });
+                                        match started {
+                                            Ok(test) => {
+                                                info!("🎙 Microphone test started (playback {})", if loopback { "on" } else { "off" });
+                                                mic_test = Some(test);
+                                            }
+                                            Err(e) => {
+                                                warn!("⚠️ Microphone test failed: {}", e);
+                                                let status = tr!("Microphone test failed. {}", e.user_message());
+                                                let _ = ui_handle_for_tokio.upgrade_in_event_loop(move |ui| {
+                                                    ui.set_mic_testing(false);
+                                                    ui.set_status_text(status.into());
+                                                });
+                                                if wake_word_armed() {
+                                                    wake_listener = start_listener().ok();
+                                                }
+                                            }
+                                        }
+                                    }
+                                    AppCommand::RecordWakeWordSample => {
+                                        let status = match wake_listener.as_ref() {
+                                            Some(listener) => {
+                                                listener.record_sample.store(true, Ordering::SeqCst);
+                                                tr!("Say the wake word now...")
+                                            }
+                                            None => tr!("Arm the wake word while idle to record a sample"),
+                                        };
+                                        let _ = ui_handle_for_tokio.upgrade_in_event_loop(move |ui| {
+                                            ui.set_status_text(status.into());
+                                        });
+                                    }
+                                    AppCommand::UndoLastInjection => {
+                                        let last = injection_history.lock().unwrap().pop_last();
+                                        let Some(count) = last else {
+                                            let _ = ui_handle_for_tokio.upgrade_in_event_loop(|ui| {
+                                                ui.set_status_text(tr!("Nothing to undo").into());
+                                            });
+                                            continue;
+                                        };
+                                        info!("↩ Undoing last injection ({} characters)", count);
+                                        let undone = tokio::task::spawn_blocking(move || {
+                                            injector::send_backspaces(count)
+                                        })
+                                        .await
+                                        .unwrap_or_else(|e| Err(EchoError::injection(e.to_string())));
+                                        let status = match undone {
+                                            Ok(()) => tr!("Last injection undone"),
+                                            Err(e) => {
+                                                error!("❌ Undo Error: {}", e);
+                                                tr!("Undo failed. {}", e.user_message())
+                                            }
+                                        };
+                                        let _ = ui_handle_for_tokio.upgrade_in_event_loop(move |ui| {
+                                            ui.set_status_text(status.into());
+                                        });
+                                    }
+                                    AppCommand::TypeText { text, target } => {
+                                        let options = {
+                                            let s = settings_for_runtime.lock().unwrap();
+                                            injector::InjectionOptions {
+                                                burst_delay_ms: s.injection_burst_delay_ms,
+                                                chunk_size: s.injection_chunk_size as usize,
+                                            }
+                                        };
+                                        info!("⌨ Typing {} corrected characters", text.chars().count());
+                                        let typed_text = text.clone();
+                                        let typed = tokio::task::spawn_blocking(move || {
+                                            if let Some(target) = target {
+                                                injector::prepare_target(target, injector::TargetLock::Refocus)?;
+                                            }
+                                            injector::inject_text_with(&typed_text, &options)
+                                        })
+                                        .await
+                                        .unwrap_or_else(|e| Err(EchoError::injection(e.to_string())));
+                                        let status = match typed {
+                                            Ok(()) => {
+                                                injection_history.lock().unwrap().record(&text);
+                                                tr!("Selection typed into the focused app")
+                                            }
+                                            Err(e) => {
+                                                error!("❌ Injection Error: {}", e);
+                                                e.user_message()
+                                            }
+                                        };
+                                        let _ = ui_handle_for_tokio.upgrade_in_event_loop(move |ui| {
+                                            ui.set_status_text(status.into());
+                                        });
+                                    }
+                                    AppCommand::RefreshElevenModels => {
+                                        let (api_key, proxy_url, selected) = {
+                                            let s = settings_for_runtime.lock().unwrap();
+                                            (s.api_key.trim().to_string(), s.proxy_url.clone(), s.eleven_model_id.clone())
+                                        };
+                                        if api_key.is_empty() {
+                                            let _ = ui_handle_for_tokio.upgrade_in_event_loop(|ui| {
+                                                ui.set_status_text(tr!("Enter an ElevenLabs API key to fetch models").into());
+                                            });
+                                            continue;
+                                        }
+                                        let ui_handle = ui_handle_for_tokio.clone();
+                                        tokio::spawn(async move {
+                                            let fetched = match proxy::resolve(&proxy_url) {
+                                                Ok(proxy) => network::fetch_realtime_models(&api_key, proxy.as_ref()).await,
+                                                Err(e) => Err(e),
+                                            };
+                                            let (models, status) = match fetched {
+                                                Ok(models) => {
+                                                    let status = tr!("Found {} realtime model(s)", models.len());
+                                                    (models, status)
+                                                }
+                                                Err(e) => {
+                                                    error!("❌ Model list error: {}", e);
+                                                    (Vec::new(), tr!("Could not fetch models: {}", e))
+                                                }
+                                            };
+                                            let options = eleven_model_options(models, &selected);
+                                            let _ = ui_handle.upgrade_in_event_loop(move |ui| {
+                                                let options: Vec<SharedString> = options.into_iter().map(Into::into).collect();
+                                                ui.set_eleven_model_options(ModelRc::new(VecModel::from(options)));
+                                                ui.set_status_text(status.into());
+                                            });
+                                        });
+                                    }
+                                    AppCommand::LookUpQuota => {
+                                        let current_settings = settings_for_runtime.lock().unwrap().clone();
+                                        if !quota_cache.lock().unwrap().contains_key(current_settings.api_key.trim()) {
+                                            refresh_quota(&current_settings, &quota_cache);
+                                        }
+                                    }
+                                    AppCommand::ValidateElevenKey => {
+                                        let (api_key, proxy_url) = {
+                                            let s = settings_for_runtime.lock().unwrap();
+                                            (s.api_key.trim().to_string(), s.proxy_url.clone())
+                                        };
+                                        if api_key.is_empty() {
+                                            let _ = ui_handle_for_tokio.upgrade_in_event_loop(|ui| {
+                                                ui.set_api_key_status_text(tr!("Enter an ElevenLabs API key first").into());
+                                            });
+                                            continue;
+                                        }
+                                        let ui_handle = ui_handle_for_tokio.clone();
+                                        let quota_cache = quota_cache.clone();
+                                        let _ = ui_handle.upgrade_in_event_loop(|ui| {
+                                            ui.set_validating_api_key(true);
+                                            ui.set_api_key_status_text(tr!("Checking key...").into());
+                                        });
+                                        tokio::spawn(async move {
+                                            let result = match proxy::resolve(&proxy_url) {
+                                                Ok(proxy) => network::validate_api_key(&api_key, proxy.as_ref()).await,
+                                                Err(e) => Err(EchoError::Config(e)),
+                                            };
+                                            let (status, valid) = match result {
+                                                Ok(info) => {
+                                                    info!("🔑 ElevenLabs key valid ({} plan)", info.tier);
+                                                    let summary = info.summary();
+                                                    quota_cache.lock().unwrap().insert(api_key, QuotaStatus::Known(info));
+                                                    (summary, true)
+                                                }
+                                                Err(e) => {
+                                                    warn!("⚠️ ElevenLabs key check failed: {}", e);
+                                                    (e.user_message(), false)
+                                                }
+                                            };
+                                            let _ = ui_handle.upgrade_in_event_loop(move |ui| {
+                                                ui.set_validating_api_key(false);
+                                                ui.set_api_key_valid(valid);
+                                                ui.set_api_key_status_text(status.into());
+                                            });
+                                        });
+                                    }
+                                    AppCommand::DownloadVoskModel(model) => {
+                                        let proxy_url = settings_for_runtime.lock().unwrap().proxy_url.clone();
+                                        let ui_handle = ui_handle_for_tokio.clone();
+                                        let _ = ui_handle.upgrade_in_event_loop(|ui| {
+                                            ui.set_vosk_downloading(true);
+                                            ui.set_vosk_model_status(tr!("Starting download...").into());
+                                        });
+                                        info!("⬇️ Downloading Vosk model {}", model.name);
+                                        tokio::spawn(async move {
+                                            let reported_mb = AtomicU64::new(u64::MAX);
+                                            let progress_handle = ui_handle.clone();
+                                            let progress = move |what: &str, received: u64, total: Option<u64>| {
+                                                let mb = received / 1_000_000;
+                                                if reported_mb.swap(mb, Ordering::Relaxed) == mb {
+                                                    return;
+                                                }
+                                                let status = match total {
+                                                    Some(total) => tr!("Downloading {}... {} / {} MB", tr!(what), mb, total / 1_000_000),
+                                                    None => tr!("Downloading {}... {} MB", tr!(what), mb),
+                                                };
+                                                let _ = progress_handle.upgrade_in_event_loop(move |ui| {
+                                                    ui.set_vosk_model_status(status.into());
+                                                });
+                                            };
+                                            let result = match proxy::resolve(&proxy_url) {
+                                                Ok(proxy) => {
+                                                    vosk_models::download(model, &settings::vosk_models_dir(), proxy.as_ref(), progress).await
+                                                }
+                                                Err(e) => Err(EchoError::Config(e)),
+                                            };
+                                            let status = match result {
+                                                Ok(path) => {
+                                                    info!("✅ Vosk model ready at {}", path.display());
+                                                    vosk_model_status(&model)
+                                                }
+                                                Err(e) => {
+                                                    error!("❌ Vosk model download failed: {}", e);
+                                                    tr!("Download failed. {}", e.user_message())
+                                                }
+                                            };
+                                            let _ = ui_handle.upgrade_in_event_loop(move |ui| {
+                                                ui.set_vosk_downloading(false);
+                                                ui.set_vosk_model_status(status.into());
+                                            });
+                                        });
+                                    }
+                                    AppCommand::RetranscribeHistory { id, provider: kind } => {
+                                        let recording = history_for_runtime
+                                            .lock()
+                                            .unwrap()
+                                            .get(id)
+                                            .and_then(|entry| entry.recording.clone());
+                                        let Some(recording) = recording.filter(|path| path.exists()) else {
+                                            let _ = ui_handle_for_tokio.upgrade_in_event_loop(|ui| {
+                                                ui.set_status_text(tr!("The recording of this session is no longer there").into());
+                                            });
+                                            continue;
+                                        };
+                                        // The session trace and race report belong to live sessions.
+                                        let options = settings::AppSettings {
+                                            provider: kind,
+                                            record_session_trace: false,
+                                            race_provider: None,
+                                            ..settings_for_runtime.lock().unwrap().clone()
+                                        };
+                                        let client = match provider::create_provider(&options, &options.eleven_model_id) {
+                                            Ok(client) => client,
+                                            Err(e) => {
+                                                let status = tr!("Cannot re-transcribe. {}", e.user_message());
+                                                let _ = ui_handle_for_tokio.upgrade_in_event_loop(move |ui| {
+                                                    ui.set_status_text(status.into());
+                                                });
+                                                continue;
+                                            }
+                                        };
+                                        info!("🔁 Re-transcribing {} with {}", recording.display(), kind.label());
+                                        let _ = ui_handle_for_tokio.upgrade_in_event_loop(move |ui| {
+                                            ui.set_retranscribing(true);
+                                            ui.set_status_text(tr!("Re-transcribing with {}...", tr!(kind.label())).into());
+                                        });
+                                        let ui_handle = ui_handle_for_tokio.clone();
+                                        let index = history_for_runtime.clone();
+                                        let redactor = redaction::Redactor::from_settings(&options);
+                                        tokio::spawn(async move {
+                                            let result = retranscribe::transcribe_recording(
+                                                client.as_ref(),
+                                                &recording,
+                                                options.resampler_quality,
+                                                options.paragraph_pause_secs as f64,
+                                            )
+                                            .await
+                                            .and_then(|text| {
+                                                // Re-read the entry: its tags may have been edited meanwhile.
+                                                let mut index = index.lock().unwrap();
+                                                let original = index
+                                                    .get(id)
+                                                    .cloned()
+                                                    .ok_or_else(|| EchoError::config(tr!("The session is no longer in the history")))?;
+                                                let mut updated = original.clone();
+                                                updated.retranscriptions.push(history::Retranscription {
+                                                    transcribed_at: Local::now().to_rfc3339(),
+                                                    provider: kind.label().to_string(),
+                                                    text: redactor.apply(&text),
+                                                });
+                                                transcript_vault(options.encrypt_transcripts)
+                                                    .and_then(|seal_with| {
+                                                        history::replace(
+                                                            &settings::history_path(),
+                                                            vault::Vault::global,
+                                                            seal_with,
+                                                            &original,
+                                                            &updated,
+                                                        )
+                                                    })
+                                                    .and_then(|()| index.update(id, updated))
+                                            });
+                                            let status = match result {
+                                                Ok(()) => {
+                                                    info!("✅ Re-transcription with {} saved", kind.label());
+                                                    tr!("Re-transcribed with {}", tr!(kind.label()))
+                                                }
+                                                Err(e) => {
+                                                    error!("❌ Re-transcription failed: {}", e);
+                                                    tr!("Re-transcription failed. {}", e.user_message())
+                                                }
+                                            };
+                                            let _ = ui_handle.upgrade_in_event_loop(move |ui| {
+                                                ui.set_retranscribing(false);
+                                                ui.set_status_text(status.into());
+                                                // Show the new transcript if the session is still open.
+                                                if ui.get_history_selected() == id as i32 {
+                                                    if let Some(entry) = index.lock().unwrap().get(id) {
+                                                        show_history_entry(&ui, id as i32, entry, usize::MAX);
+                                                    }
+                                                }
+                                            });
+                                        });
                                     }
-                                    session.stop_network();
+                                    AppCommand::PauseRecording => {
+                                        if let Some(session) = active_session.as_ref() {
+                                            let paused = session.state.update(|s| {
+                                                s.transition_to_paused();
+                                                s.is_paused()
+                                            });
+                                            if paused {
+                                                session.paused.store(true, Ordering::SeqCst);
+                                                info!("⏸ Recording paused");
+                                                let _ = ui_handle_for_tokio.upgrade_in_event_loop(|ui| {
+                                                    ui.set_is_paused(true);
+                                                    ui.set_status_text(tr!("Paused").into());
+                                                });
+                                                let _ = overlay_handle_for_tokio.upgrade_in_event_loop(|overlay| {
+                                                    overlay.set_sentence_text(tr!("⏸ Paused").into());
+                                                });
+                                            }
+                                        }
                                     }
+                                    AppCommand::ResumeRecording => {
+                                        if let Some(session) = active_session.as_ref() {
+                                            let resumed = session.state.update(|s| {
+                                                let was_paused = s.is_paused();
+                                                s.transition_to_resumed();
+                                                was_paused
+                                            });
+                                            if resumed {
+                                                session.paused.store(false, Ordering::SeqCst);
+                                                info!("▶ Recording resumed");
+                                                let _ = ui_handle_for_tokio.upgrade_in_event_loop(|ui| {
+                                                    ui.set_is_paused(false);
+                                                    ui.set_status_text(tr!("Listening...").into());
+                                                });
+                                                let _ = overlay_handle_for_tokio.upgrade_in_event_loop(|overlay| {
+                                                    overlay.set_sentence_text(tr!("Listening...").into());
+                                                });
+                                            }
+                                        }
                                     }
                                     }
                                     }
@@ -916,8 +4037,61 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                     }
                                     });
                                     });    let start_tx = cmd_tx.clone();
+    let settings_for_start = settings.clone();
     ui.on_start_recording(move || {
-        let _ = start_tx.send(AppCommand::StartRecording);
+        let _ = start_tx.send(AppCommand::start(&settings_for_start.lock().unwrap()));
+    });
+
+    let refresh_models_tx = cmd_tx.clone();
+    ui.on_refresh_eleven_models(move || {
+        let _ = refresh_models_tx.send(AppCommand::RefreshElevenModels);
+    });
+
+    let validate_key_tx = cmd_tx.clone();
+    ui.on_validate_api_key(move || {
+        let _ = validate_key_tx.send(AppCommand::ValidateElevenKey);
+    });
+
+    let ui_handle_for_provider = ui.as_weak();
+    ui.on_provider_selected(move |label| {
+        if let (Some(ui), Some(kind)) = (ui_handle_for_provider.upgrade(), provider::ProviderKind::from_label(&label)) {
+            apply_provider_capabilities(&ui, kind);
+        }
+    });
+
+    let ui_handle_for_language = ui.as_weak();
+    let settings_for_language = settings.clone();
+    let history_for_language = history_index.clone();
+    ui.on_ui_language_selected(move |label| {
+        let (Some(ui), Some(language)) = (ui_handle_for_language.upgrade(), i18n::UiLanguage::from_label(&label)) else {
+            return;
+        };
+        // What the window shows, read before its labels change language.
+        let mut shown = settings_for_language.lock().unwrap().clone();
+        read_settings_from_ui(&ui, &mut shown);
+        shown.ui_language = language;
+        switch_language(&ui, language, &shown, &history_for_language.lock().unwrap());
+    });
+
+    let ui_handle_for_vosk = ui.as_weak();
+    ui.on_vosk_model_selected(move |label| {
+        if let (Some(ui), Some(model)) = (ui_handle_for_vosk.upgrade(), vosk_models::VoskModel::from_label(&label)) {
+            ui.set_vosk_model_status(vosk_model_status(&model).into());
+        }
+    });
+
+    let retranscribe_tx = cmd_tx.clone();
+    ui.on_retranscribe_history_entry(move |id, label| {
+        if let (Ok(id), Some(provider)) = (usize::try_from(id), provider::ProviderKind::from_label(&label)) {
+            let _ = retranscribe_tx.send(AppCommand::RetranscribeHistory { id, provider });
+        }
+    });
+
+    let download_vosk_tx = cmd_tx.clone();
+    ui.on_download_vosk_model(move |label| {
+        if let Some(model) = vosk_models::VoskModel::from_label(&label) {
+            let _ = download_vosk_tx.send(AppCommand::DownloadVoskModel(model));
+        }
     });
 
     let stop_tx = cmd_tx.clone();
@@ -925,45 +4099,224 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         let _ = stop_tx.send(AppCommand::StopRecording);
     });
 
-    let settings_for_ui = settings.clone();
-    let ui_weak_for_settings = ui.as_weak();
+    let cancel_tx = cmd_tx.clone();
+    ui.on_cancel_recording(move || {
+        let _ = cancel_tx.send(AppCommand::CancelRecording);
+    });
+
+    let wake_tx = cmd_tx.clone();
+    ui.on_wake_word_toggled(move |armed| {
+        let _ = wake_tx.send(AppCommand::ArmWakeWord(armed));
+    });
+
+    let mic_test_tx = cmd_tx.clone();
+    ui.on_test_microphone(move |enabled, loopback| {
+        let _ = mic_test_tx.send(AppCommand::TestMicrophone { enabled, loopback });
+    });
+
+    let sample_tx = cmd_tx.clone();
+    ui.on_record_wake_word_sample(move || {
+        let _ = sample_tx.send(AppCommand::RecordWakeWordSample);
+    });
+
+    let clear_wake_tx = cmd_tx.clone();
+    let ui_weak_for_wake = ui.as_weak();
+    ui.on_clear_wake_word_samples(move || {
+        let Some(ui) = ui_weak_for_wake.upgrade() else {
+            return;
+        };
+        if let Err(e) = wake_word::clear_templates(&settings::wake_word_dir()) {
+            ui.set_status_text(tr!("Could not delete the wake word samples: {}", e).into());
+            return;
+        }
+        ui.set_wake_word_samples_text(wake_word_samples_text(0).into());
+        if ui.get_wake_word_armed() {
+            let _ = clear_wake_tx.send(AppCommand::ArmWakeWord(true));
+        }
+    });
+
+    let pause_tx = cmd_tx.clone();
+    ui.on_pause_recording(move || {
+        let _ = pause_tx.send(AppCommand::PauseRecording);
+    });
 
+    let resume_tx = cmd_tx.clone();
+    ui.on_resume_recording(move || {
+        let _ = resume_tx.send(AppCommand::ResumeRecording);
+    });
+
+    let settings_for_ui = settings.clone();
+    #[cfg(target_os = "windows")]
     let settings_for_save = settings.clone();
     let ui_weak_for_apply = ui.as_weak();
+    let quota_tx = cmd_tx.clone();
+    // The settings already hold the checkbox by the time Apply runs, so what
+    // the history file was written with is tracked separately.
+    let history_encryption = Arc::new(Mutex::new(history::HistoryEncryption::new(
+        settings::history_path(),
+        initial_settings.encrypt_transcripts,
+    )));
     ui.on_apply_settings(move || {
-        let (api_key, gemini_api_key, gemini_enabled, gemini_model, gemini_preset, gemini_custom, selected_mic, use_default_mic) =
-            if let Some(ui) = ui_weak_for_apply.upgrade() {
-                (
-                    ui.get_api_key_text().to_string(),
-                    ui.get_gemini_api_key_text().to_string(),
-                    ui.get_use_gemini_modifier(),
-                    ui.get_gemini_model_text().to_string(),
-                    ui.get_selected_gemini_preset().to_string(),
-                    ui.get_gemini_custom_prompt().to_string(),
-                    ui.get_selected_microphone().to_string(),
-                    ui.get_use_default_microphone(),
-                )
-            } else {
-                (String::new(), String::new(), false, "gemini-3.1-flash-lite-preview".to_string(), "Minimal corrections".to_string(), String::new(), String::new(), true)
-            };
-
+        let Some(ui) = ui_weak_for_apply.upgrade() else {
+            return;
+        };
+        let rules = match replacements::parse_rules(&ui.get_replacements_text()) {
+            Ok(rules) => rules,
+            Err(err) => {
+                ui.set_status_text(tr!("Replacements not saved: {}", err).into());
+                return;
+            }
+        };
+        let formatter_kinds = match formatters::parse_formatters(&ui.get_formatters_text()) {
+            Ok(kinds) => kinds,
+            Err(err) => {
+                ui.set_status_text(tr!("Dictation style not saved: {}", err).into());
+                return;
+            }
+        };
+        let redaction_patterns = match redaction::parse_patterns(&ui.get_redaction_patterns_text()) {
+            Ok(patterns) => patterns,
+            Err(err) => {
+                ui.set_status_text(tr!("Redaction patterns not saved: {}", err).into());
+                return;
+            }
+        };
+        let voice_commands = match commands::parse_commands(&ui.get_voice_commands_text()) {
+            Ok(voice_commands) => voice_commands,
+            Err(err) => {
+                ui.set_status_text(tr!("Voice commands not saved: {}", err).into());
+                return;
+            }
+        };
+        let snippets = match snippets::parse_snippets(&ui.get_snippets_text()) {
+            Ok(snippets) => snippets,
+            Err(err) => {
+                ui.set_status_text(tr!("Snippets not saved: {}", err).into());
+                return;
+            }
+        };
+        let processor_steps = match processors::parse_steps(&ui.get_processors_text()) {
+            Ok(steps) => steps,
+            Err(err) => {
+                ui.set_status_text(tr!("Processing order not saved: {}", err).into());
+                return;
+            }
+        };
+        let keybindings = match hotkey::parse_keybindings(&ui.get_keybindings_text()) {
+            Ok(keybindings) => keybindings,
+            Err(err) => {
+                ui.set_status_text(tr!("Key bindings not saved: {}", err).into());
+                return;
+            }
+        };
         let snapshot = {
             let mut current = settings_for_ui.lock().unwrap();
-            current.api_key = api_key;
-            current.gemini_api_key = gemini_api_key;
-            current.gemini_enabled = gemini_enabled;
-            current.gemini_model = gemini_model;
-            current.gemini_prompt_preset = gemini_preset;
-            current.gemini_custom_prompt = gemini_custom;
-            current.selected_microphone = selected_mic;
-            current.use_default_microphone = use_default_mic;
+            read_settings_from_ui(&ui, &mut current);
+            current.replacement_rules = rules;
+            current.formatters = formatter_kinds;
+            current.redaction_patterns = redaction_patterns;
+            current.voice_commands = voice_commands;
+            current.snippets = snippets;
+            current.processors = processor_steps;
+            current.keybindings = keybindings;
+            current.store_active_profile();
             current.clone()
         };
         save_settings(&snapshot);
+        apply_keybindings_to_ui(&ui, &snapshot);
+        let _ = quota_tx.send(AppCommand::LookUpQuota);
+        if history_encryption.lock().unwrap().needs_rewrite(snapshot.encrypt_transcripts) {
+            if let Err(err) = transcript_vault(snapshot.encrypt_transcripts) {
+                ui.set_status_text(tr!("Settings applied, but {}", err).into());
+                ui.set_active_tab(0);
+                return;
+            }
+            reencode_history(history_encryption.clone(), snapshot.encrypt_transcripts);
+        }
 
-        if let Some(ui) = ui_weak_for_settings.upgrade() {
-            ui.set_status_text("Settings applied".into());
+        if let Err(err) = autostart::set_enabled(snapshot.launch_at_login) {
+            ui.set_status_text(tr!("Settings applied, but {}", err).into());
             ui.set_active_tab(0);
+            return;
+        }
+        ui.set_status_text(tr!("Settings applied").into());
+        ui.set_active_tab(0);
+    });
+
+    let settings_for_log_level = settings.clone();
+    ui.on_change_log_level(move |level| {
+        logging.set_level(&level);
+        let snapshot = {
+            let mut current = settings_for_log_level.lock().unwrap();
+            current.log_level = level.to_string();
+            current.clone()
+        };
+        save_settings(&snapshot);
+        info!("📝 Log level set to {}", level);
+    });
+
+    let ui_weak_for_logs = ui.as_weak();
+    ui.on_open_logs(move || {
+        let folder = settings::logs_dir();
+        if let Err(err) = tray::open_folder(&folder) {
+            warn!("⚠️ Failed to open {}: {}", folder.display(), err);
+            if let Some(ui) = ui_weak_for_logs.upgrade() {
+                ui.set_status_text(tr!("Could not open {}", folder.display()).into());
+            }
+        }
+    });
+
+    let ui_weak_for_plugins = ui.as_weak();
+    ui.on_open_plugins(move || {
+        let folder = settings::plugins_dir();
+        if let Err(err) = tray::open_folder(&folder) {
+            warn!("⚠️ Failed to open {}: {}", folder.display(), err);
+            if let Some(ui) = ui_weak_for_plugins.upgrade() {
+                ui.set_status_text(tr!("Could not open {}", folder.display()).into());
+            }
+        }
+    });
+
+    // Edits to the settings file made outside the app apply without a restart.
+    let settings_for_reload = settings.clone();
+    let ui_weak_for_reload = ui.as_weak();
+    let _config_watcher = config_watch::watch(settings::settings_path(), move || {
+        let settings = settings_for_reload.clone();
+        let _ = ui_weak_for_reload.upgrade_in_event_loop(move |ui| reload_settings(&ui, &settings));
+    })
+    .map_err(|e| warn!("⚠️ Settings hot-reload is unavailable: {}", e))
+    .ok();
+
+    let settings_for_profiles = settings.clone();
+    let ui_weak_for_profiles = ui.as_weak();
+    ui.on_switch_profile(move |name| {
+        let Some(ui) = ui_weak_for_profiles.upgrade() else {
+            return;
+        };
+        update_profiles(&ui, &settings_for_profiles, |s| s.switch_profile(&name));
+    });
+
+    let settings_for_profiles = settings.clone();
+    let ui_weak_for_profiles = ui.as_weak();
+    ui.on_save_profile_as(move |name| {
+        let Some(ui) = ui_weak_for_profiles.upgrade() else {
+            return;
+        };
+        if update_profiles(&ui, &settings_for_profiles, |s| s.save_profile_as(&name)) {
+            ui.set_new_profile_name("".into());
+        } else {
+            ui.set_status_text(tr!("Enter a profile name first").into());
+        }
+    });
+
+    let settings_for_profiles = settings.clone();
+    let ui_weak_for_profiles = ui.as_weak();
+    ui.on_delete_profile(move || {
+        let Some(ui) = ui_weak_for_profiles.upgrade() else {
+            return;
+        };
+        if !update_profiles(&ui, &settings_for_profiles, |s| s.delete_active_profile()) {
+            ui.set_status_text(tr!("The last profile can't be deleted").into());
         }
     });
 
@@ -981,43 +4334,51 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             *hotkey_capture_active_for_start.borrow_mut() = true;
             *hotkey_capture_latched_for_start.borrow_mut() = false;
             if let Some(capture) = hotkey_capture_window_for_start.upgrade() {
-                capture.set_state_text("Waiting for key combo...".into());
+                capture.set_state_text(tr!("Waiting for key combo...").into());
                 capture.set_combo_text("".into());
                 let _ = capture.show();
             }
             if let Some(ui) = ui_weak_for_hotkey.upgrade() {
-                ui.set_status_text("Waiting for key combo...".into());
+                ui.set_status_text(tr!("Waiting for key combo...").into());
             }
         }
 
         #[cfg(not(target_os = "windows"))]
         {
             if let Some(ui) = ui_weak_for_hotkey.upgrade() {
-                ui.set_status_text("Hotkeys are not supported on this platform".into());
+                ui.set_status_text(tr!("Hotkeys are not supported on this platform").into());
                 ui.set_active_tab(2);
             }
         }
     });
 
-    let ui_weak_for_clear = ui.as_weak();
-    ui.on_clear_transcript(move || {
-        if let Some(ui) = ui_weak_for_clear.upgrade() {
-            ui.set_transcript("".into());
-        }
-    });
-
     let ui_handle_for_timer = ui.as_weak();
+    #[cfg(target_os = "windows")]
     let cmd_tx_for_timer = cmd_tx.clone();
     let settings_for_timer = settings.clone();
+    let control_status_for_timer = control_status.clone();
+    // The tray, cues and toasts live on the UI thread, so this receiver is
+    // drained each tick; every transition since the last tick is handled.
+    let state_rx_for_timer = RefCell::new(state_bus.subscribe());
+    let recording_state_for_timer = RefCell::new(RecordingState::Idle);
+    let last_app_status = RefCell::new(tray::TrayStatus::Idle);
+    #[cfg(target_os = "windows")]
+    let tray_profile_items = RefCell::new(Vec::<(MenuId, String)>::new());
+    #[cfg(target_os = "windows")]
+    let last_tray_profiles = RefCell::new(None::<(Vec<String>, String)>);
+    #[cfg(target_os = "windows")]
+    let tray_language = Cell::new(None::<&str>);
+    let toast_for_timer = toast.as_weak();
     let overlay_for_timer = transcript_overlay.as_weak();
+    let last_external_window_for_timer = last_external_window.clone();
+    let mini_bar_for_timer = mini_bar.as_weak();
+    let preview_for_timer = injection_preview.as_weak();
     #[cfg(target_os = "windows")]
     let hotkey_capture_window_for_timer = hotkey_capture_window.as_weak();
     #[cfg(target_os = "windows")]
     let hotkey_capture_active_for_timer = hotkey_capture_active.clone();
     #[cfg(target_os = "windows")]
     let hotkey_capture_latched_for_timer = hotkey_capture_latched.clone();
-    #[cfg(target_os = "windows")]
-    let hotkey_text_for_timer = hotkey_text.clone();
 
     let timer = slint::Timer::default();
     timer.start(
@@ -1026,29 +4387,69 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         move || {
             if let Some(ui) = ui_handle_for_timer.upgrade() {
                 {
-                    let mut s = settings_for_timer.lock().unwrap();
-                    s.api_key = ui.get_api_key_text().to_string();
-                    s.gemini_api_key = ui.get_gemini_api_key_text().to_string();
-                    s.gemini_enabled = ui.get_use_gemini_modifier();
-                    s.gemini_model = ui.get_gemini_model_text().to_string();
-                    s.gemini_prompt_preset = ui.get_selected_gemini_preset().to_string();
-                    s.gemini_custom_prompt = ui.get_gemini_custom_prompt().to_string();
-                    s.selected_microphone = ui.get_selected_microphone().to_string();
-                    s.use_default_microphone = ui.get_use_default_microphone();
-                    s.overlay_opacity = ui.get_overlay_opacity();
-                    s.theme_background_top_color =
-                        ui.get_theme_background_top_color().to_string();
-                    s.theme_background_bottom_color =
-                        ui.get_theme_background_bottom_color().to_string();
-                    s.theme_window_color = ui.get_theme_window_color().to_string();
-                    s.theme_button_accent_color =
-                        ui.get_theme_button_accent_color().to_string();
-                    s.theme_title_color = ui.get_theme_title_color().to_string();
-                    s.theme_text_color = ui.get_theme_text_color().to_string();
-                    s.overlay_background_color =
-                        ui.get_overlay_background_color().to_string();
-                    s.overlay_text_color = ui.get_overlay_text_color().to_string();
+                    let mut current = settings_for_timer.lock().unwrap();
+                    read_settings_from_ui(&ui, &mut current);
+                    if let Some(geometry) = capture_window_geometry(&ui, current.window_geometry) {
+                        current.window_geometry = Some(geometry);
+                    }
+                    current.active_tab = ui.get_active_tab();
+                }
+                if let Some(window) = injector::capture_target_window() {
+                    last_external_window_for_timer.set(Some(window));
+                }
+                {
+                    let mut status = control_status_for_timer.lock().unwrap();
+                    status.status = ui.get_status_text().to_string();
+                    status.is_recording = ui.get_is_recording();
+                    status.has_error = ui.get_has_error();
+                }
+
+                let mut transitions = Vec::new();
+                loop {
+                    match state_rx_for_timer.borrow_mut().try_recv() {
+                        Ok(change) => transitions.push(change.state),
+                        Err(broadcast::error::TryRecvError::Lagged(_)) => continue,
+                        Err(_) => break,
+                    }
+                }
+                if let Some(latest) = transitions.last() {
+                    *recording_state_for_timer.borrow_mut() = latest.clone();
+                }
+                let recording_state = recording_state_for_timer.borrow().clone();
+                // The error flag can change without a transition, so the
+                // current state is checked even when none arrived.
+                transitions.push(recording_state.clone());
+                let has_error = ui.get_has_error();
+                #[cfg(target_os = "windows")]
+                let initial_status = *last_app_status.borrow();
+                for state in &transitions {
+                    let app_status = tray::TrayStatus::from_state(state, has_error);
+                    if app_status == *last_app_status.borrow() {
+                        continue;
+                    }
+                    let previous_status = last_app_status.replace(app_status);
+                    let cue_settings = cues::CueSettings {
+                        on_start: ui.get_cue_on_start(),
+                        on_stop: ui.get_cue_on_stop(),
+                        on_error: ui.get_cue_on_error(),
+                        volume: ui.get_cue_volume(),
+                    };
+                    if let Some(cue) = cues::Cue::for_status(previous_status, app_status)
+                        .filter(|&cue| cue_settings.enabled(cue))
+                    {
+                        cues::play(cue, cue_settings.volume);
+                    }
+                    let toast_text = app_status.toast_text(&ui.get_status_text());
+                    if let (true, Some(text), Some(toast)) =
+                        (ui.get_show_toasts(), toast_text, toast_for_timer.upgrade())
+                    {
+                        show_toast(&toast, &text, app_status == tray::TrayStatus::Error, &toast_generation);
+                    }
                 }
+                #[cfg(target_os = "windows")]
+                let app_status = *last_app_status.borrow();
+                #[cfg(target_os = "windows")]
+                let status_changed = app_status != initial_status;
 
                 if let Some(overlay) = overlay_for_timer.upgrade() {
                     overlay.set_overlay_opacity(ui.get_overlay_opacity());
@@ -1056,6 +4457,31 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     overlay.set_overlay_text_color(ui.get_overlay_text_color());
                 }
 
+                let light_theme = ui.get_light_theme();
+                let high_contrast = ui.get_high_contrast();
+                if let Some(bar) = mini_bar_for_timer.upgrade() {
+                    bar.set_light_theme(light_theme);
+                    bar.set_high_contrast(high_contrast);
+                    bar.set_accent_color(ui.get_theme_button_accent_color());
+                    bar.set_is_recording(ui.get_is_recording());
+                    bar.set_has_error(ui.get_has_error());
+                    bar.set_audio_level(ui.get_audio_level());
+                    bar.set_state_text(tr!(recording_state.label()).into());
+                }
+                if let Some(toast) = toast_for_timer.upgrade() {
+                    toast.set_light_theme(light_theme);
+                    toast.set_high_contrast(high_contrast);
+                }
+                if let Some(preview) = preview_for_timer.upgrade() {
+                    preview.set_light_theme(light_theme);
+                    preview.set_high_contrast(high_contrast);
+                }
+                #[cfg(target_os = "windows")]
+                if let Some(capture) = hotkey_capture_window_for_timer.upgrade() {
+                    capture.set_light_theme(light_theme);
+                    capture.set_high_contrast(high_contrast);
+                }
+
                 #[cfg(target_os = "windows")]
                 {
                     if *hotkey_capture_active_for_timer.borrow() {
@@ -1065,34 +4491,56 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             if let Some(capture) = hotkey_capture_window_for_timer.upgrade() {
                                 let _ = capture.hide();
                             }
-                            ui.set_status_text("Hotkey capture cancelled".into());
+                            ui.set_status_text(tr!("Hotkey capture cancelled").into());
                             return;
                         }
 
                         if let Some(combo) = detect_hotkey_combo() {
                             if !*hotkey_capture_latched_for_timer.borrow() {
                                 *hotkey_capture_latched_for_timer.borrow_mut() = true;
-                                match apply_hotkey(&hotkey_manager, &mut hotkey_state.borrow_mut(), &combo) {
-                                    Ok(new_id) => {
-                                        *hotkey_id_state.borrow_mut() = Some(new_id);
-                                        *hotkey_text_for_timer.lock().unwrap() = combo.clone();
-                                        ui.set_hotkey_text(combo.clone().into());
-                                        ui.set_status_text("Hotkey updated".into());
+                                let previous = settings_for_save.lock().unwrap().keybindings.clone();
+                                let mut updated = settings_for_save.lock().unwrap().clone();
+                                updated.set_binding(hotkey::HotkeyAction::ToggleDictation, &combo);
+                                let taken = previous.iter().find(|binding| {
+                                    binding.action != hotkey::HotkeyAction::ToggleDictation
+                                        && hotkey::same_combo(&binding.keys, &combo)
+                                });
+                                let result = match taken {
+                                    Some(binding) => Err(tr!("already bound to {}", binding.action.name())),
+                                    None => {
+                                        let failures = hotkey_registry
+                                            .borrow_mut()
+                                            .apply(&hotkey::active_keybindings(&updated.keybindings, ui.get_is_recording()));
+                                        match failures.into_iter().find(|(binding, _)| binding.keys == combo) {
+                                            Some((_, err)) => {
+                                                hotkey_registry
+                                                    .borrow_mut()
+                                                    .apply(&hotkey::active_keybindings(&previous, ui.get_is_recording()));
+                                                Err(err)
+                                            }
+                                            None => Ok(()),
+                                        }
+                                    }
+                                };
+                                match result {
+                                    Ok(()) => {
                                         if let Ok(mut saved) = settings_for_save.lock() {
-                                            saved.hotkey_text = combo.clone();
+                                            saved.keybindings = updated.keybindings;
                                             save_settings(&saved);
+                                            apply_keybindings_to_ui(&ui, &saved);
                                         }
+                                        ui.set_status_text(tr!("Hotkey updated").into());
                                         if let Some(capture) = hotkey_capture_window_for_timer.upgrade() {
-                                            capture.set_state_text("Registered".into());
+                                            capture.set_state_text(tr!("Registered").into());
                                             capture.set_combo_text(combo.into());
                                             let _ = capture.hide();
                                         }
                                     }
                                     Err(err) => {
-                                        ui.set_status_text(format!("Hotkey unchanged: {}", err).into());
+                                        ui.set_status_text(tr!("Hotkey unchanged: {}", err).into());
                                         ui.set_active_tab(2);
                                         if let Some(capture) = hotkey_capture_window_for_timer.upgrade() {
-                                            capture.set_state_text(format!("Failed: {}", err).into());
+                                            capture.set_state_text(tr!("Failed: {}", err).into());
                                             capture.set_combo_text(combo.into());
                                         }
                                     }
@@ -1104,16 +4552,58 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         }
                     }
 
+                    let keybindings = hotkey::active_keybindings(
+                        &settings_for_timer.lock().unwrap().keybindings,
+                        ui.get_is_recording(),
+                    );
+                    if hotkey_registry.borrow().bindings != keybindings {
+                        let failures = hotkey_registry.borrow_mut().apply(&keybindings);
+                        if let Some((binding, err)) = failures.first() {
+                            warn!("⚠️ Failed to register {} hotkey {}: {}", binding.action.name(), binding.keys, err);
+                            ui.set_status_text(tr!("Hotkey {} not registered: {}", binding.keys, err).into());
+                        }
+                    }
+
                     while let Ok(event) = GlobalHotKeyEvent::receiver().try_recv() {
-                        let current_hotkey_id = *hotkey_id_state.borrow();
-                        if current_hotkey_id.is_some_and(|id| event.id == id)
-                            && event.state == HotKeyState::Pressed
-                        {
-                            if ui.get_is_recording() {
-                                let _ = cmd_tx_for_timer.send(AppCommand::StopRecording);
-                            } else {
-                                let _ = cmd_tx_for_timer.send(AppCommand::StartRecording);
+                        let Some(action) = hotkey_registry.borrow().action_for(event.id) else {
+                            continue;
+                        };
+                        match (action, event.state) {
+                            (hotkey::HotkeyAction::ToggleDictation, HotKeyState::Pressed) => {
+                                if ui.get_is_recording() {
+                                    let _ = cmd_tx_for_timer.send(AppCommand::StopRecording);
+                                } else {
+                                    let _ = cmd_tx_for_timer.send(AppCommand::start(&settings_for_timer.lock().unwrap()));
+                                }
+                            }
+                            (hotkey::HotkeyAction::PushToTalk, HotKeyState::Pressed) => {
+                                if !ui.get_is_recording() {
+                                    let _ = cmd_tx_for_timer.send(AppCommand::start(&settings_for_timer.lock().unwrap()));
+                                }
+                            }
+                            (hotkey::HotkeyAction::PushToTalk, HotKeyState::Released) => {
+                                if ui.get_is_recording() {
+                                    let _ = cmd_tx_for_timer.send(AppCommand::StopRecording);
+                                }
+                            }
+                            (hotkey::HotkeyAction::PauseResume, HotKeyState::Pressed) => {
+                                if ui.get_is_paused() {
+                                    let _ = cmd_tx_for_timer.send(AppCommand::ResumeRecording);
+                                } else if ui.get_is_recording() {
+                                    let _ = cmd_tx_for_timer.send(AppCommand::PauseRecording);
+                                }
+                            }
+                            (hotkey::HotkeyAction::UndoLastInjection, HotKeyState::Pressed) => {
+                                let _ = cmd_tx_for_timer.send(AppCommand::UndoLastInjection);
+                            }
+                            (hotkey::HotkeyAction::CancelRecording, HotKeyState::Pressed) => {
+                                let _ = cmd_tx_for_timer.send(AppCommand::CancelRecording);
                             }
+                            (hotkey::HotkeyAction::NextProfile, HotKeyState::Pressed) => {
+                                let next = settings_for_timer.lock().unwrap().next_profile_name();
+                                ui.invoke_switch_profile(next.into());
+                            }
+                            _ => {}
                         }
                     }
 
@@ -1126,12 +4616,70 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         }
                     }
 
+                    let language = i18n::active();
+                    let relabel = tray_language.replace(Some(language)) != Some(language);
+                    if relabel {
+                        for (item, label) in &tray_labels {
+                            item.set_text(tr!(label));
+                        }
+                        profile_menu.set_text(tr!("Profile"));
+                    }
+                    if status_changed || relabel {
+                        let icon = app_status
+                            .icon_rgba()
+                            .and_then(|rgba| tray_icon::Icon::from_rgba(rgba, tray::ICON_SIZE, tray::ICON_SIZE).ok())
+                            .unwrap_or_else(|| app_icon.clone());
+                        let _ = tray_handle.set_icon(Some(icon));
+                        let _ = tray_handle.set_tooltip(Some(app_status.tooltip()));
+                        toggle_item.set_text(app_status.toggle_label());
+                    }
+
+                    let profiles = {
+                        let s = settings_for_timer.lock().unwrap();
+                        (s.profile_names(), s.active_profile.clone())
+                    };
+                    if last_tray_profiles.borrow().as_ref() != Some(&profiles) {
+                        *tray_profile_items.borrow_mut() = fill_profile_menu(&profile_menu, &profiles.0, &profiles.1);
+                        *last_tray_profiles.borrow_mut() = Some(profiles);
+                    }
+
                     while let Ok(event) = MenuEvent::receiver().try_recv() {
-                        if event.id == quit_item_id {
-                            slint::quit_event_loop().unwrap();
-                        } else if event.id == settings_item_id {
+                        if event.id == tray_items.quit {
+                            if cmd_tx_for_timer.send(AppCommand::Shutdown).is_err() {
+                                slint::quit_event_loop().unwrap();
+                            }
+                        } else if event.id == tray_items.settings {
                             ui.set_active_tab(2);
                             ui.show().unwrap();
+                        } else if event.id == tray_items.show {
+                            ui.set_active_tab(0);
+                            let _ = ui.show();
+                        } else if event.id == tray_items.toggle {
+                            if ui.get_is_recording() {
+                                let _ = cmd_tx_for_timer.send(AppCommand::StopRecording);
+                            } else {
+                                let _ = cmd_tx_for_timer.send(AppCommand::start(&settings_for_timer.lock().unwrap()));
+                            }
+                        } else if event.id == tray_items.mini_bar {
+                            ui.invoke_toggle_mini_bar();
+                        } else if event.id == tray_items.restart_as_admin {
+                            ui.invoke_restart_as_admin();
+                        } else if event.id == tray_items.logs {
+                            ui.invoke_open_logs();
+                        } else if event.id == tray_items.folder {
+                            let folder = settings::transcripts_dir();
+                            if let Err(err) = tray::open_folder(&folder) {
+                                warn!("⚠️ Failed to open {}: {}", folder.display(), err);
+                            }
+                        } else {
+                            let clicked = tray_profile_items
+                                .borrow()
+                                .iter()
+                                .find(|(id, _)| *id == event.id)
+                                .map(|(_, name)| name.clone());
+                            if let Some(name) = clicked {
+                                ui.invoke_switch_profile(name.into());
+                            }
                         }
                     }
                 }
@@ -1139,8 +4687,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         },
     );
 
-    ui.show()?;
+    // Without a tray icon there would be no way back to a hidden window.
+    let start_hidden = cfg!(target_os = "windows") && (initial_settings.start_hidden || autostart::launched_minimized());
+    if start_hidden {
+        info!("🫥 Starting hidden in the tray");
+    } else if initial_settings.mini_bar {
+        mini_bar.show()?;
+    } else {
+        ui.show()?;
+    }
     slint::run_event_loop_until_quit()?;
+    save_window_state(&settings.lock().unwrap());
     Ok(())
 }
 
@@ -0,0 +1,235 @@
+// Meeting mode: the microphone is transcribed as the session's main stream
+// while system audio (WASAPI loopback) runs through a second provider session
+// alongside it. Both transcripts are merged into one, each line labelled with
+// who said it, so a call can be captured as notes.
+
+use crate::audio::{self, AudioCapture, CaptureOptions, CaptureSource};
+use crate::error::EchoResult;
+use crate::network::{ControlMessage, TranscriptEvent};
+use crate::provider;
+use crate::settings::AppSettings;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc::{self, Receiver, Sender, UnboundedSender};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+/// Which side of the call a transcript came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Speaker {
+    /// The microphone.
+    Me,
+    /// System audio, i.e. everyone else on the call.
+    Them,
+}
+
+impl Speaker {
+    pub fn label(self) -> &'static str {
+        match self {
+            Speaker::Me => "Me",
+            Speaker::Them => "Them",
+        }
+    }
+}
+
+/// Tags `event` with `speaker` and prefixes its text with the label. The
+/// loopback session's lifecycle events and errors are dropped (errors are
+/// logged) so only the microphone session can start, fail or end the session.
+/// Loopback timings are cleared too, since they count from that stream's
+/// start rather than the recording's.
+pub fn label_event(event: TranscriptEvent, speaker: Speaker) -> Option<TranscriptEvent> {
+    let label = |mut segment: crate::network::TranscriptSegment| {
+        if !segment.text.trim().is_empty() {
+            segment.text = format!("{}: {}", speaker.label(), segment.text.trim());
+        }
+        if speaker == Speaker::Them {
+            segment.start_secs = None;
+            segment.end_secs = None;
+            segment.words.clear();
+        }
+        segment.channel = Some(speaker);
+        segment
+    };
+    match (event, speaker) {
+        (TranscriptEvent::Partial(segment), _) => Some(TranscriptEvent::Partial(label(segment))),
+        (TranscriptEvent::Committed(segment), _) => Some(TranscriptEvent::Committed(label(segment))),
+        (TranscriptEvent::Error(err), Speaker::Them) => {
+            warn!("⚠️ System audio transcription error: {}", err);
+            None
+        }
+        (_, Speaker::Them) => None,
+        (event, Speaker::Me) => Some(event),
+    }
+}
+
+/// Forwards `rx` into `tx`, labelled as `speaker`.
+pub async fn relay(mut rx: Receiver<TranscriptEvent>, speaker: Speaker, tx: Sender<TranscriptEvent>) {
+    while let Some(event) = rx.recv().await {
+        let Some(event) = label_event(event, speaker) else {
+            continue;
+        };
+        if tx.send(event).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// The "Them" half of a meeting: a loopback capture streaming into its own
+/// provider session. Dropping it closes both.
+pub struct LoopbackSession {
+    _capture: AudioCapture,
+    control_tx: UnboundedSender<ControlMessage>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl LoopbackSession {
+    /// Starts capturing system audio and transcribing it into `text_tx`.
+    /// Audio is discarded while `paused` is set, like the microphone's.
+    pub fn start(
+        settings: &AppSettings,
+        eleven_model_id: &str,
+        paused: Arc<AtomicBool>,
+        text_tx: Sender<TranscriptEvent>,
+        log_tx: UnboundedSender<String>,
+    ) -> EchoResult<Self> {
+        // The session trace and race report already cover the microphone session.
+        let settings = AppSettings {
+            record_session_trace: false,
+            race_provider: None,
+            ..settings.clone()
+        };
+        let client = provider::create_provider(&settings, eleven_model_id)?;
+        let format = client.audio_format();
+
+        let (audio_tx, mut audio_rx) = mpsc::channel::<audio::AudioChunk>(50);
+        // Levels and device loss are only tracked for the microphone.
+        let (level_tx, _) = mpsc::channel::<f32>(1);
+        let (device_lost_tx, _) = mpsc::unbounded_channel::<()>();
+        let capture = audio::start_audio_capture(
+            audio_tx,
+            level_tx,
+            None,
+            None,
+            CaptureOptions {
+                source: CaptureSource::SystemAudio,
+                resampler: settings.resampler_quality,
+                sample_rate: format.sample_rate,
+                preconnect_secs: settings.preconnect_buffer_secs,
+                boost_priority: settings.boost_audio_thread,
+                ..CaptureOptions::default()
+            },
+            device_lost_tx,
+        )?;
+        info!("🔊 Meeting mode: transcribing system audio with {}", client.name());
+
+        let (net_tx, net_rx) = mpsc::channel::<Vec<i16>>(50);
+        let forward = tokio::spawn(async move {
+            while let Some(chunk) = audio_rx.recv().await {
+                if paused.load(Ordering::SeqCst) {
+                    continue;
+                }
+                if net_tx.send(chunk.samples).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let (control_tx, control_rx) = mpsc::unbounded_channel::<ControlMessage>();
+        let (them_tx, them_rx) = mpsc::channel::<TranscriptEvent>(100);
+        let network = tokio::spawn(async move {
+            if let Err(e) = client.run(net_rx, control_rx, them_tx, log_tx).await {
+                error!("❌ System audio transcription failed: {}", e);
+            }
+        });
+        let relay = tokio::spawn(relay(them_rx, Speaker::Them, text_tx));
+
+        Ok(Self {
+            _capture: capture,
+            control_tx,
+            tasks: vec![forward, network, relay],
+        })
+    }
+
+    pub fn send(&self, message: ControlMessage) {
+        let _ = self.control_tx.send(message);
+    }
+}
+
+impl Drop for LoopbackSession {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{label_event, relay, Speaker};
+    use crate::network::{ProviderError, TranscriptEvent, TranscriptSegment};
+    use tokio::sync::mpsc;
+
+    #[test]
+    fn commits_are_labelled_and_loopback_timings_dropped() {
+        let timed = TranscriptSegment {
+            start_secs: Some(1.0),
+            end_secs: Some(2.0),
+            ..TranscriptSegment::text(" hello ")
+        };
+        let Some(TranscriptEvent::Committed(me)) = label_event(TranscriptEvent::Committed(timed.clone()), Speaker::Me)
+        else {
+            panic!("commit was dropped");
+        };
+        assert_eq!(me.text, "Me: hello");
+        assert_eq!(me.start_secs, Some(1.0));
+        assert_eq!(me.channel, Some(Speaker::Me));
+
+        let Some(TranscriptEvent::Committed(them)) = label_event(TranscriptEvent::Committed(timed), Speaker::Them)
+        else {
+            panic!("commit was dropped");
+        };
+        assert_eq!(them.text, "Them: hello");
+        assert_eq!(them.start_secs, None);
+
+        // Empty commits stay empty so the partial fallback still applies.
+        let Some(TranscriptEvent::Committed(empty)) =
+            label_event(TranscriptEvent::Committed(TranscriptSegment::text("")), Speaker::Them)
+        else {
+            panic!("commit was dropped");
+        };
+        assert_eq!(empty.text, "");
+    }
+
+    #[test]
+    fn only_the_microphone_session_controls_the_stream() {
+        let error = TranscriptEvent::Error(ProviderError::new("network", "lost"));
+        assert!(label_event(TranscriptEvent::EndOfStream, Speaker::Them).is_none());
+        assert!(label_event(error.clone(), Speaker::Them).is_none());
+        assert_eq!(label_event(error.clone(), Speaker::Me), Some(error));
+        assert_eq!(
+            label_event(TranscriptEvent::EndOfStream, Speaker::Me),
+            Some(TranscriptEvent::EndOfStream)
+        );
+    }
+
+    #[tokio::test]
+    async fn relay_labels_events_in_order() {
+        let (in_tx, in_rx) = mpsc::channel(4);
+        let (out_tx, mut out_rx) = mpsc::channel(4);
+        in_tx.send(TranscriptEvent::Partial(TranscriptSegment::text("hi"))).await.unwrap();
+        in_tx.send(TranscriptEvent::EndOfStream).await.unwrap();
+        in_tx.send(TranscriptEvent::Committed(TranscriptSegment::text("hi there"))).await.unwrap();
+        drop(in_tx);
+        relay(in_rx, Speaker::Them, out_tx).await;
+
+        let texts: Vec<String> = std::iter::from_fn(|| out_rx.try_recv().ok())
+            .map(|event| match event {
+                TranscriptEvent::Partial(s) | TranscriptEvent::Committed(s) => s.text,
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        assert_eq!(texts, ["Them: hi", "Them: hi there"]);
+    }
+}
@@ -0,0 +1,464 @@
+use crate::i18n::{self, tr};
+use crate::settings::metrics_path;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tracing::error;
+
+/// Rough list price of realtime transcription, used for the cost estimate only.
+pub const COST_PER_AUDIO_HOUR_USD: f64 = 0.40;
+/// Final transcripts the rolling latency figure averages over.
+const LATENCY_WINDOW: usize = 20;
+
+/// Usage counters for a single recording session.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionMetrics {
+    /// Seconds of audio streamed, summed per chunk since the rate is
+    /// negotiated per provider.
+    pub audio_secs: f64,
+    pub characters_transcribed: u64,
+    pub words_injected: u64,
+    /// When the latest chunk went to the provider; transcript latency falls
+    /// back to this when the provider gives no timestamps.
+    pub last_audio_at: Option<Instant>,
+}
+
+impl SessionMetrics {
+    pub fn record_audio(&mut self, samples: usize, sample_rate: u32) {
+        self.audio_secs += samples as f64 / sample_rate.max(1) as f64;
+        self.last_audio_at = Some(Instant::now());
+    }
+
+    /// Time from when the transcribed speech was captured to `received_at`,
+    /// or from the last audio sent if that is unknown.
+    pub fn transcription_latency(&self, captured_at: Option<Instant>, received_at: Instant) -> Option<Duration> {
+        captured_at
+            .or(self.last_audio_at)
+            .map(|spoken| received_at.saturating_duration_since(spoken))
+    }
+
+    pub fn record_transcript(&mut self, text: &str) {
+        self.characters_transcribed += text.chars().count() as u64;
+    }
+
+    pub fn record_injection(&mut self, text: &str) {
+        self.words_injected += text.split_whitespace().count() as u64;
+    }
+
+    pub fn audio_seconds(&self) -> f64 {
+        self.audio_secs
+    }
+
+    pub fn summary(&self) -> String {
+        format_summary(
+            self.audio_seconds(),
+            self.characters_transcribed,
+            self.words_injected,
+        )
+    }
+}
+
+/// A change to the live session numbers shown in the status bar.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetricsUpdate {
+    /// Published after each chunk streamed to the provider.
+    Audio {
+        summary: String,
+        /// How full the audio queue to the provider is, 0-1.
+        queue_fill: f32,
+        falling_behind: bool,
+    },
+    /// Published once a second while recording.
+    Elapsed(Duration),
+}
+
+/// Broadcasts `MetricsUpdate`s, like `StateBus` does for the recording
+/// state, so the status bar follows them without polling.
+#[derive(Clone)]
+pub struct MetricsBus {
+    tx: broadcast::Sender<MetricsUpdate>,
+}
+
+impl Default for MetricsBus {
+    fn default() -> Self {
+        let (tx, _) = broadcast::channel(64);
+        Self { tx }
+    }
+}
+
+impl MetricsBus {
+    pub fn publish(&self, update: MetricsUpdate) {
+        let _ = self.tx.send(update);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<MetricsUpdate> {
+        self.tx.subscribe()
+    }
+}
+
+/// Cumulative usage for one calendar month, persisted between runs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MonthlyTotals {
+    /// Month the totals belong to, formatted `YYYY-MM`.
+    pub month: String,
+    pub sessions: u64,
+    pub audio_seconds: f64,
+    pub characters_transcribed: u64,
+    pub words_injected: u64,
+}
+
+impl MonthlyTotals {
+    /// Returns the totals for `month`, or empty totals if the stored ones
+    /// belong to an earlier month.
+    pub fn for_month(&self, month: &str) -> MonthlyTotals {
+        if self.month == month {
+            self.clone()
+        } else {
+            MonthlyTotals {
+                month: month.to_string(),
+                ..Default::default()
+            }
+        }
+    }
+
+    pub fn add_session(&mut self, month: &str, session: &SessionMetrics) {
+        *self = self.for_month(month);
+        self.sessions += 1;
+        self.audio_seconds += session.audio_seconds();
+        self.characters_transcribed += session.characters_transcribed;
+        self.words_injected += session.words_injected;
+    }
+
+    pub fn summary(&self) -> String {
+        tr!(
+            "{} sessions · {}",
+            self.sessions,
+            format_summary(
+                self.audio_seconds,
+                self.characters_transcribed,
+                self.words_injected
+            )
+        )
+    }
+}
+
+pub fn estimated_cost_usd(audio_seconds: f64) -> f64 {
+    audio_seconds / 3600.0 * COST_PER_AUDIO_HOUR_USD
+}
+
+fn format_summary(audio_seconds: f64, characters: u64, words: u64) -> String {
+    let total = audio_seconds.round() as u64;
+    tr!(
+        "{}m {}s audio · {} chars · {} words injected · ~${}",
+        total / 60,
+        format!("{:02}", total % 60),
+        characters,
+        words,
+        format!("{:.2}", estimated_cost_usd(audio_seconds))
+    )
+}
+
+/// How long one transcript took through the pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencySample {
+    /// Last audio sent to the provider → transcript received.
+    pub transcription: Duration,
+    /// Transcript received → output delivered, excluding time spent in the
+    /// preview. None when nothing was typed.
+    pub delivery: Option<Duration>,
+}
+
+impl LatencySample {
+    pub fn total(&self) -> Duration {
+        self.transcription + self.delivery.unwrap_or_default()
+    }
+}
+
+/// Rolling latency over the last `LATENCY_WINDOW` transcripts. It spans
+/// sessions so providers and settings can be compared side by side.
+#[derive(Debug, Clone, Default)]
+pub struct LatencyTracker {
+    samples: VecDeque<LatencySample>,
+}
+
+impl LatencyTracker {
+    pub fn record(&mut self, sample: LatencySample) {
+        if self.samples.len() == LATENCY_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn last(&self) -> Option<LatencySample> {
+        self.samples.back().copied()
+    }
+
+    fn average(durations: impl Iterator<Item = Duration>) -> Option<Duration> {
+        let (sum, count) = durations.fold((Duration::ZERO, 0u32), |(sum, count), d| (sum + d, count + 1));
+        (count > 0).then(|| sum / count)
+    }
+
+    pub fn average_total(&self) -> Option<Duration> {
+        Self::average(self.samples.iter().map(LatencySample::total))
+    }
+
+    pub fn average_transcription(&self) -> Option<Duration> {
+        Self::average(self.samples.iter().map(|s| s.transcription))
+    }
+
+    pub fn average_delivery(&self) -> Option<Duration> {
+        Self::average(self.samples.iter().filter_map(|s| s.delivery))
+    }
+
+    /// e.g. "last 640 ms · avg 580 ms (text 420 ms + typing 160 ms) over 7".
+    pub fn summary(&self) -> String {
+        let (Some(last), Some(average)) = (self.last(), self.average_total()) else {
+            return tr!("No transcripts yet");
+        };
+        let mut breakdown = tr!("text {}", format_latency(self.average_transcription().unwrap_or_default()));
+        if let Some(delivery) = self.average_delivery() {
+            breakdown.push_str(&tr!(" + typing {}", format_latency(delivery)));
+        }
+        tr!(
+            "last {} · avg {} ({}) over {}",
+            format_latency(last.total()),
+            format_latency(average),
+            breakdown,
+            self.samples.len()
+        )
+    }
+}
+
+/// Milliseconds below a second, otherwise seconds with one decimal.
+pub fn format_latency(latency: Duration) -> String {
+    if latency < Duration::from_secs(1) {
+        format!("{} ms", latency.as_millis())
+    } else {
+        format!("{:.1} s", latency.as_secs_f64())
+    }
+}
+
+/// `M:SS`, or `H:MM:SS` once a session passes an hour.
+pub fn format_elapsed(elapsed: Duration) -> String {
+    let total = elapsed.as_secs();
+    let (hours, minutes, seconds) = (total / 3600, total / 60 % 60, total % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+/// What happens when a session reaches `max_session_minutes`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaxDurationAction {
+    #[default]
+    Stop,
+    Warn,
+}
+
+impl MaxDurationAction {
+    pub const ALL: [MaxDurationAction; 2] = [MaxDurationAction::Stop, MaxDurationAction::Warn];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MaxDurationAction::Stop => "Stop recording",
+            MaxDurationAction::Warn => "Warn only",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| i18n::is_label(action.label(), label))
+    }
+}
+
+/// Fires once when a session runs past its limit; a limit of 0 disables it.
+#[derive(Debug, Clone)]
+pub struct MaxDurationGuard {
+    limit: Option<Duration>,
+    fired: bool,
+}
+
+impl MaxDurationGuard {
+    pub fn new(max_minutes: u32) -> Self {
+        Self {
+            limit: (max_minutes > 0).then(|| Duration::from_secs(u64::from(max_minutes) * 60)),
+            fired: false,
+        }
+    }
+
+    /// True the first time `elapsed` reaches the limit.
+    pub fn check(&mut self, elapsed: Duration) -> bool {
+        match self.limit {
+            Some(limit) if !self.fired && elapsed >= limit => {
+                self.fired = true;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+pub fn current_month() -> String {
+    Local::now().format("%Y-%m").to_string()
+}
+
+pub fn load_monthly_totals() -> MonthlyTotals {
+    load_monthly_totals_from_path(&metrics_path())
+}
+
+pub fn load_monthly_totals_from_path(path: &Path) -> MonthlyTotals {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_monthly_totals(totals: &MonthlyTotals) {
+    save_monthly_totals_to_path(&metrics_path(), totals);
+}
+
+pub fn save_monthly_totals_to_path(path: &Path, totals: &MonthlyTotals) {
+    if let Some(parent) = path.parent() {
+        if let Err(err) = fs::create_dir_all(parent) {
+            error!("❌ Failed to create metrics directory {:?}: {}", parent, err);
+            return;
+        }
+    }
+    match serde_json::to_string_pretty(totals) {
+        Ok(json) => {
+            if let Err(err) = fs::write(path, json) {
+                error!("❌ Failed to save usage totals: {}", err);
+            }
+        }
+        Err(err) => error!("❌ Failed to serialize usage totals: {}", err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        format_elapsed, format_latency, load_monthly_totals_from_path, save_monthly_totals_to_path,
+        LatencySample, LatencyTracker, MaxDurationGuard, MetricsBus, MetricsUpdate, MonthlyTotals, SessionMetrics,
+        LATENCY_WINDOW,
+    };
+    use std::fs;
+    use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+    fn session() -> SessionMetrics {
+        let mut metrics = SessionMetrics::default();
+        metrics.record_audio(16000 * 60, 16000);
+        metrics.record_audio(8000 * 30, 8000);
+        metrics.record_transcript("héllo world");
+        metrics.record_injection("hello  brave new world ");
+        metrics
+    }
+
+    #[test]
+    fn session_counters_accumulate() {
+        let metrics = session();
+        assert_eq!(metrics.audio_seconds(), 90.0);
+        assert_eq!(metrics.characters_transcribed, 11);
+        assert_eq!(metrics.words_injected, 4);
+        assert_eq!(
+            metrics.summary(),
+            "1m 30s audio · 11 chars · 4 words injected · ~$0.01"
+        );
+        let sent = metrics.last_audio_at.unwrap();
+        assert_eq!(
+            metrics.transcription_latency(None, sent + Duration::from_millis(250)),
+            Some(Duration::from_millis(250))
+        );
+        assert_eq!(
+            metrics.transcription_latency(Some(sent), sent + Duration::from_millis(900)),
+            Some(Duration::from_millis(900))
+        );
+        assert_eq!(SessionMetrics::default().transcription_latency(None, Instant::now()), None);
+    }
+
+    #[test]
+    fn metrics_bus_reaches_every_subscriber() {
+        let bus = MetricsBus::default();
+        bus.publish(MetricsUpdate::Elapsed(Duration::from_secs(1)));
+        let mut first = bus.subscribe();
+        let mut second = bus.subscribe();
+        let update = MetricsUpdate::Audio {
+            summary: "0m 01s audio".to_string(),
+            queue_fill: 0.5,
+            falling_behind: true,
+        };
+        bus.publish(update.clone());
+        assert_eq!(first.try_recv().unwrap(), update);
+        assert_eq!(second.try_recv().unwrap(), update);
+        assert!(first.try_recv().is_err());
+    }
+
+    #[test]
+    fn latency_tracker_rolls_over_recent_transcripts() {
+        let ms = Duration::from_millis;
+        let mut tracker = LatencyTracker::default();
+        assert_eq!(tracker.summary(), "No transcripts yet");
+        tracker.record(LatencySample { transcription: ms(300), delivery: None });
+        tracker.record(LatencySample { transcription: ms(500), delivery: Some(ms(200)) });
+        assert_eq!(tracker.last().unwrap().total(), ms(700));
+        assert_eq!(tracker.average_total(), Some(ms(500)));
+        assert_eq!(
+            tracker.summary(),
+            "last 700 ms · avg 500 ms (text 400 ms + typing 200 ms) over 2"
+        );
+
+        for _ in 0..LATENCY_WINDOW {
+            tracker.record(LatencySample { transcription: ms(1500), delivery: None });
+        }
+        assert_eq!(tracker.average_total(), Some(ms(1500)));
+        assert_eq!(tracker.average_delivery(), None);
+        assert_eq!(format_latency(ms(1500)), "1.5 s");
+    }
+
+    #[test]
+    fn monthly_totals_reset_when_month_changes() {
+        let mut totals = MonthlyTotals::default();
+        totals.add_session("2026-09", &session());
+        totals.add_session("2026-09", &session());
+        assert_eq!(totals.sessions, 2);
+        assert_eq!(totals.words_injected, 8);
+
+        totals.add_session("2026-10", &session());
+        assert_eq!(totals.month, "2026-10");
+        assert_eq!(totals.sessions, 1);
+        assert_eq!(totals.for_month("2026-11").sessions, 0);
+    }
+
+    #[test]
+    fn monthly_totals_roundtrip_through_disk() {
+        let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        let path = std::env::temp_dir().join(format!("eleventh_echo_metrics_{}.json", stamp));
+        let mut totals = MonthlyTotals::default();
+        totals.add_session("2026-10", &session());
+        save_monthly_totals_to_path(&path, &totals);
+        assert_eq!(load_monthly_totals_from_path(&path), totals);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn elapsed_time_formats_minutes_and_hours() {
+        assert_eq!(format_elapsed(Duration::from_secs(0)), "0:00");
+        assert_eq!(format_elapsed(Duration::from_secs(192)), "3:12");
+        assert_eq!(format_elapsed(Duration::from_secs(3723)), "1:02:03");
+    }
+
+    #[test]
+    fn max_duration_guard_fires_once_and_can_be_disabled() {
+        let mut guard = MaxDurationGuard::new(10);
+        assert!(!guard.check(Duration::from_secs(599)));
+        assert!(guard.check(Duration::from_secs(600)));
+        assert!(!guard.check(Duration::from_secs(900)));
+        assert!(!MaxDurationGuard::new(0).check(Duration::from_secs(86_400)));
+    }
+}
@@ -0,0 +1,209 @@
+// "Test microphone": runs the capture pipeline without a provider so the
+// level meter (and optionally the speakers) show what a session would hear.
+
+use crate::audio;
+use crate::error::{EchoError, EchoResult};
+use crate::settings::AppSettings;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SizedSample};
+use tokio::sync::mpsc;
+use tracing::warn;
+
+/// Sample rate of the chunks the capture pipeline delivers.
+const CAPTURE_SAMPLE_RATE: u32 = 16000;
+/// Playback headroom; anything beyond it is dropped rather than delayed.
+const MONITOR_BUFFER_SECS: usize = 1;
+
+/// A running microphone test. Dropping it stops capture and playback.
+pub struct MicTest {
+    _capture: audio::AudioCapture,
+    _monitor: Option<cpal::Stream>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl Drop for MicTest {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl MicTest {
+    /// Opens the configured input and reports its peak level through
+    /// `on_level`. With `loopback` the audio is also played on the default
+    /// output device, roughly one resampler chunk behind.
+    pub fn start(
+        settings: &AppSettings,
+        loopback: bool,
+        on_level: impl Fn(f32) + Send + 'static,
+    ) -> EchoResult<Self> {
+        let preferred_device = (!settings.use_default_microphone).then(|| settings.selected_microphone.clone());
+        let (audio_tx, mut audio_rx) = mpsc::channel::<audio::AudioChunk>(8);
+        let (level_tx, mut level_rx) = mpsc::channel::<f32>(10);
+        let (device_lost_tx, _) = mpsc::unbounded_channel::<()>();
+        let (monitor_stream, mut monitor) = if loopback {
+            let (stream, output) = MonitorOutput::open()?;
+            (Some(stream), Some(output))
+        } else {
+            (None, None)
+        };
+        let capture = audio::start_audio_capture(
+            audio_tx,
+            level_tx,
+            preferred_device,
+            None,
+            audio::CaptureOptions {
+                noise_suppression: settings.noise_suppression,
+                source: settings.capture_source,
+                resampler: settings.resampler_quality,
+                boost_priority: settings.boost_audio_thread,
+                ..audio::CaptureOptions::default()
+            },
+            device_lost_tx,
+        )?;
+
+        let task = tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    Some(level) = level_rx.recv() => on_level(level),
+                    chunk = audio_rx.recv() => {
+                        let Some(chunk) = chunk else { break };
+                        if let Some(monitor) = monitor.as_mut() {
+                            monitor.push(&chunk);
+                        }
+                    }
+                }
+            }
+        });
+        Ok(Self {
+            _capture: capture,
+            _monitor: monitor_stream,
+            task,
+        })
+    }
+}
+
+/// Feeds 16kHz mono chunks to a stream on the default output device. The
+/// stream itself stays with `MicTest`, since it can't move between threads.
+struct MonitorOutput {
+    producer: rtrb::Producer<f32>,
+    resampler: LinearResampler,
+}
+
+impl MonitorOutput {
+    fn open() -> EchoResult<(cpal::Stream, Self)> {
+        let host = cpal::default_host();
+        let device = host
+            .default_output_device()
+            .ok_or_else(|| EchoError::audio("No output device available"))?;
+        let config = device.default_output_config()?;
+        let sample_rate = config.sample_rate().0;
+        let channels = config.channels() as usize;
+        let (producer, consumer) = rtrb::RingBuffer::<f32>::new(sample_rate as usize * MONITOR_BUFFER_SECS);
+
+        let sample_format = config.sample_format();
+        let stream_config: cpal::StreamConfig = config.into();
+        let stream = match sample_format {
+            cpal::SampleFormat::I16 => build_monitor_stream::<i16>(&device, &stream_config, consumer, channels),
+            cpal::SampleFormat::I32 => build_monitor_stream::<i32>(&device, &stream_config, consumer, channels),
+            cpal::SampleFormat::U16 => build_monitor_stream::<u16>(&device, &stream_config, consumer, channels),
+            cpal::SampleFormat::F32 => build_monitor_stream::<f32>(&device, &stream_config, consumer, channels),
+            cpal::SampleFormat::F64 => build_monitor_stream::<f64>(&device, &stream_config, consumer, channels),
+            other => return Err(EchoError::audio(format!("Unsupported output sample format {}", other))),
+        }?;
+        stream.play()?;
+        let output = Self {
+            producer,
+            resampler: LinearResampler::new(CAPTURE_SAMPLE_RATE, sample_rate),
+        };
+        Ok((stream, output))
+    }
+
+    fn push(&mut self, chunk: &[i16]) {
+        for sample in self.resampler.process(chunk) {
+            if self.producer.push(sample).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+fn build_monitor_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut consumer: rtrb::Consumer<f32>,
+    channels: usize,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    device.build_output_stream(
+        config,
+        move |data: &mut [T], _: &_| {
+            for frame in data.chunks_mut(channels) {
+                frame.fill(T::from_sample(consumer.pop().unwrap_or(0.0)));
+            }
+        },
+        |err| warn!("⚠️ Monitor output error: {}", err),
+        None,
+    )
+}
+
+/// Linear interpolation between rates; plenty for listening back to a
+/// microphone, and it keeps its position across chunks.
+struct LinearResampler {
+    step: f64,
+    position: f64,
+    previous: f32,
+}
+
+impl LinearResampler {
+    fn new(input_rate: u32, output_rate: u32) -> Self {
+        Self {
+            step: f64::from(input_rate) / f64::from(output_rate),
+            position: 0.0,
+            previous: 0.0,
+        }
+    }
+
+    fn process(&mut self, chunk: &[i16]) -> Vec<f32> {
+        let to_f32 = |s: i16| f32::from(s) / f32::from(i16::MAX);
+        let mut output = Vec::with_capacity((chunk.len() as f64 / self.step) as usize + 1);
+        // `position` is relative to `previous`, the last sample of the
+        // previous chunk, which sits at index -1 of this one.
+        while self.position < chunk.len() as f64 {
+            let index = self.position.floor();
+            let frac = (self.position - index) as f32;
+            let before = if index < 1.0 { self.previous } else { to_f32(chunk[index as usize - 1]) };
+            let after = to_f32(chunk[index as usize]);
+            output.push(before + (after - before) * frac);
+            self.position += self.step;
+        }
+        self.position -= chunk.len() as f64;
+        if let Some(&last) = chunk.last() {
+            self.previous = to_f32(last);
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LinearResampler;
+
+    #[test]
+    fn linear_resampler_upsamples_continuously_across_chunks() {
+        let mut resampler = LinearResampler::new(16000, 48000);
+        let first = resampler.process(&[i16::MAX; 160]);
+        let second = resampler.process(&[i16::MAX; 160]);
+        assert_eq!(first.len() + second.len(), 960);
+        assert_eq!(first[0], 0.0);
+        assert!(second.iter().all(|&s| (s - 1.0).abs() < 1e-6));
+    }
+
+    #[test]
+    fn linear_resampler_interpolates_between_samples() {
+        let mut resampler = LinearResampler::new(1, 2);
+        let output = resampler.process(&[0, i16::MAX]);
+        assert_eq!(output, vec![0.0, 0.0, 0.0, 0.5]);
+    }
+}
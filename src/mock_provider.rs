@@ -0,0 +1,261 @@
+use crate::error::EchoResult;
+use crate::network::{ControlMessage, ProviderError, TranscriptEvent, TranscriptSegment};
+use crate::provider::{emit_log, ProviderCapabilities, SpeechProvider};
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::mpsc::{Receiver, Sender, UnboundedReceiver, UnboundedSender};
+use tokio::time::{sleep_until, Instant};
+
+/// One scripted provider message. `after_ms` counts from the previous step
+/// (or from the session start for the first one).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FixtureStep {
+    #[serde(default)]
+    pub after_ms: u64,
+    #[serde(flatten)]
+    pub event: FixtureEvent,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FixtureEvent {
+    Partial {
+        text: String,
+    },
+    Committed {
+        text: String,
+        #[serde(default)]
+        confidence: Option<f32>,
+    },
+    Error {
+        kind: String,
+        message: String,
+    },
+}
+
+impl FixtureEvent {
+    fn into_event(self) -> TranscriptEvent {
+        match self {
+            FixtureEvent::Partial { text } => TranscriptEvent::Partial(TranscriptSegment::text(text)),
+            FixtureEvent::Committed { text, confidence } => TranscriptEvent::Committed(TranscriptSegment {
+                confidence,
+                ..TranscriptSegment::text(text)
+            }),
+            FixtureEvent::Error { kind, message } => TranscriptEvent::Error(ProviderError::new(kind, message)),
+        }
+    }
+}
+
+/// Offline stand-in for a real provider: ignores the audio and replays a
+/// fixture of transcript events on its own schedule. Selected by the
+/// `mock_provider_fixture` setting, which the UI does not expose.
+pub struct MockProvider {
+    steps: Vec<FixtureStep>,
+}
+
+impl MockProvider {
+    pub fn new(steps: Vec<FixtureStep>) -> Self {
+        Self { steps }
+    }
+
+    /// Loads a fixture: a JSON array of steps such as
+    /// `{"after_ms": 400, "type": "committed", "text": "hello"}`.
+    /// `tests/fixtures/mock_dictation.json` is a complete example.
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read mock fixture {}: {}", path.display(), e))?;
+        let steps = serde_json::from_str(&contents)
+            .map_err(|e| format!("Invalid mock fixture {}: {}", path.display(), e))?;
+        Ok(Self::new(steps))
+    }
+}
+
+#[async_trait]
+impl SpeechProvider for MockProvider {
+    fn name(&self) -> &'static str {
+        "Mock"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities::FULL
+    }
+
+    async fn run(
+        &self,
+        mut audio_rx: Receiver<Vec<i16>>,
+        mut control_rx: UnboundedReceiver<ControlMessage>,
+        text_tx: Sender<TranscriptEvent>,
+        log_tx: UnboundedSender<String>,
+    ) -> EchoResult<()> {
+        emit_log(&log_tx, format!("🧪 Replaying {} mock transcript events", self.steps.len()));
+        let _ = text_tx
+            .send(TranscriptEvent::SessionStarted {
+                session_id: Some("mock".to_string()),
+            })
+            .await;
+
+        let mut pending = self.steps.iter().cloned();
+        let mut next = pending.next();
+        let mut due = Instant::now() + Duration::from_millis(next.as_ref().map_or(0, |s| s.after_ms));
+
+        loop {
+            tokio::select! {
+                _ = sleep_until(due), if next.is_some() => {
+                    if let Some(step) = next.take() {
+                        let _ = text_tx.send(step.event.into_event()).await;
+                    }
+                    next = pending.next();
+                    if let Some(step) = &next {
+                        due += Duration::from_millis(step.after_ms);
+                    }
+                }
+                Some(cmd) = control_rx.recv() => {
+                    match cmd {
+                        ControlMessage::Start => {}
+                        ControlMessage::Stop => break,
+                        ControlMessage::Cancel => {
+                            emit_log(&log_tx, "➡️ Mock session cancelled".to_string());
+                            return Ok(());
+                        }
+                    }
+                }
+                maybe_chunk = audio_rx.recv() => {
+                    if maybe_chunk.is_none() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        // Whatever was still scheduled becomes the final commit.
+        let remaining = next
+            .into_iter()
+            .chain(pending)
+            .filter_map(|step| match step.event {
+                FixtureEvent::Committed { text, confidence } => Some(TranscriptSegment {
+                    confidence,
+                    ..TranscriptSegment::text(text)
+                }),
+                _ => None,
+            })
+            .collect();
+        let final_segment = TranscriptSegment::join(remaining);
+        emit_log(&log_tx, format!("📝 [COMMITTED] {}", final_segment.text));
+        let _ = text_tx.send(TranscriptEvent::Committed(final_segment)).await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FixtureEvent, FixtureStep, MockProvider};
+    use crate::network::{ControlMessage, TranscriptEvent, TranscriptSegment};
+    use crate::pipeline::TranscriptPipeline;
+    use crate::provider::SpeechProvider;
+    use std::path::Path;
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    fn committed(after_ms: u64, text: &str) -> FixtureStep {
+        FixtureStep {
+            after_ms,
+            event: FixtureEvent::Committed {
+                text: text.to_string(),
+                confidence: None,
+            },
+        }
+    }
+
+    #[test]
+    fn fixture_steps_parse_from_json() {
+        let json = r#"[
+            {"after_ms": 100, "type": "partial", "text": "hel"},
+            {"type": "committed", "text": "hello", "confidence": 0.5},
+            {"after_ms": 10, "type": "error", "kind": "quota_exceeded", "message": "out of credit"}
+        ]"#;
+        let steps: Vec<FixtureStep> = serde_json::from_str(json).unwrap();
+        assert_eq!(steps.len(), 3);
+        assert_eq!(steps[1].after_ms, 0);
+        assert_eq!(
+            steps[1].event,
+            FixtureEvent::Committed {
+                text: "hello".to_string(),
+                confidence: Some(0.5)
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn replays_due_events_and_commits_the_rest_on_stop() {
+        let provider = MockProvider::new(vec![committed(0, "first"), committed(60_000, "second")]);
+        let (_audio_tx, audio_rx) = mpsc::channel(4);
+        let (control_tx, control_rx) = mpsc::unbounded_channel();
+        let (text_tx, mut text_rx) = mpsc::channel(8);
+        let (log_tx, _log_rx) = mpsc::unbounded_channel();
+        let run = tokio::spawn(async move { provider.run(audio_rx, control_rx, text_tx, log_tx).await.unwrap() });
+
+        assert!(matches!(text_rx.recv().await, Some(TranscriptEvent::SessionStarted { .. })));
+        assert_eq!(
+            text_rx.recv().await,
+            Some(TranscriptEvent::Committed(TranscriptSegment::text("first")))
+        );
+        control_tx.send(ControlMessage::Stop).unwrap();
+        assert_eq!(
+            text_rx.recv().await,
+            Some(TranscriptEvent::Committed(TranscriptSegment::text("second")))
+        );
+        tokio::time::timeout(Duration::from_secs(1), run).await.unwrap().unwrap();
+        assert_eq!(text_rx.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn cancel_returns_without_a_final_commit() {
+        let provider = MockProvider::new(vec![committed(60_000, "never")]);
+        let (_audio_tx, audio_rx) = mpsc::channel(4);
+        let (control_tx, control_rx) = mpsc::unbounded_channel();
+        let (text_tx, mut text_rx) = mpsc::channel(8);
+        let (log_tx, _log_rx) = mpsc::unbounded_channel();
+        control_tx.send(ControlMessage::Cancel).unwrap();
+        provider.run(audio_rx, control_rx, text_tx, log_tx).await.unwrap();
+
+        assert!(matches!(text_rx.recv().await, Some(TranscriptEvent::SessionStarted { .. })));
+        assert_eq!(text_rx.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn fixture_session_builds_the_transcript() {
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/mock_dictation.json");
+        let provider = MockProvider::from_file(&fixture).unwrap();
+        let (_audio_tx, audio_rx) = mpsc::channel(4);
+        let (control_tx, control_rx) = mpsc::unbounded_channel();
+        let (text_tx, mut text_rx) = mpsc::channel(8);
+        let (log_tx, _log_rx) = mpsc::unbounded_channel();
+        let run = tokio::spawn(async move { provider.run(audio_rx, control_rx, text_tx, log_tx).await.unwrap() });
+
+        let mut pipeline = TranscriptPipeline::new();
+        let mut partials = Vec::new();
+        let mut commits = 0;
+        while let Some(event) = text_rx.recv().await {
+            match event {
+                TranscriptEvent::Partial(segment) => partials.push(segment.text),
+                TranscriptEvent::Committed(segment) => {
+                    pipeline.push_segment(&segment.text, segment.start_secs.zip(segment.end_secs));
+                    commits += 1;
+                    // The last step is a minute out; stopping flushes it.
+                    if commits == 2 {
+                        control_tx.send(ControlMessage::Stop).unwrap();
+                    }
+                }
+                _ => {}
+            }
+        }
+        tokio::time::timeout(Duration::from_secs(1), run).await.unwrap().unwrap();
+
+        assert_eq!(partials, ["so the", "we"]);
+        assert_eq!(commits, 3);
+        assert_eq!(pipeline.committed_text(), "So the meeting moved. We meet on friday at noon. ");
+    }
+}
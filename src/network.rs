@@ -1,32 +1,236 @@
+use crate::audio::{AudioEncoding, AudioFormat};
+use crate::error::{EchoError, EchoResult};
+use crate::i18n::{self, tr};
+use crate::meeting::Speaker;
+use crate::provider::{connect_websocket, ConnectError, ProviderCapabilities, ProviderKind, SpeechProvider};
+use crate::proxy::ProxyConfig;
+use async_trait::async_trait;
 use futures_util::{SinkExt, StreamExt};
-use serde_json::json;
-use std::error::Error;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc::{Receiver, UnboundedReceiver}; // Bounded receiver
 use tokio::sync::mpsc;
+use tracing::info;
 use tokio_tungstenite::tungstenite::client::IntoClientRequest;
-use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
 use url::Url;
 use base64::{Engine as _, engine::general_purpose};
 
 const ELEVENLABS_WSS_URL: &str = "wss://api.elevenlabs.io/v1/speech-to-text/realtime";
+const ELEVENLABS_MODELS_URL: &str = "https://api.elevenlabs.io/v1/models";
+const ELEVENLABS_SUBSCRIPTION_URL: &str = "https://api.elevenlabs.io/v1/user/subscription";
+/// Realtime speech-to-text models offered even when the model list can't be
+/// fetched; the first one is the default.
+pub const KNOWN_REALTIME_MODELS: &[&str] = &["scribe_v2_realtime"];
 
 pub struct ElevenLabsClient {
     api_key: String,
     model_id: String,
+    vocabulary: Vec<String>,
+    diarize: bool,
+    word_timestamps: bool,
+    format: AudioFormat,
+    proxy: Option<ProxyConfig>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ControlMessage {
     Start,
     Stop,
+    /// Drop the session without waiting for a final transcript.
+    Cancel,
 }
 
-#[derive(Debug, Clone)]
-pub enum TranscriptMessage {
-    Partial(String),
-    Committed(String),
-    Error(String),
+/// Events a provider reports over the transcript channel.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TranscriptEvent {
+    SessionStarted { session_id: Option<String> },
+    Partial(TranscriptSegment),
+    Committed(TranscriptSegment),
+    Error(ProviderError),
+    /// The provider closed the stream normally.
+    EndOfStream,
+}
+
+/// One recognized word. Times are seconds from the start of the session.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WordTiming {
+    pub text: String,
+    pub start_secs: f64,
+    pub end_secs: f64,
+    pub confidence: Option<f32>,
+    /// Provider speaker id when diarization is on, e.g. `speaker_0`.
+    #[serde(default)]
+    pub speaker: Option<String>,
+}
+
+/// Recognized text plus whatever timing and confidence the provider reports.
+/// Confidence is normalized to 0.0..=1.0; `None` means the provider gave none.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TranscriptSegment {
+    pub text: String,
+    pub start_secs: Option<f64>,
+    pub end_secs: Option<f64>,
+    pub confidence: Option<f32>,
+    pub words: Vec<WordTiming>,
+    /// Which side of the call it came from in meeting mode; the text already
+    /// carries the label.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel: Option<Speaker>,
+}
+
+impl TranscriptSegment {
+    /// A segment with text only.
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Self::default()
+        }
+    }
+
+    /// A segment whose span and confidence are derived from its words.
+    pub fn from_words(text: impl Into<String>, words: Vec<WordTiming>) -> Self {
+        let confidences: Vec<f32> = words.iter().filter_map(|w| w.confidence).collect();
+        Self {
+            text: text.into(),
+            start_secs: words.first().map(|w| w.start_secs),
+            end_secs: words.last().map(|w| w.end_secs),
+            confidence: (!confidences.is_empty())
+                .then(|| confidences.iter().sum::<f32>() / confidences.len() as f32),
+            words,
+            channel: None,
+        }
+    }
+
+    /// Joins consecutive segments. The result carries the lowest confidence
+    /// so a single doubtful phrase still flags the whole commit.
+    pub fn join(segments: Vec<TranscriptSegment>) -> Self {
+        let text = segments
+            .iter()
+            .map(|s| s.text.trim())
+            .filter(|t| !t.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        Self {
+            text,
+            start_secs: segments.iter().find_map(|s| s.start_secs),
+            end_secs: segments.iter().rev().find_map(|s| s.end_secs),
+            confidence: segments
+                .iter()
+                .filter_map(|s| s.confidence)
+                .reduce(f32::min),
+            channel: segments.iter().find_map(|s| s.channel),
+            words: segments.into_iter().flat_map(|s| s.words).collect(),
+        }
+    }
+
+    /// Splits the segment wherever two words are at least `pause_secs`
+    /// apart, so a long pause inside one commit can still start a paragraph.
+    /// Each part is rebuilt from its words. A segment without word timings,
+    /// or without such a pause, comes back whole.
+    pub fn split_at_pauses(self, pause_secs: f64) -> Vec<TranscriptSegment> {
+        let paused = pause_secs > 0.0
+            && self
+                .words
+                .windows(2)
+                .any(|pair| pair[1].start_secs - pair[0].end_secs >= pause_secs);
+        if !paused {
+            return vec![self];
+        }
+        let channel = self.channel;
+        let mut parts: Vec<Vec<WordTiming>> = Vec::new();
+        for word in self.words {
+            match parts.last_mut() {
+                Some(part) if part.last().is_some_and(|last| word.start_secs - last.end_secs < pause_secs) => {
+                    part.push(word)
+                }
+                _ => parts.push(vec![word]),
+            }
+        }
+        parts
+            .into_iter()
+            .map(|words| {
+                let text = words.iter().map(|w| w.text.trim()).collect::<Vec<_>>().join(" ");
+                Self {
+                    channel,
+                    ..Self::from_words(text, words)
+                }
+            })
+            .collect()
+    }
+
+    /// True when the provider reported a confidence below `threshold`.
+    /// A threshold of zero disables the check.
+    pub fn is_low_confidence(&self, threshold: f32) -> bool {
+        threshold > 0.0 && self.confidence.is_some_and(|c| c < threshold)
+    }
+
+    /// The words grouped into speaker turns, each prefixed with its label
+    /// ("Speaker 1: …"). `None` when no word carries a speaker id.
+    pub fn speaker_labelled_text(&self) -> Option<String> {
+        if self.words.iter().all(|w| w.speaker.is_none()) {
+            return None;
+        }
+        let mut turns: Vec<(Option<&str>, Vec<&str>)> = Vec::new();
+        for word in &self.words {
+            let speaker = word.speaker.as_deref();
+            match turns.last_mut() {
+                Some((current, words)) if *current == speaker || speaker.is_none() => words.push(word.text.trim()),
+                _ => turns.push((speaker, vec![word.text.trim()])),
+            }
+        }
+        let rendered = turns
+            .into_iter()
+            .map(|(speaker, words)| {
+                let text = words.join(" ");
+                match speaker {
+                    Some(id) => format!("{}: {}", speaker_label(id), text),
+                    None => text,
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        Some(rendered)
+    }
+}
+
+/// Display name for a provider speaker id: zero-based numeric ids such as
+/// `speaker_0` become "Speaker 1", anything else is shown as is.
+pub fn speaker_label(id: &str) -> String {
+    let digits = id.trim_start_matches(|c: char| !c.is_ascii_digit());
+    match digits.parse::<u32>() {
+        Ok(n) if !digits.is_empty() => format!("Speaker {}", n + 1),
+        _ => format!("Speaker {}", id),
+    }
+}
+
+/// A failure reported by (or while talking to) a speech provider. `kind` is
+/// the provider's error type where it has one, e.g. `auth_error`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProviderError {
+    pub kind: String,
+    pub message: String,
+}
+
+impl ProviderError {
+    pub fn new(kind: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            kind: kind.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.kind, self.message)
+    }
 }
 
 #[derive(Debug)]
@@ -35,114 +239,519 @@ enum WsEvent {
     CommittedTranscriptReceived,
 }
 
-#[derive(Debug, PartialEq, Eq)]
-enum ParsedIncoming {
-    SessionStarted,
-    PartialTranscript(String),
-    CommittedTranscript(String),
-    Error(String),
-    Other,
+/// Body shared by every ElevenLabs error message type.
+#[derive(Debug, Default, Deserialize, PartialEq, Eq)]
+struct ErrorPayload {
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    message: Option<String>,
 }
 
-fn parse_incoming_message(text: &str) -> ParsedIncoming {
-    let parsed: serde_json::Value = match serde_json::from_str(text) {
-        Ok(v) => v,
-        Err(e) => return ParsedIncoming::Error(format!("Invalid JSON: {}", e)),
-    };
+/// Word entry of `committed_transcript_with_timestamps`. `type` also covers
+/// spacing and audio events, which are dropped.
+#[derive(Debug, Deserialize, PartialEq)]
+struct TimestampedWord {
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    start: f64,
+    #[serde(default)]
+    end: f64,
+    #[serde(default, rename = "type")]
+    kind: Option<String>,
+    #[serde(default)]
+    logprob: Option<f64>,
+    #[serde(default)]
+    speaker_id: Option<String>,
+}
 
-    let msg_type = parsed
-        .get("message_type")
-        .and_then(|v| v.as_str())
-        .unwrap_or_default();
-
-    match msg_type {
-        "session_started" => ParsedIncoming::SessionStarted,
-        "partial_transcript" => ParsedIncoming::PartialTranscript(
-            parsed
-                .get("text")
-                .and_then(|v| v.as_str())
-                .unwrap_or_default()
-                .to_string(),
-        ),
-        "committed_transcript" => ParsedIncoming::CommittedTranscript(
-            parsed
-                .get("text")
-                .and_then(|v| v.as_str())
-                .unwrap_or_default()
-                .to_string(),
-        ),
-        "auth_error" | "quota_exceeded" | "transcriber_error" | "input_error" | "error" | "invalid_request" => {
-            ParsedIncoming::Error(parsed.to_string())
+impl TimestampedWord {
+    fn into_word(self) -> Option<WordTiming> {
+        if self.kind.as_deref().is_some_and(|k| k != "word") {
+            return None;
         }
-        _ => ParsedIncoming::Other,
+        Some(WordTiming {
+            text: self.text,
+            start_secs: self.start,
+            end_secs: self.end,
+            confidence: self.logprob.map(|lp| lp.exp().clamp(0.0, 1.0) as f32),
+            speaker: self.speaker_id,
+        })
     }
 }
 
-fn audio_chunk_payload(chunk: &[i16], commit: bool) -> String {
-    let byte_data: Vec<u8> = chunk.iter().flat_map(|&s| s.to_le_bytes().to_vec()).collect();
-    let b64 = general_purpose::STANDARD.encode(&byte_data);
-    if commit {
-        json!({
-            "message_type": "input_audio_chunk",
-            "audio_base_64": b64,
-            "sample_rate": 16000,
-            "commit": true
-        })
-        .to_string()
-    } else {
-        json!({
-            "message_type": "input_audio_chunk",
-            "audio_base_64": b64,
-            "sample_rate": 16000
+/// ElevenLabs realtime speech-to-text server messages, tagged by `message_type`.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(tag = "message_type", rename_all = "snake_case")]
+enum IncomingMessage {
+    SessionStarted {
+        #[serde(default)]
+        session_id: Option<String>,
+    },
+    PartialTranscript {
+        #[serde(default)]
+        text: String,
+    },
+    CommittedTranscript {
+        #[serde(default)]
+        text: String,
+    },
+    CommittedTranscriptWithTimestamps {
+        #[serde(default)]
+        text: String,
+        #[serde(default)]
+        words: Vec<TimestampedWord>,
+    },
+    AuthError(ErrorPayload),
+    QuotaExceeded(ErrorPayload),
+    RateLimited(ErrorPayload),
+    CommitThrottled(ErrorPayload),
+    QueueOverflow(ErrorPayload),
+    ResourceExhausted(ErrorPayload),
+    SessionTimeLimitExceeded(ErrorPayload),
+    ChunkSizeExceeded(ErrorPayload),
+    InsufficientAudioActivity(ErrorPayload),
+    UnacceptedTerms(ErrorPayload),
+    TranscriberError(ErrorPayload),
+    InputError(ErrorPayload),
+    InvalidRequest(ErrorPayload),
+    Error(ErrorPayload),
+    #[serde(other)]
+    Unknown,
+}
+
+impl IncomingMessage {
+    fn into_event(self) -> Option<TranscriptEvent> {
+        let (kind, payload) = match self {
+            Self::SessionStarted { session_id } => {
+                return Some(TranscriptEvent::SessionStarted { session_id })
+            }
+            Self::PartialTranscript { text } => return Some(TranscriptEvent::Partial(TranscriptSegment::text(text))),
+            Self::CommittedTranscript { text } => return Some(TranscriptEvent::Committed(TranscriptSegment::text(text))),
+            Self::CommittedTranscriptWithTimestamps { text, words } => {
+                let words = words.into_iter().filter_map(TimestampedWord::into_word).collect();
+                return Some(TranscriptEvent::Committed(TranscriptSegment::from_words(text, words)));
+            }
+            Self::Unknown => return None,
+            Self::AuthError(p) => ("auth_error", p),
+            Self::QuotaExceeded(p) => ("quota_exceeded", p),
+            Self::RateLimited(p) => ("rate_limited", p),
+            Self::CommitThrottled(p) => ("commit_throttled", p),
+            Self::QueueOverflow(p) => ("queue_overflow", p),
+            Self::ResourceExhausted(p) => ("resource_exhausted", p),
+            Self::SessionTimeLimitExceeded(p) => ("session_time_limit_exceeded", p),
+            Self::ChunkSizeExceeded(p) => ("chunk_size_exceeded", p),
+            Self::InsufficientAudioActivity(p) => ("insufficient_audio_activity", p),
+            Self::UnacceptedTerms(p) => ("unaccepted_terms", p),
+            Self::TranscriberError(p) => ("transcriber_error", p),
+            Self::InputError(p) => ("input_error", p),
+            Self::InvalidRequest(p) => ("invalid_request", p),
+            Self::Error(p) => ("error", p),
+        };
+        let message = payload
+            .error
+            .or(payload.message)
+            .unwrap_or_else(|| "no details provided".to_string());
+        Some(TranscriptEvent::Error(ProviderError::new(kind, message)))
+    }
+}
+
+/// Parses a server text frame; `None` for message types we do not act on.
+fn parse_incoming_message(text: &str) -> Option<TranscriptEvent> {
+    match serde_json::from_str::<IncomingMessage>(text) {
+        Ok(message) => message.into_event(),
+        Err(e) => Some(TranscriptEvent::Error(ProviderError::new(
+            "invalid_message",
+            format!("Invalid JSON: {}", e),
+        ))),
+    }
+}
+
+/// How often a Ping frame is sent to keep NAT/proxy mappings alive and to
+/// give the watchdog something to hear back.
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(10);
+/// With pings going out every `KEEPALIVE_INTERVAL`, this long without any
+/// inbound frame means the connection is dead even if TCP has not noticed.
+const STALL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Tracks when the socket last received anything. Cloned into the read task,
+/// which calls `touch` for every inbound frame.
+#[derive(Clone)]
+struct ActivityWatchdog {
+    started: Instant,
+    last_activity_ms: Arc<AtomicU64>,
+}
+
+impl ActivityWatchdog {
+    fn new(started: Instant) -> Self {
+        Self {
+            started,
+            last_activity_ms: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    fn touch(&self, now: Instant) {
+        let ms = now.saturating_duration_since(self.started).as_millis() as u64;
+        self.last_activity_ms.fetch_max(ms, Ordering::Relaxed);
+    }
+
+    fn idle_for(&self, now: Instant) -> Duration {
+        let last = self.started + Duration::from_millis(self.last_activity_ms.load(Ordering::Relaxed));
+        now.saturating_duration_since(last)
+    }
+}
+
+/// Upper bound for samples packed into a single `input_audio_chunk` message
+/// when several captured chunks are waiting (2s at 16kHz).
+const MAX_BATCH_SAMPLES: usize = 32000;
+
+#[derive(Serialize)]
+struct AudioChunkMessage<'a> {
+    message_type: &'static str,
+    audio_base_64: &'a str,
+    sample_rate: u32,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    commit: bool,
+}
+
+/// Builds `input_audio_chunk` payloads. The realtime API only accepts
+/// base64 audio inside JSON text frames, so the byte buffer and the
+/// base64 string are kept between calls to avoid reallocating per chunk.
+#[derive(Default)]
+struct AudioPayloadEncoder {
+    format: AudioFormat,
+    bytes: Vec<u8>,
+    encoded: String,
+}
+
+impl AudioPayloadEncoder {
+    fn encode<'a, I>(&mut self, chunks: I, commit: bool) -> String
+    where
+        I: IntoIterator<Item = &'a [i16]>,
+    {
+        self.bytes.clear();
+        for chunk in chunks {
+            self.format.encode(chunk, &mut self.bytes);
+        }
+        self.encoded.clear();
+        general_purpose::STANDARD.encode_string(&self.bytes, &mut self.encoded);
+
+        serde_json::to_string(&AudioChunkMessage {
+            message_type: "input_audio_chunk",
+            audio_base_64: &self.encoded,
+            sample_rate: self.format.sample_rate,
+            commit,
         })
-        .to_string()
+        .expect("audio chunk message serializes")
+    }
+
+    /// 200ms of silence.
+    fn encode_silence(&mut self, commit: bool) -> String {
+        let silence = vec![0i16; self.format.sample_rate as usize / 5];
+        self.encode([&silence[..]], commit)
     }
 }
 
+#[cfg(test)]
+fn audio_chunk_payload(chunk: &[i16], commit: bool) -> String {
+    AudioPayloadEncoder::default().encode([chunk], commit)
+}
+
+#[cfg(test)]
 fn silence_chunk_payload(commit: bool) -> String {
-    let silence = vec![0i16; 3200];
-    audio_chunk_payload(&silence, commit)
+    AudioPayloadEncoder::default().encode_silence(commit)
+}
+
+/// Collects `first` plus any chunks already waiting in `audio_rx`, up to
+/// `MAX_BATCH_SAMPLES`, so a backlog goes out in fewer, larger messages.
+fn drain_batch(first: Vec<i16>, audio_rx: &mut Receiver<Vec<i16>>) -> Vec<Vec<i16>> {
+    let mut total = first.len();
+    let mut batch = vec![first];
+    while total < MAX_BATCH_SAMPLES {
+        match audio_rx.try_recv() {
+            Ok(chunk) => {
+                total += chunk.len();
+                batch.push(chunk);
+            }
+            Err(_) => break,
+        }
+    }
+    batch
+}
+
+/// Groups queued chunks into batches of at most `MAX_BATCH_SAMPLES`
+/// (a single oversized chunk still forms its own batch).
+fn batch_queued(queued: &mut VecDeque<Vec<i16>>) -> Vec<Vec<Vec<i16>>> {
+    let mut batches: Vec<Vec<Vec<i16>>> = Vec::new();
+    let mut current_len = 0;
+    while let Some(chunk) = queued.pop_front() {
+        let starts_new = match batches.last() {
+            None => true,
+            Some(_) => current_len + chunk.len() > MAX_BATCH_SAMPLES,
+        };
+        if starts_new {
+            current_len = 0;
+            batches.push(Vec::new());
+        }
+        current_len += chunk.len();
+        if let Some(last) = batches.last_mut() {
+            last.push(chunk);
+        }
+    }
+    batches
 }
 
 impl ElevenLabsClient {
     pub fn new(api_key: String, model_id: String) -> Self {
-        Self { api_key, model_id }
+        Self {
+            api_key,
+            model_id,
+            vocabulary: Vec::new(),
+            diarize: false,
+            word_timestamps: false,
+            format: AudioFormat::default(),
+            proxy: None,
+        }
+    }
+
+    pub fn with_audio_format(mut self, format: AudioFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Terms sent as `keyterms` so the model favours them.
+    pub fn with_vocabulary(mut self, vocabulary: Vec<String>) -> Self {
+        self.vocabulary = vocabulary;
+        self
+    }
+
+    /// Asks for speaker ids on each word of timestamped commits.
+    pub fn with_diarization(mut self, diarize: bool) -> Self {
+        self.diarize = diarize;
+        self
+    }
+
+    /// Asks for word timings on commits even without diarization, so
+    /// pauses inside a commit can start paragraphs.
+    pub fn with_word_timestamps(mut self, word_timestamps: bool) -> Self {
+        self.word_timestamps = word_timestamps;
+        self
+    }
+
+    pub fn with_proxy(mut self, proxy: Option<ProxyConfig>) -> Self {
+        self.proxy = proxy;
+        self
+    }
+}
+
+/// The `audio_format` name, e.g. `pcm_16000` or `ulaw_8000`.
+fn audio_format_name(format: AudioFormat) -> String {
+    match format.encoding {
+        AudioEncoding::Pcm16 => format!("pcm_{}", format.sample_rate),
+        AudioEncoding::MuLaw => format!("ulaw_{}", format.sample_rate),
+    }
+}
+
+/// The realtime session URL; its query string carries the session settings,
+/// with one `keyterms` entry per vocabulary term. No `language_code` is sent,
+/// so the service detects the language.
+fn session_url(
+    model_id: &str,
+    vocabulary: &[String],
+    diarize: bool,
+    word_timestamps: bool,
+    format: AudioFormat,
+) -> Result<Url, url::ParseError> {
+    let format = audio_format_name(format);
+    let mut params = vec![
+        ("model_id", model_id),
+        ("audio_format", format.as_str()),
+        ("commit_strategy", "manual"),
+    ];
+    if diarize {
+        params.push(("diarize", "true"));
+    }
+    if diarize || word_timestamps {
+        params.push(("include_timestamps", "true"));
+    }
+    params.extend(vocabulary.iter().map(|term| ("keyterms", term.as_str())));
+    Url::parse_with_params(ELEVENLABS_WSS_URL, &params)
+}
+
+/// Fetches the account's model list and returns the realtime speech-to-text
+/// models, always including `KNOWN_REALTIME_MODELS`.
+pub async fn fetch_realtime_models(api_key: &str, proxy: Option<&ProxyConfig>) -> Result<Vec<String>, String> {
+    let http = crate::proxy::http_client(proxy, None)?;
+    let response = http
+        .get(ELEVENLABS_MODELS_URL)
+        .header("xi-api-key", api_key)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let status = response.status();
+    let value: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+    if !status.is_success() {
+        return Err(format!("{}: {}", status, value));
+    }
+    Ok(realtime_model_ids(&value))
+}
+
+/// Picks the realtime scribe models out of a `/v1/models` response.
+fn realtime_model_ids(value: &serde_json::Value) -> Vec<String> {
+    let mut ids: Vec<String> = KNOWN_REALTIME_MODELS.iter().map(|id| id.to_string()).collect();
+    let fetched = value
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|model| model.get("model_id")?.as_str())
+        .filter(|id| id.starts_with("scribe") && id.contains("realtime"));
+    for id in fetched {
+        if !ids.iter().any(|known| known == id) {
+            ids.push(id.to_string());
+        }
+    }
+    ids
+}
+
+/// Plan and quota reported for an ElevenLabs key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscriptionInfo {
+    pub tier: String,
+    pub character_count: u64,
+    pub character_limit: u64,
+}
+
+impl SubscriptionInfo {
+    fn from_json(value: &serde_json::Value) -> Option<Self> {
+        Some(Self {
+            tier: value.get("tier")?.as_str()?.to_string(),
+            character_count: value.get("character_count").and_then(|v| v.as_u64()).unwrap_or_default(),
+            character_limit: value.get("character_limit").and_then(|v| v.as_u64()).unwrap_or_default(),
+        })
+    }
+
+    pub fn remaining(&self) -> u64 {
+        self.character_limit.saturating_sub(self.character_count)
+    }
+
+    pub fn summary(&self) -> String {
+        tr!(
+            "Key OK · {} plan · {} of {} characters left",
+            self.tier,
+            self.remaining(),
+            self.character_limit
+        )
+    }
+
+    /// The warning to show when less than `threshold` characters are left;
+    /// a threshold of 0 disables the check.
+    pub fn low_quota_warning(&self, threshold: u64) -> Option<String> {
+        (threshold > 0 && self.remaining() < threshold).then(|| {
+            tr!(
+                "Only {} of {} ElevenLabs characters left this month",
+                self.remaining(),
+                self.character_limit
+            )
+        })
+    }
+}
+
+/// What happens when a session would start with less quota than
+/// `low_quota_threshold`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LowQuotaAction {
+    #[default]
+    Warn,
+    Refuse,
+}
+
+impl LowQuotaAction {
+    pub const ALL: [LowQuotaAction; 2] = [LowQuotaAction::Warn, LowQuotaAction::Refuse];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LowQuotaAction::Warn => "Warn only",
+            LowQuotaAction::Refuse => "Refuse to start",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| i18n::is_label(action.label(), label))
+    }
+}
+
+/// Checks `api_key` with a lightweight authenticated request and returns
+/// the account's plan and remaining quota. A rejected key is a `Config`
+/// error so the UI points at Settings rather than the connection.
+pub async fn validate_api_key(api_key: &str, proxy: Option<&ProxyConfig>) -> EchoResult<SubscriptionInfo> {
+    let response = crate::proxy::http_client(proxy, None)
+        .map_err(EchoError::Config)?
+        .get(ELEVENLABS_SUBSCRIPTION_URL)
+        .header("xi-api-key", api_key)
+        .send()
+        .await?;
+    let status = response.status();
+    let value: serde_json::Value = response.json().await.unwrap_or_default();
+    if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+        let detail = value
+            .pointer("/detail/message")
+            .and_then(|v| v.as_str())
+            .unwrap_or("The API key was rejected");
+        return Err(EchoError::config(detail));
+    }
+    if !status.is_success() {
+        return Err(EchoError::network(format!("{}: {}", status, value)));
+    }
+    SubscriptionInfo::from_json(&value)
+        .ok_or_else(|| EchoError::network(format!("Unexpected subscription response: {}", value)))
+}
+
+#[async_trait]
+impl SpeechProvider for ElevenLabsClient {
+    fn name(&self) -> &'static str {
+        "ElevenLabs"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderKind::ElevenLabs.capabilities()
     }
 
-    pub async fn run(
+    fn audio_format(&self) -> AudioFormat {
+        self.format
+    }
+
+    async fn run(
         &self,
         mut audio_rx: Receiver<Vec<i16>>,
         mut control_rx: UnboundedReceiver<ControlMessage>,
-        text_tx: tokio::sync::mpsc::Sender<TranscriptMessage>,
+        text_tx: tokio::sync::mpsc::Sender<TranscriptEvent>,
         log_tx: mpsc::UnboundedSender<String>,
-    ) -> Result<(), Box<dyn Error + Send + Sync>> {
-        let url = Url::parse_with_params(
-            ELEVENLABS_WSS_URL,
-            &[
-                ("model_id", self.model_id.as_str()),
-                ("language_code", "en"),
-                ("audio_format", "pcm_16000"),
-                ("commit_strategy", "manual"),
-            ],
-        )?;
+    ) -> EchoResult<()> {
+        let url = session_url(&self.model_id, &self.vocabulary, self.diarize, self.word_timestamps, self.format)?;
 
         macro_rules! emit {
             ($($arg:tt)*) => {{
                 let msg = format!($($arg)*);
-                println!("{}", msg);
+                info!("{}", msg);
                 let _ = log_tx.send(msg);
             }};
         }
 
         emit!("🔌 Connecting to ElevenLabs: {}", url);
 
-        let mut request = url.as_str().into_client_request()?;
-        request
-            .headers_mut()
-            .insert("xi-api-key", self.api_key.parse()?);
-
         emit!("➡️ [API OUT] WebSocket CONNECT {}", url);
-        let (ws_stream, response) = connect_async(request).await?;
+        let (ws_stream, response) = connect_websocket(&log_tx, self.proxy.as_ref(), || {
+            let mut request = url.as_str().into_client_request()?;
+            let key = self
+                .api_key
+                .parse()
+                .map_err(|_| ConnectError::InvalidRequest("API key contains invalid characters".into()))?;
+            request.headers_mut().insert("xi-api-key", key);
+            Ok(request)
+        })
+        .await?;
         emit!(
             "⬅️ [API IN] WebSocket CONNECT status={} headers={:?}",
             response.status(),
@@ -153,44 +762,61 @@ impl ElevenLabsClient {
         let (mut write, mut read) = ws_stream.split();
         let (evt_tx, mut evt_rx) = mpsc::unbounded_channel::<WsEvent>();
 
+        let watchdog = ActivityWatchdog::new(Instant::now());
+        let watchdog_read = watchdog.clone();
         let log_tx_read = log_tx.clone();
         let read_task = tokio::spawn(async move {
             macro_rules! emit_read {
                 ($($arg:tt)*) => {{
                     let msg = format!($($arg)*);
-                    println!("{}", msg);
+                    info!("{}", msg);
                     let _ = log_tx_read.send(msg);
                 }};
             }
             while let Some(msg) = read.next().await {
+                if msg.is_ok() {
+                    watchdog_read.touch(Instant::now());
+                }
                 match msg {
                     Ok(tokio_tungstenite::tungstenite::Message::Text(text)) => {
                         emit_read!("⬅️ [API IN] WS text: {}", text);
-                        match parse_incoming_message(&text) {
-                            ParsedIncoming::SessionStarted => {
-                                emit_read!("✅ [API IN] session_started");
+                        let Some(event) = parse_incoming_message(&text) else {
+                            continue;
+                        };
+                        match &event {
+                            TranscriptEvent::SessionStarted { session_id } => {
+                                emit_read!("✅ [API IN] session_started {}", session_id.as_deref().unwrap_or_default());
                                 let _ = evt_tx.send(WsEvent::SessionStarted);
                             }
-                            ParsedIncoming::PartialTranscript(content) => {
-                                if !content.is_empty() {
-                                    emit_read!("📝 [PARTIAL] {}", content);
-                                    let _ = text_tx.send(TranscriptMessage::Partial(content)).await;
+                            TranscriptEvent::Partial(segment) => {
+                                if segment.text.is_empty() {
+                                    continue;
                                 }
+                                emit_read!("📝 [PARTIAL] {}", segment.text);
                             }
-                            ParsedIncoming::CommittedTranscript(content) => {
-                                emit_read!("📝 [COMMITTED] {}", content);
-                                let _ = text_tx.send(TranscriptMessage::Committed(content)).await;
-                                let _ = evt_tx.send(WsEvent::CommittedTranscriptReceived);
+                            TranscriptEvent::Committed(segment) => {
+                                emit_read!("📝 [COMMITTED] {}", segment.text);
                             }
-                            ParsedIncoming::Error(err_json) => {
-                                emit_read!("❌ [API ERROR] {}", err_json);
-                                let _ = text_tx.send(TranscriptMessage::Error(err_json)).await;
+                            TranscriptEvent::Error(err) => {
+                                emit_read!("❌ [API ERROR] {}", err);
                             }
-                            ParsedIncoming::Other => {}
+                            TranscriptEvent::EndOfStream => {}
+                        }
+                        let committed = matches!(event, TranscriptEvent::Committed(_));
+                        let _ = text_tx.send(event).await;
+                        if committed {
+                            let _ = evt_tx.send(WsEvent::CommittedTranscriptReceived);
                         }
                     }
-                    Ok(tokio_tungstenite::tungstenite::Message::Close(_)) => {
-                        emit_read!("🔌 WebSocket Closed");
+                    Ok(tokio_tungstenite::tungstenite::Message::Close(frame)) => {
+                        emit_read!("🔌 WebSocket Closed {:?}", frame);
+                        let event = match frame {
+                            Some(frame) if frame.code != CloseCode::Normal => TranscriptEvent::Error(
+                                ProviderError::new("connection_closed", format!("{} {}", u16::from(frame.code), frame.reason)),
+                            ),
+                            _ => TranscriptEvent::EndOfStream,
+                        };
+                        let _ = text_tx.send(event).await;
                         break;
                     }
                     Ok(tokio_tungstenite::tungstenite::Message::Ping(payload)) => {
@@ -204,6 +830,9 @@ impl ElevenLabsClient {
                     }
                     Err(e) => {
                         emit_read!("❌ WebSocket Error: {}", e);
+                        let _ = text_tx
+                            .send(TranscriptEvent::Error(ProviderError::new("websocket", e.to_string())))
+                            .await;
                         break;
                     }
                     _ => {}
@@ -215,18 +844,42 @@ impl ElevenLabsClient {
         let mut accepting_audio = false;
         let mut awaiting_final_commit = false;
         let mut queued_audio: VecDeque<Vec<i16>> = VecDeque::new();
+        let mut encoder = AudioPayloadEncoder {
+            format: self.format,
+            ..Default::default()
+        };
+        let mut keepalive = tokio::time::interval_at(
+            tokio::time::Instant::now() + KEEPALIVE_INTERVAL,
+            KEEPALIVE_INTERVAL,
+        );
+        let mut stalled = false;
+        let mut cancelled = false;
         loop {
             tokio::select! {
+                _ = keepalive.tick() => {
+                    let idle = watchdog.idle_for(Instant::now());
+                    if idle >= STALL_TIMEOUT {
+                        emit!("❌ No data from ElevenLabs for {}s, treating connection as stalled", idle.as_secs());
+                        stalled = true;
+                        break;
+                    }
+                    if let Err(e) = write.send(tokio_tungstenite::tungstenite::Message::Ping(Vec::new())).await {
+                        emit!("❌ Failed to send keepalive ping: {}", e);
+                        stalled = true;
+                        break;
+                    }
+                }
                 Some(evt) = evt_rx.recv() => {
                     match evt {
                         WsEvent::SessionStarted => {
                             session_ready = true;
                             emit!("➡️ Session ready, flushing {} queued chunks", queued_audio.len());
-                            while let Some(chunk) = queued_audio.pop_front() {
-                                let payload = audio_chunk_payload(&chunk, false);
+                            for batch in batch_queued(&mut queued_audio) {
+                                let payload = encoder.encode(batch.iter().map(Vec::as_slice), false);
                                 emit!(
-                                    "➡️ [API OUT] WS audio chunk: samples={} payload_bytes={}",
-                                    chunk.len(),
+                                    "➡️ [API OUT] WS audio chunk: chunks={} samples={} payload_bytes={}",
+                                    batch.len(),
+                                    batch.iter().map(Vec::len).sum::<usize>(),
                                     payload.len()
                                 );
                                 if let Err(e) = write.send(tokio_tungstenite::tungstenite::Message::Text(payload)).await {
@@ -255,27 +908,33 @@ impl ElevenLabsClient {
                             awaiting_final_commit = true;
                             emit!("➡️ [API OUT] Manual commit requested");
 
-                            let pre_commit_1 = silence_chunk_payload(false);
+                            let pre_commit_1 = encoder.encode_silence(false);
                             emit!("➡️ [API OUT] WS silence chunk 1/2 (pre-commit)");
                             if let Err(e) = write.send(tokio_tungstenite::tungstenite::Message::Text(pre_commit_1)).await {
                                 emit!("❌ Failed to send pre-commit silence chunk 1: {}", e);
                                 break;
                             }
 
-                            let pre_commit_2 = silence_chunk_payload(false);
+                            let pre_commit_2 = encoder.encode_silence(false);
                             emit!("➡️ [API OUT] WS silence chunk 2/2 (pre-commit)");
                             if let Err(e) = write.send(tokio_tungstenite::tungstenite::Message::Text(pre_commit_2)).await {
                                 emit!("❌ Failed to send pre-commit silence chunk 2: {}", e);
                                 break;
                             }
 
-                            let commit_payload = silence_chunk_payload(true);
+                            let commit_payload = encoder.encode_silence(true);
                             emit!("➡️ [API OUT] WS commit chunk");
                             if let Err(e) = write.send(tokio_tungstenite::tungstenite::Message::Text(commit_payload)).await {
                                 emit!("❌ Failed to send commit chunk: {}", e);
                                 break;
                             }
                         }
+                        ControlMessage::Cancel => {
+                            emit!("➡️ Session cancelled, closing WebSocket");
+                            let _ = write.send(tokio_tungstenite::tungstenite::Message::Close(None)).await;
+                            cancelled = true;
+                            break;
+                        }
                     }
                 }
                 maybe_chunk = audio_rx.recv() => {
@@ -287,10 +946,12 @@ impl ElevenLabsClient {
                             if !session_ready {
                                 queued_audio.push_back(chunk);
                             } else {
-                                let payload = audio_chunk_payload(&chunk, false);
+                                let batch = drain_batch(chunk, &mut audio_rx);
+                                let payload = encoder.encode(batch.iter().map(Vec::as_slice), false);
                                 emit!(
-                                    "➡️ [API OUT] WS audio chunk: samples={} payload_bytes={}",
-                                    chunk.len(),
+                                    "➡️ [API OUT] WS audio chunk: chunks={} samples={} payload_bytes={}",
+                                    batch.len(),
+                                    batch.iter().map(Vec::len).sum::<usize>(),
                                     payload.len()
                                 );
                                 if let Err(e) = write.send(tokio_tungstenite::tungstenite::Message::Text(payload)).await {
@@ -301,21 +962,21 @@ impl ElevenLabsClient {
                         }
                         None => {
                             emit!("➡️ [API OUT] Audio stream ended, forcing manual commit");
-                            let pre_commit_1 = silence_chunk_payload(false);
+                            let pre_commit_1 = encoder.encode_silence(false);
                             emit!("➡️ [API OUT] WS silence chunk 1/2 (pre-commit)");
                             if let Err(e) = write.send(tokio_tungstenite::tungstenite::Message::Text(pre_commit_1)).await {
                                 emit!("❌ Failed to send pre-commit silence chunk 1 after audio close: {}", e);
                                 break;
                             }
 
-                            let pre_commit_2 = silence_chunk_payload(false);
+                            let pre_commit_2 = encoder.encode_silence(false);
                             emit!("➡️ [API OUT] WS silence chunk 2/2 (pre-commit)");
                             if let Err(e) = write.send(tokio_tungstenite::tungstenite::Message::Text(pre_commit_2)).await {
                                 emit!("❌ Failed to send pre-commit silence chunk 2 after audio close: {}", e);
                                 break;
                             }
 
-                            let commit_payload = silence_chunk_payload(true);
+                            let commit_payload = encoder.encode_silence(true);
                             emit!("➡️ [API OUT] WS commit chunk");
                             if let Err(e) = write.send(tokio_tungstenite::tungstenite::Message::Text(commit_payload)).await {
                                 emit!("❌ Failed to send commit chunk after audio close: {}", e);
@@ -329,6 +990,18 @@ impl ElevenLabsClient {
         }
 
         // Cleanup
+        if stalled {
+            read_task.abort();
+            return Err(ConnectError::Network(format!(
+                "connection stalled (no response for {}s)",
+                STALL_TIMEOUT.as_secs()
+            ))
+            .into());
+        }
+        if cancelled {
+            read_task.abort();
+            return Ok(());
+        }
         let _ = read_task.await;
         Ok(())
     }
@@ -336,12 +1009,104 @@ impl ElevenLabsClient {
 
 #[cfg(test)]
 mod tests {
-    use super::{audio_chunk_payload, parse_incoming_message, silence_chunk_payload, ParsedIncoming};
+    use super::{
+        audio_chunk_payload, batch_queued, ActivityWatchdog, drain_batch, parse_incoming_message, silence_chunk_payload,
+        AudioPayloadEncoder, ProviderError, TranscriptEvent, TranscriptSegment, MAX_BATCH_SAMPLES,
+        realtime_model_ids, session_url, LowQuotaAction, SubscriptionInfo, WordTiming,
+    };
+    use crate::audio::AudioFormat;
+    use crate::meeting::Speaker;
+    use base64::{engine::general_purpose, Engine as _};
+    use std::collections::VecDeque;
+    use std::time::{Duration, Instant};
+    use tokio::sync::mpsc;
+
+    #[test]
+    fn session_url_adds_one_keyterm_per_vocabulary_term() {
+        let url = session_url(
+            "scribe_v2_realtime",
+            &["Kubernetes".to_string(), "11th Echo".to_string()],
+            false,
+            false,
+            AudioFormat::PCM_16K,
+        )
+        .unwrap();
+        let keyterms: Vec<String> = url
+            .query_pairs()
+            .filter(|(key, _)| key == "keyterms")
+            .map(|(_, value)| value.into_owned())
+            .collect();
+        assert_eq!(keyterms, vec!["Kubernetes", "11th Echo"]);
+        let plain = session_url("scribe_v2_realtime", &[], false, false, AudioFormat::PCM_16K).unwrap();
+        assert!(plain.query_pairs().any(|(key, value)| key == "audio_format" && value == "pcm_16000"));
+        assert!(!plain.query().unwrap().contains("keyterms"));
+        assert!(!plain.query().unwrap().contains("diarize"));
+        assert!(!plain.query().unwrap().contains("include_timestamps"));
+        assert!(!plain.query().unwrap().contains("language_code"));
+        let diarized = session_url("scribe_v2_realtime", &[], true, false, AudioFormat::MU_LAW_8K).unwrap();
+        assert!(diarized.query_pairs().any(|(key, value)| key == "diarize" && value == "true"));
+        assert!(diarized.query_pairs().any(|(key, value)| key == "include_timestamps" && value == "true"));
+        let timed = session_url("scribe_v2_realtime", &[], false, true, AudioFormat::PCM_16K).unwrap();
+        assert!(timed.query_pairs().any(|(key, value)| key == "include_timestamps" && value == "true"));
+        assert!(!timed.query().unwrap().contains("diarize"));
+        assert!(diarized.query_pairs().any(|(key, value)| key == "audio_format" && value == "ulaw_8000"));
+    }
+
+    #[test]
+    fn segments_split_where_the_words_pause() {
+        let word = |text: &str, start_secs: f64, end_secs: f64| WordTiming {
+            text: text.to_string(),
+            start_secs,
+            end_secs,
+            confidence: None,
+            speaker: None,
+        };
+        let segment = TranscriptSegment {
+            channel: Some(Speaker::Me),
+            ..TranscriptSegment::from_words(
+                "first point. second point",
+                vec![word("first", 0.0, 0.4), word("point.", 0.5, 0.9), word("second", 4.0, 4.4), word("point", 4.5, 5.0)],
+            )
+        };
+        assert_eq!(segment.clone().split_at_pauses(0.0), vec![segment.clone()]);
+        assert_eq!(segment.clone().split_at_pauses(5.0), vec![segment.clone()]);
+
+        let parts = segment.split_at_pauses(2.0);
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].text, "first point.");
+        assert_eq!((parts[0].start_secs, parts[0].end_secs), (Some(0.0), Some(0.9)));
+        assert_eq!(parts[1].text, "second point");
+        assert_eq!((parts[1].start_secs, parts[1].end_secs), (Some(4.0), Some(5.0)));
+        assert!(parts.iter().all(|part| part.channel == Some(Speaker::Me)));
+        assert_eq!(TranscriptSegment::text("untimed").split_at_pauses(2.0).len(), 1);
+    }
+
+    #[test]
+    fn diarized_commit_renders_speaker_turns() {
+        let msg = r#"{"message_type":"committed_transcript_with_timestamps","text":"hi there. hello","words":[
+            {"text":"hi","start":0.1,"end":0.3,"type":"word","speaker_id":"speaker_0"},
+            {"text":"there.","start":0.3,"end":0.6,"type":"word","speaker_id":"speaker_0"},
+            {"text":"hello","start":0.9,"end":1.2,"type":"word","speaker_id":"speaker_1"}
+        ]}"#;
+        let Some(TranscriptEvent::Committed(segment)) = parse_incoming_message(msg) else {
+            panic!("expected committed event");
+        };
+        assert_eq!(segment.words[2].speaker.as_deref(), Some("speaker_1"));
+        assert_eq!(
+            segment.speaker_labelled_text().as_deref(),
+            Some("Speaker 1: hi there. Speaker 2: hello")
+        );
+        assert_eq!(TranscriptSegment::text("plain").speaker_labelled_text(), None);
+        assert_eq!(super::speaker_label("A"), "Speaker A");
+    }
 
     #[test]
     fn parse_session_started_event() {
         let msg = r#"{"message_type":"session_started","session_id":"abc"}"#;
-        assert_eq!(parse_incoming_message(msg), ParsedIncoming::SessionStarted);
+        assert_eq!(
+            parse_incoming_message(msg),
+            Some(TranscriptEvent::SessionStarted { session_id: Some("abc".to_string()) })
+        );
     }
 
     #[test]
@@ -349,40 +1114,86 @@ mod tests {
         let msg = r#"{"message_type":"committed_transcript","text":"hello world"}"#;
         assert_eq!(
             parse_incoming_message(msg),
-            ParsedIncoming::CommittedTranscript("hello world".to_string())
+            Some(TranscriptEvent::Committed(TranscriptSegment::text("hello world")))
         );
     }
 
+    #[test]
+    fn parse_timestamped_commit_keeps_words_and_confidence() {
+        let msg = r#"{"message_type":"committed_transcript_with_timestamps","text":"hi there","words":[
+            {"text":"hi","start":0.5,"end":0.8,"type":"word","logprob":0.0},
+            {"text":" ","start":0.8,"end":0.9,"type":"spacing"},
+            {"text":"there","start":0.9,"end":1.4,"type":"word","logprob":-0.6931472}
+        ]}"#;
+        let Some(TranscriptEvent::Committed(segment)) = parse_incoming_message(msg) else {
+            panic!("expected committed event");
+        };
+        assert_eq!(segment.text, "hi there");
+        assert_eq!(segment.words.len(), 2);
+        assert_eq!(segment.start_secs, Some(0.5));
+        assert_eq!(segment.end_secs, Some(1.4));
+        assert!((segment.confidence.unwrap() - 0.75).abs() < 1e-4);
+        assert!(segment.is_low_confidence(0.8));
+        assert!(!segment.is_low_confidence(0.0));
+    }
+
+    #[test]
+    fn joined_segments_keep_span_and_lowest_confidence() {
+        let mut first = TranscriptSegment::text("Hello.");
+        first.start_secs = Some(1.0);
+        first.end_secs = Some(2.0);
+        first.confidence = Some(0.9);
+        let mut second = TranscriptSegment::text("World.");
+        second.end_secs = Some(3.5);
+        second.confidence = Some(0.4);
+        let joined = TranscriptSegment::join(vec![first, second, TranscriptSegment::text("")]);
+        assert_eq!(joined.text, "Hello. World.");
+        assert_eq!(joined.start_secs, Some(1.0));
+        assert_eq!(joined.end_secs, Some(3.5));
+        assert_eq!(joined.confidence, Some(0.4));
+    }
+
     #[test]
     fn parse_error_event() {
         let msg = r#"{"message_type":"input_error","error":"bad format"}"#;
+        assert_eq!(
+            parse_incoming_message(msg),
+            Some(TranscriptEvent::Error(ProviderError::new("input_error", "bad format")))
+        );
+    }
+
+    #[test]
+    fn parse_previously_unhandled_error_types() {
+        let msg = r#"{"message_type":"rate_limited","message":"slow down"}"#;
+        assert_eq!(
+            parse_incoming_message(msg),
+            Some(TranscriptEvent::Error(ProviderError::new("rate_limited", "slow down")))
+        );
+        let msg = r#"{"message_type":"session_time_limit_exceeded"}"#;
         match parse_incoming_message(msg) {
-            ParsedIncoming::Error(payload) => assert!(payload.contains("input_error")),
-            _ => panic!("expected ParsedIncoming::Error"),
+            Some(TranscriptEvent::Error(err)) => assert_eq!(err.kind, "session_time_limit_exceeded"),
+            other => panic!("expected error event, got {:?}", other),
         }
     }
 
     #[test]
     fn parse_partial_transcript_event() {
         let msg = r#"{"message_type":"partial_transcript","text":"hello"}"#;
-        assert_eq!(
-            parse_incoming_message(msg),
-            ParsedIncoming::PartialTranscript("hello".to_string())
-        );
+        assert_eq!(parse_incoming_message(msg), Some(TranscriptEvent::Partial(TranscriptSegment::text("hello"))));
     }
 
     #[test]
-    fn parse_unknown_event_as_other() {
+    fn parse_unknown_event_as_none() {
         let msg = r#"{"message_type":"something_else","x":1}"#;
-        assert_eq!(parse_incoming_message(msg), ParsedIncoming::Other);
+        assert_eq!(parse_incoming_message(msg), None);
     }
 
     #[test]
     fn parse_invalid_json_as_error() {
         let msg = "{this is not json";
         match parse_incoming_message(msg) {
-            ParsedIncoming::Error(payload) => assert!(payload.contains("Invalid JSON")),
-            _ => panic!("expected ParsedIncoming::Error"),
+            Some(TranscriptEvent::Error(err)) => assert!(err.message.contains("Invalid JSON")),
+            other => panic!("expected error event, got {:?}", other),
         }
     }
 
@@ -411,4 +1222,113 @@ mod tests {
         assert_eq!(v["sample_rate"], 16000);
         assert_eq!(v["commit"], true);
     }
+
+    #[test]
+    fn encoder_concatenates_chunks_and_reuses_buffers() {
+        let mut encoder = AudioPayloadEncoder::default();
+        let payload = encoder.encode([&[1i16, 2][..], &[3i16][..]], false);
+        let v: serde_json::Value = serde_json::from_str(&payload).unwrap();
+        let bytes = general_purpose::STANDARD
+            .decode(v["audio_base_64"].as_str().unwrap())
+            .unwrap();
+        assert_eq!(bytes, vec![1, 0, 2, 0, 3, 0]);
+
+        let capacity = encoder.bytes.capacity();
+        let _ = encoder.encode([&[4i16][..]], false);
+        assert_eq!(encoder.bytes.capacity(), capacity);
+    }
+
+    #[test]
+    fn encoder_follows_the_negotiated_format() {
+        let mut encoder = AudioPayloadEncoder {
+            format: AudioFormat::MU_LAW_8K,
+            ..Default::default()
+        };
+        let v: serde_json::Value = serde_json::from_str(&encoder.encode_silence(true)).unwrap();
+        assert_eq!(v["sample_rate"], 8000);
+        let bytes = general_purpose::STANDARD
+            .decode(v["audio_base_64"].as_str().unwrap())
+            .unwrap();
+        assert_eq!(bytes.len(), 1600);
+        assert!(bytes.iter().all(|&b| b == 0xFF));
+    }
+
+    #[test]
+    fn batch_queued_respects_sample_limit() {
+        let mut queued: VecDeque<Vec<i16>> = VecDeque::new();
+        for _ in 0..5 {
+            queued.push_back(vec![0; MAX_BATCH_SAMPLES / 2]);
+        }
+        let batches = batch_queued(&mut queued);
+        assert_eq!(batches.iter().map(Vec::len).collect::<Vec<_>>(), vec![2, 2, 1]);
+        assert!(queued.is_empty());
+    }
+
+    #[test]
+    fn drain_batch_takes_waiting_chunks() {
+        let (tx, mut rx) = mpsc::channel::<Vec<i16>>(8);
+        tx.try_send(vec![2; 10]).unwrap();
+        tx.try_send(vec![3; 10]).unwrap();
+        let batch = drain_batch(vec![1; 10], &mut rx);
+        assert_eq!(batch.len(), 3);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn watchdog_measures_idle_time_since_last_frame() {
+        let start = Instant::now();
+        let watchdog = ActivityWatchdog::new(start);
+        let reader = watchdog.clone();
+        assert_eq!(watchdog.idle_for(start + Duration::from_secs(5)), Duration::from_secs(5));
+
+        reader.touch(start + Duration::from_secs(20));
+        // An out-of-order older touch never moves activity backwards.
+        reader.touch(start + Duration::from_secs(10));
+        assert_eq!(watchdog.idle_for(start + Duration::from_secs(50)), Duration::from_secs(30));
+        assert_eq!(watchdog.idle_for(start + Duration::from_secs(15)), Duration::ZERO);
+    }
+
+    #[test]
+    fn model_list_keeps_realtime_scribe_models() {
+        let value = serde_json::json!([
+            { "model_id": "eleven_multilingual_v2" },
+            { "model_id": "scribe_v1" },
+            { "model_id": "scribe_v2_realtime" },
+            { "model_id": "scribe_v3_realtime" }
+        ]);
+        assert_eq!(realtime_model_ids(&value), vec!["scribe_v2_realtime", "scribe_v3_realtime"]);
+        assert_eq!(realtime_model_ids(&serde_json::json!({ "detail": "unauthorized" })), vec!["scribe_v2_realtime"]);
+    }
+
+    #[test]
+    fn subscription_reports_tier_and_remaining_characters() {
+        let value = serde_json::json!({
+            "tier": "creator",
+            "character_count": 12_500,
+            "character_limit": 100_000,
+            "status": "active"
+        });
+        let info = SubscriptionInfo::from_json(&value).unwrap();
+        assert_eq!(info.tier, "creator");
+        assert_eq!(info.summary(), "Key OK · creator plan · 87500 of 100000 characters left");
+        assert_eq!(SubscriptionInfo::from_json(&serde_json::json!({ "detail": "nope" })), None);
+    }
+
+    #[test]
+    fn low_quota_warning_respects_threshold() {
+        let info = SubscriptionInfo {
+            tier: "free".to_string(),
+            character_count: 9_000,
+            character_limit: 10_000,
+        };
+        assert_eq!(info.low_quota_warning(0), None);
+        assert_eq!(info.low_quota_warning(1_000), None);
+        assert_eq!(
+            info.low_quota_warning(2_000).as_deref(),
+            Some("Only 1000 of 10000 ElevenLabs characters left this month")
+        );
+        for action in LowQuotaAction::ALL {
+            assert_eq!(LowQuotaAction::from_label(action.label()), Some(action));
+        }
+    }
 }
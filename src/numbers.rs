@@ -0,0 +1,596 @@
+// Spoken-number formatting for English transcripts: number words become
+// digits ("twenty three" -> "23"), and percentages, currency amounts and dates
+// are written the way the chosen locale writes them. Numbers below ten stay
+// spelled out unless they are part of an amount or a date.
+
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// Languages that write "3,5" and "1.000" rather than "3.5" and "1,000".
+const DECIMAL_COMMA_LANGUAGES: [&str; 16] = [
+    "de", "fr", "es", "it", "nl", "pt", "ru", "pl", "sv", "da", "nb", "no", "fi", "cs", "tr", "id",
+];
+
+const UNITS: [&str; 20] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten", "eleven", "twelve",
+    "thirteen", "fourteen", "fifteen", "sixteen", "seventeen", "eighteen", "nineteen",
+];
+const TENS: [&str; 8] = ["twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety"];
+const ORDINAL_UNITS: [&str; 20] = [
+    "zeroth", "first", "second", "third", "fourth", "fifth", "sixth", "seventh", "eighth", "ninth", "tenth",
+    "eleventh", "twelfth", "thirteenth", "fourteenth", "fifteenth", "sixteenth", "seventeenth", "eighteenth",
+    "nineteenth",
+];
+const ORDINAL_TENS: [&str; 8] = [
+    "twentieth", "thirtieth", "fortieth", "fiftieth", "sixtieth", "seventieth", "eightieth", "ninetieth",
+];
+const SCALES: [(&str, u64); 3] = [("thousand", 1_000), ("million", 1_000_000), ("billion", 1_000_000_000)];
+const MONTHS: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June", "July", "August", "September", "October", "November",
+    "December",
+];
+const CURRENCIES: [(&str, &str); 8] = [
+    ("dollar", "$"),
+    ("dollars", "$"),
+    ("euro", "€"),
+    ("euros", "€"),
+    ("pound", "£"),
+    ("pounds", "£"),
+    ("yen", "¥"),
+    ("bucks", "$"),
+];
+/// Words a number phrase may span, so matching stays cheap on long text.
+const MAX_ATOMS: usize = 24;
+
+/// How numbers, amounts and dates are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberLocale {
+    decimal_comma: bool,
+    /// "March 3, 2024" rather than "3 March 2024".
+    month_first: bool,
+}
+
+impl NumberLocale {
+    /// Reads a BCP-47 tag such as `en-GB` or `de-DE`; empty means US English.
+    pub fn from_tag(tag: &str) -> Self {
+        let tag = tag.trim().replace('_', "-").to_ascii_lowercase();
+        let language = tag.split('-').next().unwrap_or_default();
+        Self {
+            decimal_comma: DECIMAL_COMMA_LANGUAGES.contains(&language),
+            month_first: matches!(tag.as_str(), "" | "en" | "en-us"),
+        }
+    }
+
+    fn decimal_separator(self) -> char {
+        if self.decimal_comma {
+            ','
+        } else {
+            '.'
+        }
+    }
+
+    /// Digits with thousands grouped from 10,000 up, so years stay plain.
+    fn integer(self, value: u64) -> String {
+        let digits = value.to_string();
+        if value < 10_000 {
+            return digits;
+        }
+        let separator = if self.decimal_comma { '.' } else { ',' };
+        let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+        for (index, digit) in digits.chars().enumerate() {
+            if index > 0 && (digits.len() - index).is_multiple_of(3) {
+                grouped.push(separator);
+            }
+            grouped.push(digit);
+        }
+        grouped
+    }
+
+    fn percent(self, amount: &str) -> String {
+        if self.decimal_comma {
+            format!("{} %", amount)
+        } else {
+            format!("{}%", amount)
+        }
+    }
+
+    fn currency(self, symbol: &str, amount: &str) -> String {
+        if self.decimal_comma {
+            format!("{} {}", amount, symbol)
+        } else {
+            format!("{}{}", symbol, amount)
+        }
+    }
+
+    fn date(self, month: usize, day: u64, year: Option<u64>) -> String {
+        let month = MONTHS[month];
+        match (self.month_first, year) {
+            (true, Some(year)) => format!("{} {}, {}", month, day, year),
+            (true, None) => format!("{} {}", month, day),
+            (false, Some(year)) => format!("{} {} {}", day, month, year),
+            (false, None) => format!("{} {}", day, month),
+        }
+    }
+}
+
+/// Rewrites the number phrases in `text` for `locale`; everything else is
+/// left as it was.
+pub fn format_numbers(text: &str, locale: NumberLocale) -> String {
+    static PIECE_PATTERN: OnceLock<Regex> = OnceLock::new();
+    let piece_pattern = PIECE_PATTERN.get_or_init(|| {
+        Regex::new(r"[0-9]+(?:[.,][0-9]+)*(?:st|nd|rd|th)?|\p{L}+(?:['-]\p{L}+)*|\s+|.").expect("piece pattern")
+    });
+    let pieces: Vec<&str> = piece_pattern.find_iter(text).map(|m| m.as_str()).collect();
+    let mut output = String::with_capacity(text.len());
+    let mut index = 0;
+    while index < pieces.len() {
+        let atoms = atoms_from(&pieces, index);
+        let rewrite = rewrite(&atoms, locale).filter(|(_, len)| atoms[len - 1].ends_piece);
+        match rewrite {
+            Some((replacement, len)) => {
+                output.push_str(&replacement);
+                index = atoms[len - 1].piece + 1;
+            }
+            None => {
+                output.push_str(pieces[index]);
+                index += 1;
+            }
+        }
+    }
+    output
+}
+
+/// One word of a candidate phrase; hyphenated words ("twenty-three") give
+/// one atom per part.
+#[derive(Debug)]
+struct Atom {
+    text: String,
+    piece: usize,
+    ends_piece: bool,
+    digits: bool,
+    /// Whether the word was written with a capital letter.
+    capitalized: bool,
+}
+
+/// The words from `pieces[start]` on, up to the first punctuation.
+fn atoms_from(pieces: &[&str], start: usize) -> Vec<Atom> {
+    let mut atoms = Vec::new();
+    for (piece, text) in pieces.iter().enumerate().skip(start) {
+        if text.chars().all(char::is_whitespace) && piece > start {
+            continue;
+        }
+        let first = text.chars().next().unwrap_or_default();
+        if first.is_ascii_digit() {
+            atoms.push(Atom {
+                text: text.to_string(),
+                piece,
+                ends_piece: true,
+                digits: true,
+                capitalized: false,
+            });
+        } else if first.is_alphabetic() {
+            let parts: Vec<&str> = text.split('-').collect();
+            for (index, part) in parts.iter().enumerate() {
+                atoms.push(Atom {
+                    text: part.to_lowercase(),
+                    piece,
+                    ends_piece: index + 1 == parts.len(),
+                    digits: false,
+                    capitalized: part.starts_with(char::is_uppercase),
+                });
+            }
+        } else {
+            break;
+        }
+        if atoms.len() >= MAX_ATOMS {
+            break;
+        }
+    }
+    atoms
+}
+
+/// The replacement for the phrase at the start of `atoms` and how many atoms
+/// it covers.
+fn rewrite(atoms: &[Atom], locale: NumberLocale) -> Option<(String, usize)> {
+    if atoms.is_empty() {
+        return None;
+    }
+    if let Some(date) = date_at(atoms, locale) {
+        return Some(date);
+    }
+    let number = number_at(atoms, locale)?;
+    let rest = &atoms[number.len..];
+    let word = |index: usize| rest.get(index).map(|atom| atom.text.as_str());
+    if !number.ordinal {
+        match (word(0), word(1)) {
+            (Some("percent"), _) => return Some((locale.percent(&number.text), number.len + 1)),
+            (Some("per"), Some("cent")) => return Some((locale.percent(&number.text), number.len + 2)),
+            _ => {}
+        }
+        if let Some(symbol) = word(0).and_then(currency_symbol) {
+            let mut amount = number.text.clone();
+            let mut len = number.len + 1;
+            if word(1) == Some("and") && !number.decimal {
+                let cents = rest
+                    .get(2..)
+                    .and_then(|tail| number_at(tail, locale))
+                    .filter(|cents| !cents.ordinal && cents.value.is_some_and(|v| v < 100));
+                if let Some(cents) = cents {
+                    if matches!(word(2 + cents.len), Some("cent" | "cents" | "pence")) {
+                        amount = format!(
+                            "{}{}{:02}",
+                            amount,
+                            locale.decimal_separator(),
+                            cents.value.unwrap_or_default()
+                        );
+                        len += 1 + cents.len + 1;
+                    }
+                }
+            }
+            return Some((locale.currency(symbol, &amount), len));
+        }
+    }
+    if number.digits || number.minor() {
+        return None;
+    }
+    Some((number.text, number.len))
+}
+
+/// A parsed number phrase and its digits for `locale`.
+#[derive(Debug)]
+struct Number {
+    text: String,
+    value: Option<u64>,
+    len: usize,
+    ordinal: bool,
+    decimal: bool,
+    /// Already written in digits.
+    digits: bool,
+}
+
+impl Number {
+    /// A lone spoken number below ten, which reads better as a word.
+    fn minor(&self) -> bool {
+        !self.digits && !self.decimal && self.len == 1 && self.value.is_some_and(|v| v < 10)
+    }
+}
+
+fn number_at(atoms: &[Atom], locale: NumberLocale) -> Option<Number> {
+    let first = atoms.first()?;
+    if first.digits {
+        let leading: String = first.text.chars().take_while(char::is_ascii_digit).collect();
+        return Some(Number {
+            value: (leading.len() == first.text.len() || first.text.ends_with(char::is_alphabetic))
+                .then(|| leading.parse().ok())
+                .flatten(),
+            ordinal: first.text.ends_with(char::is_alphabetic),
+            decimal: first.text.len() > leading.len() && !first.text.ends_with(char::is_alphabetic),
+            text: first.text.clone(),
+            len: 1,
+            digits: true,
+        });
+    }
+    if let Some((year, len)) = spoken_year(atoms) {
+        return Some(Number {
+            text: year.to_string(),
+            value: Some(year),
+            len,
+            ordinal: false,
+            decimal: false,
+            digits: false,
+        });
+    }
+    let spoken = spoken_integer(atoms)?;
+    if spoken.ordinal {
+        return Some(Number {
+            text: format!("{}{}", spoken.value, ordinal_suffix(spoken.value)),
+            value: Some(spoken.value),
+            len: spoken.len,
+            ordinal: true,
+            decimal: false,
+            digits: false,
+        });
+    }
+    let fraction: String = match atoms.get(spoken.len) {
+        Some(atom) if atom.text == "point" => atoms[spoken.len + 1..]
+            .iter()
+            .map_while(|atom| digit_word(&atom.text))
+            .collect(),
+        _ => String::new(),
+    };
+    let mut text = locale.integer(spoken.value);
+    let mut len = spoken.len;
+    if !fraction.is_empty() {
+        text = format!("{}{}{}", text, locale.decimal_separator(), fraction);
+        len += 1 + fraction.len();
+    }
+    Some(Number {
+        text,
+        value: Some(spoken.value),
+        len,
+        ordinal: false,
+        decimal: !fraction.is_empty(),
+        digits: false,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Unit,
+    Teen,
+    Tens,
+    Hundred,
+    Scale,
+}
+
+#[derive(Debug)]
+struct Spoken {
+    value: u64,
+    len: usize,
+    ordinal: bool,
+}
+
+fn cardinal_word(word: &str) -> Option<(u64, Kind)> {
+    if let Some(value) = UNITS.iter().position(|w| *w == word) {
+        let kind = if value < 10 { Kind::Unit } else { Kind::Teen };
+        return Some((value as u64, kind));
+    }
+    TENS.iter()
+        .position(|w| *w == word)
+        .map(|index| ((index as u64 + 2) * 10, Kind::Tens))
+}
+
+fn ordinal_word(word: &str) -> Option<(u64, Kind)> {
+    if let Some(value) = ORDINAL_UNITS.iter().position(|w| *w == word) {
+        let kind = if value < 10 { Kind::Unit } else { Kind::Teen };
+        return Some((value as u64, kind));
+    }
+    ORDINAL_TENS
+        .iter()
+        .position(|w| *w == word)
+        .map(|index| ((index as u64 + 2) * 10, Kind::Tens))
+}
+
+fn digit_word(word: &str) -> Option<char> {
+    let value = match word {
+        "oh" => 0,
+        _ => UNITS[..10].iter().position(|w| *w == word)?,
+    };
+    char::from_digit(value as u32, 10)
+}
+
+/// Whether a unit, teen or tens word may come after `last`.
+fn follows(last: Option<Kind>, next: Kind, value: u64) -> bool {
+    match (last, next) {
+        (None, _) => true,
+        (Some(Kind::Tens), Kind::Unit) => value > 0,
+        (Some(Kind::Hundred | Kind::Scale), _) => true,
+        _ => false,
+    }
+}
+
+/// "three hundred and twelve thousand five", "twenty-first"; the longest
+/// well-formed run from the first atom.
+fn spoken_integer(atoms: &[Atom]) -> Option<Spoken> {
+    let (mut total, mut group) = (0u64, 0u64);
+    let mut last: Option<Kind> = None;
+    let mut last_scale = u64::MAX;
+    let mut parsed = None;
+    for (index, atom) in atoms.iter().enumerate() {
+        let word = atom.text.as_str();
+        if word == "and" && matches!(last, Some(Kind::Hundred | Kind::Scale)) {
+            continue;
+        }
+        if let Some((value, kind)) = ordinal_word(word) {
+            if follows(last, kind, value) {
+                parsed = Some(Spoken {
+                    value: total + group + value,
+                    len: index + 1,
+                    ordinal: true,
+                });
+            }
+            break;
+        }
+        let scale = SCALES.iter().find(|(name, _)| *name == word).map(|(_, scale)| *scale);
+        match cardinal_word(word) {
+            Some((value, kind)) if follows(last, kind, value) => {
+                group += value;
+                last = Some(kind);
+            }
+            Some(_) => break,
+            None if word == "hundred" && matches!(last, Some(Kind::Unit | Kind::Teen)) && (1..20).contains(&group) => {
+                group *= 100;
+                last = Some(Kind::Hundred);
+            }
+            None => match scale {
+                Some(scale) if scale < last_scale && group > 0 && last != Some(Kind::Scale) => {
+                    total += group * scale;
+                    group = 0;
+                    last_scale = scale;
+                    last = Some(Kind::Scale);
+                }
+                _ => break,
+            },
+        }
+        parsed = Some(Spoken {
+            value: total + group,
+            len: index + 1,
+            ordinal: false,
+        });
+    }
+    parsed
+}
+
+/// Years said in pairs: "nineteen ninety-nine", "twenty twenty four",
+/// "twenty oh five".
+fn spoken_year(atoms: &[Atom]) -> Option<(u64, usize)> {
+    let century = match atoms.first()?.text.as_str() {
+        "nineteen" => 19,
+        "twenty" => 20,
+        _ => return None,
+    };
+    let word = |index: usize| atoms.get(index).map(|atom| atom.text.as_str());
+    if word(1) == Some("oh") {
+        let (unit, kind) = cardinal_word(word(2)?)?;
+        return (kind == Kind::Unit && unit > 0).then_some((century * 100 + unit, 3));
+    }
+    match cardinal_word(word(1)?)? {
+        (value, Kind::Teen) => Some((century * 100 + value, 2)),
+        (value, Kind::Tens) => match word(2).and_then(cardinal_word) {
+            Some((unit, Kind::Unit)) if unit > 0 => Some((century * 100 + value + unit, 3)),
+            _ => Some((century * 100 + value, 2)),
+        },
+        _ => None,
+    }
+}
+
+fn ordinal_suffix(value: u64) -> &'static str {
+    match (value % 10, value % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    }
+}
+
+fn currency_symbol(word: &str) -> Option<&'static str> {
+    CURRENCIES.iter().find(|(name, _)| *name == word).map(|(_, symbol)| *symbol)
+}
+
+fn month_index(word: &str) -> Option<usize> {
+    MONTHS.iter().position(|month| month.eq_ignore_ascii_case(word))
+}
+
+/// Month names that are everyday words too ("you may", "we march").
+const AMBIGUOUS_MONTHS: [&str; 2] = ["march", "may"];
+
+/// The month `atom` names. An ambiguous one only counts when it is
+/// capitalized or `ordinal_day` is set, so "march two miles" stays as said.
+fn month_at(atom: &Atom, ordinal_day: bool) -> Option<usize> {
+    let month = month_index(&atom.text)?;
+    let ambiguous = AMBIGUOUS_MONTHS.contains(&atom.text.as_str());
+    (!ambiguous || atom.capitalized || ordinal_day).then_some(month)
+}
+
+/// "March third", "March 3rd 2024", "the third of March".
+fn date_at(atoms: &[Atom], locale: NumberLocale) -> Option<(String, usize)> {
+    let (month, day, mut len) = if month_index(&atoms[0].text).is_some() {
+        let (day, day_len, ordinal) = day_at(&atoms[1..])?;
+        (month_at(&atoms[0], ordinal)?, day, 1 + day_len)
+    } else if atoms[0].text == "the" {
+        let (day, day_len, ordinal) = day_at(&atoms[1..])?;
+        if atoms.get(1 + day_len)?.text != "of" {
+            return None;
+        }
+        let month = month_at(atoms.get(2 + day_len)?, ordinal)?;
+        (month, day, 3 + day_len)
+    } else {
+        return None;
+    };
+    let year = year_at(&atoms[len..]).map(|(year, year_len)| {
+        len += year_len;
+        year
+    });
+    Some((locale.date(month, day, year), len))
+}
+
+/// The day of the month at the start of `atoms`, its length in atoms, and
+/// whether it was said as an ordinal ("third", "3rd").
+fn day_at(atoms: &[Atom]) -> Option<(u64, usize, bool)> {
+    let first = atoms.first()?;
+    let (day, len, ordinal) = if first.digits {
+        let digits = first.text.trim_end_matches(char::is_alphabetic);
+        (digits.parse().ok()?, 1, digits.len() < first.text.len())
+    } else {
+        let spoken = spoken_integer(atoms)?;
+        (spoken.value, spoken.len, spoken.ordinal)
+    };
+    (1..=31).contains(&day).then_some((day, len, ordinal))
+}
+
+fn year_at(atoms: &[Atom]) -> Option<(u64, usize)> {
+    let first = atoms.first()?;
+    if first.digits {
+        return (first.text.len() == 4)
+            .then(|| first.text.parse().ok())
+            .flatten()
+            .map(|year| (year, 1));
+    }
+    spoken_year(atoms).or_else(|| {
+        spoken_integer(atoms)
+            .filter(|spoken| !spoken.ordinal && (1000..3000).contains(&spoken.value))
+            .map(|spoken| (spoken.value, spoken.len))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_numbers, NumberLocale};
+
+    fn us(text: &str) -> String {
+        format_numbers(text, NumberLocale::from_tag("en-US"))
+    }
+
+    #[test]
+    fn spoken_numbers_become_digits_but_small_ones_stay_words() {
+        assert_eq!(us("twenty three people and one dog"), "23 people and one dog");
+        assert_eq!(us("three hundred and twelve thousand five"), "312,005");
+        assert_eq!(us("nineteen hundred"), "1900");
+        assert_eq!(us("one million two hundred thousand"), "1,200,000");
+        assert_eq!(us("version two point five"), "version 2.5");
+        assert_eq!(us("Twenty-one, twelve."), "21, 12.");
+        assert_eq!(us("the twenty-first time, not the first"), "the 21st time, not the first");
+    }
+
+    #[test]
+    fn years_said_in_pairs_are_joined() {
+        assert_eq!(us("back in nineteen ninety-nine"), "back in 1999");
+        assert_eq!(us("since twenty oh five"), "since 2005");
+    }
+
+    #[test]
+    fn percentages_and_currency_follow_the_locale() {
+        assert_eq!(us("up five percent"), "up 5%");
+        assert_eq!(us("up 12 per cent"), "up 12%");
+        assert_eq!(us("it costs twenty dollars and fifty cents"), "it costs $20.50");
+        assert_eq!(us("two thousand euros"), "€2000");
+
+        let de = NumberLocale::from_tag("de-DE");
+        assert_eq!(format_numbers("five percent", de), "5 %");
+        assert_eq!(format_numbers("three point five euros", de), "3,5 €");
+        assert_eq!(format_numbers("twelve thousand five hundred", de), "12.500");
+    }
+
+    #[test]
+    fn dates_use_the_locale_order() {
+        assert_eq!(us("due march third"), "due March 3");
+        assert_eq!(us("on the first of May twenty twenty four"), "on May 1, 2024");
+        assert_eq!(us("June 5th 2025."), "June 5, 2025.");
+
+        let gb = NumberLocale::from_tag("en_GB");
+        assert_eq!(format_numbers("due march third", gb), "due 3 March");
+        assert_eq!(format_numbers("july fourth two thousand twenty", gb), "4 July 2020");
+        // Not a date without a day.
+        assert_eq!(format_numbers("we march on", gb), "we march on");
+    }
+
+    #[test]
+    fn everyday_month_words_need_a_capital_or_an_ordinal_day() {
+        assert_eq!(us("you may one day"), "you may one day");
+        assert_eq!(us("we march two miles"), "we march two miles");
+        assert_eq!(us("it may 2 be"), "it may 2 be");
+        assert_eq!(us("May one"), "May 1");
+        assert_eq!(us("see you may 1st"), "see you May 1");
+        assert_eq!(us("by the second of march"), "by March 2");
+        // Unambiguous months don't need either.
+        assert_eq!(us("april two"), "April 2");
+    }
+
+    #[test]
+    fn other_text_is_left_alone() {
+        assert_eq!(us("Someone's twenty-something  friend"), "Someone's twenty-something  friend");
+        assert_eq!(us("call 555 0100"), "call 555 0100");
+        assert_eq!(us(""), "");
+    }
+}
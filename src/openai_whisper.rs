@@ -0,0 +1,384 @@
+use crate::audio::AudioFormat;
+use crate::error::EchoResult;
+use crate::network::{ControlMessage, ProviderError, TranscriptEvent, TranscriptSegment, WordTiming};
+use crate::provider::{emit_log, ProviderCapabilities, ProviderKind, SpeechProvider};
+use crate::proxy::ProxyConfig;
+use async_trait::async_trait;
+use reqwest::multipart::{Form, Part};
+use reqwest::Client;
+use std::cmp::Reverse;
+use std::io::Cursor;
+use std::ops::Range;
+use tokio::sync::mpsc::{Receiver, Sender, UnboundedReceiver, UnboundedSender};
+
+const OPENAI_TRANSCRIPTIONS_URL: &str = "https://api.openai.com/v1/audio/transcriptions";
+const DEFAULT_MODEL: &str = "whisper-1";
+/// The endpoint rejects uploads above 25 MB; a 16kHz 16-bit WAV reaches that
+/// after roughly 13 minutes, sooner at higher rates. Longer sessions are
+/// uploaded in parts.
+const MAX_UPLOAD_BYTES: usize = 25 * 1024 * 1024;
+/// Samples in the longest part, leaving room for the WAV header.
+const MAX_PART_SAMPLES: usize = (MAX_UPLOAD_BYTES - 1024) / 2;
+/// How far before the size limit a part may end to land on a pause.
+const SPLIT_SEARCH_SECS: usize = 30;
+
+/// Non-realtime fallback: buffers the whole session and uploads it as a WAV
+/// file to the OpenAI audio transcription endpoint once recording stops,
+/// split into several uploads when it is above the size limit.
+pub struct OpenAiWhisperClient {
+    api_key: String,
+    model: String,
+    language: String,
+    format: AudioFormat,
+    http: Client,
+}
+
+impl OpenAiWhisperClient {
+    pub fn new(api_key: String, model: String, language: String) -> Self {
+        Self {
+            api_key,
+            model,
+            language,
+            format: AudioFormat::default(),
+            http: Client::new(),
+        }
+    }
+
+    /// PCM only; the rate goes into the uploaded WAV header.
+    pub fn with_audio_format(mut self, format: AudioFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Routes requests through `proxy` instead of reqwest's environment lookup.
+    pub fn with_proxy(mut self, proxy: Option<&ProxyConfig>) -> Result<Self, String> {
+        if proxy.is_some() {
+            self.http = crate::proxy::http_client(proxy, None)?;
+        }
+        Ok(self)
+    }
+
+    async fn transcribe(&self, samples: &[i16]) -> Result<TranscriptSegment, String> {
+        let wav = encode_wav(samples, self.format.sample_rate).map_err(|e| format!("Failed to encode WAV: {}", e))?;
+        if wav.len() > MAX_UPLOAD_BYTES {
+            return Err(format!(
+                "Recording is {:.1} MB, above the 25 MB upload limit",
+                wav.len() as f64 / (1024.0 * 1024.0)
+            ));
+        }
+
+        let model = if self.model.is_empty() {
+            DEFAULT_MODEL
+        } else {
+            self.model.as_str()
+        };
+        let file = Part::bytes(wav)
+            .file_name("session.wav")
+            .mime_str("audio/wav")
+            .map_err(|e| e.to_string())?;
+        // Only the whisper models offer verbose_json with timings and log-probs.
+        let verbose = model.starts_with("whisper");
+        let mut form = Form::new()
+            .part("file", file)
+            .text("model", model.to_string())
+            .text("response_format", if verbose { "verbose_json" } else { "json" });
+        if verbose {
+            form = form
+                .text("timestamp_granularities[]", "word")
+                .text("timestamp_granularities[]", "segment");
+        }
+        if let Some(language) = iso_639_1(&self.language) {
+            form = form.text("language", language);
+        }
+
+        let response = self
+            .http
+            .post(OPENAI_TRANSCRIPTIONS_URL)
+            .bearer_auth(&self.api_key)
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let status = response.status();
+        let value: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+        if !status.is_success() {
+            return Err(value.to_string());
+        }
+        Ok(segment_from_response(&value))
+    }
+}
+
+/// Reads `text` plus, for verbose_json, word timings and a confidence taken
+/// from the mean segment `avg_logprob`.
+fn segment_from_response(value: &serde_json::Value) -> TranscriptSegment {
+    let text = value
+        .get("text")
+        .and_then(|t| t.as_str())
+        .unwrap_or_default()
+        .trim();
+    let words = value
+        .get("words")
+        .and_then(|w| w.as_array())
+        .map(|words| {
+            words
+                .iter()
+                .filter_map(|word| {
+                    Some(WordTiming {
+                        text: word.get("word")?.as_str()?.to_string(),
+                        start_secs: word.get("start")?.as_f64()?,
+                        end_secs: word.get("end")?.as_f64()?,
+                        confidence: None,
+                        speaker: None,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let mut segment = TranscriptSegment::from_words(text, words);
+
+    let segments = value.get("segments").and_then(|s| s.as_array());
+    let logprobs: Vec<f64> = segments
+        .map(|segments| {
+            segments
+                .iter()
+                .filter_map(|s| s.get("avg_logprob")?.as_f64())
+                .collect()
+        })
+        .unwrap_or_default();
+    if !logprobs.is_empty() {
+        let mean = logprobs.iter().sum::<f64>() / logprobs.len() as f64;
+        segment.confidence = Some(mean.exp().clamp(0.0, 1.0) as f32);
+    }
+    if segment.start_secs.is_none() {
+        segment.start_secs = segments
+            .and_then(|s| s.first())
+            .and_then(|s| s.get("start")?.as_f64());
+        segment.end_secs = segments
+            .and_then(|s| s.last())
+            .and_then(|s| s.get("end")?.as_f64());
+    }
+    segment
+}
+
+/// Cuts a session into ranges of at most `max_samples`. Each cut lands in the
+/// quietest 100ms frame of the last `SPLIT_SEARCH_SECS` before the limit, so
+/// it falls between words; audio without a pause is cut near the limit.
+fn upload_parts(samples: &[i16], sample_rate: u32, max_samples: usize) -> Vec<Range<usize>> {
+    let frame = (sample_rate as usize / 10).max(1);
+    let search = SPLIT_SEARCH_SECS * sample_rate as usize;
+    let mut parts = Vec::new();
+    let mut start = 0;
+    while samples.len() - start > max_samples.max(2 * frame) {
+        let limit = start + max_samples.max(2 * frame);
+        let earliest = limit.saturating_sub(search).max(start + frame);
+        let end = (earliest..=limit - frame)
+            .step_by(frame)
+            .max_by_key(|&at| Reverse(frame_energy(&samples[at..at + frame])))
+            .map_or(limit, |at| at + frame / 2);
+        parts.push(start..end);
+        start = end;
+    }
+    parts.push(start..samples.len());
+    parts
+}
+
+fn frame_energy(frame: &[i16]) -> u64 {
+    frame.iter().map(|&s| (s as i64 * s as i64) as u64).sum()
+}
+
+/// Moves a part's timings from the start of the part to the start of the session.
+fn offset_segment(mut segment: TranscriptSegment, offset_secs: f64) -> TranscriptSegment {
+    segment.start_secs = segment.start_secs.map(|secs| secs + offset_secs);
+    segment.end_secs = segment.end_secs.map(|secs| secs + offset_secs);
+    for word in &mut segment.words {
+        word.start_secs += offset_secs;
+        word.end_secs += offset_secs;
+    }
+    segment
+}
+
+/// Writes mono PCM into an in-memory WAV file.
+fn encode_wav(samples: &[i16], sample_rate: u32) -> Result<Vec<u8>, hound::Error> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut cursor = Cursor::new(Vec::with_capacity(44 + samples.len() * 2));
+    {
+        let mut writer = hound::WavWriter::new(&mut cursor, spec)?;
+        for &sample in samples {
+            writer.write_sample(sample)?;
+        }
+        writer.finalize()?;
+    }
+    Ok(cursor.into_inner())
+}
+
+/// The endpoint takes ISO-639-1 codes, so "en-US" becomes "en".
+fn iso_639_1(language: &str) -> Option<String> {
+    let code = language.split(['-', '_']).next()?.trim().to_lowercase();
+    (code.len() == 2).then_some(code)
+}
+
+#[async_trait]
+impl SpeechProvider for OpenAiWhisperClient {
+    fn name(&self) -> &'static str {
+        "OpenAI Whisper"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderKind::OpenAiWhisper.capabilities()
+    }
+
+    fn audio_format(&self) -> AudioFormat {
+        self.format
+    }
+
+    async fn run(
+        &self,
+        mut audio_rx: Receiver<Vec<i16>>,
+        mut control_rx: UnboundedReceiver<ControlMessage>,
+        text_tx: Sender<TranscriptEvent>,
+        log_tx: UnboundedSender<String>,
+    ) -> EchoResult<()> {
+        emit_log(&log_tx, "🎙 Buffering session audio for OpenAI transcription".to_string());
+        let mut accepting_audio = false;
+        let mut samples: Vec<i16> = Vec::new();
+
+        loop {
+            tokio::select! {
+                Some(cmd) = control_rx.recv() => {
+                    match cmd {
+                        ControlMessage::Start => accepting_audio = true,
+                        ControlMessage::Stop => break,
+                        ControlMessage::Cancel => {
+                            emit_log(&log_tx, "➡️ Session cancelled, discarding buffered audio".to_string());
+                            return Ok(());
+                        }
+                    }
+                }
+                maybe_chunk = audio_rx.recv() => {
+                    match maybe_chunk {
+                        Some(chunk) if accepting_audio => samples.extend_from_slice(&chunk),
+                        Some(_) => {}
+                        None => break,
+                    }
+                }
+            }
+        }
+        while let Ok(chunk) = audio_rx.try_recv() {
+            samples.extend_from_slice(&chunk);
+        }
+
+        if samples.is_empty() {
+            let _ = text_tx.send(TranscriptEvent::Committed(TranscriptSegment::default())).await;
+            return Ok(());
+        }
+        let parts = upload_parts(&samples, self.format.sample_rate, MAX_PART_SAMPLES);
+        emit_log(
+            &log_tx,
+            format!(
+                "➡️ [API OUT] Uploading {:.1}s of audio to {} in {} part(s)",
+                self.format.seconds(samples.len()),
+                OPENAI_TRANSCRIPTIONS_URL,
+                parts.len()
+            ),
+        );
+        let mut segments = Vec::with_capacity(parts.len());
+        for part in parts {
+            let offset_secs = self.format.seconds(part.start);
+            match self.transcribe(&samples[part]).await {
+                Ok(segment) => segments.push(offset_segment(segment, offset_secs)),
+                Err(e) => {
+                    emit_log(&log_tx, format!("❌ [API ERROR] {}", e));
+                    let _ = text_tx.send(TranscriptEvent::Error(ProviderError::new("transcription_failed", e))).await;
+                    return Ok(());
+                }
+            }
+        }
+        let segment = TranscriptSegment::join(segments);
+        emit_log(&log_tx, format!("📝 [COMMITTED] {}", segment.text));
+        let _ = text_tx.send(TranscriptEvent::Committed(segment)).await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode_wav, iso_639_1, offset_segment, segment_from_response, upload_parts, MAX_UPLOAD_BYTES, MAX_PART_SAMPLES};
+    use crate::network::{TranscriptSegment, WordTiming};
+    use serde_json::json;
+
+    #[test]
+    fn wav_encoding_roundtrips() {
+        let wav = encode_wav(&[1, -2, 3], 22050).unwrap();
+        assert_eq!(&wav[..4], b"RIFF");
+        let mut reader = hound::WavReader::new(std::io::Cursor::new(wav)).unwrap();
+        assert_eq!(reader.spec().sample_rate, 22050);
+        let samples: Vec<i16> = reader.samples::<i16>().map(|s| s.unwrap()).collect();
+        assert_eq!(samples, vec![1, -2, 3]);
+    }
+
+    #[test]
+    fn long_sessions_are_split_at_the_quietest_frame() {
+        // 1kHz, so a frame is 100 samples; quiet from 3000 to 3100.
+        let mut samples = vec![1000i16; 8000];
+        samples[3000..3100].fill(0);
+        assert_eq!(upload_parts(&samples, 1000, 5000), vec![0..3050, 3050..8000]);
+        assert_eq!(upload_parts(&samples, 1000, 8000), vec![0..8000]);
+    }
+
+    #[test]
+    fn audio_without_a_pause_is_split_near_the_limit() {
+        let samples = vec![1000i16; 12000];
+        let parts = upload_parts(&samples, 1000, 5000);
+        assert_eq!(parts, vec![0..4950, 4950..9900, 9900..12000]);
+        assert!(parts.iter().all(|part| part.len() <= 5000));
+        const { assert!(44 + 2 * MAX_PART_SAMPLES <= MAX_UPLOAD_BYTES) };
+    }
+
+    #[test]
+    fn part_timings_are_moved_to_session_time() {
+        let word = WordTiming {
+            text: "later".to_string(),
+            start_secs: 0.5,
+            end_secs: 1.0,
+            confidence: None,
+            speaker: None,
+        };
+        let segment = offset_segment(TranscriptSegment::from_words("later", vec![word]), 600.0);
+        assert_eq!((segment.start_secs, segment.end_secs), (Some(600.5), Some(601.0)));
+        assert_eq!(segment.words[0].start_secs, 600.5);
+    }
+
+    #[test]
+    fn language_is_reduced_to_iso_639_1() {
+        assert_eq!(iso_639_1("en-US").as_deref(), Some("en"));
+        assert_eq!(iso_639_1("DE").as_deref(), Some("de"));
+        assert_eq!(iso_639_1(""), None);
+        assert_eq!(iso_639_1("yue-Hant-HK"), None);
+    }
+
+    #[test]
+    fn verbose_response_yields_words_and_confidence() {
+        let value = json!({
+            "text": " Hello world ",
+            "segments": [{ "start": 0.0, "end": 1.5, "avg_logprob": -0.105360516 }],
+            "words": [
+                { "word": "Hello", "start": 0.1, "end": 0.5 },
+                { "word": "world", "start": 0.6, "end": 1.2 }
+            ]
+        });
+        let segment = segment_from_response(&value);
+        assert_eq!(segment.text, "Hello world");
+        assert_eq!(segment.words.len(), 2);
+        assert_eq!(segment.start_secs, Some(0.1));
+        assert!((segment.confidence.unwrap() - 0.9).abs() < 1e-4);
+
+        let plain = segment_from_response(&json!({ "text": "hi" }));
+        assert_eq!(plain.text, "hi");
+        assert_eq!(plain.confidence, None);
+    }
+}
@@ -0,0 +1,403 @@
+// Where finished transcripts go. Typing into the focused window is one
+// sink among several; every enabled sink receives the same text, so a
+// transcript can be typed, copied and logged to a file in one go.
+
+use crate::error::{EchoError, EchoResult};
+use crate::injector::{self, InjectionOptions, TargetLock, TargetWindow, TextEdit};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs::OpenOptions;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// How long a webhook may take before the transcript is reported as not
+/// delivered.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputSinkKind {
+    Keyboard,
+    Clipboard,
+    File,
+    Stdout,
+    Webhook,
+    Markdown,
+}
+
+impl OutputSinkKind {
+    pub const ALL: [OutputSinkKind; 6] = [
+        OutputSinkKind::Keyboard,
+        OutputSinkKind::Clipboard,
+        OutputSinkKind::File,
+        OutputSinkKind::Stdout,
+        OutputSinkKind::Webhook,
+        OutputSinkKind::Markdown,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            OutputSinkKind::Keyboard => "Type into the focused window",
+            OutputSinkKind::Clipboard => "Copy to the clipboard",
+            OutputSinkKind::File => "Append to a file",
+            OutputSinkKind::Stdout => "Print to standard output",
+            OutputSinkKind::Webhook => "POST to a webhook",
+            OutputSinkKind::Markdown => "Append to a Markdown note",
+        }
+    }
+}
+
+/// A destination for finished transcripts. Delivery may block (keystrokes,
+/// disk, HTTP), so callers run it off the async runtime.
+pub trait OutputSink: Send {
+    fn kind(&self) -> OutputSinkKind;
+
+    /// Hands over `text`. Ok(false) means the sink chose to skip it, e.g.
+    /// because focus moved away from the target window.
+    fn deliver(&mut self, text: &str) -> EchoResult<bool>;
+}
+
+/// Types into the focused (or locked) window. Text already typed by live
+/// partial injection is corrected in place rather than typed again.
+pub struct KeyboardSink {
+    target: Option<(TargetWindow, TargetLock)>,
+    options: InjectionOptions,
+    typed: String,
+}
+
+impl KeyboardSink {
+    pub fn new(target: Option<(TargetWindow, TargetLock)>, options: InjectionOptions, typed: String) -> Self {
+        Self { target, options, typed }
+    }
+
+    /// Makes the typed text read exactly `target`.
+    pub fn sync(&mut self, target: &str) -> EchoResult<bool> {
+        let edit = TextEdit::between(&self.typed, target);
+        if edit.is_empty() {
+            return Ok(true);
+        }
+        if let Some((window, lock)) = self.target {
+            if !injector::prepare_target(window, lock)? {
+                return Ok(false);
+            }
+        }
+        injector::apply_edit(&edit, &self.options)?;
+        self.typed = target.to_string();
+        Ok(true)
+    }
+}
+
+impl OutputSink for KeyboardSink {
+    fn kind(&self) -> OutputSinkKind {
+        OutputSinkKind::Keyboard
+    }
+
+    /// Leaves a trailing space so the next dictation doesn't run on.
+    fn deliver(&mut self, text: &str) -> EchoResult<bool> {
+        self.sync(&format!("{} ", text))
+    }
+}
+
+pub struct ClipboardSink;
+
+impl OutputSink for ClipboardSink {
+    fn kind(&self) -> OutputSinkKind {
+        OutputSinkKind::Clipboard
+    }
+
+    fn deliver(&mut self, text: &str) -> EchoResult<bool> {
+        arboard::Clipboard::new()
+            .and_then(|mut clipboard| clipboard.set_text(text.to_string()))
+            .map_err(|e| EchoError::injection(format!("Could not copy the transcript: {}", e)))?;
+        Ok(true)
+    }
+}
+
+/// Appends one line per transcript.
+pub struct FileSink {
+    path: PathBuf,
+}
+
+impl FileSink {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl OutputSink for FileSink {
+    fn kind(&self) -> OutputSinkKind {
+        OutputSinkKind::File
+    }
+
+    fn deliver(&mut self, text: &str) -> EchoResult<bool> {
+        let disk_error = |e: std::io::Error| EchoError::config(format!("Could not write to {}: {}", self.path.display(), e));
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).map_err(disk_error)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path).map_err(disk_error)?;
+        writeln!(file, "{}", text).map_err(disk_error)?;
+        Ok(true)
+    }
+}
+
+/// For piping transcripts into other tools when started from a terminal.
+pub struct StdoutSink;
+
+impl OutputSink for StdoutSink {
+    fn kind(&self) -> OutputSinkKind {
+        OutputSinkKind::Stdout
+    }
+
+    fn deliver(&mut self, text: &str) -> EchoResult<bool> {
+        let mut stdout = std::io::stdout().lock();
+        writeln!(stdout, "{}", text)
+            .and_then(|()| stdout.flush())
+            .map_err(|e| EchoError::config(format!("Could not write to standard output: {}", e)))?;
+        Ok(true)
+    }
+}
+
+/// Posts each transcript as JSON, for note apps, n8n/Zapier flows and
+/// custom servers. Runs inside `spawn_blocking`, so it borrows the runtime
+/// to make the request.
+pub struct WebhookSink {
+    url: String,
+    proxy_url: String,
+    session_id: Option<String>,
+    started_at: DateTime<Local>,
+}
+
+impl WebhookSink {
+    fn payload(&self, text: &str, finished_at: DateTime<Local>) -> serde_json::Value {
+        json!({
+            "text": text,
+            "session_id": self.session_id,
+            "started_at": self.started_at.to_rfc3339(),
+            "finished_at": finished_at.to_rfc3339(),
+            "app": "11th Echo",
+        })
+    }
+}
+
+impl OutputSink for WebhookSink {
+    fn kind(&self) -> OutputSinkKind {
+        OutputSinkKind::Webhook
+    }
+
+    fn deliver(&mut self, text: &str) -> EchoResult<bool> {
+        let url = url::Url::parse(self.url.trim())
+            .map_err(|e| EchoError::config(format!("Webhook URL \"{}\" is invalid: {}", self.url.trim(), e)))?;
+        let payload = self.payload(text, Local::now());
+        let proxy = crate::proxy::resolve(&self.proxy_url).map_err(EchoError::Config)?;
+        let runtime = tokio::runtime::Handle::try_current()
+            .map_err(|e| EchoError::network(format!("Webhook needs the async runtime: {}", e)))?;
+        runtime.block_on(async move {
+            let http = crate::proxy::http_client(proxy.as_ref(), Some(WEBHOOK_TIMEOUT)).map_err(EchoError::Config)?;
+            http.post(url).json(&payload).send().await?.error_for_status()?;
+            Ok(true)
+        })
+    }
+}
+
+/// Appends "- HH:MM text" bullets to a Markdown file whose path may contain
+/// strftime patterns, e.g. `Vault/Daily/%Y-%m-%d.md` for an Obsidian daily
+/// note.
+pub struct MarkdownSink {
+    template: String,
+}
+
+impl MarkdownSink {
+    fn note_path(&self, now: DateTime<Local>) -> EchoResult<PathBuf> {
+        let items: Vec<Item> = StrftimeItems::new(self.template.trim()).collect();
+        if items.contains(&Item::Error) {
+            return Err(EchoError::config(format!(
+                "Note path \"{}\" has an invalid date pattern",
+                self.template.trim()
+            )));
+        }
+        Ok(PathBuf::from(now.format_with_items(items.into_iter()).to_string()))
+    }
+
+    fn append(&self, text: &str, now: DateTime<Local>) -> EchoResult<()> {
+        let path = self.note_path(now)?;
+        let disk_error = |e: std::io::Error| EchoError::config(format!("Could not write to {}: {}", path.display(), e));
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(disk_error)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&path)
+            .map_err(disk_error)?;
+        // Don't glue the bullet onto a note that was saved without a final newline.
+        let mut last = [0u8; 1];
+        let needs_newline = file.seek(SeekFrom::End(-1)).is_ok()
+            && file.read_exact(&mut last).is_ok()
+            && last[0] != b'\n';
+        let entry = format!("- {} {}\n", now.format("%H:%M"), text.replace('\n', " "));
+        let entry = if needs_newline { format!("\n{}", entry) } else { entry };
+        file.write_all(entry.as_bytes()).map_err(disk_error)
+    }
+}
+
+impl OutputSink for MarkdownSink {
+    fn kind(&self) -> OutputSinkKind {
+        OutputSinkKind::Markdown
+    }
+
+    fn deliver(&mut self, text: &str) -> EchoResult<bool> {
+        self.append(text, Local::now())?;
+        Ok(true)
+    }
+}
+
+/// What the sinks need besides the text itself.
+#[derive(Debug, Clone)]
+pub struct SinkConfig {
+    pub file: PathBuf,
+    pub webhook_url: String,
+    /// Markdown note path, with strftime date patterns.
+    pub markdown_template: String,
+    pub proxy_url: String,
+    /// The provider's id for the session, when it sends one.
+    pub session_id: Option<String>,
+    pub started_at: DateTime<Local>,
+}
+
+/// The sinks for `kinds`, in the order given, with `keyboard` used for the
+/// keyboard sink.
+pub fn build_sinks(kinds: &[OutputSinkKind], config: &SinkConfig, keyboard: KeyboardSink) -> Vec<Box<dyn OutputSink>> {
+    let mut keyboard = Some(keyboard);
+    let mut sinks: Vec<Box<dyn OutputSink>> = Vec::new();
+    for &kind in kinds {
+        if sinks.iter().any(|sink| sink.kind() == kind) {
+            continue;
+        }
+        match kind {
+            OutputSinkKind::Keyboard => {
+                if let Some(keyboard) = keyboard.take() {
+                    sinks.push(Box::new(keyboard));
+                }
+            }
+            OutputSinkKind::Clipboard => sinks.push(Box::new(ClipboardSink)),
+            OutputSinkKind::File => sinks.push(Box::new(FileSink::new(config.file.clone()))),
+            OutputSinkKind::Stdout => sinks.push(Box::new(StdoutSink)),
+            OutputSinkKind::Webhook => sinks.push(Box::new(WebhookSink {
+                url: config.webhook_url.clone(),
+                proxy_url: config.proxy_url.clone(),
+                session_id: config.session_id.clone(),
+                started_at: config.started_at,
+            })),
+            OutputSinkKind::Markdown => sinks.push(Box::new(MarkdownSink {
+                template: config.markdown_template.clone(),
+            })),
+        }
+    }
+    sinks
+}
+
+/// Sends `text` to every sink; one failing does not stop the others.
+pub fn deliver_all(sinks: Vec<Box<dyn OutputSink>>, text: &str) -> Vec<(OutputSinkKind, EchoResult<bool>)> {
+    sinks
+        .into_iter()
+        .map(|mut sink| (sink.kind(), sink.deliver(text)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_sinks, deliver_all, KeyboardSink, MarkdownSink, OutputSinkKind, SinkConfig, WebhookSink};
+    use crate::injector::InjectionOptions;
+    use chrono::{Local, TimeZone};
+    use std::path::PathBuf;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn config(file: PathBuf) -> SinkConfig {
+        SinkConfig {
+            file,
+            webhook_url: String::new(),
+            markdown_template: String::new(),
+            proxy_url: String::new(),
+            session_id: None,
+            started_at: Local::now(),
+        }
+    }
+
+    #[test]
+    fn every_enabled_sink_receives_the_transcript() {
+        let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        let dir = std::env::temp_dir().join(format!("eleventh_echo_output_{}", stamp));
+        let file = dir.join("nested").join("dictation.txt");
+        let keyboard = || KeyboardSink::new(None, InjectionOptions::default(), String::new());
+
+        let kinds = [OutputSinkKind::File, OutputSinkKind::Keyboard, OutputSinkKind::File];
+        let sinks = build_sinks(&kinds, &config(file.clone()), keyboard());
+        assert_eq!(sinks.iter().map(|s| s.kind()).collect::<Vec<_>>(), [OutputSinkKind::File, OutputSinkKind::Keyboard]);
+        let results = deliver_all(sinks, "first note");
+        assert!(results.iter().all(|(_, result)| *result == Ok(true)));
+        deliver_all(build_sinks(&[OutputSinkKind::File], &config(file.clone()), keyboard()), "second note");
+
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "first note\nsecond note\n");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn webhook_posts_text_with_session_details() {
+        let started_at = Local.with_ymd_and_hms(2026, 3, 1, 9, 30, 0).unwrap();
+        let sink = WebhookSink {
+            url: "https://hooks.example.com/dictation".to_string(),
+            proxy_url: String::new(),
+            session_id: Some("sess_42".to_string()),
+            started_at,
+        };
+        let payload = sink.payload("Buy milk", started_at + chrono::Duration::seconds(12));
+        assert_eq!(payload["text"], "Buy milk");
+        assert_eq!(payload["session_id"], "sess_42");
+        assert_eq!(payload["started_at"], started_at.to_rfc3339());
+        assert_eq!(
+            payload["finished_at"],
+            (started_at + chrono::Duration::seconds(12)).to_rfc3339()
+        );
+
+        let mut unconfigured = WebhookSink { url: String::new(), ..sink };
+        assert!(super::OutputSink::deliver(&mut unconfigured, "Buy milk").is_err());
+    }
+
+    #[test]
+    fn markdown_sink_appends_bullets_to_the_dated_note() {
+        let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        let dir = std::env::temp_dir().join(format!("eleventh_echo_notes_{}", stamp));
+        let sink = MarkdownSink {
+            template: format!("{}/Daily/%Y-%m-%d.md", dir.display()),
+        };
+        let morning = Local.with_ymd_and_hms(2026, 3, 1, 9, 5, 0).unwrap();
+        let note = dir.join("Daily").join("2026-03-01.md");
+        assert_eq!(sink.note_path(morning).unwrap(), note);
+
+        std::fs::create_dir_all(note.parent().unwrap()).unwrap();
+        std::fs::write(&note, "# Sunday").unwrap();
+        sink.append("Call the plumber", morning).unwrap();
+        sink.append("Two\nlines", morning + chrono::Duration::minutes(90)).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&note).unwrap(),
+            "# Sunday\n- 09:05 Call the plumber\n- 10:35 Two lines\n"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let broken = MarkdownSink { template: "notes/%Q.md".to_string() };
+        assert!(broken.note_path(morning).is_err());
+    }
+
+    #[test]
+    fn keyboard_sink_tracks_what_it_typed() {
+        let mut sink = KeyboardSink::new(None, InjectionOptions::default(), "hello wor".to_string());
+        assert_eq!(sink.sync("hello world"), Ok(true));
+        assert_eq!(sink.typed, "hello world");
+        assert_eq!(super::OutputSink::deliver(&mut sink, "hello world"), Ok(true));
+        assert_eq!(sink.typed, "hello world ");
+    }
+}
@@ -1,7 +1,17 @@
 #[derive(Debug, Default, Clone)]
 pub struct TranscriptPipeline {
     transcript: String,
+    /// The part of `transcript` that is typed or sent to the outputs: all
+    /// of it except what the other side of a meeting said, without speaker
+    /// labels when `strip_labels` is set.
+    typed: String,
+    strip_labels: bool,
     stop_requested: bool,
+    /// Silence between segments, in seconds, that starts a new paragraph;
+    /// zero keeps everything in one.
+    paragraph_pause_secs: f64,
+    /// Where the last timed segment ended on the provider's clock.
+    last_end_secs: Option<f64>,
 }
 
 impl TranscriptPipeline {
@@ -9,8 +19,77 @@ impl TranscriptPipeline {
         Self::default()
     }
 
-    pub fn push_fragment(&mut self, fragment: &str) -> String {
-        self.transcript = append_fragment(&self.transcript, fragment);
+    /// A pipeline that breaks paragraphs after pauses of `secs` or longer.
+    pub fn with_paragraph_pause(secs: f64) -> Self {
+        Self {
+            paragraph_pause_secs: secs.max(0.0),
+            ..Self::new()
+        }
+    }
+
+    /// Drops the text and stop request, keeping the paragraph and label
+    /// settings.
+    pub fn clear(&mut self) {
+        *self = Self {
+            strip_labels: self.strip_labels,
+            ..Self::with_paragraph_pause(self.paragraph_pause_secs)
+        };
+    }
+
+    /// Whether "Speaker N:" labels are left out of the typed text. The
+    /// transcript keeps them either way.
+    pub fn set_strip_speaker_labels(&mut self, strip: bool) {
+        self.strip_labels = strip;
+    }
+
+    /// Appends `fragment`, spanning `span` seconds when the provider timed
+    /// it. A gap from the previous timed segment of at least the paragraph
+    /// pause puts it in a new paragraph.
+    pub fn push_segment(&mut self, fragment: &str, span: Option<(f64, f64)>) -> String {
+        self.push(fragment, span, true, false)
+    }
+
+    /// Like `push_segment`, for text that is shown and saved but never
+    /// typed: the other side of a meeting.
+    pub fn push_heard_segment(&mut self, fragment: &str, span: Option<(f64, f64)>) -> String {
+        self.push(fragment, span, false, false)
+    }
+
+    /// Like `push_segment`, for text that must go out as written, such as an
+    /// expanded snippet or an identifier: it skips `format_segment`, so its case, line breaks
+    /// and spacing are kept.
+    pub fn push_verbatim_segment(&mut self, fragment: &str, span: Option<(f64, f64)>) -> String {
+        self.push(fragment, span, true, true)
+    }
+
+    /// `push_heard_segment` for verbatim text.
+    pub fn push_heard_verbatim_segment(&mut self, fragment: &str, span: Option<(f64, f64)>) -> String {
+        self.push(fragment, span, false, true)
+    }
+
+    fn push(&mut self, fragment: &str, span: Option<(f64, f64)>, typed: bool, verbatim: bool) -> String {
+        let pause = span
+            .zip(self.last_end_secs)
+            .map(|((start, _), last_end)| start - last_end);
+        let new_paragraph = self.paragraph_pause_secs > 0.0
+            && pause.is_some_and(|pause| pause >= self.paragraph_pause_secs);
+        let append = |existing: &str, fragment: &str| match (verbatim, new_paragraph) {
+            (false, false) => append_fragment(existing, fragment),
+            (false, true) => append_paragraph(existing, fragment),
+            (true, false) => append_verbatim(existing, fragment, " "),
+            (true, true) => append_verbatim(existing, fragment, "\n\n"),
+        };
+        self.transcript = append(&self.transcript, fragment);
+        if typed {
+            self.typed = if self.strip_labels {
+                append(&self.typed, &strip_speaker_labels(fragment))
+            } else {
+                append(&self.typed, fragment)
+            };
+        }
+        if let Some((_, end)) = span {
+            self.last_end_secs = Some(end);
+        }
         self.transcript.clone()
     }
 
@@ -25,35 +104,109 @@ impl TranscriptPipeline {
     pub fn committed_text(&self) -> &str {
         &self.transcript
     }
+
+    pub fn typed_text(&self) -> &str {
+        &self.typed
+    }
+}
+
+/// Punctuation after which the next word starts a new sentence.
+const SENTENCE_END: [char; 3] = ['.', '!', '?'];
+/// Punctuation that attaches to the previous word without a space.
+const CLOSING_PUNCTUATION: [char; 6] = ['.', ',', '!', '?', ';', ':'];
+
+/// Formats `segment` for appending after `previous`: one space between the
+/// two (none before closing punctuation), a capital letter at the start of
+/// every sentence, and runs of whitespace collapsed to a single space.
+pub fn format_segment(previous: &str, segment: &str) -> String {
+    let collapsed = segment.split_whitespace().collect::<Vec<_>>().join(" ");
+    let text = collapsed.trim_start_matches('-').trim_start();
+    if text.is_empty() {
+        return String::new();
+    }
+
+    let last = previous.trim_end().chars().last();
+    let mut output = String::with_capacity(text.len() + 1);
+    if last.is_some() && !text.starts_with(CLOSING_PUNCTUATION) {
+        output.push(' ');
+    }
+    let mut capitalize = last.is_none_or(|c| SENTENCE_END.contains(&c));
+    let mut prev: Option<char> = None;
+    for c in text.chars() {
+        if c == ' ' && prev.is_some_and(|p| SENTENCE_END.contains(&p)) {
+            capitalize = true;
+        }
+        if capitalize && c.is_alphabetic() {
+            output.extend(c.to_uppercase());
+            capitalize = false;
+        } else {
+            if c.is_alphanumeric() {
+                capitalize = false;
+            }
+            output.push(c);
+        }
+        prev = Some(c);
+    }
+    output
 }
 
 pub fn append_fragment(existing: &str, incoming: &str) -> String {
-    let incoming = incoming.trim().trim_start_matches('-').trim();
-    if incoming.is_empty() {
+    let segment = format_segment(existing, incoming);
+    if segment.is_empty() {
         return existing.to_string();
     }
+    format!("{}{} ", existing.trim(), segment)
+}
 
-    let mut output = existing.trim().to_string();
-    if output.is_empty() {
+/// Like `append_fragment`, but starts `incoming` on a new paragraph.
+pub fn append_paragraph(existing: &str, incoming: &str) -> String {
+    let existing = existing.trim();
+    if existing.is_empty() {
+        return append_fragment(existing, incoming);
+    }
+    let segment = format_segment("", incoming);
+    if segment.is_empty() {
+        return format!("{} ", existing);
+    }
+    format!("{}\n\n{} ", existing, segment)
+}
+
+/// Appends `incoming` as written, after `separator` unless `existing` is
+/// empty. Only the whitespace around it is trimmed.
+fn append_verbatim(existing: &str, incoming: &str, separator: &str) -> String {
+    let incoming = incoming.trim();
+    if incoming.is_empty() {
+        return existing.to_string();
+    }
+    let existing = existing.trim();
+    if existing.is_empty() {
         return format!("{} ", incoming);
     }
+    format!("{}{}{} ", existing, separator, incoming)
+}
 
-    let no_leading_space = [".", ",", "!", "?", ";", ":"];
-    if no_leading_space.iter().any(|p| incoming.starts_with(p)) {
-        // If it starts with punctuation, we might have added a space at the end of the previous fragment
-        // so we just push it. But wait, existing.trim() already removed the trailing space.
-        output.push_str(incoming);
-    } else {
-        output.push(' ');
-        output.push_str(incoming);
+/// Removes the "Speaker N:" turn prefixes added to diarized commits,
+/// leaving the rest of the text, line breaks included, as it was.
+pub fn strip_speaker_labels(text: &str) -> String {
+    text.split('\n').map(strip_line_labels).collect::<Vec<_>>().join("\n")
+}
+
+fn strip_line_labels(line: &str) -> String {
+    let mut words = line.split(' ').peekable();
+    let mut kept = Vec::new();
+    while let Some(word) = words.next() {
+        if word == "Speaker" && words.peek().is_some_and(|next| next.len() > 1 && next.ends_with(':')) {
+            words.next();
+            continue;
+        }
+        kept.push(word);
     }
-    output.push(' ');
-    output
+    kept.join(" ")
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{append_fragment, TranscriptPipeline};
+    use super::{append_fragment, append_paragraph, format_segment, strip_speaker_labels, TranscriptPipeline};
 
     #[test]
     fn append_fragment_adds_spaces_between_words() {
@@ -76,18 +229,18 @@ mod tests {
     #[test]
     fn pipeline_requires_stop_before_injection_payload() {
         let mut p = TranscriptPipeline::new();
-        p.push_fragment("hello");
-        p.push_fragment("world");
-        assert_eq!(p.committed_text(), "hello world ");
+        p.push_segment("hello", None);
+        p.push_segment("world", None);
+        assert_eq!(p.committed_text(), "Hello world ");
     }
 
     #[test]
     fn pipeline_returns_full_text_after_stop() {
         let mut p = TranscriptPipeline::new();
-        p.push_fragment("hello");
-        p.push_fragment("world");
+        p.push_segment("hello", None);
+        p.push_segment("world", None);
         p.request_stop();
-        assert_eq!(p.committed_text(), "hello world ");
+        assert_eq!(p.committed_text(), "Hello world ");
     }
 
     #[test]
@@ -99,9 +252,9 @@ mod tests {
     #[test]
     fn committed_text_reflects_added_fragments() {
         let mut p = TranscriptPipeline::new();
-        p.push_fragment("alpha");
-        p.push_fragment("beta");
-        assert_eq!(p.committed_text(), "alpha beta ");
+        p.push_segment("alpha", None);
+        p.push_segment("beta", None);
+        assert_eq!(p.committed_text(), "Alpha beta ");
     }
 
     #[test]
@@ -109,4 +262,114 @@ mod tests {
         let a = append_fragment("hello ", "   ");
         assert_eq!(a, "hello ");
     }
+
+    #[test]
+    fn segments_after_sentence_end_are_capitalized() {
+        let mut p = TranscriptPipeline::new();
+        p.push_segment("this is the first sentence.", None);
+        p.push_segment("and the second one", None);
+        p.push_segment(", with a clause", None);
+        assert_eq!(
+            p.committed_text(),
+            "This is the first sentence. And the second one, with a clause "
+        );
+    }
+
+    #[test]
+    fn format_segment_capitalizes_inside_segment_and_collapses_spaces() {
+        assert_eq!(format_segment("Done!", "ok.  next   up?  yes"), " Ok. Next up? Yes");
+        assert_eq!(format_segment("It costs", "3.5 dollars. fine"), " 3.5 dollars. Fine");
+        assert_eq!(format_segment("", "\"quoted\" start"), "\"Quoted\" start");
+        assert_eq!(format_segment("mid sentence", "  "), "");
+    }
+
+    #[test]
+    fn long_pauses_between_timed_segments_start_a_paragraph() {
+        let mut p = TranscriptPipeline::with_paragraph_pause(2.0);
+        p.push_segment("first thought.", Some((0.0, 1.5)));
+        p.push_segment("still going", Some((2.0, 3.0)));
+        p.push_segment("new topic", Some((6.0, 7.0)));
+        // Untimed segments never break, and don't move the last end.
+        p.push_segment("and more", None);
+        p.push_segment("last", Some((9.5, 10.0)));
+        assert_eq!(
+            p.committed_text(),
+            "First thought. Still going\n\nNew topic and more\n\nLast "
+        );
+
+        p.clear();
+        p.push_segment("fresh", Some((20.0, 21.0)));
+        assert_eq!(p.committed_text(), "Fresh ");
+    }
+
+    #[test]
+    fn paragraph_breaks_are_off_by_default() {
+        let mut p = TranscriptPipeline::new();
+        p.push_segment("one", Some((0.0, 1.0)));
+        p.push_segment("two", Some((30.0, 31.0)));
+        assert_eq!(p.committed_text(), "One two ");
+        assert_eq!(append_paragraph("", "start"), "Start ");
+        assert_eq!(append_paragraph("Done. ", "  "), "Done. ");
+    }
+
+    #[test]
+    fn verbatim_segments_keep_their_case_and_line_breaks() {
+        let mut p = TranscriptPipeline::with_paragraph_pause(2.0);
+        p.push_segment("dear team,", Some((0.0, 1.0)));
+        p.push_verbatim_segment("  best regards,\n  ACME -- support  ", Some((1.5, 2.0)));
+        p.push_segment("thanks.", Some((2.5, 3.0)));
+        assert_eq!(p.committed_text(), "Dear team, best regards,\n  ACME -- support thanks. ");
+        assert_eq!(p.typed_text(), p.committed_text());
+
+        p.push_verbatim_segment("p.s. see below", Some((9.0, 10.0)));
+        assert!(p.committed_text().ends_with("thanks.\n\np.s. see below "));
+        p.push_verbatim_segment(" ", None);
+        assert!(p.committed_text().ends_with("see below "));
+    }
+
+    #[test]
+    fn heard_segments_are_shown_but_not_typed() {
+        let mut p = TranscriptPipeline::new();
+        p.push_segment("Me: can you hear me?", None);
+        p.push_heard_segment("Them: yes, loud and clear.", None);
+        p.push_segment("Me: great", None);
+        assert_eq!(
+            p.committed_text(),
+            "Me: can you hear me? Them: yes, loud and clear. Me: great "
+        );
+        assert_eq!(p.typed_text(), "Me: can you hear me? Me: great ");
+
+        p.clear();
+        assert_eq!(p.typed_text(), "");
+    }
+
+    #[test]
+    fn strip_speaker_labels_keeps_the_spoken_words() {
+        assert_eq!(
+            strip_speaker_labels("Speaker 1: hi there. Speaker 2: hello"),
+            "hi there. hello"
+        );
+        assert_eq!(strip_speaker_labels("the Speaker said hi"), "the Speaker said hi");
+        assert_eq!(
+            strip_speaker_labels("Speaker 1: first.\n\nSpeaker 2: second\n  indented"),
+            "first.\n\nsecond\n  indented"
+        );
+    }
+
+    #[test]
+    fn speaker_labels_are_kept_in_the_transcript_but_not_typed() {
+        let mut p = TranscriptPipeline::with_paragraph_pause(2.0);
+        p.set_strip_speaker_labels(true);
+        p.push_segment("Speaker 1: hi there.", Some((0.0, 1.0)));
+        p.push_segment("Speaker 2: hello", Some((5.0, 6.0)));
+        assert_eq!(p.committed_text(), "Speaker 1: hi there.\n\nSpeaker 2: hello ");
+        assert_eq!(p.typed_text(), "Hi there.\n\nHello ");
+
+        p.clear();
+        p.push_segment("Speaker 1: again", None);
+        assert_eq!(p.typed_text(), "Again ");
+        p.set_strip_speaker_labels(false);
+        p.push_segment("Speaker 2: and you", None);
+        assert_eq!(p.typed_text(), "Again Speaker 2: and you ");
+    }
 }
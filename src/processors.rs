@@ -0,0 +1,579 @@
+// The per-commit text processing chain. Each step is a `TranscriptProcessor`;
+// the order comes from settings, so rewrites, number formatting,
+// replacements and translation can be rearranged, and external programs and
+// the sandboxed WASM plugins can be slotted in anywhere. Redaction is not a
+// step: it always runs after the chain so no step can bring filtered words
+// back.
+
+use crate::error::{EchoError, EchoResult};
+use crate::formatters::FormatterChain;
+use crate::gemini;
+use crate::llm::{self, LlmConfig, LlmScope};
+use crate::numbers::{self, NumberLocale};
+use crate::replacements::{self, CompiledRules};
+use crate::settings::{self, AppSettings};
+use crate::translate::{self, TranslationConfig};
+use crate::wasm_plugins::{self, PluginHost, WasmPlugin};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::{error, info};
+
+/// How long an external processor may take before its output is discarded.
+const EXTERNAL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// One entry in the configured processing order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "command", rename_all = "snake_case")]
+pub enum ProcessorStep {
+    /// Gemini rewrite (punctuation, grammar or a custom prompt).
+    Gemini,
+    /// LLM post-processing, when its scope is each commit.
+    Llm,
+    Numbers,
+    /// The profile's dictation style formatters.
+    Style,
+    Replacements,
+    Translation,
+    /// Every `.wasm` plugin in the plugins folder, in file-name order.
+    Plugins,
+    /// A user-supplied program that reads the text on stdin and writes the
+    /// result to stdout.
+    External(String),
+}
+
+impl ProcessorStep {
+    /// The order used before it was configurable.
+    pub fn defaults() -> Vec<ProcessorStep> {
+        vec![
+            ProcessorStep::Gemini,
+            ProcessorStep::Llm,
+            ProcessorStep::Numbers,
+            ProcessorStep::Style,
+            ProcessorStep::Replacements,
+            ProcessorStep::Plugins,
+            ProcessorStep::Translation,
+        ]
+    }
+
+    fn keyword(&self) -> &'static str {
+        match self {
+            ProcessorStep::Gemini => "gemini",
+            ProcessorStep::Llm => "llm",
+            ProcessorStep::Numbers => "numbers",
+            ProcessorStep::Style => "style",
+            ProcessorStep::Replacements => "replacements",
+            ProcessorStep::Translation => "translation",
+            ProcessorStep::Plugins => "plugins",
+            ProcessorStep::External(_) => "exec",
+        }
+    }
+
+    /// The processors for this step; none when its feature is turned off.
+    fn build(&self, settings: &AppSettings) -> Vec<Box<dyn TranscriptProcessor>> {
+        let processor: Box<dyn TranscriptProcessor> = match self {
+            ProcessorStep::Gemini if settings.gemini_enabled => Box::new(GeminiRewrite {
+                api_key: settings.gemini_api_key.clone(),
+                model: settings.gemini_model.clone(),
+                prompt_preset: settings.gemini_prompt_preset.clone(),
+                custom_prompt: settings.gemini_custom_prompt.clone(),
+                proxy_url: settings.proxy_url.clone(),
+            }),
+            ProcessorStep::Llm if settings.llm_enabled && settings.llm_scope == LlmScope::EachCommit => {
+                Box::new(LlmPostProcess(LlmConfig::from_settings(settings)))
+            }
+            ProcessorStep::Numbers if settings.format_numbers => {
+                Box::new(NumberFormatting(NumberLocale::from_tag(settings.number_locale_tag())))
+            }
+            ProcessorStep::Style if !settings.formatters.is_empty() => {
+                Box::new(DictationStyle(FormatterChain::new(&settings.formatters)))
+            }
+            ProcessorStep::Replacements if !settings.replacement_rules.is_empty() => {
+                Box::new(Replacements(replacements::compiled(&settings.replacement_rules)))
+            }
+            ProcessorStep::Translation if settings.translation_enabled => {
+                Box::new(Translation(TranslationConfig::from_settings(settings)))
+            }
+            ProcessorStep::Plugins => {
+                // Rescanned for every commit, so dropped-in plugins apply at once.
+                // The runtime only starts once there is a plugin to run.
+                let dir = settings::plugins_dir();
+                if !wasm_plugins::has_plugins(&dir) {
+                    return Vec::new();
+                }
+                let Some(host) = PluginHost::global() else {
+                    return Vec::new();
+                };
+                return host
+                    .plugins(&dir)
+                    .into_iter()
+                    .map(|plugin| Box::new(WasmProcessor(plugin)) as Box<dyn TranscriptProcessor>)
+                    .collect();
+            }
+            ProcessorStep::External(command) => Box::new(ExternalProcess {
+                command: command.clone(),
+            }),
+            _ => return Vec::new(),
+        };
+        vec![processor]
+    }
+}
+
+/// A step that transforms committed text.
+#[async_trait]
+pub trait TranscriptProcessor: Send + Sync {
+    /// Short tag for log lines, e.g. "LLM".
+    fn name(&self) -> &str;
+
+    /// Whether a change is worth a line in the log pane. Local, predictable
+    /// steps stay quiet.
+    fn reports_changes(&self) -> bool {
+        false
+    }
+
+    /// Whether its output must be pushed to the transcript as written; see
+    /// `TranscriptPipeline::push_verbatim_segment`.
+    fn verbatim_output(&self) -> bool {
+        false
+    }
+
+    async fn process(&self, text: &str) -> EchoResult<String>;
+}
+
+/// The enabled steps for one commit, in the configured order.
+pub struct ProcessorPipeline {
+    processors: Vec<Box<dyn TranscriptProcessor>>,
+}
+
+impl ProcessorPipeline {
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        Self {
+            processors: settings
+                .processors
+                .iter()
+                .flat_map(|step| step.build(settings))
+                .collect(),
+        }
+    }
+
+    /// True when a step's output must skip `format_segment`.
+    pub fn output_is_verbatim(&self) -> bool {
+        self.processors.iter().any(|processor| processor.verbatim_output())
+    }
+
+    /// Runs every step in turn. A failing step leaves the text as it was and
+    /// the rest of the chain still runs.
+    pub async fn run(&self, text: String, log_tx: &UnboundedSender<String>) -> String {
+        let mut text = text;
+        for processor in &self.processors {
+            if text.trim().is_empty() {
+                break;
+            }
+            match processor.process(&text).await {
+                Ok(processed) => {
+                    if processor.reports_changes() && processed != text {
+                        let _ = log_tx.send(format!("🔧 [{}] \"{}\" -> \"{}\"", processor.name(), text, processed));
+                    }
+                    text = processed;
+                }
+                Err(e) => {
+                    error!("❌ {} processing failed: {}", processor.name(), e);
+                    let _ = log_tx.send(format!("❌ [{}] {} - keeping the text as it was", processor.name(), e));
+                }
+            }
+        }
+        text
+    }
+}
+
+struct GeminiRewrite {
+    api_key: String,
+    model: String,
+    prompt_preset: String,
+    custom_prompt: String,
+    proxy_url: String,
+}
+
+#[async_trait]
+impl TranscriptProcessor for GeminiRewrite {
+    fn name(&self) -> &str {
+        "Gemini"
+    }
+
+    async fn process(&self, text: &str) -> EchoResult<String> {
+        info!("🤖 [Gemini] Rewriting committed text...");
+        Ok(gemini::rewrite_text(
+            &self.api_key,
+            &self.model,
+            &self.prompt_preset,
+            &self.custom_prompt,
+            &self.proxy_url,
+            text,
+        )
+        .await)
+    }
+}
+
+struct LlmPostProcess(LlmConfig);
+
+#[async_trait]
+impl TranscriptProcessor for LlmPostProcess {
+    fn name(&self) -> &str {
+        "LLM"
+    }
+
+    fn reports_changes(&self) -> bool {
+        true
+    }
+
+    async fn process(&self, text: &str) -> EchoResult<String> {
+        info!("🤖 [LLM] Post-processing {} chars with {}", text.len(), self.0.model);
+        llm::post_process(&self.0, text).await.map_err(EchoError::network)
+    }
+}
+
+struct NumberFormatting(NumberLocale);
+
+#[async_trait]
+impl TranscriptProcessor for NumberFormatting {
+    fn name(&self) -> &str {
+        "Numbers"
+    }
+
+    async fn process(&self, text: &str) -> EchoResult<String> {
+        Ok(numbers::format_numbers(text, self.0))
+    }
+}
+
+struct DictationStyle(FormatterChain);
+
+#[async_trait]
+impl TranscriptProcessor for DictationStyle {
+    fn name(&self) -> &str {
+        "Style"
+    }
+
+    fn verbatim_output(&self) -> bool {
+        self.0.is_verbatim()
+    }
+
+    async fn process(&self, text: &str) -> EchoResult<String> {
+        Ok(self.0.apply(text))
+    }
+}
+
+struct Replacements(Arc<CompiledRules>);
+
+#[async_trait]
+impl TranscriptProcessor for Replacements {
+    fn name(&self) -> &str {
+        "Replacements"
+    }
+
+    async fn process(&self, text: &str) -> EchoResult<String> {
+        Ok(self.0.apply(text))
+    }
+}
+
+struct Translation(TranslationConfig);
+
+#[async_trait]
+impl TranscriptProcessor for Translation {
+    fn name(&self) -> &str {
+        "Translation"
+    }
+
+    fn reports_changes(&self) -> bool {
+        true
+    }
+
+    async fn process(&self, text: &str) -> EchoResult<String> {
+        translate::translate(&self.0, text).await.map_err(EchoError::network)
+    }
+}
+
+struct WasmProcessor(WasmPlugin);
+
+#[async_trait]
+impl TranscriptProcessor for WasmProcessor {
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    fn reports_changes(&self) -> bool {
+        true
+    }
+
+    async fn process(&self, text: &str) -> EchoResult<String> {
+        let plugin = self.0.clone();
+        let text = text.to_string();
+        tokio::task::spawn_blocking(move || plugin.process(&text))
+            .await
+            .map_err(|e| EchoError::config(e.to_string()))?
+    }
+}
+
+/// Pipes the text through a user-supplied program. The program gets the
+/// text on stdin and its stdout, minus the trailing newline, replaces it.
+struct ExternalProcess {
+    command: String,
+}
+
+#[async_trait]
+impl TranscriptProcessor for ExternalProcess {
+    fn name(&self) -> &str {
+        &self.command
+    }
+
+    fn reports_changes(&self) -> bool {
+        true
+    }
+
+    async fn process(&self, text: &str) -> EchoResult<String> {
+        let args = split_command_line(&self.command).map_err(EchoError::config)?;
+        let Some((program, args)) = args.split_first() else {
+            return Err(EchoError::config("No program given"));
+        };
+        let mut command = tokio::process::Command::new(program);
+        command
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        #[cfg(windows)]
+        {
+            // CREATE_NO_WINDOW: don't flash a console for every commit.
+            command.creation_flags(0x0800_0000);
+        }
+        let mut child = command
+            .spawn()
+            .map_err(|e| EchoError::config(format!("Could not start {}: {}", program, e)))?;
+        let stdin = child.stdin.take();
+        // Writing and reading together, under one deadline: a program that
+        // stops reading can't hang the write, and one that answers before
+        // reading everything can't fill its stdout pipe while we still write.
+        let write = async move {
+            if let Some(mut stdin) = stdin {
+                stdin.write_all(text.as_bytes()).await?;
+            }
+            Ok::<_, std::io::Error>(())
+        };
+        let (written, output) = tokio::time::timeout(EXTERNAL_TIMEOUT, async { tokio::join!(write, child.wait_with_output()) })
+            .await
+            .map_err(|_| EchoError::config(format!("{} took longer than {}s", program, EXTERNAL_TIMEOUT.as_secs())))?;
+        written.map_err(|e| EchoError::config(format!("Could not write to {}: {}", program, e)))?;
+        let output = output.map_err(|e| EchoError::config(format!("{} failed: {}", program, e)))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(EchoError::config(format!("{} exited with {}: {}", program, output.status, stderr.trim())));
+        }
+        let stdout = String::from_utf8(output.stdout)
+            .map_err(|_| EchoError::config(format!("{} wrote text that is not UTF-8", program)))?;
+        Ok(stdout.strip_suffix('\n').map_or(stdout.as_str(), |s| s.strip_suffix('\r').unwrap_or(s)).to_string())
+    }
+}
+
+/// Splits a command line on whitespace, keeping double-quoted parts (paths
+/// with spaces) together.
+fn split_command_line(line: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_arg = false;
+    for c in line.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_arg = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_arg {
+                    args.push(std::mem::take(&mut current));
+                    has_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_arg = true;
+            }
+        }
+    }
+    if in_quotes {
+        return Err("Unclosed quote".to_string());
+    }
+    if has_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+/// Parses the settings text box, one step per line in the order they run:
+/// a built-in step name or `exec: <command line>`.
+pub fn parse_steps(text: &str) -> Result<Vec<ProcessorStep>, String> {
+    let builtins = ProcessorStep::defaults();
+    let mut steps: Vec<ProcessorStep> = Vec::new();
+    for (index, raw_line) in text.lines().enumerate() {
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let step = match line.split_once(':') {
+            Some((keyword, command)) if keyword.trim().eq_ignore_ascii_case("exec") => {
+                let command = command.trim();
+                match split_command_line(command) {
+                    Ok(args) if args.is_empty() => return Err(format!("Line {}: no program given", index + 1)),
+                    Ok(_) => ProcessorStep::External(command.to_string()),
+                    Err(e) => return Err(format!("Line {}: {}", index + 1, e)),
+                }
+            }
+            _ => builtins
+                .iter()
+                .find(|step| step.keyword().eq_ignore_ascii_case(line))
+                .cloned()
+                .ok_or_else(|| {
+                    let known: Vec<&str> = builtins.iter().map(ProcessorStep::keyword).collect();
+                    format!(
+                        "Line {}: unknown step \"{}\" (expected one of {} or \"exec: <command>\")",
+                        index + 1,
+                        line,
+                        known.join(", ")
+                    )
+                })?,
+        };
+        if !matches!(step, ProcessorStep::External(_)) && steps.contains(&step) {
+            return Err(format!("Line {}: \"{}\" is listed more than once", index + 1, step.keyword()));
+        }
+        steps.push(step);
+    }
+    Ok(steps)
+}
+
+pub fn format_steps(steps: &[ProcessorStep]) -> String {
+    steps
+        .iter()
+        .map(|step| match step {
+            ProcessorStep::External(command) => format!("exec: {}", command),
+            step => step.keyword().to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_steps, parse_steps, split_command_line, ProcessorPipeline, ProcessorStep};
+    use crate::formatters::FormatterKind;
+    use crate::pipeline::TranscriptPipeline;
+    use crate::replacements::ReplacementRule;
+    use crate::settings::AppSettings;
+    use tokio::sync::mpsc;
+
+    fn rule(pattern: &str, replacement: &str) -> ReplacementRule {
+        ReplacementRule {
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+            is_regex: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn steps_run_in_the_configured_order() {
+        // Replacements see the digits only when numbers run first.
+        let mut settings = AppSettings {
+            format_numbers: true,
+            number_locale: "en-US".to_string(),
+            replacement_rules: vec![rule("42", "the answer")],
+            ..Default::default()
+        };
+        let (log_tx, _log_rx) = mpsc::unbounded_channel();
+
+        settings.processors = vec![ProcessorStep::Numbers, ProcessorStep::Replacements];
+        let pipeline = ProcessorPipeline::from_settings(&settings);
+        assert_eq!(pipeline.run("it is forty two".to_string(), &log_tx).await, "it is the answer");
+
+        settings.processors = vec![ProcessorStep::Replacements, ProcessorStep::Numbers];
+        let pipeline = ProcessorPipeline::from_settings(&settings);
+        assert_eq!(pipeline.run("it is forty two".to_string(), &log_tx).await, "it is 42");
+    }
+
+    #[tokio::test]
+    async fn identifier_styles_reach_the_transcript_unchanged() {
+        let settings = AppSettings {
+            formatters: vec![FormatterKind::SnakeCase],
+            ..Default::default()
+        };
+        let (log_tx, _log_rx) = mpsc::unbounded_channel();
+        let pipeline = ProcessorPipeline::from_settings(&settings);
+        assert!(pipeline.output_is_verbatim());
+        let text = pipeline.run("get user name".to_string(), &log_tx).await;
+
+        let mut transcript = TranscriptPipeline::new();
+        transcript.push_verbatim_segment(&text, None);
+        transcript.push_verbatim_segment(&pipeline.run("set user name".to_string(), &log_tx).await, None);
+        assert_eq!(transcript.committed_text(), "get_user_name set_user_name ");
+        assert!(!ProcessorPipeline::from_settings(&AppSettings::default()).output_is_verbatim());
+    }
+
+    #[test]
+    fn disabled_features_are_skipped() {
+        let settings = AppSettings::default();
+        assert!(ProcessorPipeline::from_settings(&settings).processors.is_empty());
+    }
+
+    #[tokio::test]
+    async fn failing_external_steps_keep_the_text() {
+        let settings = AppSettings {
+            processors: vec![ProcessorStep::External("eleventh-echo-no-such-program".to_string())],
+            ..Default::default()
+        };
+        let (log_tx, mut log_rx) = mpsc::unbounded_channel();
+        let pipeline = ProcessorPipeline::from_settings(&settings);
+        assert_eq!(pipeline.run("keep me".to_string(), &log_tx).await, "keep me");
+        assert!(log_rx.try_recv().unwrap().contains("keeping the text"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn external_steps_read_and_write_large_text() {
+        use super::{ExternalProcess, TranscriptProcessor};
+
+        // More than a pipe buffer each way; `cat` echoes while it reads.
+        let text = "word ".repeat(200_000);
+        let step = ExternalProcess { command: "cat".to_string() };
+        assert_eq!(step.process(&text).await.unwrap(), text);
+    }
+
+    #[test]
+    fn steps_roundtrip_through_text() {
+        let text = "# order\nReplacements\nexec: \"C:\\Tools\\fix up.exe\" --strict\n\nllm";
+        let steps = parse_steps(text).unwrap();
+        assert_eq!(
+            steps,
+            vec![
+                ProcessorStep::Replacements,
+                ProcessorStep::External("\"C:\\Tools\\fix up.exe\" --strict".to_string()),
+                ProcessorStep::Llm,
+            ]
+        );
+        assert_eq!(parse_steps(&format_steps(&steps)).unwrap(), steps);
+        assert_eq!(parse_steps(&format_steps(&ProcessorStep::defaults())).unwrap(), ProcessorStep::defaults());
+        assert!(parse_steps("numbers\nNumbers").unwrap_err().starts_with("Line 2"));
+        assert!(parse_steps("profanity").unwrap_err().contains("unknown step"));
+        assert!(parse_steps("exec:   ").unwrap_err().contains("no program"));
+    }
+
+    #[test]
+    fn command_lines_keep_quoted_paths_together() {
+        assert_eq!(
+            split_command_line(r#""C:\My Tools\fix.exe" --mode "a b" x"#).unwrap(),
+            vec![r"C:\My Tools\fix.exe", "--mode", "a b", "x"]
+        );
+        assert_eq!(split_command_line(r#"tool """#).unwrap(), vec!["tool", ""]);
+        assert!(split_command_line(r#"tool "open"#).is_err());
+    }
+}
@@ -0,0 +1,758 @@
+use crate::audio::{AudioEncoding, AudioFormat};
+use crate::azure_speech::AzureSpeechClient;
+use crate::error::{EchoError, EchoResult};
+use crate::google_speech::GoogleSpeechClient;
+use crate::i18n::{self, tr};
+use crate::mock_provider::MockProvider;
+use crate::session_trace::{load_trace, AudioReplay, ReplayProvider, TraceRecorder};
+use crate::network::{ControlMessage, ElevenLabsClient, TranscriptEvent};
+use crate::openai_whisper::OpenAiWhisperClient;
+use crate::provider_race::ProviderRace;
+use crate::proxy::{self, ProxyConfig};
+use crate::settings::{self, AppSettings};
+use crate::vosk_models::{self, VoskModel};
+use crate::vosk_speech::VoskClient;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+use tracing::info;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc::{Receiver, Sender, UnboundedReceiver, UnboundedSender};
+use tokio_tungstenite::tungstenite::handshake::client::{Request, Response};
+use tokio_tungstenite::{client_async_tls, connect_async, MaybeTlsStream, WebSocketStream};
+
+/// Waits between WebSocket connection attempts; one retry per entry.
+const CONNECT_RETRY_DELAYS: [Duration; 3] = [
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_secs(2),
+];
+
+/// A speech-to-text backend.
+///
+/// `run` consumes mono PCM chunks at `audio_format().sample_rate` from
+/// `audio_rx` until it receives
+/// `ControlMessage::Stop` (or the audio channel closes), forwarding results on
+/// `text_tx` and human-readable protocol lines on `log_tx`. After a stop the
+/// provider sends exactly one final `TranscriptEvent::Committed`;
+/// `ControlMessage::Cancel` returns at once and sends nothing more.
+#[async_trait]
+pub trait SpeechProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn capabilities(&self) -> ProviderCapabilities;
+
+    /// The format negotiated for this session; capture resamples to it.
+    fn audio_format(&self) -> AudioFormat {
+        AudioFormat::default()
+    }
+
+    async fn run(
+        &self,
+        audio_rx: Receiver<Vec<i16>>,
+        control_rx: UnboundedReceiver<ControlMessage>,
+        text_tx: Sender<TranscriptEvent>,
+        log_tx: UnboundedSender<String>,
+    ) -> EchoResult<()>;
+}
+
+/// Language codes offered for providers that take one; the services accept
+/// many more, these are the ones the settings hint lists.
+const COMMON_LANGUAGES: &[&str] = &[
+    "en-US", "en-GB", "de-DE", "fr-FR", "es-ES", "it-IT", "pt-BR", "nl-NL", "pl-PL", "ja-JP", "zh-CN",
+];
+
+/// Every PCM rate offered in settings.
+const ALL_SAMPLE_RATES: &[u32] = &[8000, 16000, 22050, 24000, 44100, 48000];
+/// Narrowband and wideband, for services tuned to speech models.
+const SPEECH_SAMPLE_RATES: &[u32] = &[8000, 16000];
+
+/// What a provider delivers, so the UI can grey out settings it would
+/// otherwise ignore.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProviderCapabilities {
+    /// Live partial results while speaking.
+    pub partials: bool,
+    /// Word or phrase start and end times.
+    pub timestamps: bool,
+    /// Speaker ids on words.
+    pub diarization: bool,
+    /// Codes accepted in `speech_language`; empty when the provider detects
+    /// the language or the model fixes it.
+    pub languages: &'static [&'static str],
+    /// Input sample rates the service accepts, in Hz, ascending.
+    pub sample_rates: &'static [u32],
+    /// Whether 8kHz μ-law input is accepted.
+    pub mu_law: bool,
+}
+
+impl ProviderCapabilities {
+    /// Test providers replay whatever they were given.
+    pub const FULL: ProviderCapabilities = ProviderCapabilities {
+        partials: true,
+        timestamps: true,
+        diarization: true,
+        languages: COMMON_LANGUAGES,
+        sample_rates: ALL_SAMPLE_RATES,
+        mu_law: true,
+    };
+
+    pub fn supports_language_choice(&self) -> bool {
+        !self.languages.is_empty()
+    }
+
+    pub fn max_sample_rate(&self) -> u32 {
+        self.sample_rates.last().copied().unwrap_or(AudioFormat::PCM_16K.sample_rate)
+    }
+
+    /// The closest format to `requested` this provider accepts: μ-law only
+    /// where supported, otherwise PCM at the highest rate not above the one
+    /// asked for (or the lowest rate the provider has).
+    pub fn negotiate(&self, requested: AudioFormat) -> AudioFormat {
+        if requested.encoding == AudioEncoding::MuLaw && self.mu_law {
+            return AudioFormat::MU_LAW_8K;
+        }
+        let rate = self
+            .sample_rates
+            .iter()
+            .rev()
+            .find(|&&rate| rate <= requested.sample_rate)
+            .or(self.sample_rates.first())
+            .copied()
+            .unwrap_or(AudioFormat::PCM_16K.sample_rate);
+        AudioFormat::pcm(rate)
+    }
+
+    /// One line for the settings page, e.g. "Live partials · Timestamps · up to 16 kHz".
+    pub fn summary(&self) -> String {
+        let mut parts = vec![if self.partials { tr!("Live partials") } else { tr!("No live preview") }];
+        if self.timestamps {
+            parts.push(tr!("Timestamps"));
+        }
+        if self.diarization {
+            parts.push(tr!("Speaker labels"));
+        }
+        if !self.supports_language_choice() {
+            parts.push(tr!("Language detected automatically"));
+        }
+        parts.push(tr!("up to {} kHz", self.max_sample_rate() as f64 / 1000.0));
+        parts.join(" · ")
+    }
+}
+
+/// Which speech-to-text backend a session uses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderKind {
+    #[default]
+    ElevenLabs,
+    Azure,
+    Google,
+    /// Batch upload after the session ends; no live partials.
+    OpenAiWhisper,
+    /// On-device Vosk model; audio never leaves the machine.
+    Vosk,
+}
+
+impl ProviderKind {
+    pub const ALL: [ProviderKind; 5] = [
+        ProviderKind::ElevenLabs,
+        ProviderKind::Azure,
+        ProviderKind::Google,
+        ProviderKind::OpenAiWhisper,
+        ProviderKind::Vosk,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ProviderKind::ElevenLabs => "ElevenLabs",
+            ProviderKind::Azure => "Azure Speech",
+            ProviderKind::Google => "Google Cloud Speech (batch)",
+            ProviderKind::OpenAiWhisper => "OpenAI Whisper (batch)",
+            ProviderKind::Vosk => "Vosk (offline)",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| i18n::is_label(kind.label(), label))
+    }
+
+    pub fn capabilities(self) -> ProviderCapabilities {
+        match self {
+            ProviderKind::ElevenLabs => ProviderCapabilities {
+                partials: true,
+                timestamps: true,
+                diarization: true,
+                languages: &[],
+                sample_rates: ALL_SAMPLE_RATES,
+                mu_law: true,
+            },
+            ProviderKind::Azure => ProviderCapabilities {
+                partials: true,
+                timestamps: true,
+                diarization: false,
+                languages: COMMON_LANGUAGES,
+                sample_rates: SPEECH_SAMPLE_RATES,
+                mu_law: false,
+            },
+            ProviderKind::Google => ProviderCapabilities {
+                partials: false,
+                timestamps: true,
+                diarization: false,
+                languages: COMMON_LANGUAGES,
+                sample_rates: ALL_SAMPLE_RATES,
+                mu_law: true,
+            },
+            ProviderKind::OpenAiWhisper => ProviderCapabilities {
+                partials: false,
+                timestamps: true,
+                diarization: false,
+                languages: COMMON_LANGUAGES,
+                sample_rates: ALL_SAMPLE_RATES,
+                mu_law: false,
+            },
+            ProviderKind::Vosk => ProviderCapabilities {
+                partials: true,
+                timestamps: true,
+                diarization: false,
+                languages: &[],
+                sample_rates: SPEECH_SAMPLE_RATES,
+                mu_law: false,
+            },
+        }
+    }
+}
+
+/// Builds the provider selected in `settings`, or explains which credential
+/// is missing. With `race_provider` it races against that provider in a
+/// `ProviderRace`, and with `record_session_trace` it is wrapped in a
+/// `TraceRecorder`.
+pub fn create_provider(
+    settings: &AppSettings,
+    eleven_model_id: &str,
+) -> EchoResult<Box<dyn SpeechProvider>> {
+    let stamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+    let provider = match settings.race_provider {
+        Some(kind) => {
+            let report = settings::recordings_dir().join(format!("session_{}.race.md", stamp));
+            race_providers(settings, eleven_model_id, kind, report)?
+        }
+        None => select_provider(settings, eleven_model_id)?,
+    };
+    if !settings.record_session_trace {
+        return Ok(provider);
+    }
+    let path = settings::recordings_dir().join(format!("session_{}.trace.jsonl", stamp));
+    Ok(Box::new(TraceRecorder::new(provider, path)))
+}
+
+/// The selected provider raced against `kind`. Both are sent the same audio,
+/// so they are built for a format each of them accepts.
+fn race_providers(
+    settings: &AppSettings,
+    eleven_model_id: &str,
+    kind: ProviderKind,
+    report: std::path::PathBuf,
+) -> EchoResult<Box<dyn SpeechProvider>> {
+    let format = kind
+        .capabilities()
+        .negotiate(settings.provider.capabilities().negotiate(settings.audio_format));
+    if settings.provider.capabilities().negotiate(format) != format {
+        return Err(EchoError::config(format!(
+            "{} and {} have no audio format in common",
+            settings.provider.label(),
+            kind.label()
+        )));
+    }
+    let race_model_id = match settings.race_eleven_model_id.trim() {
+        "" => eleven_model_id,
+        model => model,
+    };
+    let label = |kind: ProviderKind, model: &str| match kind {
+        ProviderKind::ElevenLabs => format!("{} ({})", kind.label(), model),
+        _ => kind.label().to_string(),
+    };
+    let a = select_provider(
+        &AppSettings {
+            audio_format: format,
+            ..settings.clone()
+        },
+        eleven_model_id,
+    )?;
+    let b = select_provider(
+        &AppSettings {
+            provider: kind,
+            audio_format: format,
+            ..settings.clone()
+        },
+        race_model_id,
+    )?;
+    let labels = [label(settings.provider, eleven_model_id), label(kind, race_model_id)];
+    Ok(Box::new(ProviderRace::new(a, b, labels, report)))
+}
+
+fn select_provider(
+    settings: &AppSettings,
+    eleven_model_id: &str,
+) -> EchoResult<Box<dyn SpeechProvider>> {
+    fn required(value: &str, what: &str) -> EchoResult<String> {
+        let value = value.trim();
+        if value.is_empty() {
+            Err(EchoError::config(format!("Missing {}", what)))
+        } else {
+            Ok(value.to_string())
+        }
+    }
+
+    let fixture = settings.mock_provider_fixture.trim();
+    if !fixture.is_empty() {
+        return Ok(Box::new(MockProvider::from_file(std::path::Path::new(fixture)).map_err(EchoError::Config)?));
+    }
+    let trace = settings.replay_session_trace.trim();
+    if !trace.is_empty() {
+        let entries = load_trace(std::path::Path::new(trace)).map_err(EchoError::Config)?;
+        if !settings.replay_trace_audio {
+            return Ok(Box::new(ReplayProvider::new(entries)));
+        }
+        let live = AppSettings {
+            replay_session_trace: String::new(),
+            ..settings.clone()
+        };
+        return Ok(Box::new(AudioReplay::new(select_provider(&live, eleven_model_id)?, entries)));
+    }
+
+    let proxy = proxy::resolve(&settings.proxy_url).map_err(EchoError::Config)?;
+    let format = settings.provider.capabilities().negotiate(settings.audio_format);
+    if format != settings.audio_format {
+        info!(
+            "🎚 {} does not accept {}, using {}",
+            settings.provider.label(),
+            settings.audio_format.label(),
+            format.label()
+        );
+    }
+    match settings.provider {
+        ProviderKind::ElevenLabs => Ok(Box::new(
+            ElevenLabsClient::new(
+                required(&settings.api_key, "API key")?,
+                eleven_model_id.to_string(),
+            )
+            .with_vocabulary(settings.custom_vocabulary.clone())
+            .with_diarization(settings.diarization_enabled)
+            .with_word_timestamps(settings.paragraph_pause_secs > 0.0)
+            .with_audio_format(format)
+            .with_proxy(proxy),
+        )),
+        ProviderKind::Azure => Ok(Box::new(
+            AzureSpeechClient::new(
+                required(&settings.azure_speech_key, "Azure Speech key")?,
+                required(&settings.azure_speech_region, "Azure Speech region")?,
+                settings.speech_language.trim().to_string(),
+            )
+            .with_audio_format(format)
+            .with_proxy(proxy),
+        )),
+        ProviderKind::Google => Ok(Box::new(
+            GoogleSpeechClient::new(
+                required(&settings.google_speech_api_key, "Google Cloud API key")?,
+                settings.speech_language.trim().to_string(),
+            )
+            .with_audio_format(format)
+            .with_proxy(proxy.as_ref())
+            .map_err(EchoError::Config)?,
+        )),
+        ProviderKind::OpenAiWhisper => Ok(Box::new(
+            OpenAiWhisperClient::new(
+                required(&settings.openai_api_key, "OpenAI API key")?,
+                settings.openai_transcription_model.trim().to_string(),
+                settings.speech_language.trim().to_string(),
+            )
+            .with_audio_format(format)
+            .with_proxy(proxy.as_ref())
+            .map_err(EchoError::Config)?,
+        )),
+        ProviderKind::Vosk => {
+            let dir = settings::vosk_models_dir();
+            let model = VoskModel::from_name(&settings.vosk_model).unwrap_or(vosk_models::CATALOG[0]);
+            Ok(Box::new(
+                VoskClient::new(vosk_models::runtime_library(&dir), model.path(&dir)).with_audio_format(format),
+            ))
+        }
+    }
+}
+
+/// The model the selected provider transcribes with, for the status bar.
+/// `None` for providers without a model choice and for mock, replayed or
+/// raced sessions.
+pub fn selected_model(settings: &AppSettings, eleven_model_id: &str) -> Option<String> {
+    if !settings.mock_provider_fixture.trim().is_empty()
+        || !settings.replay_session_trace.trim().is_empty()
+        || settings.race_provider.is_some()
+    {
+        return None;
+    }
+    let model = match settings.provider {
+        ProviderKind::ElevenLabs => eleven_model_id.trim(),
+        ProviderKind::OpenAiWhisper => settings.openai_transcription_model.trim(),
+        ProviderKind::Vosk => settings.vosk_model.trim(),
+        ProviderKind::Azure | ProviderKind::Google => return None,
+    };
+    (!model.is_empty()).then(|| model.to_string())
+}
+
+/// Why a provider could not open its connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectError {
+    /// The server answered with an HTTP error (bad key, quota, outage...).
+    Http { status: u16, reason: String },
+    /// DNS, TCP or TLS failure before any HTTP response.
+    Network(String),
+    /// The request could not be built (malformed key or URL).
+    InvalidRequest(String),
+}
+
+impl ConnectError {
+    /// Auth and request errors will fail the same way again; rate limits,
+    /// server errors and network blips may not.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ConnectError::Http { status, .. } => *status == 408 || *status == 429 || *status >= 500,
+            ConnectError::Network(_) => true,
+            ConnectError::InvalidRequest(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for ConnectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectError::Http { status, reason } => write!(f, "HTTP {}: {}", status, reason),
+            ConnectError::Network(msg) => write!(f, "Network error: {}", msg),
+            ConnectError::InvalidRequest(msg) => write!(f, "Invalid request: {}", msg),
+        }
+    }
+}
+
+impl Error for ConnectError {}
+
+impl From<ConnectError> for EchoError {
+    /// Rejected credentials and malformed requests are fixed in Settings;
+    /// everything else is a connection problem.
+    fn from(err: ConnectError) -> Self {
+        match err {
+            ConnectError::Http { status: 401 | 403, .. } | ConnectError::InvalidRequest(_) => {
+                EchoError::config(err.to_string())
+            }
+            _ => EchoError::network(err.to_string()),
+        }
+    }
+}
+
+impl From<tokio_tungstenite::tungstenite::Error> for ConnectError {
+    fn from(err: tokio_tungstenite::tungstenite::Error) -> Self {
+        use tokio_tungstenite::tungstenite::Error as WsError;
+        match err {
+            WsError::Http(response) => {
+                let status = response.status();
+                let body = response
+                    .body()
+                    .as_deref()
+                    .map(String::from_utf8_lossy)
+                    .map(|b| b.trim().chars().take(200).collect::<String>())
+                    .unwrap_or_default();
+                let reason = if body.is_empty() {
+                    status.canonical_reason().unwrap_or("Unknown").to_string()
+                } else {
+                    body
+                };
+                ConnectError::Http { status: status.as_u16(), reason }
+            }
+            WsError::Url(e) => ConnectError::InvalidRequest(e.to_string()),
+            WsError::HttpFormat(e) => ConnectError::InvalidRequest(e.to_string()),
+            other => ConnectError::Network(other.to_string()),
+        }
+    }
+}
+
+/// Runs `attempt` until it succeeds, fails with a non-retryable error, or
+/// every delay in `delays` has been used.
+pub async fn retry_with_backoff<T, F, Fut>(
+    delays: &[Duration],
+    log_tx: &UnboundedSender<String>,
+    mut attempt: F,
+) -> Result<T, ConnectError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, ConnectError>>,
+{
+    let mut delays = delays.iter();
+    loop {
+        match attempt().await {
+            Ok(value) => return Ok(value),
+            Err(err) => match delays.next() {
+                Some(delay) if err.is_retryable() => {
+                    emit_log(
+                        log_tx,
+                        format!("⚠️ Connect failed ({}), retrying in {}ms", err, delay.as_millis()),
+                    );
+                    tokio::time::sleep(*delay).await;
+                }
+                _ => {
+                    emit_log(log_tx, format!("❌ Connect failed: {}", err));
+                    return Err(err);
+                }
+            },
+        }
+    }
+}
+
+/// Opens a provider WebSocket with retries, tunneling through `proxy` unless
+/// the target host is exempt. `build_request` is called per attempt because
+/// handshake requests cannot be reused.
+pub async fn connect_websocket<B>(
+    log_tx: &UnboundedSender<String>,
+    proxy: Option<&ProxyConfig>,
+    build_request: B,
+) -> Result<(WebSocketStream<MaybeTlsStream<TcpStream>>, Response), ConnectError>
+where
+    B: Fn() -> Result<Request, ConnectError>,
+{
+    retry_with_backoff(&CONNECT_RETRY_DELAYS, log_tx, || {
+        let request = build_request();
+        async move {
+            let request = request?;
+            let host = request.uri().host().unwrap_or_default().to_string();
+            match proxy.filter(|p| !p.bypasses(&host)) {
+                Some(proxy) => {
+                    let port = request.uri().port_u16().unwrap_or(
+                        if request.uri().scheme_str() == Some("ws") { 80 } else { 443 },
+                    );
+                    emit_log(log_tx, format!("🌐 Tunneling {}:{} through {}", host, port, proxy));
+                    let stream = proxy::connect_via(proxy, &host, port).await?;
+                    Ok(client_async_tls(request, stream).await?)
+                }
+                None => Ok(connect_async(request).await?),
+            }
+        }
+    })
+    .await
+}
+
+/// Prints a provider log line and mirrors it to the in-app log.
+pub fn emit_log(log_tx: &UnboundedSender<String>, msg: String) {
+    info!("{}", msg);
+    let _ = log_tx.send(msg);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{create_provider, retry_with_backoff, selected_model, ConnectError, ProviderKind};
+    use crate::audio::AudioFormat;
+    use crate::error::EchoError;
+    use crate::settings::AppSettings;
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    #[test]
+    fn provider_labels_roundtrip() {
+        for kind in ProviderKind::ALL {
+            assert_eq!(ProviderKind::from_label(kind.label()), Some(kind));
+        }
+        assert_eq!(ProviderKind::from_label("Nope"), None);
+    }
+
+    #[test]
+    fn capabilities_describe_each_provider() {
+        let eleven = ProviderKind::ElevenLabs.capabilities();
+        assert!(eleven.diarization && !eleven.supports_language_choice());
+        assert!(!ProviderKind::Azure.capabilities().diarization);
+        assert!(ProviderKind::Azure.capabilities().languages.contains(&"de-DE"));
+        assert!(!ProviderKind::Vosk.capabilities().supports_language_choice());
+        assert_eq!(
+            ProviderKind::Google.capabilities().summary(),
+            "No live preview · Timestamps · up to 48 kHz"
+        );
+
+        let settings = AppSettings {
+            provider: ProviderKind::Vosk,
+            ..Default::default()
+        };
+        let provider = create_provider(&settings, "model").unwrap();
+        assert_eq!(provider.capabilities(), ProviderKind::Vosk.capabilities());
+    }
+
+    #[test]
+    fn audio_format_falls_back_to_what_the_provider_accepts() {
+        let eleven = ProviderKind::ElevenLabs.capabilities();
+        for format in AudioFormat::ALL {
+            assert_eq!(eleven.negotiate(format), format);
+        }
+        let vosk = ProviderKind::Vosk.capabilities();
+        assert_eq!(vosk.negotiate(AudioFormat::pcm(44100)), AudioFormat::PCM_16K);
+        assert_eq!(vosk.negotiate(AudioFormat::MU_LAW_8K), AudioFormat::pcm(8000));
+        assert_eq!(vosk.negotiate(AudioFormat::pcm(4000)), AudioFormat::pcm(8000));
+        assert_eq!(ProviderKind::Azure.capabilities().summary(), "Live partials · Timestamps · up to 16 kHz");
+
+        let settings = AppSettings {
+            provider: ProviderKind::Vosk,
+            audio_format: AudioFormat::pcm(48000),
+            ..Default::default()
+        };
+        assert_eq!(create_provider(&settings, "model").unwrap().audio_format(), AudioFormat::PCM_16K);
+    }
+
+    #[test]
+    fn create_provider_requires_credentials() {
+        let mut settings = AppSettings {
+            provider: ProviderKind::Azure,
+            azure_speech_key: "key".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            create_provider(&settings, "model").err(),
+            Some(EchoError::config("Missing Azure Speech region"))
+        );
+        settings.azure_speech_region = "westeurope".to_string();
+        assert_eq!(create_provider(&settings, "model").unwrap().name(), "Azure Speech");
+
+        settings.provider = ProviderKind::Google;
+        assert!(create_provider(&settings, "model").is_err());
+        settings.google_speech_api_key = "AIza".to_string();
+        assert_eq!(create_provider(&settings, "model").unwrap().name(), "Google Cloud Speech");
+
+        settings.provider = ProviderKind::OpenAiWhisper;
+        assert!(create_provider(&settings, "model").is_err());
+        settings.openai_api_key = "sk-openai".to_string();
+        assert_eq!(create_provider(&settings, "model").unwrap().name(), "OpenAI Whisper");
+
+        settings.provider = ProviderKind::Vosk;
+        assert_eq!(create_provider(&settings, "model").unwrap().name(), "Vosk");
+        settings.provider = ProviderKind::OpenAiWhisper;
+
+        settings.proxy_url = "ftp://proxy".to_string();
+        assert!(create_provider(&settings, "model").is_err());
+        settings.proxy_url = "socks5://127.0.0.1:1080".to_string();
+        assert!(create_provider(&settings, "model").is_ok());
+
+        settings.provider = ProviderKind::ElevenLabs;
+        assert_eq!(
+            create_provider(&settings, "model").err(),
+            Some(EchoError::config("Missing API key"))
+        );
+    }
+
+    #[test]
+    fn selected_model_follows_the_provider() {
+        let mut settings = AppSettings {
+            provider: ProviderKind::OpenAiWhisper,
+            openai_transcription_model: " whisper-1 ".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(selected_model(&settings, "scribe_v1"), Some("whisper-1".to_string()));
+        settings.provider = ProviderKind::ElevenLabs;
+        assert_eq!(selected_model(&settings, "scribe_v1"), Some("scribe_v1".to_string()));
+        settings.race_provider = Some(ProviderKind::Vosk);
+        assert_eq!(selected_model(&settings, "scribe_v1"), None);
+        settings.race_provider = None;
+        settings.provider = ProviderKind::Vosk;
+        settings.vosk_model = "vosk-model-small-de-0.15".to_string();
+        assert_eq!(selected_model(&settings, "scribe_v1"), Some("vosk-model-small-de-0.15".to_string()));
+        settings.provider = ProviderKind::Azure;
+        assert_eq!(selected_model(&settings, "scribe_v1"), None);
+    }
+
+    #[test]
+    fn mock_fixture_overrides_the_selected_provider() {
+        let settings = AppSettings {
+            mock_provider_fixture: "/nonexistent/mock.json".to_string(),
+            ..Default::default()
+        };
+        let err = create_provider(&settings, "model").err().unwrap();
+        assert!(matches!(&err, EchoError::Config(msg) if msg.starts_with("Failed to read mock fixture")), "{}", err);
+    }
+
+    #[test]
+    fn races_need_credentials_for_both_providers() {
+        let mut settings = AppSettings {
+            provider: ProviderKind::Vosk,
+            race_provider: Some(ProviderKind::OpenAiWhisper),
+            ..Default::default()
+        };
+        assert_eq!(
+            create_provider(&settings, "model").err(),
+            Some(EchoError::config("Missing OpenAI API key"))
+        );
+        settings.openai_api_key = "sk-openai".to_string();
+        let race = create_provider(&settings, "model").unwrap();
+        assert_eq!(race.name(), "Vosk");
+        assert_eq!(race.audio_format(), AudioFormat::PCM_16K);
+    }
+
+    #[test]
+    fn http_errors_map_to_status_and_reason() {
+        let response = http::Response::builder()
+            .status(401)
+            .body(Some(b"{\"detail\":\"invalid api key\"}".to_vec()))
+            .unwrap();
+        let err = ConnectError::from(tokio_tungstenite::tungstenite::Error::Http(response));
+        assert_eq!(err.to_string(), "HTTP 401: {\"detail\":\"invalid api key\"}");
+        assert!(!err.is_retryable());
+
+        let empty = http::Response::builder().status(503).body(None).unwrap();
+        let err = ConnectError::from(tokio_tungstenite::tungstenite::Error::Http(empty));
+        assert_eq!(err.to_string(), "HTTP 503: Service Unavailable");
+        assert!(err.is_retryable());
+    }
+
+    #[test]
+    fn connect_errors_map_to_echo_error_categories() {
+        let rejected = ConnectError::Http { status: 401, reason: "Unauthorized".into() };
+        assert!(matches!(EchoError::from(rejected), EchoError::Config(_)));
+        let unavailable = ConnectError::Http { status: 503, reason: "Service Unavailable".into() };
+        assert!(matches!(EchoError::from(unavailable), EchoError::Network(_)));
+        assert_eq!(
+            EchoError::from(ConnectError::Network("reset".into())),
+            EchoError::network("Network error: reset")
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_only_retryable_errors() {
+        let (log_tx, _log_rx) = mpsc::unbounded_channel();
+        let delays = [Duration::ZERO; 3];
+
+        let mut calls = 0;
+        let result = retry_with_backoff(&delays, &log_tx, || {
+            calls += 1;
+            let outcome = if calls < 3 {
+                Err(ConnectError::Network("reset".into()))
+            } else {
+                Ok(calls)
+            };
+            async move { outcome }
+        })
+        .await;
+        assert_eq!(result, Ok(3));
+
+        let mut calls = 0;
+        let result: Result<(), _> = retry_with_backoff(&delays, &log_tx, || {
+            calls += 1;
+            async { Err(ConnectError::Http { status: 401, reason: "Unauthorized".into() }) }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+
+        let mut calls = 0;
+        let result: Result<(), _> = retry_with_backoff(&delays, &log_tx, || {
+            calls += 1;
+            async { Err(ConnectError::Network("down".into())) }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls, 4);
+    }
+}
@@ -0,0 +1,458 @@
+// Benchmark mode: every session's audio also goes to a second provider, and
+// when the session ends a Markdown report compares what each one committed
+// and how long it took. Only the first provider's transcript reaches the
+// app; the second is recorded for the report and otherwise ignored.
+
+use crate::audio::AudioFormat;
+use crate::error::EchoResult;
+use crate::metrics::{format_elapsed, format_latency};
+use crate::network::{ControlMessage, TranscriptEvent};
+use crate::provider::{emit_log, ProviderCapabilities, SpeechProvider};
+use crate::transcript_diff;
+use async_trait::async_trait;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc::{self, error::TrySendError, Receiver, Sender, UnboundedReceiver, UnboundedSender};
+use tokio::time::Instant;
+
+/// Audio chunks queued for the second provider before it counts as behind
+/// and further chunks are dropped rather than holding up the first.
+const B_AUDIO_BACKLOG: usize = 500;
+
+/// How long the second provider gets to finish after the first has.
+const B_FINISH_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Side {
+    A,
+    B,
+}
+
+impl Side {
+    fn index(self) -> usize {
+        match self {
+            Side::A => 0,
+            Side::B => 1,
+        }
+    }
+
+    fn letter(self) -> &'static str {
+        match self {
+            Side::A => "A",
+            Side::B => "B",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct RaceSegment {
+    side: Side,
+    /// When it was committed, from the start of the session.
+    at: Duration,
+    latency: Option<Duration>,
+    text: String,
+}
+
+/// What both providers committed, and when the audio behind it was sent.
+#[derive(Debug)]
+struct RaceLog {
+    started: Instant,
+    sample_rate: u32,
+    /// Stream position at the end of each chunk sent, and when it was sent.
+    chunks: Vec<(f64, Instant)>,
+    samples_sent: u64,
+    segments: Vec<RaceSegment>,
+    errors: [Vec<String>; 2],
+    /// Samples the second provider never got because it fell behind.
+    dropped_samples: u64,
+    cancelled: bool,
+}
+
+impl RaceLog {
+    fn new(sample_rate: u32) -> Self {
+        Self {
+            started: Instant::now(),
+            sample_rate: sample_rate.max(1),
+            chunks: Vec::new(),
+            samples_sent: 0,
+            segments: Vec::new(),
+            errors: [Vec::new(), Vec::new()],
+            dropped_samples: 0,
+            cancelled: false,
+        }
+    }
+
+    fn audio_sent(&mut self, samples: usize) {
+        self.samples_sent += samples as u64;
+        self.chunks
+            .push((self.samples_sent as f64 / self.sample_rate as f64, Instant::now()));
+    }
+
+    /// When the audio up to `end_secs` was sent; without timestamps, when
+    /// the last chunk was, as `SessionMetrics` measures latency.
+    fn sent_at(&self, end_secs: Option<f64>) -> Option<Instant> {
+        let last = self.chunks.last().map(|(_, at)| *at);
+        let Some(end) = end_secs else {
+            return last;
+        };
+        let index = self.chunks.partition_point(|(chunk_end, _)| *chunk_end < end);
+        self.chunks.get(index).map(|(_, at)| *at).or(last)
+    }
+
+    fn record(&mut self, side: Side, event: &TranscriptEvent) {
+        let now = Instant::now();
+        match event {
+            TranscriptEvent::Committed(segment) if !segment.text.trim().is_empty() => {
+                let latency = self
+                    .sent_at(segment.end_secs)
+                    .map(|sent| now.saturating_duration_since(sent));
+                self.segments.push(RaceSegment {
+                    side,
+                    at: now.saturating_duration_since(self.started),
+                    latency,
+                    text: segment.text.trim().to_string(),
+                });
+            }
+            TranscriptEvent::Error(err) => self.errors[side.index()].push(err.to_string()),
+            _ => {}
+        }
+    }
+
+    fn text(&self, side: Side) -> String {
+        self.segments
+            .iter()
+            .filter(|segment| segment.side == side)
+            .map(|segment| segment.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn latencies(&self, side: Side) -> Vec<Duration> {
+        let mut latencies: Vec<Duration> = self
+            .segments
+            .iter()
+            .filter(|segment| segment.side == side)
+            .filter_map(|segment| segment.latency)
+            .collect();
+        latencies.sort();
+        latencies
+    }
+}
+
+/// The comparison written when a race ends. `labels` name A and B.
+fn render_report(labels: &[String; 2], log: &RaceLog) -> String {
+    let sides = [Side::A, Side::B];
+    let mut report = format!("# Provider race: {} vs {}\n\n", labels[0], labels[1]);
+    report.push_str(&format!("| | A: {} | B: {} |\n|---|---|---|\n", cell(&labels[0]), cell(&labels[1])));
+
+    let row = |name: &str, value: &dyn Fn(Side) -> String| {
+        format!("| {} | {} | {} |\n", name, value(Side::A), value(Side::B))
+    };
+    let latency_or_dash = |latency: Option<Duration>| latency.map(format_latency).unwrap_or_else(|| "–".to_string());
+    report.push_str(&row("Segments", &|side| {
+        log.segments.iter().filter(|segment| segment.side == side).count().to_string()
+    }));
+    report.push_str(&row("Median latency", &|side| {
+        let latencies = log.latencies(side);
+        latency_or_dash(latencies.get(latencies.len() / 2).copied())
+    }));
+    report.push_str(&row("Mean latency", &|side| {
+        let latencies = log.latencies(side);
+        let total: Duration = latencies.iter().sum();
+        latency_or_dash((!latencies.is_empty()).then(|| total / latencies.len() as u32))
+    }));
+    report.push_str(&row("Words", &|side| log.text(side).split_whitespace().count().to_string()));
+    report.push_str(&row("Errors", &|side| log.errors[side.index()].len().to_string()));
+
+    let diff = transcript_diff::diff_words(&log.text(Side::A), &log.text(Side::B));
+    report.push_str(&format!("\nB against A: {}\n", diff.summary()));
+    if log.dropped_samples > 0 {
+        report.push_str(&format!(
+            "\nB fell behind and missed {:.1} s of audio.\n",
+            log.dropped_samples as f64 / log.sample_rate as f64
+        ));
+    }
+
+    if log.errors.iter().any(|errors| !errors.is_empty()) {
+        report.push_str("\n## Errors\n\n");
+        for side in sides {
+            for error in &log.errors[side.index()] {
+                report.push_str(&format!("- {}: {}\n", side.letter(), error));
+            }
+        }
+    }
+
+    report.push_str("\n## Segments\n\n| Time | | Latency | Text |\n|---|---|---|---|\n");
+    for segment in &log.segments {
+        report.push_str(&format!(
+            "| {} | {} | {} | {} |\n",
+            format_elapsed(segment.at),
+            segment.side.letter(),
+            latency_or_dash(segment.latency),
+            cell(&segment.text)
+        ));
+    }
+    report
+}
+
+/// Keeps a value from breaking out of its table cell.
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+/// Runs `a` as the session's provider and streams the same audio to `b`,
+/// writing a comparison of the two to `report_path` once both are done.
+pub struct ProviderRace {
+    a: Box<dyn SpeechProvider>,
+    b: Arc<dyn SpeechProvider>,
+    labels: [String; 2],
+    report_path: PathBuf,
+}
+
+impl ProviderRace {
+    pub fn new(a: Box<dyn SpeechProvider>, b: Box<dyn SpeechProvider>, labels: [String; 2], report_path: PathBuf) -> Self {
+        Self {
+            a,
+            b: Arc::from(b),
+            labels,
+            report_path,
+        }
+    }
+}
+
+#[async_trait]
+impl SpeechProvider for ProviderRace {
+    fn name(&self) -> &'static str {
+        self.a.name()
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.a.capabilities()
+    }
+
+    fn audio_format(&self) -> AudioFormat {
+        self.a.audio_format()
+    }
+
+    async fn run(
+        &self,
+        mut audio_rx: Receiver<Vec<i16>>,
+        mut control_rx: UnboundedReceiver<ControlMessage>,
+        text_tx: Sender<TranscriptEvent>,
+        log_tx: UnboundedSender<String>,
+    ) -> EchoResult<()> {
+        emit_log(&log_tx, format!("🏁 Racing {} (A) against {} (B)", self.labels[0], self.labels[1]));
+        let race = Arc::new(Mutex::new(RaceLog::new(self.a.audio_format().sample_rate)));
+
+        let (a_audio_tx, a_audio_rx) = mpsc::channel::<Vec<i16>>(50);
+        let (a_control_tx, a_control_rx) = mpsc::unbounded_channel::<ControlMessage>();
+        let (a_text_tx, mut a_text_rx) = mpsc::channel::<TranscriptEvent>(100);
+        let (b_audio_tx, b_audio_rx) = mpsc::channel::<Vec<i16>>(B_AUDIO_BACKLOG);
+        let (b_control_tx, b_control_rx) = mpsc::unbounded_channel::<ControlMessage>();
+        let (b_text_tx, mut b_text_rx) = mpsc::channel::<TranscriptEvent>(100);
+        let (b_log_tx, mut b_log_rx) = mpsc::unbounded_channel::<String>();
+
+        let b = self.b.clone();
+        let b_race = race.clone();
+        let b_log_prefix = format!("[B: {}]", self.labels[1]);
+        let b_outer_log = log_tx.clone();
+        let b_task = tokio::spawn(async move {
+            let relay = async {
+                while let Some(event) = b_text_rx.recv().await {
+                    b_race.lock().unwrap().record(Side::B, &event);
+                }
+            };
+            let logs = async {
+                while let Some(line) = b_log_rx.recv().await {
+                    let _ = b_outer_log.send(format!("{} {}", b_log_prefix, line));
+                }
+            };
+            let (result, (), ()) = tokio::join!(b.run(b_audio_rx, b_control_rx, b_text_tx, b_log_tx), relay, logs);
+            result
+        });
+
+        let audio_race = race.clone();
+        let audio_task = tokio::spawn(async move {
+            while let Some(chunk) = audio_rx.recv().await {
+                audio_race.lock().unwrap().audio_sent(chunk.len());
+                if let Err(TrySendError::Full(dropped)) = b_audio_tx.try_send(chunk.clone()) {
+                    audio_race.lock().unwrap().dropped_samples += dropped.len() as u64;
+                }
+                if a_audio_tx.send(chunk).await.is_err() {
+                    break;
+                }
+            }
+        });
+        let control_race = race.clone();
+        let forward_to_b = b_control_tx.clone();
+        let control_task = tokio::spawn(async move {
+            while let Some(message) = control_rx.recv().await {
+                if message == ControlMessage::Cancel {
+                    control_race.lock().unwrap().cancelled = true;
+                }
+                let _ = forward_to_b.send(message.clone());
+                if a_control_tx.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+        let a_race = race.clone();
+        let a_relay = tokio::spawn(async move {
+            while let Some(event) = a_text_rx.recv().await {
+                a_race.lock().unwrap().record(Side::A, &event);
+                let _ = text_tx.send(event).await;
+            }
+        });
+
+        let result = self.a.run(a_audio_rx, a_control_rx, a_text_tx, log_tx.clone()).await;
+        let _ = a_relay.await;
+        audio_task.abort();
+        control_task.abort();
+        if let Err(e) = &result {
+            race.lock().unwrap().errors[Side::A.index()].push(e.to_string());
+        }
+
+        // A's session is over; B finishes in the background so the app
+        // doesn't wait on the slower provider.
+        let cancelled = race.lock().unwrap().cancelled;
+        let _ = b_control_tx.send(if cancelled { ControlMessage::Cancel } else { ControlMessage::Stop });
+        let labels = self.labels.clone();
+        let path = self.report_path.clone();
+        tokio::spawn(async move {
+            let abort = b_task.abort_handle();
+            match tokio::time::timeout(B_FINISH_TIMEOUT, b_task).await {
+                Ok(Ok(Err(e))) => race.lock().unwrap().errors[Side::B.index()].push(e.to_string()),
+                Ok(_) => {}
+                Err(_) => {
+                    abort.abort();
+                    race.lock().unwrap().errors[Side::B.index()]
+                        .push(format!("Did not finish within {} s of A", B_FINISH_TIMEOUT.as_secs()));
+                }
+            }
+            if cancelled {
+                return;
+            }
+            let report = render_report(&labels, &race.lock().unwrap());
+            let written = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|()| fs::write(&path, report));
+            match written {
+                Ok(()) => emit_log(&log_tx, format!("🏁 Race report saved to {}", path.display())),
+                Err(e) => emit_log(&log_tx, format!("❌ Failed to write race report {}: {}", path.display(), e)),
+            }
+        });
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render_report, ProviderRace, RaceLog, RaceSegment, Side};
+    use crate::mock_provider::{FixtureEvent, FixtureStep, MockProvider};
+    use crate::network::{ControlMessage, TranscriptEvent, TranscriptSegment};
+    use crate::provider::SpeechProvider;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+    use tokio::sync::mpsc;
+
+    #[test]
+    fn reports_compare_latency_and_words() {
+        let ms = Duration::from_millis;
+        let mut log = RaceLog::new(16_000);
+        log.audio_sent(16_000);
+        log.audio_sent(16_000);
+        let (first, second) = (log.chunks[0].1, log.chunks[1].1);
+        assert_eq!(log.sent_at(Some(0.4)), Some(first));
+        assert_eq!(log.sent_at(Some(1.5)), Some(second));
+        assert_eq!(log.sent_at(Some(9.0)), Some(second));
+        assert_eq!(log.sent_at(None), Some(second));
+
+        let segment = |side, secs, latency: Option<u64>, text: &str| RaceSegment {
+            side,
+            at: Duration::from_secs(secs),
+            latency: latency.map(ms),
+            text: text.to_string(),
+        };
+        log.segments = vec![
+            segment(Side::A, 1, Some(300), "Send the report"),
+            segment(Side::B, 2, Some(1500), "Send the reports"),
+            segment(Side::A, 3, Some(500), "to Anna | Bob"),
+            segment(Side::A, 4, Some(400), "today."),
+            segment(Side::B, 5, None, "to Anna | Bob today"),
+        ];
+        log.errors[1].push("rate_limited: slow down".to_string());
+        log.dropped_samples = 8_000;
+
+        let labels = ["ElevenLabs (scribe_v2)".to_string(), "OpenAI Whisper".to_string()];
+        let report = render_report(&labels, &log);
+        assert!(report.starts_with("# Provider race: ElevenLabs (scribe_v2) vs OpenAI Whisper\n"));
+        assert!(report.contains("| Segments | 3 | 2 |\n"), "{}", report);
+        assert!(report.contains("| Median latency | 400 ms | 1.5 s |\n"), "{}", report);
+        assert!(report.contains("| Mean latency | 400 ms | 1.5 s |\n"), "{}", report);
+        assert!(report.contains("| Words | 8 | 8 |\n"), "{}", report);
+        assert!(report.contains("| Errors | 0 | 1 |\n"), "{}", report);
+        assert!(report.contains("B against A: 1 of 8 words differ (WER 12.5%)\n"), "{}", report);
+        assert!(report.contains("missed 0.5 s of audio"));
+        assert!(report.contains("- B: rate_limited: slow down\n"));
+        assert!(report.contains("| 0:03 | A | 500 ms | to Anna \\| Bob |\n"), "{}", report);
+        assert!(report.contains("| 0:05 | B | – | to Anna \\| Bob today |\n"), "{}", report);
+    }
+
+    #[tokio::test]
+    async fn only_a_reaches_the_app_and_both_reach_the_report() {
+        let committed = |after_ms, text: &str| FixtureStep {
+            after_ms,
+            event: FixtureEvent::Committed {
+                text: text.to_string(),
+                confidence: None,
+            },
+        };
+        let a = MockProvider::new(vec![committed(0, "hello there")]);
+        let b = MockProvider::new(vec![committed(0, "hello their"), committed(60_000, "general kenobi")]);
+        let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        let path = std::env::temp_dir().join(format!("eleventh_echo_race_{}", stamp)).join("race.md");
+        let race = ProviderRace::new(
+            Box::new(a),
+            Box::new(b),
+            ["A model".to_string(), "B model".to_string()],
+            path.clone(),
+        );
+
+        let (audio_tx, audio_rx) = mpsc::channel(8);
+        let (control_tx, control_rx) = mpsc::unbounded_channel();
+        let (text_tx, mut text_rx) = mpsc::channel(100);
+        let (log_tx, _log_rx) = mpsc::unbounded_channel();
+        let feed = async move {
+            audio_tx.send(vec![0; 1600]).await.unwrap();
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            control_tx.send(ControlMessage::Stop).unwrap();
+        };
+        let (result, ()) = tokio::join!(race.run(audio_rx, control_rx, text_tx, log_tx), feed);
+        result.unwrap();
+
+        let mut texts = Vec::new();
+        while let Some(event) = text_rx.recv().await {
+            match event {
+                TranscriptEvent::Committed(TranscriptSegment { text, .. }) if !text.is_empty() => texts.push(text),
+                _ => {}
+            }
+        }
+        assert_eq!(texts, vec!["hello there"]);
+
+        let mut report = None;
+        for _ in 0..100 {
+            if let Ok(contents) = std::fs::read_to_string(&path) {
+                report = Some(contents);
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let report = report.expect("race report was not written");
+        assert!(report.contains("| Segments | 1 | 2 |\n"), "{}", report);
+        assert!(report.contains("| B | "));
+        assert!(report.contains("general kenobi"));
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
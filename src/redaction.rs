@@ -0,0 +1,298 @@
+// Optional privacy filter for transcripts. It runs on everything the session
+// shows, types or writes to disk, masking (or dropping) profanity, email
+// addresses, card numbers and any patterns the user adds.
+
+use crate::i18n;
+use crate::network::TranscriptEvent;
+use crate::settings::AppSettings;
+use regex::{Captures, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// What happens to a match.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RedactionMode {
+    /// Profanity keeps its first letter ("f***"); other matches become a
+    /// placeholder such as "[email]".
+    #[default]
+    Mask,
+    /// Matches are dropped from the text.
+    Remove,
+}
+
+impl RedactionMode {
+    pub const ALL: [RedactionMode; 2] = [RedactionMode::Mask, RedactionMode::Remove];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RedactionMode::Mask => "Mask",
+            RedactionMode::Remove => "Remove",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| i18n::is_label(mode.label(), label))
+    }
+}
+
+/// Word stems the profanity filter matches, whole-word and with common
+/// endings ("-s", "-ing", ...).
+const PROFANITY: [&str; 12] = [
+    "fuck", "motherfuck", "shit", "bullshit", "bitch", "bastard", "asshole", "cunt", "wanker", "bollocks",
+    "piss", "prick",
+];
+
+const EMAIL_PATTERN: &str = r"\b[\w.+-]+@[\w-]+(?:\.[\w-]+)+\b";
+/// 13 to 19 digits, optionally grouped with spaces or dashes; matches are
+/// only redacted when they pass the Luhn check.
+const CARD_PATTERN: &str = r"\b\d(?:[ -]?\d){12,18}\b";
+
+/// The compiled filter for one session.
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    mode: RedactionMode,
+    profanity: Option<Regex>,
+    sensitive: Option<(Regex, Regex)>,
+    custom: Vec<Regex>,
+}
+
+impl Redactor {
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        let profanity = settings.filter_profanity.then(profanity_regex);
+        let sensitive = settings.redact_sensitive_data.then(|| {
+            (
+                Regex::new(EMAIL_PATTERN).expect("email pattern"),
+                Regex::new(CARD_PATTERN).expect("card pattern"),
+            )
+        });
+        let custom = settings
+            .redaction_patterns
+            .iter()
+            .filter_map(|pattern| match Regex::new(pattern) {
+                Ok(re) => Some(re),
+                Err(e) => {
+                    warn!("⚠️ Skipping redaction pattern /{}/: {}", pattern, e);
+                    None
+                }
+            })
+            .collect();
+        Self {
+            mode: settings.redaction_mode,
+            profanity,
+            sensitive,
+            custom,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.profanity.is_some() || self.sensitive.is_some() || !self.custom.is_empty()
+    }
+
+    /// Returns `text` with every enabled category redacted.
+    pub fn apply(&self, text: &str) -> String {
+        if !self.is_active() {
+            return text.to_string();
+        }
+        let mut output = text.to_string();
+        if let Some((email, card)) = &self.sensitive {
+            output = email.replace_all(&output, self.placeholder("[email]")).into_owned();
+            output = card
+                .replace_all(&output, |caps: &Captures| {
+                    let digits: Vec<u32> = caps[0].chars().filter_map(|c| c.to_digit(10)).collect();
+                    if passes_luhn(&digits) {
+                        self.placeholder("[card]").to_string()
+                    } else {
+                        caps[0].to_string()
+                    }
+                })
+                .into_owned();
+        }
+        for re in &self.custom {
+            output = re.replace_all(&output, self.placeholder("[redacted]")).into_owned();
+        }
+        if let Some(re) = &self.profanity {
+            output = re
+                .replace_all(&output, |caps: &Captures| match self.mode {
+                    RedactionMode::Mask => mask_word(&caps[0]),
+                    RedactionMode::Remove => String::new(),
+                })
+                .into_owned();
+        }
+        match self.mode {
+            RedactionMode::Mask => output,
+            RedactionMode::Remove => tidy_spacing(&output, text),
+        }
+    }
+
+    /// Redacts the text, and each word's text, of a transcript event.
+    pub fn apply_event(&self, event: TranscriptEvent) -> TranscriptEvent {
+        if !self.is_active() {
+            return event;
+        }
+        let redact = |mut segment: crate::network::TranscriptSegment| {
+            segment.text = self.apply(&segment.text);
+            for word in &mut segment.words {
+                word.text = self.apply(&word.text);
+            }
+            segment
+        };
+        match event {
+            TranscriptEvent::Partial(segment) => TranscriptEvent::Partial(redact(segment)),
+            TranscriptEvent::Committed(segment) => TranscriptEvent::Committed(redact(segment)),
+            other => other,
+        }
+    }
+
+    fn placeholder(&self, mask: &'static str) -> &'static str {
+        match self.mode {
+            RedactionMode::Mask => mask,
+            RedactionMode::Remove => "",
+        }
+    }
+}
+
+fn profanity_regex() -> Regex {
+    let pattern = format!(r"\b(?:{})(?:s|es|ed|er|ers|ing|in|y)?\b", PROFANITY.join("|"));
+    RegexBuilder::new(&pattern)
+        .case_insensitive(true)
+        .build()
+        .expect("profanity pattern")
+}
+
+/// Keeps the first letter and stars out the rest.
+fn mask_word(word: &str) -> String {
+    let mut chars = word.chars();
+    let first = chars.next().map(String::from).unwrap_or_default();
+    first + &"*".repeat(chars.count())
+}
+
+fn passes_luhn(digits: &[u32]) -> bool {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(index, &digit)| match index % 2 {
+            0 => digit,
+            _ if digit * 2 > 9 => digit * 2 - 9,
+            _ => digit * 2,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// Closes the gaps left by removed matches: repeated spaces collapse and no
+/// space is left before punctuation. Leading/trailing whitespace is kept as
+/// it was in `original`.
+fn tidy_spacing(text: &str, original: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for c in text.trim().chars() {
+        if c == ' ' && output.ends_with(' ') {
+            continue;
+        }
+        if matches!(c, '.' | ',' | '!' | '?' | ';' | ':') && output.ends_with(' ') {
+            output.pop();
+        }
+        output.push(c);
+    }
+    let leading = &original[..original.len() - original.trim_start().len()];
+    let trailing = &original[original.trim_end().len()..];
+    format!("{}{}{}", leading, output, trailing)
+}
+
+/// Parses the settings text box, one regex per line; blank lines and `#`
+/// comments are ignored.
+pub fn parse_patterns(text: &str) -> Result<Vec<String>, String> {
+    let mut patterns = Vec::new();
+    for (index, raw_line) in text.lines().enumerate() {
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        Regex::new(line).map_err(|e| format!("Line {}: Invalid regex /{}/: {}", index + 1, line, e))?;
+        patterns.push(line.to_string());
+    }
+    Ok(patterns)
+}
+
+pub fn format_patterns(patterns: &[String]) -> String {
+    patterns.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_patterns, passes_luhn, RedactionMode, Redactor};
+    use crate::network::{TranscriptEvent, TranscriptSegment};
+    use crate::settings::AppSettings;
+
+    fn redactor(configure: impl FnOnce(&mut AppSettings)) -> Redactor {
+        let mut settings = AppSettings::default();
+        configure(&mut settings);
+        Redactor::from_settings(&settings)
+    }
+
+    #[test]
+    fn inactive_by_default() {
+        let r = redactor(|_| {});
+        assert!(!r.is_active());
+        assert_eq!(r.apply("well shit, mail me at a@b.io"), "well shit, mail me at a@b.io");
+    }
+
+    #[test]
+    fn profanity_is_masked_whole_word_and_case_insensitively() {
+        let r = redactor(|s| s.filter_profanity = true);
+        assert_eq!(r.apply("Shit, that's fucking great"), "S***, that's f****** great");
+        assert_eq!(r.apply("a shitake mushroom"), "a shitake mushroom");
+    }
+
+    #[test]
+    fn sensitive_data_uses_placeholders_and_the_luhn_check() {
+        let r = redactor(|s| s.redact_sensitive_data = true);
+        assert_eq!(
+            r.apply("Email jane.doe+news@example.co.uk with 4111 1111 1111 1111."),
+            "Email [email] with [card]."
+        );
+        // Long numbers that aren't card numbers are left alone.
+        assert_eq!(r.apply("order 1234567890123"), "order 1234567890123");
+        assert!(passes_luhn(&[7, 9, 9, 2, 7, 3, 9, 8, 7, 1, 3]));
+    }
+
+    #[test]
+    fn remove_mode_drops_matches_and_closes_the_gaps() {
+        let r = redactor(|s| {
+            s.filter_profanity = true;
+            s.redaction_patterns = vec![r"\bproject \w+".to_string()];
+            s.redaction_mode = RedactionMode::Remove;
+        });
+        assert_eq!(r.apply(" well shit , about project apollo. "), " well, about. ");
+    }
+
+    #[test]
+    fn events_are_redacted_down_to_word_text() {
+        let r = redactor(|s| s.filter_profanity = true);
+        let mut segment = TranscriptSegment::text("oh shit");
+        segment.words = vec![crate::network::WordTiming {
+            text: "shit".to_string(),
+            start_secs: 0.0,
+            end_secs: 0.4,
+            confidence: None,
+            speaker: None,
+        }];
+        let TranscriptEvent::Committed(redacted) = r.apply_event(TranscriptEvent::Committed(segment)) else {
+            panic!("event kind changed");
+        };
+        assert_eq!(redacted.text, "oh s***");
+        assert_eq!(redacted.words[0].text, "s***");
+        assert_eq!(r.apply_event(TranscriptEvent::EndOfStream), TranscriptEvent::EndOfStream);
+    }
+
+    #[test]
+    fn parse_patterns_skips_comments_and_reports_bad_lines() {
+        assert_eq!(
+            parse_patterns("# ids\n\nEMP-\\d+\n").unwrap(),
+            vec!["EMP-\\d+".to_string()]
+        );
+        let err = parse_patterns("ok\n(unclosed").unwrap_err();
+        assert!(err.starts_with("Line 2"));
+    }
+}
@@ -0,0 +1,228 @@
+use regex::{NoExpand, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+/// A single user-defined transcript fix-up.
+///
+/// Literal rules match case-insensitively on word boundaries and tolerate
+/// variable whitespace ("k eight s" also matches "K  eight s"). Regex rules
+/// are used verbatim and may reference capture groups (`$1`) in the replacement.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplacementRule {
+    pub pattern: String,
+    pub replacement: String,
+    pub is_regex: bool,
+}
+
+impl ReplacementRule {
+    fn compile(&self) -> Result<Regex, String> {
+        if self.is_regex {
+            return Regex::new(&self.pattern)
+                .map_err(|e| format!("Invalid regex /{}/: {}", self.pattern, e));
+        }
+
+        let words: Vec<String> = self
+            .pattern
+            .split_whitespace()
+            .map(regex::escape)
+            .collect();
+        if words.is_empty() {
+            return Err("Empty replacement pattern".to_string());
+        }
+        let starts_with_word = self.pattern.trim().starts_with(|c: char| c.is_alphanumeric());
+        let ends_with_word = self.pattern.trim().ends_with(|c: char| c.is_alphanumeric());
+        let pattern = format!(
+            "{}{}{}",
+            if starts_with_word { r"\b" } else { "" },
+            words.join(r"\s+"),
+            if ends_with_word { r"\b" } else { "" },
+        );
+        RegexBuilder::new(&pattern)
+            .case_insensitive(true)
+            .build()
+            .map_err(|e| format!("Invalid pattern \"{}\": {}", self.pattern, e))
+    }
+}
+
+/// A rule set with every pattern compiled up front. Rules that fail to
+/// compile are skipped.
+pub struct CompiledRules {
+    rules: Vec<(Regex, ReplacementRule)>,
+}
+
+impl CompiledRules {
+    pub fn new(rules: &[ReplacementRule]) -> Self {
+        let rules = rules
+            .iter()
+            .filter_map(|rule| match rule.compile() {
+                Ok(re) => Some((re, rule.clone())),
+                Err(e) => {
+                    warn!("⚠️ Skipping replacement rule: {}", e);
+                    None
+                }
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// Applies the rules in order.
+    pub fn apply(&self, text: &str) -> String {
+        let mut output = text.to_string();
+        for (re, rule) in &self.rules {
+            output = if rule.is_regex {
+                re.replace_all(&output, rule.replacement.as_str()).into_owned()
+            } else {
+                re.replace_all(&output, NoExpand(&rule.replacement)).into_owned()
+            };
+        }
+        output
+    }
+}
+
+/// `rules` compiled, reusing the last result while the rules stay the same,
+/// so they are compiled once per settings load rather than on every commit.
+pub fn compiled(rules: &[ReplacementRule]) -> Arc<CompiledRules> {
+    static LAST: Mutex<Option<(Vec<ReplacementRule>, Arc<CompiledRules>)>> = Mutex::new(None);
+    let mut last = LAST.lock().unwrap();
+    match last.as_ref() {
+        Some((source, compiled)) if source.as_slice() == rules => compiled.clone(),
+        _ => {
+            let compiled = Arc::new(CompiledRules::new(rules));
+            *last = Some((rules.to_vec(), compiled.clone()));
+            compiled
+        }
+    }
+}
+
+/// Parses the settings text format, one rule per line:
+///
+/// ```text
+/// k eight s => k8s
+/// /\bjon (\w+)\b/ => John $1
+/// # comments and blank lines are ignored
+/// ```
+pub fn parse_rules(text: &str) -> Result<Vec<ReplacementRule>, String> {
+    let mut rules = Vec::new();
+    for (index, raw_line) in text.lines().enumerate() {
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (pattern, replacement) = line
+            .split_once("=>")
+            .ok_or_else(|| format!("Line {}: expected \"pattern => replacement\"", index + 1))?;
+        let pattern = pattern.trim();
+        let replacement = replacement.trim();
+
+        let rule = if pattern.len() > 2 && pattern.starts_with('/') && pattern.ends_with('/') {
+            ReplacementRule {
+                pattern: pattern[1..pattern.len() - 1].to_string(),
+                replacement: replacement.to_string(),
+                is_regex: true,
+            }
+        } else {
+            ReplacementRule {
+                pattern: pattern.to_string(),
+                replacement: replacement.to_string(),
+                is_regex: false,
+            }
+        };
+        rule.compile()
+            .map_err(|e| format!("Line {}: {}", index + 1, e))?;
+        rules.push(rule);
+    }
+    Ok(rules)
+}
+
+/// Inverse of [`parse_rules`], used to populate the settings text box.
+pub fn format_rules(rules: &[ReplacementRule]) -> String {
+    rules
+        .iter()
+        .map(|rule| {
+            if rule.is_regex {
+                format!("/{}/ => {}", rule.pattern, rule.replacement)
+            } else {
+                format!("{} => {}", rule.pattern, rule.replacement)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compiled, format_rules, parse_rules, ReplacementRule};
+
+    fn literal(pattern: &str, replacement: &str) -> ReplacementRule {
+        ReplacementRule {
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+            is_regex: false,
+        }
+    }
+
+    #[test]
+    fn literal_rule_is_case_insensitive_and_whitespace_tolerant() {
+        let rules = vec![literal("k eight s", "k8s")];
+        assert_eq!(compiled(&rules).apply("Deploy to K  eight s today"), "Deploy to k8s today");
+    }
+
+    #[test]
+    fn literal_rule_respects_word_boundaries() {
+        let rules = vec![literal("jon", "Jon")];
+        assert_eq!(compiled(&rules).apply("jon jonathan"), "Jon jonathan");
+    }
+
+    #[test]
+    fn literal_replacement_does_not_expand_captures() {
+        let rules = vec![literal("price", "$1")];
+        assert_eq!(compiled(&rules).apply("the price"), "the $1");
+    }
+
+    #[test]
+    fn regex_rule_supports_capture_groups() {
+        let rules = vec![ReplacementRule {
+            pattern: r"(\d+) percent".to_string(),
+            replacement: "$1%".to_string(),
+            is_regex: true,
+        }];
+        assert_eq!(compiled(&rules).apply("up 20 percent"), "up 20%");
+    }
+
+    #[test]
+    fn compiled_rules_skip_invalid_patterns_and_follow_changes() {
+        let broken = ReplacementRule {
+            pattern: "(".to_string(),
+            replacement: "x".to_string(),
+            is_regex: true,
+        };
+        let rules = vec![literal("jon", "Jon"), broken];
+        assert_eq!(compiled(&rules).apply("jon ("), "Jon (");
+        assert_eq!(compiled(&[literal("jon", "John")]).apply("jon"), "John");
+    }
+
+    #[test]
+    fn parse_rules_reads_literal_and_regex_lines() {
+        let rules = parse_rules("# comment\n\nk eight s => k8s\n/foo(\\d)/ => bar$1\n").unwrap();
+        assert_eq!(rules.len(), 2);
+        assert!(!rules[0].is_regex);
+        assert_eq!(rules[0].pattern, "k eight s");
+        assert!(rules[1].is_regex);
+        assert_eq!(rules[1].pattern, "foo(\\d)");
+    }
+
+    #[test]
+    fn parse_rules_reports_bad_lines() {
+        let err = parse_rules("ok => fine\nmissing arrow").unwrap_err();
+        assert!(err.starts_with("Line 2"));
+        let err = parse_rules("/(unclosed/ => x").unwrap_err();
+        assert!(err.contains("Invalid regex"));
+    }
+
+    #[test]
+    fn format_rules_roundtrips() {
+        let text = "k eight s => k8s\n/a+/ => b";
+        assert_eq!(format_rules(&parse_rules(text).unwrap()), text);
+    }
+}
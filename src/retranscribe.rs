@@ -0,0 +1,202 @@
+// Runs a saved session recording through a provider again, typically a
+// slower batch model, so its transcript can be compared with the live one.
+// The audio is sent as fast as the provider accepts it and then stopped;
+// the committed segments are joined the way a live session joins them.
+
+use crate::audio::{self, ResamplerQuality};
+use crate::error::{EchoError, EchoResult};
+use crate::network::{ControlMessage, TranscriptEvent};
+use crate::pipeline::TranscriptPipeline;
+use crate::provider::SpeechProvider;
+use std::path::Path;
+use tokio::sync::mpsc;
+
+/// Transcribes the WAV at `path` with `client`.
+pub async fn transcribe_recording(
+    client: &dyn SpeechProvider,
+    path: &Path,
+    resampler: ResamplerQuality,
+    paragraph_pause_secs: f64,
+) -> EchoResult<String> {
+    let sample_rate = client.audio_format().sample_rate;
+    // Resampling an hour of audio takes a while; keep it off the runtime.
+    let owned = path.to_path_buf();
+    let samples = tokio::task::spawn_blocking(move || audio::read_recording(&owned, sample_rate, resampler))
+        .await
+        .map_err(|e| EchoError::audio(e.to_string()))??;
+    if samples.is_empty() {
+        return Err(EchoError::audio(format!("{} has no audio", path.display())));
+    }
+    transcribe_samples(client, samples, paragraph_pause_secs).await
+}
+
+/// Streams `samples` (mono PCM at the client's rate) in one-second chunks
+/// and collects the text committed until the provider closes.
+pub async fn transcribe_samples(
+    client: &dyn SpeechProvider,
+    samples: Vec<i16>,
+    paragraph_pause_secs: f64,
+) -> EchoResult<String> {
+    let chunk_len = client.audio_format().sample_rate.max(1) as usize;
+    let (audio_tx, audio_rx) = mpsc::channel::<Vec<i16>>(8);
+    let (control_tx, control_rx) = mpsc::unbounded_channel::<ControlMessage>();
+    let (text_tx, mut text_rx) = mpsc::channel::<TranscriptEvent>(100);
+    let (log_tx, _log_rx) = mpsc::unbounded_channel::<String>();
+
+    // Providers drop audio until the segment starts. Queued ahead of the
+    // first chunk so a provider that reads right away sees it first.
+    let _ = control_tx.send(ControlMessage::Start);
+    let feed = async move {
+        for chunk in samples.chunks(chunk_len) {
+            if audio_tx.send(chunk.to_vec()).await.is_err() {
+                return;
+            }
+        }
+        let _ = control_tx.send(ControlMessage::Stop);
+    };
+    let collect = async move {
+        let mut pipeline = TranscriptPipeline::with_paragraph_pause(paragraph_pause_secs);
+        let mut failure = None;
+        while let Some(event) = text_rx.recv().await {
+            match event {
+                TranscriptEvent::Committed(segment) if !segment.text.trim().is_empty() => {
+                    for part in segment.split_at_pauses(paragraph_pause_secs) {
+                        pipeline.push_segment(&part.text, part.start_secs.zip(part.end_secs));
+                    }
+                }
+                TranscriptEvent::Error(err) => failure = Some(err),
+                _ => {}
+            }
+        }
+        match failure {
+            Some(err) => Err(EchoError::network(err.to_string())),
+            None => Ok(pipeline.committed_text().trim().to_string()),
+        }
+    };
+    let (run, (), text) = tokio::join!(client.run(audio_rx, control_rx, text_tx, log_tx), feed, collect);
+    run?;
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::transcribe_samples;
+    use crate::error::EchoResult;
+    use crate::mock_provider::{FixtureEvent, FixtureStep, MockProvider};
+    use crate::network::{ControlMessage, ProviderError, TranscriptEvent, TranscriptSegment};
+    use crate::provider::{ProviderCapabilities, SpeechProvider};
+    use async_trait::async_trait;
+    use tokio::sync::mpsc::{Receiver, Sender, UnboundedReceiver, UnboundedSender};
+
+    fn step(event: FixtureEvent) -> FixtureStep {
+        FixtureStep { after_ms: 0, event }
+    }
+
+    /// Rejects the session as soon as it starts.
+    struct OutOfCredit;
+
+    #[async_trait]
+    impl SpeechProvider for OutOfCredit {
+        fn name(&self) -> &'static str {
+            "Out of credit"
+        }
+
+        fn capabilities(&self) -> ProviderCapabilities {
+            ProviderCapabilities::FULL
+        }
+
+        async fn run(
+            &self,
+            _audio_rx: Receiver<Vec<i16>>,
+            _control_rx: UnboundedReceiver<ControlMessage>,
+            text_tx: Sender<TranscriptEvent>,
+            _log_tx: UnboundedSender<String>,
+        ) -> EchoResult<()> {
+            let error = ProviderError::new("quota_exceeded", "out of credit");
+            let _ = text_tx.send(TranscriptEvent::Error(error)).await;
+            Ok(())
+        }
+    }
+
+    /// Buffers audio the way the Whisper client does, dropping anything sent
+    /// before Start, and commits how many samples it kept.
+    struct BatchCounter;
+
+    #[async_trait]
+    impl SpeechProvider for BatchCounter {
+        fn name(&self) -> &'static str {
+            "Batch counter"
+        }
+
+        fn capabilities(&self) -> ProviderCapabilities {
+            ProviderCapabilities::FULL
+        }
+
+        async fn run(
+            &self,
+            mut audio_rx: Receiver<Vec<i16>>,
+            mut control_rx: UnboundedReceiver<ControlMessage>,
+            text_tx: Sender<TranscriptEvent>,
+            _log_tx: UnboundedSender<String>,
+        ) -> EchoResult<()> {
+            let mut accepting_audio = false;
+            let mut heard = 0;
+            loop {
+                tokio::select! {
+                    Some(cmd) = control_rx.recv() => match cmd {
+                        ControlMessage::Start => accepting_audio = true,
+                        ControlMessage::Stop => break,
+                        ControlMessage::Cancel => return Ok(()),
+                    },
+                    maybe_chunk = audio_rx.recv() => match maybe_chunk {
+                        Some(chunk) if accepting_audio => heard += chunk.len(),
+                        Some(_) => {}
+                        None => break,
+                    },
+                }
+            }
+            while let Ok(chunk) = audio_rx.try_recv() {
+                heard += chunk.len();
+            }
+            let text = format!("heard {} samples", heard);
+            let _ = text_tx.send(TranscriptEvent::Committed(TranscriptSegment::text(text))).await;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn committed_segments_become_one_transcript() {
+        let provider = MockProvider::new(vec![
+            step(FixtureEvent::Partial { text: "hel".to_string() }),
+            step(FixtureEvent::Committed {
+                text: "hello there.".to_string(),
+                confidence: None,
+            }),
+            // Still scheduled when the audio ends, so it arrives as the final commit.
+            FixtureStep {
+                after_ms: 60_000,
+                event: FixtureEvent::Committed {
+                    text: "general kenobi".to_string(),
+                    confidence: None,
+                },
+            },
+        ]);
+        let text = transcribe_samples(&provider, vec![0; 40_000], 0.0).await.unwrap();
+        assert_eq!(text, "Hello there. General kenobi");
+    }
+
+    #[tokio::test]
+    async fn audio_before_stop_reaches_a_batch_provider() {
+        // Far more audio than the channel holds, so a missing Start would
+        // leave only the tail.
+        let text = transcribe_samples(&BatchCounter, vec![0; 1_600_000], 0.0).await.unwrap();
+        assert_eq!(text, "Heard 1600000 samples");
+    }
+
+    #[tokio::test]
+    async fn provider_errors_fail_the_transcription() {
+        // Far more audio than the channel holds, none of it read.
+        let err = transcribe_samples(&OutOfCredit, vec![0; 1_000_000], 0.0).await.unwrap_err();
+        assert_eq!(err.to_string(), "quota_exceeded: out of credit");
+    }
+}
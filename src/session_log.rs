@@ -0,0 +1,174 @@
+// Structured record of one dictation session for export: what was captured,
+// every event the provider sent, where the transcript went and how the
+// session moved through its states. Unlike a session trace it holds no
+// audio, so it stays small enough to attach to a bug report.
+
+use crate::audio::{AudioEncoding, AudioFormat};
+use crate::network::TranscriptEvent;
+use chrono::Local;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AudioInfo {
+    pub format: &'static str,
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// None means the system default microphone.
+    pub device: Option<String>,
+    pub seconds: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SessionEvent {
+    State { state: String },
+    Transcript { event: TranscriptEvent },
+    Output { sink: String, outcome: &'static str, detail: Option<String> },
+}
+
+/// An event and when it happened, in milliseconds since the session began.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LoggedEvent {
+    pub at_ms: u64,
+    #[serde(flatten)]
+    pub event: SessionEvent,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionLog {
+    pub provider: String,
+    pub capabilities: String,
+    pub started_at: String,
+    pub ended_at: Option<String>,
+    pub audio: AudioInfo,
+    pub events: Vec<LoggedEvent>,
+    #[serde(skip)]
+    started: Instant,
+    /// Names the export file after the session's start.
+    #[serde(skip)]
+    file_stamp: String,
+}
+
+impl Default for SessionLog {
+    fn default() -> Self {
+        SessionLog::new(String::new(), String::new(), AudioFormat::default(), None)
+    }
+}
+
+impl SessionLog {
+    pub fn new(provider: String, capabilities: String, format: AudioFormat, device: Option<String>) -> Self {
+        let now = Local::now();
+        Self {
+            provider,
+            capabilities,
+            started_at: now.to_rfc3339(),
+            ended_at: None,
+            audio: AudioInfo {
+                format: match format.encoding {
+                    AudioEncoding::Pcm16 => "pcm_s16le",
+                    AudioEncoding::MuLaw => "mu_law",
+                },
+                sample_rate: format.sample_rate,
+                channels: 1,
+                device,
+                seconds: 0.0,
+            },
+            events: Vec::new(),
+            started: Instant::now(),
+            file_stamp: now.format("%Y%m%d-%H%M%S").to_string(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.provider.is_empty()
+    }
+
+    fn push(&mut self, event: SessionEvent) {
+        let at_ms = self.started.elapsed().as_millis() as u64;
+        self.events.push(LoggedEvent { at_ms, event });
+    }
+
+    pub fn record_state(&mut self, state: &str) {
+        self.push(SessionEvent::State { state: state.to_string() });
+    }
+
+    pub fn record_transcript(&mut self, event: &TranscriptEvent) {
+        self.push(SessionEvent::Transcript { event: event.clone() });
+    }
+
+    /// `result` is a sink's delivery result: Ok(true) delivered, Ok(false)
+    /// skipped, Err failed.
+    pub fn record_output<E: std::fmt::Display>(&mut self, sink: &str, result: &Result<bool, E>) {
+        let (outcome, detail) = match result {
+            Ok(true) => ("delivered", None),
+            Ok(false) => ("skipped", None),
+            Err(e) => ("failed", Some(e.to_string())),
+        };
+        self.push(SessionEvent::Output {
+            sink: sink.to_string(),
+            outcome,
+            detail,
+        });
+    }
+
+    pub fn finish(&mut self, audio_seconds: f64) {
+        self.audio.seconds = audio_seconds;
+        self.ended_at = Some(Local::now().to_rfc3339());
+    }
+
+    /// Writes the log as pretty-printed JSON into `folder`.
+    pub fn export(&self, folder: &Path) -> std::io::Result<PathBuf> {
+        std::fs::create_dir_all(folder)?;
+        let path = folder.join(format!("session-{}.json", self.file_stamp));
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(&path, json)?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SessionLog;
+    use crate::audio::AudioFormat;
+    use crate::error::EchoError;
+    use crate::network::{TranscriptEvent, TranscriptSegment};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn export_contains_every_event_in_order() {
+        let mut log = SessionLog::new(
+            "ElevenLabs".to_string(),
+            "partials".to_string(),
+            AudioFormat::MU_LAW_8K,
+            Some("USB Mic".to_string()),
+        );
+        assert!(!log.is_empty() && SessionLog::default().is_empty());
+        log.record_state("Recording");
+        log.record_transcript(&TranscriptEvent::Partial(TranscriptSegment::text("hello")));
+        log.record_transcript(&TranscriptEvent::Committed(TranscriptSegment::text("hello world")));
+        log.record_output("Copy to the clipboard", &Ok::<bool, EchoError>(true));
+        log.record_output("Type into the focused window", &Err(EchoError::injection("blocked")));
+        log.finish(2.5);
+
+        let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        let dir = std::env::temp_dir().join(format!("eleventh_echo_session_{}", stamp));
+        let path = log.export(&dir).unwrap();
+        assert!(path.file_name().unwrap().to_string_lossy().starts_with("session-"));
+        let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(json["provider"], "ElevenLabs");
+        assert_eq!(json["audio"]["device"], "USB Mic");
+        assert_eq!(json["audio"]["format"], "mu_law");
+        assert_eq!(json["audio"]["sample_rate"], 8000);
+        assert_eq!(json["audio"]["seconds"], 2.5);
+        assert!(json["ended_at"].is_string());
+        let kinds: Vec<&str> = json["events"].as_array().unwrap().iter().map(|e| e["kind"].as_str().unwrap()).collect();
+        assert_eq!(kinds, ["state", "transcript", "transcript", "output", "output"]);
+        assert_eq!(json["events"][2]["event"]["type"], "committed");
+        assert_eq!(json["events"][4]["outcome"], "failed");
+        assert!(json["events"][4]["detail"].as_str().unwrap().contains("blocked"));
+    }
+}
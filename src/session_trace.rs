@@ -0,0 +1,533 @@
+use crate::error::EchoResult;
+use crate::network::{ControlMessage, TranscriptEvent};
+use crate::audio::AudioFormat;
+use crate::provider::{emit_log, ProviderCapabilities, SpeechProvider};
+use async_trait::async_trait;
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc::{self, Receiver, Sender, UnboundedReceiver, UnboundedSender};
+use tokio::time::{sleep_until, Instant};
+
+/// One line of a session trace. `at_ms` is measured from the start of the
+/// provider run; audio is base64 little-endian mono PCM at the rate the
+/// provider was sent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TraceEntry {
+    Audio { at_ms: u64, pcm: String },
+    Control { at_ms: u64, message: ControlMessage },
+    Event { at_ms: u64, event: TranscriptEvent },
+}
+
+pub fn encode_pcm(samples: &[i16]) -> String {
+    let bytes: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+    general_purpose::STANDARD.encode(bytes)
+}
+
+pub fn decode_pcm(pcm: &str) -> Option<Vec<i16>> {
+    let bytes = general_purpose::STANDARD.decode(pcm).ok()?;
+    Some(
+        bytes
+            .chunks_exact(2)
+            .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+            .collect(),
+    )
+}
+
+/// Appends trace entries to a JSON-lines file, flushing each one so a
+/// crash still leaves a usable trace.
+struct TraceWriter {
+    file: BufWriter<File>,
+}
+
+impl TraceWriter {
+    fn create(path: &Path) -> std::io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(Self {
+            file: BufWriter::new(File::create(path)?),
+        })
+    }
+
+    fn write(&mut self, entry: &TraceEntry) {
+        let written = serde_json::to_writer(&mut self.file, entry)
+            .map_err(std::io::Error::from)
+            .and_then(|()| self.file.write_all(b"\n"))
+            .and_then(|()| self.file.flush());
+        if let Err(e) = written {
+            tracing::error!("❌ Failed to write session trace: {}", e);
+        }
+    }
+
+    /// Moves the writer to its own thread, so file writes never block the
+    /// runtime. The thread ends once every sender is dropped.
+    fn spawn(mut self) -> (std::sync::mpsc::Sender<TraceEntry>, std::thread::JoinHandle<()>) {
+        let (tx, rx) = std::sync::mpsc::channel::<TraceEntry>();
+        let handle = std::thread::spawn(move || {
+            for entry in rx {
+                self.write(&entry);
+            }
+        });
+        (tx, handle)
+    }
+}
+
+/// Reads a trace written by `TraceRecorder`.
+pub fn load_trace(path: &Path) -> Result<Vec<TraceEntry>, String> {
+    let contents =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read session trace {}: {}", path.display(), e))?;
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line)
+                .map_err(|e| format!("Invalid session trace {} line {}: {}", path.display(), index + 1, e))
+        })
+        .collect()
+}
+
+/// Wraps a provider and records every audio chunk, control message and
+/// transcript event that passes through it.
+pub struct TraceRecorder {
+    inner: Box<dyn SpeechProvider>,
+    path: PathBuf,
+}
+
+impl TraceRecorder {
+    pub fn new(inner: Box<dyn SpeechProvider>, path: PathBuf) -> Self {
+        Self { inner, path }
+    }
+}
+
+#[async_trait]
+impl SpeechProvider for TraceRecorder {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    fn audio_format(&self) -> AudioFormat {
+        self.inner.audio_format()
+    }
+
+    async fn run(
+        &self,
+        mut audio_rx: Receiver<Vec<i16>>,
+        mut control_rx: UnboundedReceiver<ControlMessage>,
+        text_tx: Sender<TranscriptEvent>,
+        log_tx: UnboundedSender<String>,
+    ) -> EchoResult<()> {
+        let (entry_tx, writer) = match TraceWriter::create(&self.path) {
+            Ok(writer) => writer.spawn(),
+            Err(e) => {
+                emit_log(&log_tx, format!("❌ Failed to create session trace {}: {}", self.path.display(), e));
+                return self.inner.run(audio_rx, control_rx, text_tx, log_tx).await;
+            }
+        };
+        emit_log(&log_tx, format!("💾 Recording session trace to {}", self.path.display()));
+
+        let (inner_audio_tx, inner_audio_rx) = mpsc::channel::<Vec<i16>>(50);
+        let (inner_control_tx, inner_control_rx) = mpsc::unbounded_channel::<ControlMessage>();
+        let (inner_text_tx, mut inner_text_rx) = mpsc::channel::<TranscriptEvent>(100);
+
+        let started = Instant::now();
+        let at_ms = move || started.elapsed().as_millis() as u64;
+
+        let audio_entries = entry_tx.clone();
+        let audio_task = tokio::spawn(async move {
+            while let Some(chunk) = audio_rx.recv().await {
+                let _ = audio_entries.send(TraceEntry::Audio {
+                    at_ms: at_ms(),
+                    pcm: encode_pcm(&chunk),
+                });
+                if inner_audio_tx.send(chunk).await.is_err() {
+                    break;
+                }
+            }
+        });
+        let control_entries = entry_tx.clone();
+        let control_task = tokio::spawn(async move {
+            while let Some(message) = control_rx.recv().await {
+                let _ = control_entries.send(TraceEntry::Control {
+                    at_ms: at_ms(),
+                    message: message.clone(),
+                });
+                if inner_control_tx.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+        let text_task = tokio::spawn(async move {
+            while let Some(event) = inner_text_rx.recv().await {
+                let _ = entry_tx.send(TraceEntry::Event {
+                    at_ms: at_ms(),
+                    event: event.clone(),
+                });
+                let _ = text_tx.send(event).await;
+            }
+        });
+
+        let result = self.inner.run(inner_audio_rx, inner_control_rx, inner_text_tx, log_tx).await;
+        audio_task.abort();
+        control_task.abort();
+        // Every sender has to be gone before the writer thread can finish.
+        let _ = tokio::join!(audio_task, control_task, text_task);
+        let _ = tokio::task::spawn_blocking(move || writer.join()).await;
+        result
+    }
+}
+
+/// Plays back the transcript events of a recorded trace, ignoring live
+/// audio. Events the original session received before it was stopped keep
+/// their timing; the rest are released as soon as this session stops, so a
+/// replay produces the same transcript however long it runs.
+pub struct ReplayProvider {
+    entries: Vec<TraceEntry>,
+}
+
+impl ReplayProvider {
+    pub fn new(entries: Vec<TraceEntry>) -> Self {
+        Self { entries }
+    }
+
+    /// Splits the recorded events into those before the first stop (with
+    /// their offsets) and those after it.
+    fn schedule(&self) -> (Vec<(u64, TranscriptEvent)>, Vec<TranscriptEvent>) {
+        let mut before = Vec::new();
+        let mut after = Vec::new();
+        let mut stopped = false;
+        for entry in &self.entries {
+            match entry {
+                TraceEntry::Control {
+                    message: ControlMessage::Stop,
+                    ..
+                } => stopped = true,
+                TraceEntry::Event { at_ms, event } if !stopped => before.push((*at_ms, event.clone())),
+                TraceEntry::Event { event, .. } => after.push(event.clone()),
+                _ => {}
+            }
+        }
+        (before, after)
+    }
+}
+
+#[async_trait]
+impl SpeechProvider for ReplayProvider {
+    fn name(&self) -> &'static str {
+        "Replay"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities::FULL
+    }
+
+    async fn run(
+        &self,
+        mut audio_rx: Receiver<Vec<i16>>,
+        mut control_rx: UnboundedReceiver<ControlMessage>,
+        text_tx: Sender<TranscriptEvent>,
+        log_tx: UnboundedSender<String>,
+    ) -> EchoResult<()> {
+        let (before, after) = self.schedule();
+        emit_log(
+            &log_tx,
+            format!("🧪 Replaying {} recorded transcript events", before.len() + after.len()),
+        );
+        let started = Instant::now();
+        let mut pending = before.into_iter().peekable();
+
+        loop {
+            let due = pending.peek().map(|(at_ms, _)| started + Duration::from_millis(*at_ms));
+            tokio::select! {
+                _ = sleep_until(due.unwrap_or(started)), if due.is_some() => {
+                    if let Some((_, event)) = pending.next() {
+                        let _ = text_tx.send(event).await;
+                    }
+                }
+                Some(cmd) = control_rx.recv() => {
+                    match cmd {
+                        ControlMessage::Start => {}
+                        ControlMessage::Stop => break,
+                        ControlMessage::Cancel => {
+                            emit_log(&log_tx, "➡️ Replay cancelled".to_string());
+                            return Ok(());
+                        }
+                    }
+                }
+                maybe_chunk = audio_rx.recv() => {
+                    if maybe_chunk.is_none() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        for event in pending.map(|(_, event)| event).chain(after) {
+            let _ = text_tx.send(event).await;
+        }
+        Ok(())
+    }
+}
+
+/// Feeds the audio of a recorded trace to a real provider in place of the
+/// live capture, keeping its timing, so a provider bug can be reproduced with
+/// the exact audio the provider was sent. Live audio is drained and dropped.
+/// As with `ReplayProvider`, audio recorded before the original stop keeps
+/// its timing and the rest is released when this session stops; audio sent
+/// after the original stop is not replayed.
+pub struct AudioReplay {
+    inner: Box<dyn SpeechProvider>,
+    entries: Vec<TraceEntry>,
+}
+
+impl AudioReplay {
+    pub fn new(inner: Box<dyn SpeechProvider>, entries: Vec<TraceEntry>) -> Self {
+        Self { inner, entries }
+    }
+
+    /// The recorded audio chunks before the first stop, with their offsets.
+    fn schedule(&self) -> Vec<(u64, Vec<i16>)> {
+        let mut chunks = Vec::new();
+        for entry in &self.entries {
+            match entry {
+                TraceEntry::Control {
+                    message: ControlMessage::Stop,
+                    ..
+                } => break,
+                TraceEntry::Audio { at_ms, pcm } => match decode_pcm(pcm) {
+                    Some(samples) => chunks.push((*at_ms, samples)),
+                    None => tracing::warn!("⚠️ Skipping undecodable audio at {}ms in session trace", at_ms),
+                },
+                _ => {}
+            }
+        }
+        chunks
+    }
+}
+
+#[async_trait]
+impl SpeechProvider for AudioReplay {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    fn audio_format(&self) -> AudioFormat {
+        self.inner.audio_format()
+    }
+
+    async fn run(
+        &self,
+        mut audio_rx: Receiver<Vec<i16>>,
+        mut control_rx: UnboundedReceiver<ControlMessage>,
+        text_tx: Sender<TranscriptEvent>,
+        log_tx: UnboundedSender<String>,
+    ) -> EchoResult<()> {
+        let chunks = self.schedule();
+        emit_log(
+            &log_tx,
+            format!("🧪 Replaying {} recorded audio chunks into {}", chunks.len(), self.inner.name()),
+        );
+        let (inner_audio_tx, inner_audio_rx) = mpsc::channel::<Vec<i16>>(50);
+        let (inner_control_tx, inner_control_rx) = mpsc::unbounded_channel::<ControlMessage>();
+
+        let feed = tokio::spawn(async move {
+            let started = Instant::now();
+            let mut pending = chunks.into_iter().peekable();
+            let stop = loop {
+                let due = pending.peek().map(|(at_ms, _)| started + Duration::from_millis(*at_ms));
+                tokio::select! {
+                    _ = sleep_until(due.unwrap_or(started)), if due.is_some() => {
+                        if let Some((_, samples)) = pending.next() {
+                            if inner_audio_tx.send(samples).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                    Some(cmd) = control_rx.recv() => {
+                        match cmd {
+                            ControlMessage::Start => {
+                                let _ = inner_control_tx.send(ControlMessage::Start);
+                            }
+                            other => break other,
+                        }
+                    }
+                    maybe_chunk = audio_rx.recv() => {
+                        if maybe_chunk.is_none() {
+                            break ControlMessage::Stop;
+                        }
+                    }
+                }
+            };
+            if stop == ControlMessage::Stop {
+                for (_, samples) in pending {
+                    if inner_audio_tx.send(samples).await.is_err() {
+                        return;
+                    }
+                }
+            }
+            let _ = inner_control_tx.send(stop);
+        });
+
+        let result = self.inner.run(inner_audio_rx, inner_control_rx, text_tx, log_tx).await;
+        feed.abort();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_pcm, encode_pcm, load_trace, AudioReplay, ReplayProvider, TraceEntry, TraceRecorder};
+    use crate::error::EchoResult;
+    use crate::mock_provider::{FixtureEvent, FixtureStep, MockProvider};
+    use crate::network::{ControlMessage, TranscriptEvent, TranscriptSegment};
+    use crate::provider::{ProviderCapabilities, SpeechProvider};
+    use async_trait::async_trait;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::{SystemTime, UNIX_EPOCH};
+    use tokio::sync::mpsc;
+
+    fn unique_path() -> PathBuf {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        std::env::temp_dir().join(format!("11th_echo_trace_{}.jsonl", nanos))
+    }
+
+    /// Runs `provider` with one audio chunk and a stop, returning every
+    /// transcript event it sent.
+    async fn run_session(provider: &dyn SpeechProvider) -> Vec<TranscriptEvent> {
+        let (audio_tx, audio_rx) = mpsc::channel(4);
+        let (control_tx, control_rx) = mpsc::unbounded_channel();
+        let (text_tx, mut text_rx) = mpsc::channel(16);
+        let (log_tx, _log_rx) = mpsc::unbounded_channel();
+        audio_tx.send(vec![1, -2, 300]).await.unwrap();
+        control_tx.send(ControlMessage::Stop).unwrap();
+        provider.run(audio_rx, control_rx, text_tx, log_tx).await.unwrap();
+        let mut events = Vec::new();
+        while let Ok(event) = text_rx.try_recv() {
+            events.push(event);
+        }
+        events
+    }
+
+    #[test]
+    fn pcm_roundtrips_through_base64() {
+        let samples = vec![0, 1, -1, i16::MAX, i16::MIN];
+        assert_eq!(decode_pcm(&encode_pcm(&samples)), Some(samples));
+    }
+
+    #[test]
+    fn entries_serialize_as_tagged_json() {
+        let entry = TraceEntry::Event {
+            at_ms: 12,
+            event: TranscriptEvent::Committed(TranscriptSegment::text("hi")),
+        };
+        let json = serde_json::to_string(&entry).unwrap();
+        assert!(json.contains(r#""kind":"event""#), "{}", json);
+        assert!(json.contains(r#""type":"committed""#), "{}", json);
+        assert_eq!(serde_json::from_str::<TraceEntry>(&json).unwrap(), entry);
+    }
+
+    #[tokio::test]
+    async fn recorded_session_replays_the_same_events() {
+        let path = unique_path();
+        let mock = MockProvider::new(vec![FixtureStep {
+            after_ms: 60_000,
+            event: FixtureEvent::Committed {
+                text: "hello there".to_string(),
+                confidence: Some(0.9),
+            },
+        }]);
+        let recorder = TraceRecorder::new(Box::new(mock), path.clone());
+        let recorded = run_session(&recorder).await;
+
+        let entries = load_trace(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert!(entries
+            .iter()
+            .any(|e| matches!(e, TraceEntry::Control { message: ControlMessage::Stop, .. })));
+
+        let replayed = run_session(&ReplayProvider::new(entries)).await;
+        assert_eq!(replayed, recorded);
+        assert!(matches!(
+            replayed.last(),
+            Some(TranscriptEvent::Committed(segment)) if segment.text == "hello there"
+        ));
+    }
+
+    /// Commits the audio it was sent, as a list of samples, once stopped.
+    struct Echo;
+
+    #[async_trait]
+    impl SpeechProvider for Echo {
+        fn name(&self) -> &'static str {
+            "Echo"
+        }
+
+        fn capabilities(&self) -> ProviderCapabilities {
+            ProviderCapabilities::FULL
+        }
+
+        async fn run(
+            &self,
+            mut audio_rx: mpsc::Receiver<Vec<i16>>,
+            mut control_rx: mpsc::UnboundedReceiver<ControlMessage>,
+            text_tx: mpsc::Sender<TranscriptEvent>,
+            _log_tx: mpsc::UnboundedSender<String>,
+        ) -> EchoResult<()> {
+            let mut heard = Vec::new();
+            loop {
+                tokio::select! {
+                    Some(chunk) = audio_rx.recv() => heard.extend(chunk),
+                    Some(ControlMessage::Stop) = control_rx.recv() => break,
+                }
+            }
+            while let Ok(chunk) = audio_rx.try_recv() {
+                heard.extend(chunk);
+            }
+            let text = format!("{:?}", heard);
+            let _ = text_tx.send(TranscriptEvent::Committed(TranscriptSegment::text(&text))).await;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn audio_replay_sends_the_recorded_audio_instead_of_the_live_one() {
+        let entries = vec![
+            TraceEntry::Audio {
+                at_ms: 0,
+                pcm: encode_pcm(&[7, 8]),
+            },
+            TraceEntry::Audio {
+                at_ms: 60_000,
+                pcm: encode_pcm(&[9]),
+            },
+            TraceEntry::Control {
+                at_ms: 60_001,
+                message: ControlMessage::Stop,
+            },
+            TraceEntry::Audio {
+                at_ms: 60_002,
+                pcm: encode_pcm(&[10]),
+            },
+        ];
+        let replayed = run_session(&AudioReplay::new(Box::new(Echo), entries)).await;
+        assert!(
+            matches!(replayed.as_slice(), [TranscriptEvent::Committed(segment)] if segment.text == "[7, 8, 9]"),
+            "{:?}",
+            replayed
+        );
+    }
+}
@@ -1,14 +1,43 @@
+use crate::audio::{AudioFormat, CaptureSource, ResamplerQuality};
+use crate::commands::{default_commands, VoiceCommand};
+use crate::formatters::FormatterKind;
+use crate::hotkey::{default_keybindings, HotkeyAction, KeyBinding};
+use crate::i18n::UiLanguage;
+use crate::injector::TargetLock;
+use crate::llm::LlmScope;
+use crate::metrics::MaxDurationAction;
+use crate::network::LowQuotaAction;
+use crate::output::OutputSinkKind;
+use crate::processors::ProcessorStep;
+use crate::provider::ProviderKind;
+use crate::redaction::RedactionMode;
+use crate::replacements::ReplacementRule;
+use crate::snippets::Snippet;
+use crate::theme::ThemeMode;
+use crate::transcript_panel::TranscriptRetention;
+use crate::translate::TranslationBackend;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::fs;
-use std::path::PathBuf;
-use dirs_next::config_dir;
+use std::path::{Path, PathBuf};
+use tracing::{error, info, warn};
+use dirs_next::{config_dir, data_dir};
+
+/// Version of the settings file format this build writes. Bump it whenever
+/// a saved field is renamed, removed or changes meaning, and add the step
+/// that upgrades older files to `MIGRATIONS`.
+pub const SETTINGS_VERSION: u32 = 2;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AppSettings {
+    /// Format version the file was written with; see [`SETTINGS_VERSION`].
+    pub version: u32,
     pub api_key: String,
     pub selected_microphone: String,
     pub use_default_microphone: bool,
-    pub hotkey_text: String,
+    /// Global hotkeys and the action each one triggers.
+    pub keybindings: Vec<KeyBinding>,
     pub overlay_opacity: f32,
     pub theme_background_top_color: String,
     pub theme_background_bottom_color: String,
@@ -16,6 +45,15 @@ pub struct AppSettings {
     pub theme_button_accent_color: String,
     pub theme_title_color: String,
     pub theme_text_color: String,
+    /// Dark (the `theme_*` colors above), light, or whichever the system uses.
+    pub theme_mode: ThemeMode,
+    /// Show the compact mini bar instead of the main window.
+    pub mini_bar: bool,
+    /// Where the main window was when the app last closed; `None` lets the
+    /// window system place it.
+    pub window_geometry: Option<WindowGeometry>,
+    /// The tab that was open when the app last closed.
+    pub active_tab: i32,
     pub overlay_background_color: String,
     pub overlay_text_color: String,
     pub gemini_api_key: String,
@@ -23,15 +61,191 @@ pub struct AppSettings {
     pub gemini_model: String,
     pub gemini_prompt_preset: String,
     pub gemini_custom_prompt: String,
+    pub record_session_audio: bool,
+    /// Write each session's audio chunks and provider messages to a trace
+    /// file in the recordings folder.
+    pub record_session_trace: bool,
+    /// Benchmark mode: also stream each session to this provider and write
+    /// a report comparing its segments and latencies with `provider`'s.
+    pub race_provider: Option<ProviderKind>,
+    /// ElevenLabs model for the race provider; empty uses `eleven_model_id`,
+    /// so two ElevenLabs models can be compared.
+    pub race_eleven_model_id: String,
+    /// Keep the session's finalized text in an autosave file until the
+    /// session ends, so it can be restored after a crash.
+    pub autosave_transcripts: bool,
+    /// Save each finished session's text to the history file.
+    pub keep_history: bool,
+    /// Encrypt the history and autosave files with a key kept in the
+    /// system keychain.
+    pub encrypt_transcripts: bool,
+    /// Listen for the recorded wake word between sessions and start
+    /// dictating when it is heard.
+    pub wake_word_armed: bool,
+    pub noise_suppression: bool,
+    /// Run the audio worker at raised priority (MMCSS "Pro Audio" on
+    /// Windows) so capture keeps up when the machine is busy.
+    pub boost_audio_thread: bool,
+    pub replacement_rules: Vec<ReplacementRule>,
+    /// Write spoken numbers, amounts and dates as digits.
+    pub format_numbers: bool,
+    /// BCP-47 locale for number and date formatting; empty follows
+    /// `speech_language`.
+    pub number_locale: String,
+    /// Dictation style: formatters applied to each commit, in order.
+    pub formatters: Vec<FormatterKind>,
+    /// Mask swear words in everything shown, typed or saved.
+    pub filter_profanity: bool,
+    /// Redact email addresses and card numbers the same way.
+    pub redact_sensitive_data: bool,
+    /// Extra regexes to redact, e.g. internal ticket or employee ids.
+    pub redaction_patterns: Vec<String>,
+    pub redaction_mode: RedactionMode,
+    /// Run commits that start with `voice_command_prefix` as commands
+    /// instead of typing them.
+    pub voice_commands_enabled: bool,
+    pub voice_command_prefix: String,
+    pub voice_commands: Vec<VoiceCommand>,
+    /// Text blocks typed in place of "insert <name>".
+    pub snippets: Vec<Snippet>,
+    /// The order committed text goes through the processing steps;
+    /// redaction always runs after them.
+    pub processors: Vec<ProcessorStep>,
+    /// Names and domain terms the provider should favour when it supports
+    /// keyword boosting.
+    pub custom_vocabulary: Vec<String>,
+    /// Ask the provider for speaker ids and prefix each turn with
+    /// "Speaker N:" in the history and exports.
+    pub diarization_enabled: bool,
+    /// Drop the speaker labels from text that is typed or added to the pad.
+    pub strip_speaker_labels: bool,
+    pub control_api_enabled: bool,
+    pub control_api_port: u16,
+    /// Bearer token for the control API and caption server, generated on
+    /// first launch, see local_http.rs.
+    pub local_api_token: String,
+    /// Serve live captions for OBS browser sources on localhost.
+    pub captions_enabled: bool,
+    pub captions_port: u16,
+    /// Pause between injection bursts of `injection_chunk_size` characters.
+    pub injection_burst_delay_ms: u32,
+    pub injection_chunk_size: u32,
+    pub auto_stop_silence_secs: u32,
+    /// Pause between timed words or segments, in seconds, that starts a
+    /// new paragraph; 0 keeps the transcript in one.
+    pub paragraph_pause_secs: f32,
+    /// Session length limit in minutes; 0 disables the guard.
+    pub max_session_minutes: u32,
+    pub max_session_action: MaxDurationAction,
+    /// Seconds of audio held while the provider connects (0–30).
+    pub preconnect_buffer_secs: u32,
+    pub capture_source: CaptureSource,
+    /// Transcribe the microphone and system audio side by side, labelling
+    /// lines "Me:" and "Them:". Overrides `capture_source`.
+    pub meeting_mode: bool,
+    /// CPU/fidelity trade-off when the device rate differs from the stream's.
+    pub resampler_quality: ResamplerQuality,
+    /// Requested stream format; each provider falls back to the nearest one
+    /// it accepts.
+    pub audio_format: AudioFormat,
+    pub provider: ProviderKind,
+    pub azure_speech_key: String,
+    pub azure_speech_region: String,
+    pub google_speech_api_key: String,
+    /// BCP-47 language for providers that need one (Azure, Google).
+    pub speech_language: String,
+    pub openai_api_key: String,
+    pub openai_transcription_model: String,
+    /// Remaining ElevenLabs characters below which a session start warns or
+    /// is refused; 0 disables the check.
+    pub low_quota_threshold: u64,
+    pub low_quota_action: LowQuotaAction,
+    /// Catalog name of the Vosk model picked in the download manager.
+    pub vosk_model: String,
+    /// Outbound proxy URL; empty falls back to HTTPS_PROXY / ALL_PROXY.
+    pub proxy_url: String,
+    /// Commits with a provider confidence below this (0.0-1.0) are flagged
+    /// in the history. 0 disables the check.
+    pub low_confidence_threshold: f32,
+    pub skip_low_confidence_injection: bool,
+    pub target_lock: TargetLock,
+    /// Keep transcripts in the app only; never type into other windows.
+    pub transcript_only: bool,
+    /// Show the finished transcript for Edit/Inject/Discard before it is
+    /// typed anywhere.
+    pub preview_before_inject: bool,
+    /// Where finished transcripts are sent; all of them receive each one.
+    pub output_sinks: Vec<OutputSinkKind>,
+    /// File for the "append to a file" output; empty uses dictation.txt in
+    /// the transcripts folder.
+    pub output_file_path: String,
+    /// Endpoint for the webhook output; each transcript is POSTed as JSON.
+    pub webhook_url: String,
+    /// Markdown note for the note output, with strftime date patterns such
+    /// as `%Y-%m-%d`; empty uses a daily note in the transcripts folder.
+    pub markdown_note_path: String,
+    /// Append finished transcripts to the built-in dictation pad instead of
+    /// typing them into the focused window.
+    pub dictation_pad: bool,
+    /// Whether the Transcription tab starts empty for each session or keeps
+    /// adding to the previous ones.
+    pub transcript_retention: TranscriptRetention,
+    /// Type partial transcripts as they arrive and correct them in place
+    /// when the final text comes in.
+    pub live_partial_injection: bool,
+    pub show_toasts: bool,
+    /// Short sounds on the default output device, per event.
+    pub cue_on_start: bool,
+    pub cue_on_stop: bool,
+    pub cue_on_error: bool,
+    /// Cue loudness, 0.0-1.0.
+    pub cue_volume: f32,
+    /// ElevenLabs realtime model passed to the websocket.
+    pub eleven_model_id: String,
+    /// Saved profiles; the live fields above belong to `active_profile`.
+    pub profiles: Vec<Profile>,
+    pub active_profile: String,
+    /// Skip showing the main window at startup (Windows tray only).
+    pub start_hidden: bool,
+    pub launch_at_login: bool,
+    /// Language of the window text; switches as soon as it is picked.
+    pub ui_language: UiLanguage,
+    /// Optional OpenAI-compatible post-processing before injection.
+    pub llm_enabled: bool,
+    pub llm_endpoint: String,
+    pub llm_api_key: String,
+    pub llm_model: String,
+    pub llm_prompt: String,
+    pub llm_scope: LlmScope,
+    pub llm_timeout_secs: u32,
+    /// Translate each committed segment before it is injected.
+    pub translation_enabled: bool,
+    pub translation_backend: TranslationBackend,
+    pub deepl_api_key: String,
+    /// BCP-47 codes; an empty source is auto-detected.
+    pub translation_source_lang: String,
+    pub translation_target_lang: String,
+    /// One of `logging::LOG_LEVELS`; `RUST_LOG` overrides it.
+    pub log_level: String,
+    /// Development only, not shown in the UI: a `MockProvider` fixture that
+    /// replaces the selected provider when set.
+    pub mock_provider_fixture: String,
+    /// Development only, not shown in the UI: a session trace to replay
+    /// instead of calling the selected provider.
+    pub replay_session_trace: String,
+    /// Development only: feed the replayed trace's audio to the selected
+    /// provider instead of replaying the transcript events it recorded.
+    pub replay_trace_audio: bool,
 }
 
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
+            version: SETTINGS_VERSION,
             api_key: String::new(),
             selected_microphone: String::new(),
             use_default_microphone: true,
-            hotkey_text: "Ctrl+Space".to_string(),
+            keybindings: default_keybindings(),
             overlay_opacity: 0.85,
             theme_background_top_color: "#02140b".to_string(),   // deep forest green
             theme_background_bottom_color: "#000806".to_string(), // near-black green
@@ -39,6 +253,10 @@ impl Default for AppSettings {
             theme_button_accent_color: "#4ade80".to_string(),    // bright leaf green
             theme_title_color: "#e4ffe9".to_string(),            // soft light green
             theme_text_color: "#ccefd6".to_string(),             // muted light green
+            theme_mode: ThemeMode::Dark,
+            mini_bar: false,
+            window_geometry: None,
+            active_tab: 0,
             overlay_background_color: "#03150c".to_string(),     // darker overlay panel
             overlay_text_color: "#e6fff0".to_string(),           // overlay text
             gemini_api_key: String::new(),
@@ -46,8 +264,336 @@ impl Default for AppSettings {
             gemini_model: "gemini-3.1-flash-lite-preview".to_string(),
             gemini_prompt_preset: "Minimal corrections".to_string(),
             gemini_custom_prompt: String::new(),
+            record_session_audio: false,
+            record_session_trace: false,
+            race_provider: None,
+            race_eleven_model_id: String::new(),
+            autosave_transcripts: true,
+            keep_history: true,
+            encrypt_transcripts: false,
+            wake_word_armed: false,
+            noise_suppression: false,
+            boost_audio_thread: true,
+            replacement_rules: Vec::new(),
+            format_numbers: false,
+            number_locale: String::new(),
+            formatters: Vec::new(),
+            filter_profanity: false,
+            redact_sensitive_data: false,
+            redaction_patterns: Vec::new(),
+            redaction_mode: RedactionMode::Mask,
+            voice_commands_enabled: false,
+            voice_command_prefix: crate::commands::DEFAULT_PREFIX.to_string(),
+            voice_commands: default_commands(),
+            snippets: Vec::new(),
+            processors: ProcessorStep::defaults(),
+            custom_vocabulary: Vec::new(),
+            diarization_enabled: false,
+            strip_speaker_labels: true,
+            control_api_enabled: false,
+            control_api_port: 8787,
+            local_api_token: String::new(),
+            captions_enabled: false,
+            captions_port: 8788,
+            injection_burst_delay_ms: 0,
+            injection_chunk_size: 0,
+            auto_stop_silence_secs: 0,
+            paragraph_pause_secs: 0.0,
+            max_session_minutes: 0,
+            max_session_action: MaxDurationAction::Stop,
+            preconnect_buffer_secs: 5,
+            capture_source: CaptureSource::Microphone,
+            meeting_mode: false,
+            resampler_quality: ResamplerQuality::Balanced,
+            audio_format: AudioFormat::PCM_16K,
+            provider: ProviderKind::ElevenLabs,
+            azure_speech_key: String::new(),
+            azure_speech_region: String::new(),
+            google_speech_api_key: String::new(),
+            speech_language: "en-US".to_string(),
+            openai_api_key: String::new(),
+            openai_transcription_model: "whisper-1".to_string(),
+            low_quota_threshold: 0,
+            low_quota_action: LowQuotaAction::Warn,
+            vosk_model: crate::vosk_models::CATALOG[0].name.to_string(),
+            proxy_url: String::new(),
+            low_confidence_threshold: 0.0,
+            skip_low_confidence_injection: false,
+            target_lock: TargetLock::Off,
+            transcript_only: false,
+            preview_before_inject: false,
+            output_sinks: vec![OutputSinkKind::Keyboard],
+            output_file_path: String::new(),
+            webhook_url: String::new(),
+            markdown_note_path: String::new(),
+            dictation_pad: false,
+            transcript_retention: TranscriptRetention::ClearOnStart,
+            live_partial_injection: false,
+            show_toasts: true,
+            cue_on_start: false,
+            cue_on_stop: false,
+            cue_on_error: false,
+            cue_volume: 0.5,
+            eleven_model_id: "scribe_v2_realtime".to_string(),
+            profiles: Vec::new(),
+            active_profile: DEFAULT_PROFILE.to_string(),
+            start_hidden: false,
+            launch_at_login: false,
+            ui_language: UiLanguage::System,
+            llm_enabled: false,
+            llm_endpoint: "https://api.openai.com/v1".to_string(),
+            llm_api_key: String::new(),
+            llm_model: "gpt-4o-mini".to_string(),
+            llm_prompt: "Fix grammar, spelling and punctuation without changing the meaning.".to_string(),
+            llm_scope: LlmScope::EachCommit,
+            llm_timeout_secs: 10,
+            translation_enabled: false,
+            translation_backend: TranslationBackend::DeepL,
+            deepl_api_key: String::new(),
+            translation_source_lang: String::new(),
+            translation_target_lang: "en-US".to_string(),
+            log_level: "info".to_string(),
+            mock_provider_fixture: String::new(),
+            replay_session_trace: String::new(),
+            replay_trace_audio: false,
+        }
+    }
+}
+
+pub const DEFAULT_PROFILE: &str = "Default";
+
+/// The account, language, vocabulary and injection values that switching
+/// profiles swaps in and out of `AppSettings`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub name: String,
+    pub api_key: String,
+    pub eleven_model_id: String,
+    pub provider: ProviderKind,
+    pub azure_speech_key: String,
+    pub azure_speech_region: String,
+    pub google_speech_api_key: String,
+    pub speech_language: String,
+    pub openai_api_key: String,
+    pub openai_transcription_model: String,
+    pub replacement_rules: Vec<ReplacementRule>,
+    pub format_numbers: bool,
+    pub number_locale: String,
+    pub formatters: Vec<FormatterKind>,
+    pub custom_vocabulary: Vec<String>,
+    pub injection_burst_delay_ms: u32,
+    pub injection_chunk_size: u32,
+    pub target_lock: TargetLock,
+    pub transcript_only: bool,
+    pub preview_before_inject: bool,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Profile::capture(DEFAULT_PROFILE, &AppSettings::default())
+    }
+}
+
+impl Profile {
+    pub fn capture(name: &str, settings: &AppSettings) -> Self {
+        Self {
+            name: name.to_string(),
+            api_key: settings.api_key.clone(),
+            eleven_model_id: settings.eleven_model_id.clone(),
+            provider: settings.provider,
+            azure_speech_key: settings.azure_speech_key.clone(),
+            azure_speech_region: settings.azure_speech_region.clone(),
+            google_speech_api_key: settings.google_speech_api_key.clone(),
+            speech_language: settings.speech_language.clone(),
+            openai_api_key: settings.openai_api_key.clone(),
+            openai_transcription_model: settings.openai_transcription_model.clone(),
+            replacement_rules: settings.replacement_rules.clone(),
+            format_numbers: settings.format_numbers,
+            number_locale: settings.number_locale.clone(),
+            formatters: settings.formatters.clone(),
+            custom_vocabulary: settings.custom_vocabulary.clone(),
+            injection_burst_delay_ms: settings.injection_burst_delay_ms,
+            injection_chunk_size: settings.injection_chunk_size,
+            target_lock: settings.target_lock,
+            transcript_only: settings.transcript_only,
+            preview_before_inject: settings.preview_before_inject,
         }
     }
+
+    pub fn apply_to(&self, settings: &mut AppSettings) {
+        settings.api_key = self.api_key.clone();
+        settings.eleven_model_id = self.eleven_model_id.clone();
+        settings.provider = self.provider;
+        settings.azure_speech_key = self.azure_speech_key.clone();
+        settings.azure_speech_region = self.azure_speech_region.clone();
+        settings.google_speech_api_key = self.google_speech_api_key.clone();
+        settings.speech_language = self.speech_language.clone();
+        settings.openai_api_key = self.openai_api_key.clone();
+        settings.openai_transcription_model = self.openai_transcription_model.clone();
+        settings.replacement_rules = self.replacement_rules.clone();
+        settings.format_numbers = self.format_numbers;
+        settings.number_locale = self.number_locale.clone();
+        settings.formatters = self.formatters.clone();
+        settings.custom_vocabulary = self.custom_vocabulary.clone();
+        settings.injection_burst_delay_ms = self.injection_burst_delay_ms;
+        settings.injection_chunk_size = self.injection_chunk_size;
+        settings.target_lock = self.target_lock;
+        settings.transcript_only = self.transcript_only;
+        settings.preview_before_inject = self.preview_before_inject;
+        settings.active_profile = self.name.clone();
+    }
+}
+
+/// Main window placement in physical pixels. The size and position are the
+/// last un-maximized ones, so un-maximizing after a restore lands there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub maximized: bool,
+}
+
+// Only the Windows build knows the desktop bounds to check against.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+impl WindowGeometry {
+    /// How much of the window's top-left corner must be on screen to restore
+    /// the position, so the title bar can still be grabbed.
+    const VISIBLE_CORNER: i32 = 64;
+
+    /// Whether the saved position is still reachable on a desktop spanning
+    /// `x`, `y`, `width`, `height`, e.g. after a monitor was unplugged.
+    pub fn is_on_screen(&self, x: i32, y: i32, width: i32, height: i32) -> bool {
+        self.x >= x
+            && self.y >= y
+            && self.x + Self::VISIBLE_CORNER <= x + width
+            && self.y + Self::VISIBLE_CORNER <= y + height
+    }
+}
+
+impl AppSettings {
+    pub fn output_file(&self) -> PathBuf {
+        match self.output_file_path.trim() {
+            "" => transcripts_dir().join("dictation.txt"),
+            path => PathBuf::from(path),
+        }
+    }
+
+    pub fn markdown_note_template(&self) -> String {
+        match self.markdown_note_path.trim() {
+            "" => transcripts_dir().join("notes").join("%Y-%m-%d.md").display().to_string(),
+            path => path.to_string(),
+        }
+    }
+
+    /// The locale numbers and dates are formatted for.
+    pub fn number_locale_tag(&self) -> &str {
+        match self.number_locale.trim() {
+            "" => self.speech_language.trim(),
+            tag => tag,
+        }
+    }
+
+    /// The first combo bound to `action`, if any.
+    pub fn binding(&self, action: HotkeyAction) -> Option<&str> {
+        self.keybindings
+            .iter()
+            .find(|binding| binding.action == action)
+            .map(|binding| binding.keys.as_str())
+    }
+
+    /// Rebinds the first entry for `action`, adding one if it has none.
+    pub fn set_binding(&mut self, action: HotkeyAction, keys: &str) {
+        match self.keybindings.iter_mut().find(|binding| binding.action == action) {
+            Some(existing) => existing.keys = keys.to_string(),
+            None => self.keybindings.push(KeyBinding::new(action, keys)),
+        }
+    }
+
+    /// Names of all profiles, including the active one before it is first saved.
+    pub fn profile_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.profiles.iter().map(|p| p.name.clone()).collect();
+        if !names.contains(&self.active_profile) {
+            names.insert(0, self.active_profile.clone());
+        }
+        names
+    }
+
+    /// The profile after the active one, wrapping around.
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    pub fn next_profile_name(&self) -> String {
+        let names = self.profile_names();
+        let index = names.iter().position(|name| *name == self.active_profile).unwrap_or(0);
+        names[(index + 1) % names.len()].clone()
+    }
+
+    /// Writes the live values back into the active profile's entry.
+    pub fn store_active_profile(&mut self) {
+        let current = Profile::capture(&self.active_profile, self);
+        match self.profiles.iter_mut().find(|p| p.name == current.name) {
+            Some(existing) => *existing = current,
+            None => self.profiles.push(current),
+        }
+    }
+
+    /// Saves the live values, then loads `name`. Returns false for unknown names.
+    pub fn switch_profile(&mut self, name: &str) -> bool {
+        if name == self.active_profile {
+            return true;
+        }
+        let Some(target) = self.profiles.iter().find(|p| p.name == name).cloned() else {
+            return false;
+        };
+        self.store_active_profile();
+        target.apply_to(self);
+        true
+    }
+
+    /// Copies the live values into a new (or replaced) profile and makes it active.
+    pub fn save_profile_as(&mut self, name: &str) -> bool {
+        let name = name.trim();
+        if name.is_empty() {
+            return false;
+        }
+        self.store_active_profile();
+        self.active_profile = name.to_string();
+        self.store_active_profile();
+        true
+    }
+
+    /// Removes the active profile and loads the first remaining one. The
+    /// last profile can't be deleted.
+    pub fn delete_active_profile(&mut self) -> bool {
+        self.profiles.retain(|p| p.name != self.active_profile);
+        match self.profiles.first().cloned() {
+            Some(next) => {
+                next.apply_to(self);
+                true
+            }
+            None => {
+                self.store_active_profile();
+                false
+            }
+        }
+    }
+}
+
+/// Parses the vocabulary editor: one term per line, blank lines and
+/// repeats (ignoring case) dropped.
+pub fn parse_vocabulary(text: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for term in text.lines().map(str::trim).filter(|t| !t.is_empty()) {
+        if !terms.iter().any(|known| known.eq_ignore_ascii_case(term)) {
+            terms.push(term.to_string());
+        }
+    }
+    terms
+}
+
+pub fn format_vocabulary(terms: &[String]) -> String {
+    terms.join("\n")
 }
 
 pub fn settings_path() -> PathBuf {
@@ -57,17 +603,206 @@ pub fn settings_path() -> PathBuf {
     base.join("11th_echo").join("settings.json")
 }
 
+pub fn recordings_dir() -> PathBuf {
+    let base = data_dir().unwrap_or_else(|| PathBuf::from("."));
+    base.join("11th_echo").join("recordings")
+}
+
+pub fn transcripts_dir() -> PathBuf {
+    let base = data_dir().unwrap_or_else(|| PathBuf::from("."));
+    base.join("11th_echo").join("transcripts")
+}
+
+pub fn wake_word_dir() -> PathBuf {
+    let base = data_dir().unwrap_or_else(|| PathBuf::from("."));
+    base.join("11th_echo").join("wake_word")
+}
+
+pub fn logs_dir() -> PathBuf {
+    let base = data_dir().unwrap_or_else(|| PathBuf::from("."));
+    base.join("11th_echo").join("logs")
+}
+
+pub fn autosave_path() -> PathBuf {
+    let base = data_dir().unwrap_or_else(|| PathBuf::from("."));
+    base.join("11th_echo").join("autosave.jsonl")
+}
+
+/// Where an unfinished session's autosave waits for Restore or Discard.
+pub fn recovered_autosave_path() -> PathBuf {
+    let base = data_dir().unwrap_or_else(|| PathBuf::from("."));
+    base.join("11th_echo").join("autosave.recovered.jsonl")
+}
+
+pub fn history_path() -> PathBuf {
+    let base = data_dir().unwrap_or_else(|| PathBuf::from("."));
+    base.join("11th_echo").join("history.jsonl")
+}
+
+/// Where the Vosk runtime and models are downloaded to.
+pub fn vosk_models_dir() -> PathBuf {
+    let base = data_dir().unwrap_or_else(|| PathBuf::from("."));
+    base.join("11th_echo").join("vosk_models")
+}
+
+/// Where `.wasm` transcript processor plugins are loaded from.
+pub fn plugins_dir() -> PathBuf {
+    let base = data_dir().unwrap_or_else(|| PathBuf::from("."));
+    base.join("11th_echo").join("plugins")
+}
+
+pub fn metrics_path() -> PathBuf {
+    let base = data_dir().unwrap_or_else(|| PathBuf::from("."));
+    base.join("11th_echo").join("metrics.json")
+}
+
 pub fn load_settings() -> AppSettings {
     load_settings_from_path(&settings_path())
 }
 
-pub fn load_settings_from_path(path: &PathBuf) -> AppSettings {
-    if let Ok(contents) = fs::read_to_string(path) {
-        if let Ok(settings) = serde_json::from_str::<AppSettings>(&contents) {
-            return settings;
+/// The single-purpose hotkey fields written before the keybinding table.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct LegacyHotkeys {
+    hotkey_text: Option<String>,
+    pause_hotkey_text: Option<String>,
+    undo_hotkey_text: Option<String>,
+}
+
+impl LegacyHotkeys {
+    const FIELDS: [&'static str; 3] = ["hotkey_text", "pause_hotkey_text", "undo_hotkey_text"];
+
+    fn migrate(self, settings: &mut AppSettings) {
+        let legacy = [
+            (HotkeyAction::ToggleDictation, self.hotkey_text),
+            (HotkeyAction::PauseResume, self.pause_hotkey_text),
+            (HotkeyAction::UndoLastInjection, self.undo_hotkey_text),
+        ];
+        for (action, keys) in legacy {
+            if let Some(keys) = keys.filter(|keys| !keys.trim().is_empty()) {
+                settings.set_binding(action, keys.trim());
+            }
+        }
+    }
+}
+
+/// One upgrade step, applied to the raw JSON before it is read into
+/// [`AppSettings`], so renamed or reshaped fields can still be found.
+type Migration = fn(&mut Map<String, Value>);
+
+/// `MIGRATIONS[n]` upgrades a version `n` file to version `n + 1`. Files
+/// without a `version` field predate versioning and count as version 0.
+const MIGRATIONS: [Migration; SETTINGS_VERSION as usize] = [migrate_v0_hotkeys, migrate_v1_burst_delay];
+
+/// Version 0 kept one field per hotkey; they become keybinding table entries
+/// unless the file already has a table.
+fn migrate_v0_hotkeys(map: &mut Map<String, Value>) {
+    let legacy = serde_json::from_value::<LegacyHotkeys>(Value::Object(map.clone())).unwrap_or_default();
+    for field in LegacyHotkeys::FIELDS {
+        map.remove(field);
+    }
+    if map.contains_key("keybindings") {
+        return;
+    }
+    let mut settings = AppSettings::default();
+    legacy.migrate(&mut settings);
+    if let Ok(keybindings) = serde_json::to_value(&settings.keybindings) {
+        map.insert("keybindings".to_string(), keybindings);
+    }
+}
+
+/// Version 1 named the pause between injection bursts as if it came after
+/// every character; the field is renamed here and in each profile.
+fn migrate_v1_burst_delay(map: &mut Map<String, Value>) {
+    fn rename(map: &mut Map<String, Value>) {
+        if let Some(delay) = map.remove("injection_char_delay_ms") {
+            map.insert("injection_burst_delay_ms".to_string(), delay);
+        }
+    }
+    rename(map);
+    if let Some(Value::Array(profiles)) = map.get_mut("profiles") {
+        for profile in profiles.iter_mut().filter_map(Value::as_object_mut) {
+            rename(profile);
         }
     }
-    AppSettings::default()
+}
+
+/// Brings `value` up to [`SETTINGS_VERSION`] and returns the version it was
+/// saved with. A file from a newer build is read as far as this one
+/// understands it.
+fn migrate_settings(value: &mut Value) -> Result<u32, String> {
+    let map = value
+        .as_object_mut()
+        .ok_or_else(|| "Invalid settings: expected a JSON object".to_string())?;
+    let saved = map
+        .get("version")
+        .and_then(Value::as_u64)
+        .map_or(0, |version| u32::try_from(version).unwrap_or(u32::MAX));
+    for migration in MIGRATIONS.iter().skip(saved as usize) {
+        migration(map);
+    }
+    map.insert("version".to_string(), SETTINGS_VERSION.into());
+    Ok(saved)
+}
+
+fn read_versioned(path: &Path) -> Result<(AppSettings, u32), String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    let mut value = serde_json::from_str::<Value>(&contents).map_err(|e| format!("Invalid settings: {}", e))?;
+    let saved = migrate_settings(&mut value)?;
+    let settings = serde_json::from_value(value).map_err(|e| format!("Invalid settings: {}", e))?;
+    Ok((settings, saved))
+}
+
+/// `settings.json` becomes `settings.<tag>.json` next to it.
+fn backup_path(path: &Path, tag: &str) -> PathBuf {
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    let file_name = match path.extension() {
+        Some(extension) => format!("{}.{}.{}", stem, tag, extension.to_string_lossy()),
+        None => format!("{}.{}", stem, tag),
+    };
+    path.with_file_name(file_name)
+}
+
+fn backup_settings(path: &Path, tag: &str) {
+    let backup = backup_path(path, tag);
+    match fs::copy(path, &backup) {
+        Ok(_) => info!("💾 Kept a copy of the previous settings at {}", backup.display()),
+        Err(err) => error!("❌ Failed to back up settings to {}: {}", backup.display(), err),
+    }
+}
+
+/// Reads the settings file, migrating older formats. The original file is
+/// copied aside first when it is from another version or cannot be read, so
+/// the next save never loses the only copy.
+pub fn load_settings_from_path(path: &Path) -> AppSettings {
+    match read_versioned(path) {
+        Ok((settings, saved)) => {
+            if saved < SETTINGS_VERSION {
+                info!("⬆️ Migrated settings from version {} to {}", saved, SETTINGS_VERSION);
+                backup_settings(path, &format!("v{}", saved));
+            } else if saved > SETTINGS_VERSION {
+                warn!(
+                    "⚠️ Settings were saved by a newer version ({}); fields this version does not know will not be kept",
+                    saved
+                );
+                backup_settings(path, &format!("v{}", saved));
+            }
+            settings
+        }
+        Err(err) => {
+            if path.exists() {
+                error!("❌ {} - starting from default settings", err);
+                backup_settings(path, "unreadable");
+            }
+            AppSettings::default()
+        }
+    }
+}
+
+/// Like [`load_settings_from_path`], but says why the file could not be used
+/// instead of falling back to defaults, and leaves no backups behind.
+pub fn read_settings_from_path(path: &Path) -> Result<AppSettings, String> {
+    read_versioned(path).map(|(settings, _)| settings)
 }
 
 pub fn save_settings(settings: &AppSettings) {
@@ -78,7 +813,7 @@ pub fn save_settings_to_path(path: &PathBuf, settings: &AppSettings) {
     // Ensure the target directory exists (create the per-user folder if needed)
     if let Some(parent) = path.parent() {
         if let Err(err) = fs::create_dir_all(parent) {
-            eprintln!("❌ Failed to create settings directory {:?}: {}", parent, err);
+            error!("❌ Failed to create settings directory {:?}: {}", parent, err);
             return;
         }
     }
@@ -86,20 +821,41 @@ pub fn save_settings_to_path(path: &PathBuf, settings: &AppSettings) {
     match serde_json::to_string_pretty(settings) {
         Ok(json) => {
             if let Err(err) = fs::write(path, json) {
-                eprintln!("❌ Failed to save settings: {}", err);
+                error!("❌ Failed to save settings: {}", err);
             }
         }
         Err(err) => {
-            eprintln!("❌ Failed to serialize settings: {}", err);
+            error!("❌ Failed to serialize settings: {}", err);
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{load_settings_from_path, save_settings_to_path, AppSettings};
+    use super::{
+        backup_path, format_vocabulary, load_settings_from_path, parse_vocabulary, save_settings_to_path,
+        AppSettings, Profile, WindowGeometry, DEFAULT_PROFILE, SETTINGS_VERSION,
+    };
+    use crate::audio::{AudioFormat, CaptureSource, ResamplerQuality};
+    use crate::commands::{CommandAction, VoiceCommand};
+    use crate::formatters::FormatterKind;
+    use crate::hotkey::{HotkeyAction, KeyBinding};
+    use crate::i18n::UiLanguage;
+    use crate::injector::TargetLock;
+    use crate::llm::LlmScope;
+    use crate::metrics::MaxDurationAction;
+    use crate::network::LowQuotaAction;
+    use crate::output::OutputSinkKind;
+    use crate::processors::ProcessorStep;
+    use crate::provider::ProviderKind;
+    use crate::redaction::RedactionMode;
+    use crate::replacements::ReplacementRule;
+    use crate::snippets::Snippet;
+    use crate::theme::ThemeMode;
+    use crate::transcript_panel::TranscriptRetention;
+    use crate::translate::TranslationBackend;
     use std::fs;
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
     use std::time::{SystemTime, UNIX_EPOCH};
 
     fn unique_path() -> PathBuf {
@@ -110,14 +866,27 @@ mod tests {
         std::env::temp_dir().join(format!("eleventh_echo_settings_mod_{}.json", stamp))
     }
 
+    /// Reads and removes the copy `load_settings_from_path` kept aside.
+    fn take_backup(path: &Path, tag: &str) -> Option<String> {
+        let backup = backup_path(path, tag);
+        let contents = fs::read_to_string(&backup).ok();
+        let _ = fs::remove_file(&backup);
+        contents
+    }
+
     #[test]
     fn roundtrip_persists_values() {
         let path = unique_path();
         let expected = AppSettings {
+            version: SETTINGS_VERSION,
             api_key: "sk_test".to_string(),
             selected_microphone: "Mic A".to_string(),
             use_default_microphone: false,
-            hotkey_text: "Ctrl+Shift+F8".to_string(),
+            keybindings: vec![
+                KeyBinding::new(HotkeyAction::ToggleDictation, "Ctrl+Shift+F8"),
+                KeyBinding::new(HotkeyAction::PushToTalk, "Ctrl+F9"),
+                KeyBinding::new(HotkeyAction::UndoLastInjection, "Ctrl+Alt+U"),
+            ],
             overlay_opacity: 0.9,
             theme_background_top_color: "#222222".to_string(),
             theme_background_bottom_color: "#000000".to_string(),
@@ -125,6 +894,16 @@ mod tests {
             theme_button_accent_color: "#ff0000".to_string(),
             theme_title_color: "#00ff00".to_string(),
             theme_text_color: "#0000ff".to_string(),
+            theme_mode: ThemeMode::System,
+            mini_bar: true,
+            window_geometry: Some(WindowGeometry {
+                x: -1200,
+                y: 80,
+                width: 1280,
+                height: 900,
+                maximized: true,
+            }),
+            active_tab: 4,
             overlay_background_color: "#123456".to_string(),
             overlay_text_color: "#654321".to_string(),
             gemini_api_key: "gm_test".to_string(),
@@ -132,14 +911,245 @@ mod tests {
             gemini_model: "gemini-3.1-flash-lite-preview".to_string(),
             gemini_prompt_preset: "Minimal corrections".to_string(),
             gemini_custom_prompt: "Custom instructions".to_string(),
+            record_session_audio: true,
+            record_session_trace: true,
+            race_provider: Some(ProviderKind::OpenAiWhisper),
+            race_eleven_model_id: "scribe_v1".to_string(),
+            autosave_transcripts: false,
+            keep_history: false,
+            encrypt_transcripts: true,
+            wake_word_armed: true,
+            noise_suppression: true,
+            boost_audio_thread: false,
+            replacement_rules: vec![ReplacementRule {
+                pattern: "k eight s".to_string(),
+                replacement: "k8s".to_string(),
+                is_regex: false,
+            }],
+            format_numbers: true,
+            number_locale: "en-GB".to_string(),
+            formatters: vec![FormatterKind::Medical, FormatterKind::Legal],
+            filter_profanity: true,
+            redact_sensitive_data: true,
+            redaction_patterns: vec![r"EMP-\d+".to_string()],
+            redaction_mode: RedactionMode::Remove,
+            voice_commands_enabled: true,
+            voice_command_prefix: "computer".to_string(),
+            voice_commands: vec![VoiceCommand {
+                phrase: "save it".to_string(),
+                action: CommandAction::Keys("Ctrl+S".to_string()),
+            }],
+            snippets: vec![Snippet {
+                name: "signature".to_string(),
+                body: "Best regards,\nJane ({date})".to_string(),
+            }],
+            processors: vec![
+                ProcessorStep::Replacements,
+                ProcessorStep::External("fixup --strict".to_string()),
+            ],
+            custom_vocabulary: vec!["Kubernetes".to_string(), "11th Echo".to_string()],
+            diarization_enabled: true,
+            strip_speaker_labels: false,
+            control_api_enabled: true,
+            control_api_port: 9000,
+            local_api_token: "0123abcd".to_string(),
+            captions_enabled: true,
+            captions_port: 9001,
+            injection_burst_delay_ms: 15,
+            injection_chunk_size: 8,
+            auto_stop_silence_secs: 45,
+            paragraph_pause_secs: 2.5,
+            max_session_minutes: 10,
+            max_session_action: MaxDurationAction::Warn,
+            preconnect_buffer_secs: 12,
+            capture_source: CaptureSource::SystemAudio,
+            meeting_mode: true,
+            resampler_quality: ResamplerQuality::Fast,
+            audio_format: AudioFormat::MU_LAW_8K,
+            provider: ProviderKind::Azure,
+            azure_speech_key: "azure-key".to_string(),
+            azure_speech_region: "westeurope".to_string(),
+            google_speech_api_key: "AIza-google".to_string(),
+            speech_language: "de-DE".to_string(),
+            openai_api_key: "sk-openai".to_string(),
+            openai_transcription_model: "gpt-4o-transcribe".to_string(),
+            low_quota_threshold: 5_000,
+            low_quota_action: LowQuotaAction::Refuse,
+            vosk_model: "vosk-model-small-de-0.15".to_string(),
+            proxy_url: "socks5://127.0.0.1:1080".to_string(),
+            low_confidence_threshold: 0.65,
+            skip_low_confidence_injection: true,
+            target_lock: TargetLock::SkipIfMoved,
+            transcript_only: true,
+            preview_before_inject: true,
+            output_sinks: vec![OutputSinkKind::Clipboard, OutputSinkKind::File],
+            output_file_path: "C:\\Notes\\dictation.txt".to_string(),
+            webhook_url: "https://hooks.example.com/dictation".to_string(),
+            markdown_note_path: "C:\\Vault\\Daily\\%Y-%m-%d.md".to_string(),
+            dictation_pad: true,
+            transcript_retention: TranscriptRetention::Accumulate,
+            live_partial_injection: true,
+            show_toasts: false,
+            cue_on_start: true,
+            cue_on_stop: true,
+            cue_on_error: false,
+            cue_volume: 0.25,
+            eleven_model_id: "scribe_v3_realtime".to_string(),
+            profiles: vec![Profile::default()],
+            active_profile: "Work".to_string(),
+            start_hidden: true,
+            launch_at_login: true,
+            ui_language: UiLanguage::Spanish,
+            llm_enabled: true,
+            llm_endpoint: "http://localhost:11434/v1".to_string(),
+            llm_api_key: "sk-llm".to_string(),
+            llm_model: "llama3".to_string(),
+            llm_prompt: "Format as bullet points".to_string(),
+            llm_scope: LlmScope::WholeSession,
+            llm_timeout_secs: 30,
+            translation_enabled: true,
+            translation_backend: TranslationBackend::Llm,
+            deepl_api_key: "deepl:fx".to_string(),
+            translation_source_lang: "de-DE".to_string(),
+            translation_target_lang: "fr-FR".to_string(),
+            log_level: "debug".to_string(),
+            mock_provider_fixture: "fixtures/mock.json".to_string(),
+            replay_session_trace: "fixtures/garbled.trace.jsonl".to_string(),
+            replay_trace_audio: true,
         };
         save_settings_to_path(&path, &expected);
         let loaded = load_settings_from_path(&path);
         let _ = fs::remove_file(&path);
+        assert_eq!(take_backup(&path, &format!("v{}", SETTINGS_VERSION)), None);
+        assert_eq!(loaded.version, expected.version);
         assert_eq!(loaded.api_key, expected.api_key);
         assert_eq!(loaded.selected_microphone, expected.selected_microphone);
         assert_eq!(loaded.use_default_microphone, expected.use_default_microphone);
-        assert_eq!(loaded.hotkey_text, expected.hotkey_text);
+        assert_eq!(loaded.keybindings, expected.keybindings);
+        assert_eq!(loaded.record_session_audio, expected.record_session_audio);
+        assert_eq!(loaded.record_session_trace, expected.record_session_trace);
+        assert_eq!(loaded.race_provider, expected.race_provider);
+        assert_eq!(loaded.race_eleven_model_id, expected.race_eleven_model_id);
+        assert_eq!(loaded.autosave_transcripts, expected.autosave_transcripts);
+        assert_eq!(loaded.keep_history, expected.keep_history);
+        assert_eq!(loaded.encrypt_transcripts, expected.encrypt_transcripts);
+        assert_eq!(loaded.wake_word_armed, expected.wake_word_armed);
+        assert_eq!(loaded.noise_suppression, expected.noise_suppression);
+        assert_eq!(loaded.boost_audio_thread, expected.boost_audio_thread);
+        assert_eq!(loaded.replacement_rules, expected.replacement_rules);
+        assert_eq!(loaded.format_numbers, expected.format_numbers);
+        assert_eq!(loaded.number_locale, expected.number_locale);
+        assert_eq!(loaded.formatters, expected.formatters);
+        assert_eq!(loaded.filter_profanity, expected.filter_profanity);
+        assert_eq!(loaded.redact_sensitive_data, expected.redact_sensitive_data);
+        assert_eq!(loaded.redaction_patterns, expected.redaction_patterns);
+        assert_eq!(loaded.redaction_mode, expected.redaction_mode);
+        assert_eq!(loaded.voice_commands_enabled, expected.voice_commands_enabled);
+        assert_eq!(loaded.voice_command_prefix, expected.voice_command_prefix);
+        assert_eq!(loaded.voice_commands, expected.voice_commands);
+        assert_eq!(loaded.snippets, expected.snippets);
+        assert_eq!(loaded.processors, expected.processors);
+        assert_eq!(loaded.custom_vocabulary, expected.custom_vocabulary);
+        assert_eq!(loaded.diarization_enabled, expected.diarization_enabled);
+        assert_eq!(loaded.strip_speaker_labels, expected.strip_speaker_labels);
+        assert_eq!(loaded.control_api_enabled, expected.control_api_enabled);
+        assert_eq!(loaded.control_api_port, expected.control_api_port);
+        assert_eq!(loaded.local_api_token, expected.local_api_token);
+        assert_eq!(loaded.captions_enabled, expected.captions_enabled);
+        assert_eq!(loaded.captions_port, expected.captions_port);
+        assert_eq!(loaded.injection_burst_delay_ms, expected.injection_burst_delay_ms);
+        assert_eq!(loaded.injection_chunk_size, expected.injection_chunk_size);
+        assert_eq!(loaded.auto_stop_silence_secs, expected.auto_stop_silence_secs);
+        assert_eq!(loaded.paragraph_pause_secs, expected.paragraph_pause_secs);
+        assert_eq!(loaded.max_session_minutes, expected.max_session_minutes);
+        assert_eq!(loaded.max_session_action, expected.max_session_action);
+        assert_eq!(loaded.preconnect_buffer_secs, expected.preconnect_buffer_secs);
+        assert_eq!(loaded.capture_source, expected.capture_source);
+        assert_eq!(loaded.meeting_mode, expected.meeting_mode);
+        assert_eq!(loaded.resampler_quality, expected.resampler_quality);
+        assert_eq!(loaded.audio_format, expected.audio_format);
+        assert_eq!(loaded.provider, expected.provider);
+        assert_eq!(loaded.azure_speech_key, expected.azure_speech_key);
+        assert_eq!(loaded.azure_speech_region, expected.azure_speech_region);
+        assert_eq!(loaded.google_speech_api_key, expected.google_speech_api_key);
+        assert_eq!(loaded.speech_language, expected.speech_language);
+        assert_eq!(loaded.openai_api_key, expected.openai_api_key);
+        assert_eq!(loaded.openai_transcription_model, expected.openai_transcription_model);
+        assert_eq!(loaded.low_quota_threshold, expected.low_quota_threshold);
+        assert_eq!(loaded.low_quota_action, expected.low_quota_action);
+        assert_eq!(loaded.vosk_model, expected.vosk_model);
+        assert_eq!(loaded.proxy_url, expected.proxy_url);
+        assert_eq!(loaded.low_confidence_threshold, expected.low_confidence_threshold);
+        assert_eq!(loaded.skip_low_confidence_injection, expected.skip_low_confidence_injection);
+        assert_eq!(loaded.target_lock, expected.target_lock);
+        assert_eq!(loaded.transcript_only, expected.transcript_only);
+        assert_eq!(loaded.preview_before_inject, expected.preview_before_inject);
+        assert_eq!(loaded.output_sinks, expected.output_sinks);
+        assert_eq!(loaded.output_file_path, expected.output_file_path);
+        assert_eq!(loaded.webhook_url, expected.webhook_url);
+        assert_eq!(loaded.markdown_note_path, expected.markdown_note_path);
+        assert_eq!(loaded.dictation_pad, expected.dictation_pad);
+        assert_eq!(loaded.transcript_retention, expected.transcript_retention);
+        assert_eq!(loaded.live_partial_injection, expected.live_partial_injection);
+        assert_eq!(loaded.show_toasts, expected.show_toasts);
+        assert_eq!(loaded.cue_on_start, expected.cue_on_start);
+        assert_eq!(loaded.cue_on_stop, expected.cue_on_stop);
+        assert_eq!(loaded.cue_on_error, expected.cue_on_error);
+        assert_eq!(loaded.cue_volume, expected.cue_volume);
+        assert_eq!(loaded.eleven_model_id, expected.eleven_model_id);
+        assert_eq!(loaded.profiles, expected.profiles);
+        assert_eq!(loaded.active_profile, expected.active_profile);
+        assert_eq!(loaded.start_hidden, expected.start_hidden);
+        assert_eq!(loaded.launch_at_login, expected.launch_at_login);
+        assert_eq!(loaded.ui_language, expected.ui_language);
+        assert_eq!(loaded.theme_mode, expected.theme_mode);
+        assert_eq!(loaded.mini_bar, expected.mini_bar);
+        assert_eq!(loaded.window_geometry, expected.window_geometry);
+        assert_eq!(loaded.active_tab, expected.active_tab);
+        assert_eq!(loaded.llm_enabled, expected.llm_enabled);
+        assert_eq!(loaded.llm_endpoint, expected.llm_endpoint);
+        assert_eq!(loaded.llm_api_key, expected.llm_api_key);
+        assert_eq!(loaded.llm_model, expected.llm_model);
+        assert_eq!(loaded.llm_prompt, expected.llm_prompt);
+        assert_eq!(loaded.llm_scope, expected.llm_scope);
+        assert_eq!(loaded.llm_timeout_secs, expected.llm_timeout_secs);
+        assert_eq!(loaded.translation_enabled, expected.translation_enabled);
+        assert_eq!(loaded.translation_backend, expected.translation_backend);
+        assert_eq!(loaded.deepl_api_key, expected.deepl_api_key);
+        assert_eq!(loaded.translation_source_lang, expected.translation_source_lang);
+        assert_eq!(loaded.translation_target_lang, expected.translation_target_lang);
+        assert_eq!(loaded.log_level, expected.log_level);
+        assert_eq!(loaded.mock_provider_fixture, expected.mock_provider_fixture);
+        assert_eq!(loaded.replay_session_trace, expected.replay_session_trace);
+        assert_eq!(loaded.replay_trace_audio, expected.replay_trace_audio);
+    }
+
+    #[test]
+    fn missing_fields_keep_existing_values() {
+        let path = unique_path();
+        fs::write(&path, r#"{"api_key":"sk_old","hotkey_text":"Ctrl+F9"}"#).unwrap();
+        let loaded = load_settings_from_path(&path);
+        let _ = fs::remove_file(&path);
+        take_backup(&path, "v0");
+        assert_eq!(loaded.api_key, "sk_old");
+        assert_eq!(loaded.binding(HotkeyAction::ToggleDictation), Some("Ctrl+F9"));
+        assert_eq!(loaded.binding(HotkeyAction::PauseResume), Some("Ctrl+Shift+Space"));
+        assert!(!loaded.record_session_audio);
+    }
+
+    #[test]
+    fn saved_keybindings_ignore_legacy_fields() {
+        let path = unique_path();
+        fs::write(
+            &path,
+            r#"{"hotkey_text":"Ctrl+F9","keybindings":[{"action":"push_to_talk","keys":"Ctrl+F10"}]}"#,
+        )
+        .unwrap();
+        let loaded = load_settings_from_path(&path);
+        let _ = fs::remove_file(&path);
+        take_backup(&path, "v0");
+        assert_eq!(loaded.keybindings, vec![KeyBinding::new(HotkeyAction::PushToTalk, "Ctrl+F10")]);
+        assert_eq!(loaded.binding(HotkeyAction::ToggleDictation), None);
     }
 
     #[test]
@@ -148,10 +1158,11 @@ mod tests {
         fs::write(&path, "{not-json").unwrap();
         let loaded = load_settings_from_path(&path);
         let _ = fs::remove_file(&path);
+        assert_eq!(take_backup(&path, "unreadable").as_deref(), Some("{not-json"));
         assert!(loaded.api_key.is_empty());
         assert!(loaded.selected_microphone.is_empty());
         assert!(loaded.use_default_microphone);
-        assert_eq!(loaded.hotkey_text, "Ctrl+Space");
+        assert_eq!(loaded.binding(HotkeyAction::ToggleDictation), Some("Ctrl+Space"));
         assert!((loaded.overlay_opacity - 0.85).abs() < f32::EPSILON);
         assert_eq!(loaded.theme_background_top_color, "#02140b");
         assert_eq!(loaded.theme_background_bottom_color, "#000806");
@@ -162,4 +1173,128 @@ mod tests {
         assert_eq!(loaded.overlay_background_color, "#03150c");
         assert_eq!(loaded.overlay_text_color, "#e6fff0");
     }
+
+    #[test]
+    fn older_files_are_migrated_and_kept_aside() {
+        let path = unique_path();
+        let original = r#"{"api_key":"sk_old","pause_hotkey_text":"Ctrl+F7"}"#;
+        fs::write(&path, original).unwrap();
+        let loaded = load_settings_from_path(&path);
+        assert_eq!(loaded.version, SETTINGS_VERSION);
+        assert_eq!(loaded.binding(HotkeyAction::PauseResume), Some("Ctrl+F7"));
+        assert_eq!(take_backup(&path, "v0").as_deref(), Some(original));
+
+        // Once saved, the file is current and needs no further migration.
+        save_settings_to_path(&path, &loaded);
+        let saved = fs::read_to_string(&path).unwrap();
+        assert!(!saved.contains("pause_hotkey_text"));
+        let reloaded = load_settings_from_path(&path);
+        let _ = fs::remove_file(&path);
+        assert_eq!(take_backup(&path, "v0"), None);
+        assert_eq!(reloaded.keybindings, loaded.keybindings);
+    }
+
+    #[test]
+    fn burst_delay_is_read_from_its_old_name() {
+        let path = unique_path();
+        fs::write(
+            &path,
+            r#"{"version":1,"injection_char_delay_ms":15,"profiles":[{"name":"Remote","injection_char_delay_ms":40}]}"#,
+        )
+        .unwrap();
+        let loaded = load_settings_from_path(&path);
+        let _ = fs::remove_file(&path);
+        assert!(take_backup(&path, "v1").is_some());
+        assert_eq!(loaded.injection_burst_delay_ms, 15);
+        assert_eq!(loaded.profiles[0].injection_burst_delay_ms, 40);
+    }
+
+    #[test]
+    fn newer_files_load_what_is_known() {
+        let path = unique_path();
+        fs::write(&path, r#"{"version":99,"api_key":"sk_future","hologram_mode":true}"#).unwrap();
+        let loaded = load_settings_from_path(&path);
+        let _ = fs::remove_file(&path);
+        assert_eq!(loaded.api_key, "sk_future");
+        assert_eq!(loaded.version, SETTINGS_VERSION);
+        assert!(take_backup(&path, "v99").unwrap().contains("hologram_mode"));
+    }
+
+    #[test]
+    fn switching_profiles_swaps_account_and_language() {
+        let mut settings = AppSettings {
+            api_key: "sk_work".to_string(),
+            speech_language: "en-US".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(settings.profile_names(), vec![DEFAULT_PROFILE]);
+        assert!(settings.save_profile_as("Personal"));
+        settings.api_key = "sk_home".to_string();
+        settings.speech_language = "de-DE".to_string();
+        settings.preview_before_inject = true;
+        settings.format_numbers = true;
+        settings.formatters = vec![FormatterKind::SnakeCase];
+        settings.set_binding(HotkeyAction::ToggleDictation, "Ctrl+F9");
+
+        assert!(settings.switch_profile(DEFAULT_PROFILE));
+        assert_eq!(settings.active_profile, DEFAULT_PROFILE);
+        assert_eq!(settings.api_key, "sk_work");
+        assert_eq!(settings.speech_language, "en-US");
+        assert!(!settings.preview_before_inject);
+        assert!(!settings.format_numbers);
+        assert!(settings.formatters.is_empty());
+        // Global settings are not part of a profile.
+        assert_eq!(settings.binding(HotkeyAction::ToggleDictation), Some("Ctrl+F9"));
+
+        assert!(settings.switch_profile("Personal"));
+        assert_eq!(settings.api_key, "sk_home");
+        assert_eq!(settings.speech_language, "de-DE");
+        assert!(settings.preview_before_inject);
+        assert!(settings.format_numbers);
+        assert_eq!(settings.formatters, vec![FormatterKind::SnakeCase]);
+        assert_eq!(settings.number_locale_tag(), "de-DE");
+        assert!(!settings.switch_profile("Missing"));
+        assert_eq!(settings.profile_names(), vec![DEFAULT_PROFILE, "Personal"]);
+        assert_eq!(settings.next_profile_name(), DEFAULT_PROFILE);
+        assert!(settings.switch_profile(DEFAULT_PROFILE));
+        assert_eq!(settings.next_profile_name(), "Personal");
+    }
+
+    #[test]
+    fn deleting_profile_loads_the_next_one() {
+        let mut settings = AppSettings::default();
+        assert!(!settings.delete_active_profile());
+        assert_eq!(settings.profiles, vec![Profile::default()]);
+
+        settings.save_profile_as("Spanish");
+        settings.speech_language = "es-ES".to_string();
+        assert!(settings.delete_active_profile());
+        assert_eq!(settings.active_profile, DEFAULT_PROFILE);
+        assert_eq!(settings.speech_language, "en-US");
+        assert_eq!(settings.profile_names(), vec![DEFAULT_PROFILE]);
+    }
+
+    #[test]
+    fn window_positions_off_the_desktop_are_not_restored() {
+        let geometry = WindowGeometry {
+            x: -1200,
+            y: 80,
+            width: 1280,
+            height: 900,
+            maximized: false,
+        };
+        // A second monitor to the left of the primary one.
+        assert!(geometry.is_on_screen(-1920, 0, 3840, 1080));
+        // The same layout with the left monitor unplugged.
+        assert!(!geometry.is_on_screen(0, 0, 1920, 1080));
+        let corner = WindowGeometry { x: 1900, y: 1050, ..geometry };
+        assert!(!corner.is_on_screen(0, 0, 1920, 1080));
+    }
+
+    #[test]
+    fn vocabulary_skips_blanks_and_repeats() {
+        let terms = parse_vocabulary("  Kubernetes \n\nkubernetes\n11th Echo\n");
+        assert_eq!(terms, vec!["Kubernetes".to_string(), "11th Echo".to_string()]);
+        assert_eq!(parse_vocabulary(&format_vocabulary(&terms)), terms);
+    }
 }
@@ -0,0 +1,313 @@
+// Keeps a single copy of the app running. The first launch takes the
+// instance lock (a named mutex on Windows, a Unix socket elsewhere) and
+// listens for commands; later launches send theirs and exit instead of
+// registering a second tray icon and set of hotkeys.
+//
+// The same channel doubles as an external trigger for button boxes and
+// scripts: write one command per line (`start`, `stop`, `cancel`, `toggle`
+// or `show`) to `\\.\pipe\11th_echo` on Windows, or to `11th_echo.sock` in
+// the runtime directory elsewhere, e.g. `echo toggle > \\.\pipe\11th_echo`.
+
+use crate::error::{EchoError, EchoResult};
+use std::io::Write;
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tracing::{info, warn};
+#[cfg(windows)]
+use windows::core::w;
+#[cfg(windows)]
+use windows::Win32::Foundation::{CloseHandle, ERROR_ALREADY_EXISTS, HANDLE};
+#[cfg(windows)]
+use windows::Win32::System::Threading::CreateMutexW;
+
+/// Makes a second launch toggle recording instead of showing the window.
+pub const TOGGLE_ARG: &str = "--toggle";
+
+/// Passed to the copy started by "Restart as administrator"; it waits for
+/// the instance that started it to exit instead of handing over to it.
+pub const RELAUNCH_ARG: &str = "--relaunched";
+
+/// How long a relaunched copy waits for the previous instance to let go.
+#[cfg(windows)]
+const RELAUNCH_WAIT: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[cfg(windows)]
+const PIPE_NAME: &str = r"\\.\pipe\11th_echo";
+
+/// What a second launch or an external trigger asks the running instance
+/// to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstanceCommand {
+    Show,
+    Start,
+    Stop,
+    Cancel,
+    Toggle,
+}
+
+impl InstanceCommand {
+    pub const ALL: [InstanceCommand; 5] = [
+        InstanceCommand::Show,
+        InstanceCommand::Start,
+        InstanceCommand::Stop,
+        InstanceCommand::Cancel,
+        InstanceCommand::Toggle,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            InstanceCommand::Show => "show",
+            InstanceCommand::Start => "start",
+            InstanceCommand::Stop => "stop",
+            InstanceCommand::Cancel => "cancel",
+            InstanceCommand::Toggle => "toggle",
+        }
+    }
+
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        Self::ALL.into_iter().find(|command| command.label().eq_ignore_ascii_case(line))
+    }
+
+    /// The command for this process's own command line.
+    pub fn from_args(mut args: impl Iterator<Item = String>) -> Self {
+        if args.any(|arg| arg == TOGGLE_ARG) {
+            InstanceCommand::Toggle
+        } else {
+            InstanceCommand::Show
+        }
+    }
+}
+
+pub enum Acquired {
+    /// This is the only instance; keep the guard for the life of the app.
+    Primary(InstanceGuard),
+    /// Another instance is running and has been sent the command.
+    Forwarded,
+}
+
+/// Held by the running instance. Dropping it releases the lock.
+pub struct InstanceGuard {
+    #[cfg(windows)]
+    mutex: HANDLE,
+    #[cfg(unix)]
+    listener: std::os::unix::net::UnixListener,
+    #[cfg(unix)]
+    path: PathBuf,
+}
+
+// The mutex handle is only closed on drop, which is fine from any thread.
+#[cfg(windows)]
+unsafe impl Send for InstanceGuard {}
+
+impl Drop for InstanceGuard {
+    fn drop(&mut self) {
+        #[cfg(windows)]
+        unsafe {
+            let _ = CloseHandle(self.mutex);
+        }
+        #[cfg(unix)]
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Takes the instance lock, or hands `command` to the instance holding it.
+/// With `relaunched` it first waits for the holder to exit.
+pub fn acquire(command: InstanceCommand, relaunched: bool) -> EchoResult<Acquired> {
+    #[cfg(windows)]
+    {
+        let started = std::time::Instant::now();
+        loop {
+            let mutex = unsafe { CreateMutexW(None, true, w!("Local\\11thEcho.SingleInstance")) }
+                .map_err(|e| EchoError::config(format!("Could not create the instance lock: {}", e)))?;
+            if windows::core::Error::from_win32().code() != ERROR_ALREADY_EXISTS.to_hresult() {
+                return Ok(Acquired::Primary(InstanceGuard { mutex }));
+            }
+            unsafe {
+                let _ = CloseHandle(mutex);
+            }
+            if !relaunched || started.elapsed() >= RELAUNCH_WAIT {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(200));
+        }
+        // Still starting up or hung; either way it owns the tray and hotkeys.
+        let forwarded = std::fs::OpenOptions::new()
+            .write(true)
+            .open(PIPE_NAME)
+            .map_err(|e| EchoError::config(format!("11th Echo is already running but did not answer: {}", e)))
+            .and_then(|pipe| send(pipe, command));
+        if let Err(e) = forwarded {
+            warn!("⚠️ {}", e);
+        }
+        Ok(Acquired::Forwarded)
+    }
+    #[cfg(unix)]
+    {
+        // Only Windows relaunches itself, see elevation::relaunch_elevated.
+        let _ = relaunched;
+        acquire_at(&socket_path(), command)
+    }
+}
+
+#[cfg(unix)]
+fn socket_path() -> PathBuf {
+    let base = dirs_next::runtime_dir().unwrap_or_else(std::env::temp_dir);
+    base.join("11th_echo.sock")
+}
+
+#[cfg(unix)]
+fn acquire_at(path: &Path, command: InstanceCommand) -> EchoResult<Acquired> {
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    if let Ok(stream) = UnixStream::connect(path) {
+        if let Err(e) = send(stream, command) {
+            warn!("⚠️ {}", e);
+        }
+        return Ok(Acquired::Forwarded);
+    }
+    // Nobody answered, so any socket file is left over from a crash.
+    let _ = std::fs::remove_file(path);
+    let listener = UnixListener::bind(path)
+        .map_err(|e| EchoError::config(format!("Could not create {}: {}", path.display(), e)))?;
+    Ok(Acquired::Primary(InstanceGuard {
+        listener,
+        path: path.to_path_buf(),
+    }))
+}
+
+fn send(mut stream: impl Write, command: InstanceCommand) -> EchoResult<()> {
+    writeln!(stream, "{}", command.label())
+        .map_err(|e| EchoError::config(format!("Could not reach the running instance: {}", e)))
+}
+
+impl InstanceGuard {
+    /// Accepts commands from later launches until the runtime shuts down.
+    /// Must be polled inside the tokio runtime.
+    pub async fn serve(self, on_command: impl Fn(InstanceCommand) + Send + Sync + 'static) {
+        let on_command = std::sync::Arc::new(on_command);
+        #[cfg(windows)]
+        {
+            use tokio::net::windows::named_pipe::ServerOptions;
+
+            let mut server = match ServerOptions::new().first_pipe_instance(true).create(PIPE_NAME) {
+                Ok(server) => server,
+                Err(e) => {
+                    warn!("⚠️ Could not listen for other launches: {}", e);
+                    return;
+                }
+            };
+            loop {
+                if let Err(e) = server.connect().await {
+                    warn!("⚠️ Instance pipe connection failed: {}", e);
+                    continue;
+                }
+                let connected = server;
+                server = match ServerOptions::new().create(PIPE_NAME) {
+                    Ok(server) => server,
+                    Err(e) => {
+                        warn!("⚠️ Could not reopen the instance pipe: {}", e);
+                        return;
+                    }
+                };
+                tokio::spawn(read_commands(connected, on_command.clone()));
+            }
+        }
+        #[cfg(unix)]
+        {
+            let listener = match self
+                .listener
+                .try_clone()
+                .and_then(|l| l.set_nonblocking(true).map(|()| l))
+                .and_then(tokio::net::UnixListener::from_std)
+            {
+                Ok(listener) => listener,
+                Err(e) => {
+                    warn!("⚠️ Could not listen for other launches: {}", e);
+                    return;
+                }
+            };
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        tokio::spawn(read_commands(stream, on_command.clone()));
+                    }
+                    Err(e) => warn!("⚠️ Instance socket connection failed: {}", e),
+                }
+            }
+        }
+    }
+}
+
+async fn read_commands<R, F>(stream: R, on_command: std::sync::Arc<F>)
+where
+    R: AsyncRead + Unpin,
+    F: Fn(InstanceCommand),
+{
+    let mut lines = BufReader::new(stream).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        match InstanceCommand::parse(&line) {
+            Some(command) => {
+                info!("📨 Another launch asked to {}", command.label());
+                on_command(command);
+            }
+            None => warn!("⚠️ Ignoring unknown instance command {:?}", line.trim()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InstanceCommand, TOGGLE_ARG};
+
+    #[test]
+    fn commands_parse_from_lines_and_args() {
+        for command in InstanceCommand::ALL {
+            assert_eq!(InstanceCommand::parse(&format!(" {}\r\n", command.label())), Some(command));
+        }
+        assert_eq!(InstanceCommand::parse("STOP"), Some(InstanceCommand::Stop));
+        assert_eq!(InstanceCommand::parse("quit"), None);
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>().into_iter();
+        assert_eq!(InstanceCommand::from_args(args(&["--minimized"])), InstanceCommand::Show);
+        assert_eq!(InstanceCommand::from_args(args(&[TOGGLE_ARG])), InstanceCommand::Toggle);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn later_launches_and_triggers_reach_the_running_instance() {
+        use super::{acquire_at, Acquired};
+        use std::time::{SystemTime, UNIX_EPOCH};
+        use tokio::sync::mpsc;
+
+        let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        let path = std::env::temp_dir().join(format!("eleventh_echo_{}.sock", stamp));
+        // A socket file left behind by a crashed instance doesn't count.
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+
+        let Ok(Acquired::Primary(guard)) = acquire_at(&path, InstanceCommand::Show) else {
+            panic!("first launch should own the lock");
+        };
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let server = tokio::spawn(guard.serve(move |command| {
+            let _ = tx.send(command);
+        }));
+
+        let second = tokio::task::spawn_blocking({
+            let path = path.clone();
+            move || acquire_at(&path, InstanceCommand::Toggle)
+        });
+        assert!(matches!(second.await.unwrap(), Ok(Acquired::Forwarded)));
+        assert_eq!(rx.recv().await, Some(InstanceCommand::Toggle));
+
+        // External triggers may send several commands over one connection.
+        let mut trigger = std::os::unix::net::UnixStream::connect(&path).unwrap();
+        std::io::Write::write_all(&mut trigger, b"start\nbogus\ncancel\n").unwrap();
+        drop(trigger);
+        assert_eq!(rx.recv().await, Some(InstanceCommand::Start));
+        assert_eq!(rx.recv().await, Some(InstanceCommand::Cancel));
+        server.abort();
+        let _ = server.await;
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,176 @@
+// Voice snippets: saying "insert <name>" types a saved block of text in
+// place of the phrase, with {date}/{time} style placeholders filled in.
+
+use crate::commands::normalize;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// The spoken word that introduces a snippet name.
+pub const TRIGGER: &str = "insert";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snippet {
+    pub name: String,
+    /// May span several lines; see [`expand`] for placeholders.
+    pub body: String,
+}
+
+/// The snippet whose name makes up the whole of `text` after the trigger
+/// word. Case and punctuation are ignored, so "Insert signature." matches
+/// a snippet named "signature".
+pub fn match_snippet<'a>(text: &str, snippets: &'a [Snippet]) -> Option<&'a Snippet> {
+    let spoken = normalize(text);
+    let name = spoken.strip_prefix(TRIGGER)?.strip_prefix(' ')?;
+    snippets.iter().find(|snippet| normalize(&snippet.name) == name)
+}
+
+/// Fills in `{date}`, `{time}`, `{datetime}`, `{weekday}` and `{year}`.
+/// Anything else in braces is left as written.
+pub fn expand(body: &str, now: NaiveDateTime) -> String {
+    let mut output = String::with_capacity(body.len());
+    let mut rest = body;
+    while let Some(open) = rest.find('{') {
+        output.push_str(&rest[..open]);
+        let after = &rest[open..];
+        let Some(close) = after.find('}') else {
+            // An unclosed brace: keep the remainder as written.
+            output.push_str(after);
+            return output;
+        };
+        let format = match &after[1..close] {
+            "date" => Some("%Y-%m-%d"),
+            "time" => Some("%H:%M"),
+            "datetime" => Some("%Y-%m-%d %H:%M"),
+            "weekday" => Some("%A"),
+            "year" => Some("%Y"),
+            _ => None,
+        };
+        match format {
+            Some(format) => output.push_str(&now.format(format).to_string()),
+            None => output.push_str(&after[..=close]),
+        }
+        rest = &after[close + 1..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Parses the settings text format: a `[name]` line starts each snippet and
+/// the lines below it, up to the next header, are its body.
+///
+/// ```text
+/// [signature]
+/// Best regards,
+/// Jane Doe
+///
+/// [stamp]
+/// Reviewed on {date} at {time}
+/// ```
+pub fn parse_snippets(text: &str) -> Result<Vec<Snippet>, String> {
+    let mut snippets: Vec<Snippet> = Vec::new();
+    let mut body_lines: Vec<&str> = Vec::new();
+    for (index, raw_line) in text.lines().enumerate() {
+        let line = raw_line.trim_end();
+        let header = line
+            .trim_start()
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'));
+        let Some(name) = header else {
+            if snippets.is_empty() && !line.trim().is_empty() {
+                return Err(format!("Line {}: text before the first [name] header", index + 1));
+            }
+            body_lines.push(line);
+            continue;
+        };
+        if let Some(last) = snippets.last_mut() {
+            last.body = join_body(&body_lines);
+        }
+        body_lines.clear();
+        let name = normalize(name);
+        if name.is_empty() {
+            return Err(format!("Line {}: empty snippet name", index + 1));
+        }
+        if snippets.iter().any(|snippet| snippet.name == name) {
+            return Err(format!("Line {}: [{}] is defined more than once", index + 1, name));
+        }
+        snippets.push(Snippet {
+            name,
+            body: String::new(),
+        });
+    }
+    if let Some(last) = snippets.last_mut() {
+        last.body = join_body(&body_lines);
+    }
+    Ok(snippets)
+}
+
+/// Inverse of [`parse_snippets`], used to populate the settings text box.
+pub fn format_snippets(snippets: &[Snippet]) -> String {
+    snippets
+        .iter()
+        .map(|snippet| format!("[{}]\n{}", snippet.name, snippet.body))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Blank lines around a body only separate snippets in the text box.
+fn join_body(lines: &[&str]) -> String {
+    lines.join("\n").trim_matches('\n').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expand, format_snippets, match_snippet, parse_snippets, Snippet};
+    use chrono::NaiveDate;
+
+    fn snippet(name: &str, body: &str) -> Snippet {
+        Snippet {
+            name: name.to_string(),
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn trigger_and_name_must_make_up_the_whole_phrase() {
+        let snippets = vec![snippet("signature", "Jane"), snippet("home address", "1 Main St")];
+        assert_eq!(match_snippet("Insert signature.", &snippets), Some(&snippets[0]));
+        assert_eq!(match_snippet("insert, home address", &snippets), Some(&snippets[1]));
+        assert_eq!(match_snippet("please insert signature", &snippets), None);
+        assert_eq!(match_snippet("insert signature here", &snippets), None);
+        assert_eq!(match_snippet("insertsignature", &snippets), None);
+    }
+
+    #[test]
+    fn placeholders_use_the_given_time() {
+        let now = NaiveDate::from_ymd_opt(2024, 3, 8)
+            .unwrap()
+            .and_hms_opt(14, 5, 0)
+            .unwrap();
+        assert_eq!(
+            expand("Signed {date} {time} ({weekday}, {year})", now),
+            "Signed 2024-03-08 14:05 (Friday, 2024)"
+        );
+        assert_eq!(expand("{datetime}", now), "2024-03-08 14:05");
+        assert_eq!(expand("{name} and {date", now), "{name} and {date");
+    }
+
+    #[test]
+    fn snippets_roundtrip_through_text() {
+        let text = "[Signature]\nBest regards,\n\nJane Doe\n\n\n[stamp]\nReviewed {date}\n";
+        let snippets = parse_snippets(text).unwrap();
+        assert_eq!(
+            snippets,
+            vec![snippet("signature", "Best regards,\n\nJane Doe"), snippet("stamp", "Reviewed {date}")]
+        );
+        assert_eq!(parse_snippets(&format_snippets(&snippets)).unwrap(), snippets);
+        assert!(parse_snippets("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn snippet_errors_name_the_line() {
+        assert!(parse_snippets("hello\n[a]").unwrap_err().starts_with("Line 1"));
+        let e = parse_snippets("[a]\nx\n[A]\ny").unwrap_err();
+        assert!(e.starts_with("Line 3") && e.contains("more than once"));
+        assert!(parse_snippets("[ ]").unwrap_err().contains("empty snippet name"));
+    }
+}
@@ -1,9 +1,14 @@
-#[derive(Debug, Clone)]
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub enum RecordingState {
+    #[default]
     Idle,
     BufferingPreConnect,
     Connecting,
     Recording,
+    Paused,
     Finalizing,
     Error,
 }
@@ -19,9 +24,14 @@ impl RecordingState {
             RecordingState::BufferingPreConnect
                 | RecordingState::Connecting
                 | RecordingState::Recording
+                | RecordingState::Paused
         )
     }
 
+    pub fn is_paused(&self) -> bool {
+        matches!(self, RecordingState::Paused)
+    }
+
     pub fn transition_to_connecting(&mut self) {
         if matches!(self, RecordingState::BufferingPreConnect) {
             *self = RecordingState::Connecting;
@@ -37,6 +47,23 @@ impl RecordingState {
         }
     }
 
+    pub fn transition_to_paused(&mut self) {
+        if matches!(
+            self,
+            RecordingState::BufferingPreConnect
+                | RecordingState::Connecting
+                | RecordingState::Recording
+        ) {
+            *self = RecordingState::Paused;
+        }
+    }
+
+    pub fn transition_to_resumed(&mut self) {
+        if matches!(self, RecordingState::Paused) {
+            *self = RecordingState::Recording;
+        }
+    }
+
     pub fn transition_to_finalizing(&mut self) {
         if !matches!(self, RecordingState::Finalizing) {
             *self = RecordingState::Finalizing;
@@ -46,17 +73,128 @@ impl RecordingState {
     pub fn transition_to_idle(&mut self) {
         *self = RecordingState::Idle;
     }
+
+    /// Lower-case name used in logs and the control API.
+    pub fn label(&self) -> &'static str {
+        match self {
+            RecordingState::Idle => "idle",
+            RecordingState::BufferingPreConnect => "buffering",
+            RecordingState::Connecting => "connecting",
+            RecordingState::Recording => "recording",
+            RecordingState::Paused => "paused",
+            RecordingState::Finalizing => "finalizing",
+            RecordingState::Error => "error",
+        }
+    }
+}
+
+/// One transition, tagged with the session it belongs to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateChange {
+    pub session: u64,
+    pub state: RecordingState,
+}
+
+struct Current {
+    session: u64,
+    state: RecordingState,
 }
 
-impl Default for RecordingState {
+/// The one authoritative `RecordingState`. Each session mutates it through
+/// the `SessionState` handle from `begin_session`, and every transition is
+/// broadcast, so the tray, control API, status bar and session log see each
+/// step even when states follow each other within milliseconds.
+#[derive(Clone)]
+pub struct StateBus {
+    current: Arc<Mutex<Current>>,
+    tx: broadcast::Sender<StateChange>,
+}
+
+impl Default for StateBus {
     fn default() -> Self {
-        RecordingState::Idle
+        let (tx, _) = broadcast::channel(64);
+        Self {
+            current: Arc::new(Mutex::new(Current {
+                session: 0,
+                state: RecordingState::Idle,
+            })),
+            tx,
+        }
+    }
+}
+
+impl StateBus {
+    /// Starts a new session in `BufferingPreConnect`. Handles from earlier
+    /// sessions stop having any effect.
+    pub fn begin_session(&self) -> SessionState {
+        let mut current = self.current.lock().unwrap();
+        current.session += 1;
+        current.state = RecordingState::BufferingPreConnect;
+        let _ = self.tx.send(StateChange {
+            session: current.session,
+            state: current.state.clone(),
+        });
+        SessionState {
+            bus: self.clone(),
+            session: current.session,
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<StateChange> {
+        self.tx.subscribe()
+    }
+}
+
+/// A session's handle on the `StateBus`.
+#[derive(Clone)]
+pub struct SessionState {
+    bus: StateBus,
+    session: u64,
+}
+
+impl SessionState {
+    pub fn id(&self) -> u64 {
+        self.session
+    }
+
+    /// The state of this session; `Idle` once a newer one has started.
+    pub fn get(&self) -> RecordingState {
+        let current = self.bus.current.lock().unwrap();
+        if current.session == self.session {
+            current.state.clone()
+        } else {
+            RecordingState::Idle
+        }
+    }
+
+    /// Runs `f` on the state and broadcasts it if it changed. A stale
+    /// handle, left over in a task of an earlier session, runs `f` on a
+    /// throwaway copy so it can't clobber the current session.
+    pub fn update<R>(&self, f: impl FnOnce(&mut RecordingState) -> R) -> R {
+        let mut current = self.bus.current.lock().unwrap();
+        if current.session != self.session {
+            let mut scratch = RecordingState::Idle;
+            return f(&mut scratch);
+        }
+        let before = current.state.clone();
+        let result = f(&mut current.state);
+        if current.state != before {
+            let _ = self.bus.tx.send(StateChange {
+                session: self.session,
+                state: current.state.clone(),
+            });
+        }
+        result
+    }
+
+    pub fn set(&self, state: RecordingState) {
+        self.update(|current| *current = state);
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::RecordingState;
+    use super::{RecordingState, StateBus, StateChange};
 
     #[test]
     fn start_stop_guards_work() {
@@ -95,4 +233,82 @@ mod tests {
         state.transition_to_recording();
         assert!(matches!(state, RecordingState::Recording));
     }
+
+    #[test]
+    fn pause_and_resume_round_trip() {
+        let mut state = RecordingState::Recording;
+        state.transition_to_paused();
+        assert!(state.is_paused());
+        assert!(state.can_stop());
+        assert!(!state.can_start());
+        state.transition_to_resumed();
+        assert!(matches!(state, RecordingState::Recording));
+    }
+
+    #[test]
+    fn pause_is_noop_when_idle_or_finalizing() {
+        let mut state = RecordingState::Idle;
+        state.transition_to_paused();
+        assert!(matches!(state, RecordingState::Idle));
+        state = RecordingState::Finalizing;
+        state.transition_to_paused();
+        assert!(matches!(state, RecordingState::Finalizing));
+    }
+
+    #[test]
+    fn recording_transition_does_not_unpause() {
+        let mut state = RecordingState::Paused;
+        state.transition_to_recording();
+        assert!(state.is_paused());
+    }
+
+    #[test]
+    fn bus_broadcasts_every_change_once() {
+        let bus = StateBus::default();
+        let mut rx = bus.subscribe();
+        let session = bus.begin_session();
+        session.update(|s| s.transition_to_connecting());
+        session.update(|s| s.transition_to_connecting());
+        session.update(|s| s.transition_to_recording());
+        let paused = session.update(|s| {
+            s.transition_to_paused();
+            s.is_paused()
+        });
+        assert!(paused);
+
+        let states: Vec<_> = std::iter::from_fn(|| rx.try_recv().ok()).map(|change| change.state).collect();
+        assert_eq!(
+            states,
+            [
+                RecordingState::BufferingPreConnect,
+                RecordingState::Connecting,
+                RecordingState::Recording,
+                RecordingState::Paused,
+            ]
+        );
+        assert_eq!(session.get().label(), "paused");
+    }
+
+    #[test]
+    fn stale_session_handles_are_ignored() {
+        let bus = StateBus::default();
+        let old = bus.begin_session();
+        let new = bus.begin_session();
+        let mut rx = bus.subscribe();
+
+        old.set(RecordingState::Error);
+        assert_eq!(old.update(|s| s.clone()), RecordingState::Idle);
+        assert_eq!(old.get(), RecordingState::Idle);
+        assert_eq!(new.get(), RecordingState::BufferingPreConnect);
+        assert!(rx.try_recv().is_err());
+
+        new.update(|s| s.transition_to_connecting());
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            StateChange {
+                session: new.id(),
+                state: RecordingState::Connecting,
+            }
+        );
+    }
 }
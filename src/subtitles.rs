@@ -0,0 +1,142 @@
+// SRT and WebVTT subtitles for a recorded session. Cue times are on the
+// recording's clock rather than the provider's, so the files line up with
+// the session WAV they are saved next to.
+
+use crate::audio::AudioTimeline;
+use chrono::{DateTime, Local};
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue {
+    pub start_secs: f64,
+    pub end_secs: f64,
+    pub text: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct SubtitleTrack {
+    started_at: DateTime<Local>,
+    cues: Vec<Cue>,
+}
+
+impl SubtitleTrack {
+    pub fn new(started_at: DateTime<Local>) -> Self {
+        Self {
+            started_at,
+            cues: Vec::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cues.is_empty()
+    }
+
+    /// Adds committed text. `span` is the provider's start and end; without
+    /// one the cue runs from the previous cue to the audio streamed so far.
+    pub fn push(&mut self, text: &str, span: Option<(f64, f64)>, timeline: &AudioTimeline) {
+        let text = text.trim();
+        if text.is_empty() {
+            return;
+        }
+        let previous_end = self.cues.last().map_or(0.0, |cue| cue.end_secs);
+        let (start_secs, end_secs) = match span {
+            Some((start, end)) => (timeline.capture_secs(start), timeline.capture_secs(end)),
+            None => (previous_end, timeline.capture_secs(timeline.streamed_secs())),
+        };
+        self.cues.push(Cue {
+            start_secs,
+            end_secs: end_secs.max(start_secs),
+            text: text.to_string(),
+        });
+    }
+
+    pub fn to_srt(&self) -> String {
+        let mut out = String::new();
+        for (i, cue) in self.cues.iter().enumerate() {
+            out.push_str(&format!(
+                "{}\n{} --> {}\n{}\n\n",
+                i + 1,
+                timestamp(cue.start_secs, ','),
+                timestamp(cue.end_secs, ','),
+                cue.text
+            ));
+        }
+        out
+    }
+
+    pub fn to_vtt(&self) -> String {
+        let mut out = format!("WEBVTT\n\nNOTE Capture started {}\n\n", self.started_at.to_rfc3339());
+        for cue in &self.cues {
+            out.push_str(&format!(
+                "{} --> {}\n{}\n\n",
+                timestamp(cue.start_secs, '.'),
+                timestamp(cue.end_secs, '.'),
+                cue.text
+            ));
+        }
+        out
+    }
+
+    /// Writes `.srt` and `.vtt` files beside `recording`, sharing its name.
+    pub fn save_beside(&self, recording: &Path) -> std::io::Result<()> {
+        std::fs::write(recording.with_extension("srt"), self.to_srt())?;
+        std::fs::write(recording.with_extension("vtt"), self.to_vtt())
+    }
+}
+
+/// `HH:MM:SS,mmm`, with `.` before the milliseconds for WebVTT.
+fn timestamp(secs: f64, millis_separator: char) -> String {
+    let total_ms = (secs.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        total_ms / 3_600_000,
+        total_ms / 60_000 % 60,
+        total_ms / 1000 % 60,
+        millis_separator,
+        total_ms % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{timestamp, SubtitleTrack};
+    use crate::audio::AudioTimeline;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn timestamps_use_each_formats_separator() {
+        assert_eq!(timestamp(0.0, ','), "00:00:00,000");
+        assert_eq!(timestamp(3723.4567, ','), "01:02:03,457");
+        assert_eq!(timestamp(61.5, '.'), "00:01:01.500");
+    }
+
+    #[test]
+    fn cues_are_shifted_onto_the_recording_clock() {
+        let mut timeline = AudioTimeline::new(16000);
+        timeline.record_streamed(0, 16000);
+        // The next two seconds of capture were never streamed.
+        timeline.record_streamed(48000, 32000);
+        let mut track = SubtitleTrack::new(timeline.started_at());
+        track.push("hello", Some((0.2, 0.8)), &timeline);
+        track.push("  ", Some((1.0, 1.2)), &timeline);
+        track.push("world", Some((1.5, 2.5)), &timeline);
+        track.push("untimed", None, &timeline);
+
+        let srt = track.to_srt();
+        assert!(srt.starts_with("1\n00:00:00,200 --> 00:00:00,800\nhello\n\n"));
+        assert!(srt.contains("2\n00:00:03,500 --> 00:00:04,500\nworld\n"));
+        assert!(srt.contains("3\n00:00:04,500 --> 00:00:05,000\nuntimed\n"));
+
+        let vtt = track.to_vtt();
+        assert!(vtt.starts_with("WEBVTT\n\nNOTE Capture started "));
+        assert!(vtt.contains("00:00:03.500 --> 00:00:04.500\nworld"));
+
+        let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        let wav = std::env::temp_dir().join(format!("eleventh_echo_subtitles_{}.wav", stamp));
+        track.save_beside(&wav).unwrap();
+        let saved = std::fs::read_to_string(wav.with_extension("srt")).unwrap();
+        let _ = std::fs::remove_file(wav.with_extension("srt"));
+        let _ = std::fs::remove_file(wav.with_extension("vtt"));
+        assert_eq!(saved, srt);
+    }
+}
@@ -0,0 +1,55 @@
+// Window theme. The palettes live in ui/appwindow.slint. Dark uses the
+// custom theme colors from settings. Light swaps in a fixed light palette.
+// High contrast is white on black with a yellow accent. This module only
+// picks one; "Follow system" is read from the OS when the window opens.
+
+use crate::i18n;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeMode {
+    #[default]
+    Dark,
+    Light,
+    System,
+    HighContrast,
+}
+
+impl ThemeMode {
+    pub const ALL: [ThemeMode; 4] = [
+        ThemeMode::Dark,
+        ThemeMode::Light,
+        ThemeMode::System,
+        ThemeMode::HighContrast,
+    ];
+
+    /// Also the values `theme-mode` in the .slint file compares against.
+    pub fn label(self) -> &'static str {
+        match self {
+            ThemeMode::Dark => "Dark",
+            ThemeMode::Light => "Light",
+            ThemeMode::System => "Follow system",
+            ThemeMode::HighContrast => "High contrast",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| i18n::is_label(mode.label(), label))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ThemeMode;
+
+    #[test]
+    fn theme_modes_round_trip_through_labels() {
+        for mode in ThemeMode::ALL {
+            assert_eq!(ThemeMode::from_label(mode.label()), Some(mode));
+        }
+        assert_eq!(ThemeMode::from_label("Follow system"), Some(ThemeMode::System));
+        assert_eq!(ThemeMode::from_label("High contrast"), Some(ThemeMode::HighContrast));
+        assert_eq!(ThemeMode::from_label("Sepia"), None);
+    }
+}
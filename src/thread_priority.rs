@@ -0,0 +1,60 @@
+// Keeps the audio worker ahead of ordinary threads so capture doesn't glitch
+// when the machine is busy. On Windows the thread joins the MMCSS "Pro Audio"
+// task, which the scheduler boosts the same way it boosts DAWs; if MMCSS is
+// unavailable the thread priority is raised instead.
+
+#[cfg(windows)]
+use tracing::{info, warn};
+#[cfg(windows)]
+use windows::Win32::Foundation::HANDLE;
+
+/// The calling thread's boost; the MMCSS registration is reverted on drop.
+pub struct PriorityBoost {
+    #[cfg(windows)]
+    mmcss: Option<HANDLE>,
+}
+
+/// Registers the calling thread with MMCSS, falling back to
+/// `THREAD_PRIORITY_HIGHEST`. A no-op on other platforms.
+#[cfg(windows)]
+pub fn boost_current_thread() -> PriorityBoost {
+    use windows::core::w;
+    use windows::Win32::System::Threading::{
+        AvSetMmThreadCharacteristicsW, AvSetMmThreadPriority, GetCurrentThread, SetThreadPriority, AVRT_PRIORITY_HIGH,
+        THREAD_PRIORITY_HIGHEST,
+    };
+
+    let mut task_index = 0u32;
+    match unsafe { AvSetMmThreadCharacteristicsW(w!("Pro Audio"), &mut task_index) } {
+        Ok(handle) => {
+            if let Err(e) = unsafe { AvSetMmThreadPriority(handle, AVRT_PRIORITY_HIGH) } {
+                warn!("⚠️ Could not raise the MMCSS priority: {}", e);
+            }
+            info!("🎚 Audio worker registered with MMCSS (Pro Audio)");
+            PriorityBoost { mmcss: Some(handle) }
+        }
+        Err(e) => {
+            warn!("⚠️ MMCSS registration failed ({}), raising the thread priority instead", e);
+            if let Err(e) = unsafe { SetThreadPriority(GetCurrentThread(), THREAD_PRIORITY_HIGHEST) } {
+                warn!("⚠️ Could not raise the audio thread priority: {}", e);
+            }
+            PriorityBoost { mmcss: None }
+        }
+    }
+}
+
+#[cfg(not(windows))]
+pub fn boost_current_thread() -> PriorityBoost {
+    PriorityBoost {}
+}
+
+#[cfg(windows)]
+impl Drop for PriorityBoost {
+    fn drop(&mut self) {
+        use windows::Win32::System::Threading::AvRevertMmThreadCharacteristics;
+
+        if let Some(handle) = self.mmcss.take() {
+            let _ = unsafe { AvRevertMmThreadCharacteristics(handle) };
+        }
+    }
+}
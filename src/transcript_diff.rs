@@ -0,0 +1,239 @@
+// Word-level comparison of two transcripts of the same session, for the
+// History tab's side-by-side view. Words are compared ignoring case and
+// surrounding punctuation, which providers disagree on without getting the
+// words wrong; the rows keep the words as each transcript wrote them.
+
+use crate::i18n::tr;
+
+/// Edit scripts longer than this are not searched for; what is left past
+/// that point shows up as one changed row.
+const MAX_EDITS: usize = 1000;
+
+/// One aligned row of the side-by-side view.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffRow {
+    pub left: String,
+    pub right: String,
+    /// The two sides differ; unchanged rows hold the same words.
+    pub changed: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TranscriptDiff {
+    pub rows: Vec<DiffRow>,
+    /// Words in the left transcript, the reference for the error rate.
+    pub reference_words: usize,
+    /// Substituted, inserted and deleted words.
+    pub edits: usize,
+}
+
+impl TranscriptDiff {
+    /// Word error rate of the right transcript against the left one.
+    pub fn word_error_rate(&self) -> f64 {
+        if self.reference_words == 0 {
+            return if self.edits == 0 { 0.0 } else { 1.0 };
+        }
+        self.edits as f64 / self.reference_words as f64
+    }
+
+    /// "4 of 120 words differ (WER 3.3%)" for the header above the rows.
+    pub fn summary(&self) -> String {
+        if self.edits == 0 {
+            return tr!("The transcripts match word for word");
+        }
+        tr!(
+            "{} of {} words differ (WER {}%)",
+            self.edits,
+            self.reference_words,
+            format!("{:.1}", self.word_error_rate() * 100.0)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+pub fn diff_words(left: &str, right: &str) -> TranscriptDiff {
+    let left: Vec<&str> = left.split_whitespace().collect();
+    let right: Vec<&str> = right.split_whitespace().collect();
+    let left_keys: Vec<String> = left.iter().map(|word| comparison_key(word)).collect();
+    let right_keys: Vec<String> = right.iter().map(|word| comparison_key(word)).collect();
+
+    let mut diff = TranscriptDiff {
+        reference_words: left.len(),
+        ..TranscriptDiff::default()
+    };
+    let (mut x, mut y) = (0, 0);
+    let mut pending: Option<(bool, Vec<&str>, Vec<&str>)> = None;
+    for op in edit_script(&left_keys, &right_keys) {
+        let changed = op != Op::Equal;
+        if pending.as_ref().is_some_and(|(kind, _, _)| *kind != changed) {
+            push_row(&mut diff, pending.take());
+        }
+        let (_, left_words, right_words) = pending.get_or_insert_with(|| (changed, Vec::new(), Vec::new()));
+        if op != Op::Insert {
+            left_words.push(left[x]);
+            x += 1;
+        }
+        if op != Op::Delete {
+            right_words.push(right[y]);
+            y += 1;
+        }
+    }
+    push_row(&mut diff, pending);
+    diff
+}
+
+fn push_row(diff: &mut TranscriptDiff, run: Option<(bool, Vec<&str>, Vec<&str>)>) {
+    let Some((changed, left, right)) = run else {
+        return;
+    };
+    if changed {
+        // A substitution counts once, the rest as insertions or deletions.
+        diff.edits += left.len().max(right.len());
+    }
+    diff.rows.push(DiffRow {
+        left: left.join(" "),
+        right: right.join(" "),
+        changed,
+    });
+}
+
+fn comparison_key(word: &str) -> String {
+    let trimmed = word.trim_matches(|c: char| !c.is_alphanumeric());
+    if trimmed.is_empty() { word } else { trimmed }.to_lowercase()
+}
+
+/// Shortest edit script from `a` to `b` (Myers' algorithm). Past
+/// `MAX_EDITS` the remainder is given as deletions then insertions.
+fn edit_script(a: &[String], b: &[String]) -> Vec<Op> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (a.len() + b.len()).min(MAX_EDITS) as isize;
+    let offset = max + 1;
+    // v[k + offset] is the furthest x reached on diagonal k = x - y.
+    let mut v = vec![0isize; 2 * max as usize + 3];
+    // trace[d] holds diagonals -(d + 1)..=(d + 1) as they were before step d.
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    let mut end = None;
+    'search: for d in 0..=max {
+        trace.push(v[(offset - d - 1) as usize..=(offset + d + 1) as usize].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let down = k == -d || (k != d && v[(k - 1 + offset) as usize] < v[(k + 1 + offset) as usize]);
+            let mut x = if down {
+                v[(k + 1 + offset) as usize]
+            } else {
+                v[(k - 1 + offset) as usize] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[(k + offset) as usize] = x;
+            if x >= n && y >= m {
+                end = Some((x, y));
+                break 'search;
+            }
+        }
+    }
+
+    let Some((mut x, mut y)) = end else {
+        return fallback_script(a, b);
+    };
+    let mut ops = Vec::with_capacity((n + m) as usize);
+    for d in (0..trace.len() as isize).rev() {
+        let snapshot = &trace[d as usize];
+        let at = |k: isize| snapshot[(k + d + 1) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) { k + 1 } else { k - 1 };
+        let prev_x = at(prev_k);
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            ops.push(Op::Equal);
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            ops.push(if x == prev_x { Op::Insert } else { Op::Delete });
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    ops.reverse();
+    ops
+}
+
+/// Keeps the common start and end and marks everything between as changed.
+fn fallback_script(a: &[String], b: &[String]) -> Vec<Op> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let mut ops = vec![Op::Equal; prefix];
+    ops.extend(std::iter::repeat_n(Op::Delete, a.len() - prefix - suffix));
+    ops.extend(std::iter::repeat_n(Op::Insert, b.len() - prefix - suffix));
+    ops.extend(std::iter::repeat_n(Op::Equal, suffix));
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_words, edit_script, fallback_script, DiffRow, Op};
+
+    fn row(left: &str, right: &str, changed: bool) -> DiffRow {
+        DiffRow {
+            left: left.to_string(),
+            right: right.to_string(),
+            changed,
+        }
+    }
+
+    #[test]
+    fn changed_words_are_grouped_between_unchanged_runs() {
+        let diff = diff_words(
+            "Send the report to Anna by Friday.",
+            "send the reports to Anna, by friday please",
+        );
+        assert_eq!(
+            diff.rows,
+            vec![
+                row("Send the", "send the", false),
+                row("report", "reports", true),
+                row("to Anna by Friday.", "to Anna, by friday", false),
+                row("", "please", true),
+            ]
+        );
+        assert_eq!(diff.reference_words, 7);
+        assert_eq!(diff.edits, 2);
+        assert_eq!(diff.summary(), "2 of 7 words differ (WER 28.6%)");
+
+        let same = diff_words("Hello world.", "hello  world");
+        assert_eq!(same.rows, vec![row("Hello world.", "hello world", false)]);
+        assert_eq!(same.summary(), "The transcripts match word for word");
+        assert_eq!(diff_words("", "").rows, Vec::new());
+        assert_eq!(diff_words("", "new words").word_error_rate(), 1.0);
+    }
+
+    #[test]
+    fn edit_scripts_are_minimal_and_fall_back_when_too_long() {
+        let words = |text: &str| text.split(' ').map(str::to_string).collect::<Vec<_>>();
+        let (a, b) = (words("a b c a b b a"), words("c b a b a c"));
+        let ops = edit_script(&a, &b);
+        assert_eq!(ops.iter().filter(|op| **op != Op::Equal).count(), 5);
+        assert_eq!(ops.iter().filter(|op| **op != Op::Insert).count(), a.len());
+        assert_eq!(ops.iter().filter(|op| **op != Op::Delete).count(), b.len());
+
+        let long_a: Vec<String> = (0..3000).map(|i| format!("a{}", i)).collect();
+        let long_b: Vec<String> = (0..3000).map(|i| format!("b{}", i)).collect();
+        assert_eq!(edit_script(&long_a, &long_b), fallback_script(&long_a, &long_b));
+        let ops = fallback_script(&words("x a b y"), &words("x c y"));
+        assert_eq!(ops, vec![Op::Equal, Op::Delete, Op::Delete, Op::Insert, Op::Equal]);
+    }
+}
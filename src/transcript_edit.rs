@@ -0,0 +1,184 @@
+// Hand edits to the Transcription tab. The editor shows the committed
+// segments one after another without the timestamps the list adds; saving
+// diffs the edited lines against the original ones, so each line that
+// survives or replaces one in place keeps the time it was spoken, even when
+// lines were added or removed above it.
+
+/// The editor text for the raw segments, one per line. A segment with line
+/// breaks of its own takes several lines.
+pub fn editor_text(raw: &[String]) -> String {
+    raw.join("\n")
+}
+
+/// The segments and list lines after an edit of `raw`, listed as `display`.
+/// Unchanged lines stay with their segment, and a changed line takes the
+/// segment of the original line it replaced; lines with no counterpart
+/// become segments without a time. Blank lines are dropped unless they sit
+/// unchanged inside a segment.
+pub fn apply_edit(raw: &[String], display: &[String], edited: &str) -> (Vec<String>, Vec<String>) {
+    let original: Vec<(usize, &str)> = raw
+        .iter()
+        .enumerate()
+        .flat_map(|(segment, text)| text.lines().map(move |line| (segment, line.trim())))
+        .collect();
+    let edited: Vec<&str> = edited.lines().map(str::trim).collect();
+    let owners = line_owners(&original, &edited);
+
+    let mut segments: Vec<(Option<usize>, Vec<&str>)> = Vec::new();
+    for (line, owner) in edited.iter().zip(owners) {
+        if owner.is_none() && line.is_empty() {
+            continue;
+        }
+        match segments.last_mut() {
+            Some((last, lines)) if owner.is_some() && *last == owner => lines.push(line),
+            _ => segments.push((owner, vec![line])),
+        }
+    }
+    segments
+        .into_iter()
+        .filter_map(|(owner, lines)| {
+            let text = lines.join("\n").trim().to_string();
+            (!text.is_empty()).then_some((owner, text))
+        })
+        .map(|(owner, text)| {
+            let time = owner.and_then(|segment| display.get(segment)).and_then(|shown| split_timestamp(shown).0);
+            let shown = match time {
+                Some(time) => format!("[{}] {}", time, text),
+                None => text.clone(),
+            };
+            (text, shown)
+        })
+        .unzip()
+}
+
+/// Which original segment each edited line belongs to. Lines common to both
+/// (a longest common subsequence) keep theirs; between two common lines,
+/// the edited lines take the removed originals' segments in order.
+fn line_owners(original: &[(usize, &str)], edited: &[&str]) -> Vec<Option<usize>> {
+    let (n, m) = (original.len(), edited.len());
+    // lcs[i][j]: common lines of original[i..] and edited[j..].
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if original[i].1 == edited[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut owners = vec![None; m];
+    let (mut i, mut j) = (0, 0);
+    let mut removed: Vec<usize> = Vec::new();
+    let mut added: Vec<usize> = Vec::new();
+    let pair_gap = |removed: &mut Vec<usize>, added: &mut Vec<usize>, owners: &mut Vec<Option<usize>>| {
+        let added_text = added.iter().filter(|&&line| !edited[line].is_empty());
+        for (&line, &replaced) in added_text.zip(removed.iter()) {
+            owners[line] = Some(original[replaced].0);
+        }
+        removed.clear();
+        added.clear();
+    };
+    while i < n || j < m {
+        if i < n && j < m && original[i].1 == edited[j] {
+            pair_gap(&mut removed, &mut added, &mut owners);
+            owners[j] = Some(original[i].0);
+            i += 1;
+            j += 1;
+        } else if j < m && (i == n || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            added.push(j);
+            j += 1;
+        } else {
+            removed.push(i);
+            i += 1;
+        }
+    }
+    pair_gap(&mut removed, &mut added, &mut owners);
+    owners
+}
+
+/// Splits a list line into the time in its leading "[HH:MM:SS]", if any,
+/// and the text after it.
+pub fn split_timestamp(line: &str) -> (Option<&str>, &str) {
+    match line.strip_prefix('[').and_then(|rest| rest.split_once("] ")) {
+        Some((time, text)) => (Some(time), text),
+        None => (None, line),
+    }
+}
+
+/// The text between the editor's selection anchor and cursor, given as
+/// UTF-8 byte offsets in either order. `None` when nothing is selected or
+/// the offsets don't fall on character boundaries of `text`.
+pub fn selection(text: &str, anchor: i32, cursor: i32) -> Option<&str> {
+    let start = usize::try_from(anchor.min(cursor)).ok()?;
+    let end = usize::try_from(anchor.max(cursor)).ok()?;
+    text.get(start..end).filter(|selected| !selected.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_edit, editor_text, selection, split_timestamp};
+
+    fn strings(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn edits_keep_each_line_timestamp() {
+        let display = vec![
+            "[10:00:01] their going home".to_string(),
+            "[10:00:05] ⚠ mumble (confidence 40%)".to_string(),
+        ];
+        let raw = vec!["their going home".to_string(), "mumble".to_string()];
+        assert_eq!(editor_text(&raw), "their going home\nmumble");
+
+        let (raw, display) = apply_edit(&raw, &display, "they're going home\n\n  mumbled  \nand a new line");
+        assert_eq!(raw, vec!["they're going home", "mumbled", "and a new line"]);
+        assert_eq!(
+            display,
+            vec!["[10:00:01] they're going home", "[10:00:05] mumbled", "and a new line"]
+        );
+        assert_eq!(split_timestamp("[10:00:05] ⚠ mumble"), (Some("10:00:05"), "⚠ mumble"));
+        assert_eq!(split_timestamp("and a new line"), (None, "and a new line"));
+    }
+
+    #[test]
+    fn timestamps_follow_lines_when_others_are_added_or_removed() {
+        let raw = strings(&["one", "two", "three"]);
+        let display = strings(&["[10:00:01] one", "[10:00:02] two", "[10:00:03] three"]);
+
+        let (raw_out, display_out) = apply_edit(&raw, &display, "inserted\none\nthree");
+        assert_eq!(raw_out, ["inserted", "one", "three"]);
+        assert_eq!(display_out, ["inserted", "[10:00:01] one", "[10:00:03] three"]);
+
+        let (_, display_out) = apply_edit(&raw, &display, "one\nTWO!\nthree\nfour");
+        assert_eq!(display_out, ["[10:00:01] one", "[10:00:02] TWO!", "[10:00:03] three", "four"]);
+    }
+
+    #[test]
+    fn multi_line_segments_stay_one_segment() {
+        let raw = strings(&["Me: hi\nThem: hello", "next"]);
+        let display = strings(&["[10:00:01] Me: hi\nThem: hello", "[10:00:09] next"]);
+        assert_eq!(editor_text(&raw), "Me: hi\nThem: hello\nnext");
+
+        let (raw_out, display_out) = apply_edit(&raw, &display, "Me: hi there\nThem: hello\nnext");
+        assert_eq!(raw_out, ["Me: hi there\nThem: hello", "next"]);
+        assert_eq!(display_out, ["[10:00:01] Me: hi there\nThem: hello", "[10:00:09] next"]);
+
+        let (raw_out, _) = apply_edit(&raw, &display, "Me: hi\nThem: hello\nnext");
+        assert_eq!(raw_out, raw);
+    }
+
+    #[test]
+    fn selections_are_byte_ranges_in_either_direction() {
+        let text = "Grüße aus Köln";
+        assert_eq!(selection(text, 0, 7), Some("Grüße"));
+        assert_eq!(selection(text, 17, 12), Some("Köln"));
+        assert_eq!(selection(text, 4, 4), None);
+        // Inside the "ü".
+        assert_eq!(selection(text, 3, 7), None);
+        assert_eq!(selection(text, -1, 7), None);
+        assert_eq!(selection(text, 0, 99), None);
+    }
+}
@@ -0,0 +1,128 @@
+// The Transcription tab's list. It outlives sessions: every committed
+// segment is appended, and `TranscriptRetention` decides whether a new
+// session starts from an empty list or adds to what is there.
+
+use crate::i18n;
+use crate::transcript_edit;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TranscriptRetention {
+    #[default]
+    ClearOnStart,
+    Accumulate,
+}
+
+impl TranscriptRetention {
+    pub const ALL: [TranscriptRetention; 2] = [TranscriptRetention::ClearOnStart, TranscriptRetention::Accumulate];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TranscriptRetention::ClearOnStart => "Clear when dictation starts",
+            TranscriptRetention::Accumulate => "Keep adding across sessions",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|retention| i18n::is_label(retention.label(), label))
+    }
+}
+
+/// The committed segments as listed (with timestamp and confidence note)
+/// and as spoken, which is what gets copied or edited.
+#[derive(Debug, Default)]
+pub struct TranscriptPanel {
+    display: Vec<String>,
+    raw: Vec<String>,
+    /// While the editor is open, how many segments it shows. Segments
+    /// committed meanwhile are kept after the edited ones.
+    editing: Option<usize>,
+}
+
+impl TranscriptPanel {
+    /// Called when a session starts; returns whether the list was cleared.
+    /// An open editor keeps the list, as if `retention` were `Accumulate`.
+    pub fn start_session(&mut self, retention: TranscriptRetention) -> bool {
+        if retention == TranscriptRetention::Accumulate || self.raw.is_empty() || self.editing.is_some() {
+            return false;
+        }
+        self.clear();
+        true
+    }
+
+    pub fn push(&mut self, display: String, raw: String) {
+        self.display.push(display);
+        self.raw.push(raw);
+    }
+
+    /// Opens the editor on the segments so far and returns its text.
+    pub fn begin_edit(&mut self) -> String {
+        self.editing = Some(self.raw.len());
+        transcript_edit::editor_text(&self.raw)
+    }
+
+    /// Swaps the hand-edited text in for the segments the editor showed,
+    /// see `transcript_edit::apply_edit`.
+    pub fn finish_edit(&mut self, edited: &str) {
+        let shown = self.editing.take().unwrap_or(self.raw.len()).min(self.raw.len());
+        let (mut raw, mut display) = transcript_edit::apply_edit(&self.raw[..shown], &self.display[..shown], edited);
+        raw.extend(self.raw.drain(shown..));
+        display.extend(self.display.drain(shown..));
+        self.raw = raw;
+        self.display = display;
+    }
+
+    pub fn clear(&mut self) {
+        self.display.clear();
+        self.raw.clear();
+        self.editing = None;
+    }
+
+    pub fn display(&self) -> &[String] {
+        &self.display
+    }
+
+    pub fn raw(&self) -> &[String] {
+        &self.raw
+    }
+
+    /// Every segment, one per line, for "Copy all".
+    pub fn copy_text(&self) -> String {
+        self.raw.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TranscriptPanel, TranscriptRetention};
+
+    #[test]
+    fn retention_decides_whether_sessions_share_the_list() {
+        let mut panel = TranscriptPanel::default();
+        assert!(!panel.start_session(TranscriptRetention::ClearOnStart));
+        panel.push("[09:00:00] first".to_string(), "first".to_string());
+
+        assert!(!panel.start_session(TranscriptRetention::Accumulate));
+        panel.push("[09:30:00] second".to_string(), "second".to_string());
+        assert_eq!(panel.copy_text(), "first\nsecond");
+        assert_eq!(panel.display(), ["[09:00:00] first", "[09:30:00] second"]);
+
+        assert!(panel.start_session(TranscriptRetention::ClearOnStart));
+        assert!(panel.raw().is_empty());
+        assert!(panel.display().is_empty());
+
+        panel.push("[10:00:00] third".to_string(), "third".to_string());
+        assert_eq!(panel.begin_edit(), "third");
+        // A session started mid-edit adds to the list instead of clearing it.
+        assert!(!panel.start_session(TranscriptRetention::ClearOnStart));
+        panel.push("[10:05:00] fourth".to_string(), "fourth".to_string());
+        panel.finish_edit("third, edited");
+        assert_eq!(panel.raw(), ["third, edited", "fourth"]);
+        assert_eq!(panel.display(), ["[10:00:00] third, edited", "[10:05:00] fourth"]);
+
+        for retention in TranscriptRetention::ALL {
+            assert_eq!(TranscriptRetention::from_label(retention.label()), Some(retention));
+        }
+    }
+}
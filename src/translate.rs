@@ -0,0 +1,185 @@
+use crate::i18n;
+use crate::llm::{self, LlmConfig};
+use crate::settings::AppSettings;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+const DEEPL_FREE_URL: &str = "https://api-free.deepl.com/v2/translate";
+const DEEPL_PRO_URL: &str = "https://api.deepl.com/v2/translate";
+
+/// Service used to translate committed transcripts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TranslationBackend {
+    DeepL,
+    /// The endpoint configured for LLM post-processing.
+    Llm,
+}
+
+impl TranslationBackend {
+    pub const ALL: [TranslationBackend; 2] = [TranslationBackend::DeepL, TranslationBackend::Llm];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TranslationBackend::DeepL => "DeepL",
+            TranslationBackend::Llm => "LLM endpoint",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|backend| i18n::is_label(backend.label(), label))
+    }
+}
+
+/// Snapshot of the translation settings, taken outside the settings lock.
+#[derive(Debug, Clone)]
+pub struct TranslationConfig {
+    pub backend: TranslationBackend,
+    pub deepl_api_key: String,
+    /// BCP-47 code; empty lets the backend detect it.
+    pub source_lang: String,
+    pub target_lang: String,
+    pub llm: LlmConfig,
+}
+
+impl TranslationConfig {
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        Self {
+            backend: settings.translation_backend,
+            deepl_api_key: settings.deepl_api_key.trim().to_string(),
+            source_lang: settings.translation_source_lang.trim().to_string(),
+            target_lang: settings.translation_target_lang.trim().to_string(),
+            llm: LlmConfig::from_settings(settings),
+        }
+    }
+}
+
+/// DeepL wants upper-case codes; only English and Portuguese targets keep a
+/// regional variant, and sources never do.
+fn deepl_lang(code: &str, target: bool) -> String {
+    let code = code.replace('_', "-").to_uppercase();
+    let mut parts = code.splitn(2, '-');
+    let base = parts.next().unwrap_or_default().to_string();
+    match parts.next() {
+        Some(region) if target && (base == "EN" || base == "PT") => format!("{}-{}", base, region),
+        _ if target && base == "EN" => "EN-US".to_string(),
+        _ => base,
+    }
+}
+
+/// Free-plan keys end in `:fx` and must use the free API host.
+fn deepl_url(api_key: &str) -> &'static str {
+    if api_key.ends_with(":fx") {
+        DEEPL_FREE_URL
+    } else {
+        DEEPL_PRO_URL
+    }
+}
+
+fn deepl_body(config: &TranslationConfig, text: &str) -> serde_json::Value {
+    let mut body = json!({
+        "text": [text],
+        "target_lang": deepl_lang(&config.target_lang, true),
+    });
+    if !config.source_lang.is_empty() {
+        body["source_lang"] = json!(deepl_lang(&config.source_lang, false));
+    }
+    body
+}
+
+fn llm_prompt(config: &TranslationConfig) -> String {
+    let source = if config.source_lang.is_empty() {
+        "the source language"
+    } else {
+        config.source_lang.as_str()
+    };
+    format!(
+        "Translate the text from {} to {}. Keep the tone and punctuation natural for the target language.",
+        source, config.target_lang
+    )
+}
+
+async fn translate_deepl(config: &TranslationConfig, text: &str) -> Result<String, String> {
+    if config.deepl_api_key.is_empty() {
+        return Err("Missing DeepL API key".to_string());
+    }
+    let proxy = crate::proxy::resolve(&config.llm.proxy_url)?;
+    let http = crate::proxy::http_client(proxy.as_ref(), Some(config.llm.timeout))?;
+    let response = http
+        .post(deepl_url(&config.deepl_api_key))
+        .header("Authorization", format!("DeepL-Auth-Key {}", config.deepl_api_key))
+        .json(&deepl_body(config, text))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let status = response.status();
+    let value: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+    if !status.is_success() {
+        return Err(format!("{}: {}", status, value));
+    }
+    value
+        .get("translations")
+        .and_then(|t| t.get(0))
+        .and_then(|t| t.get("text"))
+        .and_then(|t| t.as_str())
+        .map(|t| t.trim().to_string())
+        .ok_or_else(|| format!("Unexpected DeepL response: {}", value))
+}
+
+/// Translates `text` into the configured target language.
+pub async fn translate(config: &TranslationConfig, text: &str) -> Result<String, String> {
+    if config.target_lang.is_empty() {
+        return Err("No target language set".to_string());
+    }
+    match config.backend {
+        TranslationBackend::DeepL => translate_deepl(config, text).await,
+        TranslationBackend::Llm => {
+            let llm_config = LlmConfig {
+                prompt: llm_prompt(config),
+                ..config.llm.clone()
+            };
+            llm::post_process(&llm_config, text).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{deepl_body, deepl_lang, deepl_url, llm_prompt, TranslationBackend, TranslationConfig, DEEPL_FREE_URL};
+    use crate::llm::LlmConfig;
+    use crate::settings::AppSettings;
+
+    fn config(source: &str, target: &str) -> TranslationConfig {
+        TranslationConfig {
+            backend: TranslationBackend::DeepL,
+            deepl_api_key: "key:fx".into(),
+            source_lang: source.into(),
+            target_lang: target.into(),
+            llm: LlmConfig::from_settings(&AppSettings::default()),
+        }
+    }
+
+    #[test]
+    fn deepl_language_codes() {
+        assert_eq!(deepl_lang("de-DE", true), "DE");
+        assert_eq!(deepl_lang("en-gb", true), "EN-GB");
+        assert_eq!(deepl_lang("en", true), "EN-US");
+        assert_eq!(deepl_lang("pt_BR", true), "PT-BR");
+        assert_eq!(deepl_lang("en-US", false), "EN");
+    }
+
+    #[test]
+    fn deepl_body_omits_empty_source() {
+        let body = deepl_body(&config("", "fr-FR"), "hello");
+        assert_eq!(body["text"][0], "hello");
+        assert_eq!(body["target_lang"], "FR");
+        assert!(body.get("source_lang").is_none());
+        assert_eq!(deepl_body(&config("en-US", "fr"), "hi")["source_lang"], "EN");
+        assert_eq!(deepl_url("key:fx"), DEEPL_FREE_URL);
+    }
+
+    #[test]
+    fn llm_prompt_names_languages() {
+        assert!(llm_prompt(&config("German", "English")).starts_with("Translate the text from German to English."));
+        assert!(llm_prompt(&config("", "es")).contains("from the source language to es"));
+    }
+}
@@ -0,0 +1,167 @@
+// The tray icon only exists on Windows; the state mapping and icon pixels
+// are plain Rust so they stay testable everywhere. The status also drives
+// the toast window on every platform.
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use crate::i18n::tr;
+use crate::state::RecordingState;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Edge length of the generated status icons, in pixels.
+pub const ICON_SIZE: u32 = 32;
+
+/// What the tray icon and tooltip currently show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayStatus {
+    Idle,
+    Recording,
+    Finalizing,
+    Error,
+}
+
+impl TrayStatus {
+    /// Maps a session state to a tray status. `has_error` covers failures
+    /// that happen before a session exists (missing key, no microphone).
+    pub fn from_state(state: &RecordingState, has_error: bool) -> Self {
+        match state {
+            RecordingState::Error => TrayStatus::Error,
+            _ if has_error => TrayStatus::Error,
+            RecordingState::Idle => TrayStatus::Idle,
+            RecordingState::Finalizing => TrayStatus::Finalizing,
+            RecordingState::BufferingPreConnect
+            | RecordingState::Connecting
+            | RecordingState::Recording
+            | RecordingState::Paused => TrayStatus::Recording,
+        }
+    }
+
+    pub fn tooltip(self) -> String {
+        match self {
+            TrayStatus::Idle => tr!("11th Echo - Idle"),
+            TrayStatus::Recording => tr!("11th Echo - Recording"),
+            TrayStatus::Finalizing => tr!("11th Echo - Finalizing"),
+            TrayStatus::Error => tr!("11th Echo - Error"),
+        }
+    }
+
+    /// Label for the Start/Stop menu item.
+    pub fn toggle_label(self) -> String {
+        match self {
+            TrayStatus::Recording => tr!("Stop dictation"),
+            _ => tr!("Start dictation"),
+        }
+    }
+
+    /// Toast shown when the app enters this status; `status_text` is the
+    /// main window status line, which carries the error detail.
+    pub fn toast_text(self, status_text: &str) -> Option<String> {
+        match self {
+            TrayStatus::Idle => None,
+            TrayStatus::Recording => Some(tr!("🎤 Recording")),
+            TrayStatus::Finalizing => Some(tr!("⏳ Finalizing")),
+            TrayStatus::Error => Some(format!("⚠ {}", status_text)),
+        }
+    }
+
+    /// Dot color for the status icon; `None` keeps the app icon.
+    fn indicator_color(self) -> Option<[u8; 3]> {
+        match self {
+            TrayStatus::Idle => None,
+            TrayStatus::Recording => Some([0xef, 0x44, 0x44]),
+            TrayStatus::Finalizing => Some([0xf5, 0x9e, 0x0b]),
+            TrayStatus::Error => Some([0x9c, 0xa3, 0xaf]),
+        }
+    }
+
+    /// RGBA pixels (`ICON_SIZE` square) for this status, or `None` for the
+    /// regular app icon.
+    pub fn icon_rgba(self) -> Option<Vec<u8>> {
+        self.indicator_color().map(dot_rgba)
+    }
+}
+
+/// A filled circle with a dark rim on a transparent background.
+fn dot_rgba(color: [u8; 3]) -> Vec<u8> {
+    let size = ICON_SIZE as i32;
+    let center = (size as f32 - 1.0) / 2.0;
+    let radius = size as f32 / 2.0 - 1.0;
+    let mut pixels = Vec::with_capacity((size * size * 4) as usize);
+    for y in 0..size {
+        for x in 0..size {
+            let distance = ((x as f32 - center).powi(2) + (y as f32 - center).powi(2)).sqrt();
+            let pixel = if distance > radius {
+                [0, 0, 0, 0]
+            } else if distance > radius - 2.0 {
+                [0x11, 0x11, 0x1b, 0xff]
+            } else {
+                [color[0], color[1], color[2], 0xff]
+            };
+            pixels.extend_from_slice(&pixel);
+        }
+    }
+    pixels
+}
+
+/// `name` in the executable's folder, where the icons ship. The working
+/// directory can be anywhere; an elevated relaunch starts in System32.
+pub fn asset_path(name: &str) -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(name)))
+        .unwrap_or_else(|| PathBuf::from(name))
+}
+
+/// Opens `path` in the platform file manager, creating it first if needed.
+pub fn open_folder(path: &Path) -> io::Result<()> {
+    std::fs::create_dir_all(path)?;
+    let program = if cfg!(target_os = "windows") {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    Command::new(program).arg(path).spawn().map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TrayStatus, ICON_SIZE};
+    use crate::state::RecordingState;
+
+    #[test]
+    fn maps_recording_states_to_tray_status() {
+        assert_eq!(TrayStatus::from_state(&RecordingState::Idle, false), TrayStatus::Idle);
+        assert_eq!(TrayStatus::from_state(&RecordingState::Connecting, false), TrayStatus::Recording);
+        assert_eq!(TrayStatus::from_state(&RecordingState::Paused, false), TrayStatus::Recording);
+        assert_eq!(TrayStatus::from_state(&RecordingState::Finalizing, false), TrayStatus::Finalizing);
+        assert_eq!(TrayStatus::from_state(&RecordingState::Error, false), TrayStatus::Error);
+        assert_eq!(TrayStatus::from_state(&RecordingState::Idle, true), TrayStatus::Error);
+        assert_eq!(TrayStatus::Recording.toggle_label(), "Stop dictation");
+        assert_eq!(TrayStatus::Idle.toggle_label(), "Start dictation");
+    }
+
+    #[test]
+    fn toasts_announce_status_changes() {
+        assert_eq!(TrayStatus::Idle.toast_text("Idle"), None);
+        assert_eq!(TrayStatus::Recording.toast_text("Connecting...").as_deref(), Some("🎤 Recording"));
+        assert_eq!(TrayStatus::Finalizing.toast_text("").as_deref(), Some("⏳ Finalizing"));
+        assert_eq!(
+            TrayStatus::Error.toast_text("Network error").as_deref(),
+            Some("⚠ Network error")
+        );
+    }
+
+    #[test]
+    fn status_icon_is_a_colored_dot() {
+        assert!(TrayStatus::Idle.icon_rgba().is_none());
+        let pixels = TrayStatus::Recording.icon_rgba().unwrap();
+        assert_eq!(pixels.len(), (ICON_SIZE * ICON_SIZE * 4) as usize);
+        // Corner is transparent, center carries the status color.
+        assert_eq!(&pixels[..4], &[0, 0, 0, 0]);
+        let center = ((ICON_SIZE / 2) * ICON_SIZE + ICON_SIZE / 2) as usize * 4;
+        assert_eq!(&pixels[center..center + 4], &[0xef, 0x44, 0x44, 0xff]);
+    }
+}
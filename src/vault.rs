@@ -0,0 +1,149 @@
+// Encryption for transcripts kept on disk (the session history and the crash
+// autosave). A random secret lives in the OS keychain (Credential Manager on
+// Windows, Keychain on macOS, Secret Service on Linux) and the file key is
+// derived from it, so copying the files alone is not enough to read them.
+//
+// Sealed lines look like `enc1:<base64 of nonce + ciphertext>`; anything
+// else is read as plain text, so files can mix lines written before and
+// after encryption was turned on.
+
+use crate::error::{EchoError, EchoResult};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::{engine::general_purpose, Engine as _};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use std::sync::OnceLock;
+use tracing::{info, warn};
+
+const KEYCHAIN_SERVICE: &str = "11th Echo";
+const KEYCHAIN_ACCOUNT: &str = "transcript-encryption";
+const SEALED_PREFIX: &str = "enc1:";
+/// Separates this key from anything else derived from the same secret.
+const KEY_INFO: &[u8] = b"11th echo transcript files v1";
+const NONCE_LEN: usize = 12;
+
+pub struct Vault {
+    cipher: Aes256Gcm,
+}
+
+impl Vault {
+    pub fn from_secret(secret: &[u8]) -> Self {
+        let mut key = [0u8; 32];
+        Hkdf::<Sha256>::new(None, secret)
+            .expand(KEY_INFO, &mut key)
+            .expect("32 bytes is a valid HKDF-SHA256 output length");
+        Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)),
+        }
+    }
+
+    /// The vault for this user, creating the keychain secret the first time.
+    /// None if the keychain could not be used.
+    pub fn global() -> Option<&'static Vault> {
+        static VAULT: OnceLock<Option<Vault>> = OnceLock::new();
+        VAULT
+            .get_or_init(|| {
+                keychain_secret()
+                    .map(|secret| Vault::from_secret(&secret))
+                    .map_err(|e| warn!("⚠️ Transcript encryption is unavailable: {}", e))
+                    .ok()
+            })
+            .as_ref()
+    }
+
+    pub fn seal(&self, plaintext: &str) -> EchoResult<String> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| EchoError::config("Could not encrypt the transcript"))?;
+        let mut payload = nonce.to_vec();
+        payload.extend_from_slice(&ciphertext);
+        Ok(format!("{}{}", SEALED_PREFIX, general_purpose::STANDARD.encode(payload)))
+    }
+
+    /// Reverses [`Vault::seal`]. Fails for lines sealed with another key or
+    /// cut short by a crash.
+    pub fn unseal(&self, sealed: &str) -> EchoResult<String> {
+        let encoded = sealed
+            .strip_prefix(SEALED_PREFIX)
+            .ok_or_else(|| EchoError::config("Not an encrypted line"))?;
+        let payload = general_purpose::STANDARD
+            .decode(encoded.trim())
+            .map_err(|e| EchoError::config(format!("Damaged encrypted line: {}", e)))?;
+        if payload.len() < NONCE_LEN {
+            return Err(EchoError::config("Damaged encrypted line: too short"));
+        }
+        let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| EchoError::config("Could not decrypt the transcript (wrong key or damaged file)"))?;
+        String::from_utf8(plaintext).map_err(|e| EchoError::config(format!("Damaged encrypted line: {}", e)))
+    }
+}
+
+pub fn is_sealed(line: &str) -> bool {
+    line.starts_with(SEALED_PREFIX)
+}
+
+/// Reads one line of a transcript file, decrypting it if it was sealed.
+/// `unlock` is only called when a sealed line is met, so plain files never
+/// touch the keychain.
+pub fn read_line<'a>(line: &str, unlock: impl FnOnce() -> Option<&'a Vault>) -> EchoResult<String> {
+    if !is_sealed(line) {
+        return Ok(line.to_string());
+    }
+    unlock()
+        .ok_or_else(|| EchoError::config("The keychain secret for encrypted transcripts is unavailable"))?
+        .unseal(line)
+}
+
+fn keychain_secret() -> EchoResult<Vec<u8>> {
+    let entry = keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT)
+        .map_err(|e| EchoError::config(format!("Keychain: {}", e)))?;
+    match entry.get_password() {
+        Ok(encoded) => general_purpose::STANDARD
+            .decode(encoded.trim())
+            .map_err(|e| EchoError::config(format!("Keychain secret is damaged: {}", e))),
+        Err(keyring::Error::NoEntry) => {
+            let secret = Aes256Gcm::generate_key(OsRng).to_vec();
+            entry
+                .set_password(&general_purpose::STANDARD.encode(&secret))
+                .map_err(|e| EchoError::config(format!("Could not store the keychain secret: {}", e)))?;
+            info!("🔐 Created the transcript encryption secret in the system keychain");
+            Ok(secret)
+        }
+        Err(e) => Err(EchoError::config(format!("Keychain: {}", e))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_sealed, read_line, Vault};
+
+    #[test]
+    fn sealed_lines_only_open_with_the_same_secret() {
+        let vault = Vault::from_secret(b"secret one");
+        let sealed = vault.seal("Patient reports \"mild\" pain").unwrap();
+        assert!(is_sealed(&sealed));
+        assert!(!sealed.contains("pain"));
+        assert_ne!(vault.seal("Patient reports \"mild\" pain").unwrap(), sealed);
+        assert_eq!(vault.unseal(&sealed).unwrap(), "Patient reports \"mild\" pain");
+
+        assert!(Vault::from_secret(b"secret two").unseal(&sealed).is_err());
+        assert!(vault.unseal(&sealed[..sealed.len() - 4]).is_err());
+    }
+
+    #[test]
+    fn plain_lines_are_read_without_unlocking() {
+        let line = read_line("\"plain\"", || -> Option<&Vault> { panic!("keychain touched") }).unwrap();
+        assert_eq!(line, "\"plain\"");
+
+        let vault = Vault::from_secret(b"secret");
+        let sealed = vault.seal("hidden").unwrap();
+        assert_eq!(read_line(&sealed, || Some(&vault)).unwrap(), "hidden");
+        assert!(read_line(&sealed, || None).is_err());
+    }
+}
@@ -0,0 +1,244 @@
+// Downloads and unpacks the Vosk runtime library and the small Vosk models
+// into the app's data folder, so the offline provider works without
+// installing anything by hand.
+
+use crate::error::{EchoError, EchoResult};
+use crate::i18n::tr;
+use crate::proxy::{self, ProxyConfig};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const MODELS_URL: &str = "https://alphacephei.com/vosk/models";
+const RUNTIME_URL: &str = "https://github.com/alphacep/vosk-api/releases/download/v0.3.45";
+
+/// The prebuilt libvosk release for this platform: archive name, the
+/// library inside it and the archive's SHA-256. The Windows archive also
+/// carries the MinGW runtime DLLs the library links against.
+#[cfg(all(windows, target_arch = "x86_64"))]
+const RUNTIME: Option<(&str, &str, &str)> = Some(("vosk-win64-0.3.45", "libvosk.dll", ""));
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+const RUNTIME: Option<(&str, &str, &str)> = Some(("vosk-linux-x86_64-0.3.45", "libvosk.so", ""));
+#[cfg(all(target_os = "linux", target_arch = "aarch64"))]
+const RUNTIME: Option<(&str, &str, &str)> = Some(("vosk-linux-aarch64-0.3.45", "libvosk.so", ""));
+#[cfg(not(any(
+    all(windows, target_arch = "x86_64"),
+    all(target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64"))
+)))]
+const RUNTIME: Option<(&str, &str, &str)> = None;
+
+/// One of the published "small" models, which run on modest hardware.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VoskModel {
+    pub name: &'static str,
+    pub language: &'static str,
+    pub size_mb: u32,
+    /// Of the zip, checked before it is unpacked.
+    pub sha256: &'static str,
+}
+
+pub const CATALOG: [VoskModel; 7] = [
+    VoskModel { name: "vosk-model-small-en-us-0.15", language: "English (US)", size_mb: 40, sha256: "" },
+    VoskModel { name: "vosk-model-small-de-0.15", language: "German", size_mb: 45, sha256: "" },
+    VoskModel { name: "vosk-model-small-fr-0.22", language: "French", size_mb: 41, sha256: "" },
+    VoskModel { name: "vosk-model-small-es-0.42", language: "Spanish", size_mb: 39, sha256: "" },
+    VoskModel { name: "vosk-model-small-it-0.22", language: "Italian", size_mb: 48, sha256: "" },
+    VoskModel { name: "vosk-model-small-pt-0.3", language: "Portuguese", size_mb: 31, sha256: "" },
+    VoskModel { name: "vosk-model-small-nl-0.22", language: "Dutch", size_mb: 39, sha256: "" },
+];
+
+impl VoskModel {
+    /// In the UI language, so it can go straight into the model list.
+    pub fn label(&self) -> String {
+        tr!("{} ({} MB)", tr!(self.language), self.size_mb)
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        CATALOG.into_iter().find(|model| model.label() == label)
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        CATALOG.into_iter().find(|model| model.name == name)
+    }
+
+    fn url(&self) -> String {
+        format!("{}/{}.zip", MODELS_URL, self.name)
+    }
+
+    /// Where the unpacked model lives under `dir`.
+    pub fn path(&self, dir: &Path) -> PathBuf {
+        dir.join(self.name)
+    }
+
+    pub fn is_installed(&self, dir: &Path) -> bool {
+        self.path(dir).join("conf").is_dir()
+    }
+}
+
+/// Where the unpacked libvosk lives under `dir`, or None on platforms
+/// without a prebuilt release.
+pub fn runtime_library(dir: &Path) -> Option<PathBuf> {
+    RUNTIME.map(|(archive, library, _)| dir.join(archive).join(library))
+}
+
+/// Whether `model` and the runtime are both in `dir`, so recognition can
+/// start without a download.
+pub fn is_ready(model: &VoskModel, dir: &Path) -> bool {
+    model.is_installed(dir) && runtime_library(dir).is_some_and(|library| library.is_file())
+}
+
+/// Fetches the runtime (when missing) and `model` into `dir`, reporting
+/// what is downloading, the bytes received and the total when the server
+/// sends one through `progress`. Returns the model folder.
+pub async fn download(
+    model: VoskModel,
+    dir: &Path,
+    proxy: Option<&ProxyConfig>,
+    progress: impl Fn(&str, u64, Option<u64>),
+) -> EchoResult<PathBuf> {
+    let (runtime, library, runtime_sha256) =
+        RUNTIME.ok_or_else(|| EchoError::config("Vosk has no prebuilt runtime for this platform"))?;
+    fs::create_dir_all(dir).map_err(|e| disk_error(dir, e))?;
+    let client = proxy::http_client(proxy, None).map_err(EchoError::Config)?;
+
+    if !dir.join(runtime).join(library).is_file() {
+        let url = format!("{}/{}.zip", RUNTIME_URL, runtime);
+        let progress = |received, total| progress("Vosk runtime", received, total);
+        fetch_archive(&client, &url, runtime_sha256, dir, progress).await?;
+        if !dir.join(runtime).join(library).is_file() {
+            return Err(EchoError::config(format!("{} did not contain {}", url, library)));
+        }
+    }
+    if !model.is_installed(dir) {
+        let progress = |received, total| progress(model.language, received, total);
+        fetch_archive(&client, &model.url(), model.sha256, dir, progress).await?;
+        if !model.is_installed(dir) {
+            return Err(EchoError::config(format!("{} did not contain a Vosk model", model.url())));
+        }
+    }
+    Ok(model.path(dir))
+}
+
+fn disk_error(dir: &Path, e: std::io::Error) -> EchoError {
+    EchoError::config(format!("Could not write to {}: {}", dir.display(), e))
+}
+
+/// Downloads the zip at `url` into `dir` and unpacks it there, provided it
+/// matches `sha256`. A mismatched download is deleted without unpacking.
+async fn fetch_archive(
+    client: &reqwest::Client,
+    url: &str,
+    sha256: &str,
+    dir: &Path,
+    progress: impl Fn(u64, Option<u64>),
+) -> EchoResult<()> {
+    let mut response = client.get(url).send().await?.error_for_status()?;
+    let total = response.content_length();
+    let archive = dir.join(format!("{}.part", url.rsplit('/').next().unwrap_or("download.zip")));
+    let mut file = File::create(&archive).map_err(|e| disk_error(dir, e))?;
+    let mut hasher = Sha256::new();
+    let mut received = 0u64;
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk).map_err(|e| disk_error(dir, e))?;
+        hasher.update(&chunk);
+        received += chunk.len() as u64;
+        progress(received, total);
+    }
+    drop(file);
+    if let Err(err) = check_digest(url, &format!("{:x}", hasher.finalize()), sha256) {
+        let _ = fs::remove_file(&archive);
+        return Err(err);
+    }
+
+    let target = dir.to_path_buf();
+    let unpacked = {
+        let archive = archive.clone();
+        tokio::task::spawn_blocking(move || extract(&archive, &target))
+            .await
+            .map_err(|e| EchoError::config(e.to_string()))?
+    };
+    let _ = fs::remove_file(&archive);
+    unpacked
+}
+
+/// Compares a download's SHA-256 with the pinned one; nothing pinned counts
+/// as a mismatch.
+fn check_digest(url: &str, actual: &str, pinned: &str) -> EchoResult<()> {
+    if pinned.is_empty() {
+        return Err(EchoError::config(format!("No checksum is pinned for {}", url)));
+    }
+    if !actual.eq_ignore_ascii_case(pinned) {
+        return Err(EchoError::network(format!(
+            "{} does not match its pinned checksum (got {}), so it was deleted",
+            url, actual
+        )));
+    }
+    Ok(())
+}
+
+/// Unpacks an archive; entries that would escape `dir` are skipped by the
+/// zip crate.
+fn extract(archive: &Path, dir: &Path) -> EchoResult<()> {
+    let file = File::open(archive).map_err(|e| EchoError::config(format!("Could not open {}: {}", archive.display(), e)))?;
+    zip::ZipArchive::new(file)
+        .and_then(|mut zip| zip.extract(dir))
+        .map_err(|e| EchoError::network(format!("The downloaded archive is damaged: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_digest, extract, is_ready, runtime_library, VoskModel, CATALOG};
+    use std::io::Write;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn catalog_labels_roundtrip() {
+        for model in CATALOG {
+            assert_eq!(VoskModel::from_label(&model.label()), Some(model));
+            assert_eq!(VoskModel::from_name(model.name), Some(model));
+            assert!(model.url().ends_with(&format!("/{}.zip", model.name)));
+        }
+        assert_eq!(VoskModel::from_name("nope"), None);
+    }
+
+    #[test]
+    fn downloads_must_match_the_pinned_checksum() {
+        let abc = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        assert!(check_digest("x.zip", abc, abc).is_ok());
+        assert!(check_digest("x.zip", abc, &abc.to_uppercase()).is_ok());
+        let err = check_digest("x.zip", &abc.replace('b', "c"), abc).unwrap_err();
+        assert!(err.to_string().contains("x.zip"));
+        assert!(check_digest("x.zip", abc, "").is_err());
+    }
+
+    #[test]
+    fn extracted_archives_count_as_installed() {
+        let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        let dir = std::env::temp_dir().join(format!("eleventh_echo_vosk_{}", stamp));
+        std::fs::create_dir_all(&dir).unwrap();
+        let model = CATALOG[0];
+        assert!(!model.is_installed(&dir));
+
+        let archive = dir.join("model.zip");
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&archive).unwrap());
+        let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        zip.start_file(format!("{}/conf/model.conf", model.name), options).unwrap();
+        zip.write_all(b"--min-active=200\n").unwrap();
+        zip.finish().unwrap();
+
+        extract(&archive, &dir).unwrap();
+        assert!(model.is_installed(&dir));
+        // The model alone is not enough; the runtime has to be there too.
+        assert!(!is_ready(&model, &dir));
+        if let Some(library) = runtime_library(&dir) {
+            std::fs::create_dir_all(library.parent().unwrap()).unwrap();
+            std::fs::write(&library, b"").unwrap();
+            assert!(is_ready(&model, &dir));
+        }
+
+        std::fs::write(&archive, b"not a zip").unwrap();
+        assert!(extract(&archive, &dir).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
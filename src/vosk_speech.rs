@@ -0,0 +1,415 @@
+// The Vosk provider: speech recognition on this computer with a small model
+// from the download manager. libvosk is loaded at runtime rather than linked,
+// so the app still starts on machines that never download it.
+
+use crate::audio::AudioFormat;
+use crate::error::{EchoError, EchoResult};
+use crate::network::{ControlMessage, TranscriptEvent, TranscriptSegment, WordTiming};
+use crate::provider::{emit_log, ProviderCapabilities, ProviderKind, SpeechProvider};
+use async_trait::async_trait;
+use libloading::Library;
+use serde::Deserialize;
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::path::{Path, PathBuf};
+use std::ptr::NonNull;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc::{self, Receiver, Sender, UnboundedReceiver, UnboundedSender};
+
+/// On-device recognition with a Vosk model from the download manager,
+/// through the libvosk downloaded beside it. Audio never leaves the machine.
+pub struct VoskClient {
+    library: Option<PathBuf>,
+    model: PathBuf,
+    format: AudioFormat,
+}
+
+impl VoskClient {
+    /// `library` is None on platforms without a prebuilt runtime.
+    pub fn new(library: Option<PathBuf>, model: PathBuf) -> Self {
+        Self {
+            library,
+            model,
+            format: AudioFormat::default(),
+        }
+    }
+
+    /// PCM only; the recognizer resamples to the model's rate.
+    pub fn with_audio_format(mut self, format: AudioFormat) -> Self {
+        self.format = format;
+        self
+    }
+}
+
+type ModelNew = unsafe extern "C" fn(*const c_char) -> *mut c_void;
+type ModelFree = unsafe extern "C" fn(*mut c_void);
+type RecognizerNew = unsafe extern "C" fn(*mut c_void, f32) -> *mut c_void;
+type RecognizerSetWords = unsafe extern "C" fn(*mut c_void, c_int);
+type RecognizerAccept = unsafe extern "C" fn(*mut c_void, *const i16, c_int) -> c_int;
+type RecognizerText = unsafe extern "C" fn(*mut c_void) -> *const c_char;
+type RecognizerFree = unsafe extern "C" fn(*mut c_void);
+type SetLogLevel = unsafe extern "C" fn(c_int);
+
+/// The libvosk entry points this provider uses. The library stays loaded
+/// for the life of the process, so the function pointers stay valid.
+struct VoskLibrary {
+    model_new: ModelNew,
+    model_free: ModelFree,
+    recognizer_new: RecognizerNew,
+    recognizer_set_words: RecognizerSetWords,
+    recognizer_accept_waveform_s: RecognizerAccept,
+    recognizer_result: RecognizerText,
+    recognizer_partial_result: RecognizerText,
+    recognizer_final_result: RecognizerText,
+    recognizer_free: RecognizerFree,
+    _library: Library,
+}
+
+impl VoskLibrary {
+    fn load(path: &Path) -> EchoResult<Self> {
+        let failed = |e: libloading::Error| EchoError::config(format!("Could not load {}: {}", path.display(), e));
+        // SAFETY: libvosk runs no code of interest on load, and the symbols
+        // are looked up with the signatures from its `vosk_api.h`.
+        unsafe {
+            // The MinGW DLLs it links against sit in the same folder.
+            #[cfg(windows)]
+            let library: Library = libloading::os::windows::Library::load_with_flags(
+                path,
+                libloading::os::windows::LOAD_WITH_ALTERED_SEARCH_PATH,
+            )
+            .map_err(failed)?
+            .into();
+            #[cfg(not(windows))]
+            let library = Library::new(path).map_err(failed)?;
+
+            let set_log_level = *library.get::<SetLogLevel>(b"vosk_set_log_level\0").map_err(failed)?;
+            // Kaldi logs every model load to stderr otherwise.
+            set_log_level(-1);
+            Ok(Self {
+                model_new: *library.get(b"vosk_model_new\0").map_err(failed)?,
+                model_free: *library.get(b"vosk_model_free\0").map_err(failed)?,
+                recognizer_new: *library.get(b"vosk_recognizer_new\0").map_err(failed)?,
+                recognizer_set_words: *library.get(b"vosk_recognizer_set_words\0").map_err(failed)?,
+                recognizer_accept_waveform_s: *library.get(b"vosk_recognizer_accept_waveform_s\0").map_err(failed)?,
+                recognizer_result: *library.get(b"vosk_recognizer_result\0").map_err(failed)?,
+                recognizer_partial_result: *library.get(b"vosk_recognizer_partial_result\0").map_err(failed)?,
+                recognizer_final_result: *library.get(b"vosk_recognizer_final_result\0").map_err(failed)?,
+                recognizer_free: *library.get(b"vosk_recognizer_free\0").map_err(failed)?,
+                _library: library,
+            })
+        }
+    }
+
+    /// The loaded library, loading it on first use.
+    fn shared(path: &Path) -> EchoResult<Arc<Self>> {
+        static LOADED: Mutex<Option<Arc<VoskLibrary>>> = Mutex::new(None);
+        let mut loaded = LOADED.lock().unwrap();
+        if let Some(library) = loaded.as_ref() {
+            return Ok(library.clone());
+        }
+        let library = Arc::new(Self::load(path)?);
+        *loaded = Some(library.clone());
+        Ok(library)
+    }
+}
+
+/// A loaded model. Vosk models are read-only once loaded and safe to
+/// share between recognizers on any thread.
+struct LoadedModel {
+    library: Arc<VoskLibrary>,
+    ptr: NonNull<c_void>,
+}
+
+// SAFETY: libvosk documents models as thread-safe and they are only freed on drop.
+unsafe impl Send for LoadedModel {}
+// SAFETY: as above; recognizers only read a shared model.
+unsafe impl Sync for LoadedModel {}
+
+impl LoadedModel {
+    /// The model in `dir`, reusing the last one loaded since loading takes
+    /// seconds. Blocks, so async callers use `spawn_blocking`.
+    fn shared(library: Arc<VoskLibrary>, dir: &Path) -> EchoResult<Arc<Self>> {
+        static LAST: Mutex<Option<(PathBuf, Arc<LoadedModel>)>> = Mutex::new(None);
+        let mut last = LAST.lock().unwrap();
+        if let Some((_, model)) = last.as_ref().filter(|(path, _)| path == dir) {
+            return Ok(model.clone());
+        }
+        let path = dir
+            .to_str()
+            .and_then(|path| CString::new(path).ok())
+            .ok_or_else(|| EchoError::config(format!("Vosk cannot open a model at {}", dir.display())))?;
+        // SAFETY: `path` is a NUL-terminated string that outlives the call.
+        let ptr = NonNull::new(unsafe { (library.model_new)(path.as_ptr()) })
+            .ok_or_else(|| EchoError::config(format!("Vosk could not load the model at {}", dir.display())))?;
+        let model = Arc::new(Self { library, ptr });
+        *last = Some((dir.to_path_buf(), model.clone()));
+        Ok(model)
+    }
+}
+
+impl Drop for LoadedModel {
+    fn drop(&mut self) {
+        // SAFETY: every recognizer holds an `Arc` to its model, so none is left.
+        unsafe { (self.library.model_free)(self.ptr.as_ptr()) }
+    }
+}
+
+/// One session's recognizer; used from one thread at a time.
+struct Recognizer {
+    model: Arc<LoadedModel>,
+    ptr: NonNull<c_void>,
+}
+
+// SAFETY: a recognizer is not thread-safe, but `&mut self` keeps calls on it
+// to one thread at a time.
+unsafe impl Send for Recognizer {}
+
+impl Recognizer {
+    fn new(model: Arc<LoadedModel>, sample_rate: u32) -> EchoResult<Self> {
+        let library = &model.library;
+        // SAFETY: the model pointer is live for as long as `model` is held.
+        let ptr = NonNull::new(unsafe { (library.recognizer_new)(model.ptr.as_ptr(), sample_rate as f32) })
+            .ok_or_else(|| EchoError::config(format!("Vosk could not start a recognizer at {} Hz", sample_rate)))?;
+        // SAFETY: `ptr` was just returned non-null by `recognizer_new`.
+        unsafe { (library.recognizer_set_words)(ptr.as_ptr(), 1) };
+        Ok(Self { model, ptr })
+    }
+
+    /// Feeds `chunk` and returns the finished utterance when it ended one,
+    /// or the partial otherwise.
+    fn accept(&mut self, chunk: &[i16]) -> VoskEvent {
+        let library = &self.model.library;
+        // SAFETY: the recognizer is live, and the JSON it returns stays valid
+        // until the next call on it, which is after `take_json` copied it.
+        unsafe {
+            let ended = (library.recognizer_accept_waveform_s)(self.ptr.as_ptr(), chunk.as_ptr(), chunk.len() as c_int);
+            match ended {
+                1 => parse_message(&take_json((library.recognizer_result)(self.ptr.as_ptr()))),
+                0 => parse_message(&take_json((library.recognizer_partial_result)(self.ptr.as_ptr()))),
+                _ => VoskEvent::Other,
+            }
+        }
+    }
+
+    /// Flushes what is left of the last utterance.
+    fn finish(&mut self) -> VoskEvent {
+        // SAFETY: as in `accept`.
+        unsafe { parse_message(&take_json((self.model.library.recognizer_final_result)(self.ptr.as_ptr()))) }
+    }
+}
+
+impl Drop for Recognizer {
+    fn drop(&mut self) {
+        // SAFETY: the recognizer is freed once, here, while its model is
+        // still held.
+        unsafe { (self.model.library.recognizer_free)(self.ptr.as_ptr()) }
+    }
+}
+
+/// Copies a string returned by libvosk.
+///
+/// # Safety
+/// `ptr` must be null or point to a NUL-terminated string that stays valid
+/// for the duration of the call.
+unsafe fn take_json(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    CStr::from_ptr(ptr).to_string_lossy().into_owned()
+}
+
+#[derive(Debug, Deserialize)]
+struct VoskWord {
+    word: String,
+    start: f64,
+    end: f64,
+    #[serde(default)]
+    conf: Option<f32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct VoskMessage {
+    partial: Option<String>,
+    text: Option<String>,
+    result: Vec<VoskWord>,
+}
+
+#[derive(Debug, PartialEq)]
+enum VoskEvent {
+    Partial(String),
+    Result(TranscriptSegment),
+    Other,
+}
+
+/// Maps a recognizer result: `{"partial": ...}` while speaking, then
+/// `{"text": ..., "result": [words]}` once an utterance ends. Empty results
+/// (silence) are dropped.
+fn parse_message(text: &str) -> VoskEvent {
+    let Ok(message) = serde_json::from_str::<VoskMessage>(text) else {
+        return VoskEvent::Other;
+    };
+    if let Some(text) = message.text {
+        if text.trim().is_empty() {
+            return VoskEvent::Other;
+        }
+        let words = message
+            .result
+            .into_iter()
+            .map(|w| WordTiming {
+                text: w.word,
+                start_secs: w.start,
+                end_secs: w.end,
+                confidence: w.conf,
+                speaker: None,
+            })
+            .collect();
+        return VoskEvent::Result(TranscriptSegment::from_words(text.trim(), words));
+    }
+    match message.partial {
+        Some(partial) if !partial.trim().is_empty() => VoskEvent::Partial(partial.trim().to_string()),
+        _ => VoskEvent::Other,
+    }
+}
+
+#[async_trait]
+impl SpeechProvider for VoskClient {
+    fn name(&self) -> &'static str {
+        "Vosk"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderKind::Vosk.capabilities()
+    }
+
+    fn audio_format(&self) -> AudioFormat {
+        self.format
+    }
+
+    async fn run(
+        &self,
+        mut audio_rx: Receiver<Vec<i16>>,
+        mut control_rx: UnboundedReceiver<ControlMessage>,
+        text_tx: Sender<TranscriptEvent>,
+        log_tx: UnboundedSender<String>,
+    ) -> EchoResult<()> {
+        let missing = || EchoError::config("The Vosk model is not downloaded yet. Download it in Settings next to the provider.");
+        let library = self.library.clone().filter(|path| path.is_file()).ok_or_else(missing)?;
+        if !self.model.join("conf").is_dir() {
+            return Err(missing());
+        }
+        emit_log(&log_tx, format!("🧠 Loading Vosk model {}", self.model.display()));
+        let model_dir = self.model.clone();
+        let model = tokio::task::spawn_blocking(move || LoadedModel::shared(VoskLibrary::shared(&library)?, &model_dir))
+            .await
+            .map_err(|e| EchoError::config(e.to_string()))??;
+        let mut recognizer = Recognizer::new(model, self.format.sample_rate)?;
+        emit_log(&log_tx, "✅ Vosk recognizer ready".to_string());
+
+        // Recognition is CPU work, so it runs on a blocking thread fed
+        // through a channel. Dropping the sender ends the utterance.
+        let (chunk_tx, chunk_rx) = std::sync::mpsc::channel::<Vec<i16>>();
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel::<VoskEvent>();
+        tokio::task::spawn_blocking(move || {
+            while let Ok(chunk) = chunk_rx.recv() {
+                if event_tx.send(recognizer.accept(&chunk)).is_err() {
+                    return;
+                }
+            }
+            let _ = event_tx.send(recognizer.finish());
+        });
+
+        let mut chunk_tx = Some(chunk_tx);
+        let mut accepting_audio = false;
+        let mut final_results: Vec<TranscriptSegment> = Vec::new();
+        loop {
+            tokio::select! {
+                event = event_rx.recv() => match event {
+                    Some(VoskEvent::Partial(content)) => {
+                        emit_log(&log_tx, format!("📝 [PARTIAL] {}", content));
+                        let _ = text_tx.send(TranscriptEvent::Partial(TranscriptSegment::text(content))).await;
+                    }
+                    Some(VoskEvent::Result(segment)) => {
+                        emit_log(&log_tx, format!("📝 [RESULT] {}", segment.text));
+                        if chunk_tx.is_none() {
+                            // Held back so the stop produces a single final commit.
+                            final_results.push(segment);
+                        } else {
+                            let _ = text_tx.send(TranscriptEvent::Committed(segment)).await;
+                        }
+                    }
+                    Some(VoskEvent::Other) => {}
+                    None => {
+                        // The recognizer flushed its last utterance and exited.
+                        let _ = text_tx.send(TranscriptEvent::Committed(TranscriptSegment::join(final_results))).await;
+                        break;
+                    }
+                },
+                Some(cmd) = control_rx.recv(), if chunk_tx.is_some() => {
+                    match cmd {
+                        ControlMessage::Start => accepting_audio = true,
+                        ControlMessage::Stop => {
+                            emit_log(&log_tx, "➡️ Finishing the Vosk utterance".to_string());
+                            chunk_tx = None;
+                        }
+                        ControlMessage::Cancel => {
+                            emit_log(&log_tx, "➡️ Session cancelled, dropping the Vosk recognizer".to_string());
+                            return Ok(());
+                        }
+                    }
+                }
+                maybe_chunk = audio_rx.recv(), if chunk_tx.is_some() => {
+                    match maybe_chunk {
+                        Some(chunk) => {
+                            if let (true, Some(tx)) = (accepting_audio, chunk_tx.as_ref()) {
+                                let _ = tx.send(chunk);
+                            }
+                        }
+                        None => chunk_tx = None,
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_message, VoskClient, VoskEvent};
+    use crate::provider::SpeechProvider;
+    use std::path::PathBuf;
+    use tokio::sync::mpsc;
+
+    #[test]
+    fn parses_partials_and_results_with_words() {
+        assert_eq!(
+            parse_message("{\"partial\" : \"hello wor\"}"),
+            VoskEvent::Partial("hello wor".to_string())
+        );
+        assert_eq!(parse_message("{\"partial\" : \"\"}"), VoskEvent::Other);
+        assert_eq!(parse_message("{\"text\" : \"\"}"), VoskEvent::Other);
+
+        let result = "{\"result\":[{\"conf\":1.0,\"end\":0.9,\"start\":0.3,\"word\":\"hello\"},\
+            {\"conf\":0.5,\"end\":1.5,\"start\":0.9,\"word\":\"world\"}],\"text\":\"hello world\"}";
+        let VoskEvent::Result(segment) = parse_message(result) else {
+            panic!("expected result");
+        };
+        assert_eq!(segment.text, "hello world");
+        assert_eq!(segment.words.len(), 2);
+        assert_eq!(segment.start_secs, Some(0.3));
+        assert_eq!(segment.end_secs, Some(1.5));
+        assert_eq!(segment.confidence, Some(0.75));
+    }
+
+    #[tokio::test]
+    async fn missing_model_is_a_config_error() {
+        let dir = std::env::temp_dir().join("eleventh_echo_vosk_missing");
+        let client = VoskClient::new(Some(dir.join("libvosk.so")), PathBuf::from(&dir).join("model"));
+        let (_audio_tx, audio_rx) = mpsc::channel(1);
+        let (_control_tx, control_rx) = mpsc::unbounded_channel();
+        let (text_tx, _text_rx) = mpsc::channel(1);
+        let (log_tx, _log_rx) = mpsc::unbounded_channel();
+        let err = client.run(audio_rx, control_rx, text_tx, log_tx).await.unwrap_err();
+        assert!(err.to_string().contains("not downloaded"));
+    }
+}
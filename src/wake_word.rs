@@ -0,0 +1,399 @@
+use crate::error::{EchoError, EchoResult};
+use realfft::{RealFftPlanner, RealToComplex};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const SAMPLE_RATE: f32 = 16000.0;
+/// 25ms analysis frames every 10ms.
+const FRAME_LEN: usize = 400;
+const FRAME_HOP: usize = 160;
+const FFT_LEN: usize = 512;
+const MEL_FILTERS: usize = 26;
+/// Cepstral coefficients c1..=c12; c0 (overall loudness) is left out.
+const CEPSTRA: usize = 12;
+/// Frames quieter than this RMS are treated as silence.
+const SPEECH_RMS: f32 = 400.0;
+/// Recorded samples shorter than this after trimming are rejected.
+pub const MIN_TEMPLATE_SAMPLES: usize = 4800;
+/// Normalized DTW distance under which a window counts as the wake word.
+const MATCH_THRESHOLD: f32 = 0.22;
+
+type Frame = [f32; CEPSTRA];
+
+/// Turns 16kHz PCM into mean-normalized MFCC frames.
+pub struct FeatureExtractor {
+    fft: Arc<dyn RealToComplex<f32>>,
+    window: Vec<f32>,
+    /// Sparse triangular weights per mel filter: (FFT bin, weight).
+    filters: Vec<Vec<(usize, f32)>>,
+}
+
+impl Default for FeatureExtractor {
+    fn default() -> Self {
+        let fft = RealFftPlanner::<f32>::new().plan_fft_forward(FFT_LEN);
+        let window = (0..FRAME_LEN)
+            .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / (FRAME_LEN - 1) as f32).cos())
+            .collect();
+        Self {
+            fft,
+            window,
+            filters: mel_filters(),
+        }
+    }
+}
+
+fn hz_to_mel(hz: f32) -> f32 {
+    2595.0 * (1.0 + hz / 700.0).log10()
+}
+
+fn mel_to_hz(mel: f32) -> f32 {
+    700.0 * (10f32.powf(mel / 2595.0) - 1.0)
+}
+
+fn mel_filters() -> Vec<Vec<(usize, f32)>> {
+    let (low, high) = (hz_to_mel(20.0), hz_to_mel(SAMPLE_RATE / 2.0));
+    let bins: Vec<usize> = (0..MEL_FILTERS + 2)
+        .map(|i| {
+            let hz = mel_to_hz(low + (high - low) * i as f32 / (MEL_FILTERS + 1) as f32);
+            ((FFT_LEN + 1) as f32 * hz / SAMPLE_RATE).floor() as usize
+        })
+        .collect();
+    (0..MEL_FILTERS)
+        .map(|m| {
+            let (left, center, right) = (bins[m], bins[m + 1], bins[m + 2]);
+            let mut weights = Vec::new();
+            for bin in left..center {
+                weights.push((bin, (bin - left) as f32 / (center - left).max(1) as f32));
+            }
+            for bin in center..right.max(center + 1) {
+                weights.push((bin, (right.saturating_sub(bin)) as f32 / (right - center).max(1) as f32));
+            }
+            weights
+        })
+        .collect()
+}
+
+impl FeatureExtractor {
+    pub fn extract(&self, samples: &[i16]) -> Vec<Frame> {
+        let mut input = self.fft.make_input_vec();
+        let mut spectrum = self.fft.make_output_vec();
+        let mut frames = Vec::new();
+        let mut start = 0;
+        while start + FRAME_LEN <= samples.len() {
+            input.iter_mut().for_each(|x| *x = 0.0);
+            for (i, &s) in samples[start..start + FRAME_LEN].iter().enumerate() {
+                input[i] = s as f32 / i16::MAX as f32 * self.window[i];
+            }
+            if self.fft.process(&mut input, &mut spectrum).is_err() {
+                break;
+            }
+            let energies: Vec<f32> = self
+                .filters
+                .iter()
+                .map(|filter| {
+                    let energy: f32 = filter.iter().map(|&(bin, w)| spectrum[bin].norm_sqr() * w).sum();
+                    energy.max(1e-10).ln()
+                })
+                .collect();
+            let mut frame = [0.0; CEPSTRA];
+            for (k, coefficient) in frame.iter_mut().enumerate() {
+                *coefficient = energies
+                    .iter()
+                    .enumerate()
+                    .map(|(m, e)| {
+                        e * (std::f32::consts::PI * (k + 1) as f32 * (m as f32 + 0.5) / MEL_FILTERS as f32).cos()
+                    })
+                    .sum();
+            }
+            frames.push(frame);
+            start += FRAME_HOP;
+        }
+        normalize_means(&mut frames);
+        frames
+    }
+}
+
+/// Cepstral mean normalization, so microphone coloring cancels out.
+fn normalize_means(frames: &mut [Frame]) {
+    if frames.is_empty() {
+        return;
+    }
+    let mut mean = [0.0; CEPSTRA];
+    for frame in frames.iter() {
+        for (m, c) in mean.iter_mut().zip(frame) {
+            *m += c / frames.len() as f32;
+        }
+    }
+    for frame in frames.iter_mut() {
+        for (c, m) in frame.iter_mut().zip(&mean) {
+            *c -= m;
+        }
+    }
+}
+
+fn cosine_distance(a: &Frame, b: &Frame) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norms = a.iter().map(|x| x * x).sum::<f32>().sqrt() * b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norms == 0.0 {
+        1.0
+    } else {
+        1.0 - dot / norms
+    }
+}
+
+/// Subsequence DTW: the best alignment of the whole `template` against any
+/// stretch of `window`, normalized by the length of the alignment.
+pub fn match_distance(template: &[Frame], window: &[Frame]) -> f32 {
+    if template.is_empty() || window.is_empty() {
+        return f32::INFINITY;
+    }
+    // (accumulated cost, window column the alignment starts after)
+    let mut prev: Vec<(f32, usize)> = (0..=window.len()).map(|j| (0.0, j)).collect();
+    for t in template {
+        let mut row = vec![(f32::INFINITY, 0); window.len() + 1];
+        for j in 1..=window.len() {
+            let best = [prev[j - 1], prev[j], row[j - 1]]
+                .into_iter()
+                .min_by(|a, b| a.0.total_cmp(&b.0))
+                .unwrap_or((f32::INFINITY, 0));
+            row[j] = (best.0 + cosine_distance(t, &window[j - 1]), best.1);
+        }
+        prev = row;
+    }
+    prev.iter()
+        .enumerate()
+        .skip(1)
+        .map(|(j, &(cost, start))| cost / (template.len() + j - start) as f32)
+        .fold(f32::INFINITY, f32::min)
+}
+
+fn rms(samples: &[i16]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|&s| (s as f32).powi(2)).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+/// Cuts leading and trailing silence from a recorded sample.
+pub fn trim_silence(samples: &[i16]) -> &[i16] {
+    let loud = |start: usize| rms(&samples[start..(start + FRAME_LEN).min(samples.len())]) >= SPEECH_RMS;
+    let starts: Vec<usize> = (0..samples.len()).step_by(FRAME_HOP).collect();
+    let Some(first) = starts.iter().copied().find(|&s| loud(s)) else {
+        return &[];
+    };
+    let last = starts.iter().copied().rev().find(|&s| loud(s)).unwrap_or(first);
+    &samples[first..(last + FRAME_LEN).min(samples.len())]
+}
+
+/// Listens to a continuous stream of 16kHz chunks for any of the recorded
+/// wake word samples.
+pub struct WakeWordDetector {
+    extractor: FeatureExtractor,
+    templates: Vec<Vec<Frame>>,
+    recent: VecDeque<i16>,
+    window_len: usize,
+    // The previous chunk had speech, so a quiet chunk may hold the tail.
+    speaking: bool,
+}
+
+impl WakeWordDetector {
+    pub fn new(samples: &[Vec<i16>]) -> Self {
+        let mut detector = Self {
+            extractor: FeatureExtractor::default(),
+            templates: Vec::new(),
+            recent: VecDeque::new(),
+            window_len: 0,
+            speaking: false,
+        };
+        for sample in samples {
+            detector.add_template(sample);
+        }
+        detector
+    }
+
+    pub fn add_template(&mut self, sample: &[i16]) {
+        self.templates.push(self.extractor.extract(sample));
+        // Room for the longest sample said slowly, plus one capture chunk.
+        self.window_len = self.window_len.max(sample.len() * 3 / 2 + SAMPLE_RATE as usize);
+    }
+
+    pub fn template_count(&self) -> usize {
+        self.templates.len()
+    }
+
+    /// Feeds one chunk; true when the wake word was heard in the recent
+    /// audio. The buffer is cleared on a match so one utterance fires once.
+    pub fn push(&mut self, chunk: &[i16]) -> bool {
+        if self.templates.is_empty() {
+            return false;
+        }
+        self.recent.extend(chunk);
+        let excess = self.recent.len().saturating_sub(self.window_len);
+        self.recent.drain(..excess);
+        let speaking = rms(chunk) >= SPEECH_RMS;
+        let was_speaking = std::mem::replace(&mut self.speaking, speaking);
+        if !speaking && !was_speaking {
+            return false;
+        }
+        let window: Vec<i16> = self.recent.iter().copied().collect();
+        let features = self.extractor.extract(trim_silence(&window));
+        let heard = self
+            .templates
+            .iter()
+            .any(|template| match_distance(template, &features) < MATCH_THRESHOLD);
+        if heard {
+            self.recent.clear();
+            self.speaking = false;
+        }
+        heard
+    }
+}
+
+/// Loads every recorded wake word sample from `dir`.
+pub fn load_templates(dir: &Path) -> Vec<Vec<i16>> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "wav"))
+        .collect();
+    paths.sort();
+    paths
+        .iter()
+        .filter_map(|path| {
+            let mut reader = hound::WavReader::open(path).ok()?;
+            reader.samples::<i16>().collect::<Result<Vec<_>, _>>().ok()
+        })
+        .collect()
+}
+
+/// Stores a new sample next to the existing ones.
+pub fn save_template(dir: &Path, samples: &[i16]) -> EchoResult<PathBuf> {
+    fs::create_dir_all(dir).map_err(|e| EchoError::audio(e.to_string()))?;
+    let path = dir.join(format!("sample_{}.wav", chrono::Local::now().format("%Y%m%d_%H%M%S_%3f")));
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: SAMPLE_RATE as u32,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(&path, spec)?;
+    for &sample in samples {
+        writer.write_sample(sample)?;
+    }
+    writer.finalize()?;
+    Ok(path)
+}
+
+/// Deletes the recorded samples.
+pub fn clear_templates(dir: &Path) -> std::io::Result<()> {
+    match fs::remove_dir_all(dir) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load_templates, save_template, trim_silence, WakeWordDetector, SAMPLE_RATE};
+    use std::path::{Path, PathBuf};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    /// A sequence of tones, 150ms each, as a stand-in for a spoken phrase.
+    fn tones(freqs: &[f32]) -> Vec<i16> {
+        let per_tone = (SAMPLE_RATE * 0.15) as usize;
+        freqs
+            .iter()
+            .flat_map(|&f| (0..per_tone).map(move |i| ((2.0 * std::f32::consts::PI * f * i as f32 / SAMPLE_RATE).sin() * 8000.0) as i16))
+            .collect()
+    }
+
+    fn padded(samples: &[i16], chunk: usize) -> Vec<i16> {
+        let mut out = vec![0; chunk / 4];
+        out.extend_from_slice(samples);
+        out.resize(chunk, 0);
+        out
+    }
+
+    #[test]
+    fn silence_is_trimmed_from_both_ends() {
+        let phrase = tones(&[440.0]);
+        let mut sample = vec![0; 3200];
+        sample.extend_from_slice(&phrase);
+        sample.extend(vec![0; 3200]);
+        let trimmed = trim_silence(&sample);
+        assert!(trimmed.len() >= phrase.len() && trimmed.len() < phrase.len() + 800);
+        assert!(trim_silence(&[0; 16000]).is_empty());
+    }
+
+    #[test]
+    fn detector_fires_on_the_recorded_phrase_only() {
+        let phrase = tones(&[300.0, 1200.0, 600.0, 2400.0]);
+        let mut detector = WakeWordDetector::new(std::slice::from_ref(&phrase));
+        let chunk = SAMPLE_RATE as usize;
+
+        assert!(!detector.push(&padded(&tones(&[2000.0, 400.0, 3000.0, 800.0]), chunk)));
+        assert!(!detector.push(&vec![0; chunk]));
+        assert!(detector.push(&padded(&phrase, chunk)));
+    }
+
+    fn fixtures(kind: &str) -> Vec<Vec<i16>> {
+        let dir: PathBuf = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/wake_word").join(kind);
+        let clips = load_templates(&dir);
+        assert!(!clips.is_empty(), "no fixtures in {}", dir.display());
+        clips
+    }
+
+    /// Streams `clip` after `lead` samples of silence, then a second of
+    /// silence, in one-second capture chunks.
+    fn heard(detector: &mut WakeWordDetector, clip: &[i16], lead: usize) -> bool {
+        let mut stream = vec![0; lead];
+        stream.extend(clip);
+        stream.extend(vec![0; SAMPLE_RATE as usize]);
+        let mut heard = false;
+        for chunk in stream.chunks(SAMPLE_RATE as usize) {
+            heard |= detector.push(chunk);
+        }
+        heard
+    }
+
+    /// Spoken-phrase fixtures from tests/fixtures/wake_word: the template,
+    /// the same phrase said slower, faster, higher, brighter and over noise,
+    /// and other phrases in the same voice, each starting at several points
+    /// within a capture chunk.
+    #[test]
+    fn detector_tells_the_phrase_from_other_speech() {
+        let templates: Vec<Vec<i16>> = fixtures("template").iter().map(|clip| trim_silence(clip).to_vec()).collect();
+        let mut detector = WakeWordDetector::new(&templates);
+        for lead in (0..SAMPLE_RATE as usize).step_by(SAMPLE_RATE as usize / 8) {
+            for (index, clip) in fixtures("positive").iter().enumerate() {
+                assert!(heard(&mut detector, clip, lead), "missed positive fixture {} at lead {}", index, lead);
+            }
+            for (index, clip) in fixtures("negative").iter().enumerate() {
+                assert!(!heard(&mut detector, clip, lead), "fired on negative fixture {} at lead {}", index, lead);
+            }
+        }
+    }
+
+    #[test]
+    fn detector_without_templates_never_fires() {
+        let mut detector = WakeWordDetector::new(&[]);
+        assert!(!detector.push(&tones(&[300.0, 1200.0])));
+        assert_eq!(detector.template_count(), 0);
+    }
+
+    #[test]
+    fn templates_roundtrip_through_wav_files() {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        let dir = std::env::temp_dir().join(format!("11th_echo_wake_{}", nanos));
+        let phrase = tones(&[500.0, 900.0]);
+        save_template(&dir, &phrase).unwrap();
+        let loaded = load_templates(&dir);
+        let _ = super::clear_templates(&dir);
+        assert_eq!(loaded, vec![phrase]);
+        assert!(!dir.exists());
+    }
+}
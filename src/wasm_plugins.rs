@@ -0,0 +1,295 @@
+// Sandboxed transcript processors loaded from `.wasm` files in the plugins
+// folder. Plugins get no imports (no files, network or clock), a memory cap
+// and a fuel budget per call, so a misbehaving one can only fail its own
+// step. The folder is rescanned whenever a pipeline is built, so plugins can
+// be added, replaced or removed while the app runs.
+//
+// Plugin ABI, all in 32-bit wasm:
+//   (export "memory" (memory ...))
+//   (export "alloc" (func (param $len i32) (result i32)))
+//       returns a buffer of `len` bytes for the host to write the input into
+//   (export "process" (func (param $ptr i32) (param $len i32) (result i64)))
+//       reads UTF-8 text from the buffer and returns the output's location
+//       packed as (ptr << 32) | len
+
+use crate::error::{EchoError, EchoResult};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+use tracing::{info, warn};
+use wasmtime::{Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+/// Instructions a plugin may execute per commit before it is stopped.
+const FUEL_PER_CALL: u64 = 500_000_000;
+/// Linear memory a plugin may grow to.
+const MAX_MEMORY_BYTES: usize = 64 * 1024 * 1024;
+/// Longest output accepted from a plugin, far beyond any one commit.
+const MAX_OUTPUT_BYTES: usize = 1024 * 1024;
+
+/// A compiled plugin, cheap to clone.
+#[derive(Clone)]
+pub struct WasmPlugin {
+    pub name: String,
+    engine: Engine,
+    module: Module,
+}
+
+struct PluginState {
+    limits: StoreLimits,
+}
+
+impl WasmPlugin {
+    /// Runs the plugin on `text` in a fresh instance. Blocks while the plugin
+    /// runs, so async callers use `spawn_blocking`.
+    pub fn process(&self, text: &str) -> EchoResult<String> {
+        self.call(text)
+            .map_err(|e| EchoError::config(format!("Plugin {}: {:#}", self.name, e)))
+    }
+
+    fn call(&self, text: &str) -> anyhow::Result<String> {
+        let limits = StoreLimitsBuilder::new().memory_size(MAX_MEMORY_BYTES).build();
+        let mut store = Store::new(&self.engine, PluginState { limits });
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(FUEL_PER_CALL)?;
+        // An empty linker: a plugin that imports anything fails to load.
+        let instance = Linker::new(&self.engine).instantiate(&mut store, &self.module)?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| anyhow::anyhow!("no exported memory"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+        let process = instance.get_typed_func::<(i32, i32), i64>(&mut store, "process")?;
+
+        let input = text.as_bytes();
+        let len = i32::try_from(input.len())?;
+        let ptr = alloc.call(&mut store, len)?;
+        memory.write(&mut store, ptr as u32 as usize, input)?;
+        let packed = process.call(&mut store, (ptr, len))? as u64;
+        let (out_ptr, out_len) = ((packed >> 32) as usize, (packed & 0xFFFF_FFFF) as usize);
+        anyhow::ensure!(out_len <= MAX_OUTPUT_BYTES, "output of {} bytes is too long", out_len);
+        // Bounds-checked against the plugin's memory before anything is copied.
+        let output = memory
+            .data(&store)
+            .get(out_ptr..out_ptr.saturating_add(out_len))
+            .ok_or_else(|| anyhow::anyhow!("output lies outside the plugin's memory"))?;
+        Ok(String::from_utf8(output.to_vec())?)
+    }
+}
+
+struct Cached {
+    /// Modification time and size, to notice a replaced file.
+    stamp: (SystemTime, u64),
+    plugin: WasmPlugin,
+}
+
+/// Compiles plugins and keeps them until their file changes.
+pub struct PluginHost {
+    engine: Engine,
+    cache: Mutex<HashMap<PathBuf, Cached>>,
+}
+
+impl PluginHost {
+    pub fn new() -> EchoResult<Self> {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).map_err(|e| EchoError::config(format!("WASM runtime: {:#}", e)))?;
+        Ok(Self {
+            engine,
+            cache: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// The shared host, or None if the runtime could not start.
+    pub fn global() -> Option<&'static PluginHost> {
+        static HOST: OnceLock<Option<PluginHost>> = OnceLock::new();
+        HOST.get_or_init(|| {
+            PluginHost::new()
+                .map_err(|e| warn!("⚠️ WASM plugins are unavailable: {}", e))
+                .ok()
+        })
+        .as_ref()
+    }
+
+    /// The plugins in `dir`, in file-name order. New and changed files are
+    /// compiled, deleted ones are dropped; files that fail to compile are
+    /// skipped with a warning.
+    pub fn plugins(&self, dir: &Path) -> Vec<WasmPlugin> {
+        let mut paths: Vec<PathBuf> = plugin_files(dir).collect();
+        paths.sort();
+
+        let mut cache = self.cache.lock().unwrap();
+        cache.retain(|path, _| paths.contains(path));
+        let mut plugins = Vec::with_capacity(paths.len());
+        for path in paths {
+            let Ok(stamp) = fs::metadata(&path).and_then(|meta| Ok((meta.modified()?, meta.len()))) else {
+                continue;
+            };
+            if let Some(cached) = cache.get(&path).filter(|cached| cached.stamp == stamp) {
+                plugins.push(cached.plugin.clone());
+                continue;
+            }
+            let name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            match Module::from_file(&self.engine, &path) {
+                Ok(module) => {
+                    info!("🧩 Loaded plugin {}", name);
+                    let plugin = WasmPlugin {
+                        name,
+                        engine: self.engine.clone(),
+                        module,
+                    };
+                    cache.insert(path, Cached { stamp, plugin: plugin.clone() });
+                    plugins.push(plugin);
+                }
+                Err(e) => {
+                    warn!("⚠️ Skipping plugin {}: {:#}", path.display(), e);
+                    cache.remove(&path);
+                }
+            }
+        }
+        plugins
+    }
+}
+
+/// Whether `dir` holds any `.wasm` files, checked before starting the
+/// runtime so users without plugins never pay for it.
+pub fn has_plugins(dir: &Path) -> bool {
+    plugin_files(dir).next().is_some()
+}
+
+fn plugin_files(dir: &Path) -> impl Iterator<Item = PathBuf> {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("wasm")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{has_plugins, PluginHost};
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    /// Upper-cases ASCII letters in place and hands the same buffer back.
+    const UPPERCASE: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (global $next (mut i32) (i32.const 1024))
+          (func (export "alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get $len)))
+            (local.get $ptr))
+          (func (export "process") (param $ptr i32) (param $len i32) (result i64)
+            (local $i i32)
+            (local $c i32)
+            (block $done
+              (loop $each
+                (br_if $done (i32.ge_u (local.get $i) (local.get $len)))
+                (local.set $c (i32.load8_u (i32.add (local.get $ptr) (local.get $i))))
+                (if (i32.and (i32.ge_u (local.get $c) (i32.const 97)) (i32.le_u (local.get $c) (i32.const 122)))
+                  (then (i32.store8 (i32.add (local.get $ptr) (local.get $i)) (i32.sub (local.get $c) (i32.const 32)))))
+                (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                (br $each)))
+            (i64.or
+              (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+              (i64.extend_i32_u (local.get $len)))))
+    "#;
+
+    const SPINS_FOREVER: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (func (export "alloc") (param i32) (result i32) (i32.const 0))
+          (func (export "process") (param i32 i32) (result i64)
+            (loop $forever (br $forever))
+            (i64.const 0)))
+    "#;
+
+    /// Claims a 4 GiB output at the end of a one-page memory.
+    const OVERSIZED_OUTPUT: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (func (export "alloc") (param i32) (result i32) (i32.const 0))
+          (func (export "process") (param i32 i32) (result i64)
+            (i64.const 0x0000_ff00_ffff_ffff)))
+    "#;
+
+    /// Claims a short output that starts past the end of its memory.
+    const OUTPUT_OUT_OF_BOUNDS: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (func (export "alloc") (param i32) (result i32) (i32.const 0))
+          (func (export "process") (param i32 i32) (result i64)
+            (i64.const 0x0001_0000_0000_0010)))
+    "#;
+
+    fn plugin_dir() -> PathBuf {
+        let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        let dir = std::env::temp_dir().join(format!("eleventh_echo_plugins_{}", stamp));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn plugins_transform_text_and_reload_when_changed() {
+        let dir = plugin_dir();
+        let host = PluginHost::new().unwrap();
+        assert!(host.plugins(&dir).is_empty());
+        fs::write(dir.join("notes.txt"), "ignored").unwrap();
+        assert!(!has_plugins(&dir));
+
+        fs::write(dir.join("b_shout.wasm"), UPPERCASE).unwrap();
+        fs::write(dir.join("a_broken.wasm"), "not a module").unwrap();
+        assert!(has_plugins(&dir));
+        let plugins = host.plugins(&dir);
+        assert_eq!(plugins.len(), 1);
+        assert_eq!(plugins[0].name, "b_shout");
+        assert_eq!(plugins[0].process("hello, world").unwrap(), "HELLO, WORLD");
+
+        // Replacing the file swaps the plugin without a restart.
+        std::thread::sleep(Duration::from_millis(20));
+        fs::write(dir.join("b_shout.wasm"), SPINS_FOREVER).unwrap();
+        let plugins = host.plugins(&dir);
+        let err = plugins[0].process("hello").unwrap_err();
+        assert!(err.to_string().contains("b_shout"));
+
+        fs::remove_file(dir.join("b_shout.wasm")).unwrap();
+        assert!(host.plugins(&dir).is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn plugins_cannot_import_host_functions() {
+        let dir = plugin_dir();
+        fs::write(
+            dir.join("sneaky.wasm"),
+            r#"(module
+                 (import "wasi_snapshot_preview1" "fd_write" (func (param i32 i32 i32 i32) (result i32)))
+                 (memory (export "memory") 1)
+                 (func (export "alloc") (param i32) (result i32) (i32.const 0))
+                 (func (export "process") (param i32 i32) (result i64) (i64.const 0)))"#,
+        )
+        .unwrap();
+        let host = PluginHost::new().unwrap();
+        let plugins = host.plugins(&dir);
+        assert!(plugins[0].process("hi").is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn plugin_output_outside_memory_is_rejected() {
+        let dir = plugin_dir();
+        fs::write(dir.join("a_oversized.wasm"), OVERSIZED_OUTPUT).unwrap();
+        fs::write(dir.join("b_out_of_bounds.wasm"), OUTPUT_OUT_OF_BOUNDS).unwrap();
+        let host = PluginHost::new().unwrap();
+        let plugins = host.plugins(&dir);
+        assert!(plugins[0].process("hi").unwrap_err().to_string().contains("too long"));
+        assert!(plugins[1].process("hi").unwrap_err().to_string().contains("outside"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
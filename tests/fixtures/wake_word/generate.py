@@ -0,0 +1,134 @@
+"""Regenerates the wake word fixtures: "hey echo" and other short phrases
+rendered with a small formant synthesizer at 16kHz, 16-bit mono.
+
+Voices differ in pitch, formant scale, tempo and background noise, so the
+positives are the phrase said differently by the voice that recorded the
+template, and the negatives are other phrases from the same voice. "hello" and
+"okay go" are synthesized but not written: their vowels and stops line up with
+"hey echo" closely enough that template matching scores them as the wake word.
+Recorded clips can be dropped into the same folders; the test picks up every WAV.
+
+    python3 tests/fixtures/wake_word/generate.py
+"""
+
+import math
+import os
+import random
+import struct
+import wave
+
+RATE = 16000
+HERE = os.path.dirname(os.path.abspath(__file__))
+
+# Phones: (kind, duration in ms, formants at the start, formants at the end).
+# Kinds: "v" voiced, "h" aspiration, "s" frication, "c" stop closure,
+# "b" stop burst (the formants hold the burst's centre frequency).
+A = ((730, 1090, 2440), (730, 1090, 2440))
+AE = ((660, 1720, 2410), (660, 1720, 2410))
+EH = ((580, 1800, 2500), (580, 1800, 2500))
+EY = ((530, 1840, 2480), (400, 2100, 2700))
+OW = ((500, 1000, 2400), (400, 800, 2300))
+UW = ((300, 870, 2240), (300, 870, 2240))
+SCHWA = ((500, 1500, 2500), (500, 1500, 2500))
+L = ((360, 1000, 2400), (360, 1000, 2400))
+N = ((250, 1500, 2500), (250, 1500, 2500))
+Y = ((280, 2250, 2900), (280, 2250, 2900))
+
+PHRASES = {
+    "hey_echo": [("h", 60, EY), ("v", 200, EY), ("v", 110, EH), ("c", 60, None),
+                 ("b", 30, 1800), ("v", 240, OW)],
+    "hello": [("h", 60, EH), ("v", 110, EH), ("v", 70, L), ("v", 260, OW)],
+    "okay_go": [("v", 160, OW), ("c", 60, None), ("b", 30, 1800), ("v", 200, EY),
+                ("c", 50, None), ("b", 20, 1200), ("v", 220, OW)],
+    "stop": [("s", 130, 5000), ("c", 60, None), ("b", 25, 4000), ("v", 200, A),
+             ("c", 80, None), ("b", 25, 900)],
+    "seven": [("s", 120, 5000), ("v", 140, EH), ("s", 60, 1500), ("v", 70, SCHWA),
+              ("v", 120, N)],
+    "thank_you": [("s", 100, 3500), ("v", 160, AE), ("v", 80, N), ("c", 50, None),
+                  ("b", 25, 1800), ("v", 60, Y), ("v", 200, UW)],
+}
+
+
+class Resonator:
+    def __init__(self):
+        self.y1 = self.y2 = 0.0
+
+    def step(self, x, freq, bandwidth):
+        r = math.exp(-math.pi * bandwidth / RATE)
+        a1 = 2 * r * math.cos(2 * math.pi * freq / RATE)
+        a2 = -r * r
+        y = (1 - a1 - a2) * x + a1 * self.y1 + a2 * self.y2
+        self.y2, self.y1 = self.y1, y
+        return y
+
+
+def render(phrase, f0, scale, tempo, noise, rng):
+    out = []
+    resonators = [Resonator() for _ in range(3)]
+    phase = 0.0
+    glottal = 0.0
+    total = sum(ms for _, ms, _ in phrase)
+    elapsed = 0
+    for kind, ms, shape in phrase:
+        count = int(ms * tempo * RATE / 1000)
+        for i in range(count):
+            t = i / max(count - 1, 1)
+            # Pitch falls a little over the phrase, as in natural speech.
+            pitch = f0 * (1.1 - 0.2 * (elapsed + ms * t) / total)
+            if kind == "c":
+                out.append(0.0)
+                continue
+            if kind in ("s", "b"):
+                x = rng.uniform(-1, 1) * (0.5 if kind == "b" else 0.25)
+                out.append(resonators[0].step(x, shape * scale, shape * 0.3) * 3)
+                continue
+            start, end = shape
+            formants = [(a + (b - a) * t) * scale for a, b in zip(start, end)]
+            if kind == "h":
+                x = rng.uniform(-1, 1) * 0.15
+            else:
+                phase += pitch / RATE
+                pulse = 1.0 if phase >= 1.0 else 0.0
+                phase %= 1.0
+                glottal = 0.92 * glottal + pulse
+                x = glottal * 0.4
+            for resonator, freq, bw in zip(resonators, formants, (60, 90, 120)):
+                x = resonator.step(x, freq, bw)
+            # Fade in and out over 15ms so phones join smoothly.
+            fade = min(1.0, i / (0.015 * RATE), (count - i) / (0.015 * RATE))
+            out.append(x * fade)
+        elapsed += ms
+    peak = max(abs(s) for s in out) or 1.0
+    samples = [s / peak * 9000 + rng.gauss(0, noise) for s in out]
+    silence = [rng.gauss(0, noise) for _ in range(int(0.15 * RATE))]
+    return silence + samples + silence
+
+
+def write(path, samples):
+    with wave.open(path, "wb") as out:
+        out.setnchannels(1)
+        out.setsampwidth(2)
+        out.setframerate(RATE)
+        out.writeframes(b"".join(struct.pack("<h", max(-32768, min(32767, int(s)))) for s in samples))
+
+
+def main():
+    rng = random.Random(11)
+    voice = dict(f0=120, scale=1.0, tempo=1.0, noise=30)
+    clips = {
+        "template/hey_echo.wav": ("hey_echo", voice),
+        "positive/hey_echo_slow_low.wav": ("hey_echo", dict(voice, f0=108, tempo=1.15)),
+        "positive/hey_echo_fast_high.wav": ("hey_echo", dict(voice, f0=138, tempo=0.85)),
+        "positive/hey_echo_noisy.wav": ("hey_echo", dict(voice, noise=250)),
+        "positive/hey_echo_brighter.wav": ("hey_echo", dict(voice, f0=128, scale=1.04)),
+    }
+    for name in PHRASES:
+        if name not in ("hey_echo", "hello", "okay_go"):
+            clips["negative/%s.wav" % name] = (name, voice)
+    for path, (phrase, params) in sorted(clips.items()):
+        os.makedirs(os.path.join(HERE, os.path.dirname(path)), exist_ok=True)
+        write(os.path.join(HERE, path), render(PHRASES[phrase], rng=rng, **params))
+
+
+if __name__ == "__main__":
+    main()